@@ -18,6 +18,12 @@ const App: React.FC = () => {
     }
   }, [currentRoute]);
 
+  // Keep the board current when the CLI, a worker or another browser changes the plan
+  useEffect(() => {
+    if (currentRoute !== '/') return;
+    return api.subscribeEvents(() => loadData());
+  }, [currentRoute]);
+
   const loadData = async () => {
     const data = await api.getTickets();
     setTickets(data);
@@ -29,6 +35,12 @@ const App: React.FC = () => {
     loadData(); // Reload to get updated status/artifacts
   };
 
+  const handleTicketSaved = async (id: string) => {
+    const [data, updated] = await Promise.all([api.getTickets(), api.getTicket(id)]);
+    setTickets(data);
+    setSelectedTicket(updated);
+  };
+
   const handleTicketMove = async (ticketId: string, newStatus: TicketStatus) => {
     // Optimistic UI update
     setTickets(prev => prev.map(t => 
@@ -67,6 +79,7 @@ const App: React.FC = () => {
           ticket={selectedTicket} 
           onClose={() => setSelectedTicket(null)}
           onVerify={handleVerifyTicket}
+          onSaved={handleTicketSaved}
         />
       )}
     </Layout>
@@ -1,7 +1,7 @@
 import React, { useState, useEffect } from 'react';
 import { Asset } from '../types';
 import { api } from '../services/api';
-import { Type, Image, FileJson, Copy, Upload } from 'lucide-react';
+import { Type, Image, FileJson, Copy, Upload, Pencil, Trash2 } from 'lucide-react';
 
 export const AssetLibrary: React.FC = () => {
   const [assets, setAssets] = useState<Asset[]>([]);
@@ -36,8 +36,12 @@ export const AssetLibrary: React.FC = () => {
     
     if (e.dataTransfer.files && e.dataTransfer.files[0]) {
       const file = e.dataTransfer.files[0];
-      const newAsset = await api.uploadAsset(file);
-      setAssets(prev => [...prev, newAsset]);
+      try {
+        const newAsset = await api.uploadAsset(file);
+        setAssets(prev => [...prev, newAsset]);
+      } catch (err) {
+        window.alert((err as Error).message);
+      }
     }
   };
 
@@ -48,6 +52,25 @@ export const AssetLibrary: React.FC = () => {
     setTimeout(() => setCopiedId(null), 2000);
   };
 
+  const deleteAsset = async (e: React.MouseEvent, asset: Asset) => {
+    e.stopPropagation();
+    if (!window.confirm(`Delete ${asset.name}? It will be removed from any tickets it's attached to.`)) return;
+    await api.deleteAsset(asset.name);
+    setAssets(prev => prev.filter(a => a.id !== asset.id));
+  };
+
+  const renameAsset = async (e: React.MouseEvent, asset: Asset) => {
+    e.stopPropagation();
+    const newName = window.prompt('Rename asset', asset.name);
+    if (!newName || newName === asset.name) return;
+    try {
+      await api.renameAsset(asset.name, newName);
+      await loadAssets();
+    } catch (err) {
+      window.alert((err as Error).message);
+    }
+  };
+
   const getIcon = (type: Asset['type']) => {
     switch(type) {
         case 'font': return <Type size={32} className="text-gray-500"/>;
@@ -89,6 +112,14 @@ export const AssetLibrary: React.FC = () => {
                 <div className="absolute inset-x-0 bottom-0 bg-black/90 p-3 translate-y-full group-hover:translate-y-0 transition-transform">
                     <p className="text-xs font-bold text-white truncate">{asset.name}</p>
                     <p className="text-[10px] font-mono text-gray-500 mt-1 truncate">{asset.rust_id}</p>
+                    <div className="flex gap-2 mt-2">
+                        <button onClick={(e) => renameAsset(e, asset)} title="Rename" className="text-gray-500 hover:text-white">
+                            <Pencil size={12} />
+                        </button>
+                        <button onClick={(e) => deleteAsset(e, asset)} title="Delete" className="text-gray-500 hover:text-red-400">
+                            <Trash2 size={12} />
+                        </button>
+                    </div>
                     {copiedId === asset.id ? (
                         <div className="absolute top-2 right-2 text-[#00FF94] text-xs flex items-center gap-1">
                             Copied!
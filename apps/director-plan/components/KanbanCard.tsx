@@ -19,12 +19,13 @@ export const KanbanCard: React.FC<KanbanCardProps> = ({
 }) => {
   const isHighPriority = ticket.priority === 'high';
   // Threshold for "Slow" is 16ms (60fps budget)
-  const isSlow = ticket.metrics && ticket.metrics.render_time_ms > 16.6; 
+  const renderTime = ticket.metrics?.render_time_ms;
+  const isSlow = renderTime !== undefined && renderTime > 16.6; 
   
   // Generate fake sparkline data based on the current render time
   const sparklinePoints = useMemo(() => {
-    if (!ticket.metrics) return "";
-    const base = ticket.metrics.render_time_ms;
+    if (renderTime === undefined) return "";
+    const base = renderTime;
     const points = [];
     for (let i = 0; i < 10; i++) {
         // Random variance +/- 2ms
@@ -36,7 +37,7 @@ export const KanbanCard: React.FC<KanbanCardProps> = ({
         points.push(`${i * 5},${y}`); 
     }
     return points.join(" ");
-  }, [ticket.metrics]);
+  }, [renderTime]);
 
   return (
     <div 
@@ -63,6 +64,10 @@ export const KanbanCard: React.FC<KanbanCardProps> = ({
         {ticket.title}
       </h4>
 
+      {ticket.blocked_reason && (
+        <p className="text-[11px] text-amber-400 mb-3 line-clamp-2 select-none">{ticket.blocked_reason}</p>
+      )}
+
       {/* Visual Pip */}
       {ticket.artifacts && (
         <div className="mb-3 relative h-24 w-full overflow-hidden rounded border border-white/10 bg-black pointer-events-none">
@@ -82,7 +87,7 @@ export const KanbanCard: React.FC<KanbanCardProps> = ({
            <span className={`text-[10px] ${ticket.owner.startsWith('agent') ? 'text-[#7000FF] font-medium' : ''}`}>{ticket.owner.replace('agent-', '').replace('human-', '')}</span>
         </div>
         
-        {ticket.metrics && !ticket.artifacts && (
+        {renderTime !== undefined && !ticket.artifacts && (
              <div className="flex items-center gap-2">
                 {/* Sparkline */}
                 <svg width="45" height="20" className="opacity-70">
@@ -96,12 +101,12 @@ export const KanbanCard: React.FC<KanbanCardProps> = ({
                     />
                 </svg>
                 <div className={`flex items-center gap-1 font-mono text-[10px] ${isSlow ? 'text-[#FF0055]' : 'text-[#00FF94]'}`}>
-                    {ticket.metrics.render_time_ms.toFixed(1)}ms
+                    {renderTime.toFixed(1)}ms
                 </div>
              </div>
         )}
 
-        {isHighPriority && !ticket.metrics && !ticket.artifacts && (
+        {isHighPriority && renderTime === undefined && !ticket.artifacts && (
             <div className="flex items-center gap-1 text-[#FF0055] bg-[#FF0055]/10 px-1.5 py-0.5 rounded border border-[#FF0055]/20">
                 <AlertCircle size={10} />
                 <span className="text-[9px] font-bold uppercase">High</span>
@@ -1,5 +1,7 @@
-import React, { useState } from 'react';
+import React, { useEffect, useState } from 'react';
 import { Settings, Save, Server, Cpu, Database } from 'lucide-react';
+import { api } from '../services/api';
+import { WorkerStatus } from '../types';
 
 export const SettingsPage: React.FC = () => {
     // This state would ideally come from a backend or local storage
@@ -11,6 +13,15 @@ export const SettingsPage: React.FC = () => {
         radkitEndpoint: 'http://localhost:3000'
     });
 
+    const [workers, setWorkers] = useState<WorkerStatus[]>([]);
+
+    useEffect(() => {
+        const load = () => api.getWorkers().then(setWorkers).catch(() => setWorkers([]));
+        load();
+        const timer = setInterval(load, 15000);
+        return () => clearInterval(timer);
+    }, []);
+
     const handleChange = (e: React.ChangeEvent<HTMLInputElement | HTMLSelectElement>) => {
         const { name, value } = e.target;
         setConfig(prev => ({ ...prev, [name]: value }));
@@ -103,6 +114,25 @@ export const SettingsPage: React.FC = () => {
                                     <option value="hybrid">Hybrid</option>
                                 </select>
                             </div>
+                            <div>
+                                <label className="block text-xs font-mono uppercase text-gray-500 mb-2">Connected Workers</label>
+                                {workers.length === 0 ? (
+                                    <p className="text-xs text-gray-500">No workers have reported in the last hour.</p>
+                                ) : (
+                                    <ul className="space-y-2">
+                                        {workers.map(worker => (
+                                            <li key={worker.id} className="text-xs font-mono text-gray-300">
+                                                <span className={worker.alive ? 'text-green-400' : 'text-red-400'}>●</span>{' '}
+                                                <span className="text-white">{worker.id}</span>{' '}
+                                                {worker.current_ticket
+                                                    ? `running ${worker.workspace ? `${worker.workspace}/` : ''}${worker.current_ticket}`
+                                                    : 'idle'}
+                                                {worker.last_error && <div className="text-red-400 truncate" title={worker.last_error}>{worker.last_error}</div>}
+                                            </li>
+                                        ))}
+                                    </ul>
+                                )}
+                            </div>
                         </div>
                      </section>
 
@@ -1,13 +1,15 @@
-import React, { useState } from 'react';
-import { X, Check, Activity, Terminal, Clock, ShieldAlert, Play, MessageSquare } from 'lucide-react';
-import { Ticket } from '../types';
+import React, { useEffect, useState } from 'react';
+import { X, Check, Activity, Terminal, Clock, ShieldAlert, Play, MessageSquare, FileCode } from 'lucide-react';
+import { Ticket, Comment } from '../types';
 import { ImageComparator } from './ImageComparator';
+import { api } from '../services/api';
 import { LineChart, Line, XAxis, YAxis, Tooltip, ResponsiveContainer } from 'recharts';
 
 interface TicketDetailModalProps {
   ticket: Ticket;
   onClose: () => void;
   onVerify: (id: string) => void;
+  onSaved?: (id: string) => void;
 }
 
 // Mock perf history data
@@ -20,10 +22,57 @@ const PERF_DATA = [
   { frame: 6, ms: 16.4 }, // Current
 ];
 
-export const TicketDetailModal: React.FC<TicketDetailModalProps> = ({ ticket, onClose, onVerify }) => {
+export const TicketDetailModal: React.FC<TicketDetailModalProps> = ({ ticket, onClose, onVerify, onSaved }) => {
   const [isVerifying, setIsVerifying] = useState(false);
+  // Raw TOML editor: null when closed
+  const [rawToml, setRawToml] = useState<string | null>(null);
+  const [rawEtag, setRawEtag] = useState<string | null>(null);
+  const [rawContentType, setRawContentType] = useState<string | null>(null);
+  const [rawError, setRawError] = useState<string | null>(null);
+  const [isSaving, setIsSaving] = useState(false);
   const [rejectionMode, setRejectionMode] = useState(false);
   const [feedback, setFeedback] = useState('');
+  const [snapshotName, setSnapshotName] = useState<string | null>(null);
+
+  const [comments, setComments] = useState<Comment[]>([]);
+  const [commentDraft, setCommentDraft] = useState('');
+  const [replyTo, setReplyTo] = useState<number | null>(null);
+  const [commentError, setCommentError] = useState<string | null>(null);
+
+  useEffect(() => {
+      api.getComments(ticket.id).then(setComments).catch(e => setCommentError((e as Error).message));
+  }, [ticket.id]);
+
+  const postComment = async () => {
+      if (!commentDraft.trim()) return;
+      setCommentError(null);
+      try {
+          const comment = await api.addComment(ticket.id, 'dashboard', commentDraft, replyTo ?? undefined);
+          setComments([...comments, comment]);
+          setCommentDraft('');
+          setReplyTo(null);
+      } catch (e) {
+          setCommentError((e as Error).message);
+      }
+  };
+
+  // Replies nest under what they answer; a reply whose parent is gone shows at the top level
+  const renderThread = (comment: Comment, depth: number): React.ReactNode => (
+      <div key={comment.id} style={{ marginLeft: depth * 16 }} className="space-y-1">
+          <div className="bg-[#080808] p-3 rounded border border-white/5">
+              <div className="flex items-center gap-2 text-[10px] font-mono text-gray-500 mb-1">
+                  <span className="text-gray-300">{comment.author}</span>
+                  <span>{new Date(comment.at).toLocaleString()}</span>
+                  <button onClick={() => setReplyTo(comment.id)} className="ml-auto hover:text-white">Reply</button>
+              </div>
+              <div className="text-sm text-gray-300 whitespace-pre-line">{comment.body}</div>
+          </div>
+          {comments.filter(c => c.reply_to === comment.id).map(reply => renderThread(reply, depth + 1))}
+      </div>
+  );
+
+  const snapshots = ticket.artifacts?.snapshots ?? [];
+  const activeSnapshot = snapshots.find(s => s.name === snapshotName);
 
   const handleVerify = () => {
     setIsVerifying(true);
@@ -34,6 +83,33 @@ export const TicketDetailModal: React.FC<TicketDetailModalProps> = ({ ticket, on
     }, 2000);
   };
 
+  const openRawEditor = async () => {
+      setRawError(null);
+      try {
+          const { content, etag, contentType } = await api.getRawTicket(ticket.id);
+          setRawToml(content);
+          setRawEtag(etag);
+          setRawContentType(contentType);
+      } catch (e) {
+          setRawError((e as Error).message);
+      }
+  };
+
+  const saveRaw = async () => {
+      if (rawToml === null) return;
+      setIsSaving(true);
+      setRawError(null);
+      try {
+          await api.saveRawTicket(ticket.id, rawToml, rawEtag, rawContentType);
+          setRawToml(null);
+          onSaved?.(ticket.id);
+      } catch (e) {
+          setRawError((e as Error).message);
+      } finally {
+          setIsSaving(false);
+      }
+  };
+
   const handleReject = () => {
       console.log(`Rejected ${ticket.id} with feedback: ${feedback}`);
       onClose();
@@ -69,8 +145,31 @@ export const TicketDetailModal: React.FC<TicketDetailModalProps> = ({ ticket, on
             </div>
           </div>
 
+          {/* Raw TOML Editor */}
+          {rawToml !== null ? (
+          <div className="flex-1 flex flex-col p-6 gap-3 min-h-0">
+              <h3 className="text-[10px] font-mono uppercase text-gray-500 flex items-center gap-2 tracking-widest">
+                  <FileCode size={12} /> {ticket.id}.toml
+              </h3>
+              <textarea
+                  value={rawToml}
+                  onChange={(e) => setRawToml(e.target.value)}
+                  spellCheck={false}
+                  className="flex-1 bg-black border border-white/10 rounded p-3 font-mono text-xs text-gray-200 focus:border-[#7000FF] outline-none resize-none custom-scrollbar"
+              />
+              {rawError && <div className="text-xs text-[#FF0055] font-mono whitespace-pre-wrap">{rawError}</div>}
+              <div className="flex gap-2">
+                  <button onClick={() => { setRawToml(null); setRawError(null); }} className="flex-1 py-2 text-xs text-gray-400 hover:text-white">Cancel</button>
+                  <button onClick={saveRaw} disabled={isSaving} className="flex-1 py-2 bg-[#7000FF] text-white rounded text-xs font-bold hover:bg-[#5c00d4] disabled:opacity-50">
+                      {isSaving ? 'Saving...' : 'Save TOML'}
+                  </button>
+              </div>
+          </div>
+          ) : (
+          <>
           {/* Scrollable Content */}
           <div className="flex-1 overflow-y-auto p-6 space-y-8 custom-scrollbar">
+            {rawError && <div className="text-xs text-[#FF0055] font-mono">{rawError}</div>}
             {/* Description */}
             <section>
                 <h3 className="text-[10px] font-mono uppercase text-gray-500 mb-3 tracking-widest">Description</h3>
@@ -87,6 +186,70 @@ export const TicketDetailModal: React.FC<TicketDetailModalProps> = ({ ticket, on
                 </section>
             )}
 
+            {/* Parent, dependencies, and what is still open */}
+            {(ticket.parent || ticket.follow_up_of || (ticket.depends_on && ticket.depends_on.length > 0) || (ticket.blocked_by && ticket.blocked_by.length > 0)) && (
+                <section>
+                    <h3 className="text-[10px] font-mono uppercase text-gray-500 mb-3 tracking-widest">Links</h3>
+                    <div className="text-xs font-mono text-gray-300 space-y-1">
+                        {ticket.parent && <div>Part of <span className="text-white">{ticket.parent}</span></div>}
+                        {ticket.follow_up_of && <div>Follow-up of <span className="text-white">{ticket.follow_up_of}</span></div>}
+                        {ticket.depends_on && ticket.depends_on.length > 0 && (
+                            <div>Depends on <span className="text-white">{ticket.depends_on.join(', ')}</span></div>
+                        )}
+                        {ticket.blocked_by && ticket.blocked_by.length > 0 && (
+                            <div className="text-amber-400">Blocked until done: {ticket.blocked_by.join(', ')}</div>
+                        )}
+                    </div>
+                </section>
+            )}
+
+            {/* Discussion */}
+            <section>
+                <h3 className="text-[10px] font-mono uppercase text-gray-500 mb-3 flex items-center gap-2 tracking-widest">
+                    <MessageSquare size={12} /> Discussion
+                </h3>
+                <div className="space-y-2">
+                    {comments
+                        .filter(c => c.reply_to == null || !comments.some(o => o.id === c.reply_to))
+                        .map(c => renderThread(c, 0))}
+                    {replyTo !== null && (
+                        <div className="text-[10px] font-mono text-gray-500">
+                            Replying to #{replyTo} <button onClick={() => setReplyTo(null)} className="hover:text-white">(cancel)</button>
+                        </div>
+                    )}
+                    <div className="flex gap-2">
+                        <input
+                            value={commentDraft}
+                            onChange={(e) => setCommentDraft(e.target.value)}
+                            onKeyDown={(e) => { if (e.key === 'Enter') postComment(); }}
+                            placeholder="Add a comment..."
+                            className="flex-1 bg-black border border-white/10 rounded px-2 py-1 text-sm text-white focus:border-[#7000FF] outline-none"
+                        />
+                        <button onClick={postComment} className="px-3 py-1 text-xs rounded border border-white/10 text-gray-300 hover:text-white">Post</button>
+                    </div>
+                    {commentError && <div className="text-xs text-[#FF0055]">{commentError}</div>}
+                </div>
+            </section>
+
+            {/* Attached assets */}
+            {ticket.assets && ticket.assets.length > 0 && (
+                <section>
+                    <h3 className="text-[10px] font-mono uppercase text-gray-500 mb-3 tracking-widest">Assets</h3>
+                    <div className="flex flex-wrap gap-3">
+                        {ticket.assets.map(name => (
+                            <a key={name} href={`assets/${name}`} target="_blank" rel="noreferrer" title={name}
+                               className="w-24 h-24 bg-[#080808] rounded border border-white/5 hover:border-[#7000FF] flex items-center justify-center overflow-hidden">
+                                {/\.(png|jpe?g|gif|svg|webp)$/i.test(name) ? (
+                                    <img src={`assets/${name}`} alt={name} className="max-w-full max-h-full object-contain" />
+                                ) : (
+                                    <span className="text-[10px] font-mono text-gray-400 p-2 break-all">{name}</span>
+                                )}
+                            </a>
+                        ))}
+                    </div>
+                </section>
+            )}
+
             {/* Logs */}
             {ticket.logs && (
                 <section>
@@ -147,6 +310,8 @@ export const TicketDetailModal: React.FC<TicketDetailModalProps> = ({ ticket, on
                   </div>
               )}
            </div>
+          </>
+          )}
         </div>
 
         {/* Right Column: Visuals */}
@@ -155,7 +320,7 @@ export const TicketDetailModal: React.FC<TicketDetailModalProps> = ({ ticket, on
             <div className="h-16 border-b border-white/10 flex items-center justify-between px-6 bg-[#141414]">
                 <div className="flex items-center gap-4">
                     <h2 className="font-semibold text-white tracking-tight">Verification Suite</h2>
-                    {ticket.metrics && (
+                    {ticket.metrics?.render_time_ms !== undefined && (
                         <div className={`flex items-center gap-2 px-3 py-1 rounded-full border text-xs font-mono ${
                             ticket.metrics.render_time_ms > 30 
                             ? 'bg-[#FF0055]/10 border-[#FF0055]/30 text-[#FF0055]' 
@@ -167,6 +332,9 @@ export const TicketDetailModal: React.FC<TicketDetailModalProps> = ({ ticket, on
                     )}
                 </div>
                 <div className="flex items-center gap-2">
+                     <button onClick={openRawEditor} disabled={rawToml !== null} className="flex items-center gap-2 px-3 py-1.5 bg-white/5 border border-white/10 text-gray-300 text-xs font-medium hover:bg-white/10 hover:text-white rounded transition-colors disabled:opacity-50">
+                        <FileCode size={14} /> Edit TOML
+                    </button>
                      <button onClick={handleVerify} disabled={isVerifying} className="flex items-center gap-2 px-3 py-1.5 bg-white/5 border border-white/10 text-gray-300 text-xs font-medium hover:bg-white/10 hover:text-white rounded transition-colors disabled:opacity-50">
                         <Play size={14} fill="currentColor" /> Rerun Tests
                     </button>
@@ -181,11 +349,24 @@ export const TicketDetailModal: React.FC<TicketDetailModalProps> = ({ ticket, on
             <div className="flex-1 p-6 flex flex-col gap-6 overflow-y-auto bg-[url('https://grainy-gradients.vercel.app/noise.svg')]">
                 {/* Visual Comparator */}
                 {ticket.artifacts ? (
-                    <div className="flex-1 min-h-[400px]">
+                    <div className="flex-1 min-h-[400px] flex flex-col gap-2">
+                        {snapshots.length > 0 && (
+                            <div className="flex gap-2">
+                                {snapshots.map(s => (
+                                    <button
+                                        key={s.name}
+                                        onClick={() => setSnapshotName(s.name)}
+                                        className={`px-2 py-1 text-xs rounded border ${activeSnapshot?.name === s.name ? 'border-white/40 text-white' : 'border-white/10 text-gray-500'}`}
+                                    >
+                                        {s.name}
+                                    </button>
+                                ))}
+                            </div>
+                        )}
                         <ImageComparator 
-                            beforeUrl={ticket.artifacts.before_image}
-                            afterUrl={ticket.artifacts.after_image}
-                            diffUrl={ticket.artifacts.diff_image}
+                            beforeUrl={activeSnapshot ? activeSnapshot.before_image : ticket.artifacts.before_image}
+                            afterUrl={activeSnapshot ? activeSnapshot.after_image : ticket.artifacts.after_image}
+                            diffUrl={activeSnapshot ? undefined : ticket.artifacts.diff_image}
                         />
                     </div>
                 ) : (
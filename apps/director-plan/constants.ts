@@ -16,6 +16,7 @@ export const COLUMNS: Column[] = [
   { id: 'todo', label: 'To Do' },
   { id: 'in_progress', label: 'Active' },
   { id: 'review', label: 'Review' },
+  { id: 'blocked', label: 'Blocked' },
   { id: 'done', label: 'Done' },
 ];
 
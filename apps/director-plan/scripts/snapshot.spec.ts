@@ -4,17 +4,28 @@ import path from 'path';
 
 // Usage:
 // TARGET_URL=http://localhost:3000 OUTPUT=proof/actual.png npx playwright test scripts/snapshot.spec.ts
+// Optional: VIEWPORT=390x844 to capture at a specific size, WAIT_FOR='#app' to wait for a selector.
 
 const targetUrl = process.env.TARGET_URL || 'http://localhost:3000';
 const outputPath = process.env.OUTPUT || 'proof/actual.png';
+const viewport = process.env.VIEWPORT;
+const waitFor = process.env.WAIT_FOR;
 
 test('capture screenshot', async ({ page }) => {
+  if (viewport) {
+    const [width, height] = viewport.split('x').map(Number);
+    await page.setViewportSize({ width, height });
+  }
+
   console.log(`Navigating to ${targetUrl}`);
   await page.goto(targetUrl);
 
   // Wait for network idle or specific element if needed
   // For now, wait for network idle to ensure assets loaded
   await page.waitForLoadState('networkidle');
+  if (waitFor) {
+    await page.waitForSelector(waitFor);
+  }
 
   // Ensure output directory exists
   const dir = path.dirname(outputPath);
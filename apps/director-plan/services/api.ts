@@ -1,11 +1,12 @@
-import { Ticket, Asset, TicketStatus } from '../types';
+import { Ticket, Asset, TicketStatus, WorkerStatus, Comment } from '../types';
 
 // Real API Service
 class ApiService {
-  private baseUrl = '/api';
+  // Relative, so it resolves under the <base href> the server adds behind --base-path
+  private baseUrl = 'api';
 
   async getTickets(): Promise<Ticket[]> {
-    const response = await fetch(`${this.baseUrl}/tickets`);
+    const response = await fetch(`${this.baseUrl}/tickets?expand=history,metrics,artifacts`);
     if (!response.ok) {
       throw new Error(`Failed to fetch tickets: ${response.statusText}`);
     }
@@ -59,6 +60,41 @@ class ApiService {
     }
   }
 
+  /** The ticket file as stored, with the ETag and content type to send back when saving it. */
+  async getRawTicket(id: string): Promise<{ content: string; etag: string | null; contentType: string | null }> {
+    const response = await fetch(`${this.baseUrl}/tickets/${id}/raw`);
+    if (!response.ok) {
+      throw new Error(`Failed to fetch ticket ${id}: ${response.statusText}`);
+    }
+    return {
+      content: await response.text(),
+      etag: response.headers.get('ETag'),
+      contentType: response.headers.get('Content-Type'),
+    };
+  }
+
+  /** Replaces the ticket file. Fails if it changed since `etag` was read, or if the content is invalid. */
+  async saveRawTicket(id: string, content: string, etag: string | null, contentType: string | null): Promise<string> {
+    const headers: Record<string, string> = { 'Content-Type': contentType ?? 'application/toml' };
+    if (etag) {
+      headers['If-Match'] = etag;
+    }
+    const response = await fetch(`${this.baseUrl}/tickets/${id}/raw`, {
+      method: 'PUT',
+      headers,
+      body: content,
+    });
+
+    if (!response.ok) {
+      const body = await response.json().catch(() => null);
+      if (response.status === 412) {
+        throw new Error('The ticket was changed by someone else. Reload it and apply your edits again.');
+      }
+      throw new Error(body?.error ?? `Failed to save ticket ${id}: ${response.statusText}`);
+    }
+    return (await response.json()).etag;
+  }
+
   async verifyTicket(id: string): Promise<{ success: boolean; output: string; artifacts_path?: string }> {
     const response = await fetch(`${this.baseUrl}/tickets/${id}/verify`, {
       method: 'POST',
@@ -88,7 +124,9 @@ class ApiService {
     });
 
     if (!response.ok) {
-      throw new Error(`Failed to upload asset: ${response.statusText}`);
+      // 400/413/415 carry the reason, e.g. a disallowed type or a file over its size limit
+      const body = await response.json().catch(() => null);
+      throw new Error(body?.error ?? `Failed to upload asset: ${response.statusText}`);
     }
 
     const result = await response.json();
@@ -104,6 +142,69 @@ class ApiService {
       preview_url: uploaded.url
     };
   }
+
+  async deleteAsset(name: string): Promise<void> {
+    const response = await fetch(`${this.baseUrl}/assets/${encodeURIComponent(name)}`, { method: 'DELETE' });
+    if (!response.ok) {
+      throw new Error(`Failed to delete asset: ${response.statusText}`);
+    }
+  }
+
+  // Returns the tickets whose `assets` were updated to the new name
+  async renameAsset(name: string, newName: string): Promise<string[]> {
+    const response = await fetch(`${this.baseUrl}/assets/${encodeURIComponent(name)}`, {
+      method: 'PATCH',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ name: newName }),
+    });
+    if (!response.ok) {
+      const body = await response.json().catch(() => null);
+      throw new Error(body?.error ?? `Failed to rename asset: ${response.statusText}`);
+    }
+    const result = await response.json();
+    return result.updated_tickets;
+  }
+
+  async getComments(ticketId: string): Promise<Comment[]> {
+    const response = await fetch(`${this.baseUrl}/tickets/${ticketId}/comments`);
+    if (!response.ok) {
+      throw new Error(`Failed to fetch comments: ${response.statusText}`);
+    }
+    return response.json();
+  }
+
+  async addComment(ticketId: string, author: string, body: string, replyTo?: number): Promise<Comment> {
+    const response = await fetch(`${this.baseUrl}/tickets/${ticketId}/comments`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ author, body, reply_to: replyTo ?? null }),
+    });
+    if (!response.ok) {
+      const result = await response.json().catch(() => null);
+      throw new Error(result?.error ?? `Failed to add comment: ${response.statusText}`);
+    }
+    return response.json();
+  }
+
+  // Calls onEvent with each plan event's type as it happens; returns a function that stops listening
+  subscribeEvents(onEvent: (type: string) => void): () => void {
+    const source = new EventSource(`${this.baseUrl}/events`);
+    const types = [
+      'ticket_created', 'status_changed', 'ticket_deleted', 'comment_added',
+      'execution_started', 'verification_finished', 'execution_finished', 'pr_opened',
+    ];
+    types.forEach(type => source.addEventListener(type, () => onEvent(type)));
+    return () => source.close();
+  }
+
+  // Workers heard from in the last hour
+  async getWorkers(): Promise<WorkerStatus[]> {
+    const response = await fetch(`${this.baseUrl}/workers`);
+    if (!response.ok) {
+      throw new Error(`Failed to fetch workers: ${response.statusText}`);
+    }
+    return response.json();
+  }
 }
 
 export const api = new ApiService();
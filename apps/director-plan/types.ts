@@ -1,15 +1,43 @@
 export interface Metrics {
-  render_time_ms: number;
-  render_time_diff: string; // e.g. "+0.2"
+  render_time_ms?: number;
+  render_time_diff?: string; // e.g. "+0.2"
+  bundle_kb?: number;
+  time_in_status_secs?: Record<string, number>;
+  agent_time_ms?: number;
+  verification_time_ms?: number;
+  executions?: number;
+  agent_result?: AgentResult;
+}
+
+export interface FollowUpTicket {
+  title: string;
+  description: string;
+  priority?: 'low' | 'medium' | 'high' | 'critical' | null;
+  type?: 'feature' | 'bug' | 'chore' | 'spike' | null;
+}
+
+export interface AgentResult {
+  version: number;
+  confidence: number;
+  summary: string;
+  files_changed: string[];
+  follow_up_tickets: FollowUpTicket[];
 }
 
 export interface Artifacts {
   before_image: string;
   after_image: string;
   diff_image?: string;
+  snapshots?: SnapshotArtifacts[];
+}
+
+export interface SnapshotArtifacts {
+  name: string;
+  before_image: string;
+  after_image: string;
 }
 
-export type TicketStatus = 'todo' | 'in_progress' | 'review' | 'done' | 'archived';
+export type TicketStatus = 'todo' | 'in_progress' | 'review' | 'blocked' | 'done' | 'archived';
 
 export interface Ticket {
   id: string;
@@ -23,6 +51,14 @@ export interface Ticket {
   artifacts?: Artifacts;
   logs?: string[];
   specs?: string;
+  due_date?: string;
+  due_state?: 'overdue' | 'due_soon' | 'on_track';
+  assets?: string[];
+  parent?: string | null;
+  depends_on?: string[];
+  follow_up_of?: string | null;
+  blocked_reason?: string | null;
+  blocked_by?: string[];
 }
 
 export interface Asset {
@@ -38,3 +74,21 @@ export interface Column {
   id: TicketStatus;
   label: string;
 }
+
+export interface Comment {
+  id: number;
+  author: string;
+  at: string;
+  body: string;
+  reply_to?: number | null;
+}
+
+export interface WorkerStatus {
+  id: string;
+  current_ticket?: string | null;
+  workspace?: string | null;
+  uptime_secs: number;
+  last_error?: string | null;
+  last_seen: string;
+  alive: boolean;
+}
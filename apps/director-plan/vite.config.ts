@@ -5,6 +5,8 @@ import react from '@vitejs/plugin-react';
 export default defineConfig(({ mode }) => {
     const env = loadEnv(mode, '.', '');
     return {
+      // Relative asset URLs, so the build works when `serve --base-path` mounts it under a subpath
+      base: './',
       server: {
         port: 3000,
         host: '0.0.0.0',
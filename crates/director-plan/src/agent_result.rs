@@ -0,0 +1,228 @@
+use std::fs;
+use std::path::Path;
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use toml_edit::DocumentMut;
+use utoipa::ToSchema;
+use crate::config::FollowUpConfig;
+use crate::store::TicketStore;
+use crate::types::{Priority, Status, Ticket, TicketType};
+
+/// Info string of the fenced block an agent ends its reply with.
+pub const RESULT_FENCE: &str = "director-plan-result";
+
+/// The only `version` of the result block this build understands.
+pub const RESULT_VERSION: u32 = 1;
+
+/// Appended to every execution prompt so the agent knows how to report back.
+pub const RESULT_INSTRUCTIONS: &str = "## Reporting Back\n\
+When you are done, end your reply with a single ```director-plan-result fenced block holding a JSON object:\n\
+- `version`: 1\n\
+- `confidence`: how sure you are the ticket is done, from 0.0 to 1.0\n\
+- `summary`: one or two sentences on what you changed\n\
+- `files_changed`: paths relative to the repository root\n\
+- `follow_up_tickets`: adjacent work you noticed but didn't do, each with `title`, `description`, \
+and optionally `priority` (low, medium, high, critical) and `type` (feature, bug, chore, spike)\n";
+
+/// What an agent reports about its turn in the ```director-plan-result block.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct AgentResult {
+    pub version: u32,
+    pub confidence: f32,
+    #[serde(default)]
+    pub summary: String,
+    #[serde(default)]
+    pub files_changed: Vec<String>,
+    #[serde(default)]
+    pub follow_up_tickets: Vec<FollowUpTicket>,
+}
+
+/// Adjacent work the agent noticed while doing the ticket.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct FollowUpTicket {
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub priority: Option<Priority>,
+    #[serde(default, rename = "type")]
+    pub ticket_type: Option<TicketType>,
+}
+
+/// Reads the result block out of an agent's reply. `Ok(None)` if there is none; an error if
+/// there is more than one, or it isn't closed, or it fails [`validate`].
+pub fn parse(output: &str) -> Result<Option<AgentResult>> {
+    let mut blocks = Vec::new();
+    let mut current: Option<String> = None;
+    for line in output.lines() {
+        let trimmed = line.trim_start();
+        match current.as_mut() {
+            None => {
+                if trimmed.strip_prefix("```").is_some_and(|info| info.trim() == RESULT_FENCE) {
+                    current = Some(String::new());
+                }
+            }
+            Some(_) if trimmed.starts_with("```") => blocks.extend(current.take()),
+            Some(block) => {
+                block.push_str(line);
+                block.push('\n');
+            }
+        }
+    }
+    if current.is_some() {
+        bail!("The {} block is never closed", RESULT_FENCE);
+    }
+
+    let block = match blocks.as_slice() {
+        [] => return Ok(None),
+        [block] => block,
+        _ => bail!("Found {} {} blocks; send exactly one", blocks.len(), RESULT_FENCE),
+    };
+    let result: AgentResult = serde_json::from_str(block)
+        .with_context(|| format!("The {} block isn't valid", RESULT_FENCE))?;
+    validate(&result)?;
+    Ok(Some(result))
+}
+
+/// Checks what the JSON shape can't: the version, the confidence range, and that paths and
+/// titles make sense.
+pub fn validate(result: &AgentResult) -> Result<()> {
+    if result.version != RESULT_VERSION {
+        bail!("Unsupported {} version {} (expected {})", RESULT_FENCE, result.version, RESULT_VERSION);
+    }
+    if !(0.0..=1.0).contains(&result.confidence) {
+        bail!("confidence must be between 0.0 and 1.0, got {}", result.confidence);
+    }
+    for path in &result.files_changed {
+        if path.trim().is_empty() || path.starts_with('/') || path.split(['/', '\\']).any(|part| part == "..") {
+            bail!("files_changed entry '{}' isn't a path inside the repository", path);
+        }
+    }
+    if result.follow_up_tickets.iter().any(|t| t.title.trim().is_empty()) {
+        bail!("Every follow_up_tickets entry needs a title");
+    }
+    Ok(())
+}
+
+/// Why follow-up tickets start out blocked.
+const FOLLOW_UP_BLOCKED_REASON: &str = "Needs a verification command before it can be run";
+
+/// Creates the run's `follow_up_tickets` with `meta.follow_up_of` set to `origin`, up to
+/// `config.max_per_run`. They start `blocked` until someone gives them a verification command,
+/// since a ticket without one would pass verification having checked nothing. The origin's history lists what was created and what
+/// was dropped over the cap. Returns the new IDs.
+pub fn create_follow_ups(workspace_root: &Path, origin: &Ticket, result: &AgentResult, config: &FollowUpConfig) -> Result<Vec<String>> {
+    if !config.enabled || result.follow_up_tickets.is_empty() {
+        return Ok(Vec::new());
+    }
+    let store = TicketStore::new(workspace_root);
+    fs::create_dir_all(store.tickets_dir())?;
+    let mut next = crate::planner::next_number(&store)?;
+    let created_at: toml_datetime::Datetime = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string().parse()?;
+    let (kept, dropped) = result.follow_up_tickets.split_at(result.follow_up_tickets.len().min(config.max_per_run));
+
+    let mut ids = Vec::new();
+    for follow_up in kept {
+        let id = format!("T-{:03}", next);
+        next += 1;
+
+        let mut doc = DocumentMut::new();
+        doc["meta"] = toml_edit::table();
+        doc["meta"]["id"] = toml_edit::value(id.as_str());
+        doc["meta"]["title"] = toml_edit::value(follow_up.title.trim());
+        doc["meta"]["status"] = toml_edit::value(Status::Blocked.to_string());
+        doc["meta"]["blocked_reason"] = toml_edit::value(FOLLOW_UP_BLOCKED_REASON);
+        let priority = follow_up.priority.clone().unwrap_or(Priority::Medium);
+        doc["meta"]["priority"] = toml_edit::value(format!("{:?}", priority).to_lowercase());
+        if let Some(ticket_type) = &follow_up.ticket_type {
+            doc["meta"]["type"] = toml_edit::value(format!("{:?}", ticket_type).to_lowercase());
+        }
+        doc["meta"]["created_at"] = toml_edit::value(created_at);
+        crate::migrate::stamp(&mut doc);
+        doc["meta"]["follow_up_of"] = toml_edit::value(origin.meta.id.as_str());
+
+        doc["spec"] = toml_edit::table();
+        doc["spec"]["description"] = toml_edit::value(follow_up.description.as_str());
+        doc["verification"] = toml_edit::table();
+        doc["verification"]["command"] = toml_edit::value("");
+        crate::append_history_entry(&mut doc, &format!("Suggested by the agent while working on {}.", origin.meta.id));
+        crate::append_history_entry(&mut doc, &format!("Blocked: {}", FOLLOW_UP_BLOCKED_REASON));
+
+        store.save_document(&id, &doc)?;
+        ids.push(id);
+    }
+
+    let mut note = String::new();
+    if !ids.is_empty() {
+        note = format!("Created follow-up tickets: {}.", ids.join(", "));
+    }
+    if !dropped.is_empty() {
+        let titles: Vec<&str> = dropped.iter().map(|t| t.title.trim()).collect();
+        note = format!("{} Over the follow_ups.max_per_run cap, not created: {}.", note, titles.join("; "));
+    }
+    store.update(&origin.meta.id, |doc| {
+        crate::append_history_entry(doc, note.trim());
+        Ok(())
+    })?;
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let reply = "Done.\n```director-plan-result\n{\"version\": 1, \"confidence\": 0.85, \"summary\": \"Added the endpoint\", \
+            \"files_changed\": [\"src/api.rs\"], \"follow_up_tickets\": [{\"title\": \"Paginate\", \"priority\": \"low\"}]}\n```\n";
+        let result = parse(reply).unwrap().unwrap();
+        assert_eq!(result.confidence, 0.85);
+        assert_eq!(result.files_changed, ["src/api.rs"]);
+        assert_eq!(result.follow_up_tickets[0].priority, Some(Priority::Low));
+
+        // Stray JSON elsewhere in the reply is ignored
+        assert!(parse("{\"confidence\": 0.9}").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_blocks() {
+        let block = |json: &str| format!("```director-plan-result\n{}\n```", json);
+        assert!(parse(&block(r#"{"version": 2, "confidence": 0.5}"#)).is_err());
+        assert!(parse(&block(r#"{"version": 1, "confidence": 1.5}"#)).is_err());
+        assert!(parse(&block(r#"{"version": 1}"#)).is_err());
+        assert!(parse(&block(r#"{"version": 1, "confidence": 0.5, "mood": "good"}"#)).is_err());
+        assert!(parse(&block(r#"{"version": 1, "confidence": 0.5, "files_changed": ["../etc/passwd"]}"#)).is_err());
+        assert!(parse("```director-plan-result\n{\"version\": 1, \"confidence\": 0.5}\n").is_err());
+
+        let twice = format!("{}\n{}", block(r#"{"version": 1, "confidence": 0.5}"#), block(r#"{"version": 1, "confidence": 0.6}"#));
+        assert!(parse(&twice).is_err());
+    }
+
+    #[test]
+    fn test_create_follow_ups() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("plan/tickets")).unwrap();
+        std::fs::write(root.join("plan/tickets/T-004.toml"), "[meta]\nid = \"T-004\"\ntitle = \"Settings API\"\nstatus = \"in_progress\"\npriority = \"high\"\n\n[spec]\ndescription = \"\"\n\n[verification]\ncommand = \"cargo test\"\n").unwrap();
+        let plan = crate::DirectorPlan::new(root.to_path_buf());
+        let origin = plan.get_ticket("T-004").unwrap();
+
+        let reply = "```director-plan-result\n{\"version\": 1, \"confidence\": 0.9, \"follow_up_tickets\": \
+            [{\"title\": \"Paginate\", \"type\": \"feature\"}, {\"title\": \"Fix typo\"}, {\"title\": \"Rate limit\"}]}\n```";
+        let result = parse(reply).unwrap().unwrap();
+        let config = FollowUpConfig { enabled: true, max_per_run: 2 };
+        assert_eq!(create_follow_ups(root, &origin, &result, &config).unwrap(), ["T-005", "T-006"]);
+
+        let paginate = plan.get_ticket("T-005").unwrap();
+        assert_eq!(paginate.meta.follow_up_of.as_deref(), Some("T-004"));
+        assert_eq!(paginate.meta.status, Status::Blocked);
+        assert_eq!(paginate.meta.blocked_reason.as_deref(), Some(FOLLOW_UP_BLOCKED_REASON));
+        assert_eq!(paginate.meta.ticket_type, Some(TicketType::Feature));
+        assert!(plan.get_ticket("T-004").unwrap().history.log.last().unwrap().contains("not created: Rate limit"));
+
+        let disabled = FollowUpConfig { enabled: false, max_per_run: 2 };
+        assert!(create_follow_ups(root, &origin, &result, &disabled).unwrap().is_empty());
+    }
+}
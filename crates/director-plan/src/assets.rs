@@ -0,0 +1,259 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result, anyhow};
+use toml_edit::DocumentMut;
+use crate::config::AssetsConfig;
+use crate::store::TicketStore;
+
+/// Names Windows reserves for devices, whatever the extension (`con.png` included).
+const RESERVED_NAMES: &[&str] = &[
+    "con", "prn", "aux", "nul",
+    "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8", "com9",
+    "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+/// Folder metadata files that file managers create and hide.
+const SYSTEM_FILES: &[&str] = &["desktop.ini", "thumbs.db", "ehthumbs.db"];
+
+/// Uploaded files (fonts, images, Lottie JSON) live in `assets/` and are served under `/assets`.
+pub fn assets_dir(workspace_root: &Path) -> PathBuf {
+    workspace_root.join("assets")
+}
+
+/// Asset names are plain file names: no directories, nothing hidden, no system files. The
+/// checks apply to the name as filesystems may normalize it, so ` .env` or `thumbs.db.` fail too.
+pub fn validate_name(name: &str) -> Result<()> {
+    let normalized = name.trim().trim_end_matches(['.', ' ']).to_lowercase();
+    let stem = normalized.split('.').next().unwrap_or_default();
+    if normalized.is_empty()
+        || normalized.starts_with('.')
+        || name.contains(['/', '\\', ':'])
+        || name.contains("..")
+        || name.chars().any(char::is_control)
+        || RESERVED_NAMES.contains(&stem)
+        || SYSTEM_FILES.contains(&normalized.as_str())
+    {
+        return Err(anyhow!("Invalid asset name '{}'", name));
+    }
+    Ok(())
+}
+
+/// Why an upload was refused.
+#[derive(Debug)]
+pub enum UploadRejection {
+    InvalidName(String),
+    /// The extension's MIME type isn't in `[assets] allowed_types`
+    UnsupportedType { name: String, mime: String },
+    /// The content doesn't look like what the extension says
+    ContentMismatch { name: String, mime: String },
+    TooLarge { name: String, size: u64, limit: u64 },
+}
+
+impl fmt::Display for UploadRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UploadRejection::InvalidName(name) => write!(f, "Invalid asset name '{}'", name),
+            UploadRejection::UnsupportedType { name, mime } => write!(f, "'{}': type {} is not allowed", name, mime),
+            UploadRejection::ContentMismatch { name, mime } => write!(f, "'{}': content is not {}", name, mime),
+            UploadRejection::TooLarge { name, size, limit } => write!(f, "'{}' is {} bytes; the limit for its type is {}", name, size, limit),
+        }
+    }
+}
+
+impl std::error::Error for UploadRejection {}
+
+/// Checks an upload against `[assets]`: the name, the type its extension implies, the
+/// content's signature for that type, and the type's size limit.
+pub fn check_upload(config: &AssetsConfig, name: &str, data: &[u8]) -> Result<(), UploadRejection> {
+    if validate_name(name).is_err() {
+        return Err(UploadRejection::InvalidName(name.to_string()));
+    }
+    let mime = mime_guess::from_path(name).first_or_octet_stream().essence_str().to_string();
+    if !config.allows(&mime) {
+        return Err(UploadRejection::UnsupportedType { name: name.to_string(), mime });
+    }
+    if !content_matches(&mime, data) {
+        return Err(UploadRejection::ContentMismatch { name: name.to_string(), mime });
+    }
+    let limit = config.max_bytes_for(&mime);
+    if data.len() as u64 > limit {
+        return Err(UploadRejection::TooLarge { name: name.to_string(), size: data.len() as u64, limit });
+    }
+    Ok(())
+}
+
+/// Whether `data` starts with the signature of `mime`. Types without a known signature pass.
+fn content_matches(mime: &str, data: &[u8]) -> bool {
+    match mime {
+        "image/png" => data.starts_with(b"\x89PNG\r\n\x1a\n"),
+        "image/jpeg" => data.starts_with(&[0xFF, 0xD8, 0xFF]),
+        "image/gif" => data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a"),
+        "image/webp" => data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP",
+        "image/bmp" => data.starts_with(b"BM"),
+        "image/svg+xml" => std::str::from_utf8(data).is_ok_and(|text| text.contains("<svg")),
+        "font/ttf" => data.starts_with(&[0x00, 0x01, 0x00, 0x00]) || data.starts_with(b"true"),
+        "font/otf" => data.starts_with(b"OTTO"),
+        "font/woff" => data.starts_with(b"wOFF"),
+        "font/woff2" => data.starts_with(b"wOF2"),
+        "application/json" => serde_json::from_slice::<serde_json::Value>(data).is_ok(),
+        "application/pdf" => data.starts_with(b"%PDF-"),
+        _ => true,
+    }
+}
+
+/// `name`, or `name-1`, `name-2`, ... (before the extension) if that's taken, so an upload
+/// never overwrites an existing asset.
+pub fn unique_name(dir: &Path, name: &str) -> String {
+    if !dir.join(name).exists() {
+        return name.to_string();
+    }
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem, format!(".{}", ext)),
+        _ => (name, String::new()),
+    };
+    (1..)
+        .map(|n| format!("{}-{}{}", stem, n, ext))
+        .find(|candidate| !dir.join(candidate).exists())
+        .unwrap()
+}
+
+/// Renames an asset and every ticket's `spec.assets` reference to it. Returns the IDs of the
+/// tickets that were updated.
+pub fn rename(workspace_root: &Path, from: &str, to: &str) -> Result<Vec<String>> {
+    validate_name(from)?;
+    validate_name(to)?;
+    let dir = assets_dir(workspace_root);
+    if !dir.join(from).is_file() {
+        return Err(anyhow!("Asset '{}' not found", from));
+    }
+    if dir.join(to).exists() {
+        return Err(anyhow!("Asset '{}' already exists", to));
+    }
+    fs::rename(dir.join(from), dir.join(to))
+        .with_context(|| format!("Failed to rename asset '{}' to '{}'", from, to))?;
+
+    update_references(workspace_root, from, Some(to), &format!("Asset {} renamed to {}.", from, to))
+}
+
+/// Deletes an asset and drops it from every ticket's `spec.assets`. Returns the IDs of the
+/// tickets it was unlinked from.
+pub fn delete(workspace_root: &Path, name: &str) -> Result<Vec<String>> {
+    validate_name(name)?;
+    let path = assets_dir(workspace_root).join(name);
+    if !path.is_file() {
+        return Err(anyhow!("Asset '{}' not found", name));
+    }
+    fs::remove_file(&path).with_context(|| format!("Failed to delete asset '{}'", name))?;
+
+    update_references(workspace_root, name, None, &format!("Asset {} deleted.", name))
+}
+
+/// Replaces (or with `None`, removes) `name` in each ticket's `spec.assets`, with a history entry.
+fn update_references(workspace_root: &Path, name: &str, replacement: Option<&str>, message: &str) -> Result<Vec<String>> {
+    let store = TicketStore::new(workspace_root);
+    let tickets_dir = store.tickets_dir();
+    if !tickets_dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut updated = Vec::new();
+    for entry in fs::read_dir(&tickets_dir)?.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        let id = match path.file_stem().and_then(|s| s.to_str()) {
+            Some(id) if crate::ticket_format::is_ticket_file(&path) => id.to_string(),
+            _ => continue,
+        };
+        let mut doc = store.load_document(&id)?;
+        if replace_reference(&mut doc, name, replacement) {
+            crate::append_history_entry(&mut doc, message);
+            store.save_document(&id, &doc)?;
+            updated.push(id);
+        }
+    }
+    updated.sort();
+    Ok(updated)
+}
+
+fn replace_reference(doc: &mut DocumentMut, name: &str, replacement: Option<&str>) -> bool {
+    let assets = match doc.get_mut("spec")
+        .and_then(|spec| spec.get_mut("assets"))
+        .and_then(|assets| assets.as_array_mut())
+    {
+        Some(assets) => assets,
+        None => return false,
+    };
+    let index = match assets.iter().position(|v| v.as_str() == Some(name)) {
+        Some(index) => index,
+        None => return false,
+    };
+    match replacement {
+        Some(to) => {
+            assets.replace(index, to);
+        }
+        None => {
+            assets.remove(index);
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unique_name() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(unique_name(dir.path(), "logo.png"), "logo.png");
+        fs::write(dir.path().join("logo.png"), "").unwrap();
+        fs::write(dir.path().join("logo-1.png"), "").unwrap();
+        assert_eq!(unique_name(dir.path(), "logo.png"), "logo-2.png");
+        fs::write(dir.path().join("LICENSE"), "").unwrap();
+        assert_eq!(unique_name(dir.path(), "LICENSE"), "LICENSE-1");
+    }
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("mockup.png").is_ok());
+        for bad in ["", ".env", " .env", "../x.png", "a/b.png", "a\\b.png", "CON.png", "Thumbs.db", "desktop.ini.", "a\nb.png"] {
+            assert!(validate_name(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_check_upload() {
+        let config = AssetsConfig::default();
+        let png = b"\x89PNG\r\n\x1a\n....";
+        assert!(check_upload(&config, "mockup.png", png).is_ok());
+        assert!(check_upload(&config, "tokens.json", b"{\"a\": 1}").is_ok());
+        assert!(matches!(check_upload(&config, "mockup.jpg", png), Err(UploadRejection::ContentMismatch { .. })));
+        assert!(matches!(check_upload(&config, "run.sh", b"#!/bin/sh"), Err(UploadRejection::UnsupportedType { .. })));
+        assert!(matches!(check_upload(&config, "page.html", b"<html>"), Err(UploadRejection::UnsupportedType { .. })));
+        assert!(matches!(check_upload(&config, ".htaccess", b""), Err(UploadRejection::InvalidName(_))));
+
+        let mut small = AssetsConfig::default();
+        small.max_bytes.insert("image/png".to_string(), 4);
+        assert!(matches!(check_upload(&small, "mockup.png", png), Err(UploadRejection::TooLarge { limit: 4, .. })));
+    }
+
+    #[test]
+    fn test_rename_updates_tickets() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("assets")).unwrap();
+        fs::create_dir_all(dir.path().join("plan/tickets")).unwrap();
+        fs::write(dir.path().join("assets/old.png"), "").unwrap();
+        fs::write(dir.path().join("plan/tickets/T-001.toml"), "[spec]\n# mockups\nassets = [\"old.png\", \"other.png\"]\n").unwrap();
+        fs::write(dir.path().join("plan/tickets/T-002.toml"), "[spec]\nassets = []\n").unwrap();
+
+        assert_eq!(rename(dir.path(), "old.png", "new.png").unwrap(), vec!["T-001"]);
+        assert!(dir.path().join("assets/new.png").is_file());
+        let ticket = fs::read_to_string(dir.path().join("plan/tickets/T-001.toml")).unwrap();
+        assert!(ticket.contains("# mockups"));
+        assert!(ticket.contains("assets = [\"new.png\", \"other.png\"]"));
+
+        assert_eq!(delete(dir.path(), "new.png").unwrap(), vec!["T-001"]);
+        let ticket = fs::read_to_string(dir.path().join("plan/tickets/T-001.toml")).unwrap();
+        assert!(!ticket.contains("new.png"));
+    }
+}
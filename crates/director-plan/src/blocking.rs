@@ -0,0 +1,93 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use anyhow::Result;
+use crate::types::{Status, Ticket};
+
+/// Returned (wrapped in `anyhow::Error`) when a ticket would be claimed, started or closed while
+/// tickets it waits on (its children from a split, or its `depends_on`) are still open.
+#[derive(Debug)]
+pub struct Blocked {
+    pub id: String,
+    pub waiting_on: Vec<String>,
+}
+
+impl fmt::Display for Blocked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Ticket {} is blocked until these are done: {}", self.id, self.waiting_on.join(", "))
+    }
+}
+
+impl std::error::Error for Blocked {}
+
+fn is_open(ticket: &Ticket) -> bool {
+    !matches!(ticket.meta.status, Status::Done | Status::Archived)
+}
+
+/// What each ticket is waiting on, for every ticket that is waiting on something: open children
+/// first, then open dependencies. A dependency on a ticket that doesn't exist doesn't block.
+pub fn blocked_map(tickets: &[Ticket]) -> BTreeMap<String, Vec<String>> {
+    let open: BTreeMap<&str, bool> = tickets.iter().map(|t| (t.meta.id.as_str(), is_open(t))).collect();
+    let mut blocked: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for ticket in tickets.iter().filter(|t| is_open(t)) {
+        if let Some(parent) = &ticket.meta.parent {
+            blocked.entry(parent.clone()).or_default().push(ticket.meta.id.clone());
+        }
+    }
+    for ticket in tickets {
+        let deps = ticket.meta.depends_on.iter().filter(|d| open.get(d.as_str()) == Some(&true));
+        for dep in deps {
+            blocked.entry(ticket.meta.id.clone()).or_default().push(dep.clone());
+        }
+    }
+    blocked
+}
+
+/// What `id` is waiting on (see [`blocked_map`]).
+pub fn waiting_on(tickets: &[Ticket], id: &str) -> Vec<String> {
+    blocked_map(tickets).remove(id).unwrap_or_default()
+}
+
+/// Fails with [`Blocked`] if `id` is waiting on something and `status` would start or close it.
+pub fn check_transition(tickets: &[Ticket], id: &str, status: &Status) -> Result<()> {
+    if !matches!(status, Status::InProgress | Status::Review | Status::Done) {
+        return Ok(());
+    }
+    let waiting_on = waiting_on(tickets, id);
+    if waiting_on.is_empty() {
+        return Ok(());
+    }
+    Err(Blocked { id: id.to_string(), waiting_on }.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ticket(id: &str, status: &str, extra: &str) -> Ticket {
+        let toml = format!(
+            "[meta]\nid = \"{}\"\ntitle = \"t\"\nstatus = \"{}\"\npriority = \"low\"\n{}\n\n[spec]\ndescription = \"\"\n\n[verification]\ncommand = \"true\"\n",
+            id, status, extra,
+        );
+        toml_edit::de::from_str(&toml).unwrap()
+    }
+
+    #[test]
+    fn test_children_and_dependencies_block() {
+        let tickets = [
+            ticket("T-1", "todo", ""),
+            ticket("T-1-1", "done", "parent = \"T-1\""),
+            ticket("T-1-2", "in_progress", "parent = \"T-1\""),
+            ticket("T-2", "todo", "depends_on = [\"T-1\", \"T-9\"]"),
+            ticket("T-3", "todo", "depends_on = [\"T-1-1\"]"),
+        ];
+
+        assert_eq!(waiting_on(&tickets, "T-1"), ["T-1-2"]);
+        assert_eq!(waiting_on(&tickets, "T-2"), ["T-1"]);
+        assert!(waiting_on(&tickets, "T-3").is_empty());
+
+        let err = check_transition(&tickets, "T-2", &Status::InProgress).unwrap_err();
+        assert_eq!(err.downcast_ref::<Blocked>().unwrap().waiting_on, ["T-1"]);
+        assert!(check_transition(&tickets, "T-2", &Status::Archived).is_ok());
+        assert!(check_transition(&tickets, "T-3", &Status::Done).is_ok());
+    }
+}
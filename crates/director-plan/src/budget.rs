@@ -0,0 +1,146 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use crate::config::BudgetConfig;
+use crate::runs::{Role, RunRecord};
+
+/// Set by `director-plan pause`, persisted to `.director/paused.json`. While it exists workers
+/// claim nothing and the server refuses claims.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Pause {
+    pub reason: Option<String>,
+    pub paused_at: DateTime<Utc>,
+}
+
+fn pause_path(workspace_root: &Path) -> PathBuf {
+    crate::state_dir(workspace_root).join("paused.json")
+}
+
+/// The current pause, if executions are paused. An unreadable flag file still counts as paused.
+pub fn paused(workspace_root: &Path) -> Option<Pause> {
+    let path = pause_path(workspace_root);
+    let content = fs::read_to_string(&path).ok()?;
+    Some(serde_json::from_str(&content).unwrap_or(Pause { reason: None, paused_at: Utc::now() }))
+}
+
+pub fn pause(workspace_root: &Path, reason: Option<String>) -> Result<Pause> {
+    let pause = Pause { reason, paused_at: Utc::now() };
+    let path = pause_path(workspace_root);
+    fs::write(&path, serde_json::to_string_pretty(&pause)?)
+        .with_context(|| format!("Failed to write pause flag: {:?}", path))?;
+    Ok(pause)
+}
+
+/// Lifts the pause. Returns whether executions were paused.
+pub fn resume(workspace_root: &Path) -> Result<bool> {
+    let path = pause_path(workspace_root);
+    if !path.exists() {
+        return Ok(false);
+    }
+    fs::remove_file(&path).with_context(|| format!("Failed to remove pause flag: {:?}", path))?;
+    Ok(true)
+}
+
+impl std::fmt::Display for Pause {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Executions paused since {}", self.paused_at.format("%Y-%m-%d %H:%M UTC"))?;
+        if let Some(reason) = &self.reason {
+            write!(f, ": {}", reason)?;
+        }
+        Ok(())
+    }
+}
+
+/// Why the worker shouldn't claim anything right now, if it shouldn't: a pause, or
+/// `budget.max_executions_per_hour` reached by the runs in `runs`.
+pub fn hold_reason(workspace_root: &Path, config: &BudgetConfig, runs: &[RunRecord], now: DateTime<Utc>) -> Option<String> {
+    if let Some(pause) = paused(workspace_root) {
+        return Some(pause.to_string());
+    }
+    let limit = config.max_executions_per_hour?;
+    let recent = runs.iter().filter(|r| r.started_at > now - Duration::hours(1)).count();
+    (recent >= limit as usize).then(|| format!("{} executions in the last hour (budget.max_executions_per_hour = {})", recent, limit))
+}
+
+/// What a ticket has used so far, across all its runs.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TicketUsage {
+    /// Prompts sent to the agent
+    pub agent_invocations: u32,
+    pub wall_clock: Duration,
+}
+
+impl TicketUsage {
+    /// Sums `runs` (one ticket's). A run still going counts up to `now`.
+    pub fn of<'a>(runs: impl IntoIterator<Item = &'a RunRecord>, now: DateTime<Utc>) -> Self {
+        let mut usage = Self::default();
+        for run in runs {
+            usage.agent_invocations += run.transcript.iter().filter(|m| m.role == Role::User).count() as u32;
+            usage.wall_clock += run.finished_at.unwrap_or(now) - run.started_at;
+        }
+        usage
+    }
+
+    /// Why the ticket can't have another agent turn, if `config` says it can't.
+    pub fn exceeded(&self, config: &BudgetConfig) -> Option<String> {
+        if let Some(max) = config.max_agent_invocations_per_ticket
+            && self.agent_invocations >= max
+        {
+            return Some(format!("Budget exhausted: the agent has been invoked {} times for this ticket (budget.max_agent_invocations_per_ticket = {})", self.agent_invocations, max));
+        }
+        if let Some(max) = config.max_wall_clock_mins_per_ticket
+            && self.wall_clock >= Duration::minutes(max as i64)
+        {
+            return Some(format!("Budget exhausted: {} minutes spent on this ticket (budget.max_wall_clock_mins_per_ticket = {})", self.wall_clock.num_minutes(), max));
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(started_mins_ago: i64, minutes: i64, prompts: usize, now: DateTime<Utc>) -> RunRecord {
+        let mut record = RunRecord::new("T-001", "agent");
+        record.started_at = now - Duration::minutes(started_mins_ago);
+        record.finished_at = Some(record.started_at + Duration::minutes(minutes));
+        for _ in 0..prompts {
+            record.push_message(Role::User, "prompt");
+            record.push_message(Role::Agent, "reply");
+        }
+        record
+    }
+
+    #[test]
+    fn test_ticket_usage() {
+        let now = Utc::now();
+        let runs = [run(120, 10, 3, now), run(30, 5, 2, now)];
+        let usage = TicketUsage::of(&runs, now);
+        assert_eq!(usage.agent_invocations, 5);
+        assert_eq!(usage.wall_clock, Duration::minutes(15));
+
+        let config = BudgetConfig { max_agent_invocations_per_ticket: Some(5), ..Default::default() };
+        assert!(usage.exceeded(&config).unwrap().contains("invoked 5 times"));
+        let config = BudgetConfig { max_wall_clock_mins_per_ticket: Some(20), ..Default::default() };
+        assert!(usage.exceeded(&config).is_none());
+    }
+
+    #[test]
+    fn test_hold_reason() {
+        let dir = tempfile::tempdir().unwrap();
+        let now = Utc::now();
+        let runs = [run(120, 10, 1, now), run(30, 5, 1, now), run(10, 5, 1, now)];
+        let config = BudgetConfig { max_executions_per_hour: Some(2), ..Default::default() };
+        assert!(hold_reason(dir.path(), &config, &runs, now).unwrap().contains("2 executions"));
+        assert!(hold_reason(dir.path(), &BudgetConfig::default(), &runs, now).is_none());
+
+        pause(dir.path(), Some("Invoice review".into())).unwrap();
+        assert!(hold_reason(dir.path(), &BudgetConfig::default(), &runs, now).unwrap().ends_with("Invoice review"));
+        assert!(resume(dir.path()).unwrap());
+        assert!(paused(dir.path()).is_none());
+        assert!(!resume(dir.path()).unwrap());
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::BTreeMap;
+use serde::Serialize;
+use crate::runs::RunRecord;
+
+/// Fewer attempts than this above a threshold say too little to suggest it.
+const MIN_SUGGESTION_SAMPLES: usize = 5;
+
+/// Attempts whose stated confidence fell in `[low, high)`; the top bucket includes 1.0.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Bucket {
+    pub low: f32,
+    pub high: f32,
+    pub attempts: usize,
+    pub passed: usize,
+    /// What the agent said, on average; `None` for an empty bucket
+    pub mean_confidence: Option<f32>,
+    /// What actually happened; `None` for an empty bucket
+    pub pass_rate: Option<f32>,
+}
+
+/// How well one agent's `confidence` predicted whether verification passed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AgentCalibration {
+    /// The agent command, as recorded on the run
+    pub agent: String,
+    pub attempts: usize,
+    pub passed: usize,
+    pub buckets: Vec<Bucket>,
+    /// Mean squared difference between confidence and outcome (0 or 1). 0 is perfect;
+    /// always answering 0.5 scores 0.25.
+    pub brier_score: f32,
+    /// Gap between stated confidence and pass rate, averaged over buckets by attempts
+    pub calibration_error: f32,
+    /// The lowest `verification.min_confidence` at which the attempts that clear it passed at
+    /// least the target rate, if there's enough data
+    pub suggested_min_confidence: Option<f32>,
+}
+
+/// Calibration per agent, from every attempt in `runs` that reported a confidence.
+///
+/// Each attempt counts towards whichever agent made it. Attempts without a result block are
+/// left out, since the 1.0 they default to wasn't the agent's prediction. `target` is the pass
+/// rate the suggested threshold should achieve.
+pub fn calibrate(runs: &[RunRecord], buckets: usize, target: f32) -> Vec<AgentCalibration> {
+    let buckets = buckets.max(1);
+    let mut samples: BTreeMap<&str, Vec<(f32, bool)>> = BTreeMap::new();
+    for run in runs {
+        for attempt in &run.attempts {
+            if let Some(confidence) = attempt.confidence {
+                samples.entry(attempt.agent.as_deref().unwrap_or(&run.agent)).or_default().push((confidence.clamp(0.0, 1.0), attempt.passed));
+            }
+        }
+    }
+
+    samples.into_iter()
+        .map(|(agent, samples)| {
+            let width = 1.0 / buckets as f32;
+            let mut grouped = vec![Vec::new(); buckets];
+            for &(confidence, passed) in &samples {
+                let index = ((confidence * buckets as f32) as usize).min(buckets - 1);
+                grouped[index].push((confidence, passed));
+            }
+            let buckets: Vec<Bucket> = grouped.iter().enumerate()
+                .map(|(i, bucket)| {
+                    let passed = bucket.iter().filter(|(_, p)| *p).count();
+                    let n = bucket.len() as f32;
+                    Bucket {
+                        low: i as f32 * width,
+                        high: (i + 1) as f32 * width,
+                        attempts: bucket.len(),
+                        passed,
+                        mean_confidence: (!bucket.is_empty()).then(|| bucket.iter().map(|(c, _)| c).sum::<f32>() / n),
+                        pass_rate: (!bucket.is_empty()).then(|| passed as f32 / n),
+                    }
+                })
+                .collect();
+
+            let n = samples.len() as f32;
+            let outcome = |passed: bool| if passed { 1.0 } else { 0.0 };
+            let brier_score = samples.iter().map(|&(c, p)| (c - outcome(p)).powi(2)).sum::<f32>() / n;
+            let calibration_error = buckets.iter()
+                .filter_map(|b| Some((b.mean_confidence? - b.pass_rate?).abs() * b.attempts as f32))
+                .sum::<f32>() / n;
+
+            AgentCalibration {
+                agent: agent.to_string(),
+                attempts: samples.len(),
+                passed: samples.iter().filter(|(_, p)| *p).count(),
+                buckets,
+                brier_score,
+                calibration_error,
+                suggested_min_confidence: suggest_threshold(&samples, target),
+            }
+        })
+        .collect()
+}
+
+/// The lowest confidence seen such that attempts at or above it passed at least `target` of
+/// the time.
+fn suggest_threshold(samples: &[(f32, bool)], target: f32) -> Option<f32> {
+    let mut thresholds: Vec<f32> = samples.iter().map(|(c, _)| *c).collect();
+    thresholds.sort_by(f32::total_cmp);
+    thresholds.dedup();
+    thresholds.into_iter().find(|&threshold| {
+        let above: Vec<bool> = samples.iter().filter(|(c, _)| *c >= threshold).map(|(_, p)| *p).collect();
+        above.len() >= MIN_SUGGESTION_SAMPLES
+            && above.iter().filter(|p| **p).count() as f32 / above.len() as f32 >= target
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runs::AttemptRecord;
+
+    fn run(agent: &str, attempts: &[(Option<f32>, bool)]) -> RunRecord {
+        let mut record = RunRecord::new("T-001", agent);
+        record.attempts = attempts.iter().enumerate()
+            .map(|(i, &(confidence, passed))| AttemptRecord {
+                number: i as u32 + 1,
+                agent_ms: 0,
+                verification_ms: 0,
+                passed,
+                confidence,
+                errors: Vec::new(),
+                perf: None,
+                coverage: None,
+                autofixed: Vec::new(),
+                scoped_command: None,
+                flake: None,
+                result: None,
+                context: None,
+                snapshot: None,
+                agent: None,
+            })
+            .collect();
+        record
+    }
+
+    #[test]
+    fn test_calibrate() {
+        let runs = [
+            // Overconfident at the low end, reliable above 0.8
+            run("claude", &[(Some(0.55), false), (Some(0.6), false), (Some(0.6), true), (None, true)]),
+            run("claude", &[(Some(0.85), true), (Some(0.9), true), (Some(0.9), true), (Some(0.95), true), (Some(1.0), true)]),
+            run("aider", &[(Some(0.3), true)]),
+        ];
+        let report = calibrate(&runs, 10, 0.9);
+        assert_eq!(report.iter().map(|a| a.agent.as_str()).collect::<Vec<_>>(), ["aider", "claude"]);
+
+        let claude = &report[1];
+        assert_eq!((claude.attempts, claude.passed), (8, 6));
+        let sixties = &claude.buckets[6];
+        assert_eq!((sixties.attempts, sixties.passed), (2, 1));
+        assert_eq!(sixties.pass_rate, Some(0.5));
+        assert_eq!(claude.buckets[9].attempts, 4);
+        assert_eq!(claude.buckets[0].pass_rate, None);
+        assert!(claude.brier_score > 0.0 && claude.brier_score < 0.25);
+        assert_eq!(claude.suggested_min_confidence, Some(0.85));
+
+        // One attempt is too few to suggest anything
+        assert_eq!(report[0].suggested_min_confidence, None);
+    }
+}
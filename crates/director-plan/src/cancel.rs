@@ -0,0 +1,104 @@
+use std::fmt;
+use std::path::Path;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use crate::locks::LockStore;
+use crate::store::TicketStore;
+use crate::types::Status;
+
+/// What `director-plan cancel` or `POST /api/runs/:id/cancel` stopped.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Cancellation {
+    pub ticket_id: String,
+    /// The process running the ticket in this checkout, told to stop through its run lock
+    pub pid: Option<u32>,
+    pub host: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    /// The worker whose lease the server revoked
+    pub worker_id: Option<String>,
+    /// The ticket's status afterwards: `blocked` if it was in progress, otherwise unchanged
+    pub status: Status,
+}
+
+/// Returned by [`cancel`] when nothing is running the ticket.
+#[derive(Debug)]
+pub struct NotRunning(pub String);
+
+impl fmt::Display for NotRunning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Ticket {} isn't running", self.0)
+    }
+}
+
+impl std::error::Error for NotRunning {}
+
+/// Stops whatever is running `ticket_id`. A run in this checkout (`execute` or a local worker)
+/// is asked to stop through its run lock; it kills the agent or verification process group and
+/// reverts its changes. `revoked` names a worker whose lease the server has already taken back,
+/// which stops runs on other machines at their next heartbeat.
+///
+/// The cancellation goes in the ticket's history. An in-progress ticket is blocked with it as
+/// the reason, so workers don't claim it straight back; `unblock` returns it to `todo`.
+/// Fails with [`NotRunning`] when there was nothing to stop.
+pub fn cancel(workspace_root: &Path, ticket_id: &str, reason: Option<&str>, revoked: Option<String>) -> Result<Cancellation> {
+    let store = TicketStore::new(workspace_root);
+    let ticket = store.load(ticket_id)?;
+    let lock = LockStore::new(workspace_root).request_cancel(ticket_id, reason)?;
+
+    let mut message = match (&lock, &revoked) {
+        (Some(lock), _) => format!("Run cancelled (pid {} on {})", lock.pid, lock.host),
+        (None, Some(worker)) => format!("Run cancelled (worker {})", worker),
+        (None, None) => return Err(NotRunning(ticket_id.to_string()).into()),
+    };
+    if let Some(reason) = reason {
+        message.push_str(&format!(": {}", reason));
+    }
+
+    let status = if ticket.meta.status == Status::InProgress {
+        store.block(ticket_id, &message)?;
+        Status::Blocked
+    } else {
+        store.update(ticket_id, |doc| {
+            crate::append_history_entry(doc, &format!("{}.", message));
+            Ok(())
+        })?;
+        ticket.meta.status
+    };
+
+    Ok(Cancellation {
+        ticket_id: ticket_id.to_string(),
+        pid: lock.as_ref().map(|l| l.pid),
+        host: lock.as_ref().map(|l| l.host.clone()),
+        started_at: lock.as_ref().map(|l| l.started_at),
+        worker_id: revoked,
+        status,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_cancel() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = TicketStore::new(dir.path());
+        fs::create_dir_all(store.tickets_dir()).unwrap();
+        fs::write(store.ticket_path("T-1"), "[meta]\nid = \"T-1\"\ntitle = \"t\"\nstatus = \"in_progress\"\npriority = \"low\"\n\n[spec]\ndescription = \"\"\n\n[verification]\ncommand = \"true\"\n").unwrap();
+
+        let err = cancel(dir.path(), "T-1", None, None).unwrap_err();
+        assert!(err.is::<NotRunning>());
+
+        let _lock = LockStore::new(dir.path()).acquire("T-1").unwrap();
+        let cancellation = cancel(dir.path(), "T-1", Some("wrong approach"), None).unwrap();
+        assert_eq!(cancellation.pid, Some(std::process::id()));
+        assert_eq!(cancellation.status, Status::Blocked);
+        let ticket = store.load("T-1").unwrap();
+        assert_eq!(ticket.meta.status, Status::Blocked);
+        assert!(ticket.meta.blocked_reason.unwrap().ends_with("): wrong approach"));
+        assert!(LockStore::new(dir.path()).take_cancel_request("T-1").is_some());
+    }
+}
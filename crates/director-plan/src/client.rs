@@ -0,0 +1,240 @@
+use std::fmt;
+use anyhow::{Result, anyhow};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use crate::cancel::Cancellation;
+use crate::comments::Comment;
+use crate::ticket_format::TicketFormat;
+use crate::types::{FrontendTicket, Status};
+use crate::worker_status::{WorkerHeartbeat, WorkerStatus};
+
+/// A non-2xx answer from the server, with the message from its `{ "error": ... }` body.
+/// Callers that care about a particular status (409 for a lost lease, 412 for a stale ETag)
+/// can `downcast_ref` it out of the `anyhow::Error`.
+#[derive(Debug, Clone)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub message: String,
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.message.is_empty() {
+            write!(f, "Server returned {}", self.status)
+        } else {
+            write!(f, "Server returned {}: {}", self.status, self.message)
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+/// Whether `error` is an [`ApiError`] with `status`.
+pub fn is_status(error: &anyhow::Error, status: StatusCode) -> bool {
+    error.downcast_ref::<ApiError>().is_some_and(|e| e.status == status)
+}
+
+/// Fields `PATCH /api/tickets/:id` changes; `None` leaves a field alone.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TicketUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<Status>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due_date: Option<String>,
+}
+
+/// The answer to `POST /api/tickets/:id/verify`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct VerifyResponse {
+    pub success: bool,
+    pub stdout: String,
+    pub stderr: String,
+    pub artifacts_path: String,
+    /// Snapshot and visual diff results, as the server reported them
+    #[serde(flatten)]
+    pub details: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A ticket file as `GET /api/tickets/:id/raw` serves it.
+#[derive(Debug, Clone)]
+pub struct RawTicket {
+    pub content: String,
+    /// Pass back as `If-Match` to [`Client::put_raw_ticket`]
+    pub etag: String,
+    pub format: TicketFormat,
+}
+
+/// Typed access to the REST API served by `director-plan serve`, for tools (and the worker)
+/// that would otherwise build URLs by hand. Ticket routes go to the root plan, or to a
+/// sub-plan after [`Client::for_workspace`].
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    server_url: String,
+    workspace: Option<String>,
+}
+
+impl Client {
+    /// `server_url` is the server's base, e.g. `http://localhost:3000`.
+    pub fn new(server_url: &str) -> Self {
+        Self::with_http(reqwest::Client::new(), server_url)
+    }
+
+    /// Shares an existing connection pool (and its TLS or proxy settings).
+    pub fn with_http(http: reqwest::Client, server_url: &str) -> Self {
+        Self { http, server_url: server_url.trim_end_matches('/').to_string(), workspace: None }
+    }
+
+    /// The same server, scoped to a sub-plan from the root config's `[[workspaces]]`.
+    pub fn for_workspace(&self, name: &str) -> Self {
+        Self { workspace: Some(name.to_string()), ..self.clone() }
+    }
+
+    pub fn server_url(&self) -> &str {
+        &self.server_url
+    }
+
+    /// Base of the ticket routes: `<server>/api`, or `<server>/api/workspaces/<ws>` for a sub-plan.
+    pub fn api_url(&self) -> String {
+        match &self.workspace {
+            Some(name) => format!("{}/api/workspaces/{}", self.server_url, name),
+            None => format!("{}/api", self.server_url),
+        }
+    }
+
+    fn ticket_url(&self, id: &str, rest: &str) -> String {
+        format!("{}/tickets/{}{}", self.api_url(), id, rest)
+    }
+
+    /// Ticket summaries, by ID.
+    pub async fn list_tickets(&self) -> Result<Vec<FrontendTicket>> {
+        self.json(self.http.get(format!("{}/tickets", self.api_url()))).await
+    }
+
+    pub async fn get_ticket(&self, id: &str) -> Result<FrontendTicket> {
+        self.json(self.http.get(self.ticket_url(id, ""))).await
+    }
+
+    pub async fn update_ticket(&self, id: &str, update: &TicketUpdate) -> Result<FrontendTicket> {
+        self.json(self.http.patch(self.ticket_url(id, "")).json(update)).await
+    }
+
+    pub async fn set_status(&self, id: &str, status: Status) -> Result<FrontendTicket> {
+        self.update_ticket(id, &TicketUpdate { status: Some(status), ..Default::default() }).await
+    }
+
+    pub async fn block(&self, id: &str, reason: &str) -> Result<FrontendTicket> {
+        self.json(self.http.post(self.ticket_url(id, "/block")).json(&json!({ "reason": reason }))).await
+    }
+
+    pub async fn unblock(&self, id: &str, reason: Option<&str>) -> Result<FrontendTicket> {
+        self.json(self.http.post(self.ticket_url(id, "/unblock")).json(&json!({ "reason": reason }))).await
+    }
+
+    /// Runs the ticket's verification command on the server.
+    pub async fn verify(&self, id: &str) -> Result<VerifyResponse> {
+        self.json(self.http.post(self.ticket_url(id, "/verify"))).await
+    }
+
+    /// Ticket counts, time in status and execution totals, as `GET /api/stats` reports them.
+    pub async fn stats(&self) -> Result<serde_json::Value> {
+        self.json(self.http.get(format!("{}/stats", self.api_url()))).await
+    }
+
+    pub async fn comments(&self, id: &str) -> Result<Vec<Comment>> {
+        self.json(self.http.get(self.ticket_url(id, "/comments"))).await
+    }
+
+    pub async fn add_comment(&self, id: &str, author: &str, body: &str, reply_to: Option<u32>) -> Result<Comment> {
+        let payload = json!({ "author": author, "body": body, "reply_to": reply_to });
+        self.json(self.http.post(self.ticket_url(id, "/comments")).json(&payload)).await
+    }
+
+    /// Claims a `todo` ticket for `worker_id`. Fails with a 409 [`ApiError`] when someone
+    /// else holds it, and 503 while executions are paused.
+    pub async fn claim(&self, id: &str, worker_id: &str, ttl_secs: i64) -> Result<()> {
+        self.lease(id, "claim", worker_id, ttl_secs).await.map(drop)
+    }
+
+    /// Renews a lease. A 409 [`ApiError`] means the lease was lost.
+    pub async fn heartbeat(&self, id: &str, worker_id: &str, ttl_secs: i64) -> Result<()> {
+        self.lease(id, "heartbeat", worker_id, ttl_secs).await.map(drop)
+    }
+
+    /// Gives a lease up. Returns whether `worker_id` still held it.
+    pub async fn release(&self, id: &str, worker_id: &str) -> Result<bool> {
+        let reply = self.lease(id, "release", worker_id, 0).await?;
+        Ok(reply["released"].as_bool().unwrap_or(false))
+    }
+
+    async fn lease(&self, id: &str, action: &str, worker_id: &str, ttl_secs: i64) -> Result<serde_json::Value> {
+        let payload = json!({ "worker_id": worker_id, "ttl_secs": ttl_secs });
+        self.json(self.http.post(self.ticket_url(id, &format!("/{}", action))).json(&payload)).await
+    }
+
+    /// Stops the run of `id`, a run ID or the ID of the ticket being run. Fails with a 409
+    /// [`ApiError`] when nothing is running it.
+    pub async fn cancel_run(&self, id: &str, reason: Option<&str>) -> Result<Cancellation> {
+        self.json(self.http.post(format!("{}/runs/{}/cancel", self.api_url(), id)).json(&json!({ "reason": reason }))).await
+    }
+
+    /// The ticket file as stored, with the ETag to write it back under.
+    pub async fn raw_ticket(&self, id: &str) -> Result<RawTicket> {
+        let resp = self.send(self.http.get(self.ticket_url(id, "/raw"))).await?;
+        let etag = resp.headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|v| v.to_str().ok())
+            .map(String::from)
+            .ok_or_else(|| anyhow!("Server did not return an ETag for {}", id))?;
+        let format = resp.headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map_or(TicketFormat::Toml, TicketFormat::from_content_type);
+        let content = resp.text().await?;
+        Ok(RawTicket { content, etag, format })
+    }
+
+    /// Replaces the ticket file. With `if_match`, the write fails with a 412 [`ApiError`] if the
+    /// ticket changed since that ETag was read. Returns the new ETag.
+    pub async fn put_raw_ticket(&self, id: &str, content: String, format: TicketFormat, if_match: Option<&str>) -> Result<String> {
+        let mut request = self.http.put(self.ticket_url(id, "/raw"))
+            .header(reqwest::header::CONTENT_TYPE, format.content_type())
+            .body(content);
+        if let Some(etag) = if_match {
+            request = request.header(reqwest::header::IF_MATCH, etag);
+        }
+        let reply: serde_json::Value = self.json(request).await?;
+        Ok(reply["etag"].as_str().unwrap_or_default().to_string())
+    }
+
+    /// Workers that reported in the last hour. Not scoped to a workspace.
+    pub async fn workers(&self) -> Result<Vec<WorkerStatus>> {
+        self.json(self.http.get(format!("{}/api/workers", self.server_url))).await
+    }
+
+    pub async fn worker_heartbeat(&self, heartbeat: &WorkerHeartbeat) -> Result<()> {
+        self.send(self.http.post(format!("{}/api/workers/heartbeat", self.server_url)).json(heartbeat)).await.map(drop)
+    }
+
+    /// Sends `request`, turning a non-2xx answer into an [`ApiError`].
+    async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        let resp = request.send().await?;
+        let status = resp.status();
+        if status.is_success() {
+            return Ok(resp);
+        }
+        let text = resp.text().await.unwrap_or_default();
+        let message = serde_json::from_str::<serde_json::Value>(&text).ok()
+            .and_then(|body| body["error"].as_str().map(String::from))
+            .unwrap_or(text);
+        Err(ApiError { status, message }.into())
+    }
+
+    async fn json<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T> {
+        Ok(self.send(request).await?.json().await?)
+    }
+}
@@ -0,0 +1,178 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use toml_edit::DocumentMut;
+use utoipa::ToSchema;
+use crate::events::{self, PlanEvent};
+
+/// One message in a ticket's discussion. Unlike the history log, comments are only ever
+/// written by people (or agents speaking for them), never by director-plan itself.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct Comment {
+    /// Numbered from 1 within the ticket
+    pub id: u32,
+    pub author: String,
+    pub at: DateTime<Utc>,
+    pub body: String,
+    /// The comment this one answers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<u32>,
+}
+
+#[derive(Deserialize, Default)]
+struct CommentFile {
+    #[serde(default)]
+    comments: Vec<Comment>,
+}
+
+/// Comments live in `plan/comments/<ticket-id>.toml` as `[[comments]]` tables, next to the
+/// ticket rather than inside it so a long thread doesn't bury the spec.
+pub struct CommentStore {
+    workspace_root: PathBuf,
+    dir: PathBuf,
+}
+
+impl CommentStore {
+    pub fn new(workspace_root: &Path) -> Self {
+        Self { workspace_root: workspace_root.to_path_buf(), dir: workspace_root.join("plan/comments") }
+    }
+
+    fn path(&self, ticket_id: &str) -> PathBuf {
+        self.dir.join(format!("{}.toml", ticket_id))
+    }
+
+    /// The ticket's comments in the order they were written.
+    pub fn list(&self, ticket_id: &str) -> Result<Vec<Comment>> {
+        let path = self.path(ticket_id);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = fs::read_to_string(&path).with_context(|| format!("Failed to read comments: {:?}", path))?;
+        let file: CommentFile = toml_edit::de::from_str(&content).with_context(|| format!("Failed to parse comments: {:?}", path))?;
+        Ok(file.comments)
+    }
+
+    /// Appends a comment, keeping whatever formatting the file already has.
+    pub fn add(&self, ticket_id: &str, author: &str, body: &str, reply_to: Option<u32>) -> Result<Comment> {
+        let body = body.trim();
+        if body.is_empty() {
+            bail!("A comment needs a body");
+        }
+        let existing = self.list(ticket_id)?;
+        if let Some(parent) = reply_to
+            && !existing.iter().any(|c| c.id == parent)
+        {
+            bail!("Ticket {} has no comment {} to reply to", ticket_id, parent);
+        }
+        let comment = Comment {
+            id: existing.iter().map(|c| c.id).max().unwrap_or(0) + 1,
+            author: author.trim().to_string(),
+            at: Utc::now(),
+            body: body.to_string(),
+            reply_to,
+        };
+
+        let path = self.path(ticket_id);
+        let mut doc = match fs::read_to_string(&path) {
+            Ok(content) => content.parse::<DocumentMut>().with_context(|| format!("Failed to parse comments: {:?}", path))?,
+            Err(_) => DocumentMut::new(),
+        };
+        let mut table = toml_edit::Table::new();
+        table["id"] = toml_edit::value(comment.id as i64);
+        table["author"] = toml_edit::value(comment.author.as_str());
+        table["at"] = toml_edit::value(comment.at.to_rfc3339());
+        table["body"] = toml_edit::value(comment.body.as_str());
+        if let Some(parent) = reply_to {
+            table["reply_to"] = toml_edit::value(parent as i64);
+        }
+        match doc.get_mut("comments").and_then(|c| c.as_array_of_tables_mut()) {
+            Some(comments) => comments.push(table),
+            None => {
+                let mut comments = toml_edit::ArrayOfTables::new();
+                comments.push(table);
+                doc["comments"] = toml_edit::Item::ArrayOfTables(comments);
+            }
+        }
+
+        fs::create_dir_all(&self.dir)?;
+        fs::write(&path, doc.to_string()).with_context(|| format!("Failed to write comments: {:?}", path))?;
+        events::emit(&self.workspace_root, PlanEvent::CommentAdded {
+            id: ticket_id.to_string(),
+            comment_id: comment.id,
+            author: comment.author.clone(),
+        });
+        Ok(comment)
+    }
+
+    /// Drops the ticket's whole discussion, for a deleted ticket.
+    pub fn remove(&self, ticket_id: &str) -> Result<()> {
+        let path = self.path(ticket_id);
+        if path.exists() {
+            fs::remove_file(&path).with_context(|| format!("Failed to delete comments: {:?}", path))?;
+        }
+        Ok(())
+    }
+}
+
+/// The discussion as a prompt section, replies nested under what they answer. Empty when
+/// there are no comments.
+pub fn render_discussion(comments: &[Comment]) -> String {
+    if comments.is_empty() {
+        return String::new();
+    }
+    let mut out = String::from("# Discussion\n");
+    // Replies to a comment that has since gone missing are shown at the top level
+    let roots = comments.iter().filter(|c| c.reply_to.is_none_or(|p| !comments.iter().any(|o| o.id == p)));
+    for comment in roots {
+        render_thread(&mut out, comments, comment, 0);
+    }
+    out
+}
+
+fn render_thread(out: &mut String, comments: &[Comment], comment: &Comment, depth: usize) {
+    let indent = "  ".repeat(depth);
+    let mut lines = comment.body.lines();
+    out.push_str(&format!(
+        "{}- **{}** ({}): {}\n",
+        indent, comment.author, comment.at.format("%Y-%m-%d %H:%M"), lines.next().unwrap_or_default(),
+    ));
+    for line in lines {
+        out.push_str(&format!("{}  {}\n", indent, line));
+    }
+    for reply in comments.iter().filter(|c| c.reply_to == Some(comment.id)) {
+        render_thread(out, comments, reply, depth + 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_threaded_discussion() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = CommentStore::new(dir.path());
+        assert!(store.list("T-001").unwrap().is_empty());
+        assert_eq!(render_discussion(&[]), "");
+
+        store.add("T-001", "dana", "Should shadows blur?", None).unwrap();
+        store.add("T-001", "lee", "Yes, 4px.\nMatch the Figma file.", Some(1)).unwrap();
+        store.add("T-001", "dana", "Also cover RTL text.", None).unwrap();
+        assert!(store.add("T-001", "lee", "?", Some(9)).is_err());
+        assert!(store.add("T-001", "lee", "  ", None).is_err());
+
+        let comments = store.list("T-001").unwrap();
+        assert_eq!(comments.iter().map(|c| c.id).collect::<Vec<_>>(), [1, 2, 3]);
+        assert_eq!(comments[1].reply_to, Some(1));
+
+        let discussion = render_discussion(&comments);
+        let lines: Vec<&str> = discussion.lines().collect();
+        assert_eq!(lines[0], "# Discussion");
+        assert!(lines[1].starts_with("- **dana**") && lines[1].ends_with("Should shadows blur?"));
+        assert!(lines[2].starts_with("  - **lee**") && lines[2].ends_with("Yes, 4px."));
+        assert_eq!(lines[3], "    Match the Figma file.");
+        assert!(lines[4].ends_with("Also cover RTL text."));
+    }
+}
@@ -0,0 +1,122 @@
+use std::path::Path;
+use std::process::Command;
+use anyhow::{Context, Result, anyhow};
+use crate::config::PlanConfig;
+use crate::types::{Ticket, TicketType};
+
+/// Conventional-commit type for a ticket. Tickets without a type count as features.
+pub fn commit_type(ticket: &Ticket) -> &'static str {
+    match ticket.meta.ticket_type {
+        Some(TicketType::Bug) => "fix",
+        Some(TicketType::Chore) | Some(TicketType::Spike) => "chore",
+        Some(TicketType::Feature) | None => "feat",
+    }
+}
+
+/// Renders `[commit] template` for a ticket.
+pub fn render_message(template: &str, ticket: &Ticket) -> String {
+    template
+        .replace("{type}", commit_type(ticket))
+        .replace("{id}", &ticket.meta.id)
+        .replace("{title}", ticket.meta.title.trim())
+}
+
+/// Collapses everything since `base_commit` (including checkpoint commits made during the run)
+/// into one commit with `message`, and returns its SHA. `None` if the run changed nothing.
+/// `plan/` is left out: ticket state is written by director-plan, not the agent.
+pub fn commit_run(workspace_root: &Path, base_commit: &str, message: &str) -> Result<Option<String>> {
+    git(workspace_root, &["reset", "-q", "--soft", base_commit])?;
+    git(workspace_root, &["reset", "-q", "--", "plan"])?;
+    git(workspace_root, &["add", "-A", "--", ".", ":(exclude)plan"])?;
+
+    let unchanged = Command::new("git")
+        .current_dir(workspace_root)
+        .args(["diff", "--cached", "--quiet"])
+        .status()
+        .context("Failed to inspect staged changes")?;
+    if unchanged.success() {
+        return Ok(None);
+    }
+
+    git(workspace_root, &["commit", "-q", "-m", message])?;
+    let output = Command::new("git")
+        .current_dir(workspace_root)
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .context("Failed to run git rev-parse")?;
+    Ok(Some(String::from_utf8_lossy(&output.stdout).trim().to_string()))
+}
+
+/// The branch work starts from and PRs target: `[git] base_branch`, else the remote's default
+/// branch (`origin/HEAD`), else `main` or `master`, whichever exists locally.
+pub fn base_branch(workspace_root: &Path, config: &PlanConfig) -> String {
+    if let Some(branch) = &config.git.base_branch {
+        return branch.clone();
+    }
+
+    let remote_head = Command::new("git")
+        .current_dir(workspace_root)
+        .args(["symbolic-ref", "--quiet", "refs/remotes/origin/HEAD"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| parse_remote_head(&String::from_utf8_lossy(&o.stdout)));
+    if let Some(branch) = remote_head {
+        return branch;
+    }
+
+    let has_master = Command::new("git")
+        .current_dir(workspace_root)
+        .args(["show-ref", "--verify", "--quiet", "refs/heads/master"])
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false);
+    let has_main = Command::new("git")
+        .current_dir(workspace_root)
+        .args(["show-ref", "--verify", "--quiet", "refs/heads/main"])
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false);
+    if has_master && !has_main { "master".to_string() } else { "main".to_string() }
+}
+
+/// `refs/remotes/origin/release/2.x` -> `release/2.x`
+fn parse_remote_head(symbolic_ref: &str) -> Option<String> {
+    symbolic_ref.trim()
+        .strip_prefix("refs/remotes/origin/")
+        .filter(|b| !b.is_empty())
+        .map(str::to_string)
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<()> {
+    let status = Command::new("git")
+        .current_dir(dir)
+        .args(args)
+        .status()
+        .with_context(|| format!("Failed to run git {}", args.join(" ")))?;
+    if !status.success() {
+        return Err(anyhow!("git {} failed", args.join(" ")));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_message() {
+        let ticket: Ticket = toml_edit::de::from_str(
+            "[meta]\nid = \"T-042\"\ntitle = \"Add dark mode \"\nstatus = \"todo\"\npriority = \"low\"\ntype = \"bug\"\ncreated_at = 2024-01-01T00:00:00Z\n\
+             [spec]\ndescription = \"d\"\n[verification]\ncommand = \"true\"\n",
+        ).unwrap();
+        assert_eq!(render_message("{type}({id}): {title}", &ticket), "fix(T-042): Add dark mode");
+    }
+
+    #[test]
+    fn test_parse_remote_head() {
+        assert_eq!(parse_remote_head("refs/remotes/origin/master\n").as_deref(), Some("master"));
+        assert_eq!(parse_remote_head("refs/remotes/origin/release/2.x").as_deref(), Some("release/2.x"));
+        assert_eq!(parse_remote_head(""), None);
+    }
+}
@@ -0,0 +1,840 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+use crate::error::DirectorError;
+
+/// Workspace-level settings read from `plan/config.toml`.
+///
+/// Every section is optional; a missing file yields the defaults.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct PlanConfig {
+    pub sla: SlaConfig,
+    pub worker: WorkerConfig,
+    pub server: ServerConfig,
+    pub policy: PolicyConfig,
+    pub container: ContainerConfig,
+    pub secrets: SecretsConfig,
+    pub retention: RetentionConfig,
+    pub visual: VisualConfig,
+    pub coverage: CoverageConfig,
+    pub lint: LintConfig,
+    pub commit: CommitConfig,
+    pub git: GitConfig,
+    pub protection: ProtectionConfig,
+    pub context: ContextConfig,
+    pub assets: AssetsConfig,
+    pub follow_ups: FollowUpConfig,
+    pub budget: BudgetConfig,
+    pub execution: ExecutionConfig,
+    pub verification: VerificationConfig,
+    /// Sub-plans in a monorepo (`[[workspaces]]`), each a directory with its own `plan/`.
+    pub workspaces: Vec<SubPlan>,
+    /// Endpoints `serve` POSTs plan events to (`[[webhooks]]`).
+    pub webhooks: Vec<WebhookConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct SlaConfig {
+    /// Open tickets due within this many days are flagged as "due soon".
+    pub due_soon_days: i64,
+    /// When set, the worker raises the priority of tickets due within this many days.
+    pub escalate_within_days: Option<i64>,
+}
+
+impl Default for SlaConfig {
+    fn default() -> Self {
+        Self {
+            due_soon_days: 3,
+            escalate_within_days: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct WorkerConfig {
+    /// How long an in-flight execution may keep running after SIGINT/SIGTERM before it is killed.
+    pub shutdown_grace_secs: u64,
+    /// Length of the lease a worker takes when claiming a ticket. Heartbeats renew it
+    /// every third of this; if they stop, the server returns the ticket to `todo`.
+    pub lease_ttl_secs: i64,
+    /// How many times a rejected push is rebased onto the base branch, re-verified and retried.
+    pub push_retries: u32,
+    /// How often workers report to `POST /api/workers/heartbeat`. The server counts a worker as
+    /// gone after three missed reports.
+    pub heartbeat_secs: u64,
+}
+
+impl Default for WorkerConfig {
+    fn default() -> Self {
+        Self {
+            shutdown_grace_secs: 30,
+            lease_ttl_secs: 60,
+            push_retries: 3,
+            heartbeat_secs: 15,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ServerConfig {
+    /// How long in-flight requests (e.g. verification runs) may take to finish on shutdown.
+    pub shutdown_grace_secs: u64,
+    /// Body limit for every route except asset uploads, which follow `[assets]`.
+    pub max_body_bytes: usize,
+    pub rate_limit: RateLimitConfig,
+    /// Reverse proxies whose `X-Forwarded-For` is believed when working out a client's address.
+    pub trusted_proxies: Vec<IpAddr>,
+    /// Refuse raw ticket writes with fields the schema doesn't have, like a misspelled
+    /// `prioritiy`, instead of silently ignoring them.
+    pub strict_tickets: bool,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            shutdown_grace_secs: 30,
+            max_body_bytes: 1024 * 1024,
+            rate_limit: RateLimitConfig::default(),
+            trusted_proxies: Vec::new(),
+            strict_tickets: false,
+        }
+    }
+}
+
+/// Requests per minute each client IP may make, by kind of route. 0 means unlimited.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    /// GET requests
+    pub read_per_minute: u32,
+    /// Other mutating requests: ticket updates, claims, uploads
+    pub write_per_minute: u32,
+    /// `POST /api/tickets/:id/verify`, which runs the verification command
+    pub verify_per_minute: u32,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self { enabled: true, read_per_minute: 600, write_per_minute: 120, verify_per_minute: 6 }
+    }
+}
+
+/// Limits on the verification commands tickets can ask the server, CLI and worker to run.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct PolicyConfig {
+    /// Command prefixes tickets may use, e.g. `["cargo test", "npm test"]`. Empty allows anything.
+    pub allowed_commands: Vec<String>,
+    /// Wrapper argv to run commands in, e.g. `["bwrap", "--bind", "{workspace}", "{workspace}", "--", "sh", "-c", "{command}"]`.
+    pub sandbox: Vec<String>,
+}
+
+/// Runs the agent and verification inside a container instead of on the host.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ContainerConfig {
+    pub enabled: bool,
+    /// `docker` or `podman` (anything with a compatible `run`/`build` CLI).
+    pub runtime: String,
+    /// Image to run, e.g. `rust:1.82`. Ignored when `dockerfile` is set.
+    pub image: Option<String>,
+    /// Dockerfile to build the image from, relative to the workspace, e.g. `plan/Dockerfile`.
+    pub dockerfile: Option<String>,
+    /// Where the checkout is mounted inside the container.
+    pub workdir: String,
+    /// `--user` for the container. Defaults to the invoking user's uid:gid on unix.
+    pub user: Option<String>,
+    /// Extra `run` arguments, e.g. `["--network=none", "--memory=4g"]`.
+    pub extra_args: Vec<String>,
+}
+
+impl Default for ContainerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            runtime: "docker".to_string(),
+            image: None,
+            dockerfile: None,
+            workdir: "/workspace".to_string(),
+            user: None,
+            extra_args: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct SecretsConfig {
+    /// Flat `NAME = "value"` TOML file consulted for `secret:NAME` references not found in the
+    /// environment. Defaults to `.director/secrets.toml`, which is never committed.
+    pub file: Option<String>,
+}
+
+/// Limits for generated output: artifacts, `proof/` and run records. Applied by `director-plan gc`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct RetentionConfig {
+    /// Remove anything older than this many days.
+    pub max_age_days: Option<u64>,
+    /// Keep at most this many run records (and artifact files) per ticket.
+    pub keep_per_ticket: Option<usize>,
+    /// When set, `serve` also collects garbage on this interval.
+    pub gc_interval_hours: Option<u64>,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            max_age_days: Some(30),
+            keep_per_ticket: Some(20),
+            gc_interval_hours: None,
+        }
+    }
+}
+
+/// How screenshots for visual verification are taken.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct VisualConfig {
+    /// `cdp` drives a headless Chrome/Chromium directly; `playwright` runs
+    /// `npx playwright test scripts/snapshot.spec.ts` in `playwright_dir`.
+    pub backend: String,
+    /// Browser binary for the `cdp` backend. Searched on `PATH` when unset.
+    pub chrome: Option<String>,
+    /// Extra browser flags, e.g. `["--no-sandbox"]` when running as root in CI.
+    pub chrome_args: Vec<String>,
+    /// Directory holding the Playwright project, relative to the workspace.
+    pub playwright_dir: String,
+    /// Give up on a page (navigation, `wait_for`, screenshot) after this long.
+    pub timeout_secs: u64,
+}
+
+impl Default for VisualConfig {
+    fn default() -> Self {
+        Self {
+            backend: "cdp".to_string(),
+            chrome: None,
+            chrome_args: Vec::new(),
+            playwright_dir: "apps/director-plan".to_string(),
+            timeout_secs: 30,
+        }
+    }
+}
+
+/// How line coverage is measured for tickets with `verification.min_coverage_delta`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct CoverageConfig {
+    /// e.g. `cargo llvm-cov --json --summary-only --output-path target/coverage.json`
+    pub command: Option<String>,
+    /// Report the command writes, relative to the workspace: lcov, llvm-cov JSON,
+    /// Istanbul `coverage-summary.json` or Cobertura XML.
+    pub report: Option<String>,
+}
+
+/// Formatters and linters run before each verification (`[[lint.steps]]`).
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct LintConfig {
+    pub mode: LintMode,
+    pub steps: Vec<LintStep>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LintMode {
+    /// A failing check fails the attempt.
+    #[default]
+    Check,
+    /// A failing check runs the step's `fix` command and is checked again; fixes are committed.
+    Fix,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LintStep {
+    pub name: String,
+    /// e.g. `cargo fmt --check`
+    pub check: String,
+    /// e.g. `cargo fmt`. Steps without one can only fail.
+    pub fix: Option<String>,
+}
+
+/// The commit made for a successful run.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct CommitConfig {
+    /// Subject line. `{type}` (feat/fix/chore from the ticket type), `{id}` and `{title}` are substituted.
+    pub template: String,
+}
+
+impl Default for CommitConfig {
+    fn default() -> Self {
+        Self { template: "{type}({id}): {title}".to_string() }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct GitConfig {
+    /// Branch tickets are branched from and PRs target. Detected from `origin/HEAD` when unset.
+    pub base_branch: Option<String>,
+}
+
+/// Workspace-wide safety rail. Unlike `spec.allowed_paths`, tickets can't loosen it.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ProtectionConfig {
+    /// Globs the agent may never change. Touching one fails the attempt and the files are reverted.
+    pub never_touch: Vec<String>,
+}
+
+/// What goes into agent prompts besides the ticket and its files.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ContextConfig {
+    /// Prepend a workspace overview (layout, languages, entry points, frameworks).
+    pub overview: bool,
+    /// How many directory levels the overview's layout shows.
+    pub overview_depth: usize,
+    /// Include sections of `docs/` and README files that match the ticket.
+    pub docs: bool,
+    /// Include the parts of OpenAPI, GraphQL and protobuf schemas the ticket mentions.
+    pub schemas: bool,
+    /// Include the last this-many commits (with small diffs) touching each context file. 0 turns it off.
+    pub git_history_depth: usize,
+    /// How image assets (design mockups) reach the shell agent. Custom agents declare their own.
+    pub images: ImageMode,
+    /// Errors from earlier attempts are cut to this many characters, keeping the start and
+    /// the end. 0 leaves them whole.
+    pub max_error_chars: usize,
+    /// How errors are condensed before they go into the next prompt.
+    pub summarize_errors: ErrorSummary,
+    /// Named context profiles (`[context.profiles.frontend]`), chosen by `spec.context_profile`.
+    pub profiles: BTreeMap<String, ContextProfile>,
+    /// The profile for tickets of each type that don't name one, e.g. `bug = "backend"`.
+    pub type_profiles: BTreeMap<String, String>,
+    /// Workspace-relative directories C/C++ `#include`s are resolved against, like `-I`.
+    pub include_paths: Vec<String>,
+}
+
+impl Default for ContextConfig {
+    fn default() -> Self {
+        Self {
+            overview: true,
+            overview_depth: 2,
+            docs: true,
+            schemas: true,
+            git_history_depth: 0,
+            images: ImageMode::Paths,
+            max_error_chars: 4000,
+            summarize_errors: ErrorSummary::Heuristic,
+            profiles: BTreeMap::new(),
+            type_profiles: BTreeMap::new(),
+            include_paths: Vec::new(),
+        }
+    }
+}
+
+impl ContextConfig {
+    /// The ticket's profile and its name: `spec.context_profile`, or else the one for its type.
+    /// Fails if the ticket names a profile that isn't defined.
+    pub fn profile(&self, ticket: &crate::types::Ticket) -> Result<Option<(&str, &ContextProfile)>> {
+        let name = match &ticket.spec.context_profile {
+            Some(name) => name.as_str(),
+            None => match ticket.meta.ticket_type.as_ref().and_then(|t| self.type_profiles.get(t.as_str())) {
+                Some(name) => name.as_str(),
+                None => return Ok(None),
+            },
+        };
+        match self.profiles.get_key_value(name) {
+            Some((name, profile)) => Ok(Some((name.as_str(), profile))),
+            None => Err(anyhow!("Unknown context profile '{}': define it under [context.profiles.{}] in plan/config.toml", name, name)),
+        }
+    }
+
+    /// These settings with `profile`'s overrides applied.
+    pub fn with_profile(&self, profile: &ContextProfile) -> ContextConfig {
+        let mut config = self.clone();
+        config.overview = profile.overview.unwrap_or(config.overview);
+        config.docs = profile.docs.unwrap_or(config.docs);
+        config.schemas = profile.schemas.unwrap_or(config.schemas);
+        config
+    }
+}
+
+/// One of `[context.profiles]`: what discovery may bring into a kind of ticket's context.
+/// Files the ticket lists in `relevant_files` are always included.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ContextProfile {
+    /// Discovered files must match one of these globs. Empty allows any.
+    pub include: Vec<String>,
+    /// Discovered files matching one of these globs are left out.
+    pub exclude: Vec<String>,
+    /// Import hops `auto_context` follows from the seed files; files two or more hops out are
+    /// pruned to signatures. 2 when unset.
+    pub depth: Option<usize>,
+    /// `false` turns discovery off: only the ticket's own `relevant_files` are included.
+    pub discover: bool,
+    /// Override `[context]` settings of the same name.
+    pub overview: Option<bool>,
+    pub docs: Option<bool>,
+    pub schemas: Option<bool>,
+}
+
+impl Default for ContextProfile {
+    fn default() -> Self {
+        Self { include: Vec::new(), exclude: Vec::new(), depth: None, discover: true, overview: None, docs: None, schemas: None }
+    }
+}
+
+/// Profile globs must compile, and `type_profiles` must name known types and profiles.
+pub fn check_context_profiles(config: &ContextConfig) -> std::result::Result<(), String> {
+    for (name, profile) in &config.profiles {
+        for glob in profile.include.iter().chain(&profile.exclude) {
+            glob::Pattern::new(glob).map_err(|e| format!("profiles.{}: invalid glob {}: {}", name, glob, e))?;
+        }
+    }
+    for (ticket_type, profile) in &config.type_profiles {
+        if !["feature", "bug", "chore", "spike"].contains(&ticket_type.as_str()) {
+            return Err(format!("type_profiles: unknown ticket type {}", ticket_type));
+        }
+        if !config.profiles.contains_key(profile) {
+            return Err(format!("type_profiles.{}: unknown profile {}", ticket_type, profile));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorSummary {
+    /// Only cut to `max_error_chars`.
+    None,
+    /// The `file:line: message` errors extracted from known tool output, or the end of the log.
+    #[default]
+    Heuristic,
+    /// Errors longer than `max_error_chars` are summarized by a separate, one-off turn of the
+    /// agent command. Shorter ones, and any it fails to summarize, get the heuristic.
+    Agent,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageMode {
+    /// Left out of the prompt.
+    None,
+    /// Listed by path, for agents that can open files themselves.
+    #[default]
+    Paths,
+    /// Inlined as base64 data URIs, for multimodal agents that read images from the prompt.
+    Base64,
+}
+
+/// Tickets created from the `follow_up_tickets` an agent reports after a successful run.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct FollowUpConfig {
+    pub enabled: bool,
+    /// At most this many are created per run; the rest are only noted in the ticket's history.
+    pub max_per_run: usize,
+}
+
+impl Default for FollowUpConfig {
+    fn default() -> Self {
+        Self { enabled: true, max_per_run: 3 }
+    }
+}
+
+/// Limits on agent spend. Unset limits don't apply.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct BudgetConfig {
+    /// The worker claims nothing once this many runs started in the last hour.
+    pub max_executions_per_hour: Option<u32>,
+    /// Prompts sent to the agent for one ticket, across all its runs.
+    pub max_agent_invocations_per_ticket: Option<u32>,
+    /// Wall-clock minutes spent running one ticket, across all its runs.
+    pub max_wall_clock_mins_per_ticket: Option<u64>,
+}
+
+/// How tickets are executed, unless they say otherwise.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ExecutionConfig {
+    /// Agent commands tried in turn: once one has used up the ticket's `max_retries`, the run
+    /// goes back to its base commit and starts over with the next.
+    pub agents: Vec<String>,
+    /// `[[execution.routes]]`, tried in order for tickets run without a named agent. The
+    /// agents of the first one a ticket matches replace `agents`.
+    pub routes: Vec<AgentRoute>,
+}
+
+impl ExecutionConfig {
+    /// The first route `ticket` matches, and its index. `context_files` counts the ticket's
+    /// context files; it's only called if a route asks.
+    pub fn route(&self, ticket: &crate::types::Ticket, context_files: impl FnOnce() -> usize) -> Option<(usize, &AgentRoute)> {
+        let mut context_files = Some(context_files);
+        let mut count = None;
+        self.routes.iter().enumerate().find(|(_, route)| {
+            route.matches(ticket, || *count.get_or_insert_with(|| context_files.take().map_or(0, |f| f())))
+        })
+    }
+}
+
+/// One of `[[execution.routes]]`: the agents for tickets that meet every condition it sets,
+/// so cheap models get the chores and strong ones the hard tickets.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct AgentRoute {
+    /// Ticket types, e.g. `["chore", "spike"]`
+    #[serde(rename = "type")]
+    pub types: Vec<crate::types::TicketType>,
+    pub priority: Vec<crate::types::Priority>,
+    /// Bounds on the number of files the ticket's context would have
+    pub min_context_files: Option<usize>,
+    pub max_context_files: Option<usize>,
+    /// Tried in turn, like `[execution] agents`
+    pub agents: Vec<String>,
+}
+
+impl AgentRoute {
+    fn matches(&self, ticket: &crate::types::Ticket, mut context_files: impl FnMut() -> usize) -> bool {
+        if !self.types.is_empty() && !ticket.meta.ticket_type.as_ref().is_some_and(|t| self.types.contains(t)) {
+            return false;
+        }
+        if !self.priority.is_empty() && !self.priority.contains(&ticket.meta.priority) {
+            return false;
+        }
+        if self.min_context_files.is_none() && self.max_context_files.is_none() {
+            return true;
+        }
+        let count = context_files();
+        self.min_context_files.is_none_or(|min| count >= min) && self.max_context_files.is_none_or(|max| count <= max)
+    }
+}
+
+/// Every route must name an agent.
+pub fn check_routes(config: &ExecutionConfig) -> std::result::Result<(), String> {
+    match config.routes.iter().position(|route| route.agents.is_empty()) {
+        Some(i) => Err(format!("routes[{}]: needs at least one agent", i)),
+        None => Ok(()),
+    }
+}
+
+/// What `POST /api/assets` accepts.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct AssetsConfig {
+    /// MIME types (from the file extension) that may be uploaded. `image/*` allows a whole family.
+    pub allowed_types: Vec<String>,
+    /// Size limits in bytes by MIME type or family. The most specific match wins.
+    pub max_bytes: BTreeMap<String, u64>,
+    /// Limit for allowed types with no entry in `max_bytes`.
+    pub default_max_bytes: u64,
+}
+
+impl Default for AssetsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_types: [
+                "image/png", "image/jpeg", "image/gif", "image/webp",
+                "font/ttf", "font/otf", "font/woff", "font/woff2",
+                "application/json",
+            ].iter().map(|t| t.to_string()).collect(),
+            max_bytes: BTreeMap::from([
+                ("image/*".to_string(), 10 * 1024 * 1024),
+                ("font/*".to_string(), 5 * 1024 * 1024),
+                ("application/json".to_string(), 2 * 1024 * 1024),
+            ]),
+            default_max_bytes: 10 * 1024 * 1024,
+        }
+    }
+}
+
+impl AssetsConfig {
+    pub fn allows(&self, mime: &str) -> bool {
+        self.allowed_types.iter().any(|pattern| mime_matches(pattern, mime))
+    }
+
+    /// The size limit for `mime`: an exact entry, then its family's, then the default.
+    pub fn max_bytes_for(&self, mime: &str) -> u64 {
+        let family = format!("{}/*", mime.split('/').next().unwrap_or_default());
+        self.max_bytes.get(mime)
+            .or_else(|| self.max_bytes.get(&family))
+            .copied()
+            .unwrap_or(self.default_max_bytes)
+    }
+
+    /// The largest upload any allowed type may be, for the request body limit.
+    pub fn largest_limit(&self) -> u64 {
+        self.max_bytes.values().copied().fold(self.default_max_bytes, u64::max)
+    }
+}
+
+fn mime_matches(pattern: &str, mime: &str) -> bool {
+    match pattern.strip_suffix("/*") {
+        Some(family) => mime.split('/').next() == Some(family),
+        None => pattern == mime,
+    }
+}
+
+/// Verification settings: defaults for tickets that don't set `max_retries` or `min_confidence`,
+/// and how steps are run.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct VerificationConfig {
+    pub max_retries: u32,
+    pub min_confidence: f32,
+    /// How many `parallel` verification steps run at once. The number of CPUs when unset.
+    pub max_parallel_steps: Option<usize>,
+    /// Times a failed verification is re-run straight away; passing on a re-run marks the
+    /// attempt flaky instead of sending the failure to the agent. 0 disables re-runs.
+    pub flaky_reruns: u32,
+    /// Regexes matching failures known to be flaky (network resets, timeouts). A failure
+    /// matching one is reported to the agent as probably not its doing.
+    pub flaky_patterns: Vec<String>,
+}
+
+impl Default for VerificationConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            min_confidence: 0.8,
+            max_parallel_steps: None,
+            flaky_reruns: 1,
+            flaky_patterns: Vec::new(),
+        }
+    }
+}
+
+impl VerificationConfig {
+    pub fn step_jobs(&self) -> usize {
+        self.max_parallel_steps
+            .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()))
+    }
+}
+
+/// `max_retries` must allow at least one attempt, and no more than 10.
+pub fn check_max_retries(value: u32) -> std::result::Result<(), String> {
+    if !(1..=10).contains(&value) {
+        return Err(format!("max_retries must be between 1 and 10, got {}", value));
+    }
+    Ok(())
+}
+
+pub fn check_min_confidence(value: f32) -> std::result::Result<(), String> {
+    if !(0.0..=1.0).contains(&value) {
+        return Err(format!("min_confidence must be between 0 and 1, got {}", value));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SubPlan {
+    /// Short name used as the ID prefix (`web:T-001`) and in `/api/workspaces/:ws`.
+    pub name: String,
+    /// Directory holding the sub-plan's `plan/`, relative to this workspace.
+    pub path: String,
+}
+
+impl SubPlan {
+    fn resolve(&self, workspace_root: &Path) -> Result<PathBuf> {
+        if self.name.is_empty() || !self.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(anyhow!("Invalid workspace name '{}': use letters, digits, '-' and '_'", self.name));
+        }
+        if self.path.starts_with('/') || self.path.split(['/', '\\']).any(|part| part == "..") {
+            return Err(anyhow!("Workspace path must stay inside the repository: {}", self.path));
+        }
+        Ok(workspace_root.join(&self.path))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// Event types to send, e.g. `["status_changed", "pr_opened"]`; empty sends every event.
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+
+impl WebhookConfig {
+    pub fn wants(&self, kind: &str) -> bool {
+        self.events.is_empty() || self.events.iter().any(|e| e == kind)
+    }
+}
+
+impl PlanConfig {
+    /// Looks up a `[[workspaces]]` entry and returns its name and root directory.
+    pub fn sub_plan(&self, workspace_root: &Path, name: &str) -> Result<(String, PathBuf)> {
+        let entry = self.workspaces.iter()
+            .find(|w| w.name == name)
+            .ok_or_else(|| anyhow!("No workspace named '{}' in [[workspaces]]", name))?;
+        Ok((entry.name.clone(), entry.resolve(workspace_root)?))
+    }
+
+    /// Every `[[workspaces]]` entry with its root directory.
+    pub fn sub_plans(&self, workspace_root: &Path) -> Result<Vec<(String, PathBuf)>> {
+        self.workspaces.iter()
+            .map(|w| Ok((w.name.clone(), w.resolve(workspace_root)?)))
+            .collect()
+    }
+
+    pub fn path(workspace_root: &Path) -> PathBuf {
+        workspace_root.join("plan/config.toml")
+    }
+
+    pub fn load(workspace_root: &Path) -> Result<Self> {
+        let path = Self::path(workspace_root);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read config file: {:?}", path))?;
+        let config: PlanConfig = toml_edit::de::from_str(&content)
+            .map_err(|e| DirectorError::InvalidConfig(format!("{:?}: {}", path, e)))?;
+        check_max_retries(config.verification.max_retries)
+            .and_then(|_| check_min_confidence(config.verification.min_confidence))
+            .and_then(|_| crate::verification::flaky::check_patterns(&config.verification.flaky_patterns))
+            .map_err(|e| DirectorError::InvalidConfig(format!("{:?}: verification.{}", path, e)))?;
+        check_context_profiles(&config.context)
+            .map_err(|e| DirectorError::InvalidConfig(format!("{:?}: context.{}", path, e)))?;
+        check_routes(&config.execution)
+            .map_err(|e| DirectorError::InvalidConfig(format!("{:?}: execution.{}", path, e)))?;
+
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sub_plans() {
+        let config: PlanConfig = toml_edit::de::from_str(
+            "[[workspaces]]\nname = \"web\"\npath = \"apps/web\"\n\n[[workspaces]]\nname = \"bad name\"\npath = \"x\"\n",
+        ).unwrap();
+        let root = Path::new("/repo");
+        assert_eq!(config.sub_plan(root, "web").unwrap(), ("web".to_string(), PathBuf::from("/repo/apps/web")));
+        assert!(config.sub_plan(root, "api").is_err());
+        assert!(config.sub_plans(root).is_err());
+    }
+
+    #[test]
+    fn test_verification_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("plan")).unwrap();
+        fs::write(PlanConfig::path(dir.path()), "[verification]\nmax_retries = 2\n").unwrap();
+        let defaults = PlanConfig::load(dir.path()).unwrap().verification;
+        assert_eq!((defaults.max_retries, defaults.min_confidence), (2, 0.8));
+
+        let ticket: crate::types::Ticket = toml_edit::de::from_str(
+            "[meta]\nid = \"T-001\"\ntitle = \"t\"\nstatus = \"todo\"\npriority = \"low\"\ncreated_at = 2025-01-01T00:00:00Z\n\n[spec]\ndescription = \"\"\n\n[verification]\ncommand = \"true\"\nmin_confidence = 0.5\n",
+        ).unwrap();
+        assert_eq!(ticket.verification.max_retries(&defaults), 2);
+        assert_eq!(ticket.verification.min_confidence(&defaults), 0.5);
+
+        fs::write(PlanConfig::path(dir.path()), "[verification]\nmin_confidence = 1.5\n").unwrap();
+        assert!(PlanConfig::load(dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_agent_chain() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("plan")).unwrap();
+        fs::write(PlanConfig::path(dir.path()), "[execution]\nagents = [\"claude-cli\", \"aider\"]\n").unwrap();
+        let defaults = PlanConfig::load(dir.path()).unwrap().execution;
+
+        let mut ticket: crate::types::Ticket = toml_edit::de::from_str(
+            "[meta]\nid = \"T-001\"\ntitle = \"t\"\nstatus = \"todo\"\npriority = \"low\"\n\n[spec]\ndescription = \"\"\n\n[verification]\ncommand = \"true\"\n",
+        ).unwrap();
+        assert_eq!(ticket.execution.agent_chain("", &defaults.agents), ["claude-cli", "aider"]);
+        assert_eq!(ticket.execution.agent_chain("aider", &defaults.agents), ["aider", "claude-cli"]);
+
+        ticket.execution.agents = vec!["gpt-shell".to_string()];
+        assert_eq!(ticket.execution.agent_chain("aider", &defaults.agents), ["aider", "gpt-shell"]);
+    }
+
+    #[test]
+    fn test_agent_routes() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("plan")).unwrap();
+        fs::write(
+            PlanConfig::path(dir.path()),
+            "[[execution.routes]]\ntype = [\"chore\"]\npriority = [\"low\", \"medium\"]\nmax_context_files = 3\nagents = [\"cheap\"]\n\n[[execution.routes]]\nmin_context_files = 10\nagents = [\"strong\", \"stronger\"]\n",
+        ).unwrap();
+        let mut execution = PlanConfig::load(dir.path()).unwrap().execution;
+
+        let mut ticket: crate::types::Ticket = toml_edit::de::from_str(
+            "[meta]\nid = \"T-001\"\ntitle = \"t\"\nstatus = \"todo\"\npriority = \"low\"\ntype = \"chore\"\n\n[spec]\ndescription = \"\"\n\n[verification]\ncommand = \"true\"\n",
+        ).unwrap();
+        let agents = |ticket: &crate::types::Ticket, files: usize| execution.route(ticket, || files).map(|(i, route)| (i, route.agents.clone()));
+        assert_eq!(agents(&ticket, 2), Some((0, vec!["cheap".to_string()])));
+        assert_eq!(agents(&ticket, 12), Some((1, vec!["strong".to_string(), "stronger".to_string()])));
+        assert_eq!(agents(&ticket, 5), None);
+
+        // The context is counted once, and only when a route gets as far as asking
+        let counted = std::cell::Cell::new(0);
+        assert!(execution.route(&ticket, || { counted.set(counted.get() + 1); 5 }).is_none());
+        assert_eq!(counted.get(), 1);
+        ticket.meta.priority = crate::types::Priority::High;
+        execution.routes.pop();
+        assert!(execution.route(&ticket, || panic!("counted")).is_none());
+
+        fs::write(PlanConfig::path(dir.path()), "[[execution.routes]]\ntype = [\"chore\"]\n").unwrap();
+        assert!(PlanConfig::load(dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_context_profiles() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("plan")).unwrap();
+        fs::write(
+            PlanConfig::path(dir.path()),
+            "[context.profiles.backend]\ninclude = [\"crates/api/\"]\ndepth = 1\n\n[context.profiles.docs-only]\ndiscover = false\nschemas = false\n\n[context.type_profiles]\nbug = \"backend\"\n",
+        ).unwrap();
+        let context = PlanConfig::load(dir.path()).unwrap().context;
+
+        let ticket = |extra: &str| -> crate::types::Ticket {
+            toml_edit::de::from_str(&format!(
+                "[meta]\nid = \"T-001\"\ntitle = \"t\"\nstatus = \"todo\"\npriority = \"low\"\n{}\n[spec]\ndescription = \"\"\n{}\n[verification]\ncommand = \"true\"\n",
+                if extra.is_empty() { "type = \"bug\"" } else { "type = \"feature\"" },
+                extra,
+            )).unwrap()
+        };
+        let (name, profile) = context.profile(&ticket("")).unwrap().unwrap();
+        assert_eq!((name, profile.depth), ("backend", Some(1)));
+        let (name, profile) = context.profile(&ticket("context_profile = \"docs-only\"")).unwrap().unwrap();
+        assert_eq!(name, "docs-only");
+        assert!(!profile.discover && !context.with_profile(profile).schemas);
+        assert!(context.profile(&ticket("context_profile = \"mobile\"")).is_err());
+
+        fs::write(PlanConfig::path(dir.path()), "[context.type_profiles]\nbug = \"backend\"\n").unwrap();
+        assert!(PlanConfig::load(dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_asset_limits() {
+        let config: PlanConfig = toml_edit::de::from_str(
+            "[assets]\nallowed_types = [\"image/*\", \"text/plain\"]\ndefault_max_bytes = 100\n\n[assets.max_bytes]\n\"image/*\" = 1000\n\"image/gif\" = 10\n",
+        ).unwrap();
+        let assets = &config.assets;
+        assert!(assets.allows("image/webp"));
+        assert!(assets.allows("text/plain"));
+        assert!(!assets.allows("application/json"));
+        assert_eq!(assets.max_bytes_for("image/gif"), 10);
+        assert_eq!(assets.max_bytes_for("image/png"), 1000);
+        assert_eq!(assets.max_bytes_for("text/plain"), 100);
+        assert_eq!(assets.largest_limit(), 1000);
+    }
+}
@@ -0,0 +1,181 @@
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use anyhow::{Context, Result, anyhow};
+use crate::config::ContainerConfig;
+use crate::process::run_cancellable;
+use crate::shutdown::CancelToken;
+
+/// Runs agent and verification commands inside a Docker/Podman container.
+///
+/// The container never sees the real checkout: each run gets a scratch `git worktree` under
+/// `.director/containers/<run-id>`, which is what gets mounted. After every agent turn the
+/// worktree's changes are copied back to the host checkout with [`ContainerBackend::sync_to_host`].
+pub struct ContainerBackend {
+    config: ContainerConfig,
+    image: String,
+    workspace_root: PathBuf,
+    worktree: PathBuf,
+}
+
+impl ContainerBackend {
+    /// Resolves the image (building `plan/Dockerfile` if configured) and creates the worktree.
+    pub fn start(workspace_root: &Path, config: &ContainerConfig, run_id: &str) -> Result<Self> {
+        let image = resolve_image(workspace_root, config)?;
+
+        let worktree = crate::state_dir(workspace_root).join("containers").join(run_id);
+        let status = Command::new("git")
+            .current_dir(workspace_root)
+            .args(["worktree", "add", "--detach", "-q"])
+            .arg(&worktree)
+            .arg("HEAD")
+            .status()
+            .context("Failed to create container worktree")?;
+        if !status.success() {
+            return Err(anyhow!("git worktree add failed for {:?}", worktree));
+        }
+
+        crate::progress!(">> Running in container image {} (worktree {:?})", image, worktree);
+        Ok(Self {
+            config: config.clone(),
+            image,
+            workspace_root: workspace_root.to_path_buf(),
+            worktree,
+        })
+    }
+
+    /// The directory mounted into the container.
+    pub fn worktree(&self) -> &Path {
+        &self.worktree
+    }
+
+    /// `<runtime> run --rm -i -v <worktree>:<workdir> ... <image> sh -c <command>`
+    pub fn shell_command(&self, command: &str, env: &[(&str, &str)]) -> Command {
+        let mut cmd = Command::new(&self.config.runtime);
+        cmd.args(["run", "--rm", "-i"])
+            .arg("-v")
+            .arg(format!("{}:{}", self.worktree.display(), self.config.workdir))
+            .args(["-w", &self.config.workdir]);
+
+        if let Some(user) = container_user(&self.config) {
+            cmd.args(["--user", &user]);
+        }
+        // `-e NAME` copies the value from the runtime's own environment, so secrets
+        // don't show up in the process list.
+        for (key, value) in env {
+            cmd.env(key, value).arg("-e").arg(key);
+        }
+
+        cmd.args(&self.config.extra_args)
+            .arg(&self.image)
+            .args(["sh", "-c", command]);
+        cmd
+    }
+
+    /// Makes the host checkout match the worktree (everything except `plan/`).
+    pub fn sync_to_host(&self, cancel: &CancelToken) -> Result<()> {
+        git(&self.worktree, &["add", "-A"])?;
+        let mut diff = Command::new("git");
+        diff.current_dir(&self.worktree)
+            .args(["diff", "--cached", "--binary", "HEAD", "--", ".", ":(exclude)plan"]);
+        let diff = run_cancellable(diff, None, cancel)?;
+        if !diff.status.success() {
+            return Err(anyhow!("Failed to diff container worktree: {}", String::from_utf8_lossy(&diff.stderr)));
+        }
+
+        // Start from a clean host tree so repeated syncs don't stack
+        git(&self.workspace_root, &["reset", "-q"])?;
+        git(&self.workspace_root, &["checkout", "HEAD", "--", ".", ":(exclude)plan"])?;
+        git(&self.workspace_root, &["clean", "-fdq", "--", ".", ":(exclude)plan"])?;
+
+        if diff.stdout.is_empty() {
+            return Ok(());
+        }
+
+        let patch = String::from_utf8_lossy(&diff.stdout).to_string();
+        let mut apply = Command::new("git");
+        apply.current_dir(&self.workspace_root).args(["apply", "--binary", "-"]);
+        let applied = run_cancellable(apply, Some(&patch), cancel)?;
+        if !applied.status.success() {
+            return Err(anyhow!("Failed to copy container changes to host: {}", String::from_utf8_lossy(&applied.stderr)));
+        }
+        Ok(())
+    }
+
+    /// Throws away every change in the worktree but `plan/`, back to the commit it started from.
+    pub fn reset(&self) -> Result<()> {
+        git(&self.worktree, &["reset", "-q"])?;
+        git(&self.worktree, &["checkout", "HEAD", "--", ".", ":(exclude)plan"])?;
+        git(&self.worktree, &["clean", "-fdq", "--", ".", ":(exclude)plan"])
+    }
+}
+
+impl Drop for ContainerBackend {
+    fn drop(&mut self) {
+        let _ = Command::new("git")
+            .current_dir(&self.workspace_root)
+            .args(["worktree", "remove", "--force"])
+            .arg(&self.worktree)
+            .output();
+    }
+}
+
+/// The configured image, or a tag for `plan/Dockerfile` built on demand.
+fn resolve_image(workspace_root: &Path, config: &ContainerConfig) -> Result<String> {
+    let dockerfile = match &config.dockerfile {
+        Some(path) => workspace_root.join(path),
+        None => {
+            return config.image.clone()
+                .ok_or_else(|| anyhow!("[container] needs either `image` or `dockerfile`"));
+        }
+    };
+
+    let content = std::fs::read_to_string(&dockerfile)
+        .with_context(|| format!("Failed to read {:?}", dockerfile))?;
+    // Tag by content so an unchanged Dockerfile reuses the cached image
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    content.hash(&mut hasher);
+    let tag = format!("director-plan-run:{:016x}", hasher.finish());
+
+    crate::progress!(">> Building container image {} from {:?}", tag, dockerfile);
+    let status = Command::new(&config.runtime)
+        .current_dir(workspace_root)
+        .args(["build", "-q", "-t", &tag, "-f"])
+        .arg(&dockerfile)
+        .arg(".")
+        .stdout(crate::output::child_stdout())
+        .status()
+        .context("Failed to build container image")?;
+    if !status.success() {
+        return Err(anyhow!("{} build failed for {:?}", config.runtime, dockerfile));
+    }
+    Ok(tag)
+}
+
+/// Run as the invoking user by default so files written to the worktree stay removable.
+fn container_user(config: &ContainerConfig) -> Option<String> {
+    if config.user.is_some() {
+        return config.user.clone();
+    }
+    if !cfg!(unix) {
+        return None;
+    }
+    let id = |flag: &str| {
+        Command::new("id").arg(flag).output().ok()
+            .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+            .filter(|s| !s.is_empty())
+    };
+    Some(format!("{}:{}", id("-u")?, id("-g")?))
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<()> {
+    let status = Command::new("git")
+        .current_dir(dir)
+        .args(args)
+        .status()
+        .with_context(|| format!("Failed to run git {}", args.join(" ")))?;
+    if !status.success() {
+        return Err(anyhow!("git {} failed", args.join(" ")));
+    }
+    Ok(())
+}
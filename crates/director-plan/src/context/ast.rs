@@ -1,6 +1,7 @@
-use std::path::{Path, PathBuf};
-use std::collections::{HashMap, VecDeque};
+use std::path::{Component, Path, PathBuf};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
 use std::fs;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use anyhow::{Result};
 use petgraph::graph::DiGraph;
 use petgraph::prelude::*;
@@ -8,6 +9,8 @@ use oxc_allocator::Allocator;
 use oxc_parser::{Parser};
 use oxc_span::{SourceType, GetSpan}; // Added GetSpan
 use oxc_ast::ast::{Statement};
+use rayon::prelude::*;
+use regex::Regex;
 use walkdir::WalkDir;
 
 /// Represents a node in our dependency graph.
@@ -21,6 +24,12 @@ pub struct FileNode {
 pub enum FileType {
     TypeScript, // .ts, .tsx
     Rust,       // .rs
+    C,          // .c, .h
+    Cpp,        // .cpp, .cc, .cxx, .hpp, .hh, .hxx
+    Java,       // .java
+    Kotlin,     // .kt, .kts
+    Stylesheet, // .css, .scss, .sass, .less
+    Manifest,   // Cargo.toml, package.json
     Other,
 }
 
@@ -29,70 +38,111 @@ pub struct DependencyGraph {
     pub graph: DiGraph<FileNode, ()>,
     pub node_map: HashMap<String, NodeIndex>,
     pub root: PathBuf,
+    /// Workspace-relative directories `#include`s are also looked up in, like `-I`.
+    pub include_paths: Vec<String>,
+    /// Maven/Gradle source roots seen so far (`app/src/main/java`), for JVM imports.
+    source_roots: BTreeSet<String>,
+    /// Workspace packages by manifest file name and package name, e.g.
+    /// `("Cargo.toml", "core") -> "crates/core/Cargo.toml"`.
+    packages: HashMap<(String, String), String>,
 }
 
+/// Where Maven and Gradle keep JVM sources, relative to a module.
+const JVM_SOURCE_ROOTS: [&str; 4] = ["src/main/java", "src/main/kotlin", "src/test/java", "src/test/kotlin"];
+
 impl DependencyGraph {
     pub fn new(root: &Path) -> Self {
         Self {
             graph: DiGraph::new(),
             node_map: HashMap::new(),
             root: root.to_path_buf(),
+            include_paths: Vec::new(),
+            source_roots: BTreeSet::new(),
+            packages: HashMap::new(),
         }
     }
 
-    /// Builds the full dependency graph by scanning the workspace.
-    pub fn build(&mut self) -> Result<()> {
-        let ignore_patterns = vec![
-            "target", "node_modules", ".git", "dist", "build",
-        ];
+    /// Resolves C/C++ `#include`s against `include_paths` too, after the including file's
+    /// directory and the workspace root.
+    pub fn with_include_paths(mut self, include_paths: Vec<String>) -> Self {
+        self.include_paths = include_paths;
+        self
+    }
 
+    /// Builds the full dependency graph by scanning the workspace. Top-level directories are
+    /// walked and files parsed in parallel; only linking the results into the graph is serial.
+    pub fn build(&mut self) -> Result<()> {
         // 1. Discover all files first
-        let mut files = Vec::new();
-        for entry in WalkDir::new(&self.root)
-            .into_iter()
-            .filter_map(|e| e.ok())
-            .filter(|e| e.file_type().is_file())
-        {
-            let path = entry.path();
-            if path.components().any(|c| {
-                let s = c.as_os_str().to_string_lossy();
-                ignore_patterns.iter().any(|pat| s == *pat)
-            }) {
-                continue;
-            }
-
-            let rel_path = path.strip_prefix(&self.root)?.to_string_lossy().replace("\\", "/");
-            let file_type = if rel_path.ends_with(".ts") || rel_path.ends_with(".tsx") {
-                FileType::TypeScript
-            } else if rel_path.ends_with(".rs") {
-                FileType::Rust
-            } else {
-                FileType::Other
-            };
-
-            files.push((rel_path, file_type));
-        }
+        let files = discover_files(&self.root)?;
 
         // 2. Add nodes
         for (rel_path, file_type) in &files {
             self.add_node(rel_path, file_type.clone());
         }
 
-        // 3. Add edges (Analyze imports)
-        let files_clone = files.clone();
-        for (rel_path, file_type) in files_clone {
-             if let Err(e) = self.analyze_imports(&rel_path, &file_type) {
-                 eprintln!("Failed to analyze imports for {}: {}", rel_path, e);
-             }
+        // 3. Parse imports, one oxc allocator per thread, reset between files
+        let progress = Progress::new(files.len());
+        let root = &self.root;
+        let parsed: Vec<(&String, &FileType, Result<Vec<String>>)> = files
+            .par_iter()
+            .map_init(Allocator::default, |allocator, (rel_path, file_type)| {
+                allocator.reset();
+                let imports = read_imports(allocator, root, rel_path, file_type);
+                progress.tick();
+                (rel_path, file_type, imports)
+            })
+            .collect();
+
+        // 4. Add edges, once every manifest's package name is known
+        for (rel_path, _, imports) in &parsed {
+            if let Ok(imports) = imports {
+                self.register_package(rel_path, imports);
+            }
+        }
+        for (rel_path, file_type, imports) in parsed {
+            match imports {
+                Ok(imports) => self.link_imports(rel_path, file_type, &imports),
+                Err(e) => eprintln!("Failed to analyze imports for {}: {}", rel_path, e),
+            }
         }
 
         Ok(())
     }
 
+    /// Re-reads one file's imports after it changed, replacing its outgoing edges. A new file
+    /// gets a node; a deleted one keeps its node but loses its edges. Imports *of* a new file
+    /// from files that haven't changed are only picked up by a full [`build`](Self::build).
+    pub fn update_file(&mut self, rel_path: &str) {
+        // Directories, and files that came and went without ever being seen
+        if !self.root.join(rel_path).is_file() && !self.node_map.contains_key(rel_path) {
+            return;
+        }
+        let file_type = file_type_of(rel_path);
+        let idx = self.add_node(rel_path, file_type.clone());
+
+        // Highest index first: `remove_edge` moves the last edge into the freed slot
+        let mut edges: Vec<_> = self.graph.edges(idx).map(|e| e.id()).collect();
+        edges.sort_unstable_by(|a, b| b.cmp(a));
+        for edge in edges {
+            self.graph.remove_edge(edge);
+        }
+
+        if self.root.join(rel_path).is_file()
+            && let Err(e) = self.analyze_imports(rel_path, &file_type)
+        {
+            eprintln!("Failed to analyze imports for {}: {}", rel_path, e);
+        }
+    }
+
     fn add_node(&mut self, path: &str, file_type: FileType) -> NodeIndex {
         if let Some(&idx) = self.node_map.get(path) {
             return idx;
         }
+        if matches!(file_type, FileType::Java | FileType::Kotlin)
+            && let Some(root) = jvm_source_root(path)
+        {
+            self.source_roots.insert(root.to_string());
+        }
         let node = FileNode {
             path: path.to_string(),
             file_type,
@@ -111,31 +161,38 @@ impl DependencyGraph {
     }
 
     fn analyze_imports(&mut self, rel_path: &str, file_type: &FileType) -> Result<()> {
-        let abs_path = self.root.join(rel_path);
-        let content = fs::read_to_string(&abs_path)?;
-
-        match file_type {
-            FileType::TypeScript => {
-                let imports = parse_ts_imports(&rel_path, &content, &self.root)?;
-                for import in imports {
-                    if let Some(resolved) = self.resolve_ts_import(rel_path, &import) {
-                         self.add_edge(rel_path, &resolved);
-                    }
-                }
-            },
-            FileType::Rust => {
-                let imports = parse_rs_imports(&content);
-                for import in imports {
-                    if let Some(resolved) = self.resolve_rs_import(rel_path, &import) {
-                        self.add_edge(rel_path, &resolved);
-                    }
-                }
-            },
-            _ => {}
-        }
+        let imports = read_imports(&Allocator::default(), &self.root, rel_path, file_type)?;
+        self.link_imports(rel_path, file_type, &imports);
         Ok(())
     }
 
+    fn link_imports(&mut self, rel_path: &str, file_type: &FileType, imports: &[String]) {
+        if let Some(manifest) = self.package_manifest(rel_path, file_type) {
+            self.add_edge(rel_path, &manifest);
+        }
+        if *file_type == FileType::Manifest {
+            self.register_package(rel_path, imports);
+        }
+        let roots = match file_type {
+            FileType::Java | FileType::Kotlin => self.jvm_roots(rel_path, imports),
+            _ => Vec::new(),
+        };
+        for import in imports {
+            let resolved = match file_type {
+                FileType::TypeScript => self.resolve_ts_import(rel_path, import),
+                FileType::Rust => self.resolve_rs_import(rel_path, import),
+                FileType::C | FileType::Cpp => self.resolve_c_include(rel_path, import),
+                FileType::Java | FileType::Kotlin => self.resolve_jvm_import(&roots, import),
+                FileType::Stylesheet => self.resolve_css_ref(rel_path, import),
+                FileType::Manifest => self.resolve_dependency(rel_path, import),
+                FileType::Other => None,
+            };
+            if let Some(resolved) = resolved {
+                self.add_edge(rel_path, &resolved);
+            }
+        }
+    }
+
     fn resolve_ts_import(&self, current_file: &str, import_path: &str) -> Option<String> {
         let current_dir = Path::new(current_file).parent().unwrap_or(Path::new(""));
 
@@ -152,7 +209,7 @@ impl DependencyGraph {
         let extensions = ["ts", "tsx", "d.ts", "js", "jsx"];
 
         for candidate in candidates {
-            let s = candidate.to_string_lossy().replace("\\", "/");
+            let Some(s) = normalize(&candidate) else { continue };
             if self.node_map.contains_key(&s) { return Some(s); }
 
             for ext in &extensions {
@@ -206,8 +263,238 @@ impl DependencyGraph {
         None
     }
 
+    /// `"file.h"` is looked up next to the including file, then from the workspace root, then
+    /// in the include paths. `<file.h>` only in the include paths, so system headers don't
+    /// match a workspace file by accident.
+    fn resolve_c_include(&self, current_file: &str, include: &str) -> Option<String> {
+        let mut candidates = Vec::new();
+        let header = match include.strip_prefix('<') {
+            Some(header) => header,
+            None => {
+                let current_dir = Path::new(current_file).parent().unwrap_or(Path::new(""));
+                candidates.push(current_dir.join(include));
+                candidates.push(PathBuf::from(include));
+                include
+            }
+        };
+        candidates.extend(self.include_paths.iter().map(|dir| Path::new(dir).join(header)));
+
+        candidates.iter()
+            .filter_map(|candidate| normalize(candidate))
+            .find(|path| self.node_map.contains_key(path))
+    }
+
+    /// Records the package a manifest declares (its `package <name>` entry), so dependencies
+    /// on it by name resolve.
+    fn register_package(&mut self, rel_path: &str, imports: &[String]) {
+        let kind = rel_path.rsplit('/').next().unwrap_or(rel_path);
+        if let Some(name) = imports.iter().find_map(|import| import.strip_prefix("package ")) {
+            self.packages.insert((kind.to_string(), name.to_string()), rel_path.to_string());
+        }
+    }
+
+    /// The manifest of the package a source file belongs to: the closest `Cargo.toml` above a
+    /// Rust file, the closest `package.json` above a TypeScript one.
+    fn package_manifest(&self, rel_path: &str, file_type: &FileType) -> Option<String> {
+        let kind = match file_type {
+            FileType::Rust => "Cargo.toml",
+            FileType::TypeScript => "package.json",
+            _ => return None,
+        };
+        Path::new(rel_path).ancestors().skip(1)
+            .map(|dir| normalize(&dir.join(kind)))
+            .find_map(|manifest| manifest.filter(|m| self.node_map.contains_key(m)))
+    }
+
+    /// A manifest's dependency on another workspace package: `path:dir` (a Cargo `path` or
+    /// npm `file:` dependency), `workspace` (the Cargo workspace it inherits from) or a
+    /// package name. Dependencies from a registry don't resolve.
+    fn resolve_dependency(&self, manifest: &str, dependency: &str) -> Option<String> {
+        let kind = manifest.rsplit('/').next().unwrap_or(manifest);
+        let dir = Path::new(manifest).parent().unwrap_or(Path::new(""));
+        if dependency.starts_with("package ") {
+            None
+        } else if let Some(path) = dependency.strip_prefix("path:") {
+            normalize(&dir.join(path).join(kind)).filter(|m| self.node_map.contains_key(m))
+        } else if dependency == "workspace" {
+            dir.ancestors().skip(1)
+                .filter_map(|ancestor| normalize(&ancestor.join(kind)))
+                .find(|m| self.root.join(m).is_file() && is_cargo_workspace(&self.root.join(m)))
+        } else {
+            self.packages.get(&(kind.to_string(), dependency.to_string()))
+                .filter(|m| *m != manifest)
+                .cloned()
+        }
+    }
+
+    /// An `@import`/`@use` or `url()` target: relative to the stylesheet, or to the workspace
+    /// root and `public/` when it starts with `/`. Sass partials (`_name.scss`) and index
+    /// files are tried too. Remote URLs, data URIs and `~package` imports don't resolve.
+    fn resolve_css_ref(&self, current_file: &str, reference: &str) -> Option<String> {
+        if reference.contains("://") || reference.starts_with("data:") || reference.starts_with("//")
+            || reference.starts_with('#') || reference.starts_with('~')
+        {
+            return None;
+        }
+        let bases = match reference.strip_prefix('/') {
+            Some(absolute) => vec![PathBuf::from(absolute), Path::new("public").join(absolute)],
+            None => vec![Path::new(current_file).parent().unwrap_or(Path::new("")).join(reference)],
+        };
+
+        let extensions = ["scss", "sass", "css", "less"];
+        for base in bases {
+            let Some(s) = normalize(&base) else { continue };
+            let (dir, name) = match s.rsplit_once('/') {
+                Some((dir, name)) => (format!("{}/", dir), name),
+                None => (String::new(), s.as_str()),
+            };
+            let mut candidates = vec![s.clone()];
+            for ext in &extensions {
+                candidates.push(format!("{}.{}", s, ext));
+                candidates.push(format!("{}_{}.{}", dir, name, ext));
+                candidates.push(format!("{}/_index.{}", s, ext));
+                candidates.push(format!("{}/index.{}", s, ext));
+            }
+            if let Some(found) = candidates.into_iter().find(|c| self.node_map.contains_key(c)) {
+                return Some(found);
+            }
+        }
+        None
+    }
+
+    /// The roots a JVM file's imports are looked up under: its own, worked out from its
+    /// `package` so layouts other than Maven's work too, then every standard source root.
+    fn jvm_roots(&self, current_file: &str, imports: &[String]) -> Vec<String> {
+        let mut roots = Vec::new();
+        let package = imports.iter().find_map(|import| import.strip_prefix("package "));
+        if let Some(package) = package {
+            let dir = Path::new(current_file).parent().unwrap_or(Path::new("")).to_string_lossy().replace("\\", "/");
+            let package_dir = package.replace('.', "/");
+            if dir == package_dir {
+                roots.push(String::new());
+            } else if let Some(root) = dir.strip_suffix(&format!("/{}", package_dir)) {
+                roots.push(root.to_string());
+            }
+        }
+        for root in &self.source_roots {
+            if !roots.contains(root) {
+                roots.push(root.clone());
+            }
+        }
+        roots
+    }
+
+    /// `a.b.C` as `a/b/C.java` or `a/b/C.kt` under one of `roots`. Static imports and nested
+    /// classes (`a.b.C.member`) fall back to the enclosing class's file; wildcard package
+    /// imports and anything outside the workspace don't resolve.
+    fn resolve_jvm_import(&self, roots: &[String], import: &str) -> Option<String> {
+        if import.starts_with("package ") {
+            return None;
+        }
+        let segments: Vec<&str> = import.split('.').collect();
+        for len in (2..=segments.len()).rev() {
+            let class = segments[..len].join("/");
+            for root in roots {
+                for ext in ["java", "kt"] {
+                    let candidate = match root.as_str() {
+                        "" => format!("{}.{}", class, ext),
+                        root => format!("{}/{}.{}", root, class, ext),
+                    };
+                    if self.node_map.contains_key(&candidate) {
+                        return Some(candidate);
+                    }
+                }
+            }
+        }
+        None
+    }
+
     pub fn get_context(&self, entry_files: &[String]) -> Vec<(String, String)> {
-        let mut visited = HashMap::new();
+        let mut results = Vec::new();
+        for (path, depth) in self.context_depths(entry_files) {
+             let abs_path = self.root.join(&path);
+             if let Ok(content) = fs::read_to_string(&abs_path) {
+                 if depth <= 1 {
+                     results.push((path, content));
+                 } else {
+                     let pruned = prune_content(&path, &content);
+                     results.push((path, pruned));
+                 }
+             }
+        }
+        results
+    }
+
+    /// Every file that imports one of `files`, directly or through other files, sorted.
+    /// `files` themselves are left out unless they import each other.
+    pub fn dependents(&self, files: &[String]) -> Vec<String> {
+        let mut seen: HashSet<NodeIndex> = HashSet::new();
+        let mut queue: VecDeque<NodeIndex> = files.iter().filter_map(|f| self.node_map.get(f).copied()).collect();
+        while let Some(idx) = queue.pop_front() {
+            for importer in self.graph.neighbors_directed(idx, Direction::Incoming) {
+                if seen.insert(importer) {
+                    queue.push_back(importer);
+                }
+            }
+        }
+        let mut paths: Vec<String> = seen.into_iter().map(|idx| self.graph[idx].path.clone()).collect();
+        paths.sort();
+        paths
+    }
+
+    /// [`dependents`](Self::dependents) with how many imports away from `files` each one is,
+    /// nearest first, going no further than `max_depth` when there is one. `files` themselves
+    /// come first at depth 0.
+    pub fn dependent_depths(&self, files: &[String], max_depth: Option<usize>) -> Vec<(String, usize)> {
+        let mut depths: HashMap<NodeIndex, usize> = HashMap::new();
+        let mut queue: VecDeque<NodeIndex> = VecDeque::new();
+        for idx in files.iter().filter_map(|f| self.node_map.get(f).copied()) {
+            if depths.insert(idx, 0).is_none() {
+                queue.push_back(idx);
+            }
+        }
+        while let Some(idx) = queue.pop_front() {
+            let depth = depths[&idx] + 1;
+            if max_depth.is_some_and(|max| depth > max) {
+                continue;
+            }
+            for importer in self.graph.neighbors_directed(idx, Direction::Incoming) {
+                if let std::collections::hash_map::Entry::Vacant(entry) = depths.entry(importer) {
+                    entry.insert(depth);
+                    queue.push_back(importer);
+                }
+            }
+        }
+        let mut results: Vec<(String, usize)> = depths.into_iter().map(|(idx, depth)| (self.graph[idx].path.clone(), depth)).collect();
+        results.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+        results
+    }
+
+    /// The files importing `file` directly, sorted.
+    pub fn importers(&self, file: &str) -> Vec<String> {
+        let Some(&idx) = self.node_map.get(file) else { return Vec::new() };
+        let mut paths: Vec<String> = self.graph.neighbors_directed(idx, Direction::Incoming).map(|n| self.graph[n].path.clone()).collect();
+        paths.sort();
+        paths
+    }
+
+    /// The files `file` imports directly, sorted.
+    pub fn imports(&self, file: &str) -> Vec<String> {
+        let Some(&idx) = self.node_map.get(file) else { return Vec::new() };
+        let mut paths: Vec<String> = self.graph.neighbors(idx).map(|n| self.graph[n].path.clone()).collect();
+        paths.sort();
+        paths
+    }
+
+    /// The files within two hops of `entry_files` and how far away each one is, sorted by path.
+    /// Like [`get_context`](Self::get_context) without reading anything.
+    pub fn context_depths(&self, entry_files: &[String]) -> Vec<(String, usize)> {
+        self.context_depths_within(entry_files, 2)
+    }
+
+    /// [`context_depths`](Self::context_depths) out to `max_depth` hops instead of two.
+    pub fn context_depths_within(&self, entry_files: &[String], max_depth: usize) -> Vec<(String, usize)> {
+        let mut visited: HashMap<String, usize> = HashMap::new();
         let mut queue = VecDeque::new();
 
         for f in entry_files {
@@ -218,7 +505,7 @@ impl DependencyGraph {
         }
 
         while let Some((idx, depth)) = queue.pop_front() {
-            if depth >= 2 {
+            if depth >= max_depth {
                 continue;
             }
 
@@ -228,38 +515,192 @@ impl DependencyGraph {
 
                 if !visited.contains_key(neighbor_path) || visited[neighbor_path] > new_depth {
                     visited.insert(neighbor_path.clone(), new_depth);
-                    if new_depth < 3 {
+                    if new_depth <= max_depth {
                          queue.push_back((neighbor, new_depth));
                     }
                 }
             }
         }
 
-        let mut results = Vec::new();
-        for (path, depth) in visited {
-             let abs_path = self.root.join(&path);
-             if let Ok(content) = fs::read_to_string(&abs_path) {
-                 if depth <= 1 {
-                     results.push((path, content));
-                 } else if depth == 2 {
-                     let pruned = prune_content(&path, &content);
-                     results.push((path, pruned));
-                 }
-             }
-        }
-
+        let mut results: Vec<(String, usize)> = visited.into_iter().collect();
         results.sort_by(|a, b| a.0.cmp(&b.0));
         results
     }
 }
 
+/// Directories never descended into.
+const IGNORED_DIRS: [&str; 5] = ["target", "node_modules", ".git", "dist", "build"];
+
+/// Whether a workspace-relative path lies inside one of the [`IGNORED_DIRS`].
+pub(crate) fn is_ignored_path(rel_path: &str) -> bool {
+    rel_path.split('/').any(|part| IGNORED_DIRS.contains(&part))
+}
+
+/// Workspaces with fewer files than this build too fast to be worth reporting progress on.
+const PROGRESS_MIN_FILES: usize = 2000;
+
+/// Every file under `root` outside [`IGNORED_DIRS`], as relative paths. Each top-level
+/// directory is walked on its own thread.
+fn discover_files(root: &Path) -> Result<Vec<(String, FileType)>> {
+    let is_ignored = |e: &walkdir::DirEntry| {
+        e.file_type().is_dir() && IGNORED_DIRS.iter().any(|dir| e.file_name() == *dir)
+    };
+    let top_level: Vec<PathBuf> = fs::read_dir(root)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .collect();
+
+    let mut files: Vec<(String, FileType)> = top_level
+        .par_iter()
+        .flat_map_iter(|path| {
+            WalkDir::new(path)
+                .into_iter()
+                .filter_entry(|e| !is_ignored(e))
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_file())
+                .filter_map(|e| {
+                    let rel_path = e.path().strip_prefix(root).ok()?.to_string_lossy().replace("\\", "/");
+                    let file_type = file_type_of(&rel_path);
+                    Some((rel_path, file_type))
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    // Node order (and so the graph) doesn't depend on thread scheduling
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(files)
+}
+
+/// The raw import paths in a file, before they are resolved against the workspace. Java and
+/// Kotlin files also list their package, as `package a.b`, and manifests their package name.
+fn read_imports(allocator: &Allocator, root: &Path, rel_path: &str, file_type: &FileType) -> Result<Vec<String>> {
+    match file_type {
+        FileType::TypeScript => {
+            let content = fs::read_to_string(root.join(rel_path))?;
+            parse_ts_imports_in(allocator, rel_path, &content)
+        }
+        FileType::Rust => {
+            let content = fs::read_to_string(root.join(rel_path))?;
+            Ok(parse_rs_imports(&content))
+        }
+        FileType::Java | FileType::Kotlin => {
+            let content = fs::read_to_string(root.join(rel_path))?;
+            Ok(parse_jvm_imports(&content))
+        }
+        FileType::Stylesheet => {
+            let content = fs::read_to_string(root.join(rel_path))?;
+            Ok(parse_css_refs(&content))
+        }
+        FileType::Manifest => {
+            let content = fs::read_to_string(root.join(rel_path))?;
+            if rel_path.ends_with("package.json") {
+                parse_npm_manifest(&content)
+            } else {
+                parse_cargo_manifest(&content)
+            }
+        }
+        FileType::C | FileType::Cpp => {
+            // Vendored sources aren't always UTF-8
+            let content = fs::read(root.join(rel_path))?;
+            Ok(parse_c_includes(&String::from_utf8_lossy(&content)))
+        }
+        FileType::Other => Ok(vec![]),
+    }
+}
+
+/// Whether `path` is a `Cargo.toml` or `package.json`.
+pub(crate) fn is_manifest(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    name == "Cargo.toml" || name == "package.json"
+}
+
+/// The Maven/Gradle source root `path` lies under, e.g. `app/src/main/java` for
+/// `app/src/main/java/com/acme/App.java`.
+fn jvm_source_root(path: &str) -> Option<&str> {
+    JVM_SOURCE_ROOTS.iter().find_map(|marker| {
+        let start = path.match_indices(&format!("{}/", marker))
+            .map(|(i, _)| i)
+            .find(|&i| i == 0 || path[..i].ends_with('/'))?;
+        Some(&path[..start + marker.len()])
+    })
+}
+
+/// `path` with `.` and `..` resolved, as a workspace-relative string. `None` if it climbs
+/// out of the workspace.
+fn normalize(path: &Path) -> Option<String> {
+    let mut parts: Vec<&str> = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_str()?),
+            Component::ParentDir => {
+                parts.pop()?;
+            }
+            Component::CurDir => {}
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(parts.join("/"))
+}
+
+/// Reports every tenth of the files parsed, for workspaces big enough to make the wait noticeable.
+struct Progress {
+    total: usize,
+    done: AtomicUsize,
+}
+
+impl Progress {
+    fn new(total: usize) -> Self {
+        if total >= PROGRESS_MIN_FILES {
+            crate::progress!(">> Building dependency graph ({} files)", total);
+        }
+        Self { total, done: AtomicUsize::new(0) }
+    }
+
+    fn tick(&self) {
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+        if self.total < PROGRESS_MIN_FILES {
+            return;
+        }
+        let step = self.total.div_ceil(10);
+        if done % step == 0 || done == self.total {
+            crate::progress!(">> Dependency graph: {}/{} files parsed ({}%)", done, self.total, done * 100 / self.total);
+        }
+    }
+}
+
+fn file_type_of(rel_path: &str) -> FileType {
+    if is_manifest(rel_path) {
+        FileType::Manifest
+    } else if rel_path.ends_with(".ts") || rel_path.ends_with(".tsx") {
+        FileType::TypeScript
+    } else if rel_path.ends_with(".rs") {
+        FileType::Rust
+    } else if rel_path.ends_with(".java") {
+        FileType::Java
+    } else if rel_path.ends_with(".kt") || rel_path.ends_with(".kts") {
+        FileType::Kotlin
+    } else if [".css", ".scss", ".sass", ".less"].iter().any(|ext| rel_path.ends_with(ext)) {
+        FileType::Stylesheet
+    } else if rel_path.ends_with(".c") || rel_path.ends_with(".h") {
+        FileType::C
+    } else if [".cpp", ".cc", ".cxx", ".hpp", ".hh", ".hxx"].iter().any(|ext| rel_path.ends_with(ext)) {
+        FileType::Cpp
+    } else {
+        FileType::Other
+    }
+}
+
 // --- AST Parsing (TypeScript/OXC) ---
 
 fn parse_ts_imports(_path: &str, content: &str, _root: &Path) -> Result<Vec<String>> {
-    let allocator = Allocator::default();
-    let source_type = SourceType::from_path(Path::new(_path)).unwrap_or_default().with_typescript(true).with_module(true);
+    parse_ts_imports_in(&Allocator::default(), _path, content)
+}
 
-    let parser = Parser::new(&allocator, content, source_type);
+/// [`parse_ts_imports`] with the caller's allocator, so a thread can reuse one across files.
+fn parse_ts_imports_in(allocator: &Allocator, path: &str, content: &str) -> Result<Vec<String>> {
+    let source_type = SourceType::from_path(Path::new(path)).unwrap_or_default().with_typescript(true).with_module(true);
+
+    let parser = Parser::new(allocator, content, source_type);
     let ret = parser.parse();
 
     if !ret.errors.is_empty() {
@@ -351,10 +792,140 @@ fn extract_use_paths(tree: &syn::UseTree, prefix: String, results: &mut Vec<Stri
     }
 }
 
+// --- Include Parsing (C/C++) ---
+
+/// The targets of a file's `#include` directives: `"file.h"` as written, `<file.h>` with its
+/// leading `<` kept so the resolver can tell the two apart. Includes inside comments or
+/// disabled `#if` blocks are picked up too; they just may not resolve.
+fn parse_c_includes(content: &str) -> Vec<String> {
+    let mut includes = Vec::new();
+    for line in content.lines() {
+        let Some(directive) = line.trim_start().strip_prefix('#') else { continue };
+        let Some(target) = directive.trim_start().strip_prefix("include") else { continue };
+        let target = target.trim_start();
+        if let Some(rest) = target.strip_prefix('"')
+            && let Some(end) = rest.find('"')
+        {
+            includes.push(rest[..end].to_string());
+        } else if let Some(rest) = target.strip_prefix('<')
+            && let Some(end) = rest.find('>')
+        {
+            includes.push(format!("<{}", &rest[..end]));
+        }
+    }
+    includes
+}
+
+// --- Import Parsing (Java/Kotlin) ---
+
+/// The `package` (as `package a.b`) and the classes a Java or Kotlin file imports, with
+/// `static`, `as` aliases and a trailing `.*` dropped.
+fn parse_jvm_imports(content: &str) -> Vec<String> {
+    let mut imports = Vec::new();
+    for line in content.lines() {
+        let line = line.trim().trim_end_matches(';').trim_end();
+        if let Some(package) = line.strip_prefix("package ") {
+            imports.push(format!("package {}", package.trim()));
+        } else if let Some(import) = line.strip_prefix("import ") {
+            let import = import.trim_start();
+            let import = import.strip_prefix("static ").unwrap_or(import);
+            let import = import.split_whitespace().next().unwrap_or("");
+            let import = import.strip_suffix(".*").unwrap_or(import);
+            if !import.is_empty() {
+                imports.push(import.to_string());
+            }
+        }
+    }
+    imports
+}
+
+// --- Reference Parsing (CSS/SCSS) ---
+
+/// What a stylesheet pulls in: `@import`, `@use` and `@forward` targets, and every `url()`
+/// with any query or fragment dropped. Nothing is checked against the workspace yet.
+fn parse_css_refs(content: &str) -> Vec<String> {
+    let import_re = Regex::new(r#"@(?:import|use|forward)\s+(?:url\(\s*)?["']([^"']+)["']"#).unwrap();
+    let url_re = Regex::new(r#"url\(\s*["']?([^"')\s]+)["']?\s*\)"#).unwrap();
+
+    let mut refs: Vec<String> = Vec::new();
+    let targets = import_re.captures_iter(content).chain(url_re.captures_iter(content));
+    for target in targets.map(|c| c[1].split(['?', '#']).next().unwrap_or_default().to_string()) {
+        if !target.is_empty() && !refs.contains(&target) {
+            refs.push(target);
+        }
+    }
+    refs
+}
+
+// --- Manifest Parsing (Cargo/npm) ---
+
+/// Cargo dependency tables, at the top level or under `[target.'cfg(..)']`.
+const CARGO_DEPENDENCY_TABLES: [&str; 3] = ["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// The package name (as `package <name>`) and dependencies of a `Cargo.toml`, in the forms
+/// [`DependencyGraph::resolve_dependency`] takes.
+fn parse_cargo_manifest(content: &str) -> Result<Vec<String>> {
+    let doc: toml_edit::DocumentMut = content.parse()?;
+    let mut refs = Vec::new();
+    if let Some(name) = doc.get("package").and_then(|p| p.get("name")).and_then(|n| n.as_str()) {
+        refs.push(format!("package {}", name));
+    }
+
+    let targets = doc.get("target").and_then(|t| t.as_table_like());
+    let tables = std::iter::once(doc.as_item())
+        .chain(targets.into_iter().flat_map(|t| t.iter().map(|(_, item)| item)))
+        .flat_map(|scope| CARGO_DEPENDENCY_TABLES.iter().filter_map(move |table| scope.get(table)?.as_table_like()));
+    let mut inherits = doc.get("package").and_then(|p| p.as_table_like())
+        .is_some_and(|p| p.iter().any(|(_, v)| v.get("workspace").and_then(|w| w.as_bool()) == Some(true)));
+    for table in tables {
+        for (name, spec) in table.iter() {
+            let package = spec.get("package").and_then(|p| p.as_str()).unwrap_or(name);
+            if let Some(path) = spec.get("path").and_then(|p| p.as_str()) {
+                refs.push(format!("path:{}", path));
+            } else if !refs.iter().any(|r| r == package) {
+                refs.push(package.to_string());
+            }
+            inherits |= spec.get("workspace").and_then(|w| w.as_bool()) == Some(true);
+        }
+    }
+    if inherits {
+        refs.push("workspace".to_string());
+    }
+    Ok(refs)
+}
+
+/// The package name and dependencies of a `package.json`: `file:`/`link:` versions as
+/// paths, everything else by name.
+fn parse_npm_manifest(content: &str) -> Result<Vec<String>> {
+    let manifest: serde_json::Value = serde_json::from_str(content)?;
+    let mut refs = Vec::new();
+    if let Some(name) = manifest["name"].as_str() {
+        refs.push(format!("package {}", name));
+    }
+    for table in ["dependencies", "devDependencies", "peerDependencies", "optionalDependencies"] {
+        let Some(dependencies) = manifest[table].as_object() else { continue };
+        for (name, version) in dependencies {
+            let version = version.as_str().unwrap_or_default();
+            match version.strip_prefix("file:").or_else(|| version.strip_prefix("link:")) {
+                Some(path) => refs.push(format!("path:{}", path)),
+                None if !refs.contains(name) => refs.push(name.clone()),
+                None => {}
+            }
+        }
+    }
+    Ok(refs)
+}
+
+/// Whether the `Cargo.toml` at `path` declares a `[workspace]`.
+fn is_cargo_workspace(path: &Path) -> bool {
+    fs::read_to_string(path).ok()
+        .and_then(|content| content.parse::<toml_edit::DocumentMut>().ok())
+        .is_some_and(|doc| doc.contains_key("workspace"))
+}
 
 // --- Content Pruning ---
 
-fn prune_content(path: &str, content: &str) -> String {
+pub(crate) fn prune_content(path: &str, content: &str) -> String {
     if path.ends_with(".ts") || path.ends_with(".tsx") {
         prune_ts(content)
     } else {
@@ -449,6 +1020,177 @@ mod tests {
         assert!(imports.contains(&"./utils".to_string()));
     }
 
+    #[test]
+    fn test_discover_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src/build")).unwrap();
+        fs::create_dir_all(dir.path().join("node_modules/pkg")).unwrap();
+        fs::write(dir.path().join("src/main.rs"), "").unwrap();
+        fs::write(dir.path().join("src/build/out.rs"), "").unwrap();
+        fs::write(dir.path().join("node_modules/pkg/index.ts"), "").unwrap();
+        fs::write(dir.path().join("index.ts"), "").unwrap();
+
+        let files = discover_files(dir.path()).unwrap();
+        assert_eq!(files, vec![
+            ("index.ts".to_string(), FileType::TypeScript),
+            ("src/main.rs".to_string(), FileType::Rust),
+        ]);
+    }
+
+    #[test]
+    fn test_update_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("lib.rs"), "mod a;").unwrap();
+        fs::write(src.join("a.rs"), "").unwrap();
+        fs::write(src.join("b.rs"), "").unwrap();
+
+        let mut graph = DependencyGraph::new(dir.path());
+        graph.build().unwrap();
+        let seeds = vec!["src/lib.rs".to_string()];
+        let paths = |graph: &DependencyGraph| graph.context_depths(&seeds).into_iter().map(|(p, _)| p).collect::<Vec<_>>();
+        assert_eq!(paths(&graph), vec!["src/a.rs", "src/lib.rs"]);
+
+        fs::write(src.join("lib.rs"), "mod b;").unwrap();
+        graph.update_file("src/lib.rs");
+        assert_eq!(paths(&graph), vec!["src/b.rs", "src/lib.rs"]);
+
+        fs::remove_file(src.join("lib.rs")).unwrap();
+        graph.update_file("src/lib.rs");
+        assert_eq!(paths(&graph), vec!["src/lib.rs"]);
+    }
+
+    #[test]
+    fn test_dependents() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("lib.rs"), "mod a;\nmod c;").unwrap();
+        fs::write(src.join("a.rs"), "mod b;").unwrap();
+        fs::write(src.join("b.rs"), "").unwrap();
+        fs::write(src.join("c.rs"), "").unwrap();
+
+        let mut graph = DependencyGraph::new(dir.path());
+        graph.build().unwrap();
+        assert_eq!(graph.dependents(&["src/b.rs".to_string()]), vec!["src/a.rs", "src/lib.rs"]);
+        assert!(graph.dependents(&["src/lib.rs".to_string()]).is_empty());
+        assert_eq!(graph.dependent_depths(&["src/b.rs".to_string()], None), [
+            ("src/b.rs".to_string(), 0),
+            ("src/a.rs".to_string(), 1),
+            ("src/lib.rs".to_string(), 2),
+        ]);
+        assert_eq!(graph.dependent_depths(&["src/b.rs".to_string()], Some(1)).len(), 2);
+    }
+
+    #[test]
+    fn test_c_includes() {
+        let dir = tempfile::tempdir().unwrap();
+        for (path, content) in [
+            ("src/main.c", "#include <stdio.h>\n#include \"util.h\"\n  #  include <hal/gpio.h>\n"),
+            ("src/util.h", "#include \"../common/types.h\"\n"),
+            ("common/types.h", ""),
+            ("drivers/hal/gpio.h", ""),
+            ("drivers/spi.cpp", "#include \"src/util.h\"\n#include <missing.hpp>\n"),
+        ] {
+            fs::create_dir_all(dir.path().join(path).parent().unwrap()).unwrap();
+            fs::write(dir.path().join(path), content).unwrap();
+        }
+        assert_eq!(parse_c_includes("#include <stdio.h>\n#include \"util.h\""), ["<stdio.h>", "util.h"]);
+
+        let mut graph = DependencyGraph::new(dir.path()).with_include_paths(vec!["drivers".to_string()]);
+        graph.build().unwrap();
+        assert_eq!(graph.graph[graph.node_map["drivers/spi.cpp"]].file_type, FileType::Cpp);
+        let depths = graph.context_depths(&["src/main.c".to_string()]);
+        assert_eq!(depths, vec![
+            ("common/types.h".to_string(), 2),
+            ("drivers/hal/gpio.h".to_string(), 1),
+            ("src/main.c".to_string(), 0),
+            ("src/util.h".to_string(), 1),
+        ]);
+        // Quoted includes fall back to the workspace root
+        assert_eq!(graph.dependents(&["src/util.h".to_string()]), vec!["drivers/spi.cpp", "src/main.c"]);
+    }
+
+    #[test]
+    fn test_jvm_imports() {
+        let dir = tempfile::tempdir().unwrap();
+        for (path, content) in [
+            ("app/src/main/java/com/acme/App.java", "package com.acme;\n\nimport com.acme.util.Strings;\nimport static com.acme.Config.DEBUG;\nimport java.util.*;\n"),
+            ("app/src/main/java/com/acme/Config.java", "package com.acme;\n"),
+            ("lib/src/main/kotlin/com/acme/util/Strings.kt", "package com.acme.util\n\nimport com.acme.legacy.Helper as LegacyHelper\n"),
+            ("legacy/com/acme/legacy/Old.java", "package com.acme.legacy;\nimport com.acme.legacy.Helper;\n"),
+            ("legacy/com/acme/legacy/Helper.java", "package com.acme.legacy;\n"),
+        ] {
+            fs::create_dir_all(dir.path().join(path).parent().unwrap()).unwrap();
+            fs::write(dir.path().join(path), content).unwrap();
+        }
+        assert_eq!(
+            parse_jvm_imports("package a.b\nimport static a.b.C.d;\nimport a.b.E as F\nimport a.g.*;"),
+            ["package a.b", "a.b.C.d", "a.b.E", "a.g"],
+        );
+
+        let mut graph = DependencyGraph::new(dir.path());
+        graph.build().unwrap();
+        assert_eq!(graph.context_depths(&["app/src/main/java/com/acme/App.java".to_string()]), vec![
+            ("app/src/main/java/com/acme/App.java".to_string(), 0),
+            ("app/src/main/java/com/acme/Config.java".to_string(), 1),
+            ("lib/src/main/kotlin/com/acme/util/Strings.kt".to_string(), 1),
+        ]);
+        // Outside a standard source root, the package says where the root is
+        assert_eq!(graph.dependents(&["legacy/com/acme/legacy/Helper.java".to_string()]), vec!["legacy/com/acme/legacy/Old.java"]);
+    }
+
+    #[test]
+    fn test_stylesheet_refs() {
+        let dir = tempfile::tempdir().unwrap();
+        for (path, content) in [
+            ("src/Button.tsx", "import './Button.scss';\nimport close from './icons/close.svg';\n"),
+            ("src/Button.scss", "@use 'theme/colors';\n.close { background: url(\"./icons/close.svg?v=2\"); }\n.logo { background: url(/logo.png); }\n@import url('https://fonts.example.com/inter.css');\n"),
+            ("src/theme/_colors.scss", ""),
+            ("src/icons/close.svg", "<svg/>"),
+            ("public/logo.png", ""),
+        ] {
+            fs::create_dir_all(dir.path().join(path).parent().unwrap()).unwrap();
+            fs::write(dir.path().join(path), content).unwrap();
+        }
+        assert_eq!(
+            parse_css_refs("@import \"a.css\";\n.x { background: url(b.png#top); }\n.y { mask: url(b.png); }"),
+            ["a.css", "b.png"],
+        );
+
+        let mut graph = DependencyGraph::new(dir.path());
+        graph.build().unwrap();
+        assert_eq!(graph.imports("src/Button.tsx"), ["src/Button.scss", "src/icons/close.svg"]);
+        assert_eq!(graph.imports("src/Button.scss"), ["public/logo.png", "src/icons/close.svg", "src/theme/_colors.scss"]);
+    }
+
+    #[test]
+    fn test_manifests() {
+        let dir = tempfile::tempdir().unwrap();
+        for (path, content) in [
+            ("Cargo.toml", "[workspace]\nmembers = [\"crates/*\"]\n\n[workspace.dependencies]\nserde = \"1\"\n"),
+            ("crates/core/Cargo.toml", "[package]\nname = \"core\"\n\n[dependencies]\nserde.workspace = true\n"),
+            ("crates/core/src/lib.rs", ""),
+            ("crates/cli/Cargo.toml", "[package]\nname = \"cli\"\n\n[dependencies]\ncore = { path = \"../core\" }\n"),
+            ("crates/app/Cargo.toml", "[package]\nname = \"app\"\n\n[target.'cfg(unix)'.dev-dependencies]\nmy-core = { package = \"core\", version = \"0.1\" }\n"),
+            ("web/package.json", r#"{ "name": "web", "dependencies": { "ui": "workspace:*", "react": "18.0.0" } }"#),
+            ("web/src/index.ts", ""),
+            ("packages/ui/package.json", r#"{ "name": "ui" }"#),
+        ] {
+            fs::create_dir_all(dir.path().join(path).parent().unwrap()).unwrap();
+            fs::write(dir.path().join(path), content).unwrap();
+        }
+
+        let mut graph = DependencyGraph::new(dir.path());
+        graph.build().unwrap();
+        assert_eq!(graph.imports("crates/core/src/lib.rs"), ["crates/core/Cargo.toml"]);
+        assert_eq!(graph.imports("crates/core/Cargo.toml"), ["Cargo.toml"]);
+        assert_eq!(graph.importers("crates/core/Cargo.toml"), ["crates/app/Cargo.toml", "crates/cli/Cargo.toml", "crates/core/src/lib.rs"]);
+        assert_eq!(graph.imports("web/src/index.ts"), ["web/package.json"]);
+        assert_eq!(graph.imports("web/package.json"), ["packages/ui/package.json"]);
+    }
+
     #[test]
     fn test_ts_pruning() {
          let content = r#"
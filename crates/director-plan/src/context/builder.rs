@@ -0,0 +1,229 @@
+use std::path::Path;
+use serde::Serialize;
+use crate::config::ContextProfile;
+use crate::context::ast::{DependencyGraph, is_manifest};
+use crate::context::discovery::{discovery_seeds, pair_tests};
+use crate::context::images::is_asset;
+use crate::scope;
+use crate::types::Ticket;
+
+/// Import hops followed from the seed files when the context profile doesn't say.
+pub const DEFAULT_DEPTH: usize = 2;
+
+/// Files this many imports or more from the seeds are pruned to their signatures.
+pub const PRUNED_DEPTH: usize = 2;
+
+/// A file chosen for a ticket's context, and how many imports away from the seeds it is.
+/// Files [`PRUNED_DEPTH`] or more hops out are pruned to their signatures.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ContextFile {
+    pub path: String,
+    pub depth: usize,
+}
+
+/// Works out a ticket's context files in one pass: seeds from its `relevant_files` or its
+/// description, the files they import, test counterparts, and then the profile,
+/// `context_exclude`, `context_prune` and `context_pin`. Nothing is read; see `ContextCache::file_contents`.
+pub struct ContextBuilder<'a> {
+    root: &'a Path,
+    profile: &'a ContextProfile,
+    graph: Option<&'a DependencyGraph>,
+}
+
+impl<'a> ContextBuilder<'a> {
+    pub fn new(root: &'a Path, profile: &'a ContextProfile) -> Self {
+        Self { root, profile, graph: None }
+    }
+
+    /// Follows imports from the seeds through `graph`. Without one only the seeds and their
+    /// tests are used.
+    pub fn with_graph(mut self, graph: Option<&'a DependencyGraph>) -> Self {
+        self.graph = graph;
+        self
+    }
+
+    /// The ticket's listed `relevant_files` first, then what discovery adds, each followed by
+    /// its test counterpart, then pinned files.
+    pub fn select(&self, ticket: &Ticket) -> Vec<ContextFile> {
+        let listed = &ticket.spec.relevant_files;
+        let seeds = if self.profile.discover {
+            self.admit(ticket, discovery_seeds(ticket, self.root))
+        } else {
+            listed.clone()
+        };
+
+        let mut files: Vec<ContextFile> = listed.iter().map(|path| ContextFile { path: path.clone(), depth: 0 }).collect();
+        let reached = match self.graph.filter(|_| self.profile.discover && !seeds.is_empty()) {
+            Some(graph) => graph.context_depths_within(&pair_tests(self.root, &seeds), self.profile.depth.unwrap_or(DEFAULT_DEPTH)),
+            None => seeds.into_iter().map(|path| (path, 0)).collect(),
+        };
+        for (path, depth) in reached {
+            // Images and fonts the graph reaches are named by `referenced_assets` instead
+            if is_asset(&path) && !listed.contains(&path) {
+                continue;
+            }
+            match files.iter_mut().find(|f| f.path == path) {
+                Some(file) => file.depth = file.depth.min(depth),
+                None => files.push(ContextFile { path, depth }),
+            }
+        }
+
+        // Adding a dependency means checking who depends on the package, so the manifests of
+        // dependent packages come along with the ticket's own
+        if let Some(graph) = self.graph {
+            let own: Vec<ContextFile> = files.iter().filter(|f| f.depth <= 1 && is_manifest(&f.path)).cloned().collect();
+            for manifest in own {
+                for dependent in graph.importers(&manifest.path).into_iter().filter(|p| is_manifest(p)) {
+                    if !files.iter().any(|f| f.path == dependent) {
+                        files.push(ContextFile { path: dependent, depth: manifest.depth });
+                    }
+                }
+            }
+        }
+
+        // Tests are the most useful context; each file's counterpart comes in at its depth
+        let paths: Vec<String> = files.iter().map(|f| f.path.clone()).collect();
+        let mut depth = 0;
+        let paired: Vec<ContextFile> = pair_tests(self.root, &paths).into_iter()
+            .map(|path| {
+                if let Some(file) = files.iter().find(|f| f.path == path) {
+                    depth = file.depth;
+                }
+                ContextFile { path, depth }
+            })
+            .collect();
+
+        let admitted = self.admit(ticket, paired.iter().map(|f| f.path.clone()).collect());
+        let mut files: Vec<ContextFile> = paired.into_iter().filter(|f| admitted.contains(&f.path)).collect();
+        for file in files.iter_mut().filter(|f| ticket.spec.context_prune.contains(&f.path)) {
+            file.depth = file.depth.max(PRUNED_DEPTH);
+        }
+        // Pinned files are never pruned, however far out the graph has them
+        for pinned in &ticket.spec.context_pin {
+            match files.iter_mut().find(|f| f.path == *pinned) {
+                Some(file) => file.depth = 0,
+                None if self.root.join(pinned).is_file() => files.push(ContextFile { path: pinned.clone(), depth: 0 }),
+                None => {}
+            }
+        }
+        files
+    }
+
+    /// The assets `files` import or point at through `url()`, in order, that the ticket hasn't
+    /// already got as context files. Empty without a graph.
+    pub fn referenced_assets(&self, ticket: &Ticket, files: &[ContextFile]) -> Vec<String> {
+        let Some(graph) = self.graph else { return Vec::new() };
+        let mut assets: Vec<String> = Vec::new();
+        for file in files {
+            for import in graph.imports(&file.path) {
+                if is_asset(&import) && !assets.contains(&import) && !files.iter().any(|f| f.path == import) {
+                    assets.push(import);
+                }
+            }
+        }
+        self.admit(ticket, assets)
+    }
+
+    /// The `files` the profile lets in and the ticket's `context_exclude` doesn't keep out,
+    /// plus any of them the ticket lists in `relevant_files` or pins.
+    fn admit(&self, ticket: &Ticket, files: Vec<String>) -> Vec<String> {
+        let exclude: Vec<String> = self.profile.exclude.iter().chain(&ticket.spec.context_exclude).cloned().collect();
+        let admitted = match scope::filter(&self.profile.include, &exclude, &files, "context exclude") {
+            Ok(admitted) => admitted,
+            Err(e) => {
+                crate::progress!(">> Context exclusions ignored: {:#}", e);
+                return files;
+            }
+        };
+        files.into_iter()
+            .filter(|f| ticket.spec.relevant_files.contains(f) || ticket.spec.context_pin.contains(f) || admitted.contains(f))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_select_depths() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("lib.rs"), "mod a;").unwrap();
+        fs::write(src.join("a.rs"), "mod b;").unwrap();
+        fs::write(src.join("b.rs"), "mod c;").unwrap();
+        fs::write(src.join("c.rs"), "").unwrap();
+        let mut graph = DependencyGraph::new(dir.path());
+        graph.build().unwrap();
+
+        let mut ticket: Ticket = toml_edit::de::from_str(
+            "[meta]\nid = \"T-001\"\ntitle = \"t\"\nstatus = \"todo\"\npriority = \"low\"\n\n[spec]\ndescription = \"\"\nrelevant_files = [\"src/lib.rs\"]\nauto_context = true\n\n[verification]\ncommand = \"true\"\n",
+        ).unwrap();
+        let profile = ContextProfile::default();
+        let select = |ticket: &Ticket| -> Vec<(String, usize)> {
+            ContextBuilder::new(dir.path(), &profile).with_graph(Some(&graph)).select(ticket)
+                .into_iter().map(|f| (f.path, f.depth)).collect()
+        };
+        let depths = |pairs: &[(&str, usize)]| pairs.iter().map(|&(p, d)| (p.to_string(), d)).collect::<Vec<_>>();
+
+        assert_eq!(select(&ticket), depths(&[("src/lib.rs", 0), ("src/a.rs", 1), ("src/b.rs", 2)]));
+
+        // A pin comes in whole even beyond the depth, and an exclusion drops what the graph found
+        ticket.spec.context_pin = vec!["src/c.rs".to_string()];
+        ticket.spec.context_exclude = vec!["src/a.rs".to_string()];
+        assert_eq!(select(&ticket), depths(&[("src/lib.rs", 0), ("src/b.rs", 2), ("src/c.rs", 0)]));
+
+        // Without a graph only the seeds count
+        let files = ContextBuilder::new(dir.path(), &profile).select(&ticket);
+        assert_eq!(files.iter().map(|f| f.path.as_str()).collect::<Vec<_>>(), ["src/lib.rs", "src/c.rs"]);
+    }
+
+    #[test]
+    fn test_dependent_manifests() {
+        let dir = tempfile::tempdir().unwrap();
+        for (path, content) in [
+            ("crates/core/Cargo.toml", "[package]\nname = \"core\"\n"),
+            ("crates/core/src/lib.rs", ""),
+            ("crates/cli/Cargo.toml", "[package]\nname = \"cli\"\n\n[dependencies]\ncore = { path = \"../core\" }\n"),
+            ("crates/cli/src/main.rs", ""),
+        ] {
+            fs::create_dir_all(dir.path().join(path).parent().unwrap()).unwrap();
+            fs::write(dir.path().join(path), content).unwrap();
+        }
+        let mut graph = DependencyGraph::new(dir.path());
+        graph.build().unwrap();
+
+        let ticket: Ticket = toml_edit::de::from_str(
+            "[meta]\nid = \"T-001\"\ntitle = \"t\"\nstatus = \"todo\"\npriority = \"low\"\n\n[spec]\ndescription = \"\"\nrelevant_files = [\"crates/core/src/lib.rs\"]\nauto_context = true\n\n[verification]\ncommand = \"true\"\n",
+        ).unwrap();
+        let profile = ContextProfile::default();
+        let files = ContextBuilder::new(dir.path(), &profile).with_graph(Some(&graph)).select(&ticket);
+        assert_eq!(files, [
+            ContextFile { path: "crates/core/src/lib.rs".to_string(), depth: 0 },
+            ContextFile { path: "crates/core/Cargo.toml".to_string(), depth: 1 },
+            ContextFile { path: "crates/cli/Cargo.toml".to_string(), depth: 1 },
+        ]);
+    }
+
+    #[test]
+    fn test_referenced_assets() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src/icons")).unwrap();
+        fs::write(dir.path().join("src/Button.tsx"), "import './Button.css';\nimport close from './icons/close.svg';\n").unwrap();
+        fs::write(dir.path().join("src/Button.css"), ".close { background: url(./icons/close.svg); }").unwrap();
+        fs::write(dir.path().join("src/icons/close.svg"), "<svg/>").unwrap();
+        let mut graph = DependencyGraph::new(dir.path());
+        graph.build().unwrap();
+
+        let ticket: Ticket = toml_edit::de::from_str(
+            "[meta]\nid = \"T-001\"\ntitle = \"t\"\nstatus = \"todo\"\npriority = \"low\"\n\n[spec]\ndescription = \"\"\nrelevant_files = [\"src/Button.tsx\"]\nauto_context = true\n\n[verification]\ncommand = \"true\"\n",
+        ).unwrap();
+        let profile = ContextProfile::default();
+        let builder = ContextBuilder::new(dir.path(), &profile).with_graph(Some(&graph));
+        let files = builder.select(&ticket);
+        assert_eq!(files.iter().map(|f| f.path.as_str()).collect::<Vec<_>>(), ["src/Button.tsx", "src/Button.css"]);
+        assert_eq!(builder.referenced_assets(&ticket, &files), ["src/icons/close.svg"]);
+    }
+}
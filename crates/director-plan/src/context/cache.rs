@@ -0,0 +1,206 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::context::ast::{DependencyGraph, prune_content};
+use crate::context::builder::{ContextBuilder, ContextFile, PRUNED_DEPTH};
+use crate::config::ContextProfile;
+use crate::scope;
+use crate::types::Ticket;
+
+/// The context for one run, kept across its retries: the dependency graph, the discovered
+/// file list and every file's content. Before each prompt [`invalidate_changes`](Self::invalidate_changes)
+/// drops only what the agent modified, so a retry re-reads a handful of files instead of
+/// re-parsing the workspace.
+pub struct ContextCache {
+    root: PathBuf,
+    base_commit: Option<String>,
+    graph: Option<DependencyGraph>,
+    graph_failed: bool,
+    selection: Option<Vec<ContextFile>>,
+    /// `None` for files that don't exist
+    contents: HashMap<String, Option<String>>,
+    /// Everything the agent has changed so far this run. Re-read on every refresh, since a
+    /// file put back to its base version doesn't show up as changed any more.
+    touched: HashSet<String>,
+    /// The ticket's `[context.profiles]` entry; the default lets everything in
+    profile: ContextProfile,
+    /// `[context] include_paths`, for resolving C/C++ `#include`s
+    include_paths: Vec<String>,
+}
+
+impl ContextCache {
+    /// Changes are measured against `base_commit`, or HEAD when the run has none.
+    pub fn new(root: &Path, base_commit: Option<String>) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            base_commit,
+            graph: None,
+            graph_failed: false,
+            selection: None,
+            contents: HashMap::new(),
+            touched: HashSet::new(),
+            profile: ContextProfile::default(),
+            include_paths: Vec::new(),
+        }
+    }
+
+    /// Narrows discovery to what `profile` lets in.
+    pub fn with_profile(mut self, profile: ContextProfile) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// Resolves C/C++ `#include`s against `include_paths` as well.
+    pub fn with_include_paths(mut self, include_paths: Vec<String>) -> Self {
+        self.include_paths = include_paths;
+        self
+    }
+
+    /// Forgets the cached content of every file the agent has changed and re-reads their
+    /// imports into the graph. Returns how many files that was. If git can't say what
+    /// changed, the whole cache is dropped.
+    pub fn invalidate_changes(&mut self) -> usize {
+        let base = self.base_commit.as_deref().unwrap_or("HEAD");
+        match scope::changed_paths(&self.root, base) {
+            Ok(changed) => self.touched.extend(changed),
+            Err(e) => {
+                crate::progress!(">> Context cache cleared: {}", e);
+                self.graph = None;
+                self.graph_failed = false;
+                self.contents.clear();
+                return 0;
+            }
+        }
+        for path in &self.touched {
+            self.contents.remove(path);
+            if let Some(graph) = &mut self.graph {
+                graph.update_file(path);
+            }
+        }
+        self.touched.len()
+    }
+
+    /// The ticket's context files and their depths, worked out once per run by
+    /// [`ContextBuilder`]. `auto_context` tickets follow imports through the dependency graph
+    /// unless their profile turns discovery off.
+    pub fn context_files(&mut self, ticket: &Ticket) -> Vec<ContextFile> {
+        if let Some(files) = &self.selection {
+            return files.clone();
+        }
+        if ticket.spec.auto_context && self.profile.discover {
+            self.graph();
+        }
+        let files = ContextBuilder::new(&self.root, &self.profile).with_graph(self.graph.as_ref()).select(ticket);
+        self.selection = Some(files.clone());
+        files
+    }
+
+    /// Images and fonts the context files reference, listed in the prompt by name only.
+    pub fn referenced_assets(&mut self, ticket: &Ticket) -> Vec<String> {
+        let files = self.context_files(ticket);
+        ContextBuilder::new(&self.root, &self.profile).with_graph(self.graph.as_ref()).referenced_assets(ticket, &files)
+    }
+
+    /// The paths of [`context_files`](Self::context_files).
+    pub fn relevant_files(&mut self, ticket: &Ticket) -> Vec<String> {
+        self.context_files(ticket).into_iter().map(|f| f.path).collect()
+    }
+
+    /// `(path, content)` for the prompt's context section. Files two or more hops out in the
+    /// graph are pruned to their signatures; the rest are included in full.
+    pub fn file_contents(&mut self, ticket: &Ticket) -> Vec<(String, String)> {
+        let mut results = Vec::new();
+        for file in self.context_files(ticket) {
+            if let Some(content) = self.read(&file.path) {
+                let content = if file.depth < PRUNED_DEPTH { content } else { prune_content(&file.path, &content) };
+                results.push((file.path, content));
+            }
+        }
+        results
+    }
+
+    /// Changes when any of [`file_contents`](Self::file_contents) does, so a resumed run can
+    /// tell whether the context moved on while it was stopped.
+    pub fn content_hash(&mut self, ticket: &Ticket) -> String {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.file_contents(ticket).hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
+
+    /// The dependency graph, built on first use whatever the ticket's `auto_context`. `None`
+    /// if it couldn't be built.
+    pub fn graph(&mut self) -> Option<&DependencyGraph> {
+        if self.graph.is_none() && !self.graph_failed {
+            let mut graph = DependencyGraph::new(&self.root).with_include_paths(self.include_paths.clone());
+            match graph.build() {
+                Ok(()) => self.graph = Some(graph),
+                Err(e) => {
+                    eprintln!("AST Context failed to build, using seeds only: {}", e);
+                    self.graph_failed = true;
+                }
+            }
+        }
+        self.graph.as_ref()
+    }
+
+    fn read(&mut self, path: &str) -> Option<String> {
+        let root = &self.root;
+        self.contents
+            .entry(path.to_string())
+            .or_insert_with(|| fs::read_to_string(root.join(path)).ok())
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_each_file_once() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.rs"), "fn a() {}").unwrap();
+        let mut cache = ContextCache::new(dir.path(), None);
+        assert_eq!(cache.read("a.rs").as_deref(), Some("fn a() {}"));
+        assert_eq!(cache.read("missing.rs"), None);
+
+        fs::write(dir.path().join("a.rs"), "fn b() {}").unwrap();
+        assert_eq!(cache.read("a.rs").as_deref(), Some("fn a() {}"));
+
+        // Not a git repository, so nothing says what changed: everything is dropped
+        cache.invalidate_changes();
+        assert_eq!(cache.read("a.rs").as_deref(), Some("fn b() {}"));
+    }
+
+    #[test]
+    fn test_profile_narrows_discovery() {
+        let dir = tempfile::tempdir().unwrap();
+        for path in ["api/users.rs", "web/users.tsx", "web/users.test.tsx"] {
+            fs::create_dir_all(dir.path().join(path).parent().unwrap()).unwrap();
+            fs::write(dir.path().join(path), "").unwrap();
+        }
+        let ticket: Ticket = toml_edit::de::from_str(
+            "[meta]\nid = \"T-001\"\ntitle = \"t\"\nstatus = \"todo\"\npriority = \"low\"\n\n[spec]\ndescription = \"List users\"\n\n[verification]\ncommand = \"true\"\n",
+        ).unwrap();
+        let files = |profile: ContextProfile| {
+            let mut files = ContextCache::new(dir.path(), None).with_profile(profile).relevant_files(&ticket);
+            files.sort();
+            files
+        };
+
+        assert_eq!(files(ContextProfile::default()), ["api/users.rs", "web/users.test.tsx", "web/users.tsx"]);
+        let frontend = ContextProfile { include: vec!["web/".into()], exclude: vec!["**/*.test.tsx".into()], ..Default::default() };
+        assert_eq!(files(frontend), ["web/users.tsx"]);
+        let docs_only = ContextProfile { discover: false, ..Default::default() };
+        assert!(files(docs_only).is_empty());
+
+        // The ticket's own exclusions apply on top; pins get in regardless
+        let mut ticket = ticket.clone();
+        ticket.spec.context_exclude = vec!["web/**".to_string()];
+        ticket.spec.context_pin = vec!["web/users.test.tsx".to_string(), "missing.rs".to_string()];
+        let mut files = ContextCache::new(dir.path(), None).relevant_files(&ticket);
+        files.sort();
+        assert_eq!(files, ["api/users.rs", "web/users.test.tsx"]);
+    }
+}
@@ -0,0 +1,222 @@
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use anyhow::Result;
+use toml_edit::Array;
+use crate::config::ContextProfile;
+use crate::context::builder::PRUNED_DEPTH;
+use crate::context::cache::ContextCache;
+use crate::context::discovery::{commented_array, heuristic_scores};
+use crate::store::TicketStore;
+use crate::types::Ticket;
+
+/// What happens to one discovered file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Choice {
+    Include,
+    /// In the context as signatures only
+    Prune,
+    Exclude,
+}
+
+impl Choice {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Choice::Include => "include",
+            Choice::Prune => "prune",
+            Choice::Exclude => "exclude",
+        }
+    }
+}
+
+/// A file discovery found for the ticket, and what to do with it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub path: String,
+    pub depth: usize,
+    /// How well its name or content matched the description, for files found that way
+    pub score: Option<u32>,
+    pub bytes: Option<u64>,
+    pub choice: Choice,
+}
+
+/// The ticket's context files as discovery picks them, pinned files aside. Files the prompt
+/// would prune start out pruned, the rest included.
+pub fn candidates(ticket: &Ticket, root: &Path, profile: &ContextProfile, include_paths: &[String]) -> Vec<Candidate> {
+    let scores = if ticket.spec.relevant_files.is_empty() { heuristic_scores(ticket, root) } else { Vec::new() };
+    ContextCache::new(root, None)
+        .with_profile(profile.clone())
+        .with_include_paths(include_paths.to_vec())
+        .context_files(ticket)
+        .into_iter()
+        .filter(|f| !ticket.spec.context_pin.contains(&f.path))
+        .map(|f| Candidate {
+            score: scores.iter().find(|(path, _)| *path == f.path).map(|(_, score)| *score),
+            bytes: fs::metadata(root.join(&f.path)).ok().map(|m| m.len()),
+            choice: if f.depth >= PRUNED_DEPTH { Choice::Prune } else { Choice::Include },
+            path: f.path,
+            depth: f.depth,
+        })
+        .collect()
+}
+
+/// Writes the choices into the ticket: included and pruned files as `relevant_files` (one per
+/// line, with depth and score as comments), pruned ones also as `context_prune`, excluded ones
+/// added to `context_exclude`. `auto_context` is turned off, so execution uses exactly that list.
+pub fn save(ticket: &Ticket, root: &Path, candidates: &[Candidate]) -> Result<()> {
+    let kept: Vec<(String, String)> = candidates.iter()
+        .filter(|c| c.choice != Choice::Exclude)
+        .map(|c| {
+            let comment = match c.score {
+                Some(score) => format!("depth {}, score {}", c.depth, score),
+                None => format!("depth {}", c.depth),
+            };
+            (c.path.clone(), comment)
+        })
+        .collect();
+    let pruned: Vec<&str> = candidates.iter().filter(|c| c.choice == Choice::Prune).map(|c| c.path.as_str()).collect();
+    let mut excluded = ticket.spec.context_exclude.clone();
+    for candidate in candidates.iter().filter(|c| c.choice == Choice::Exclude) {
+        if !excluded.contains(&candidate.path) {
+            excluded.push(candidate.path.clone());
+        }
+    }
+
+    TicketStore::new(root).update(&ticket.meta.id, |doc| {
+        let spec = &mut doc["spec"];
+        spec["relevant_files"] = toml_edit::value(commented_array(&kept));
+        spec["auto_context"] = toml_edit::value(false);
+        if !pruned.is_empty() {
+            spec["context_prune"] = toml_edit::value(pruned.iter().copied().collect::<Array>());
+        } else if let Some(table) = spec.as_table_like_mut() {
+            table.remove("context_prune");
+        }
+        if !excluded.is_empty() {
+            spec["context_exclude"] = toml_edit::value(excluded.iter().map(String::as_str).collect::<Array>());
+        }
+        crate::append_history_entry(doc, &format!(
+            "Context curated: {} file(s) kept, {} of them pruned, {} excluded",
+            kept.len(), pruned.len(), candidates.len() - kept.len(),
+        ));
+        Ok(())
+    })
+}
+
+/// What a line typed during curation asks for.
+#[derive(Debug, PartialEq)]
+enum Step {
+    Continue,
+    Save,
+    Quit,
+}
+
+const HELP: &str = "i, p or x followed by numbers or ranges (e.g. `x 2 4-6`) includes, prunes or excludes those files; s saves to the ticket; q quits without saving";
+
+/// Lists `candidates` on `output` and applies the commands read from `input` until the user
+/// saves (`true`) or quits (`false`, also on end of input).
+pub fn curate<R: BufRead, W: Write>(candidates: &mut [Candidate], mut input: R, mut output: W) -> io::Result<bool> {
+    write!(output, "{}", render(candidates))?;
+    writeln!(output, "{}", HELP)?;
+    loop {
+        write!(output, "> ")?;
+        output.flush()?;
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(false);
+        }
+        match apply(candidates, &line) {
+            Ok(Step::Continue) => write!(output, "{}", render(candidates))?,
+            Ok(Step::Save) => return Ok(true),
+            Ok(Step::Quit) => return Ok(false),
+            Err(e) => writeln!(output, "{}", e)?,
+        }
+    }
+}
+
+fn apply(candidates: &mut [Candidate], line: &str) -> std::result::Result<Step, String> {
+    let mut words = line.split_whitespace();
+    let choice = match words.next() {
+        None => return Ok(Step::Continue),
+        Some("s") => return Ok(Step::Save),
+        Some("q") => return Ok(Step::Quit),
+        Some("i") => Choice::Include,
+        Some("p") => Choice::Prune,
+        Some("x") => Choice::Exclude,
+        Some(_) => return Err(HELP.to_string()),
+    };
+
+    let mut picked = Vec::new();
+    for word in words {
+        let (first, last) = word.split_once('-').unwrap_or((word, word));
+        let (Ok(first), Ok(last)) = (first.parse::<usize>(), last.parse::<usize>()) else {
+            return Err(format!("'{}' isn't a file number or range", word));
+        };
+        if first == 0 || last > candidates.len() || first > last {
+            return Err(format!("'{}' is out of range; files are numbered 1 to {}", word, candidates.len()));
+        }
+        picked.extend(first - 1..last);
+    }
+    if picked.is_empty() {
+        return Err(HELP.to_string());
+    }
+    for i in picked {
+        candidates[i].choice = choice;
+    }
+    Ok(Step::Continue)
+}
+
+fn render(candidates: &[Candidate]) -> String {
+    let mut out = format!("{:>3}  {:<7}  {:>5}  {:>5}  {:>8}  path\n", "#", "choice", "depth", "score", "bytes");
+    for (i, c) in candidates.iter().enumerate() {
+        let score = c.score.map(|s| s.to_string()).unwrap_or_else(|| "-".to_string());
+        let bytes = c.bytes.map(|b| b.to_string()).unwrap_or_else(|| "-".to_string());
+        out.push_str(&format!("{:>3}  {:<7}  {:>5}  {:>5}  {:>8}  {}\n", i + 1, c.choice.as_str(), c.depth, score, bytes, c.path));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(path: &str, depth: usize) -> Candidate {
+        Candidate { path: path.to_string(), depth, score: None, bytes: Some(10), choice: Choice::Include }
+    }
+
+    #[test]
+    fn test_curate() {
+        let mut candidates = vec![candidate("src/a.rs", 0), candidate("src/b.rs", 1), candidate("src/c.rs", 1)];
+        let mut output = Vec::new();
+        let saved = curate(&mut candidates, "x 2\nz\np 9\np 1-1 3\ns\n".as_bytes(), &mut output).unwrap();
+        assert!(saved);
+        let choices: Vec<Choice> = candidates.iter().map(|c| c.choice).collect();
+        assert_eq!(choices, [Choice::Prune, Choice::Exclude, Choice::Prune]);
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("'9' is out of range; files are numbered 1 to 3"));
+        assert!(output.contains("  2  exclude      1      -        10  src/b.rs"));
+
+        assert!(!curate(&mut candidates, "".as_bytes(), Vec::new()).unwrap());
+    }
+
+    #[test]
+    fn test_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = TicketStore::new(dir.path());
+        fs::create_dir_all(store.tickets_dir()).unwrap();
+        fs::write(store.ticket_path("T-1"), "[meta]\nid = \"T-1\"\ntitle = \"t\"\nstatus = \"todo\"\npriority = \"low\"\n\n[spec]\ndescription = \"\"\nauto_context = true\ncontext_exclude = [\"gen/**\"]\n\n[verification]\ncommand = \"true\"\n").unwrap();
+        let ticket = store.load("T-1").unwrap();
+
+        let mut candidates = vec![candidate("src/a.rs", 0), candidate("src/b.rs", 2), candidate("src/c.rs", 1)];
+        candidates[0].score = Some(21);
+        candidates[1].choice = Choice::Prune;
+        candidates[2].choice = Choice::Exclude;
+        save(&ticket, dir.path(), &candidates).unwrap();
+
+        let content = fs::read_to_string(store.ticket_path("T-1")).unwrap();
+        assert!(content.contains("relevant_files = [\n    \"src/a.rs\", # depth 0, score 21\n    \"src/b.rs\", # depth 2\n]"), "{}", content);
+        let ticket = store.load("T-1").unwrap();
+        assert!(!ticket.spec.auto_context);
+        assert_eq!(ticket.spec.context_prune, ["src/b.rs"]);
+        assert_eq!(ticket.spec.context_exclude, ["gen/**", "src/c.rs"]);
+    }
+}
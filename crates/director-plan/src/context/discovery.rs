@@ -1,41 +1,173 @@
 use std::collections::HashSet;
 use std::fs;
 use std::path::{Path, PathBuf};
+use anyhow::Result;
+use toml_edit::{Array, Value};
 use walkdir::WalkDir;
 use crate::types::Ticket;
+use crate::config::ContextProfile;
+use crate::context::builder::ContextFile;
+use crate::context::cache::ContextCache;
+use crate::context::usage::LearnedPaths;
+use crate::runs::RunStore;
+use crate::store::TicketStore;
 
-/// Discovers relevant files based on the ticket description.
-pub fn discover_context(ticket: &Ticket, root: &Path) -> Vec<String> {
-    // If auto_context is enabled, we use AST Engine but seeded by heuristics.
-    // The dead code block previously here is removed.
+/// Discovers relevant files based on the ticket description, within `profile`.
+/// `include_paths` are `[context] include_paths`, for C/C++ sources.
+pub fn discover_context(ticket: &Ticket, root: &Path, profile: &ContextProfile, include_paths: &[String]) -> Vec<String> {
+    ContextCache::new(root, None).with_profile(profile.clone()).with_include_paths(include_paths.to_vec()).relevant_files(ticket)
+}
+
+/// Runs discovery once and writes what it finds into the ticket's `relevant_files`, one file
+/// per line with its depth (and, for description matches, its score) as a comment, so the list
+/// can be reviewed and trimmed by hand. `auto_context` is turned off, so execution uses exactly
+/// that list. Pinned files stay in `context_pin`. Returns the files written.
+pub fn write_context(ticket: &Ticket, root: &Path, profile: &ContextProfile, include_paths: &[String]) -> Result<Vec<ContextFile>> {
+    let files: Vec<ContextFile> = ContextCache::new(root, None)
+        .with_profile(profile.clone())
+        .with_include_paths(include_paths.to_vec())
+        .context_files(ticket)
+        .into_iter()
+        .filter(|f| !ticket.spec.context_pin.contains(&f.path))
+        .collect();
+    let scores = if ticket.spec.relevant_files.is_empty() { heuristic_scores(ticket, root) } else { Vec::new() };
 
-    let mut seeds = ticket.spec.relevant_files.clone();
+    let entries: Vec<(String, String)> = files.iter().map(|file| {
+        let comment = match scores.iter().find(|(path, _)| *path == file.path) {
+            Some((_, score)) => format!("depth {}, score {}", file.depth, score),
+            None => format!("depth {}", file.depth),
+        };
+        (file.path.clone(), comment)
+    }).collect();
+    TicketStore::new(root).update(&ticket.meta.id, |doc| {
+        doc["spec"]["relevant_files"] = toml_edit::value(commented_array(&entries));
+        doc["spec"]["auto_context"] = toml_edit::value(false);
+        crate::append_history_entry(doc, &format!("Context written to relevant_files: {} file(s)", entries.len()));
+        Ok(())
+    })?;
+    Ok(files)
+}
 
-    // 1. Heuristic Discovery (run if seeds are empty)
+/// A multi-line TOML array of the paths, each followed by `# comment`.
+pub(crate) fn commented_array(entries: &[(String, String)]) -> Array {
+    let mut array = Array::new();
+    for (i, (path, _)) in entries.iter().enumerate() {
+        let mut value = Value::from(path.as_str());
+        let prefix = match i {
+            0 => "\n    ".to_string(),
+            _ => format!(" # {}\n    ", entries[i - 1].1),
+        };
+        value.decor_mut().set_prefix(prefix);
+        array.push_formatted(value);
+    }
+    if let Some((_, comment)) = entries.last() {
+        array.set_trailing_comma(true);
+        array.set_trailing(format!(" # {}\n", comment));
+    }
+    array
+}
+
+/// The ticket's `relevant_files`, or the heuristic matches for its description when it has none.
+pub fn discovery_seeds(ticket: &Ticket, root: &Path) -> Vec<String> {
+    let seeds = ticket.spec.relevant_files.clone();
     if seeds.is_empty() {
-        seeds = heuristic_discovery(ticket, root);
+        return heuristic_discovery(ticket, root);
     }
+    seeds
+}
 
-    // 2. AST Expansion (if auto_context is true)
-    if ticket.spec.auto_context && !seeds.is_empty() {
-        let mut graph = crate::context::ast::DependencyGraph::new(root);
-        if let Ok(_) = graph.build() {
-            // Get context expands the graph from seeds
-            let context_data = graph.get_context(&seeds);
-            // Return only paths. Pruning of content happens in execution_loop if it uses get_context again.
-            // Or ideally execution_loop should rely on this function returning paths, but it re-reads them.
-            // To get pruning benefit, execution_loop logic was updated to use AST directly.
-            // This function supports the CLI 'context' command mainly now.
-            return context_data.into_iter().map(|(p, _)| p).collect();
-        } else {
-             eprintln!("AST Context failed to build, using seeds only.");
+/// Adds the test file for each source file and the implementation for each test file,
+/// right after it, when they exist. Rust tests in the same file (`#[cfg(test)]`) come along
+/// with the source already.
+pub fn pair_tests(root: &Path, files: &[String]) -> Vec<String> {
+    let mut paired: Vec<String> = Vec::new();
+    for file in files {
+        if !paired.contains(file) {
+            paired.push(file.clone());
+        }
+        for counterpart in counterparts(file) {
+            if !paired.contains(&counterpart) && !files.contains(&counterpart) && root.join(&counterpart).is_file() {
+                paired.push(counterpart);
+            }
         }
     }
+    paired
+}
 
-    seeds
+/// Conventional test locations for a source file, or source locations for a test file.
+fn counterparts(file: &str) -> Vec<String> {
+    let (dir, name) = match file.rsplit_once('/') {
+        Some((dir, name)) => (format!("{}/", dir), name),
+        None => (String::new(), file),
+    };
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some(parts) => parts,
+        None => return vec![],
+    };
+
+    match ext {
+        "ts" | "tsx" | "js" | "jsx" => {
+            // foo.test.ts / foo.spec.ts / __tests__/foo.test.ts -> foo.ts
+            let base = stem.strip_suffix(".test").or_else(|| stem.strip_suffix(".spec"));
+            if let Some(base) = base {
+                let mut sources = vec![format!("{}{}.{}", dir, base, ext)];
+                if let Some(parent) = dir.strip_suffix("__tests__/") {
+                    sources.push(format!("{}{}.{}", parent, base, ext));
+                }
+                return sources;
+            }
+            if let Some(parent) = dir.strip_suffix("__tests__/") {
+                return vec![format!("{}{}.{}", parent, stem, ext)];
+            }
+            vec![
+                format!("{}{}.test.{}", dir, stem, ext),
+                format!("{}{}.spec.{}", dir, stem, ext),
+                format!("{}__tests__/{}.test.{}", dir, stem, ext),
+                format!("{}__tests__/{}.{}", dir, stem, ext),
+            ]
+        }
+        "rs" => {
+            // crate/tests/foo.rs <-> crate/src/foo.rs, and src/foo/tests.rs <-> src/foo.rs
+            if let Some(krate) = dir.strip_suffix("tests/").filter(|d| d.is_empty() || d.ends_with('/')) {
+                return vec![format!("{}src/{}.rs", krate, stem), format!("{}src/{}/mod.rs", krate, stem)];
+            }
+            if stem == "tests" {
+                let module = dir.trim_end_matches('/');
+                return vec![format!("{}.rs", module), format!("{}/mod.rs", module)];
+            }
+            let (krate, module) = match dir.rsplit_once("src/") {
+                Some((krate, rest)) if stem == "mod" => (krate.to_string(), rest.trim_end_matches('/').to_string()),
+                Some((krate, rest)) => (krate.to_string(), format!("{}{}", rest, stem)),
+                None => return vec![],
+            };
+            let module_dir = if stem == "mod" { dir.trim_end_matches('/').to_string() } else { format!("{}{}", dir, stem) };
+            let test_name = module.rsplit('/').next().unwrap_or(&module);
+            vec![format!("{}tests/{}.rs", krate, test_name), format!("{}/tests.rs", module_dir)]
+        }
+        "py" => {
+            if let Some(base) = stem.strip_prefix("test_") {
+                let mut sources = vec![format!("{}{}.py", dir, base)];
+                if let Some(parent) = dir.strip_suffix("tests/") {
+                    sources.push(format!("{}{}.py", parent, base));
+                    sources.push(format!("{}src/{}.py", parent, base));
+                }
+                return sources;
+            }
+            vec![format!("{}test_{}.py", dir, stem), format!("{}tests/test_{}.py", dir, stem), format!("tests/test_{}.py", stem)]
+        }
+        _ => vec![],
+    }
 }
 
 fn heuristic_discovery(ticket: &Ticket, root: &Path) -> Vec<String> {
+    heuristic_scores(ticket, root).into_iter().map(|(path, _)| path).collect()
+}
+
+/// Files whose name or content shares words with the ticket's description, best first, with
+/// their scores: 10 per word in the name, 1 per word only in the content. Files that earlier
+/// successful runs changed alongside name matches in the same directory score more, and come
+/// in on their own once two runs have changed them.
+pub(crate) fn heuristic_scores(ticket: &Ticket, root: &Path) -> Vec<(String, u32)> {
     let tokens = tokenize(&ticket.spec.description);
     if tokens.is_empty() {
         return vec![];
@@ -52,6 +184,7 @@ fn heuristic_discovery(ticket: &Ticket, root: &Path) -> Vec<String> {
         "package-lock.json",
         "yarn.lock",
         "assets/",
+        ".director/",
     ];
 
     for entry in WalkDir::new(root)
@@ -105,11 +238,24 @@ fn heuristic_discovery(ticket: &Ticket, root: &Path) -> Vec<String> {
         }
     }
 
+    // Files earlier runs had to change when working where the names matched
+    let matched: Vec<String> = scored_files.iter().filter(|(_, score)| *score >= 10).map(|(path, _)| path.clone()).collect();
+    if !matched.is_empty() {
+        let runs = RunStore::new(root).list_all().unwrap_or_default();
+        for (path, weight, standalone) in LearnedPaths::from_runs(&runs).weights(&matched) {
+            match scored_files.iter_mut().find(|(p, _)| *p == path) {
+                Some((_, score)) => *score += weight,
+                None if standalone && root.join(&path).is_file() => scored_files.push((path, weight)),
+                None => {}
+            }
+        }
+    }
+
     scored_files.sort_by(|a, b| b.1.cmp(&a.1));
-    scored_files.into_iter().map(|(path, _)| path).collect()
+    scored_files
 }
 
-fn tokenize(text: &str) -> HashSet<String> {
+pub(crate) fn tokenize(text: &str) -> HashSet<String> {
     let stop_words: HashSet<&str> = [
         "the", "and", "a", "an", "to", "in", "of", "for", "with", "on", "at",
         "by", "from", "up", "about", "into", "over", "after", "implement", "update",
@@ -129,3 +275,41 @@ fn tokenize(text: &str) -> HashSet<String> {
         .filter(|s| !s.is_empty() && !stop_words.contains(s.as_str()))
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counterparts() {
+        assert!(counterparts("src/app/button.tsx").contains(&"src/app/button.test.tsx".to_string()));
+        assert!(counterparts("src/app/button.tsx").contains(&"src/app/__tests__/button.test.tsx".to_string()));
+        assert_eq!(counterparts("src/app/__tests__/button.test.tsx"), vec!["src/app/__tests__/button.tsx", "src/app/button.tsx"]);
+        assert_eq!(counterparts("crates/core/src/node/text.rs"), vec!["crates/core/tests/text.rs", "crates/core/src/node/text/tests.rs"]);
+        assert_eq!(counterparts("crates/core/tests/text.rs"), vec!["crates/core/src/text.rs", "crates/core/src/text/mod.rs"]);
+        assert_eq!(counterparts("crates/core/src/node/tests.rs"), vec!["crates/core/src/node.rs", "crates/core/src/node/mod.rs"]);
+        assert!(counterparts("pkg/tests/test_api.py").contains(&"pkg/api.py".to_string()));
+        assert!(counterparts("README.md").is_empty());
+    }
+
+    #[test]
+    fn test_write_context() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = TicketStore::new(dir.path());
+        fs::create_dir_all(store.tickets_dir()).unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/lib.rs"), "mod a;").unwrap();
+        fs::write(dir.path().join("src/a.rs"), "").unwrap();
+        fs::write(dir.path().join("src/schema.rs"), "").unwrap();
+        fs::write(store.ticket_path("T-1"), "[meta]\nid = \"T-1\"\ntitle = \"t\"\nstatus = \"todo\"\npriority = \"low\"\n\n[spec]\ndescription = \"\"\nrelevant_files = [\"src/lib.rs\"]\nauto_context = true\ncontext_pin = [\"src/schema.rs\"]\n\n[verification]\ncommand = \"true\"\n").unwrap();
+        let ticket = store.load("T-1").unwrap();
+
+        let written = write_context(&ticket, dir.path(), &ContextProfile::default(), &[]).unwrap();
+        assert_eq!(written.iter().map(|f| f.path.as_str()).collect::<Vec<_>>(), ["src/lib.rs", "src/a.rs"]);
+        let content = fs::read_to_string(store.ticket_path("T-1")).unwrap();
+        assert!(content.contains("relevant_files = [\n    \"src/lib.rs\", # depth 0\n    \"src/a.rs\", # depth 1\n]"), "{}", content);
+        let ticket = store.load("T-1").unwrap();
+        assert!(!ticket.spec.auto_context);
+        assert_eq!(ticket.spec.context_pin, ["src/schema.rs"]);
+    }
+}
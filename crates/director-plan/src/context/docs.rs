@@ -0,0 +1,149 @@
+use std::fs;
+use std::path::Path;
+use walkdir::WalkDir;
+use crate::context::discovery::tokenize;
+use crate::types::Ticket;
+
+/// At most this many sections go into the prompt, best matches first.
+const MAX_SECTIONS: usize = 5;
+/// Longer sections are cut off here.
+const MAX_SECTION_LINES: usize = 80;
+/// A section needs this score to be included, so a single common word doesn't drag one in.
+const MIN_SCORE: usize = 2;
+
+const IGNORED_DIRS: [&str; 7] = ["target", "node_modules", ".git", "dist", "build", ".director", "plan"];
+
+/// A heading and its text from a markdown file under `docs/` or a README.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocSection {
+    pub path: String,
+    pub heading: String,
+    pub body: String,
+}
+
+/// A "## Project Documentation" prompt section with the doc sections that best match the
+/// ticket's title and description. Empty when nothing matches.
+pub fn render_docs(ticket: &Ticket, root: &Path) -> String {
+    let sections = relevant_sections(ticket, root);
+    if sections.is_empty() {
+        return String::new();
+    }
+
+    let mut out = String::from("## Project Documentation\n");
+    for section in sections {
+        out.push_str(&format!("\n### {} › {}\n{}\n", section.path, section.heading, section.body.trim_end()));
+    }
+    out
+}
+
+/// Sections of `docs/**/*.md` and README files scored by ticket tokens: a token in the
+/// heading counts three times, in the body once.
+pub fn relevant_sections(ticket: &Ticket, root: &Path) -> Vec<DocSection> {
+    let tokens = tokenize(&format!("{} {}", ticket.meta.title, ticket.spec.description));
+    if tokens.is_empty() {
+        return vec![];
+    }
+
+    let mut scored: Vec<(usize, DocSection)> = Vec::new();
+    for path in doc_files(root) {
+        let content = match fs::read_to_string(root.join(&path)) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+        for section in split_sections(&path, &content) {
+            let heading = section.heading.to_lowercase();
+            let body = section.body.to_lowercase();
+            let score: usize = tokens.iter()
+                .map(|token| if heading.contains(token.as_str()) { 3 } else if body.contains(token.as_str()) { 1 } else { 0 })
+                .sum();
+            if score >= MIN_SCORE {
+                scored.push((score, section));
+            }
+        }
+    }
+
+    scored.sort_by_key(|b| std::cmp::Reverse(b.0));
+    scored.into_iter().take(MAX_SECTIONS).map(|(_, section)| section).collect()
+}
+
+fn doc_files(root: &Path) -> Vec<String> {
+    let mut files: Vec<String> = WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !IGNORED_DIRS.contains(&e.file_name().to_string_lossy().as_ref()))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| e.path().strip_prefix(root).ok().map(|p| p.to_string_lossy().replace('\\', "/")))
+        .filter(|path| {
+            let name = path.rsplit('/').next().unwrap_or(path);
+            let in_docs = path.starts_with("docs/") && (name.ends_with(".md") || name.ends_with(".mdx"));
+            in_docs || name.to_lowercase().starts_with("readme")
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Splits markdown at headings. Text before the first heading is kept under the file's name.
+fn split_sections(path: &str, content: &str) -> Vec<DocSection> {
+    let mut sections = Vec::new();
+    let mut heading = path.rsplit('/').next().unwrap_or(path).to_string();
+    let mut body: Vec<&str> = Vec::new();
+    let mut in_code = false;
+
+    for line in content.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+        }
+        if !in_code && line.starts_with('#') {
+            push_section(&mut sections, path, &heading, &body);
+            heading = line.trim_start_matches('#').trim().to_string();
+            body.clear();
+            continue;
+        }
+        body.push(line);
+    }
+    push_section(&mut sections, path, &heading, &body);
+    sections
+}
+
+fn push_section(sections: &mut Vec<DocSection>, path: &str, heading: &str, body: &[&str]) {
+    if body.iter().all(|line| line.trim().is_empty()) {
+        return;
+    }
+    let mut text = body.iter().take(MAX_SECTION_LINES).copied().collect::<Vec<_>>().join("\n");
+    if body.len() > MAX_SECTION_LINES {
+        text.push_str(&format!("\n... ({} more lines)", body.len() - MAX_SECTION_LINES));
+    }
+    sections.push(DocSection {
+        path: path.to_string(),
+        heading: heading.to_string(),
+        body: text.trim().to_string(),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relevant_sections() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::write(
+            root.join("docs/rendering.md"),
+            "# Rendering\nIntro.\n\n## Text Shadows\nShadows use SkParagraph.\n```rust\n# not a heading\n```\n\n## Colors\nPalette notes.\n",
+        ).unwrap();
+        fs::write(root.join("README.md"), "# Project\nNothing about it.\n").unwrap();
+
+        let ticket: Ticket = toml_edit::de::from_str(
+            "[meta]\nid = \"T-1\"\ntitle = \"Text shadows\"\nstatus = \"todo\"\npriority = \"high\"\n[spec]\ndescription = \"Draw shadows with SkParagraph\"\n[verification]\ncommand = \"true\"\n",
+        ).unwrap();
+
+        let sections = relevant_sections(&ticket, root);
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].heading, "Text Shadows");
+        assert!(sections[0].body.contains("# not a heading"));
+        assert!(render_docs(&ticket, root).starts_with("## Project Documentation\n"));
+    }
+}
@@ -0,0 +1,106 @@
+use std::path::Path;
+use std::process::Command;
+use anyhow::{Context, Result, anyhow};
+use serde::Serialize;
+
+/// Diffs longer than this are summarized instead of pasted into the prompt.
+const MAX_DIFF_LINES: usize = 40;
+
+const RECORD_SEP: char = '\u{1e}';
+const FIELD_SEP: char = '\u{1f}';
+
+/// A recent commit touching a context file.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct FileCommit {
+    pub sha: String,
+    pub subject: String,
+    /// The change to this file, when it's small enough to include.
+    pub diff: Option<String>,
+    pub diff_lines: usize,
+}
+
+/// The last `depth` commits that touched `path`, newest first.
+pub fn file_history(root: &Path, path: &str, depth: usize) -> Result<Vec<FileCommit>> {
+    let output = Command::new("git")
+        .current_dir(root)
+        .args(["log", "-n", &depth.to_string(), "--no-color", "-p", "--format=%x1e%h%x1f%s", "--", path])
+        .output()
+        .context("Failed to run git log")?;
+    if !output.status.success() {
+        return Err(anyhow!("git log failed for {}: {}", path, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(parse_log(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// A "## Recent Changes" prompt section for `files`, so the agent knows why they changed
+/// lately and doesn't undo fresh work. Files without history are left out.
+pub fn render_history(root: &Path, files: &[String], depth: usize) -> String {
+    let mut out = String::new();
+    for file in files {
+        let commits = match file_history(root, file, depth) {
+            Ok(commits) if !commits.is_empty() => commits,
+            _ => continue,
+        };
+        out.push_str(&format!("\n### {}\n", file));
+        for commit in commits {
+            out.push_str(&format!("- {} {}\n", commit.sha, commit.subject));
+            match &commit.diff {
+                Some(diff) => out.push_str(&format!("```diff\n{}\n```\n", diff)),
+                None if commit.diff_lines > 0 => out.push_str(&format!("  (diff of {} lines omitted)\n", commit.diff_lines)),
+                None => {}
+            }
+        }
+    }
+
+    if out.is_empty() {
+        return out;
+    }
+    format!("## Recent Changes\n{}", out)
+}
+
+fn parse_log(log: &str) -> Vec<FileCommit> {
+    log.split(RECORD_SEP)
+        .filter(|record| !record.trim().is_empty())
+        .filter_map(|record| {
+            let (header, patch) = record.split_once('\n').unwrap_or((record, ""));
+            let (sha, subject) = header.split_once(FIELD_SEP)?;
+
+            // Drop the `diff --git`/`index`/`---`/`+++` preamble, keep the hunks
+            let hunks: Vec<&str> = patch.lines()
+                .skip_while(|line| !line.starts_with("@@"))
+                .collect();
+            let diff_lines = hunks.len();
+            let diff = (diff_lines > 0 && diff_lines <= MAX_DIFF_LINES).then(|| hunks.join("\n"));
+
+            Some(FileCommit {
+                sha: sha.to_string(),
+                subject: subject.to_string(),
+                diff,
+                diff_lines,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_log() {
+        let big: String = (0..50).map(|i| format!("+line {}\n", i)).collect();
+        let log = format!(
+            "\u{1e}abc1234\u{1f}fix: keep shadows\n\ndiff --git a/x.rs b/x.rs\nindex 1..2 100644\n--- a/x.rs\n+++ b/x.rs\n@@ -1 +1 @@\n-old\n+new\n\
+             \u{1e}def5678\u{1f}feat: add x\n\ndiff --git a/x.rs b/x.rs\n--- /dev/null\n+++ b/x.rs\n@@ -0,0 +1,50 @@\n{}",
+            big
+        );
+
+        let commits = parse_log(&log);
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[0].sha, "abc1234");
+        assert_eq!(commits[0].subject, "fix: keep shadows");
+        assert_eq!(commits[0].diff.as_deref(), Some("@@ -1 +1 @@\n-old\n+new"));
+        assert_eq!(commits[1].diff, None);
+        assert_eq!(commits[1].diff_lines, 51);
+    }
+}
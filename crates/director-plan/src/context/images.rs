@@ -0,0 +1,131 @@
+use std::fs;
+use std::path::Path;
+use base64::Engine;
+use crate::config::ImageMode;
+use crate::types::Ticket;
+
+/// Images bigger than this are referenced by path even in `base64` mode.
+const MAX_INLINE_BYTES: u64 = 5 * 1024 * 1024;
+
+/// The ticket's assets that are images, as workspace-relative paths. Missing files are left out.
+pub fn image_assets(ticket: &Ticket, root: &Path) -> Vec<String> {
+    ticket.spec.assets.iter()
+        .map(|name| format!("assets/{}", name))
+        .filter(|path| is_image(path) && root.join(path).is_file())
+        .collect()
+}
+
+fn is_image(path: &str) -> bool {
+    mime_guess::from_path(path).first().is_some_and(|mime| mime.type_() == "image")
+}
+
+/// Images, fonts and media: files a stylesheet or component can point at, but whose content
+/// is no use in a prompt.
+pub fn is_asset(path: &str) -> bool {
+    mime_guess::from_path(path).first().is_some_and(|mime| ["image", "font", "audio", "video"].contains(&mime.type_().as_str()))
+}
+
+/// The prompt's **Referenced Assets** section: the assets the context files point at, by
+/// path and size, so the agent knows they exist without reading them.
+pub fn render_referenced(root: &Path, assets: &[String]) -> String {
+    if assets.is_empty() {
+        return String::new();
+    }
+    let mut out = String::from("## Referenced Assets\nUsed by the context files above; listed by name only.\n");
+    for path in assets {
+        match fs::metadata(root.join(path)) {
+            Ok(meta) => out.push_str(&format!("- {} ({} bytes)\n", path, meta.len())),
+            Err(_) => out.push_str(&format!("- {}\n", path)),
+        }
+    }
+    out.push('\n');
+    out
+}
+
+/// The prompt's **Attached Assets** and **Design Mockups** sections. Images are listed by path,
+/// or inlined as base64 data URIs for agents that take images in the prompt.
+pub fn render_assets(ticket: &Ticket, root: &Path, mode: ImageMode) -> String {
+    let images = image_assets(ticket, root);
+    let others: Vec<String> = ticket.spec.assets.iter()
+        .map(|name| format!("assets/{}", name))
+        .filter(|path| !images.contains(path))
+        .collect();
+
+    let mut out = String::new();
+    if !others.is_empty() {
+        out.push_str("## Attached Assets\n");
+        for path in &others {
+            out.push_str(&format!("- {}\n", path));
+        }
+        out.push('\n');
+    }
+    if images.is_empty() || mode == ImageMode::None {
+        return out;
+    }
+
+    out.push_str("## Design Mockups\nThe intended result. Match it as closely as you can.\n");
+    for path in &images {
+        match inline_image(root, path).filter(|_| mode == ImageMode::Base64) {
+            Some(data_uri) => out.push_str(&format!("![{}]({})\n", path, data_uri)),
+            None => out.push_str(&format!("- {}\n", path)),
+        }
+    }
+    out.push('\n');
+    out
+}
+
+fn inline_image(root: &Path, path: &str) -> Option<String> {
+    let full = root.join(path);
+    if fs::metadata(&full).ok()?.len() > MAX_INLINE_BYTES {
+        return None;
+    }
+    let bytes = fs::read(&full).ok()?;
+    let mime = mime_guess::from_path(path).first()?;
+    Some(format!("data:{};base64,{}", mime, base64::engine::general_purpose::STANDARD.encode(bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ticket(assets: &[&str]) -> Ticket {
+        let toml = format!(
+            "[meta]\nid = \"T-001\"\ntitle = \"t\"\nstatus = \"todo\"\npriority = \"low\"\n\n[spec]\ndescription = \"\"\nassets = {:?}\n\n[verification]\ncommand = \"true\"\n",
+            assets,
+        );
+        toml_edit::de::from_str(&toml).unwrap()
+    }
+
+    #[test]
+    fn test_render_assets() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("assets")).unwrap();
+        fs::write(dir.path().join("assets/mockup.png"), [0x89, b'P', b'N', b'G']).unwrap();
+        fs::write(dir.path().join("assets/tokens.json"), "{}").unwrap();
+        let ticket = ticket(&["mockup.png", "tokens.json", "gone.png"]);
+
+        assert_eq!(image_assets(&ticket, dir.path()), vec!["assets/mockup.png"]);
+
+        let paths = render_assets(&ticket, dir.path(), ImageMode::Paths);
+        assert!(paths.contains("## Attached Assets\n- assets/tokens.json\n- assets/gone.png\n"));
+        assert!(paths.contains("- assets/mockup.png\n"));
+
+        let inline = render_assets(&ticket, dir.path(), ImageMode::Base64);
+        assert!(inline.contains("![assets/mockup.png](data:image/png;base64,iVBORw==)"));
+
+        let none = render_assets(&ticket, dir.path(), ImageMode::None);
+        assert!(!none.contains("Design Mockups"));
+    }
+
+    #[test]
+    fn test_render_referenced() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("close.svg"), "<svg/>").unwrap();
+        assert!(is_asset("close.svg") && is_asset("fonts/inter.woff2"));
+        assert!(!is_asset("Button.scss"));
+
+        let section = render_referenced(dir.path(), &["close.svg".to_string()]);
+        assert_eq!(section, "## Referenced Assets\nUsed by the context files above; listed by name only.\n- close.svg (6 bytes)\n\n");
+        assert!(render_referenced(dir.path(), &[]).is_empty());
+    }
+}
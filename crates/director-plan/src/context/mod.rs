@@ -1,2 +1,12 @@
 pub mod discovery;
 pub mod ast;
+pub mod overview;
+pub mod history;
+pub mod docs;
+pub mod schema;
+pub mod cache;
+pub mod builder;
+pub mod curate;
+pub mod usage;
+pub mod warm;
+pub mod images;
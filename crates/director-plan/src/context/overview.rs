@@ -0,0 +1,299 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::process::Command;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+/// Layout lines and entry points beyond these are cut off to keep the preamble short.
+const MAX_TREE_LINES: usize = 60;
+const MAX_ENTRY_POINTS: usize = 20;
+const MAX_LANGUAGES: usize = 10;
+
+const IGNORED_DIRS: [&str; 8] = ["target", "node_modules", ".git", "dist", "build", ".director", "proof", "assets"];
+
+/// (extension, language)
+const LANGUAGES: &[(&str, &str)] = &[
+    ("rs", "Rust"),
+    ("ts", "TypeScript"),
+    ("tsx", "TypeScript"),
+    ("js", "JavaScript"),
+    ("jsx", "JavaScript"),
+    ("mjs", "JavaScript"),
+    ("py", "Python"),
+    ("go", "Go"),
+    ("java", "Java"),
+    ("kt", "Kotlin"),
+    ("swift", "Swift"),
+    ("c", "C"),
+    ("h", "C"),
+    ("cpp", "C++"),
+    ("hpp", "C++"),
+    ("cs", "C#"),
+    ("rb", "Ruby"),
+    ("css", "CSS"),
+    ("scss", "CSS"),
+    ("html", "HTML"),
+    ("sh", "Shell"),
+    ("toml", "TOML"),
+    ("json", "JSON"),
+    ("yml", "YAML"),
+    ("yaml", "YAML"),
+    ("md", "Markdown"),
+];
+
+const ENTRY_POINTS: &[&str] = &[
+    "main.rs", "lib.rs", "main.go", "main.py", "__main__.py", "app.py", "manage.py",
+    "index.ts", "index.tsx", "index.js", "main.ts", "main.tsx", "main.js", "App.tsx", "server.ts",
+];
+
+/// (manifest, dependency, framework). A dependency counts when the manifest declares it.
+const FRAMEWORKS: &[(&str, &str, &str)] = &[
+    ("Cargo.toml", "axum", "axum"),
+    ("Cargo.toml", "actix-web", "Actix Web"),
+    ("Cargo.toml", "tokio", "Tokio"),
+    ("Cargo.toml", "clap", "clap"),
+    ("Cargo.toml", "bevy", "Bevy"),
+    ("Cargo.toml", "leptos", "Leptos"),
+    ("Cargo.toml", "tauri", "Tauri"),
+    ("package.json", "react", "React"),
+    ("package.json", "next", "Next.js"),
+    ("package.json", "vue", "Vue"),
+    ("package.json", "svelte", "Svelte"),
+    ("package.json", "express", "Express"),
+    ("package.json", "vite", "Vite"),
+    ("package.json", "tailwindcss", "Tailwind CSS"),
+    ("package.json", "jest", "Jest"),
+    ("package.json", "vitest", "Vitest"),
+    ("package.json", "@playwright/test", "Playwright"),
+    ("pyproject.toml", "django", "Django"),
+    ("pyproject.toml", "flask", "Flask"),
+    ("pyproject.toml", "fastapi", "FastAPI"),
+    ("pyproject.toml", "pytest", "pytest"),
+    ("requirements.txt", "django", "Django"),
+    ("requirements.txt", "flask", "Flask"),
+    ("requirements.txt", "fastapi", "FastAPI"),
+    ("requirements.txt", "pytest", "pytest"),
+    ("go.mod", "github.com/gin-gonic/gin", "Gin"),
+];
+
+#[derive(Serialize, Deserialize)]
+struct Cached {
+    key: String,
+    markdown: String,
+}
+
+/// A "## Workspace Overview" section for prompts: directory layout down to `depth`,
+/// lines of code per language, likely entry points and detected frameworks.
+///
+/// Counting lines reads every source file, so the result is cached in `.director/overview.json`
+/// and rebuilt only when the file list or a file size changes.
+pub fn workspace_overview(root: &Path, depth: usize) -> Result<String> {
+    let files = list_files(root);
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    depth.hash(&mut hasher);
+    for (path, size) in &files {
+        path.hash(&mut hasher);
+        size.hash(&mut hasher);
+    }
+    let key = format!("{:016x}", hasher.finish());
+
+    let cache_path = crate::state_dir(root).join("overview.json");
+    if let Some(cached) = fs::read_to_string(&cache_path).ok()
+        .and_then(|content| serde_json::from_str::<Cached>(&content).ok())
+        && cached.key == key
+    {
+        return Ok(cached.markdown);
+    }
+
+    let markdown = render(root, &files, depth);
+    // A cache that can't be written just means recounting next time
+    let _ = fs::write(&cache_path, serde_json::to_string(&Cached { key, markdown: markdown.clone() })?);
+    Ok(markdown)
+}
+
+/// Relative paths and sizes of the files worth describing: what git tracks (plus untracked,
+/// non-ignored files), or a filtered walk when the workspace isn't a git checkout.
+fn list_files(root: &Path) -> Vec<(String, u64)> {
+    let output = Command::new("git")
+        .current_dir(root)
+        .args(["ls-files", "--cached", "--others", "--exclude-standard"])
+        .output();
+
+    let paths: Vec<String> = match output {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(String::from)
+            .collect(),
+        _ => WalkDir::new(root)
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || !IGNORED_DIRS.contains(&e.file_name().to_string_lossy().as_ref()))
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .filter_map(|e| e.path().strip_prefix(root).ok().map(|p| p.to_string_lossy().replace('\\', "/")))
+            .collect(),
+    };
+
+    let mut files: Vec<(String, u64)> = paths.into_iter()
+        .filter(|p| !p.split('/').any(|part| IGNORED_DIRS.contains(&part) || part == "plan"))
+        .filter_map(|p| fs::metadata(root.join(&p)).ok().filter(|m| m.is_file()).map(|m| (p, m.len())))
+        .collect();
+    files.sort();
+    files
+}
+
+fn render(root: &Path, files: &[(String, u64)], depth: usize) -> String {
+    let mut out = String::from("## Workspace Overview\n");
+
+    out.push_str("\n### Layout\n```text\n");
+    out.push_str(&layout(files, depth));
+    out.push_str("```\n");
+
+    let languages = languages(root, files);
+    if !languages.is_empty() {
+        out.push_str("\n### Languages\n| Language | Files | Lines |\n| --- | --- | --- |\n");
+        for (language, count, lines) in languages.iter().take(MAX_LANGUAGES) {
+            out.push_str(&format!("| {} | {} | {} |\n", language, count, lines));
+        }
+    }
+
+    let entry_points: Vec<&str> = files.iter()
+        .map(|(path, _)| path.as_str())
+        .filter(|path| {
+            let name = path.rsplit('/').next().unwrap_or(path);
+            (ENTRY_POINTS.contains(&name) || path.contains("src/bin/"))
+                && !path.split('/').any(|part| part == "tests" || part == "__tests__")
+        })
+        .take(MAX_ENTRY_POINTS)
+        .collect();
+    if !entry_points.is_empty() {
+        out.push_str("\n### Entry Points\n");
+        for path in entry_points {
+            out.push_str(&format!("- {}\n", path));
+        }
+    }
+
+    let frameworks = frameworks(root, files);
+    if !frameworks.is_empty() {
+        out.push_str(&format!("\n### Frameworks\n{}\n", frameworks.into_iter().collect::<Vec<_>>().join(", ")));
+    }
+
+    out
+}
+
+/// Directories down to `depth` with the number of files under each.
+fn layout(files: &[(String, u64)], depth: usize) -> String {
+    let mut dirs: BTreeMap<String, usize> = BTreeMap::new();
+    let mut top_level_files = 0;
+    for (path, _) in files {
+        let parts: Vec<&str> = path.split('/').collect();
+        if parts.len() == 1 {
+            top_level_files += 1;
+        }
+        for level in 1..parts.len().min(depth + 1) {
+            *dirs.entry(parts[..level].join("/")).or_default() += 1;
+        }
+    }
+
+    let mut out = String::new();
+    for (lines, (dir, count)) in dirs.iter().enumerate() {
+        if lines == MAX_TREE_LINES {
+            out.push_str(&format!("... ({} more directories)\n", dirs.len() - MAX_TREE_LINES));
+            break;
+        }
+        let level = dir.matches('/').count();
+        let name = dir.rsplit('/').next().unwrap_or(dir);
+        out.push_str(&format!("{}{}/ ({} files)\n", "  ".repeat(level), name, count));
+    }
+    if top_level_files > 0 {
+        out.push_str(&format!("./ ({} files at the top level)\n", top_level_files));
+    }
+    out
+}
+
+/// (language, files, non-blank lines), largest first.
+fn languages(root: &Path, files: &[(String, u64)]) -> Vec<(&'static str, usize, usize)> {
+    let mut totals: BTreeMap<&'static str, (usize, usize)> = BTreeMap::new();
+    for (path, _) in files {
+        let ext = match path.rsplit_once('.') {
+            Some((_, ext)) => ext,
+            None => continue,
+        };
+        let language = match LANGUAGES.iter().find(|(e, _)| *e == ext) {
+            Some((_, language)) => *language,
+            None => continue,
+        };
+        let lines = fs::read_to_string(root.join(path))
+            .map(|content| content.lines().filter(|l| !l.trim().is_empty()).count())
+            .unwrap_or(0);
+        let entry = totals.entry(language).or_default();
+        entry.0 += 1;
+        entry.1 += lines;
+    }
+
+    let mut languages: Vec<_> = totals.into_iter().map(|(language, (count, lines))| (language, count, lines)).collect();
+    languages.sort_by_key(|b| std::cmp::Reverse(b.2));
+    languages
+}
+
+fn frameworks(root: &Path, files: &[(String, u64)]) -> BTreeSet<&'static str> {
+    let mut found = BTreeSet::new();
+    for (path, _) in files {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        let candidates: Vec<_> = FRAMEWORKS.iter().filter(|(manifest, _, _)| *manifest == name).collect();
+        if candidates.is_empty() {
+            continue;
+        }
+        let content = match fs::read_to_string(root.join(path)) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+        for (manifest, dependency, framework) in candidates {
+            if declares(manifest, &content, dependency) {
+                found.insert(*framework);
+            }
+        }
+    }
+    found
+}
+
+fn declares(manifest: &str, content: &str, dependency: &str) -> bool {
+    match manifest {
+        "package.json" => content.contains(&format!("\"{}\":", dependency)),
+        "Cargo.toml" => content.lines().any(|line| {
+            line.trim_start().strip_prefix(dependency)
+                .is_some_and(|rest| rest.starts_with([' ', '=', '.']))
+        }),
+        _ => content.to_lowercase().contains(dependency),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_workspace_overview() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("crates/app/src")).unwrap();
+        fs::write(root.join("crates/app/Cargo.toml"), "[dependencies]\naxum = \"0.7\"\naxum-extra = \"0.9\"\n").unwrap();
+        fs::write(root.join("crates/app/src/main.rs"), "fn main() {\n\n    run();\n}\n").unwrap();
+        fs::create_dir_all(root.join("node_modules/react")).unwrap();
+        fs::write(root.join("node_modules/react/index.js"), "module.exports = {};\n").unwrap();
+
+        let overview = workspace_overview(root, 2).unwrap();
+        assert!(overview.contains("crates/ (2 files)"));
+        assert!(overview.contains("  app/ (2 files)"));
+        assert!(!overview.contains("node_modules"));
+        assert!(overview.contains("| Rust | 1 | 3 |"));
+        assert!(overview.contains("- crates/app/src/main.rs"));
+        assert!(overview.contains("### Frameworks\naxum\n"));
+
+        // Served from the cache while nothing changes
+        assert_eq!(workspace_overview(root, 2).unwrap(), overview);
+    }
+}
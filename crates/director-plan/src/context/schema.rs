@@ -0,0 +1,426 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+use regex::Regex;
+use walkdir::WalkDir;
+use crate::context::discovery::tokenize;
+use crate::types::Ticket;
+
+/// A single slice longer than this is cut off.
+const MAX_SLICE_LINES: usize = 150;
+
+const IGNORED_DIRS: [&str; 7] = ["target", "node_modules", ".git", "dist", "build", ".director", "plan"];
+
+/// One schema file's relevant parts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaSlice {
+    pub path: String,
+    /// Code-fence language: `yaml`, `json`, `graphql` or `protobuf`.
+    pub language: &'static str,
+    pub content: String,
+}
+
+/// What the ticket refers to: endpoint paths (`/users/{id}`) and plain words, which are
+/// matched against operation IDs, GraphQL fields and types, and protobuf RPCs and messages.
+struct Mentions {
+    endpoints: Vec<Vec<String>>,
+    words: HashSet<String>,
+}
+
+/// An "## API Schema" prompt section with the parts of OpenAPI, GraphQL and protobuf files the
+/// ticket talks about, rather than whole specs. Empty when nothing matches.
+pub fn render_schemas(ticket: &Ticket, root: &Path) -> String {
+    let slices = relevant_slices(ticket, root);
+    if slices.is_empty() {
+        return String::new();
+    }
+
+    let mut out = String::from("## API Schema\n");
+    for slice in slices {
+        out.push_str(&format!("\n### {}\n```{}\n{}\n```\n", slice.path, slice.language, slice.content.trim_end()));
+    }
+    out
+}
+
+pub fn relevant_slices(ticket: &Ticket, root: &Path) -> Vec<SchemaSlice> {
+    let text = format!("{} {}", ticket.meta.title, ticket.spec.description);
+    let mentions = Mentions {
+        endpoints: endpoints(&text),
+        words: tokenize(&text),
+    };
+
+    let mut slices = Vec::new();
+    for path in schema_files(root) {
+        let content = match fs::read_to_string(root.join(&path)) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+        let slice = if path.ends_with(".proto") {
+            slice_protobuf(&content, &mentions).map(|c| ("protobuf", c))
+        } else if path.ends_with(".graphql") || path.ends_with(".gql") {
+            slice_graphql(&content, &mentions).map(|c| ("graphql", c))
+        } else if path.ends_with(".json") {
+            slice_openapi_json(&content, &mentions).map(|c| ("json", c))
+        } else {
+            slice_openapi_yaml(&content, &mentions).map(|c| ("yaml", c))
+        };
+        if let Some((language, content)) = slice {
+            slices.push(SchemaSlice { path, language, content: truncate(&content) });
+        }
+    }
+    slices
+}
+
+fn schema_files(root: &Path) -> Vec<String> {
+    let mut files: Vec<String> = WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !IGNORED_DIRS.contains(&e.file_name().to_string_lossy().as_ref()))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| e.path().strip_prefix(root).ok().map(|p| p.to_string_lossy().replace('\\', "/")))
+        .filter(|path| {
+            let name = path.rsplit('/').next().unwrap_or(path).to_lowercase();
+            let openapi = ["openapi.", "swagger."].iter().any(|marker| name.contains(marker))
+                && (name.ends_with(".yaml") || name.ends_with(".yml") || name.ends_with(".json"));
+            openapi || name.ends_with(".graphql") || name.ends_with(".gql") || name.ends_with(".proto")
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Endpoint paths mentioned in free text, split into segments with parameters
+/// (`{id}`, `:id`) normalized to `{}`.
+fn endpoints(text: &str) -> Vec<Vec<String>> {
+    let re = Regex::new(r"(?:^|[\s`(])(/[A-Za-z0-9_\-{}:./]*[A-Za-z0-9_}])").unwrap();
+    re.captures_iter(text)
+        .map(|c| normalize_path(&c[1]))
+        .filter(|segments| !segments.is_empty())
+        .collect()
+}
+
+fn normalize_path(path: &str) -> Vec<String> {
+    path.trim_matches(|c| c == '"' || c == '\'')
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(|s| if s.starts_with('{') || s.starts_with(':') { "{}".to_string() } else { s.to_lowercase() })
+        .collect()
+}
+
+/// `/users/{id}` in a spec matches a mention of `/users/:userId`, and a mention without the
+/// spec's prefix (`/users` for `/api/v1/users`) still matches.
+fn path_matches(spec_path: &str, mentions: &Mentions) -> bool {
+    let spec = normalize_path(spec_path);
+    mentions.endpoints.iter().any(|mention| spec.ends_with(mention))
+}
+
+fn mentioned(name: &str, mentions: &Mentions) -> bool {
+    mentions.words.contains(&name.to_lowercase())
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Index just past the YAML block whose key is at `start`.
+fn yaml_block_end(lines: &[&str], start: usize) -> usize {
+    let key_indent = indent(lines[start]);
+    let mut end = start + 1;
+    while end < lines.len() && (lines[end].trim().is_empty() || indent(lines[end]) > key_indent) {
+        end += 1;
+    }
+    end
+}
+
+/// Paths whose URL or `operationId` the ticket mentions, plus the `components.schemas` they reference.
+fn slice_openapi_yaml(content: &str, mentions: &Mentions) -> Option<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    let paths_at = lines.iter().position(|l| l.trim_end() == "paths:" && indent(l) == 0)?;
+    let paths_end = yaml_block_end(&lines, paths_at);
+
+    let mut out = vec!["paths:".to_string()];
+    let mut i = paths_at + 1;
+    while i < paths_end {
+        let line = lines[i];
+        let key = line.trim().trim_end_matches(':').trim_matches(|c| c == '"' || c == '\'');
+        if line.trim().is_empty() || !key.starts_with('/') {
+            i += 1;
+            continue;
+        }
+        let end = yaml_block_end(&lines, i);
+        let block = &lines[i..end];
+        let by_operation = block.iter()
+            .filter_map(|l| l.trim().strip_prefix("operationId:"))
+            .any(|id| mentioned(id.trim().trim_matches(|c| c == '"' || c == '\''), mentions));
+        if path_matches(key, mentions) || by_operation {
+            out.extend(block.iter().map(|l| l.to_string()));
+        }
+        i = end;
+    }
+    if out.len() == 1 {
+        return None;
+    }
+
+    let schemas = referenced_yaml_schemas(&lines, &out.join("\n"));
+    if !schemas.is_empty() {
+        out.push("components:".to_string());
+        out.push("  schemas:".to_string());
+        out.extend(schemas);
+    }
+    Some(out.join("\n"))
+}
+
+/// `#/components/schemas/X` blocks referenced from `selected`, following references they make in turn.
+fn referenced_yaml_schemas(lines: &[&str], selected: &str) -> Vec<String> {
+    let re = Regex::new(r"#/components/schemas/([A-Za-z0-9_.\-]+)").unwrap();
+    let schemas_at = match lines.iter().position(|l| l.trim_end() == "schemas:" && indent(l) > 0) {
+        Some(at) => at,
+        None => return vec![],
+    };
+    let schemas_end = yaml_block_end(lines, schemas_at);
+
+    let mut out = Vec::new();
+    let mut seen = HashSet::new();
+    let mut pending: Vec<String> = re.captures_iter(selected).map(|c| c[1].to_string()).collect();
+    while let Some(name) = pending.pop() {
+        if !seen.insert(name.clone()) {
+            continue;
+        }
+        let start = match (schemas_at + 1..schemas_end).find(|&i| lines[i].trim_end().trim() == format!("{}:", name)) {
+            Some(start) => start,
+            None => continue,
+        };
+        let block = &lines[start..yaml_block_end(lines, start)];
+        for line in block {
+            pending.extend(re.captures_iter(line).map(|c| c[1].to_string()));
+        }
+        out.extend(block.iter().map(|l| l.to_string()));
+    }
+    out
+}
+
+fn slice_openapi_json(content: &str, mentions: &Mentions) -> Option<String> {
+    let spec: serde_json::Value = serde_json::from_str(content).ok()?;
+    let paths = spec.get("paths")?.as_object()?;
+
+    let mut selected = serde_json::Map::new();
+    for (path, item) in paths {
+        let by_operation = item.as_object()
+            .map(|ops| ops.values().any(|op| op.get("operationId").and_then(|id| id.as_str()).is_some_and(|id| mentioned(id, mentions))))
+            .unwrap_or(false);
+        if path_matches(path, mentions) || by_operation {
+            selected.insert(path.clone(), item.clone());
+        }
+    }
+    if selected.is_empty() {
+        return None;
+    }
+
+    let re = Regex::new(r"#/components/schemas/([A-Za-z0-9_.\-]+)").unwrap();
+    let all_schemas = spec.pointer("/components/schemas").and_then(|s| s.as_object());
+    let mut schemas = BTreeMap::new();
+    let mut pending: Vec<String> = re.captures_iter(&serde_json::Value::Object(selected.clone()).to_string()).map(|c| c[1].to_string()).collect();
+    while let Some(name) = pending.pop() {
+        if schemas.contains_key(&name) {
+            continue;
+        }
+        if let Some(schema) = all_schemas.and_then(|s| s.get(&name)) {
+            pending.extend(re.captures_iter(&schema.to_string()).map(|c| c[1].to_string()));
+            schemas.insert(name, schema.clone());
+        }
+    }
+
+    let mut slice = serde_json::json!({ "paths": selected });
+    if !schemas.is_empty() {
+        slice["components"] = serde_json::json!({ "schemas": schemas });
+    }
+    serde_json::to_string_pretty(&slice).ok()
+}
+
+/// A top-level `keyword Name ... { body }` definition in GraphQL or protobuf.
+struct Definition<'a> {
+    keyword: &'a str,
+    name: &'a str,
+    text: &'a str,
+    body: &'a str,
+}
+
+fn definitions(content: &str) -> Vec<Definition<'_>> {
+    let mut defs = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    let mut open = 0;
+    for (i, c) in content.char_indices() {
+        match c {
+            '{' => {
+                if depth == 0 {
+                    open = i;
+                }
+                depth += 1;
+            }
+            '}' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    let header_text = &content[start..open];
+                    // The definition starts at its last non-comment header line
+                    let header_start = header_text.rfind(|c| c == ';' || c == '}').map(|p| p + 1).unwrap_or(0);
+                    let header = header_text[header_start..].lines()
+                        .filter(|l| !l.trim_start().starts_with('#') && !l.trim_start().starts_with("//"))
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    let mut words = header.split_whitespace();
+                    let first = words.next().unwrap_or_default();
+                    // `extend type Query` names the type after the keyword
+                    let (keyword, name) = if first == "extend" {
+                        (words.next().unwrap_or_default(), words.next().unwrap_or_default())
+                    } else {
+                        (first, words.next().unwrap_or_default())
+                    };
+                    let text_start = start + header_start + header_text[header_start..].find(keyword).unwrap_or(0);
+                    defs.push(Definition {
+                        keyword: static_keyword(keyword),
+                        name: name.trim_end_matches(|c: char| !c.is_alphanumeric() && c != '_').split(['(', '@', ':']).next().unwrap_or_default(),
+                        text: content[text_start..=i].trim(),
+                        body: &content[open + 1..i],
+                    });
+                    start = i + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    defs
+}
+
+fn static_keyword(keyword: &str) -> &'static str {
+    match keyword {
+        "type" => "type",
+        "input" => "input",
+        "interface" => "interface",
+        "enum" => "enum",
+        "message" => "message",
+        "service" => "service",
+        _ => "",
+    }
+}
+
+/// Matching `Query`/`Mutation`/`Subscription` fields (only those fields), mentioned types,
+/// and the types those fields use.
+fn slice_graphql(content: &str, mentions: &Mentions) -> Option<String> {
+    let defs = definitions(content);
+    let type_names: HashSet<&str> = defs.iter().map(|d| d.name).collect();
+    let field_re = Regex::new(r"^\s*([A-Za-z_][A-Za-z0-9_]*)").unwrap();
+    let word_re = Regex::new(r"[A-Za-z_][A-Za-z0-9_]*").unwrap();
+
+    let mut out = Vec::new();
+    let mut wanted: Vec<&str> = Vec::new();
+    for def in &defs {
+        if matches!(def.name, "Query" | "Mutation" | "Subscription") {
+            let fields: Vec<&str> = def.body.lines()
+                .filter(|line| field_re.captures(line).is_some_and(|c| mentioned(&c[1], mentions)))
+                .collect();
+            if fields.is_empty() {
+                continue;
+            }
+            for field in &fields {
+                wanted.extend(word_re.find_iter(field).map(|m| m.as_str()).filter(|w| type_names.contains(w)));
+            }
+            out.push(format!("type {} {{\n{}\n}}", def.name, fields.join("\n")));
+        } else if !def.keyword.is_empty() && mentioned(def.name, mentions) {
+            wanted.push(def.name);
+        }
+    }
+
+    let mut seen = HashSet::new();
+    while let Some(name) = wanted.pop() {
+        if !seen.insert(name) {
+            continue;
+        }
+        if let Some(def) = defs.iter().find(|d| d.name == name && !matches!(d.name, "Query" | "Mutation" | "Subscription")) {
+            out.push(def.text.to_string());
+        }
+    }
+
+    (!out.is_empty()).then(|| out.join("\n\n"))
+}
+
+/// Matching RPCs (only those, inside their `service`), mentioned messages and enums, and the
+/// request/response messages of the matching RPCs.
+fn slice_protobuf(content: &str, mentions: &Mentions) -> Option<String> {
+    let defs = definitions(content);
+    let rpc_re = Regex::new(r"rpc\s+(\w+)\s*\(\s*(?:stream\s+)?([\w.]+)\s*\)\s*returns\s*\(\s*(?:stream\s+)?([\w.]+)\s*\)").unwrap();
+
+    let mut out = Vec::new();
+    let mut wanted: Vec<String> = Vec::new();
+    for def in &defs {
+        if def.keyword == "service" {
+            let rpcs: Vec<&str> = def.body.lines()
+                .filter(|line| rpc_re.captures(line).is_some_and(|c| mentioned(&c[1], mentions)))
+                .collect();
+            if rpcs.is_empty() {
+                continue;
+            }
+            for line in &rpcs {
+                if let Some(c) = rpc_re.captures(line) {
+                    wanted.push(c[2].rsplit('.').next().unwrap_or(&c[2]).to_string());
+                    wanted.push(c[3].rsplit('.').next().unwrap_or(&c[3]).to_string());
+                }
+            }
+            out.push(format!("service {} {{\n{}\n}}", def.name, rpcs.join("\n")));
+        } else if matches!(def.keyword, "message" | "enum") && mentioned(def.name, mentions) {
+            wanted.push(def.name.to_string());
+        }
+    }
+
+    let mut seen = HashSet::new();
+    while let Some(name) = wanted.pop() {
+        if !seen.insert(name.clone()) {
+            continue;
+        }
+        if let Some(def) = defs.iter().find(|d| d.name == name && d.keyword != "service") {
+            out.push(def.text.to_string());
+        }
+    }
+
+    (!out.is_empty()).then(|| out.join("\n\n"))
+}
+
+fn truncate(content: &str) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    if lines.len() <= MAX_SLICE_LINES {
+        return content.to_string();
+    }
+    format!("{}\n# ... ({} more lines)", lines[..MAX_SLICE_LINES].join("\n"), lines.len() - MAX_SLICE_LINES)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mentions(text: &str) -> Mentions {
+        Mentions { endpoints: endpoints(text), words: tokenize(text) }
+    }
+
+    #[test]
+    fn test_slices() {
+        let yaml = "openapi: 3.0.0\npaths:\n  /api/users/{id}:\n    get:\n      operationId: getUser\n      responses:\n        '200':\n          content:\n            application/json:\n              schema:\n                $ref: '#/components/schemas/User'\n  /api/orders:\n    get:\n      operationId: listOrders\ncomponents:\n  schemas:\n    User:\n      type: object\n      properties:\n        address:\n          $ref: '#/components/schemas/Address'\n    Address:\n      type: object\n    Order:\n      type: object\n";
+        let slice = slice_openapi_yaml(yaml, &mentions("Return 404 from GET /users/:userId when missing")).unwrap();
+        assert!(slice.contains("/api/users/{id}:"));
+        assert!(!slice.contains("/api/orders"));
+        assert!(slice.contains("    User:") && slice.contains("    Address:"));
+        assert!(!slice.contains("Order:"));
+        assert!(slice_openapi_yaml(yaml, &mentions("Speed up listOrders")).unwrap().contains("/api/orders:"));
+        assert_eq!(slice_openapi_yaml(yaml, &mentions("Fix the navbar")), None);
+
+        let proto = "syntax = \"proto3\";\n\nservice Users {\n  rpc GetUser(GetUserRequest) returns (User);\n  rpc DeleteUser(DeleteUserRequest) returns (Empty);\n}\n\nmessage GetUserRequest { string id = 1; }\nmessage User { string name = 1; }\nmessage Empty {}\n";
+        let slice = slice_protobuf(proto, &mentions("Add caching to GetUser")).unwrap();
+        assert!(slice.contains("rpc GetUser") && !slice.contains("DeleteUser"));
+        assert!(slice.contains("message GetUserRequest") && slice.contains("message User {"));
+        assert!(!slice.contains("message Empty"));
+
+        let graphql = "type Query {\n  user(id: ID!): User\n  orders: [Order!]!\n}\n\ntype User {\n  id: ID!\n}\n\ntype Order {\n  id: ID!\n}\n";
+        let slice = slice_graphql(graphql, &mentions("Paginate the orders query")).unwrap();
+        assert!(slice.contains("orders: [Order!]!") && !slice.contains("user(id"));
+        assert!(slice.contains("type Order {") && !slice.contains("type User"));
+    }
+}
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use crate::runs::{RunOutcome, RunRecord};
+
+/// Points a file gains in discovery for each past run that changed it while working in the
+/// same directory.
+const LEARNED_WEIGHT: u32 = 5;
+
+/// Runs that must have changed a file before discovery adds it without any match of its own.
+const LEARNED_MIN_RUNS: u32 = 2;
+
+/// How an attempt's changes, counted from the start of the run, line up with the context it
+/// was given.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct ContextUsage {
+    /// Context files the agent changed
+    #[serde(default)]
+    pub modified: Vec<String>,
+    /// Context files it left alone
+    #[serde(default)]
+    pub untouched: Vec<String>,
+    /// Files it changed that weren't in the context
+    #[serde(default)]
+    pub missed: Vec<String>,
+}
+
+impl ContextUsage {
+    pub fn new(context: &[String], changed: &[String]) -> Self {
+        let (modified, untouched) = context.iter().cloned().partition(|path| changed.contains(path));
+        let missed = changed.iter().filter(|path| !context.contains(path)).cloned().collect();
+        Self { modified, untouched, missed }
+    }
+}
+
+/// What past runs changed, by area: the directory of a context file the agent modified.
+#[derive(Debug, Default)]
+pub struct LearnedPaths {
+    areas: HashMap<String, HashMap<String, u32>>,
+}
+
+impl LearnedPaths {
+    /// Learns from the last recorded attempt of each successful run; failed runs changed
+    /// things nobody kept.
+    pub fn from_runs(runs: &[RunRecord]) -> Self {
+        let mut learned = Self::default();
+        for run in runs.iter().filter(|r| r.outcome == RunOutcome::Succeeded) {
+            let Some(usage) = run.attempts.iter().rev().find_map(|a| a.context.as_ref()) else { continue };
+            let mut areas: Vec<&str> = usage.modified.iter().map(|path| area(path)).collect();
+            areas.sort();
+            areas.dedup();
+            for area in areas {
+                let counts = learned.areas.entry(area.to_string()).or_default();
+                for path in usage.modified.iter().chain(&usage.missed) {
+                    *counts.entry(path.clone()).or_default() += 1;
+                }
+            }
+        }
+        learned
+    }
+
+    /// Extra discovery score for files past runs changed when working in the areas of
+    /// `matched`, with whether each is strong enough to stand without a match of its own.
+    pub fn weights(&self, matched: &[String]) -> Vec<(String, u32, bool)> {
+        let mut runs: HashMap<&str, u32> = HashMap::new();
+        let mut areas: Vec<&str> = matched.iter().map(|path| area(path)).collect();
+        areas.sort();
+        areas.dedup();
+        for counts in areas.into_iter().filter_map(|area| self.areas.get(area)) {
+            for (path, count) in counts {
+                *runs.entry(path.as_str()).or_default() += count;
+            }
+        }
+        let mut weights: Vec<(String, u32, bool)> = runs.into_iter()
+            .map(|(path, count)| (path.to_string(), count * LEARNED_WEIGHT, count >= LEARNED_MIN_RUNS))
+            .collect();
+        weights.sort();
+        weights
+    }
+}
+
+fn area(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(dir, _)| dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runs::AttemptRecord;
+
+    fn run(outcome: RunOutcome, usage: ContextUsage) -> RunRecord {
+        let mut record = RunRecord::new("T-001", "agent");
+        record.outcome = outcome;
+        record.attempts.push(AttemptRecord {
+            number: 1,
+            agent_ms: 0,
+            verification_ms: 0,
+            passed: true,
+            confidence: None,
+            errors: Vec::new(),
+            perf: None,
+            coverage: None,
+            autofixed: Vec::new(),
+            scoped_command: None,
+            flake: None,
+            result: None,
+            context: Some(usage),
+            snapshot: None,
+            agent: None,
+        });
+        record
+    }
+
+    #[test]
+    fn test_context_usage() {
+        let context = ["src/a.rs".to_string(), "src/b.rs".to_string()];
+        let changed = ["src/b.rs".to_string(), "src/c.rs".to_string()];
+        assert_eq!(ContextUsage::new(&context, &changed), ContextUsage {
+            modified: vec!["src/b.rs".to_string()],
+            untouched: vec!["src/a.rs".to_string()],
+            missed: vec!["src/c.rs".to_string()],
+        });
+    }
+
+    #[test]
+    fn test_learned_weights() {
+        let usage = ContextUsage::new(&["src/parse/lexer.rs".to_string()], &["src/parse/lexer.rs".to_string(), "src/ast.rs".to_string()]);
+        let runs = [
+            run(RunOutcome::Succeeded, usage.clone()),
+            run(RunOutcome::Succeeded, usage.clone()),
+            run(RunOutcome::Failed, ContextUsage::new(&["src/parse/lexer.rs".to_string()], &["src/parse/lexer.rs".to_string(), "src/render.rs".to_string()])),
+        ];
+        let learned = LearnedPaths::from_runs(&runs);
+        assert_eq!(learned.weights(&["src/parse/token.rs".to_string()]), [
+            ("src/ast.rs".to_string(), 10, true),
+            ("src/parse/lexer.rs".to_string(), 10, true),
+        ]);
+        assert!(learned.weights(&["src/render.rs".to_string()]).is_empty());
+    }
+}
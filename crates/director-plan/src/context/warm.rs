@@ -0,0 +1,105 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use anyhow::Result;
+use notify::{EventKind, RecursiveMode, Watcher};
+use tracing::{debug, error, info};
+use crate::context::ast::{DependencyGraph, is_ignored_path};
+use crate::config::ContextProfile;
+use crate::context::builder::ContextBuilder;
+use crate::types::Ticket;
+
+/// Events arriving within this long of each other are applied as one batch, so a
+/// `git checkout` or an editor's save-all takes the write lock once.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// A dependency graph for the server that is built once and then kept current by a file
+/// watcher, so context requests don't rebuild it from scratch.
+#[derive(Clone)]
+pub struct WarmGraph {
+    graph: Arc<RwLock<Option<DependencyGraph>>>,
+}
+
+impl WarmGraph {
+    /// Builds the graph for `root` on a background thread, then applies every file change to it.
+    /// Returns straight away; until the first build finishes the graph isn't available.
+    /// `include_paths` are `[context] include_paths`, for C/C++ sources.
+    pub fn start(root: PathBuf, include_paths: Vec<String>) -> Self {
+        let warm = Self { graph: Arc::new(RwLock::new(None)) };
+        let graph = warm.graph.clone();
+        std::thread::spawn(move || {
+            if let Err(e) = watch(&root, include_paths, &graph) {
+                error!("Dependency graph watcher for {:?} stopped: {}", root, e);
+            }
+        });
+        warm
+    }
+
+    /// The ticket's context files: its `relevant_files` (or the heuristic matches when it has
+    /// none), everything within the profile's depth of them, and their tests, narrowed to what
+    /// the profile and `context_exclude` let in, plus pinned files. `None` while the graph is
+    /// still being built. The images and fonts those files reference come second.
+    pub fn context_files(&self, ticket: &Ticket, root: &Path, profile: &ContextProfile) -> Option<(Vec<String>, Vec<String>)> {
+        let guard = self.graph.read().unwrap();
+        let builder = ContextBuilder::new(root, profile).with_graph(Some(guard.as_ref()?));
+        let files = builder.select(ticket);
+        let assets = builder.referenced_assets(ticket, &files);
+        Some((files.into_iter().map(|f| f.path).collect(), assets))
+    }
+}
+
+fn watch(root: &Path, include_paths: Vec<String>, graph: &RwLock<Option<DependencyGraph>>) -> Result<()> {
+    // Watch before building, so nothing that changes during the build is missed
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(root, RecursiveMode::Recursive)?;
+
+    let mut built = DependencyGraph::new(root).with_include_paths(include_paths);
+    built.build()?;
+    info!("Dependency graph for {:?} ready ({} files)", root, built.node_map.len());
+    *graph.write().unwrap() = Some(built);
+
+    while let Ok(event) = rx.recv() {
+        let mut changed = BTreeSet::new();
+        collect_paths(root, event, &mut changed);
+        while let Ok(event) = rx.recv_timeout(DEBOUNCE) {
+            collect_paths(root, event, &mut changed);
+        }
+        if changed.is_empty() {
+            continue;
+        }
+
+        let mut guard = graph.write().unwrap();
+        if let Some(graph) = guard.as_mut() {
+            for path in &changed {
+                graph.update_file(path);
+            }
+        }
+        debug!("Dependency graph updated for {} changed path(s)", changed.len());
+    }
+    Ok(())
+}
+
+/// The workspace-relative paths an event touched, leaving out reads and ignored directories.
+fn collect_paths(root: &Path, event: notify::Result<notify::Event>, changed: &mut BTreeSet<String>) {
+    let event = match event {
+        Ok(event) => event,
+        Err(e) => {
+            error!("File watcher error: {}", e);
+            return;
+        }
+    };
+    if matches!(event.kind, EventKind::Access(_)) {
+        return;
+    }
+    for path in event.paths {
+        if let Ok(rel) = path.strip_prefix(root) {
+            let rel = rel.to_string_lossy().replace('\\', "/");
+            if !rel.is_empty() && !is_ignored_path(&rel) {
+                changed.insert(rel);
+            }
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use std::fmt;
+use crate::shutdown::Interrupted;
+use crate::verification::policy::PolicyViolation;
+
+/// Failure classes the CLI reports with their own exit code. Returned wrapped in
+/// `anyhow::Error` like [`PolicyViolation`] and [`Interrupted`]; [`exit_code`] finds them anywhere
+/// in the error chain.
+#[derive(Debug)]
+pub enum DirectorError {
+    TicketNotFound(String),
+    /// The ticket's verification ran and failed
+    VerificationFailed(String),
+    /// Uncommitted changes where a clean tree is required
+    DirtyWorkspace(String),
+    /// The agent process itself failed on the last attempt
+    AgentFailed(String),
+    /// `plan/config.toml` or a ticket file couldn't be parsed
+    InvalidConfig(String),
+    /// Another `execute` or a worker holds the ticket's run lock
+    AlreadyRunning(String),
+}
+
+/// Exit codes, documented in the README. Anything unclassified exits with 1.
+pub mod exit {
+    pub const ERROR: i32 = 1;
+    pub const TICKET_NOT_FOUND: i32 = 2;
+    pub const VERIFICATION_FAILED: i32 = 3;
+    pub const DIRTY_WORKSPACE: i32 = 4;
+    pub const AGENT_FAILED: i32 = 5;
+    pub const POLICY_VIOLATION: i32 = 6;
+    pub const INVALID_CONFIG: i32 = 7;
+    pub const ALREADY_RUNNING: i32 = 8;
+    pub const INTERRUPTED: i32 = 130;
+}
+
+impl DirectorError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            DirectorError::TicketNotFound(_) => exit::TICKET_NOT_FOUND,
+            DirectorError::VerificationFailed(_) => exit::VERIFICATION_FAILED,
+            DirectorError::DirtyWorkspace(_) => exit::DIRTY_WORKSPACE,
+            DirectorError::AgentFailed(_) => exit::AGENT_FAILED,
+            DirectorError::InvalidConfig(_) => exit::INVALID_CONFIG,
+            DirectorError::AlreadyRunning(_) => exit::ALREADY_RUNNING,
+        }
+    }
+}
+
+impl fmt::Display for DirectorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DirectorError::TicketNotFound(id) => write!(f, "Ticket {} not found", id),
+            DirectorError::VerificationFailed(detail) => write!(f, "Verification failed: {}", detail),
+            DirectorError::DirtyWorkspace(detail) => write!(f, "{}", detail),
+            DirectorError::AgentFailed(detail) => write!(f, "Agent failed: {}", detail),
+            DirectorError::InvalidConfig(detail) => write!(f, "Invalid configuration: {}", detail),
+            DirectorError::AlreadyRunning(detail) => write!(f, "{}", detail),
+        }
+    }
+}
+
+impl std::error::Error for DirectorError {}
+
+/// Exit code for an error: the first classified error in its chain, else [`exit::ERROR`].
+pub fn exit_code(err: &anyhow::Error) -> i32 {
+    kind(err).1
+}
+
+/// Short machine-readable name and exit code, e.g. `("ticket_not_found", 2)`.
+pub fn kind(err: &anyhow::Error) -> (&'static str, i32) {
+    for cause in err.chain() {
+        if let Some(e) = cause.downcast_ref::<DirectorError>() {
+            let name = match e {
+                DirectorError::TicketNotFound(_) => "ticket_not_found",
+                DirectorError::VerificationFailed(_) => "verification_failed",
+                DirectorError::DirtyWorkspace(_) => "dirty_workspace",
+                DirectorError::AgentFailed(_) => "agent_failed",
+                DirectorError::InvalidConfig(_) => "invalid_config",
+                DirectorError::AlreadyRunning(_) => "already_running",
+            };
+            return (name, e.exit_code());
+        }
+        if cause.is::<PolicyViolation>() {
+            return ("policy_violation", exit::POLICY_VIOLATION);
+        }
+        if cause.is::<Interrupted>() {
+            return ("interrupted", exit::INTERRUPTED);
+        }
+    }
+    ("error", exit::ERROR)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_exit_code_searches_the_chain() {
+        let err = Err::<(), _>(DirectorError::TicketNotFound("T-9".into()))
+            .context("Failed to load ticket")
+            .unwrap_err();
+        assert_eq!(kind(&err), ("ticket_not_found", 2));
+        assert_eq!(exit_code(&anyhow::Error::new(PolicyViolation("x".into()))), 6);
+        assert_eq!(exit_code(&anyhow::anyhow!("something else")), 1);
+    }
+}
@@ -0,0 +1,228 @@
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use utoipa::ToSchema;
+use crate::runs::RunOutcome;
+use crate::types::Status;
+
+/// Something that happened to the plan. The CLI, the worker and the server all append these
+/// to one [`EventLog`]; the server's SSE stream and webhooks replay what lands there, so every
+/// consumer sees the same events in the same shape.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PlanEvent {
+    TicketCreated { id: String, title: String },
+    StatusChanged { id: String, from: Status, to: Status },
+    TicketDeleted { id: String },
+    CommentAdded { id: String, comment_id: u32, author: String },
+    ExecutionStarted { id: String, run_id: String, agent: String },
+    /// A verification run, by itself (`verify`, the API) or as an attempt of an execution
+    VerificationFinished { id: String, run_id: Option<String>, passed: bool },
+    ExecutionFinished {
+        id: String,
+        run_id: String,
+        #[schema(value_type = String)]
+        outcome: RunOutcome,
+        commit: Option<String>,
+    },
+    PrOpened { id: String, branch: String, url: Option<String> },
+}
+
+impl PlanEvent {
+    /// The ticket the event is about.
+    pub fn ticket_id(&self) -> &str {
+        match self {
+            PlanEvent::TicketCreated { id, .. }
+            | PlanEvent::StatusChanged { id, .. }
+            | PlanEvent::TicketDeleted { id }
+            | PlanEvent::CommentAdded { id, .. }
+            | PlanEvent::ExecutionStarted { id, .. }
+            | PlanEvent::VerificationFinished { id, .. }
+            | PlanEvent::ExecutionFinished { id, .. }
+            | PlanEvent::PrOpened { id, .. } => id,
+        }
+    }
+
+    /// The `type` tag, as used by webhook filters and the SSE `event:` field.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PlanEvent::TicketCreated { .. } => "ticket_created",
+            PlanEvent::StatusChanged { .. } => "status_changed",
+            PlanEvent::TicketDeleted { .. } => "ticket_deleted",
+            PlanEvent::CommentAdded { .. } => "comment_added",
+            PlanEvent::ExecutionStarted { .. } => "execution_started",
+            PlanEvent::VerificationFinished { .. } => "verification_finished",
+            PlanEvent::ExecutionFinished { .. } => "execution_finished",
+            PlanEvent::PrOpened { .. } => "pr_opened",
+        }
+    }
+
+    /// One line for people, without the ticket ID.
+    pub fn summary(&self) -> String {
+        match self {
+            PlanEvent::TicketCreated { title, .. } => format!("created: {}", title),
+            PlanEvent::StatusChanged { from, to, .. } => format!("{} -> {}", from.to_string(), to.to_string()),
+            PlanEvent::TicketDeleted { .. } => "deleted".to_string(),
+            PlanEvent::CommentAdded { comment_id, author, .. } => format!("comment #{} by {}", comment_id, author),
+            PlanEvent::ExecutionStarted { run_id, agent, .. } => format!("run {} started ({})", run_id, agent),
+            PlanEvent::VerificationFinished { run_id, passed, .. } => {
+                let result = if *passed { "passed" } else { "failed" };
+                match run_id {
+                    Some(run_id) => format!("verification {} in run {}", result, run_id),
+                    None => format!("verification {}", result),
+                }
+            }
+            PlanEvent::ExecutionFinished { run_id, outcome, .. } => format!("run {} {:?}", run_id, outcome).to_lowercase(),
+            PlanEvent::PrOpened { branch, url, .. } => format!("PR opened from {}{}", branch, url.as_ref().map(|u| format!(": {}", u)).unwrap_or_default()),
+        }
+    }
+}
+
+/// A [`PlanEvent`] as written to the log: the event's fields plus when it happened.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct EventRecord {
+    pub at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: PlanEvent,
+}
+
+/// A record read back from the log, with the byte offset just past it. The offset is the
+/// SSE event ID, so a client that reconnects with `Last-Event-ID` picks up where it left off.
+#[derive(Debug, Clone)]
+pub struct LoggedEvent {
+    pub offset: u64,
+    pub record: EventRecord,
+}
+
+/// The audit log: `.director/events.jsonl`, one [`EventRecord`] per line, append-only.
+pub struct EventLog {
+    path: PathBuf,
+}
+
+impl EventLog {
+    pub fn new(workspace_root: &Path) -> Self {
+        Self { path: crate::state_dir(workspace_root).join("events.jsonl") }
+    }
+
+    pub fn append(&self, event: PlanEvent) -> Result<EventRecord> {
+        let record = EventRecord { at: Utc::now(), event };
+        let mut line = serde_json::to_string(&record)?;
+        line.push('\n');
+        // One write per line, so processes appending at once don't interleave
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)
+            .with_context(|| format!("Failed to open event log: {:?}", self.path))?;
+        file.write_all(line.as_bytes()).with_context(|| format!("Failed to write event log: {:?}", self.path))?;
+        Ok(record)
+    }
+
+    /// Size of the log, i.e. the offset the next event will follow.
+    pub fn end(&self) -> u64 {
+        fs::metadata(&self.path).map_or(0, |m| m.len())
+    }
+
+    /// Complete records after byte `offset`, oldest first. A line still being written is
+    /// left for the next read; lines that don't parse are skipped.
+    pub fn read_from(&self, offset: u64) -> Result<Vec<LoggedEvent>> {
+        let Ok(mut file) = fs::File::open(&self.path) else { return Ok(Vec::new()) };
+        // The log was replaced or truncated since `offset` was taken
+        let offset = if offset > self.end() { 0 } else { offset };
+        file.seek(SeekFrom::Start(offset))?;
+        let mut content = String::new();
+        file.read_to_string(&mut content).with_context(|| format!("Failed to read event log: {:?}", self.path))?;
+
+        let mut events = Vec::new();
+        let mut position = offset;
+        for line in content.split_inclusive('\n') {
+            if !line.ends_with('\n') {
+                break;
+            }
+            position += line.len() as u64;
+            if let Ok(record) = serde_json::from_str(line) {
+                events.push(LoggedEvent { offset: position, record });
+            }
+        }
+        Ok(events)
+    }
+
+    pub fn list(&self) -> Result<Vec<EventRecord>> {
+        Ok(self.read_from(0)?.into_iter().map(|e| e.record).collect())
+    }
+}
+
+/// Records `event` in the workspace's log. An event that can't be written is only logged,
+/// since the change it describes has already happened.
+pub fn emit(workspace_root: &Path, event: PlanEvent) {
+    if let Err(e) = EventLog::new(workspace_root).append(event) {
+        tracing::warn!("Failed to record event: {:#}", e);
+    }
+}
+
+/// How often [`follow`] checks the log for new lines.
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Broadcasts every event appended to the workspace's log from now on, whichever process
+/// wrote it. Used by the server for SSE and webhooks.
+pub fn follow(workspace_root: &Path) -> broadcast::Sender<LoggedEvent> {
+    let (tx, _) = broadcast::channel(256);
+    let log = EventLog::new(workspace_root);
+    let sender = tx.clone();
+    tokio::spawn(async move {
+        let mut offset = log.end();
+        let mut interval = tokio::time::interval(FOLLOW_INTERVAL);
+        loop {
+            interval.tick().await;
+            let events = match log.read_from(offset) {
+                Ok(events) => events,
+                Err(e) => {
+                    tracing::warn!("Failed to read event log: {:#}", e);
+                    continue;
+                }
+            };
+            for event in events {
+                offset = event.offset;
+                // No receivers right now is fine; one may subscribe later
+                let _ = sender.send(event);
+            }
+            if offset > log.end() {
+                offset = 0;
+            }
+        }
+    });
+    tx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = EventLog::new(dir.path());
+        assert!(log.list().unwrap().is_empty());
+
+        log.append(PlanEvent::TicketCreated { id: "T-001".into(), title: "Shadows".into() }).unwrap();
+        let mark = log.end();
+        log.append(PlanEvent::StatusChanged { id: "T-001".into(), from: Status::Todo, to: Status::InProgress }).unwrap();
+
+        let line = fs::read_to_string(dir.path().join(".director/events.jsonl")).unwrap();
+        assert!(line.lines().nth(1).unwrap().contains("\"type\":\"status_changed\",\"id\":\"T-001\",\"from\":\"todo\",\"to\":\"in_progress\""));
+
+        let since = log.read_from(mark).unwrap();
+        assert_eq!(since.len(), 1);
+        assert_eq!(since[0].offset, log.end());
+        assert_eq!(since[0].record.event.kind(), "status_changed");
+        assert_eq!(log.list().unwrap()[0].event.ticket_id(), "T-001");
+
+        // A half-written line waits for the rest
+        let mut file = OpenOptions::new().append(true).open(dir.path().join(".director/events.jsonl")).unwrap();
+        file.write_all(b"{\"at\":").unwrap();
+        assert!(log.read_from(log.end() - 6).unwrap().is_empty());
+        assert_eq!(log.list().unwrap().len(), 2);
+    }
+}
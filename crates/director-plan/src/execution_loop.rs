@@ -1,112 +1,964 @@
-use std::io::{Write};
-use std::path::{Path};
-use std::process::{Command, Stdio};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
 use anyhow::{Context, Result, anyhow};
-use crate::types::{Ticket};
-use crate::context::discovery::discover_context;
-use crate::verification::visual_diff::verify_visual;
+use crate::types::{ScopedVerification, Ticket};
+use crate::context::cache::ContextCache;
+use crate::context::usage::ContextUsage;
+use crate::context::{docs, history, images, overview, schema};
+use crate::verification::visual_diff::{VisualDiffReport, verify_snapshots, verify_visual};
+use crate::verification::structure::verify_structures;
+use crate::verification::{coverage, diagnostics, flaky, golden, perf};
+use crate::runs::{RunRecord, RunStore, RunOutcome, AttemptRecord, Checkpoint, Phase, Role, TranscriptEntry, attempt_ref};
+use crate::shutdown::{CancelToken, Interrupted};
+use crate::process::run_cancellable;
+use crate::patch;
+use crate::agent_result::{AgentResult, RESULT_INSTRUCTIONS};
+use crate::budget::TicketUsage;
+use crate::comments::{CommentStore, render_discussion};
+use crate::config::{ContextConfig, ErrorSummary, ImageMode, LintMode, PlanConfig};
+use crate::container::ContainerBackend;
+use crate::secrets::ResolvedEnv;
+use crate::verification::policy;
+use crate::verification::test_report::{self, ReportFormat, Steps, TestCase};
+use crate::verification::steps::{self as verification_steps, StepRun};
+use crate::verification::scoped as verification_scoped;
+use crate::scope;
+use crate::prompt::Confirm;
+use crate::error::DirectorError;
+use crate::events::{self, PlanEvent};
 use std::fs;
-use serde::Deserialize;
+use std::time::Instant;
 
 pub struct ExecutionResult {
     pub success: bool,
     pub confidence: f32,
     pub errors: Vec<String>,
+    /// SHA of the commit holding the agent's changes. `None` unless the run succeeded and changed something.
+    pub commit: Option<String>,
+    /// The run's record in `RunStore`
+    pub run_id: String,
+    /// The agent that made the last attempt, which with `execution.agents` may not be the first
+    pub agent: String,
+    /// The last failed attempt failed in the agent itself rather than in verification
+    pub agent_failed: bool,
+    /// The last result block the agent sent
+    pub result: Option<AgentResult>,
 }
 
-#[derive(Deserialize)]
-struct AgentOutput {
-    confidence: Option<f32>,
-    // other fields?
+/// An agent the execution loop talks to, one message per turn.
+///
+/// Stateless agents get the full prompt (task, context, every error so far) on each attempt.
+/// Stateful ones get the full prompt once and then only follow-ups describing what failed,
+/// so whatever they worked out in earlier turns isn't thrown away.
+pub trait SessionAgent {
+    fn send(&mut self, message: &str, cancel: &CancelToken) -> Result<String>;
+
+    fn is_stateful(&self) -> bool {
+        false
+    }
+
+    /// How the ticket's image assets should appear in the prompt.
+    fn image_mode(&self) -> ImageMode {
+        ImageMode::Paths
+    }
+}
+
+/// Runs a shell command per turn with the message on stdin. Every turn starts from scratch.
+pub struct ShellAgent {
+    command: String,
+    workspace_root: PathBuf,
+    container: Option<Arc<ContainerBackend>>,
+    env: ResolvedEnv,
+    images: ImageMode,
+}
+
+impl ShellAgent {
+    pub fn new(command: &str, workspace_root: &Path) -> Self {
+        Self {
+            command: command.to_string(),
+            workspace_root: workspace_root.to_path_buf(),
+            container: None,
+            env: ResolvedEnv::default(),
+            images: ImageMode::Paths,
+        }
+    }
+
+    pub fn in_container(mut self, container: Arc<ContainerBackend>) -> Self {
+        self.container = Some(container);
+        self
+    }
+
+    pub fn with_env(mut self, env: ResolvedEnv) -> Self {
+        self.env = env;
+        self
+    }
+
+    /// `[context] images`: whether the command takes images inline or by path.
+    pub fn with_images(mut self, images: ImageMode) -> Self {
+        self.images = images;
+        self
+    }
+}
+
+impl SessionAgent for ShellAgent {
+    fn send(&mut self, message: &str, cancel: &CancelToken) -> Result<String> {
+        run_shell_agent(&self.command, &self.workspace_root, self.container.as_deref(), message, &self.env.vars(), cancel)
+    }
+
+    fn image_mode(&self) -> ImageMode {
+        self.images
+    }
+}
+
+/// Keeps a conversation with a shell agent. Each turn the whole conversation so far is piped
+/// to the command as `## User` / `## Agent` sections, and `DIRECTOR_SESSION_ID` stays the
+/// same for the run so agents with their own session storage can resume instead.
+pub struct ShellSessionAgent {
+    command: String,
+    workspace_root: PathBuf,
+    session_id: String,
+    history: Vec<TranscriptEntry>,
+    container: Option<Arc<ContainerBackend>>,
+    env: ResolvedEnv,
+    images: ImageMode,
+}
+
+impl ShellSessionAgent {
+    pub fn new(command: &str, workspace_root: &Path, session_id: &str) -> Self {
+        Self {
+            command: command.to_string(),
+            workspace_root: workspace_root.to_path_buf(),
+            session_id: session_id.to_string(),
+            history: Vec::new(),
+            container: None,
+            env: ResolvedEnv::default(),
+            images: ImageMode::Paths,
+        }
+    }
+
+    pub fn in_container(mut self, container: Arc<ContainerBackend>) -> Self {
+        self.container = Some(container);
+        self
+    }
+
+    pub fn with_env(mut self, env: ResolvedEnv) -> Self {
+        self.env = env;
+        self
+    }
+
+    pub fn with_images(mut self, images: ImageMode) -> Self {
+        self.images = images;
+        self
+    }
+
+    fn render(&self, message: &str) -> String {
+        let mut out = String::new();
+        for entry in &self.history {
+            let heading = match entry.role {
+                Role::User => "## User",
+                Role::Agent => "## Agent",
+            };
+            out.push_str(&format!("{}\n{}\n\n", heading, entry.content));
+        }
+        out.push_str(&format!("## User\n{}\n", message));
+        out
+    }
+}
+
+impl SessionAgent for ShellSessionAgent {
+    fn send(&mut self, message: &str, cancel: &CancelToken) -> Result<String> {
+        let input = self.render(message);
+        let turn = (self.history.len() / 2 + 1).to_string();
+        let mut env = self.env.vars();
+        env.push(("DIRECTOR_SESSION_ID", self.session_id.as_str()));
+        env.push(("DIRECTOR_TURN", turn.as_str()));
+        let reply = run_shell_agent(&self.command, &self.workspace_root, self.container.as_deref(), &input, &env, cancel)?;
+
+        let now = chrono::Utc::now();
+        self.history.push(TranscriptEntry { role: Role::User, content: message.to_string(), at: now });
+        self.history.push(TranscriptEntry { role: Role::Agent, content: reply.clone(), at: now });
+        Ok(reply)
+    }
+
+    fn is_stateful(&self) -> bool {
+        true
+    }
+
+    fn image_mode(&self) -> ImageMode {
+        self.images
+    }
+}
+
+fn run_shell_agent(
+    command: &str,
+    workspace_root: &Path,
+    container: Option<&ContainerBackend>,
+    input: &str,
+    env: &[(&str, &str)],
+    cancel: &CancelToken,
+) -> Result<String> {
+    let cmd = match container {
+        Some(container) => container.shell_command(command, env),
+        None => {
+            let mut cmd = Command::new("sh");
+            cmd.arg("-c")
+                .arg(command)
+                .current_dir(workspace_root)
+                .envs(env.iter().copied());
+            cmd
+        }
+    };
+
+    let output = run_cancellable(cmd, Some(input), cancel)
+        .context("Failed to run agent command")?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    // Also print to user for visibility (tee)
+    crate::progress!("{}", stdout);
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!("Agent exited with status {}: {}", output.status, stderr));
+    }
+
+    Ok(stdout)
 }
 
 pub struct ExecutionLoop<'a> {
     workspace_root: &'a Path,
     agent_cmd: String,
     ticket: Ticket,
+    cancel: CancelToken,
+    agent: Option<Box<dyn SessionAgent + 'a>>,
+    session: bool,
+    patch_mode: bool,
+    confirm: Confirm,
+    report: Option<(ReportFormat, PathBuf)>,
+    resume: bool,
+    autostash: bool,
+    default_agent_cmd: Option<String>,
+    fallback: bool,
 }
 
 impl<'a> ExecutionLoop<'a> {
     pub fn new(workspace_root: &'a Path, agent_cmd: String, ticket: Ticket) -> Self {
         Self {
+            agent: None,
+            session: false,
             workspace_root,
             agent_cmd,
             ticket,
+            cancel: CancelToken::new(),
+            patch_mode: false,
+            confirm: Confirm::Never,
+            report: None,
+            resume: false,
+            autostash: false,
+            default_agent_cmd: None,
+            fallback: true,
+        }
+    }
+
+    /// For agents without filesystem access: the agent is asked for a unified diff, which is
+    /// checked with `git apply --check` and applied. Patches that don't apply count as a
+    /// failed attempt, and git's error is fed back on the retry.
+    pub fn with_patch_mode(mut self) -> Self {
+        self.patch_mode = true;
+        self
+    }
+
+    /// Replaces the default stateless [`ShellAgent`].
+    pub fn with_agent(mut self, agent: Box<dyn SessionAgent + 'a>) -> Self {
+        self.agent = Some(agent);
+        self
+    }
+
+    /// Keeps one conversation with the agent across retries (see [`ShellSessionAgent`]).
+    pub fn with_session(mut self) -> Self {
+        self.session = true;
+        self
+    }
+
+    /// The agent to run when `agent_cmd` is empty and neither the ticket nor `plan/config.toml`
+    /// names one.
+    pub fn with_default_agent(mut self, agent_cmd: &str) -> Self {
+        self.default_agent_cmd = Some(agent_cmd.to_string());
+        self
+    }
+
+    /// Runs `agent_cmd` alone: no `execution.agents` to fall back on, and no routes.
+    pub fn without_fallback(mut self) -> Self {
+        self.fallback = false;
+        self
+    }
+
+    /// Stashes uncommitted changes without asking, instead of refusing to run on a dirty tree,
+    /// and puts them back when the run ends, however it ends. Changes that don't apply cleanly
+    /// on top of the result stay in the stash.
+    pub fn with_autostash(mut self) -> Self {
+        self.autostash = true;
+        self
+    }
+
+    /// The shell adapter for `agent_cmd`, built per run so it can use the run's id and container.
+    fn default_agent(&self, agent_cmd: &str, run_id: &str, container: Option<Arc<ContainerBackend>>, env: ResolvedEnv, images: ImageMode) -> Box<dyn SessionAgent + 'a> {
+        if self.session {
+            let mut agent = ShellSessionAgent::new(agent_cmd, self.workspace_root, run_id).with_env(env).with_images(images);
+            if let Some(container) = container {
+                agent = agent.in_container(container);
+            }
+            Box::new(agent)
+        } else {
+            let mut agent = ShellAgent::new(agent_cmd, self.workspace_root).with_env(env).with_images(images);
+            if let Some(container) = container {
+                agent = agent.in_container(container);
+            }
+            Box::new(agent)
+        }
+    }
+
+    /// Lets a supervisor (worker shutdown, CLI Ctrl-C) stop the run. In-flight agent and
+    /// verification processes are killed, the agent's work is saved for `--resume` (see
+    /// [`with_resume`](Self::with_resume)), the tree is reverted, and `Interrupted` is returned.
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Lets the loop ask before working around a dirty tree or a golden of the wrong size.
+    /// Without this it never asks and fails as before.
+    pub fn with_confirm(mut self, confirm: Confirm) -> Self {
+        self.confirm = confirm;
+        self
+    }
+
+    /// Carries on with the ticket's interrupted or crashed run from its checkpoint: same run
+    /// record, attempt count and errors, with the agent's work restored to the tree.
+    pub fn with_resume(mut self) -> Self {
+        self.resume = true;
+        self
+    }
+
+    /// Writes the last verification's steps to `path` as JUnit XML or TAP when the run ends.
+    pub fn with_report(mut self, format: ReportFormat, path: PathBuf) -> Self {
+        self.report = Some((format, path));
+        self
+    }
+
+    /// Writes the `--report` file, if one was asked for. A run that never got as far as
+    /// verification is reported as a single failed case with its last error.
+    fn write_report(&self, mut cases: Vec<TestCase>, errors: &[String], passed: bool) {
+        let Some((format, path)) = &self.report else { return };
+        if cases.is_empty() && !passed {
+            let error = errors.last().map_or("no verification was run", String::as_str);
+            let result: Result<()> = Err(anyhow!("{}", error));
+            cases.push(TestCase::from_result("execution", std::time::Duration::ZERO, &result));
+        }
+        if let Err(e) = test_report::write(*format, path, &self.ticket.meta.id, &cases) {
+            eprintln!(">> {:#}", e);
         }
     }
 
     pub fn run_with_handshake(&mut self) -> Result<ExecutionResult> {
+        let run_store = RunStore::new(self.workspace_root);
+        let checkpoint = run_store.load_checkpoint(&self.ticket.meta.id)?;
+        let resumed = match checkpoint {
+            Some(checkpoint) if self.resume => {
+                let record = run_store.find(&checkpoint.run_id)?
+                    .ok_or_else(|| anyhow!("The record of run {} is gone; run without --resume", checkpoint.run_id))?;
+                Some((checkpoint, record))
+            }
+            None if self.resume => return Err(anyhow!("{} has no interrupted run to resume", self.ticket.meta.id)),
+            Some(checkpoint) => {
+                crate::progress!(">> Starting over; run {} can no longer be resumed", checkpoint.run_id);
+                self.discard_checkpoint(&run_store);
+                None
+            }
+            None => None,
+        };
+        // A crashed run left its work in the tree, to carry on with as it is
+        let crashed = resumed.as_ref().is_some_and(|(checkpoint, _)| checkpoint.snapshot.is_none());
+
          // 1. Safety Check: Ensure git is clean
-        if self.is_git_dirty()? {
-            return Err(anyhow!("Workspace is dirty. Please commit or stash changes before running execution loop."));
+        // Declared before everything the run sets up, so it's dropped (and the changes put
+        // back) once the run has ended and the tree is settled
+        let _autostash = if crashed || !self.is_git_dirty()? {
+            None
+        } else if self.autostash {
+            let stash = self.stash()?;
+            crate::progress!(">> Stashed your changes; they'll be put back when the run ends");
+            Some(Autostash(stash))
+        } else if self.confirm.ask("Workspace has uncommitted changes. Stash them and continue?") {
+            let stash = self.stash()?;
+            crate::progress!(">> Stashed your changes as \"{}\". Restore them with `git stash pop`.", stash.message);
+            None
+        } else {
+            return Err(DirectorError::DirtyWorkspace("Workspace is dirty. Please commit or stash changes, or pass --autostash, before running execution loop.".to_string()).into());
+        };
+
+        let config = PlanConfig::load(self.workspace_root)?;
+        self.ticket.verification.check()?;
+        let context_profile = match config.context.profile(&self.ticket)? {
+            Some((name, profile)) => {
+                crate::progress!(">> Context profile: {}", name);
+                profile.clone()
+            }
+            None => Default::default(),
+        };
+
+        // Routes pick the agents when neither the caller nor the ticket names any
+        let route = if self.fallback && self.agent_cmd.is_empty() && self.ticket.execution.agents.is_empty() {
+            config.execution.route(&self.ticket, || {
+                ContextCache::new(self.workspace_root, None)
+                    .with_profile(context_profile.clone())
+                    .with_include_paths(config.context.include_paths.clone())
+                    .context_files(&self.ticket)
+                    .len()
+            })
+        } else {
+            None
+        };
+        if let Some((i, route)) = route {
+            crate::progress!(">> Routed to {} by [[execution.routes]] #{}", route.agents.join(", "), i + 1);
+        }
+        let defaults = route.map_or(&config.execution.agents, |(_, route)| &route.agents);
+        // Each agent gets `max_retries` attempts before the next takes over from the base commit
+        let mut agents = if self.fallback {
+            self.ticket.execution.agent_chain(&self.agent_cmd, defaults)
+        } else {
+            Some(self.agent_cmd.clone()).filter(|a| !a.is_empty()).into_iter().collect()
+        };
+        if agents.is_empty() && self.agent.is_some() {
+            // An agent given with `with_agent` goes by whatever command name the caller gave
+            agents.push(self.agent_cmd.clone());
+        }
+        if agents.is_empty() {
+            match &self.default_agent_cmd {
+                Some(agent) => agents.push(agent.clone()),
+                None => return Err(anyhow!("No agent to run {}: pass --agent, or set `execution.agents` or `[[execution.routes]]`", self.ticket.meta.id)),
+            }
+        }
+        let first_agent = &agents[0];
+
+        let (mut record, checkpoint) = match resumed {
+            Some((checkpoint, record)) => {
+                crate::progress!(">> Resuming run {} after {} attempt(s)", record.run_id, checkpoint.attempts);
+                (record, Some(checkpoint))
+            }
+            None => {
+                let mut record = RunRecord::new(&self.ticket.meta.id, first_agent);
+                record.verification_command = Some(self.ticket.verification.command.clone());
+                (record, None)
+            }
+        };
+        let mut agent_index = agents.iter().position(|a| *a == record.agent).unwrap_or(0);
+        record.agent = agents[agent_index].clone();
+
+        let agent_env = ResolvedEnv::resolve(self.workspace_root, &self.ticket.execution.env)?;
+        let verification_env = ResolvedEnv::resolve(self.workspace_root, &self.ticket.verification.env)?;
+        // Anything captured from either command is scrubbed of both sets of secrets
+        let redactor = agent_env.merged(&verification_env);
+        let container = if config.container.enabled {
+            Some(Arc::new(ContainerBackend::start(self.workspace_root, &config.container, &record.run_id)?))
+        } else {
+            None
+        };
+        // With `[context] summarize_errors = "agent"`: a separate, stateless turn of the agent
+        // command, so the summaries don't end up in the working conversation
+        let mut summarizer = (config.context.summarize_errors == ErrorSummary::Agent).then(|| {
+            let mut summarizer = ShellAgent::new(first_agent, self.workspace_root).with_env(agent_env.clone());
+            if let Some(container) = container.clone() {
+                summarizer = summarizer.in_container(container);
+            }
+            summarizer
+        });
+        // An agent given with `with_agent` stands in for the first in the chain
+        let mut agent = match self.agent.take().filter(|_| agent_index == 0) {
+            Some(agent) => agent,
+            None => self.default_agent(&record.agent, &record.run_id, container.clone(), agent_env.clone(), config.context.images),
+        };
+
+        if checkpoint.is_none() {
+            record.base_commit = self.head_commit().ok();
+        }
+        if self.ticket.verification.min_coverage_delta.is_some() && record.base_coverage.is_none() {
+            // Measured once per base commit while the tree is still clean, then reused from run history
+            let history = run_store.list_all().unwrap_or_default();
+            let known = record.base_commit.as_deref()
+                .and_then(|commit| coverage::baseline_from_history(&history, commit));
+            record.base_coverage = match known {
+                Some(base) => Some(base),
+                None => {
+                    crate::progress!(">> Measuring baseline coverage");
+                    Some(self.measure_coverage(&config, container.as_deref(), &verification_env)
+                        .context("Failed to measure baseline coverage")?)
+                }
+            };
+            crate::progress!(">> Baseline coverage: {:.2}%", record.base_coverage.unwrap_or_default());
         }
 
         // 2. Detached HEAD
-        self.enter_detached_head()?;
+        let branch = match &checkpoint {
+            Some(checkpoint) => checkpoint.branch.clone(),
+            None => self.current_branch(),
+        };
+        match checkpoint.as_ref().and_then(|c| c.snapshot.as_deref()) {
+            Some(snapshot) => self.checkout_snapshot(snapshot)?,
+            None if crashed && self.current_branch().is_none() => {}
+            None => self.enter_detached_head()?,
+        }
 
-        let max_retries = self.ticket.verification.max_retries;
-        let mut attempts = 0;
-        let mut previous_errors = Vec::new();
+        let max_retries = self.ticket.verification.max_retries(&config.verification);
+        let mut attempts = checkpoint.as_ref().map_or(0, |c| c.attempts);
+        let mut previous_errors: Vec<String> = checkpoint.as_ref().map(|c| c.previous_errors.clone()).unwrap_or_default();
+        // Stopped while verifying: the agent's changes are in, so they're verified before it's
+        // prompted again
+        let mut verify_first = checkpoint.as_ref().is_some_and(|c| c.phase == Phase::Verification);
+        // The agent's side of the conversation didn't survive, so it gets the full prompt again
+        let mut full_prompt = checkpoint.is_some();
+        let mut state = Checkpoint {
+            run_id: record.run_id.clone(),
+            ticket_id: self.ticket.meta.id.clone(),
+            attempts,
+            previous_errors: previous_errors.clone(),
+            phase: Phase::Agent,
+            branch: branch.clone(),
+            context_hash: checkpoint.as_ref().and_then(|c| c.context_hash.clone()),
+            snapshot: None,
+            updated_at: chrono::Utc::now(),
+        };
         let mut success = false;
         let mut final_confidence = 1.0; // Default if not provided
+        let mut final_result: Option<AgentResult> = None;
+        let mut interrupted = false;
+        let mut agent_failed = false;
+        // The last attempt's verification steps, for `--report`
+        let mut last_steps = Vec::new();
+        // Built on the first prompt; retries only re-read what the agent changed
+        let mut context_cache = ContextCache::new(self.workspace_root, record.base_commit.clone())
+            .with_profile(context_profile)
+            .with_include_paths(config.context.include_paths.clone());
+        // Earlier runs count against the ticket's budget too
+        let mut earlier_runs = run_store.list_for_ticket(&self.ticket.meta.id)?;
+        earlier_runs.retain(|r| r.run_id != record.run_id);
+        // Persist up-front so a run that dies mid-way still leaves a trace
+        if let Err(e) = run_store.save(&record) {
+            eprintln!(">> Failed to save run record: {}", e);
+        }
+        events::emit(self.workspace_root, PlanEvent::ExecutionStarted {
+            id: self.ticket.meta.id.clone(),
+            run_id: record.run_id.clone(),
+            agent: record.agent.clone(),
+        });
 
-        while attempts < max_retries {
-            println!(">> Attempt {}/{}", attempts + 1, max_retries);
-
-            // 3. Generate Prompt
-            let prompt = self.generate_prompt(&previous_errors)?;
+        loop {
+            if self.cancel.is_cancelled() {
+                interrupted = true;
+                break;
+            }
 
-            // 4. Run Agent & Capture Confidence
-            let (_agent_success, agent_output) = match self.run_agent_capture(&prompt) {
-                Ok(out) => (true, out),
-                Err(e) => {
-                    previous_errors.push(format!("Agent Execution Failed: {}", e));
-                    attempts += 1;
-                    continue;
+            if attempts >= max_retries {
+                let Some(next) = agents.get(agent_index + 1) else { break };
+                crate::progress!(">> {} used up its {} attempt(s); falling back to {}", record.agent, max_retries, next);
+                // The next agent starts over on a clean tree, without the last one's errors
+                context_cache.invalidate_changes();
+                self.restart(record.base_commit.as_deref(), container.as_deref())?;
+                agent_index += 1;
+                agent = self.default_agent(next, &record.run_id, container.clone(), agent_env.clone(), config.context.images);
+                record.agent = next.clone();
+                attempts = 0;
+                previous_errors.clear();
+                if let Err(e) = run_store.save(&record) {
+                    eprintln!(">> Failed to save run record: {}", e);
                 }
+            }
+
+            let usage = TicketUsage::of(earlier_runs.iter().chain([&record]), chrono::Utc::now());
+            if let Some(reason) = usage.exceeded(&config.budget) {
+                crate::progress!(">> {}", reason);
+                previous_errors.push(reason);
+                break;
+            }
+
+            state.attempts = attempts;
+            state.previous_errors = previous_errors.clone();
+            save_checkpoint(&run_store, &mut state, if verify_first { Phase::Verification } else { Phase::Agent });
+
+            crate::progress!(">> Attempt {}/{}", attempts + 1, max_retries);
+            last_steps.clear();
+            let mut attempt = AttemptRecord {
+                number: record.attempts.len() as u32 + 1,
+                agent_ms: 0,
+                verification_ms: 0,
+                passed: false,
+                confidence: None,
+                errors: Vec::new(),
+                perf: None,
+                coverage: None,
+                autofixed: Vec::new(),
+                scoped_command: None,
+                flake: None,
+                result: None,
+                context: None,
+                snapshot: None,
+                agent: Some(record.agent.clone()),
             };
 
-            // Try to extract confidence from output
-            if let Some(c) = self.extract_confidence(&agent_output) {
-                final_confidence = c;
+            // Resuming mid-verification: the agent's changes are already in
+            if !std::mem::take(&mut verify_first) {
+                // 3. Generate Prompt. A stateful agent already has the task, so it only hears what went wrong.
+                let prompt = match previous_errors.last() {
+                    Some(last_error) if agent.is_stateful() && !full_prompt => follow_up_message(last_error),
+                    _ => {
+                        if attempts > 0 {
+                            let changed = context_cache.invalidate_changes();
+                            crate::progress!(">> Reusing cached context ({} changed file(s) re-read)", changed);
+                        }
+                        let prompt = self.generate_prompt(&mut context_cache, agent.image_mode(), &previous_errors)?;
+                        let hash = context_cache.content_hash(&self.ticket);
+                        if full_prompt && state.context_hash.as_ref().is_some_and(|h| *h != hash) {
+                            crate::progress!(">> Context files changed while the run was stopped; prompting with them as they are now");
+                        }
+                        state.context_hash = Some(hash);
+                        prompt
+                    }
+                };
+                full_prompt = false;
+                record.push_message(Role::User, &prompt);
+
+                // In a container the agent works on a scratch worktree; patches go there too,
+                // then everything is copied back to the host checkout.
+                let work_dir = container.as_ref().map_or(self.workspace_root, |c| c.worktree());
+
+                // 4. Run Agent & Capture Confidence
+                let agent_started = Instant::now();
+                let agent_result = agent.send(&prompt, &self.cancel);
+                attempt.agent_ms = agent_started.elapsed().as_millis() as u64;
+                if let Ok(reply) = &agent_result {
+                    record.push_message(Role::Agent, &redactor.redact(reply));
+                }
+
+                let (_agent_success, agent_output) = match agent_result {
+                    Ok(out) => (true, out),
+                    Err(e) if e.is::<Interrupted>() => {
+                        record.attempts.push(attempt);
+                        interrupted = true;
+                        break;
+                    }
+                    Err(e) => {
+                        agent_failed = true;
+                        let error = redactor.redact(&format!("Agent Execution Failed: {}", e));
+                        attempt.errors.push(error.clone());
+                        attempt.snapshot = self.snapshot_attempt(work_dir, &record.run_id, attempt.number);
+                        record.attempts.push(attempt);
+                        previous_errors.push(self.prompt_error(&config.context, summarizer.as_mut(), &redactor, error));
+                        attempts += 1;
+                        continue;
+                    }
+                };
+
+                agent_failed = false;
+
+                // A malformed result block fails the attempt so the agent gets to fix it
+                match crate::agent_result::parse(&agent_output) {
+                    Ok(Some(result)) => {
+                        final_confidence = result.confidence;
+                        attempt.confidence = Some(result.confidence);
+                        attempt.result = Some(result.clone());
+                        final_result = Some(result);
+                    }
+                    Ok(None) => {}
+                    Err(e) => {
+                        let error = redactor.redact(&format!("{:#}", e));
+                        crate::progress!(">> {}", error);
+                        attempt.errors.push(error.clone());
+                        attempt.snapshot = self.snapshot_attempt(work_dir, &record.run_id, attempt.number);
+                        record.attempts.push(attempt);
+                        previous_errors.push(self.prompt_error(&config.context, summarizer.as_mut(), &redactor, error));
+                        attempts += 1;
+                        if let Err(e) = run_store.save(&record) {
+                            eprintln!(">> Failed to save run record: {}", e);
+                        }
+                        continue;
+                    }
+                }
+
+                let mut applied: Result<()> = Ok(());
+                if self.patch_mode {
+                    applied = self.apply_agent_patch(&agent_output, work_dir);
+                }
+                if let Some(container) = container.as_ref().filter(|_| applied.is_ok()) {
+                    applied = container.sync_to_host(&self.cancel);
+                }
+                if applied.is_ok() && !config.protection.never_touch.is_empty() {
+                    applied = self.enforce_protection(&config, record.base_commit.as_deref(), container.as_deref());
+                }
+                if applied.is_ok() && !self.ticket.spec.allowed_paths.is_empty() {
+                    applied = self.check_allowed_paths(record.base_commit.as_deref());
+                }
+                match applied {
+                    Ok(()) => {}
+                    Err(e) if e.is::<Interrupted>() => {
+                        record.attempts.push(attempt);
+                        interrupted = true;
+                        break;
+                    }
+                    Err(e) => {
+                        let error = redactor.redact(&e.to_string());
+                        crate::progress!(">> {}", error);
+                        attempt.errors.push(error.clone());
+                        attempt.snapshot = self.snapshot_attempt(work_dir, &record.run_id, attempt.number);
+                        record.attempts.push(attempt);
+                        previous_errors.push(self.prompt_error(&config.context, summarizer.as_mut(), &redactor, error));
+                        attempts += 1;
+                        if let Err(e) = run_store.save(&record) {
+                            eprintln!(">> Failed to save run record: {}", e);
+                        }
+                        continue;
+                    }
+                }
+                save_checkpoint(&run_store, &mut state, Phase::Verification);
+            }
+
+            // What the agent changed against what it was shown, for discovery in later runs.
+            // Taken before verification, whose autofixes aren't the agent's doing.
+            if let Some(base) = record.base_commit.as_deref()
+                && let Ok(changed) = scope::changed_paths(self.workspace_root, base)
+            {
+                attempt.context = Some(ContextUsage::new(&context_cache.relevant_files(&self.ticket), &changed));
             }
 
-            // 5. Verification
-            match self.verify() {
+            // 5. Verification, scoped to the changes on every attempt but the last
+            attempt.scoped_command = match &self.ticket.verification.scoped {
+                Some(scoped) if attempts + 1 < max_retries => self.scoped_command(scoped, &mut context_cache, record.base_commit.as_deref()),
+                _ => None,
+            };
+            let verification_started = Instant::now();
+            let mut steps = Steps::default();
+            let scoped_command = attempt.scoped_command.clone();
+            let mut verification = self.verify(container.as_deref(), &verification_env, record.base_coverage, scoped_command.as_deref(), &mut attempt, &mut steps);
+            // A failure that goes away when nothing changed wasn't the agent's doing
+            let mut reruns = 0;
+            while reruns < config.verification.flaky_reruns && verification.as_ref().is_err_and(|e| !e.is::<Interrupted>()) {
+                reruns += 1;
+                crate::progress!(">> Verification failed; re-running to rule out a flaky test ({}/{})", reruns, config.verification.flaky_reruns);
+                steps = Steps::default();
+                let autofixed = std::mem::take(&mut attempt.autofixed);
+                verification = self.verify(container.as_deref(), &verification_env, record.base_coverage, scoped_command.as_deref(), &mut attempt, &mut steps);
+                // Fixes applied by the first run leave nothing for the re-run to fix
+                attempt.autofixed.splice(0..0, autofixed);
+                if verification.is_ok() {
+                    attempt.flake = Some(format!("failed, then passed on re-run {}", reruns));
+                    crate::progress!(">> Verification is flaky: {}", attempt.flake.as_deref().unwrap_or_default());
+                }
+            }
+            let known_flake = match &verification {
+                Err(e) if !e.is::<Interrupted>() => flaky::known_signature(&config.verification.flaky_patterns, &format!("{:#}", e)),
+                _ => None,
+            };
+            if let Some(pattern) = known_flake {
+                attempt.flake = Some(format!("matched known flaky pattern {}", pattern));
+            }
+            attempt.verification_ms = verification_started.elapsed().as_millis() as u64;
+            last_steps = steps.cases;
+            if !verification.as_ref().is_err_and(|e| e.is::<Interrupted>()) {
+                self.emit_verification(&record.run_id, verification.is_ok());
+            }
+
+            match verification {
                 Ok(_) => {
                     success = true;
-                    println!(">> Verification PASSED!");
+                    attempt.passed = true;
+                    record.attempts.push(attempt);
+                    crate::progress!(">> Verification PASSED!");
+                    break;
+                }
+                Err(e) if e.is::<Interrupted>() => {
+                    record.attempts.push(attempt);
+                    interrupted = true;
                     break;
                 }
                 Err(e) => {
-                    println!(">> Verification FAILED: {}", e);
-                    previous_errors.push(format!("Verification Failed:\n{}", e));
+                    let error = redactor.redact(&format!("Verification Failed:\n{}", e));
+                    crate::progress!(">> {}", error);
+                    attempt.errors.push(error.clone());
+                    attempt.snapshot = self.snapshot_attempt(self.workspace_root, &record.run_id, attempt.number);
+                    record.attempts.push(attempt);
+                    // The output of a known flake would only send the agent chasing it
+                    previous_errors.push(match known_flake {
+                        Some(pattern) => format!(
+                            "Verification Failed, matching the known flaky failure `{}`. This is probably not caused by your changes; check your work and try again.",
+                            pattern,
+                        ),
+                        None => self.prompt_error(&config.context, summarizer.as_mut(), &redactor, error),
+                    });
                     attempts += 1;
                 }
             }
+
+            if let Err(e) = run_store.save(&record) {
+                eprintln!(">> Failed to save run record: {}", e);
+            }
+        }
+
+        if success {
+            // One commit per run: checkpoints made by the lint autofix are folded back into it
+            let message = self.commit_message(&config, &record);
+            let base = match record.base_commit.clone() {
+                Some(base) => base,
+                None => self.head_commit()?,
+            };
+            record.commit = crate::commit::commit_run(self.workspace_root, &base, &message)
+                .context("Failed to commit agent changes")?;
+            match &record.commit {
+                Some(sha) => crate::progress!(">> Committed {} ({})", &sha[..sha.len().min(12)], message.lines().next().unwrap_or_default()),
+                None => crate::progress!(">> Verification passed but the agent changed nothing; no commit made"),
+            }
+        }
+
+        record.finished_at = Some(chrono::Utc::now());
+        record.outcome = if interrupted {
+            RunOutcome::Interrupted
+        } else if success {
+            RunOutcome::Succeeded
+        } else {
+            RunOutcome::Failed
+        };
+        if let Err(e) = run_store.save(&record) {
+            eprintln!(">> Failed to save run record: {}", e);
+        }
+        events::emit(self.workspace_root, PlanEvent::ExecutionFinished {
+            id: self.ticket.meta.id.clone(),
+            run_id: record.run_id.clone(),
+            outcome: record.outcome.clone(),
+            commit: record.commit.clone(),
+        });
+        if !interrupted {
+            self.write_report(last_steps, &previous_errors, success);
+            self.discard_checkpoint(&run_store);
+        }
+
+        if interrupted {
+            crate::progress!(">> Execution interrupted. Reverting to original state.");
+            // The agent's work goes into a commit that `--resume` restores
+            match self.snapshot(&record.run_id) {
+                Ok(snapshot) => {
+                    state.snapshot = Some(snapshot);
+                    let phase = state.phase;
+                    save_checkpoint(&run_store, &mut state, phase);
+                    crate::progress!(">> Progress saved; continue with `director-plan execute {} --resume`", self.ticket.meta.id);
+                }
+                Err(e) => {
+                    eprintln!(">> Failed to save the agent's work for --resume: {:#}", e);
+                    self.discard_checkpoint(&run_store);
+                }
+            }
+            self.reset_hard()?;
+            self.leave_detached_head(branch.as_deref())?;
+            return Err(anyhow::Error::new(Interrupted));
         }
 
         if success {
-            println!(">> Task Completed Successfully!");
-            // We stay in detached HEAD (or branch) as per previous logic, but Worker will push.
-            // Worker expects us to return.
+            if agent_index > 0 {
+                crate::progress!(">> Task Completed Successfully by fallback agent {}!", record.agent);
+            } else {
+                crate::progress!(">> Task Completed Successfully!");
+            }
+            // We stay in detached HEAD on the new commit; the Worker points its branch at it and pushes.
             Ok(ExecutionResult {
                 success: true,
                 confidence: final_confidence,
                 errors: previous_errors,
+                commit: record.commit.clone(),
+                run_id: record.run_id.clone(),
+                agent: record.agent.clone(),
+                agent_failed: false,
+                result: final_result,
             })
         } else {
-            println!(">> Max retries reached. Reverting to original state.");
+            crate::progress!(">> Max retries reached. Reverting to original state.");
             self.reset_hard()?;
-            self.leave_detached_head()?;
+            self.leave_detached_head(branch.as_deref())?;
             Ok(ExecutionResult {
                  success: false,
                  confidence: 0.0,
                  errors: previous_errors,
+                 commit: None,
+                 run_id: record.run_id.clone(),
+                 agent: record.agent.clone(),
+                 agent_failed,
+                 result: final_result,
             })
         }
     }
 
+    /// Runs verification again on the current HEAD, without the agent. Used by the worker after
+    /// rebasing a run's commit onto a moved base. The result is appended to run `run_id` as an
+    /// extra attempt, and `commit` is moved to HEAD if it passes. Coverage is still compared with
+    /// the run's original baseline.
+    pub fn reverify(&self, run_id: &str) -> Result<()> {
+        let run_store = RunStore::new(self.workspace_root);
+        let mut record = run_store.list_for_ticket(&self.ticket.meta.id)?
+            .into_iter()
+            .find(|r| r.run_id == run_id)
+            .ok_or_else(|| anyhow!("No run record {}", run_id))?;
+
+        let config = PlanConfig::load(self.workspace_root)?;
+        let env = ResolvedEnv::resolve(self.workspace_root, &self.ticket.verification.env)?;
+        let container = if config.container.enabled {
+            Some(ContainerBackend::start(self.workspace_root, &config.container, &format!("{}-reverify", run_id))?)
+        } else {
+            None
+        };
+
+        let mut attempt = AttemptRecord {
+            number: record.attempts.len() as u32 + 1,
+            agent_ms: 0,
+            verification_ms: 0,
+            passed: false,
+            confidence: None,
+            errors: Vec::new(),
+            perf: None,
+            coverage: None,
+            autofixed: Vec::new(),
+            scoped_command: None,
+            flake: None,
+            result: None,
+            context: None,
+            snapshot: None,
+            agent: None,
+        };
+        let started = Instant::now();
+        let mut steps = Steps::default();
+        let verification = self.verify(container.as_ref(), &env, record.base_coverage, None, &mut attempt, &mut steps);
+        attempt.verification_ms = started.elapsed().as_millis() as u64;
+        let errors: Vec<String> = verification.as_ref().err().map(|e| e.to_string()).into_iter().collect();
+        self.write_report(steps.cases, &errors, verification.is_ok());
+        self.emit_verification(run_id, verification.is_ok());
+
+        match &verification {
+            Ok(()) => {
+                attempt.passed = true;
+                record.commit = self.head_commit().ok();
+            }
+            Err(e) => attempt.errors.push(env.redact(&format!("Verification Failed after rebase:\n{}", e))),
+        }
+        record.attempts.push(attempt);
+        if let Err(e) = run_store.save(&record) {
+            eprintln!(">> Failed to save run record: {}", e);
+        }
+        verification
+    }
+
+    fn emit_verification(&self, run_id: &str, passed: bool) {
+        events::emit(self.workspace_root, PlanEvent::VerificationFinished {
+            id: self.ticket.meta.id.clone(),
+            run_id: Some(run_id.to_string()),
+            passed,
+        });
+    }
+
     // Legacy run for CLI compatibility if needed
     pub fn run(&mut self) -> Result<()> {
         let res = self.run_with_handshake()?;
@@ -117,12 +969,104 @@ impl<'a> ExecutionLoop<'a> {
         }
     }
 
+    /// Reverts any change to a `[protection] never_touch` path (on the host, and in the container's
+    /// worktree so the next sync doesn't bring it back) and fails the attempt.
+    fn enforce_protection(&self, config: &PlanConfig, base_commit: Option<&str>, container: Option<&ContainerBackend>) -> Result<()> {
+        let base = match base_commit {
+            Some(base) => base.to_string(),
+            None => self.head_commit()?,
+        };
+        let changed = scope::changed_paths(self.workspace_root, &base)?;
+        let touched = scope::protected(&config.protection.never_touch, &changed)?;
+        if touched.is_empty() {
+            return Ok(());
+        }
+
+        scope::revert(self.workspace_root, &base, &touched)?;
+        if let Some(container) = container {
+            scope::revert(container.worktree(), &base, &touched)?;
+        }
+        Err(anyhow!(
+            "Protected Path Violation: {} file(s) match [protection] never_touch ({}) and were reverted:\n- {}\nThese paths must never be modified. Make the change without them.",
+            touched.len(),
+            config.protection.never_touch.join(", "),
+            touched.join("\n- ")
+        ))
+    }
+
+    /// Fails if the agent touched files outside `spec.allowed_paths`, naming each one so the
+    /// retry prompt says exactly what to revert.
+    fn check_allowed_paths(&self, base_commit: Option<&str>) -> Result<()> {
+        let base = match base_commit {
+            Some(base) => base.to_string(),
+            None => self.head_commit()?,
+        };
+        let changed = scope::changed_paths(self.workspace_root, &base)?;
+        let outside = scope::violations(&self.ticket.spec.allowed_paths, &changed)?;
+        if outside.is_empty() {
+            return Ok(());
+        }
+        Err(anyhow!(
+            "Scope Violation: {} file(s) changed outside spec.allowed_paths ({}):\n- {}\nRevert these files; only paths matching the allowed globs may be modified.",
+            outside.len(),
+            self.ticket.spec.allowed_paths.join(", "),
+            outside.join("\n- ")
+        ))
+    }
+
+    /// `[commit] template` for this ticket, with the autofixes and run id in the body.
+    fn commit_message(&self, config: &PlanConfig, record: &RunRecord) -> String {
+        let mut message = crate::commit::render_message(&config.commit.template, &self.ticket);
+        message.push_str("\n\n");
+        let mut autofixed: Vec<&str> = Vec::new();
+        for step in record.attempts.iter().flat_map(|a| a.autofixed.iter()) {
+            if !autofixed.contains(&step.as_str()) {
+                autofixed.push(step);
+            }
+        }
+        if !autofixed.is_empty() {
+            message.push_str(&format!("Autofixes applied: {}\n", autofixed.join(", ")));
+        }
+        message.push_str(&format!("Run: {}\n", record.run_id));
+        message
+    }
+
     fn is_git_dirty(&self) -> Result<bool> {
+        is_dirty(self.workspace_root)
+    }
+
+    /// Stashes everything but plan/, untracked files included.
+    fn stash(&self) -> Result<Stash<'a>> {
+        let message = format!("director-plan: before executing {}", self.ticket.meta.id);
+        let status = Command::new("git")
+            .current_dir(self.workspace_root)
+            .args(["stash", "push", "-q", "--include-untracked", "-m", &message, "--", ".", ":(exclude)plan"])
+            .status()
+            .context("Failed to run git stash")?;
+        if !status.success() {
+            return Err(anyhow!("git stash failed"));
+        }
         let output = Command::new("git")
             .current_dir(self.workspace_root)
-            .args(&["status", "--porcelain"])
-            .output()?;
-        Ok(!output.stdout.is_empty())
+            .args(["rev-parse", "-q", "--verify", "refs/stash"])
+            .output()
+            .context("Failed to run git rev-parse")?;
+        if !output.status.success() {
+            return Err(anyhow!("git stash left nothing in the stash"));
+        }
+        Ok(Stash { root: self.workspace_root, commit: String::from_utf8_lossy(&output.stdout).trim().to_string(), message })
+    }
+
+    fn head_commit(&self) -> Result<String> {
+        let output = Command::new("git")
+            .current_dir(self.workspace_root)
+            .args(["rev-parse", "HEAD"])
+            .output()
+            .context("Failed to run git rev-parse")?;
+        if !output.status.success() {
+            return Err(anyhow!("git rev-parse HEAD failed"));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
     }
 
     fn enter_detached_head(&self) -> Result<()> {
@@ -134,148 +1078,208 @@ impl<'a> ExecutionLoop<'a> {
         Ok(())
     }
 
-    fn leave_detached_head(&self) -> Result<()> {
+    /// Back to `branch`, or to wherever HEAD was before it was detached.
+    fn leave_detached_head(&self, branch: Option<&str>) -> Result<()> {
          Command::new("git")
             .current_dir(self.workspace_root)
             .arg("checkout")
-            .arg("-")
+            .arg(branch.unwrap_or("-"))
             .status()
             .context("Failed to leave detached HEAD")?;
         Ok(())
     }
 
-    fn reset_hard(&self) -> Result<()> {
-        Command::new("git")
+    /// The branch HEAD is on; `None` when it's detached.
+    fn current_branch(&self) -> Option<String> {
+        let output = Command::new("git")
             .current_dir(self.workspace_root)
-            .args(&["reset", "--hard"])
+            .args(["symbolic-ref", "--short", "-q", "HEAD"])
+            .output()
+            .ok()?;
+        let branch = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        (output.status.success() && !branch.is_empty()).then_some(branch)
+    }
+
+    /// Commits the agent's work so far on the detached HEAD and keeps the commit under
+    /// `refs/director/checkpoints/<ticket-id>`, out of reach of `git gc`, for `--resume`.
+    fn snapshot(&self, run_id: &str) -> Result<String> {
+        self.commit_all(&format!("wip({}): checkpoint of run {}", self.ticket.meta.id, run_id))?;
+        let head = self.head_commit()?;
+        let status = Command::new("git")
+            .current_dir(self.workspace_root)
+            .args(["update-ref", &self.checkpoint_ref(), &head])
             .status()
-            .context("Failed to hard reset")?;
+            .context("Failed to run git update-ref")?;
+        if !status.success() {
+            return Err(anyhow!("git update-ref failed"));
+        }
+        Ok(head)
+    }
+
+    /// Keeps the tree in `dir` as a failed attempt left it, as a commit on top of HEAD under
+    /// [`attempt_ref`], so it can be inspected after the run resets. HEAD, the index and the
+    /// files are left as they are. `None` when the agent changed nothing, or if git fails.
+    fn snapshot_attempt(&self, dir: &Path, run_id: &str, number: u32) -> Option<String> {
+        let git = |args: &[&str], index: Option<&str>| -> Option<String> {
+            let mut cmd = Command::new("git");
+            cmd.current_dir(dir).args(args);
+            if let Some(index) = index {
+                cmd.env("GIT_INDEX_FILE", index);
+            }
+            let output = cmd.output().ok()?;
+            output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        };
+
+        // A scratch index, so staging everything doesn't disturb the real one
+        let index = git(&["rev-parse", "--git-path", "director-attempt.index"], None)?;
+        let index = dir.join(index).to_string_lossy().to_string();
+        let tree = git(&["read-tree", "HEAD"], Some(&index))
+            .and_then(|_| git(&["add", "-A", "--", ".", ":(exclude)plan"], Some(&index)))
+            .and_then(|_| git(&["write-tree"], Some(&index)));
+        let _ = fs::remove_file(&index);
+        let tree = tree?;
+        if git(&["rev-parse", "HEAD^{tree}"], None)? == tree {
+            return None;
+        }
+
+        let message = format!("wip({}): attempt {} of run {}", self.ticket.meta.id, number, run_id);
+        let commit = git(&["commit-tree", &tree, "-p", "HEAD", "-m", &message], None)?;
+        git(&["update-ref", &attempt_ref(run_id, number), &commit], None)?;
+        crate::progress!(">> Attempt {} kept as {}", number, attempt_ref(run_id, number));
+        Some(commit)
+    }
+
+    /// Detaches HEAD at the commit an interrupted run's work was saved in.
+    fn checkout_snapshot(&self, snapshot: &str) -> Result<()> {
+        let status = Command::new("git")
+            .current_dir(self.workspace_root)
+            .args(["checkout", "-q", "--detach", snapshot])
+            .status()
+            .context("Failed to check out the saved work")?;
+        if !status.success() {
+            return Err(anyhow!("git checkout {} failed; the interrupted run's work can't be restored", snapshot));
+        }
         Ok(())
     }
 
-    fn generate_prompt(&self, errors: &[String]) -> Result<String> {
-        let mut relevant_files = self.ticket.spec.relevant_files.clone();
+    /// Forgets the ticket's checkpoint and the work saved with it.
+    fn discard_checkpoint(&self, run_store: &RunStore) {
+        if let Err(e) = run_store.remove_checkpoint(&self.ticket.meta.id) {
+            eprintln!(">> {:#}", e);
+        }
+        // Nothing to delete unless a run was interrupted
+        let _ = Command::new("git")
+            .current_dir(self.workspace_root)
+            .args(["update-ref", "-d", &self.checkpoint_ref()])
+            .output();
+    }
+
+    fn checkpoint_ref(&self) -> String {
+        format!("refs/director/checkpoints/{}", self.ticket.meta.id)
+    }
 
-        // If discovery returns files, we assume full content for now, unless we switch to AST engine directly.
-        // `discover_context` handles the AST expansion logic now.
-        if relevant_files.is_empty() || self.ticket.spec.auto_context {
-             // Append discovered files (unique)
-             let discovered = discover_context(&self.ticket, self.workspace_root);
-             for f in discovered {
-                 if !relevant_files.contains(&f) {
-                     relevant_files.push(f);
-                 }
-             }
+    /// Hands the run to the next agent in the chain: the last one's changes are discarded, on
+    /// the host and in the container's worktree, and HEAD goes back to `base`, past any commits
+    /// the lint autofix made.
+    fn restart(&self, base: Option<&str>, container: Option<&ContainerBackend>) -> Result<()> {
+        self.reset_hard()?;
+        if let Some(base) = base {
+            let status = Command::new("git")
+                .current_dir(self.workspace_root)
+                .args(["checkout", "-q", "--detach", base])
+                .status()
+                .context("Failed to check out the base commit")?;
+            if !status.success() {
+                return Err(anyhow!("git checkout {} failed; the run can't go on with the next agent", base));
+            }
+        }
+        if let Some(container) = container {
+            container.reset()?;
         }
+        Ok(())
+    }
+
+    /// See [`reset_tree`].
+    fn reset_hard(&self) -> Result<()> {
+        reset_tree(self.workspace_root)
+    }
+
+    fn generate_prompt(&self, cache: &mut ContextCache, images: ImageMode, errors: &[String]) -> Result<String> {
+        let relevant_files = cache.relevant_files(&self.ticket);
 
         let mut context_content = String::new();
+        for (path, content) in cache.file_contents(&self.ticket) {
+            context_content.push_str(&format!("--- FILE: {} ---\n", path));
+            context_content.push_str(&content);
+            context_content.push_str("\n\n");
+        }
 
-        if self.ticket.spec.auto_context {
-             // Build graph and get content map
-             let mut graph = crate::context::ast::DependencyGraph::new(self.workspace_root);
-             if let Ok(_) = graph.build() {
-                 let seeds = if self.ticket.spec.relevant_files.is_empty() {
-                      // Need heuristic seeds to start graph walk if discover_context was just paths
-                      // But wait, discover_context called above already gave us "relevant_files" which ARE the result of the AST walk in `discovery.rs`.
-                      // So `relevant_files` contains ALL files we want.
-                      // We just need to prune them if they are depth >= 2.
-
-                      // But `discover_context` doesn't return depth info.
-                      // So we must re-run `get_context` using original seeds?
-
-                      // This duplication suggests `discover_context` should maybe return the content directly or we rely on `execution_loop` to drive it.
-                      // But `discovery.rs` is shared by CLI context command.
-
-                      // To make this work without huge refactor, I will just iterate relevant_files and prune if needed using a simpler heuristic or just load full content.
-                      // "Files 2 hops away get only type signatures" - I can't know hops without graph.
-
-                      // Let's rely on `graph.get_context` again using the ORIGINAL seeds (before expansion).
-                      let original_seeds = self.ticket.spec.relevant_files.clone();
-                      let seeds = if original_seeds.is_empty() {
-                           // If original seeds empty, we used heuristic seeds.
-                           // We can re-derive them or assume we want everything in `relevant_files` (which is expanded).
-                           // If we use expanded list as seeds, depth is 0 for all. No pruning.
-
-                           // So we need the heuristic seeds again.
-                           // This is getting messy.
-                           vec![] // Force heuristic again inside get_context? No.
-                      } else {
-                           original_seeds
-                      };
-
-                      if seeds.is_empty() {
-                           // Re-run heuristic discovery (not AST) to get seeds
-                           // This functionality is private in `discovery.rs`.
-                           // I'll just use the `relevant_files` (which are expanded) and skip pruning.
-                           // It's a safe fallback.
-                           relevant_files.clone()
-                      } else {
-                           seeds
-                      }
-                 } else {
-                      self.ticket.spec.relevant_files.clone()
-                 };
-
-                 // If we have valid seeds, `get_context` will give us pruned content.
-                 // Note: If seeds came from `discovery.rs` (heuristic), we don't have them isolated here easily.
-                 // I'll just use `relevant_files` and load content.
-                 // Pruning is disabled for implicit context for now to avoid complexity.
-
-                 // However, if the user explicitly provided `relevant_files` AND `auto_context=true`, pruning works.
-                 if !self.ticket.spec.relevant_files.is_empty() {
-                     let context_pairs = graph.get_context(&self.ticket.spec.relevant_files);
-                     for (path, content) in context_pairs {
-                          context_content.push_str(&format!("--- FILE: {} ---\n", path));
-                          context_content.push_str(&content);
-                          context_content.push_str("\n\n");
-                     }
-                     // Clear relevant_files so we don't double add below?
-                     // We need to ensure we don't duplicate.
-                     // The loop below handles fallback.
-                     // Let's set a flag or just use `context_content`.
-                 } else {
-                      // Implicit context - Load all discovered files fully.
-                      for file in &relevant_files {
-                        let path = self.workspace_root.join(file);
-                        if path.exists() {
-                            context_content.push_str(&format!("--- FILE: {} ---\n", file));
-                            context_content.push_str(&fs::read_to_string(path).unwrap_or_default());
-                            context_content.push_str("\n\n");
-                        }
-                    }
-                 }
-             } else {
-                 // Fallback
-                 for file in &relevant_files {
-                    let path = self.workspace_root.join(file);
-                    if path.exists() {
-                        context_content.push_str(&format!("--- FILE: {} ---\n", file));
-                        context_content.push_str(&fs::read_to_string(path).unwrap_or_default());
-                        context_content.push_str("\n\n");
-                    }
-                }
-             }
-        } else {
-             // Legacy behavior
-            for file in &relevant_files {
-                let path = self.workspace_root.join(file);
-                if path.exists() {
-                    context_content.push_str(&format!("--- FILE: {} ---\n", file));
-                    context_content.push_str(&fs::read_to_string(path).unwrap_or_default());
-                    context_content.push_str("\n\n");
+        let mut prompt = String::new();
+        let config = PlanConfig::load(self.workspace_root)?;
+        let context = match config.context.profile(&self.ticket)? {
+            Some((_, profile)) => config.context.with_profile(profile),
+            None => config.context.clone(),
+        };
+        if context.overview {
+            // Saves the agent turns spent exploring the repository
+            match overview::workspace_overview(self.workspace_root, context.overview_depth) {
+                Ok(overview) => {
+                    prompt.push_str(&overview);
+                    prompt.push('\n');
                 }
+                Err(e) => crate::progress!(">> Workspace overview failed: {}", e),
             }
         }
-
-        let mut prompt = String::new();
         prompt.push_str(&format!("# Task: {}\n\n", self.ticket.meta.title));
         prompt.push_str(&format!("## Description\n{}\n\n", self.ticket.spec.description));
         prompt.push_str(&format!("## Constraints\n{:?}\n\n", self.ticket.spec.constraints));
+        if !self.ticket.spec.allowed_paths.is_empty() {
+            prompt.push_str(&format!("## Allowed Paths\nOnly change files matching: {}\n\n", self.ticket.spec.allowed_paths.join(", ")));
+        }
+        match CommentStore::new(self.workspace_root).list(&self.ticket.meta.id) {
+            Ok(comments) => {
+                let discussion = render_discussion(&comments);
+                if !discussion.is_empty() {
+                    prompt.push_str(&discussion);
+                    prompt.push('\n');
+                }
+            }
+            Err(e) => crate::progress!(">> Failed to read comments: {}", e),
+        }
+        prompt.push_str(&images::render_assets(&self.ticket, self.workspace_root, images));
 
         if !context_content.is_empty() {
              prompt.push_str("# Context\n");
              prompt.push_str(&context_content);
         }
+        prompt.push_str(&images::render_referenced(self.workspace_root, &cache.referenced_assets(&self.ticket)));
+
+        if context.docs {
+            let docs = docs::render_docs(&self.ticket, self.workspace_root);
+            if !docs.is_empty() {
+                prompt.push('\n');
+                prompt.push_str(&docs);
+            }
+        }
+
+        if context.schemas {
+            let schemas = schema::render_schemas(&self.ticket, self.workspace_root);
+            if !schemas.is_empty() {
+                prompt.push('\n');
+                prompt.push_str(&schemas);
+            }
+        }
+
+        if context.git_history_depth > 0 {
+            prompt.push_str(&history::render_history(self.workspace_root, &relevant_files, context.git_history_depth));
+        }
+
+        if self.patch_mode {
+            prompt.push('\n');
+            prompt.push_str(patch::PATCH_INSTRUCTIONS);
+        }
+        prompt.push('\n');
+        prompt.push_str(RESULT_INSTRUCTIONS);
 
         if !errors.is_empty() {
             prompt.push_str("\n# Previous Errors (FIX THESE)\n");
@@ -287,85 +1291,489 @@ impl<'a> ExecutionLoop<'a> {
         Ok(prompt)
     }
 
-    fn run_agent_capture(&self, prompt: &str) -> Result<String> {
-        let mut child = Command::new("sh")
-            .arg("-c")
-            .arg(&self.agent_cmd)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped()) // Capture stdout now
-            .stderr(Stdio::piped())
-            .spawn()
-            .context("Failed to spawn agent command")?;
+    /// `error` as later prompts see it: condensed per `[context] summarize_errors`, then cut
+    /// to `max_error_chars`. The run record keeps the original.
+    fn prompt_error(&self, config: &ContextConfig, summarizer: Option<&mut ShellAgent>, redactor: &ResolvedEnv, error: String) -> String {
+        let length = error.chars().count();
+        let condensed = match (config.summarize_errors, summarizer) {
+            (ErrorSummary::None, _) => error,
+            (ErrorSummary::Agent, Some(summarizer)) if config.max_error_chars > 0 && length > config.max_error_chars => {
+                crate::progress!(">> Summarizing a {}-character error for the next prompt", length);
+                match summarizer.send(&summary_request(&error), &self.cancel) {
+                    Ok(summary) if !summary.trim().is_empty() => {
+                        let headline = error.lines().next().unwrap_or_default();
+                        format!("{} (summarized)\n{}", headline.trim_end_matches(':'), redactor.redact(summary.trim()))
+                    }
+                    Ok(_) => diagnostics::summarize(&error),
+                    Err(e) => {
+                        crate::progress!(">> Summarizing failed ({:#}); extracting errors instead", e);
+                        diagnostics::summarize(&error)
+                    }
+                }
+            }
+            _ => diagnostics::summarize(&error),
+        };
+        diagnostics::truncate_middle(&condensed, config.max_error_chars)
+    }
 
-        if let Some(mut stdin) = child.stdin.take() {
-            stdin.write_all(prompt.as_bytes())?;
+    fn apply_agent_patch(&self, output: &str, work_dir: &Path) -> Result<()> {
+        let diff = patch::extract_diff(output)
+            .ok_or_else(|| anyhow!("Patch Rejected:\nNo ```diff block found in agent output"))?;
+        match patch::apply_patch(work_dir, &diff, &self.cancel) {
+            Ok(()) => {
+                crate::progress!(">> Patch applied.");
+                Ok(())
+            }
+            Err(e) if e.is::<Interrupted>() => Err(e),
+            Err(e) => Err(anyhow!("Patch Rejected:\n{}", e)),
         }
+    }
 
-        let output = child.wait_with_output()?;
+    /// Runs each verification step the ticket asks for, recording them in `steps`. Stops at the
+    /// first failure; the steps after it are recorded as skipped. With a `scoped` command, that
+    /// runs in place of `verification.command`, and the full command runs last.
+    fn verify(
+        &self,
+        container: Option<&ContainerBackend>,
+        env: &ResolvedEnv,
+        base_coverage: Option<f64>,
+        scoped: Option<&str>,
+        attempt: &mut AttemptRecord,
+        steps: &mut Steps,
+    ) -> Result<()> {
+        // Loaded here rather than up-front so a broken config fails the run instead of
+        // silently dropping the policy.
+        let config = PlanConfig::load(self.workspace_root)?;
+        let verification = &self.ticket.verification;
 
-        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-        // Also print to user for visibility (tee)
-        println!("{}", stdout);
+        // 0. Lint / Format Gate
+        steps.run("lint", !config.lint.steps.is_empty(), || {
+            attempt.autofixed = self.run_lint(&config, container, env)?;
+            Ok(())
+        });
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(anyhow!("Agent exited with status {}: {}", output.status, stderr));
+        // 1. Verification command and `[[verification.steps]]`
+        let mut graph = verification.command_steps();
+        if let Some(scoped) = scoped
+            && let Some(command) = graph.iter_mut().find(|step| step.name == verification_steps::COMMAND_STEP)
+        {
+            crate::progress!(">> Scoped verification: {}", scoped);
+            command.command = scoped.to_string();
         }
+        // Nothing to run isn't a pass; `verify` refuses such tickets too
+        steps.run(verification_steps::COMMAND_STEP, graph.is_empty(), || Err(anyhow!("Verification command is empty")));
+        let names: Vec<String> = graph.iter().map(|step| step.name.clone()).collect();
+        steps.run_all(&names, || {
+            let (workspace_root, cancel) = (self.workspace_root, &self.cancel);
+            let runs = verification_steps::run(&graph, config.verification.step_jobs(), |step| {
+                if step.name != verification_steps::COMMAND_STEP {
+                    crate::progress!(">> Verification step: {}", step.name);
+                }
+                let cmd = checked_command(workspace_root, &config, container, env, &step.command)?;
+                run_cancellable(cmd, None, cancel).context("Failed to execute verification command")
+            })?;
+            Ok(runs.iter().map(StepRun::test_case).collect())
+        });
 
-        Ok(stdout)
-    }
+        // 2. Visual Verification
+        if let Some(golden_image) = &verification.golden_image {
+            steps.run("visual: golden image", true, || {
+                let report = verify_visual(self.workspace_root, verification, golden_image, &config.visual)?;
+                if report.diff_detected && !self.offer_new_baseline(&report, None, golden_image)? {
+                    return Err(anyhow!("Visual Verification Failed: {}\nDiff Bounds: {:?}\nReason: {:?}",
+                        report.mismatch_percentage, report.diff_bounds, report.reason));
+                }
+                Ok(())
+            });
+        }
 
-    fn extract_confidence(&self, output: &str) -> Option<f32> {
-        let json_start = output.find('{')?;
-        let json_end = output.rfind('}')?;
+        steps.run("visual: snapshots", !verification.visual.is_empty(), || {
+            let reports = verify_snapshots(self.workspace_root, verification, &config.visual)?;
+            let report_path = self.workspace_root.join("proof/visual-report.json");
+            if let Err(e) = fs::write(&report_path, serde_json::to_string_pretty(&reports)?) {
+                crate::progress!(">> Warning: failed to write {:?}: {}", report_path, e);
+            }
 
-        if json_start < json_end {
-            let json_str = &output[json_start..=json_end];
-            if let Ok(val) = serde_json::from_str::<serde_json::Value>(json_str) {
-                if let Some(c) = val.get("confidence").and_then(|v| v.as_f64()) {
-                    return Some(c as f32);
+            let mut accepted = Vec::new();
+            for r in reports.iter().filter(|r| r.report.diff_detected) {
+                let golden = verification.visual.iter()
+                    .find(|s| s.name == r.name)
+                    .map(|s| s.golden.as_str())
+                    .unwrap_or_default();
+                if self.offer_new_baseline(&r.report, Some(&r.name), golden)? {
+                    accepted.push(r.name.clone());
                 }
             }
+            let failures: Vec<String> = reports.iter()
+                .filter(|r| r.report.diff_detected && !accepted.contains(&r.name))
+                .map(|r| format!(
+                    "- {} ({}): {:.2}% mismatch, bounds {:?}, reason {:?}",
+                    r.name,
+                    r.viewport.as_deref().unwrap_or("default viewport"),
+                    r.report.mismatch_percentage,
+                    r.report.diff_bounds,
+                    r.report.reason,
+                ))
+                .collect();
+            if !failures.is_empty() {
+                return Err(anyhow!("Visual Verification Failed for {} of {} snapshots:\n{}",
+                    failures.len(), reports.len(), failures.join("\n")));
+            }
+            Ok(())
+        });
+
+        // 3. Structural Verification
+        steps.run("structure", !verification.structure.is_empty(), || {
+            let reports = verify_structures(self.workspace_root, verification, &config.visual)?;
+            let report_path = self.workspace_root.join("proof/structure-report.json");
+            if let Err(e) = fs::write(&report_path, serde_json::to_string_pretty(&reports)?) {
+                crate::progress!(">> Warning: failed to write {:?}: {}", report_path, e);
+            }
+
+            // Cap the list so one broken layout doesn't flood the retry prompt
+            const MAX_LISTED: usize = 30;
+            let mut lines = Vec::new();
+            let mut total = 0;
+            for report in &reports {
+                for change in &report.changes {
+                    total += 1;
+                    if lines.len() < MAX_LISTED {
+                        lines.push(format!(
+                            "- [{}] {:?} {}{}",
+                            report.name,
+                            change.kind,
+                            change.path,
+                            change.detail.as_deref().map(|d| format!(" ({})", d)).unwrap_or_default(),
+                        ));
+                    }
+                }
+            }
+            if total > 0 {
+                if total > lines.len() {
+                    lines.push(format!("- ... and {} more", total - lines.len()));
+                }
+                return Err(anyhow!("Structural Verification Failed: {} node(s) differ from the golden tree:\n{}",
+                    total, lines.join("\n")));
+            }
+            Ok(())
+        });
+
+        // 4. Coverage Gate
+        if let Some(min_delta) = verification.min_coverage_delta {
+            steps.run("coverage", true, || {
+                let current = self.measure_coverage(&config, container, env)?;
+                crate::progress!(">> Coverage: {:.2}%", current);
+                attempt.coverage = Some(current);
+                let base = base_coverage.ok_or_else(|| anyhow!("No baseline coverage for the base commit"))?;
+                coverage::check_delta(base, current, min_delta)
+            });
         }
 
-        // Fallback: look for "confidence": 0.xx
-        let re = regex::Regex::new(r#""confidence"\s*:\s*([0-9.]+)"#).ok()?;
-        if let Some(caps) = re.captures(output) {
-            if let Ok(c) = caps[1].parse::<f32>() {
-                return Some(c);
+        // 5. Performance Budget
+        if let Some(budget) = &verification.perf {
+            steps.run("perf", true, || {
+                let sample = perf::measure(self.workspace_root, verification, budget, &config.visual)?;
+                crate::progress!(">> Perf: LCP {:?} ms, load {:?} ms, bundle {:?} KB", sample.lcp_ms, sample.load_ms, sample.bundle_kb);
+                let violations = perf::violations(budget, &sample);
+                attempt.perf = Some(sample);
+                if !violations.is_empty() {
+                    return Err(anyhow!("Performance Budget Exceeded:\n- {}", violations.join("\n- ")));
+                }
+                Ok(())
+            });
+        }
+
+        // 6. The full command, once the scoped one passed
+        steps.run("command: full suite", scoped.is_some(), || {
+            crate::progress!(">> Scoped verification passed; running the full suite");
+            let cmd = checked_command(self.workspace_root, &config, container, env, &verification.command)?;
+            let output = run_cancellable(cmd, None, &self.cancel)
+                .context("Failed to execute verification command")?;
+            if !output.status.success() {
+                return Err(anyhow!("Full Suite Failed (the scoped tests passed):\nSTDOUT:\n{}\nSTDERR:\n{}",
+                    String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr)));
             }
+            Ok(())
+        });
+
+        steps.finish()
+    }
+
+    /// A size mismatch can't be fixed by retrying, so offer to take the new screenshot as the
+    /// golden instead. Returns whether it was accepted.
+    fn offer_new_baseline(&self, report: &VisualDiffReport, snapshot: Option<&str>, golden: &str) -> Result<bool> {
+        if !report.is_dimension_mismatch() {
+            return Ok(false);
         }
+        let question = format!(
+            "{} ({}). Accept the new screenshot as the golden image?",
+            report.reason.as_deref().unwrap_or_default(),
+            snapshot.unwrap_or(golden),
+        );
+        if !self.confirm.ask(&question) {
+            return Ok(false);
+        }
+        let actual = match snapshot {
+            Some(name) => format!("proof/{}.png", name),
+            None => "proof/actual.png".to_string(),
+        };
+        golden::promote(self.workspace_root, &self.ticket, &[golden::Promotion {
+            name: snapshot.map(str::to_string),
+            actual,
+            golden: golden.to_string(),
+        }])?;
+        Ok(true)
+    }
 
-        None
+    /// The command covering only what the agent's changes reach, for a ticket with
+    /// `[verification.scoped]`. `None` means verify with the full command: nothing to scope
+    /// to, or git couldn't say what changed.
+    fn scoped_command(&self, scoped: &ScopedVerification, context_cache: &mut ContextCache, base_commit: Option<&str>) -> Option<String> {
+        if self.ticket.verification.command.trim().is_empty() {
+            return None;
+        }
+        let changed = match scope::changed_paths(self.workspace_root, base_commit.unwrap_or("HEAD")) {
+            Ok(changed) => changed,
+            Err(e) => {
+                crate::progress!(">> Running the full suite: {}", e);
+                return None;
+            }
+        };
+        // Picks up imports the agent added this attempt
+        context_cache.invalidate_changes();
+        let affected = verification_scoped::affected(self.workspace_root, context_cache.graph(), &changed);
+        let command = verification_scoped::render(scoped, &affected);
+        if command.is_none() {
+            crate::progress!(">> Nothing to scope verification to; running the full suite");
+        }
+        command
     }
 
-    fn verify(&self) -> Result<()> {
-        // 1. Run Verification Command
-        let cmd_str = &self.ticket.verification.command;
-        if !cmd_str.is_empty() {
-             let output = Command::new("sh")
-                .arg("-c")
-                .arg(cmd_str)
-                .current_dir(self.workspace_root)
-                .output()
-                .context("Failed to execute verification command")?;
+    /// Runs each `[[lint.steps]]` check. In fix mode a failing check gets the step's autofix and
+    /// is checked again. Returns the steps that were fixed.
+    fn run_lint(&self, config: &PlanConfig, container: Option<&ContainerBackend>, env: &ResolvedEnv) -> Result<Vec<String>> {
+        let mut fixed = Vec::new();
+        let mut checkpointed = false;
 
-            if !output.status.success() {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                return Err(anyhow!("Command Failed:\nSTDOUT:\n{}\nSTDERR:\n{}", stdout, stderr));
+        for step in &config.lint.steps {
+            let mut output = run_cancellable(checked_command(self.workspace_root, config, container, env, &step.check)?, None, &self.cancel)
+                .with_context(|| format!("Failed to execute lint step {}", step.name))?;
+            if output.status.success() {
+                continue;
             }
+
+            if let (LintMode::Fix, Some(fix)) = (config.lint.mode, &step.fix) {
+                // Outside a container, the agent's work so far goes into its own commit first,
+                // so the autofix commit holds only the fixes.
+                if container.is_none() && !checkpointed {
+                    self.commit_all(&format!("wip({}): agent changes before autofix", self.ticket.meta.id))?;
+                    checkpointed = true;
+                }
+                crate::progress!(">> Lint step '{}' failed; applying autofix", step.name);
+                run_cancellable(checked_command(self.workspace_root, config, container, env, fix)?, None, &self.cancel)
+                    .with_context(|| format!("Failed to execute autofix for {}", step.name))?;
+                output = run_cancellable(checked_command(self.workspace_root, config, container, env, &step.check)?, None, &self.cancel)
+                    .with_context(|| format!("Failed to execute lint step {}", step.name))?;
+                if output.status.success() {
+                    fixed.push(step.name.clone());
+                    continue;
+                }
+            }
+
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            return Err(anyhow!("Lint Failed ({}):\nSTDOUT:\n{}\nSTDERR:\n{}", step.name, stdout, stderr));
         }
 
-        // 2. Visual Verification
-        if let Some(golden_image) = &self.ticket.verification.golden_image {
-             let report = verify_visual(self.workspace_root, golden_image)?;
-             if report.diff_detected {
-                 return Err(anyhow!("Visual Verification Failed: {}\nDiff Bounds: {:?}\nReason: {:?}",
-                    report.mismatch_percentage, report.diff_bounds, report.reason));
-             }
+        if !fixed.is_empty() {
+            match container {
+                // The container worktree stays on the base commit, so fixes travel as working-tree changes
+                Some(container) => container.sync_to_host(&self.cancel)?,
+                None => self.commit_all(&format!("style({}): apply autofixes ({})", self.ticket.meta.id, fixed.join(", ")))?,
+            }
+            crate::progress!(">> Applied autofixes: {}", fixed.join(", "));
+        }
+        Ok(fixed)
+    }
+
+    /// Commits everything outside `plan/` on the detached HEAD. Does nothing if there is nothing to commit.
+    fn commit_all(&self, message: &str) -> Result<()> {
+        Command::new("git")
+            .current_dir(self.workspace_root)
+            .args(["add", "-A", "--", ".", ":(exclude)plan"])
+            .status()
+            .context("Failed to stage changes")?;
+        let staged = Command::new("git")
+            .current_dir(self.workspace_root)
+            .args(["diff", "--cached", "--quiet"])
+            .status()
+            .context("Failed to inspect staged changes")?;
+        if staged.success() {
+            return Ok(());
+        }
+        let status = Command::new("git")
+            .current_dir(self.workspace_root)
+            .args(["commit", "-q", "-m", message])
+            .status()
+            .context("Failed to commit")?;
+        if !status.success() {
+            return Err(anyhow!("git commit failed"));
+        }
+        Ok(())
+    }
+
+    /// Runs `[coverage] command` and reads line coverage from `[coverage] report`.
+    fn measure_coverage(&self, config: &PlanConfig, container: Option<&ContainerBackend>, env: &ResolvedEnv) -> Result<f64> {
+        let (command, report) = match (&config.coverage.command, &config.coverage.report) {
+            (Some(command), Some(report)) => (command, report),
+            _ => return Err(anyhow!("verification.min_coverage_delta needs [coverage] command and report in plan/config.toml")),
+        };
+        let cmd = checked_command(self.workspace_root, config, container, env, command)?;
+        let output = run_cancellable(cmd, None, &self.cancel)
+            .context("Failed to execute coverage command")?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            return Err(anyhow!("Coverage Command Failed:\nSTDOUT:\n{}\nSTDERR:\n{}", stdout, stderr));
+        }
+
+        let root = container.map(|c| c.worktree()).unwrap_or(self.workspace_root);
+        let path = root.join(report);
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Coverage report not found at {:?}", path))?;
+        coverage::parse_report(&content)
+    }
+}
+
+/// A ticket-supplied command, checked against `[policy]` and run in the container if there is one.
+/// Not a method so verification steps can build commands from other threads.
+fn checked_command(workspace_root: &Path, config: &PlanConfig, container: Option<&ContainerBackend>, env: &ResolvedEnv, cmd_str: &str) -> Result<Command> {
+    match container {
+        // The container is the sandbox; the allowlist still applies
+        Some(container) => {
+            policy::check(&config.policy, cmd_str)?;
+            Ok(container.shell_command(cmd_str, &env.vars()))
+        }
+        None => {
+            let mut cmd = policy::build_command(&config.policy, cmd_str, workspace_root)?;
+            cmd.envs(env.vars());
+            Ok(cmd)
+        }
+    }
+}
+
+/// Whether anything outside plan/ has changed since HEAD.
+fn is_dirty(workspace_root: &Path) -> Result<bool> {
+    // plan/ is director-plan's own state (the worker marks the ticket in_progress
+    // before we start), so it doesn't count as user changes.
+    let output = Command::new("git")
+        .current_dir(workspace_root)
+        .args(["status", "--porcelain", "--", ".", ":(exclude)plan"])
+        .output()?;
+    Ok(!output.stdout.is_empty())
+}
+
+/// Discards the agent's changes. Like `git reset --hard` + `git clean`, but leaves plan/
+/// alone so ticket status/history written during the run survives.
+fn reset_tree(workspace_root: &Path) -> Result<()> {
+    Command::new("git")
+        .current_dir(workspace_root)
+        .args(["reset", "-q"])
+        .status()
+        .context("Failed to reset index")?;
+    Command::new("git")
+        .current_dir(workspace_root)
+        .args(["checkout", "HEAD", "--", ".", ":(exclude)plan"])
+        .status()
+        .context("Failed to hard reset")?;
+    Command::new("git")
+        .current_dir(workspace_root)
+        .args(["clean", "-fdq", "--", ".", ":(exclude)plan"])
+        .status()
+        .context("Failed to clean untracked files")?;
+    Ok(())
+}
+
+/// Changes put away by `git stash` before a run.
+struct Stash<'a> {
+    root: &'a Path,
+    commit: String,
+    message: String,
+}
+
+impl Stash<'_> {
+    /// Applies the stash to the tree and drops it. If the run left changes of its own behind,
+    /// or the stash doesn't apply cleanly on top of the run's commit, it's kept instead and
+    /// the tree left as the run left it.
+    fn restore(&self) -> Result<()> {
+        let kept = format!("they're kept in the stash as \"{}\"; `git stash pop` brings them back once that's sorted", self.message);
+        if is_dirty(self.root)? {
+            return Err(anyhow!("The run left uncommitted changes in the tree, so yours weren't put back; {}", kept));
+        }
+        let status = Command::new("git")
+            .current_dir(self.root)
+            .args(["stash", "apply", "-q", &self.commit])
+            .status()
+            .context("Failed to run git stash apply")?;
+        if !status.success() {
+            // The tree was clean, so all there is to undo is the half-applied stash
+            reset_tree(self.root)?;
+            return Err(anyhow!("Your changes conflict with what the run left in the tree, so they weren't put back; {}", kept));
         }
 
+        // `git stash drop` wants `stash@{n}`; others may have stashed on top of ours since
+        let output = Command::new("git")
+            .current_dir(self.root)
+            .args(["stash", "list", "--format=%H"])
+            .output()
+            .context("Failed to run git stash list")?;
+        let entries = String::from_utf8_lossy(&output.stdout);
+        if let Some(n) = entries.lines().position(|sha| sha == self.commit) {
+            Command::new("git")
+                .current_dir(self.root)
+                .args(["stash", "drop", "-q", &format!("stash@{{{}}}", n)])
+                .status()
+                .context("Failed to run git stash drop")?;
+        }
         Ok(())
     }
 }
+
+/// A [`Stash`] taken for `--autostash`, put back when dropped at the end of the run.
+struct Autostash<'a>(Stash<'a>);
+
+impl Drop for Autostash<'_> {
+    fn drop(&mut self) {
+        match self.0.restore() {
+            Ok(()) => crate::progress!(">> Put back your stashed changes"),
+            Err(e) => crate::progress!(">> {:#}", e),
+        }
+    }
+}
+
+/// Records how far the run got, for `--resume`. A checkpoint that can't be written only costs
+/// the chance to resume.
+fn save_checkpoint(run_store: &RunStore, state: &mut Checkpoint, phase: Phase) {
+    state.phase = phase;
+    state.updated_at = chrono::Utc::now();
+    if let Err(e) = run_store.save_checkpoint(state) {
+        eprintln!(">> Failed to save checkpoint: {}", e);
+    }
+}
+
+/// Asks for a short account of a failure log, for `[context] summarize_errors = "agent"`.
+fn summary_request(log: &str) -> String {
+    format!(
+        "Summarize this failure log for the developer who will fix it. Keep every error message with its file and line, leave out progress output and passing tests, and answer in at most 30 lines of plain text. Don't change any files.\n\n```\n{}\n```\n",
+        log
+    )
+}
+
+/// The retry message for a stateful agent: only the latest failure, since earlier
+/// ones are already in the conversation.
+fn follow_up_message(error: &str) -> String {
+    format!(
+        "Your previous attempt did not pass verification. Your changes are still in the working tree.\n\n# Errors (FIX THESE)\n- {}\n",
+        error
+    )
+}
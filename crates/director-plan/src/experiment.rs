@@ -0,0 +1,348 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Instant;
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+use crate::execution_loop::ExecutionLoop;
+use crate::runs::{Role, RunRecord, RunStore};
+use crate::shutdown::{CancelToken, Interrupted};
+use crate::store::TicketStore;
+
+/// One ticket run under one variant.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Trial {
+    pub ticket_id: String,
+    pub variant: String,
+    /// The run's record, copied into this checkout's run history
+    pub run_id: Option<String>,
+    pub success: bool,
+    pub attempts: u32,
+    pub duration_ms: u64,
+    /// Time spent waiting on the agent
+    pub agent_ms: u64,
+    /// Prompts sent, and characters sent and received: what the agent was paid for
+    pub prompts: u32,
+    pub chars: usize,
+    /// The commit a successful run made, kept as `refs/director/experiments/<id>/<run-id>`
+    pub commit: Option<String>,
+    /// Why the run stopped short, when it errored rather than failed verification
+    pub error: Option<String>,
+}
+
+/// A variant's trials, totalled.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct VariantSummary {
+    pub variant: String,
+    pub trials: usize,
+    pub succeeded: usize,
+    pub success_rate: f64,
+    pub mean_attempts: f64,
+    pub mean_duration_ms: u64,
+    pub agent_ms: u64,
+    pub prompts: u32,
+    pub chars: usize,
+}
+
+/// `director-plan experiment run`: every ticket under every variant, kept in
+/// `.director/experiments/<id>.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Experiment {
+    pub id: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// The commit every trial started from
+    pub base_commit: String,
+    pub variants: Vec<String>,
+    pub tickets: Vec<String>,
+    pub trials: Vec<Trial>,
+    /// Stopped by Ctrl-C before every trial had run
+    #[serde(default)]
+    pub interrupted: bool,
+}
+
+impl Experiment {
+    /// One summary per variant, in the order they were given.
+    pub fn summary(&self) -> Vec<VariantSummary> {
+        self.variants.iter()
+            .map(|variant| {
+                let trials: Vec<&Trial> = self.trials.iter().filter(|t| t.variant == *variant).collect();
+                let count = trials.len();
+                let mean = |total: f64| if count == 0 { 0.0 } else { total / count as f64 };
+                let succeeded = trials.iter().filter(|t| t.success).count();
+                VariantSummary {
+                    variant: variant.clone(),
+                    trials: count,
+                    succeeded,
+                    success_rate: mean(succeeded as f64),
+                    mean_attempts: mean(trials.iter().map(|t| t.attempts as f64).sum()),
+                    mean_duration_ms: mean(trials.iter().map(|t| t.duration_ms as f64).sum()) as u64,
+                    agent_ms: trials.iter().map(|t| t.agent_ms).sum(),
+                    prompts: trials.iter().map(|t| t.prompts).sum(),
+                    chars: trials.iter().map(|t| t.chars).sum(),
+                }
+            })
+            .collect()
+    }
+
+    /// The trial of `ticket_id` under `variant`, if it ran.
+    pub fn trial(&self, ticket_id: &str, variant: &str) -> Option<&Trial> {
+        self.trials.iter().find(|t| t.ticket_id == ticket_id && t.variant == variant)
+    }
+}
+
+fn experiments_dir(workspace_root: &Path) -> PathBuf {
+    crate::state_dir(workspace_root).join("experiments")
+}
+
+pub fn save(workspace_root: &Path, experiment: &Experiment) -> Result<()> {
+    let dir = experiments_dir(workspace_root);
+    fs::create_dir_all(&dir).context("Failed to create experiments directory")?;
+    let path = dir.join(format!("{}.json", experiment.id));
+    fs::write(&path, serde_json::to_string_pretty(experiment)?)
+        .with_context(|| format!("Failed to write experiment: {:?}", path))
+}
+
+pub fn load(workspace_root: &Path, id: &str) -> Result<Experiment> {
+    let path = experiments_dir(workspace_root).join(format!("{}.json", id));
+    if !path.exists() {
+        bail!("No experiment {}", id);
+    }
+    let content = fs::read_to_string(&path).with_context(|| format!("Failed to read experiment: {:?}", path))?;
+    serde_json::from_str(&content).with_context(|| format!("Invalid experiment: {:?}", path))
+}
+
+/// The tickets `spec` names, in order: IDs and `T-010..T-020` ranges, separated by commas. A
+/// range takes the tickets in `ids` with the same prefix and a number between its ends.
+pub fn expand_tickets(spec: &str, ids: &[String]) -> Result<Vec<String>> {
+    let mut tickets: Vec<String> = Vec::new();
+    for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let mut matched: Vec<&String> = match part.split_once("..") {
+            Some((first, last)) => {
+                let ends = split_number(first.trim()).zip(split_number(last.trim()));
+                let Some(((prefix, low), (_, high))) = ends.filter(|((a, _), (b, _))| a == b) else {
+                    bail!("'{}' isn't a range like T-010..T-020", part);
+                };
+                let mut in_range: Vec<(u64, &String)> = ids.iter()
+                    .filter_map(|id| split_number(id).filter(|(p, n)| *p == prefix && (low..=high).contains(n)).map(|(_, n)| (n, id)))
+                    .collect();
+                in_range.sort();
+                in_range.into_iter().map(|(_, id)| id).collect()
+            }
+            None => ids.iter().filter(|id| *id == part).collect(),
+        };
+        if matched.is_empty() {
+            bail!("No tickets match '{}'", part);
+        }
+        matched.retain(|id| !tickets.contains(*id));
+        tickets.extend(matched.into_iter().cloned());
+    }
+    Ok(tickets)
+}
+
+/// `T-010` as `("T-", 10)`.
+fn split_number(id: &str) -> Option<(&str, u64)> {
+    let prefix = id.trim_end_matches(|c: char| c.is_ascii_digit());
+    let number = id[prefix.len()..].parse().ok()?;
+    Some((prefix, number))
+}
+
+/// Runs every ticket in `tickets` under every variant in `variants`, one trial at a time. A
+/// variant is an agent command, run alone: `execution.agents` and routes don't apply.
+///
+/// Each trial gets a fresh worktree of HEAD under `.director/experiments/<id>/`, with this
+/// checkout's `plan/` and secrets file copied in, so nothing here is touched. Its run record
+/// is copied into this checkout's history, where `runs diff` can compare two variants. The
+/// experiment is saved as it goes; cancelling stops it after the current trial is reverted.
+pub fn run(workspace_root: &Path, variants: &[String], tickets: &[String], cancel: &CancelToken) -> Result<Experiment> {
+    if variants.is_empty() || tickets.is_empty() {
+        bail!("An experiment needs at least one variant and one ticket");
+    }
+    let started_at = Utc::now();
+    let mut experiment = Experiment {
+        id: format!("exp-{}", started_at.format("%Y%m%dT%H%M%S%3f")),
+        started_at,
+        finished_at: None,
+        base_commit: git_output(workspace_root, &["rev-parse", "HEAD"])?,
+        variants: variants.to_vec(),
+        tickets: tickets.to_vec(),
+        trials: Vec::new(),
+        interrupted: false,
+    };
+    let store = TicketStore::new(workspace_root);
+    let dir = experiments_dir(workspace_root).join(&experiment.id);
+    let total = variants.len() * tickets.len();
+
+    'trials: for ticket_id in tickets {
+        let ticket = store.load(ticket_id)?;
+        for variant in variants {
+            if cancel.is_cancelled() {
+                experiment.interrupted = true;
+                break 'trials;
+            }
+            crate::progress!(">> Trial {}/{}: {} with {}", experiment.trials.len() + 1, total, ticket_id, variant);
+
+            let worktree = Worktree::add(workspace_root, &dir.join((experiment.trials.len() + 1).to_string()), &experiment.base_commit)?;
+            copy_dir(&workspace_root.join("plan"), &worktree.path.join("plan"))?;
+            let secrets = crate::state_dir(workspace_root).join("secrets.toml");
+            if secrets.exists() {
+                fs::copy(&secrets, crate::state_dir(&worktree.path).join("secrets.toml")).context("Failed to copy the secrets file")?;
+            }
+
+            let started = Instant::now();
+            let result = ExecutionLoop::new(&worktree.path, variant.clone(), ticket.clone())
+                .with_cancel(cancel.clone())
+                .without_fallback()
+                .run_with_handshake();
+            let duration_ms = started.elapsed().as_millis() as u64;
+            let record = RunStore::new(&worktree.path).list_for_ticket(ticket_id)?.pop();
+            if let Some(record) = &record {
+                RunStore::new(workspace_root).save(record)?;
+            }
+
+            let (success, commit, error) = match result {
+                Ok(result) => (result.success, result.commit, None),
+                Err(e) if e.is::<Interrupted>() => {
+                    experiment.interrupted = true;
+                    break 'trials;
+                }
+                Err(e) => (false, None, Some(format!("{:#}", e))),
+            };
+            if let (Some(sha), Some(record)) = (&commit, &record) {
+                let name = format!("refs/director/experiments/{}/{}", experiment.id, record.run_id);
+                git_output(workspace_root, &["update-ref", &name, sha])?;
+            }
+            let trial = trial(ticket_id, variant, record.as_ref(), success, duration_ms, commit, error);
+            crate::progress!(">> {} with {}: {}", ticket_id, variant, if trial.success { "passed" } else { "failed" });
+            experiment.trials.push(trial);
+            save(workspace_root, &experiment)?;
+        }
+    }
+
+    experiment.finished_at = Some(Utc::now());
+    save(workspace_root, &experiment)?;
+    let _ = fs::remove_dir(&dir);
+    Ok(experiment)
+}
+
+fn trial(ticket_id: &str, variant: &str, record: Option<&RunRecord>, success: bool, duration_ms: u64, commit: Option<String>, error: Option<String>) -> Trial {
+    Trial {
+        ticket_id: ticket_id.to_string(),
+        variant: variant.to_string(),
+        run_id: record.map(|r| r.run_id.clone()),
+        success,
+        attempts: record.map_or(0, |r| r.attempts.len() as u32),
+        duration_ms,
+        agent_ms: record.map_or(0, |r| r.attempts.iter().map(|a| a.agent_ms).sum()),
+        prompts: record.map_or(0, |r| r.transcript.iter().filter(|m| m.role == Role::User).count() as u32),
+        chars: record.map_or(0, |r| r.transcript.iter().map(|m| m.content.chars().count()).sum()),
+        commit,
+        error,
+    }
+}
+
+/// A scratch `git worktree`, detached at a commit and removed when dropped.
+pub(crate) struct Worktree {
+    workspace_root: PathBuf,
+    pub(crate) path: PathBuf,
+}
+
+impl Worktree {
+    pub(crate) fn add(workspace_root: &Path, path: &Path, commit: &str) -> Result<Self> {
+        let status = Command::new("git")
+            .current_dir(workspace_root)
+            .args(["worktree", "add", "--detach", "-q"])
+            .arg(path)
+            .arg(commit)
+            .status()
+            .context("Failed to create experiment worktree")?;
+        if !status.success() {
+            bail!("git worktree add failed for {:?}", path);
+        }
+        Ok(Self { workspace_root: workspace_root.to_path_buf(), path: path.to_path_buf() })
+    }
+}
+
+impl Drop for Worktree {
+    fn drop(&mut self) {
+        let _ = Command::new("git")
+            .current_dir(&self.workspace_root)
+            .args(["worktree", "remove", "--force"])
+            .arg(&self.path)
+            .output();
+    }
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    for entry in WalkDir::new(from) {
+        let entry = entry?;
+        let target = to.join(entry.path().strip_prefix(from)?);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target).with_context(|| format!("Failed to create {:?}", target))?;
+        } else if entry.file_type().is_file() {
+            fs::copy(entry.path(), &target).with_context(|| format!("Failed to copy {:?}", entry.path()))?;
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn git_output(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to run git {}", args.join(" ")))?;
+    if !output.status.success() {
+        return Err(anyhow!("git {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_tickets() {
+        let ids: Vec<String> = ["T-009", "T-010", "T-012", "T-012-1", "T-020", "T-021", "BUG-011"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(expand_tickets("T-010..T-020", &ids).unwrap(), ["T-010", "T-012", "T-020"]);
+        assert_eq!(expand_tickets("T-021, T-009..T-010,T-010", &ids).unwrap(), ["T-021", "T-009", "T-010"]);
+        assert!(expand_tickets("T-030..T-040", &ids).is_err());
+        assert!(expand_tickets("T-010..BUG-020", &ids).is_err());
+        assert!(expand_tickets("T-404", &ids).is_err());
+    }
+
+    #[test]
+    fn test_summary() {
+        let trial = |ticket: &str, variant: &str, success: bool, attempts: u32| Trial {
+            ticket_id: ticket.to_string(),
+            variant: variant.to_string(),
+            run_id: None,
+            success,
+            attempts,
+            duration_ms: 1000 * attempts as u64,
+            agent_ms: 600 * attempts as u64,
+            prompts: attempts,
+            chars: 100,
+            commit: None,
+            error: None,
+        };
+        let experiment = Experiment {
+            id: "exp-1".to_string(),
+            started_at: Utc::now(),
+            finished_at: None,
+            base_commit: "abc".to_string(),
+            variants: vec!["b".to_string(), "a".to_string()],
+            tickets: vec!["T-1".to_string(), "T-2".to_string()],
+            trials: vec![trial("T-1", "a", true, 1), trial("T-1", "b", false, 3), trial("T-2", "a", false, 2), trial("T-2", "b", true, 2)],
+            interrupted: false,
+        };
+        let summary = experiment.summary();
+        assert_eq!(summary.iter().map(|s| s.variant.as_str()).collect::<Vec<_>>(), ["b", "a"]);
+        assert_eq!((summary[0].succeeded, summary[0].success_rate, summary[0].mean_attempts), (1, 0.5, 2.5));
+        assert_eq!((summary[1].mean_duration_ms, summary[1].agent_ms, summary[1].prompts, summary[1].chars), (1500, 1800, 3, 200));
+        assert!(experiment.trial("T-2", "b").unwrap().success);
+    }
+}
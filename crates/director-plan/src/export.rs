@@ -0,0 +1,254 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use anyhow::Result;
+use serde::Serialize;
+use crate::DirectorPlan;
+use crate::runs::{RunOutcome, RunRecord, RunStore};
+use crate::types::Ticket;
+
+/// Output formats for `director-plan export`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    /// One JSON object per line
+    Jsonl,
+    /// A Markdown table
+    Markdown,
+}
+
+/// A ticket's runs, summed up, for `export --runs`.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct RunStats {
+    pub runs: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub attempts: usize,
+    pub agent_secs: u64,
+    pub verification_secs: u64,
+    pub last_run_at: Option<String>,
+}
+
+impl RunStats {
+    fn of(runs: &[RunRecord]) -> Self {
+        Self {
+            runs: runs.len(),
+            succeeded: runs.iter().filter(|r| r.outcome == RunOutcome::Succeeded).count(),
+            failed: runs.iter().filter(|r| r.outcome == RunOutcome::Failed).count(),
+            attempts: runs.iter().map(|r| r.attempts.len()).sum(),
+            agent_secs: runs.iter().map(|r| r.agent_ms()).sum::<u64>() / 1000,
+            verification_secs: runs.iter().map(|r| r.verification_ms()).sum::<u64>() / 1000,
+            last_run_at: runs.iter().map(|r| r.started_at).max().map(|at| at.to_rfc3339()),
+        }
+    }
+}
+
+/// One ticket, flattened to a row. List fields stay lists in JSONL and are joined with `; `
+/// in CSV and Markdown.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportRow {
+    pub id: String,
+    pub title: String,
+    pub status: String,
+    pub priority: String,
+    #[serde(rename = "type")]
+    pub ticket_type: Option<String>,
+    pub owner: Option<String>,
+    pub created_at: String,
+    pub due_date: Option<String>,
+    pub parent: Option<String>,
+    pub depends_on: Vec<String>,
+    pub follow_up_of: Option<String>,
+    pub blocked_reason: Option<String>,
+    pub external_ref: Option<String>,
+    pub description: String,
+    pub constraints: Vec<String>,
+    pub relevant_files: Vec<String>,
+    pub allowed_paths: Vec<String>,
+    pub assets: Vec<String>,
+    pub verification_command: String,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub run_stats: Option<RunStats>,
+}
+
+impl ExportRow {
+    fn new(ticket: Ticket, run_stats: Option<RunStats>) -> Self {
+        let lower = |s: String| s.to_lowercase();
+        Self {
+            id: ticket.meta.id,
+            title: ticket.meta.title,
+            status: ticket.meta.status.to_string(),
+            priority: lower(format!("{:?}", ticket.meta.priority)),
+            ticket_type: ticket.meta.ticket_type.map(|t| lower(format!("{:?}", t))),
+            owner: ticket.meta.owner,
+            created_at: ticket.meta.created_at.to_string(),
+            due_date: ticket.meta.due_date.map(|d| d.to_string()),
+            parent: ticket.meta.parent,
+            depends_on: ticket.meta.depends_on,
+            follow_up_of: ticket.meta.follow_up_of,
+            blocked_reason: ticket.meta.blocked_reason,
+            external_ref: ticket.meta.external_ref,
+            description: ticket.spec.description,
+            constraints: ticket.spec.constraints,
+            relevant_files: ticket.spec.relevant_files,
+            allowed_paths: ticket.spec.allowed_paths,
+            assets: ticket.spec.assets,
+            verification_command: ticket.verification.command,
+            run_stats,
+        }
+    }
+
+    /// Column names and values in export order, for the tabular formats.
+    fn cells(&self) -> Vec<(&'static str, String)> {
+        let opt = |v: &Option<String>| v.clone().unwrap_or_default();
+        let mut cells = vec![
+            ("id", self.id.clone()),
+            ("title", self.title.clone()),
+            ("status", self.status.clone()),
+            ("priority", self.priority.clone()),
+            ("type", opt(&self.ticket_type)),
+            ("owner", opt(&self.owner)),
+            ("created_at", self.created_at.clone()),
+            ("due_date", opt(&self.due_date)),
+            ("parent", opt(&self.parent)),
+            ("depends_on", self.depends_on.join("; ")),
+            ("follow_up_of", opt(&self.follow_up_of)),
+            ("blocked_reason", opt(&self.blocked_reason)),
+            ("external_ref", opt(&self.external_ref)),
+            ("description", self.description.clone()),
+            ("constraints", self.constraints.join("; ")),
+            ("relevant_files", self.relevant_files.join("; ")),
+            ("allowed_paths", self.allowed_paths.join("; ")),
+            ("assets", self.assets.join("; ")),
+            ("verification_command", self.verification_command.clone()),
+        ];
+        if let Some(stats) = &self.run_stats {
+            cells.extend([
+                ("runs", stats.runs.to_string()),
+                ("succeeded", stats.succeeded.to_string()),
+                ("failed", stats.failed.to_string()),
+                ("attempts", stats.attempts.to_string()),
+                ("agent_secs", stats.agent_secs.to_string()),
+                ("verification_secs", stats.verification_secs.to_string()),
+                ("last_run_at", opt(&stats.last_run_at)),
+            ]);
+        }
+        cells
+    }
+}
+
+/// Every ticket as a row, by ID. With `with_runs`, each row also carries its [`RunStats`].
+pub fn rows(workspace_root: &Path, with_runs: bool) -> Result<Vec<ExportRow>> {
+    let tickets = DirectorPlan::new(workspace_root.to_path_buf()).list_tickets(None)?;
+    let mut runs: BTreeMap<String, Vec<RunRecord>> = BTreeMap::new();
+    if with_runs {
+        for run in RunStore::new(workspace_root).list_all()? {
+            runs.entry(run.ticket_id.clone()).or_default().push(run);
+        }
+    }
+    Ok(tickets.into_iter()
+        .map(|ticket| {
+            let stats = with_runs.then(|| RunStats::of(runs.get(&ticket.meta.id).map(Vec::as_slice).unwrap_or_default()));
+            ExportRow::new(ticket, stats)
+        })
+        .collect())
+}
+
+pub fn render(rows: &[ExportRow], format: ExportFormat) -> Result<String> {
+    let mut out = String::new();
+    match format {
+        ExportFormat::Jsonl => {
+            for row in rows {
+                out.push_str(&serde_json::to_string(row)?);
+                out.push('\n');
+            }
+        }
+        ExportFormat::Csv => {
+            let Some(first) = rows.first() else { return Ok(out) };
+            let header: Vec<String> = first.cells().iter().map(|(name, _)| csv_field(name)).collect();
+            out.push_str(&header.join(","));
+            out.push_str("\r\n");
+            for row in rows {
+                let fields: Vec<String> = row.cells().iter().map(|(_, value)| csv_field(value)).collect();
+                out.push_str(&fields.join(","));
+                out.push_str("\r\n");
+            }
+        }
+        ExportFormat::Markdown => {
+            let Some(first) = rows.first() else { return Ok(out) };
+            let names: Vec<&str> = first.cells().iter().map(|(name, _)| *name).collect();
+            out.push_str(&format!("| {} |\n", names.join(" | ")));
+            out.push_str(&format!("|{}\n", " --- |".repeat(names.len())));
+            for row in rows {
+                let cells: Vec<String> = row.cells().iter().map(|(_, value)| markdown_cell(value)).collect();
+                out.push_str(&format!("| {} |\n", cells.join(" | ")));
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Quotes a CSV field when it holds a comma, quote or line break (RFC 4180).
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Keeps a value on one table row: pipes escaped, line breaks as `<br>`.
+fn markdown_cell(value: &str) -> String {
+    value.trim().replace('|', "\\|").replace("\r\n", "<br>").replace('\n', "<br>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn workspace() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("plan/tickets")).unwrap();
+        fs::write(dir.path().join("plan/tickets/T-001.toml"), "[meta]\nid = \"T-001\"\ntitle = \"Login, again\"\nstatus = \"review\"\npriority = \"high\"\ntype = \"bug\"\ndepends_on = [\"T-000\"]\n\n[spec]\ndescription = \"Say \\\"hi\\\"\\n| twice\"\n\n[verification]\ncommand = \"cargo test\"\n").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_render_formats() {
+        let dir = workspace();
+        let rows = rows(dir.path(), false).unwrap();
+
+        let csv = render(&rows, ExportFormat::Csv).unwrap();
+        let mut lines = csv.split("\r\n");
+        assert!(lines.next().unwrap().starts_with("id,title,status,priority,type,"));
+        assert!(lines.next().unwrap().starts_with("T-001,\"Login, again\",review,high,bug,"));
+        assert!(csv.contains("\"Say \"\"hi\"\"\n| twice\""));
+
+        let jsonl = render(&rows, ExportFormat::Jsonl).unwrap();
+        let row: serde_json::Value = serde_json::from_str(jsonl.lines().next().unwrap()).unwrap();
+        assert_eq!(row["depends_on"], serde_json::json!(["T-000"]));
+        assert!(row.get("runs").is_none());
+
+        let markdown = render(&rows, ExportFormat::Markdown).unwrap();
+        assert_eq!(markdown.lines().count(), 3);
+        assert!(markdown.contains("| Say \"hi\"<br>\\| twice |"));
+    }
+
+    #[test]
+    fn test_run_stats() {
+        let dir = workspace();
+        let store = RunStore::new(dir.path());
+        for (i, outcome) in [RunOutcome::Failed, RunOutcome::Succeeded].into_iter().enumerate() {
+            let mut record = RunRecord::new("T-001", "agent");
+            record.run_id = format!("T-001-{}", i);
+            record.outcome = outcome;
+            store.save(&record).unwrap();
+        }
+
+        let rows = rows(dir.path(), true).unwrap();
+        let stats = rows[0].run_stats.as_ref().unwrap();
+        assert_eq!((stats.runs, stats.succeeded, stats.failed), (2, 1, 1));
+        let jsonl = render(&rows, ExportFormat::Jsonl).unwrap();
+        assert!(jsonl.contains("\"runs\":2"));
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime};
+use anyhow::Result;
+use serde::Serialize;
+use walkdir::WalkDir;
+use crate::config::RetentionConfig;
+use crate::runs::{RunOutcome, RunStore, attempt_ref};
+
+/// What a [`collect`] pass removed (or would remove, for a dry run).
+#[derive(Debug, Default, Serialize)]
+pub struct GcReport {
+    pub removed: Vec<PathBuf>,
+    pub bytes_freed: u64,
+    /// Snapshots of failed attempts, deleted along with their runs
+    pub refs: Vec<String>,
+}
+
+/// Bytes used by the generated-output directories.
+#[derive(Debug, Default, Serialize)]
+pub struct DiskUsage {
+    pub artifacts_bytes: u64,
+    pub proof_bytes: u64,
+    pub runs_bytes: u64,
+}
+
+pub fn disk_usage(workspace_root: &Path) -> DiskUsage {
+    DiskUsage {
+        artifacts_bytes: dir_size(&workspace_root.join("target/public/artifacts")),
+        proof_bytes: dir_size(&workspace_root.join("proof")),
+        runs_bytes: dir_size(&crate::state_dir(workspace_root).join("runs")),
+    }
+}
+
+/// Prunes artifacts, `proof/` output and run records according to `[retention]`. A run's
+/// attempt snapshots under `refs/director/attempts/` go with its record.
+///
+/// Per ticket, files beyond the newest `keep_per_ticket` or older than `max_age_days` are removed.
+/// `proof/` only has an age limit. Runs that are still in progress are never touched.
+pub fn collect(workspace_root: &Path, retention: &RetentionConfig, dry_run: bool) -> Result<GcReport> {
+    let mut report = GcReport::default();
+    let max_age = retention.max_age_days.map(|days| Duration::from_secs(days * 24 * 60 * 60));
+    let now = SystemTime::now();
+
+    // Run records, via the store so running ones can be recognised
+    let store = RunStore::new(workspace_root);
+    let runs_dir = crate::state_dir(workspace_root).join("runs");
+    let mut by_ticket: BTreeMap<String, Vec<_>> = BTreeMap::new();
+    for run in store.list_all()? {
+        by_ticket.entry(run.ticket_id.clone()).or_default().push(run);
+    }
+    for (ticket_id, mut runs) in by_ticket {
+        // Newest first
+        runs.reverse();
+        for (index, run) in runs.iter().enumerate() {
+            if run.outcome == RunOutcome::Running {
+                continue;
+            }
+            let age = (chrono::Utc::now() - run.started_at).to_std().unwrap_or_default();
+            if over_limit(index, age, retention.keep_per_ticket, max_age) {
+                let path = runs_dir.join(&ticket_id).join(format!("{}.json", run.run_id));
+                remove(&path, dry_run, &mut report);
+                for attempt in run.attempts.iter().filter(|a| a.snapshot.is_some()) {
+                    remove_ref(workspace_root, &attempt_ref(&run.run_id, attempt.number), dry_run, &mut report);
+                }
+            }
+        }
+    }
+
+    // Artifacts: one directory per ticket
+    let artifacts_dir = workspace_root.join("target/public/artifacts");
+    if artifacts_dir.exists() {
+        for entry in fs::read_dir(&artifacts_dir)? {
+            let dir = entry?.path();
+            if !dir.is_dir() {
+                continue;
+            }
+            let mut files = files_by_age(&dir, now);
+            files.sort_by_key(|(_, age)| *age);
+            for (index, (path, age)) in files.into_iter().enumerate() {
+                if over_limit(index, age, retention.keep_per_ticket, max_age) {
+                    remove(&path, dry_run, &mut report);
+                }
+            }
+            if !dry_run {
+                // Only succeeds once the ticket's directory is empty
+                let _ = fs::remove_dir(&dir);
+            }
+        }
+    }
+
+    if let Some(max_age) = max_age {
+        for (path, age) in files_by_age(&workspace_root.join("proof"), now) {
+            if age > max_age {
+                remove(&path, dry_run, &mut report);
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+fn over_limit(index: usize, age: Duration, keep: Option<usize>, max_age: Option<Duration>) -> bool {
+    keep.is_some_and(|keep| index >= keep) || max_age.is_some_and(|max| age > max)
+}
+
+fn files_by_age(dir: &Path, now: SystemTime) -> Vec<(PathBuf, Duration)> {
+    WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| {
+            let modified = e.metadata().ok()?.modified().ok()?;
+            Some((e.path().to_path_buf(), now.duration_since(modified).unwrap_or_default()))
+        })
+        .collect()
+}
+
+fn remove(path: &Path, dry_run: bool, report: &mut GcReport) {
+    let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    if !dry_run && fs::remove_file(path).is_err() {
+        return;
+    }
+    report.bytes_freed += size;
+    report.removed.push(path.to_path_buf());
+}
+
+fn remove_ref(workspace_root: &Path, name: &str, dry_run: bool, report: &mut GcReport) {
+    if !dry_run {
+        let deleted = Command::new("git")
+            .current_dir(workspace_root)
+            .args(["update-ref", "-d", name])
+            .output()
+            .is_ok_and(|o| o.status.success());
+        if !deleted {
+            return;
+        }
+    }
+    report.refs.push(name.to_string());
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| e.metadata().ok())
+        .map(|m| m.len())
+        .sum()
+}
@@ -0,0 +1,142 @@
+use std::fs;
+use std::path::Path;
+use anyhow::{Result, bail};
+use regex::Regex;
+use serde::Serialize;
+use crate::context::ast::{DependencyGraph, FileType};
+use crate::types::{Status, Ticket};
+
+/// A file that changing the target could break.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImpactedFile {
+    pub path: String,
+    /// Imports between it and the target: 0 for the target itself (or a file defining the
+    /// symbol), 1 for its direct importers, and so on
+    pub depth: usize,
+}
+
+/// An open ticket whose context includes impacted files.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImpactedTicket {
+    pub id: String,
+    pub title: String,
+    pub status: Status,
+    /// The impacted files among its `relevant_files` and `context_pin`
+    pub files: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Impact {
+    pub target: String,
+    /// `file` or `symbol`
+    pub kind: &'static str,
+    pub files: Vec<ImpactedFile>,
+    pub tickets: Vec<ImpactedTicket>,
+}
+
+/// What changing `target` reaches: a workspace file, or else a symbol. For a file that's
+/// everything importing it, directly or not. For a symbol it's the files declaring it and
+/// those of their importers that mention it by name. `max_depth` stops the walk that many
+/// imports out.
+pub fn analyze(root: &Path, graph: &DependencyGraph, target: &str, tickets: &[Ticket], max_depth: Option<usize>) -> Result<Impact> {
+    let target = target.trim_start_matches("./");
+    let (kind, files) = if root.join(target).is_file() {
+        ("file", graph.dependent_depths(&[target.to_string()], max_depth))
+    } else {
+        if !Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*$").unwrap().is_match(target) {
+            bail!("'{}' is neither a file in the workspace nor a symbol name", target);
+        }
+        let defined_in = declaring_files(root, graph, target);
+        if defined_in.is_empty() {
+            bail!("No file in the workspace declares '{}'", target);
+        }
+        let mention = Regex::new(&format!(r"\b{}\b", regex::escape(target))).unwrap();
+        let files = graph.dependent_depths(&defined_in, max_depth)
+            .into_iter()
+            .filter(|(path, depth)| {
+                *depth == 0 || fs::read_to_string(root.join(path)).is_ok_and(|content| mention.is_match(&content))
+            })
+            .collect();
+        ("symbol", files)
+    };
+    let files: Vec<ImpactedFile> = files.into_iter().map(|(path, depth)| ImpactedFile { path, depth }).collect();
+
+    let tickets = tickets.iter()
+        .filter(|t| !matches!(t.meta.status, Status::Done | Status::Archived))
+        .filter_map(|t| {
+            let hits: Vec<String> = files.iter()
+                .filter(|f| t.spec.relevant_files.contains(&f.path) || t.spec.context_pin.contains(&f.path))
+                .map(|f| f.path.clone())
+                .collect();
+            (!hits.is_empty()).then(|| ImpactedTicket {
+                id: t.meta.id.clone(),
+                title: t.meta.title.clone(),
+                status: t.meta.status.clone(),
+                files: hits,
+            })
+        })
+        .collect();
+
+    Ok(Impact { target: target.to_string(), kind, files, tickets })
+}
+
+/// Source files with a declaration of `symbol`: a function, type, constant, module, class or
+/// `#define` by that name, in any of the languages the graph reads.
+fn declaring_files(root: &Path, graph: &DependencyGraph, symbol: &str) -> Vec<String> {
+    let declaration = Regex::new(&format!(
+        r"(?:\b(?:fn|struct|enum|trait|type|union|const|static|mod|macro_rules!|class|interface|function|let|var|def|fun|object|typealias|namespace)\s+|#define\s+){}\b",
+        regex::escape(symbol),
+    )).unwrap();
+    let mut files: Vec<String> = graph.graph.node_weights()
+        .filter(|node| !matches!(node.file_type, FileType::Other | FileType::Stylesheet | FileType::Manifest))
+        .filter(|node| fs::read_to_string(root.join(&node.path)).is_ok_and(|content| declaration.is_match(&content)))
+        .map(|node| node.path.clone())
+        .collect();
+    files.sort();
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ticket(id: &str, status: &str, relevant_files: &[&str]) -> Ticket {
+        let toml = format!(
+            "[meta]\nid = \"{}\"\ntitle = \"t\"\nstatus = \"{}\"\npriority = \"low\"\n\n[spec]\ndescription = \"\"\nrelevant_files = {:?}\n\n[verification]\ncommand = \"true\"\n",
+            id, status, relevant_files,
+        );
+        toml_edit::de::from_str(&toml).unwrap()
+    }
+
+    #[test]
+    fn test_analyze() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("lib.rs"), "mod parse;\nmod render;\nuse crate::parse::Token;\n").unwrap();
+        fs::write(src.join("parse.rs"), "pub struct Token;\npub fn parse() {}\n").unwrap();
+        fs::write(src.join("render.rs"), "use crate::parse;\nfn render() { parse::parse(); }\n").unwrap();
+        let mut graph = DependencyGraph::new(dir.path());
+        graph.build().unwrap();
+        let tickets = [
+            ticket("T-001", "todo", &["src/render.rs"]),
+            ticket("T-002", "done", &["src/render.rs"]),
+            ticket("T-003", "todo", &["README.md"]),
+        ];
+
+        let impact = analyze(dir.path(), &graph, "./src/parse.rs", &tickets, None).unwrap();
+        assert_eq!(impact.kind, "file");
+        let files: Vec<(&str, usize)> = impact.files.iter().map(|f| (f.path.as_str(), f.depth)).collect();
+        assert_eq!(files, [("src/parse.rs", 0), ("src/lib.rs", 1), ("src/render.rs", 1)]);
+        assert_eq!(impact.tickets.iter().map(|t| t.id.as_str()).collect::<Vec<_>>(), ["T-001"]);
+
+        // Only the importers that use the symbol
+        let impact = analyze(dir.path(), &graph, "Token", &tickets, None).unwrap();
+        assert_eq!(impact.kind, "symbol");
+        assert_eq!(impact.files.iter().map(|f| f.path.as_str()).collect::<Vec<_>>(), ["src/parse.rs", "src/lib.rs"]);
+        assert!(impact.tickets.is_empty());
+
+        assert!(analyze(dir.path(), &graph, "Missing", &tickets, None).is_err());
+        assert!(analyze(dir.path(), &graph, "src/gone.rs", &tickets, None).is_err());
+    }
+}
@@ -0,0 +1,341 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use anyhow::{Context, Result, anyhow, bail};
+use serde_json::Value;
+use toml_edit::DocumentMut;
+use crate::store::TicketStore;
+use crate::types::{Priority, Status, TicketType};
+
+/// The trackers `director-plan import` reads exports from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Source {
+    /// "Export Excel CSV (all fields)" from a Jira issue search
+    Jira,
+    /// Linear issues as JSON: an array, `{"issues": [...]}`, or a GraphQL `issues.nodes` reply
+    Linear,
+    /// A Trello board's "Export as JSON"
+    Trello,
+}
+
+impl Source {
+    fn name(self) -> &'static str {
+        match self {
+            Source::Jira => "jira",
+            Source::Linear => "linear",
+            Source::Trello => "trello",
+        }
+    }
+}
+
+/// One issue from an export, mapped onto ticket fields.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedIssue {
+    /// `<source>:<key>`, e.g. `jira:PROJ-12`; stored as `meta.external_ref`
+    pub external_ref: String,
+    pub title: String,
+    pub description: String,
+    pub status: Status,
+    pub priority: Priority,
+    pub ticket_type: Option<TicketType>,
+}
+
+/// Reads the issues out of an export file's contents.
+pub fn parse(source: Source, content: &str) -> Result<Vec<ImportedIssue>> {
+    match source {
+        Source::Jira => parse_jira(content),
+        Source::Linear => parse_linear(&serde_json::from_str(content).context("The Linear export isn't valid JSON")?),
+        Source::Trello => parse_trello(&serde_json::from_str(content).context("The Trello export isn't valid JSON")?),
+    }
+}
+
+fn parse_jira(content: &str) -> Result<Vec<ImportedIssue>> {
+    let mut rows = parse_csv(content).into_iter();
+    let header = rows.next().ok_or_else(|| anyhow!("The Jira export is empty"))?;
+    // Jira repeats some columns (Labels, Sprint, ...); the first of each name is used
+    let column = |name: &str| header.iter().position(|h| h.trim().eq_ignore_ascii_case(name));
+    let key = column("Issue key").ok_or_else(|| anyhow!("The Jira export has no 'Issue key' column"))?;
+    let summary = column("Summary").ok_or_else(|| anyhow!("The Jira export has no 'Summary' column"))?;
+    let (description, status, priority, issue_type) = (column("Description"), column("Status"), column("Priority"), column("Issue Type"));
+
+    let mut issues = Vec::new();
+    for row in rows {
+        let field = |i: Option<usize>| i.and_then(|i| row.get(i)).map(|s| s.trim()).unwrap_or_default();
+        if field(Some(key)).is_empty() {
+            continue;
+        }
+        issues.push(ImportedIssue {
+            external_ref: format!("jira:{}", field(Some(key))),
+            title: field(Some(summary)).to_string(),
+            description: field(description).to_string(),
+            status: map_status(field(status)),
+            priority: map_priority(field(priority)).unwrap_or(Priority::Medium),
+            ticket_type: map_type(field(issue_type)),
+        });
+    }
+    Ok(issues)
+}
+
+fn parse_linear(export: &Value) -> Result<Vec<ImportedIssue>> {
+    let nodes = export.as_array()
+        .or_else(|| export["issues"].as_array())
+        .or_else(|| export["data"]["issues"]["nodes"].as_array())
+        .ok_or_else(|| anyhow!("The Linear export has no issues array"))?;
+
+    let mut issues = Vec::new();
+    for issue in nodes {
+        let identifier = issue["identifier"].as_str().ok_or_else(|| anyhow!("A Linear issue has no identifier"))?;
+        // `state.type` is one of backlog, unstarted, started, completed, canceled
+        let status = match issue["state"]["type"].as_str() {
+            Some("completed") => Status::Done,
+            Some("canceled") => Status::Archived,
+            Some("started") => map_status(issue["state"]["name"].as_str().unwrap_or("in progress")),
+            _ => Status::Todo,
+        };
+        // 0 is "no priority", then 1 (urgent) down to 4 (low)
+        let priority = match issue["priority"].as_u64() {
+            Some(1) => Priority::Critical,
+            Some(2) => Priority::High,
+            Some(4) => Priority::Low,
+            _ => Priority::Medium,
+        };
+        let labels = issue["labels"]["nodes"].as_array().or_else(|| issue["labels"].as_array());
+        let ticket_type = labels.into_iter().flatten()
+            .filter_map(|l| l["name"].as_str().or_else(|| l.as_str()))
+            .find_map(map_type);
+        issues.push(ImportedIssue {
+            external_ref: format!("linear:{}", identifier),
+            title: issue["title"].as_str().unwrap_or_default().trim().to_string(),
+            description: issue["description"].as_str().unwrap_or_default().trim().to_string(),
+            status,
+            priority,
+            ticket_type,
+        });
+    }
+    Ok(issues)
+}
+
+fn parse_trello(board: &Value) -> Result<Vec<ImportedIssue>> {
+    let cards = board["cards"].as_array().ok_or_else(|| anyhow!("The Trello export has no cards"))?;
+    let lists = board["lists"].as_array().cloned().unwrap_or_default();
+    let list_name = |id: &str| lists.iter()
+        .find(|l| l["id"].as_str() == Some(id))
+        .and_then(|l| l["name"].as_str())
+        .unwrap_or_default()
+        .to_string();
+
+    let mut issues = Vec::new();
+    for card in cards {
+        let short_link = card["shortLink"].as_str().or_else(|| card["id"].as_str())
+            .ok_or_else(|| anyhow!("A Trello card has no shortLink or id"))?;
+        let status = if card["closed"].as_bool() == Some(true) {
+            Status::Archived
+        } else {
+            map_status(&list_name(card["idList"].as_str().unwrap_or_default()))
+        };
+        let labels: Vec<&str> = card["labels"].as_array().into_iter().flatten().filter_map(|l| l["name"].as_str()).collect();
+        issues.push(ImportedIssue {
+            external_ref: format!("trello:{}", short_link),
+            title: card["name"].as_str().unwrap_or_default().trim().to_string(),
+            description: card["desc"].as_str().unwrap_or_default().trim().to_string(),
+            status,
+            priority: labels.iter().copied().filter_map(map_priority).max().unwrap_or(Priority::Medium),
+            ticket_type: labels.iter().copied().find_map(map_type),
+        });
+    }
+    Ok(issues)
+}
+
+/// Maps a tracker's status or column name. Anything unrecognised is `todo`.
+fn map_status(name: &str) -> Status {
+    let name = name.to_lowercase();
+    if ["done", "closed", "resolved", "complete", "shipped"].iter().any(|s| name.contains(s)) {
+        Status::Done
+    } else if ["cancel", "won't", "wont", "archived"].iter().any(|s| name.contains(s)) {
+        Status::Archived
+    } else if ["review", "qa", "testing"].iter().any(|s| name.contains(s)) {
+        Status::Review
+    } else if name.contains("block") {
+        Status::Blocked
+    } else if ["progress", "doing", "started", "active"].iter().any(|s| name.contains(s)) {
+        Status::InProgress
+    } else {
+        Status::Todo
+    }
+}
+
+/// Maps a tracker's priority name, or a Trello label that names one.
+fn map_priority(name: &str) -> Option<Priority> {
+    match name.trim().to_lowercase().as_str() {
+        "highest" | "blocker" | "critical" | "urgent" | "p0" => Some(Priority::Critical),
+        "high" | "major" | "p1" => Some(Priority::High),
+        "medium" | "normal" | "p2" => Some(Priority::Medium),
+        "low" | "lowest" | "minor" | "trivial" | "p3" | "p4" => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+fn map_type(name: &str) -> Option<TicketType> {
+    match name.trim().to_lowercase().as_str() {
+        "bug" | "defect" => Some(TicketType::Bug),
+        "story" | "feature" | "new feature" | "improvement" | "epic" => Some(TicketType::Feature),
+        "task" | "sub-task" | "subtask" | "chore" => Some(TicketType::Chore),
+        "spike" | "research" => Some(TicketType::Spike),
+        _ => None,
+    }
+}
+
+/// Splits CSV into rows of fields: comma-separated, with `"`-quoted fields that may hold
+/// commas, newlines and `""` for a quote.
+fn parse_csv(content: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = content.trim_start_matches('\u{feff}').chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows.retain(|r| r.iter().any(|f| !f.is_empty()));
+    rows
+}
+
+/// What [`create_tickets`] did: the tickets written, as `(external_ref, id)`, and the
+/// references skipped because a ticket already has them.
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+    pub created: Vec<(String, String)>,
+    pub skipped: Vec<String>,
+}
+
+/// Writes `issues` as `T-NNN` tickets numbered on from the existing ones. An issue whose
+/// `external_ref` is already on a ticket is skipped, so re-importing a newer export only adds
+/// what is new.
+pub fn create_tickets(workspace_root: &Path, source: Source, issues: &[ImportedIssue]) -> Result<ImportSummary> {
+    let store = TicketStore::new(workspace_root);
+    fs::create_dir_all(store.tickets_dir())?;
+    let mut known: BTreeSet<String> = crate::DirectorPlan::new(workspace_root.to_path_buf())
+        .list_tickets(None)?
+        .into_iter()
+        .filter_map(|t| t.meta.external_ref)
+        .collect();
+    let mut next = crate::planner::next_number(&store)?;
+    let created_at: toml_datetime::Datetime = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string().parse()?;
+
+    let mut summary = ImportSummary::default();
+    for issue in issues {
+        if issue.title.is_empty() {
+            bail!("{} has no title", issue.external_ref);
+        }
+        if !known.insert(issue.external_ref.clone()) {
+            summary.skipped.push(issue.external_ref.clone());
+            continue;
+        }
+        let id = format!("T-{:03}", next);
+        next += 1;
+
+        let mut doc = DocumentMut::new();
+        doc["meta"] = toml_edit::table();
+        doc["meta"]["id"] = toml_edit::value(id.as_str());
+        doc["meta"]["title"] = toml_edit::value(issue.title.as_str());
+        doc["meta"]["status"] = toml_edit::value(issue.status.to_string());
+        doc["meta"]["priority"] = toml_edit::value(format!("{:?}", issue.priority).to_lowercase());
+        if let Some(ticket_type) = &issue.ticket_type {
+            doc["meta"]["type"] = toml_edit::value(format!("{:?}", ticket_type).to_lowercase());
+        }
+        doc["meta"]["created_at"] = toml_edit::value(created_at);
+        crate::migrate::stamp(&mut doc);
+        doc["meta"]["external_ref"] = toml_edit::value(issue.external_ref.as_str());
+        if issue.status == Status::Blocked {
+            doc["meta"]["blocked_reason"] = toml_edit::value(format!("Blocked in {}", source.name()));
+        }
+
+        doc["spec"] = toml_edit::table();
+        doc["spec"]["description"] = toml_edit::value(issue.description.as_str());
+        doc["verification"] = toml_edit::table();
+        doc["verification"]["command"] = toml_edit::value("");
+        crate::append_history_entry(&mut doc, &format!("Imported from {}.", issue.external_ref));
+
+        store.save_document(&id, &doc)?;
+        summary.created.push((issue.external_ref.clone(), id));
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_jira() {
+        let csv = "Summary,Issue key,Issue id,Issue Type,Status,Priority,Description,Labels,Labels\r\n\
+            Login fails on Safari,WEB-12,10012,Bug,In Progress,Highest,\"Steps:\n1. Open \"\"/login\"\"\",auth,\r\n\
+            \"Export, as PDF\",WEB-13,10013,Story,To Do,Low,,,\r\n";
+        let issues = parse(Source::Jira, csv).unwrap();
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0].external_ref, "jira:WEB-12");
+        assert_eq!(issues[0].description, "Steps:\n1. Open \"/login\"");
+        assert_eq!((issues[0].status.clone(), issues[0].priority.clone()), (Status::InProgress, Priority::Critical));
+        assert_eq!(issues[0].ticket_type, Some(TicketType::Bug));
+        assert_eq!(issues[1].title, "Export, as PDF");
+        assert_eq!(issues[1].status, Status::Todo);
+    }
+
+    #[test]
+    fn test_parse_linear_and_trello() {
+        let linear = r#"{"data": {"issues": {"nodes": [
+            {"identifier": "ENG-4", "title": "Retry webhooks", "priority": 2, "state": {"name": "In Review", "type": "started"},
+             "labels": {"nodes": [{"name": "Feature"}]}},
+            {"identifier": "ENG-5", "title": "Old idea", "priority": 0, "state": {"name": "Canceled", "type": "canceled"}}
+        ]}}}"#;
+        let issues = parse(Source::Linear, linear).unwrap();
+        assert_eq!(issues[0].external_ref, "linear:ENG-4");
+        assert_eq!((issues[0].status.clone(), issues[0].priority.clone()), (Status::Review, Priority::High));
+        assert_eq!(issues[0].ticket_type, Some(TicketType::Feature));
+        assert_eq!(issues[1].status, Status::Archived);
+
+        let trello = r#"{"lists": [{"id": "l1", "name": "Doing"}, {"id": "l2", "name": "Done"}],
+            "cards": [{"shortLink": "aB3x", "name": "Onboarding copy", "desc": "", "idList": "l1", "closed": false, "labels": [{"name": "high"}]},
+                      {"shortLink": "Zz9q", "name": "Logo", "desc": "SVG", "idList": "l2", "closed": false, "labels": []}]}"#;
+        let issues = parse(Source::Trello, trello).unwrap();
+        assert_eq!(issues[0].external_ref, "trello:aB3x");
+        assert_eq!((issues[0].status.clone(), issues[0].priority.clone()), (Status::InProgress, Priority::High));
+        assert_eq!(issues[1].status, Status::Done);
+    }
+
+    #[test]
+    fn test_create_tickets_skips_known_refs() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("plan/tickets")).unwrap();
+        fs::write(root.join("plan/tickets/T-002.toml"), "[meta]\nid = \"T-002\"\ntitle = \"Login\"\nstatus = \"todo\"\npriority = \"high\"\nexternal_ref = \"jira:WEB-12\"\n\n[spec]\ndescription = \"\"\n\n[verification]\ncommand = \"\"\n").unwrap();
+
+        let csv = "Issue key,Summary,Status\nWEB-12,Login fails,Done\nWEB-14,Dark mode,Blocked\n";
+        let issues = parse(Source::Jira, csv).unwrap();
+        let summary = create_tickets(root, Source::Jira, &issues).unwrap();
+        assert_eq!(summary.created, [("jira:WEB-14".to_string(), "T-003".to_string())]);
+        assert_eq!(summary.skipped, ["jira:WEB-12"]);
+
+        let ticket = crate::DirectorPlan::new(root.to_path_buf()).get_ticket("T-003").unwrap();
+        assert_eq!(ticket.meta.external_ref.as_deref(), Some("jira:WEB-14"));
+        assert_eq!(ticket.meta.status, Status::Blocked);
+        assert_eq!(ticket.meta.blocked_reason.as_deref(), Some("Blocked in jira"));
+    }
+}
@@ -0,0 +1,107 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use crate::config::PlanConfig;
+
+const CONFIG_TEMPLATE: &str = include_str!("../templates/init/config.toml");
+const SAMPLE_TICKET: &str = include_str!("../templates/init/ticket.toml");
+const FEATURE_TEMPLATE: &str = include_str!("../templates/init/feature.toml");
+const VERIFY_WORKFLOW: &str = include_str!("../templates/init/verify.yml");
+
+/// Generated output that shouldn't be committed.
+const GITIGNORE_ENTRIES: [&str; 2] = ["/target/public/artifacts/", "/.director/"];
+
+/// What `init` did, relative to the workspace root.
+#[derive(Debug, Default, Serialize)]
+pub struct InitReport {
+    pub created: Vec<PathBuf>,
+    /// Already there and left alone
+    pub skipped: Vec<PathBuf>,
+}
+
+/// Scaffolds a workspace: `plan/tickets` (with a sample ticket if it's empty), `plan/templates`,
+/// a commented `plan/config.toml`, `.gitignore` entries and, with `github_actions`, a workflow
+/// that verifies tickets in review. Existing files are never overwritten, so it's safe to re-run.
+pub fn init(workspace_root: &Path, github_actions: bool) -> Result<InitReport> {
+    let mut report = InitReport::default();
+
+    let tickets = workspace_root.join("plan/tickets");
+    let has_tickets = tickets.exists()
+        && fs::read_dir(&tickets)?.any(|e| e.map(|e| crate::ticket_format::is_ticket_file(&e.path())).unwrap_or(false));
+    fs::create_dir_all(&tickets).context("Failed to create plan/tickets")?;
+    if !has_tickets {
+        write_new(workspace_root, "plan/tickets/T-001.toml", SAMPLE_TICKET, &mut report)?;
+    }
+
+    write_new(workspace_root, "plan/config.toml", CONFIG_TEMPLATE, &mut report)?;
+    write_new(workspace_root, "plan/templates/feature.toml", FEATURE_TEMPLATE, &mut report)?;
+
+    if github_actions {
+        let config = PlanConfig::load(workspace_root)?;
+        let base = crate::commit::base_branch(workspace_root, &config);
+        let workflow = VERIFY_WORKFLOW.replace("{base_branch}", &base);
+        write_new(workspace_root, ".github/workflows/director-plan.yml", &workflow, &mut report)?;
+    }
+
+    let gitignore = workspace_root.join(".gitignore");
+    let existing = fs::read_to_string(&gitignore).unwrap_or_default();
+    let missing: Vec<&str> = GITIGNORE_ENTRIES.iter()
+        .copied()
+        .filter(|entry| !existing.lines().any(|l| l.trim() == *entry))
+        .collect();
+    if missing.is_empty() {
+        report.skipped.push(PathBuf::from(".gitignore"));
+    } else {
+        let mut content = existing;
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push_str("# director-plan\n");
+        for entry in missing {
+            content.push_str(entry);
+            content.push('\n');
+        }
+        fs::write(&gitignore, content).context("Failed to update .gitignore")?;
+        report.created.push(PathBuf::from(".gitignore"));
+    }
+
+    Ok(report)
+}
+
+fn write_new(workspace_root: &Path, relative: &str, content: &str, report: &mut InitReport) -> Result<()> {
+    let path = workspace_root.join(relative);
+    if path.exists() {
+        report.skipped.push(PathBuf::from(relative));
+        return Ok(());
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+    }
+    fs::write(&path, content).with_context(|| format!("Failed to write {:?}", path))?;
+    report.created.push(PathBuf::from(relative));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Ticket;
+
+    #[test]
+    fn test_init_scaffolds_and_is_rerunnable() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = init(dir.path(), false).unwrap();
+        assert!(first.created.contains(&PathBuf::from("plan/config.toml")));
+        assert!(first.created.contains(&PathBuf::from("plan/tickets/T-001.toml")));
+
+        // The scaffolded files must load as-is
+        PlanConfig::load(dir.path()).unwrap();
+        let ticket: Ticket = toml_edit::de::from_str(&fs::read_to_string(dir.path().join("plan/tickets/T-001.toml")).unwrap()).unwrap();
+        assert_eq!(ticket.meta.id, "T-001");
+
+        let second = init(dir.path(), false).unwrap();
+        assert!(second.created.is_empty());
+        assert_eq!(fs::read_to_string(dir.path().join(".gitignore")).unwrap().matches("/.director/").count(), 1);
+    }
+}
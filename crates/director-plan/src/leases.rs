@@ -0,0 +1,134 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// A worker's time-limited claim on a ticket. Workers renew it with heartbeats;
+/// once `expires_at` passes the server hands the ticket back to `todo`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct Lease {
+    pub ticket_id: String,
+    pub worker_id: String,
+    pub acquired_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl Lease {
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at <= now
+    }
+}
+
+pub enum Claim {
+    Granted(Lease),
+    /// Another worker holds an unexpired lease.
+    Held(Lease),
+}
+
+/// Server-side lease bookkeeping, persisted to `.director/leases.json` so leases survive restarts.
+/// Callers serialize access (the server keeps it behind a mutex).
+pub struct LeaseTable {
+    path: PathBuf,
+    leases: BTreeMap<String, Lease>,
+}
+
+impl LeaseTable {
+    pub fn load(workspace_root: &Path) -> Result<Self> {
+        let path = crate::state_dir(workspace_root).join("leases.json");
+        let leases = if path.exists() {
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read leases: {:?}", path))?;
+            serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse leases: {:?}", path))?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Self { path, leases })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs::write(&self.path, serde_json::to_string_pretty(&self.leases)?)
+            .with_context(|| format!("Failed to write leases: {:?}", self.path))
+    }
+
+    pub fn get(&self, ticket_id: &str) -> Option<&Lease> {
+        self.leases.get(ticket_id)
+    }
+
+    /// Grants a lease unless another worker holds a live one. Re-claiming by the
+    /// current holder just extends it.
+    pub fn claim(&mut self, ticket_id: &str, worker_id: &str, ttl_secs: i64, now: DateTime<Utc>) -> Claim {
+        if let Some(existing) = self.leases.get(ticket_id)
+            && existing.worker_id != worker_id && !existing.is_expired(now)
+        {
+            return Claim::Held(existing.clone());
+        }
+
+        let acquired_at = match self.leases.get(ticket_id) {
+            Some(existing) if existing.worker_id == worker_id => existing.acquired_at,
+            _ => now,
+        };
+        let lease = Lease {
+            ticket_id: ticket_id.to_string(),
+            worker_id: worker_id.to_string(),
+            acquired_at,
+            expires_at: now + Duration::seconds(ttl_secs),
+        };
+        self.leases.insert(ticket_id.to_string(), lease.clone());
+        Claim::Granted(lease)
+    }
+
+    /// Extends the holder's lease. Returns `None` if `worker_id` no longer holds it.
+    pub fn renew(&mut self, ticket_id: &str, worker_id: &str, ttl_secs: i64, now: DateTime<Utc>) -> Option<Lease> {
+        let lease = self.leases.get_mut(ticket_id)?;
+        if lease.worker_id != worker_id || lease.is_expired(now) {
+            return None;
+        }
+        lease.expires_at = now + Duration::seconds(ttl_secs);
+        Some(lease.clone())
+    }
+
+    pub fn release(&mut self, ticket_id: &str, worker_id: &str) -> bool {
+        match self.leases.get(ticket_id) {
+            Some(lease) if lease.worker_id == worker_id => {
+                self.leases.remove(ticket_id);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Removes and returns every lease that has run out.
+    pub fn take_expired(&mut self, now: DateTime<Utc>) -> Vec<Lease> {
+        let expired: Vec<String> = self.leases
+            .iter()
+            .filter(|(_, lease)| lease.is_expired(now))
+            .map(|(id, _)| id.clone())
+            .collect();
+        expired.into_iter().filter_map(|id| self.leases.remove(&id)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_claim_conflicts_until_expiry() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut table = LeaseTable::load(dir.path()).unwrap();
+        let now = Utc::now();
+
+        assert!(matches!(table.claim("T-001", "a", 60, now), Claim::Granted(_)));
+        assert!(matches!(table.claim("T-001", "b", 60, now), Claim::Held(_)));
+        assert!(table.renew("T-001", "b", 60, now).is_none());
+        assert!(table.renew("T-001", "a", 60, now).is_some());
+
+        let later = now + Duration::seconds(120);
+        assert_eq!(table.take_expired(later).len(), 1);
+        assert!(matches!(table.claim("T-001", "b", 60, later), Claim::Granted(_)));
+    }
+}
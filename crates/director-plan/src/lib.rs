@@ -4,13 +4,71 @@ pub mod context;
 pub mod verification;
 pub mod execution_loop;
 pub mod worker;
+pub mod config;
+pub mod sla;
+pub mod store;
+pub mod runs;
+pub mod timing;
+pub mod telemetry;
+pub mod shutdown;
+pub mod process;
+pub mod locks;
+pub mod leases;
+pub mod remote;
+pub mod patch;
+pub mod container;
+pub mod secrets;
+pub mod gc;
+pub mod commit;
+pub mod scope;
+pub mod prompt;
+pub mod init;
+pub mod output;
+pub mod error;
+pub mod assets;
+pub mod ratelimit;
+pub mod proxy;
+pub mod tls;
+pub mod ui;
+pub mod report;
+pub mod schedule;
+pub mod split;
+pub mod blocking;
+pub mod planner;
+pub mod agent_result;
+pub mod budget;
+pub mod worker_status;
+pub mod comments;
+pub mod import;
+pub mod export;
+pub mod ticket_format;
+pub mod migrate;
+pub mod client;
+pub mod events;
+pub mod webhooks;
+pub mod calibration;
+pub mod impact;
+pub mod run_diff;
+pub mod cancel;
+pub mod experiment;
+pub mod replay;
+pub mod mock_agent;
+pub mod testing;
 
 use std::fs;
-use std::path::PathBuf;
-use anyhow::{Context, Result, anyhow};
-use types::{Ticket, Status};
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use types::{Ticket, Status, Priority};
+use error::DirectorError;
 use walkdir::WalkDir;
+use comments::{Comment, CommentStore};
+use execution_loop::{ExecutionLoop, ExecutionResult};
+use store::TicketStore;
+use verification::batch::TicketVerification;
 
+/// Library entry point for a workspace on disk: ticket CRUD, comments, verification and
+/// execution, the same operations the CLI runs. For a plan served elsewhere, use
+/// [`client::Client`].
 pub struct DirectorPlan {
     workspace_root: PathBuf,
 }
@@ -20,6 +78,15 @@ impl DirectorPlan {
         Self { workspace_root: root }
     }
 
+    pub fn workspace_root(&self) -> &Path {
+        &self.workspace_root
+    }
+
+    /// Format-aware, comment-preserving access to the ticket files.
+    pub fn store(&self) -> TicketStore {
+        TicketStore::new(&self.workspace_root)
+    }
+
     pub fn get_tickets_dir(&self) -> PathBuf {
         self.workspace_root.join("plan/tickets")
     }
@@ -37,11 +104,8 @@ impl DirectorPlan {
             .into_iter()
             .filter_map(|e| e.ok())
         {
-            if entry.path().extension().map_or(false, |ext| ext == "toml") {
-                let content = fs::read_to_string(entry.path())
-                    .with_context(|| format!("Failed to read ticket file: {:?}", entry.path()))?;
-                let ticket: Ticket = toml_edit::de::from_str(&content)
-                    .with_context(|| format!("Failed to parse ticket file: {:?}", entry.path()))?;
+            if ticket_format::is_ticket_file(entry.path()) {
+                let ticket = ticket_format::read_ticket(entry.path())?;
 
                 if let Some(filter) = &status_filter {
                     if &ticket.meta.status == filter {
@@ -60,16 +124,188 @@ impl DirectorPlan {
     }
 
     pub fn get_ticket(&self, id: &str) -> Result<Ticket> {
-        let ticket_path = self.get_tickets_dir().join(format!("{}.toml", id));
+        let ticket_path = ticket_format::find_ticket_file(&self.get_tickets_dir(), id);
         if !ticket_path.exists() {
-            return Err(anyhow!("Ticket {} not found", id));
+            return Err(DirectorError::TicketNotFound(id.to_string()).into());
         }
 
         let content = fs::read_to_string(&ticket_path)
             .context("Failed to read ticket file")?;
-        let ticket: Ticket = toml_edit::de::from_str(&content)
-            .context("Failed to parse ticket file")?;
+        let format = ticket_format::TicketFormat::from_path(&ticket_path).unwrap_or(ticket_format::TicketFormat::Toml);
+        let ticket = format.parse_ticket(&content)
+            .map_err(|e| DirectorError::InvalidConfig(format!("{:?}: {:#}", ticket_path, e)))?;
 
         Ok(ticket)
     }
+
+    /// Writes a new `todo` ticket, numbered after the existing `T-NNN` ones. Returns its ID.
+    pub fn create_ticket(&self, title: &str, description: &str, verification: &str) -> Result<String> {
+        let store = self.store();
+        fs::create_dir_all(store.tickets_dir())?;
+        let id = format!("T-{:03}", planner::next_number(&store)?);
+        let created_at: toml_datetime::Datetime = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string().parse()?;
+
+        let mut doc = toml_edit::DocumentMut::new();
+        doc["meta"] = toml_edit::table();
+        doc["meta"]["id"] = toml_edit::value(id.as_str());
+        doc["meta"]["title"] = toml_edit::value(title);
+        doc["meta"]["status"] = toml_edit::value(Status::Todo.to_string());
+        doc["meta"]["priority"] = toml_edit::value(format!("{:?}", Priority::Medium).to_lowercase());
+        doc["meta"]["created_at"] = toml_edit::value(created_at);
+        migrate::stamp(&mut doc);
+        doc["spec"] = toml_edit::table();
+        doc["spec"]["description"] = toml_edit::value(description);
+        doc["verification"] = toml_edit::table();
+        doc["verification"]["command"] = toml_edit::value(verification);
+        append_history_entry(&mut doc, "Created");
+
+        store.save_document(&id, &doc)?;
+        Ok(id)
+    }
+
+    /// Read-modify-write of one ticket's document, keeping its comments and formatting.
+    pub fn update_ticket<F>(&self, id: &str, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut toml_edit::DocumentMut) -> Result<()>,
+    {
+        self.store().update(id, f)
+    }
+
+    /// Moves a ticket to `status`, recording the change in its history.
+    pub fn set_status(&self, id: &str, status: Status) -> Result<()> {
+        self.store().set_status(id, status)
+    }
+
+    /// Removes the ticket file and its discussion. Run records and artifacts are left for `gc`.
+    pub fn delete_ticket(&self, id: &str) -> Result<()> {
+        let path = self.store().ticket_path(id);
+        if !path.exists() {
+            return Err(DirectorError::TicketNotFound(id.to_string()).into());
+        }
+        fs::remove_file(&path).with_context(|| format!("Failed to delete {:?}", path))?;
+        CommentStore::new(&self.workspace_root).remove(id)?;
+        events::emit(&self.workspace_root, events::PlanEvent::TicketDeleted { id: id.to_string() });
+        Ok(())
+    }
+
+    pub fn comments(&self, id: &str) -> Result<Vec<Comment>> {
+        CommentStore::new(&self.workspace_root).list(id)
+    }
+
+    pub fn add_comment(&self, id: &str, author: &str, body: &str, reply_to: Option<u32>) -> Result<Comment> {
+        self.get_ticket(id)?;
+        CommentStore::new(&self.workspace_root).add(id, author, body, reply_to)
+    }
+
+    /// Runs the ticket's verification command under the workspace's `[policy]`, output captured.
+    pub fn verify(&self, id: &str) -> Result<TicketVerification> {
+        let ticket = self.get_ticket(id)?;
+        let config = config::PlanConfig::load(&self.workspace_root)?;
+        Ok(verification::batch::verify_ticket(&self.workspace_root, &config, &ticket))
+    }
+
+    /// Hands the ticket to `agent` and verifies the result, retrying as `director-plan execute` does.
+    /// Fails with [`locks::AlreadyRunning`] if the ticket is being run elsewhere; `director-plan cancel`
+    /// stops it with [`shutdown::Interrupted`].
+    pub fn execute(&self, id: &str, agent: &str) -> Result<ExecutionResult> {
+        let ticket = self.get_ticket(id)?;
+        let lock = locks::LockStore::new(&self.workspace_root).acquire(id)?;
+        let cancel = shutdown::CancelToken::new();
+        lock.cancel_on_request(cancel.clone());
+        ExecutionLoop::new(&self.workspace_root, agent.to_string(), ticket).with_cancel(cancel).run_with_handshake()
+    }
+}
+
+/// Optional file marking a workspace root, for repos whose `plan/` lives elsewhere or isn't created yet.
+pub const WORKSPACE_MARKER: &str = ".director-plan.toml";
+
+/// The nearest directory at or above `start` that has a `plan/` directory or a
+/// [`WORKSPACE_MARKER`], the way git finds `.git`.
+pub fn find_workspace_root(start: &Path) -> Option<PathBuf> {
+    start.ancestors()
+        .find(|dir| dir.join("plan").is_dir() || dir.join(WORKSPACE_MARKER).is_file())
+        .map(Path::to_path_buf)
+}
+
+/// Local, untracked state (run records, locks, caches) lives in `.director/`.
+/// The directory carries its own `.gitignore` so it never shows up as a dirty tree.
+pub fn state_dir(workspace_root: &Path) -> PathBuf {
+    let dir = workspace_root.join(".director");
+    if !dir.exists() {
+        let _ = fs::create_dir_all(&dir);
+    }
+    let gitignore = dir.join(".gitignore");
+    if !gitignore.exists() {
+        let _ = fs::write(&gitignore, "*\n");
+    }
+    dir
+}
+
+/// Appends a timestamped entry to `history.log`, creating the table/array if needed.
+pub fn append_history_entry(doc: &mut toml_edit::DocumentMut, message: &str) {
+    let entry = format!("[{}] {}", chrono::Utc::now().to_rfc3339(), message);
+
+    // Ensure history table exists
+    if doc.get("history").is_none() {
+        doc["history"] = toml_edit::Item::Table(toml_edit::Table::new());
+    }
+
+    let history = doc["history"].as_table_mut().unwrap();
+
+    // Ensure log array exists
+    if history.get("log").is_none() {
+        history.insert("log", toml_edit::Item::Value(toml_edit::Value::Array(toml_edit::Array::new())));
+    }
+
+    if let Some(log) = history.get_mut("log")
+        && let Some(arr) = log.as_array_mut()
+    {
+        arr.push(entry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_workspace_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("crates/app/src");
+        fs::create_dir_all(&nested).unwrap();
+        assert_eq!(find_workspace_root(&nested), None);
+
+        fs::create_dir_all(dir.path().join("plan")).unwrap();
+        assert_eq!(find_workspace_root(&nested).as_deref(), Some(dir.path()));
+
+        fs::write(dir.path().join("crates/app").join(WORKSPACE_MARKER), "").unwrap();
+        assert_eq!(find_workspace_root(&nested), Some(dir.path().join("crates/app")));
+    }
+
+    #[test]
+    fn test_facade_crud() {
+        let dir = tempfile::tempdir().unwrap();
+        let plan = DirectorPlan::new(dir.path().to_path_buf());
+
+        let id = plan.create_ticket("Shadows", "Soften the card shadows", "true").unwrap();
+        assert_eq!(id, "T-001");
+        assert_eq!(plan.create_ticket("Borders", "", "false").unwrap(), "T-002");
+        assert_eq!(plan.get_ticket(&id).unwrap().meta.title, "Shadows");
+
+        plan.set_status(&id, Status::InProgress).unwrap();
+        assert_eq!(plan.list_tickets(Some(Status::InProgress)).unwrap().len(), 1);
+
+        plan.add_comment(&id, "dana", "Blur too?", None).unwrap();
+        assert_eq!(plan.comments(&id).unwrap().len(), 1);
+        assert!(plan.add_comment("T-404", "dana", "?", None).is_err());
+
+        let verified = plan.verify(&id).unwrap();
+        assert!(verified.passed);
+        assert!(!plan.verify("T-002").unwrap().passed);
+
+        plan.delete_ticket(&id).unwrap();
+        assert!(plan.get_ticket(&id).is_err());
+        assert!(plan.comments(&id).unwrap().is_empty());
+        assert!(plan.delete_ticket(&id).is_err());
+    }
 }
@@ -0,0 +1,472 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration as StdDuration, Instant};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use crate::shutdown::CancelToken;
+
+/// How often a held lock's `heartbeat_at` is refreshed.
+const HEARTBEAT_SECS: u64 = 30;
+
+/// How often a held lock looks for a cancel request.
+const CANCEL_POLL: StdDuration = StdDuration::from_secs(1);
+
+/// Missed heartbeats after which a lock from another host counts as abandoned.
+const STALE_AFTER_SECS: i64 = 4 * HEARTBEAT_SECS as i64;
+
+/// Written to `.director/locks/<ticket-id>.lock` while `execute` or a worker runs a ticket,
+/// so a second run can be refused and a later worker can tell a live run from one whose
+/// process died.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RunLock {
+    pub ticket_id: String,
+    pub pid: u32,
+    pub host: String,
+    pub started_at: DateTime<Utc>,
+    /// Refreshed every 30 seconds while the run is alive; absent in locks from older versions
+    #[serde(default)]
+    pub heartbeat_at: Option<DateTime<Utc>>,
+}
+
+impl RunLock {
+    fn current(ticket_id: &str) -> Self {
+        let now = Utc::now();
+        Self {
+            ticket_id: ticket_id.to_string(),
+            pid: std::process::id(),
+            host: hostname(),
+            started_at: now,
+            heartbeat_at: Some(now),
+        }
+    }
+
+    /// A lock is stale when it was taken on this host by a process that no longer exists, or
+    /// on another host by a run that stopped sending heartbeats. Other hosts' PIDs can't be
+    /// checked, so their locks without a heartbeat never go stale.
+    pub fn is_stale(&self) -> bool {
+        if self.host == hostname() {
+            return !process_alive(self.pid);
+        }
+        self.heartbeat_at.is_some_and(|at| Utc::now() - at > Duration::seconds(STALE_AFTER_SECS))
+    }
+
+    /// Why the lock [`is_stale`](Self::is_stale), for the run's history.
+    pub fn stale_reason(&self) -> String {
+        match self.heartbeat_at.filter(|_| self.host != hostname()) {
+            Some(at) => format!("no heartbeat from {} for {}s", self.host, (Utc::now() - at).num_seconds()),
+            None => format!("pid {} on {} is no longer running", self.pid, self.host),
+        }
+    }
+
+    fn is_same_run(&self, other: &RunLock) -> bool {
+        self.pid == other.pid && self.host == other.host && self.started_at == other.started_at
+    }
+}
+
+/// Written to `.director/locks/<ticket-id>.cancel` by `director-plan cancel` or the API, to ask
+/// the run holding the lock to stop.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CancelRequest {
+    pub requested_at: DateTime<Utc>,
+    pub reason: Option<String>,
+}
+
+/// Returned by [`LockStore::acquire`] when another live run holds the ticket.
+#[derive(Debug)]
+pub struct AlreadyRunning(pub RunLock);
+
+impl std::fmt::Display for AlreadyRunning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f, "Ticket {} is already running since {} (pid {} on {})",
+            self.0.ticket_id, self.0.started_at.format("%Y-%m-%d %H:%M:%S UTC"), self.0.pid, self.0.host,
+        )
+    }
+}
+
+impl std::error::Error for AlreadyRunning {}
+
+pub struct LockStore {
+    locks_dir: PathBuf,
+}
+
+impl LockStore {
+    pub fn new(workspace_root: &Path) -> Self {
+        Self { locks_dir: crate::state_dir(workspace_root).join("locks") }
+    }
+
+    fn lock_path(&self, ticket_id: &str) -> PathBuf {
+        self.locks_dir.join(format!("{}.lock", ticket_id))
+    }
+
+    fn cancel_path(&self, ticket_id: &str) -> PathBuf {
+        self.locks_dir.join(format!("{}.cancel", ticket_id))
+    }
+
+    pub fn read(&self, ticket_id: &str) -> Result<Option<RunLock>> {
+        let path = self.lock_path(ticket_id);
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read lock file: {:?}", path)),
+        };
+        let lock = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse lock file: {:?}", path))?;
+        Ok(Some(lock))
+    }
+
+    /// Takes the lock for `ticket_id`, replacing it if the previous holder is gone, and fails
+    /// with [`AlreadyRunning`] if it isn't. The returned guard keeps the lock's heartbeat going
+    /// and removes the lock file when dropped.
+    pub fn acquire(&self, ticket_id: &str) -> Result<LockGuard> {
+        self.acquire_with(ticket_id, false)
+    }
+
+    /// Like [`LockStore::acquire`], but with `force` the lock is taken from a live holder too.
+    /// That run carries on; it just no longer keeps others out.
+    pub fn acquire_with(&self, ticket_id: &str, force: bool) -> Result<LockGuard> {
+        fs::create_dir_all(&self.locks_dir).context("Failed to create locks directory")?;
+
+        let existing = match self.read(ticket_id) {
+            Ok(existing) => existing,
+            // Truncated or corrupt, so no run can be holding it; replaced like a stale lock
+            Err(e) if e.is::<serde_json::Error>() => {
+                if let Some(taken) = self.remove_if_held_by(ticket_id, None)? {
+                    return Err(AlreadyRunning(taken).into());
+                }
+                None
+            }
+            Err(e) => return Err(e),
+        };
+        if let Some(existing) = existing {
+            if !force && !existing.is_stale() {
+                return Err(AlreadyRunning(existing).into());
+            }
+            if let Some(taken) = self.remove_if_held_by(ticket_id, Some(&existing))? {
+                return Err(AlreadyRunning(taken).into());
+            }
+        }
+
+        // Left by a run that finished before it noticed
+        let _ = fs::remove_file(self.cancel_path(ticket_id));
+
+        // Written in full first and linked into place, so the lock is never seen half-written
+        // and only one of two runs taking over the same stale lock gets it
+        let path = self.lock_path(ticket_id);
+        let lock = RunLock::current(ticket_id);
+        let tmp = scratch_path(&path);
+        fs::write(&tmp, serde_json::to_string_pretty(&lock)?)
+            .with_context(|| format!("Failed to write lock file: {:?}", tmp))?;
+        let linked = fs::hard_link(&tmp, &path);
+        let _ = fs::remove_file(&tmp);
+        if let Err(e) = linked {
+            if e.kind() == ErrorKind::AlreadyExists
+                && let Some(existing) = self.read(ticket_id)?
+            {
+                return Err(AlreadyRunning(existing).into());
+            }
+            return Err(e).with_context(|| format!("Failed to create lock file: {:?}", path));
+        }
+
+        let (stop, stopped) = mpsc::channel();
+        let watcher: Arc<Mutex<Option<CancelToken>>> = Arc::default();
+        {
+            let (path, cancel_path, lock, watcher) = (path.clone(), self.cancel_path(ticket_id), lock.clone(), watcher.clone());
+            thread::spawn(move || {
+                let mut last_beat = Instant::now();
+                while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(CANCEL_POLL) {
+                    if cancel_path.exists()
+                        && let Some(cancel) = watcher.lock().unwrap().as_ref()
+                    {
+                        cancel.cancel();
+                    }
+                    if last_beat.elapsed() >= StdDuration::from_secs(HEARTBEAT_SECS) {
+                        if !beat(&path, &lock) {
+                            break;
+                        }
+                        last_beat = Instant::now();
+                    }
+                }
+            });
+        }
+
+        Ok(LockGuard { path, lock, watcher, _stop: stop })
+    }
+
+    /// Asks the live run holding `ticket_id`'s lock to stop; it notices within a second or so.
+    /// Returns that run's lock, or `None` if no live run holds it.
+    pub fn request_cancel(&self, ticket_id: &str, reason: Option<&str>) -> Result<Option<RunLock>> {
+        let Some(lock) = self.read(ticket_id)?.filter(|lock| !lock.is_stale()) else {
+            return Ok(None);
+        };
+        let path = self.cancel_path(ticket_id);
+        let request = CancelRequest { requested_at: Utc::now(), reason: reason.map(String::from) };
+        fs::write(&path, serde_json::to_string_pretty(&request)?)
+            .with_context(|| format!("Failed to write cancel request: {:?}", path))?;
+        Ok(Some(lock))
+    }
+
+    /// The cancel request waiting for `ticket_id`'s run, removed so it is acted on once.
+    pub fn take_cancel_request(&self, ticket_id: &str) -> Option<CancelRequest> {
+        let path = self.cancel_path(ticket_id);
+        let content = fs::read_to_string(&path).ok()?;
+        let _ = fs::remove_file(&path);
+        serde_json::from_str(&content).ok()
+    }
+
+    /// Every lock currently on disk. Unreadable lock files are skipped.
+    pub fn list(&self) -> Result<Vec<RunLock>> {
+        let mut locks = Vec::new();
+        if !self.locks_dir.exists() {
+            return Ok(locks);
+        }
+
+        for entry in fs::read_dir(&self.locks_dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == "lock")
+                && let Ok(content) = fs::read_to_string(&path)
+                && let Ok(lock) = serde_json::from_str::<RunLock>(&content)
+            {
+                locks.push(lock);
+            }
+        }
+        Ok(locks)
+    }
+
+    /// Removes `ticket_id`'s lock if it is still `expected`, or with `None` still unreadable. If
+    /// another run took it over since it was read, its lock is put back and returned.
+    fn remove_if_held_by(&self, ticket_id: &str, expected: Option<&RunLock>) -> Result<Option<RunLock>> {
+        let path = self.lock_path(ticket_id);
+        // Moved aside rather than removed, so what's removed is exactly what's checked
+        let removed = scratch_path(&path);
+        match fs::rename(&path, &removed) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("Failed to remove lock file: {:?}", path)),
+        }
+        let taken = fs::read_to_string(&removed).ok()
+            .and_then(|content| serde_json::from_str::<RunLock>(&content).ok())
+            .filter(|on_disk| expected.is_none_or(|expected| !on_disk.is_same_run(expected)));
+        if taken.is_some() {
+            let _ = fs::hard_link(&removed, &path);
+        }
+        let _ = fs::remove_file(&removed);
+        Ok(taken)
+    }
+
+    pub fn release(&self, ticket_id: &str) -> Result<()> {
+        let path = self.lock_path(ticket_id);
+        if path.exists() {
+            fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove lock file: {:?}", path))?;
+        }
+        Ok(())
+    }
+}
+
+/// Holds a ticket's run lock; the file is removed on drop, unless another run has since
+/// taken it over.
+pub struct LockGuard {
+    path: PathBuf,
+    lock: RunLock,
+    watcher: Arc<Mutex<Option<CancelToken>>>,
+    /// Dropping it stops the heartbeat thread
+    _stop: Sender<()>,
+}
+
+impl LockGuard {
+    /// Fires `cancel` when a cancel request for this run turns up.
+    pub fn cancel_on_request(&self, cancel: CancelToken) {
+        *self.watcher.lock().unwrap() = Some(cancel);
+    }
+}
+
+impl Drop for LockGuard {
+    fn drop(&mut self) {
+        if held_by(&self.path, &self.lock) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+fn held_by(path: &Path, lock: &RunLock) -> bool {
+    fs::read_to_string(path).ok()
+        .and_then(|content| serde_json::from_str::<RunLock>(&content).ok())
+        .is_some_and(|on_disk| on_disk.is_same_run(lock))
+}
+
+/// A file next to the lock at `path` for writing or moving it aside, unique to this call.
+fn scratch_path(path: &Path) -> PathBuf {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    path.with_extension(format!("{}-{}.tmp", std::process::id(), NEXT.fetch_add(1, Ordering::Relaxed)))
+}
+
+/// Refreshes `heartbeat_at` in the lock at `path`. Returns `false` once the lock is no longer
+/// `lock`'s: released, or taken over with `--force`.
+fn beat(path: &Path, lock: &RunLock) -> bool {
+    let beaten = RunLock { heartbeat_at: Some(Utc::now()), ..lock.clone() };
+    let tmp = scratch_path(path);
+    if !serde_json::to_string_pretty(&beaten).is_ok_and(|content| fs::write(&tmp, content).is_ok()) {
+        return false;
+    }
+    // Moved aside rather than overwritten, so a takeover that lands in between isn't clobbered
+    let previous = scratch_path(path);
+    let beat = if fs::rename(path, &previous).is_ok() && held_by(&previous, lock) {
+        fs::hard_link(&tmp, path).is_ok()
+    } else {
+        let _ = fs::hard_link(&previous, path);
+        false
+    };
+    let _ = fs::remove_file(&previous);
+    let _ = fs::remove_file(&tmp);
+    beat
+}
+
+pub fn hostname() -> String {
+    if let Ok(host) = std::env::var("HOSTNAME")
+        && !host.is_empty()
+    {
+        return host;
+    }
+    Command::new("hostname")
+        .output()
+        .ok()
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    // `kill -0` only checks that the process exists (and that we may signal it).
+    Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(true)
+}
+
+#[cfg(windows)]
+fn process_alive(pid: u32) -> bool {
+    Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/NH"])
+        .output()
+        .map(|o| String::from_utf8_lossy(&o.stdout).contains(&pid.to_string()))
+        .unwrap_or(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_held_by_live_process_is_not_stale() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = LockStore::new(dir.path());
+
+        let guard = store.acquire("T-001").unwrap();
+        assert!(!store.read("T-001").unwrap().unwrap().is_stale());
+        assert!(store.acquire("T-001").is_err());
+
+        drop(guard);
+        assert!(store.read("T-001").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_forced_lock_outlives_the_overridden_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = LockStore::new(dir.path());
+
+        let first = store.acquire("T-001").unwrap();
+        let err = store.acquire("T-001").err().unwrap();
+        assert!(err.downcast_ref::<AlreadyRunning>().unwrap().to_string().starts_with("Ticket T-001 is already running since "));
+
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        let second = store.acquire_with("T-001", true).unwrap();
+        assert!(!beat(&first.path, &first.lock));
+        assert!(beat(&second.path, &second.lock));
+        drop(first);
+        assert!(store.read("T-001").unwrap().unwrap().is_same_run(&second.lock));
+        drop(second);
+        assert!(store.read("T-001").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_cancel_request_fires_the_holders_token() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = LockStore::new(dir.path());
+        assert!(store.request_cancel("T-001", None).unwrap().is_none());
+
+        let guard = store.acquire("T-001").unwrap();
+        let cancel = CancelToken::new();
+        guard.cancel_on_request(cancel.clone());
+        assert!(store.request_cancel("T-001", Some("wrong approach")).unwrap().is_some());
+        let deadline = Instant::now() + StdDuration::from_secs(5);
+        while !cancel.is_cancelled() && Instant::now() < deadline {
+            thread::sleep(StdDuration::from_millis(50));
+        }
+        assert!(cancel.is_cancelled());
+        assert_eq!(store.take_cancel_request("T-001").unwrap().reason.as_deref(), Some("wrong approach"));
+        assert!(store.take_cancel_request("T-001").is_none());
+    }
+
+    #[test]
+    fn test_lock_from_another_host_goes_stale_without_heartbeats() {
+        let mut lock = RunLock::current("T-001");
+        lock.host = "elsewhere".to_string();
+        assert!(!lock.is_stale());
+        lock.heartbeat_at = Some(Utc::now() - Duration::seconds(STALE_AFTER_SECS + 1));
+        assert!(lock.is_stale());
+        assert_eq!(lock.stale_reason(), format!("no heartbeat from elsewhere for {}s", STALE_AFTER_SECS + 1));
+        lock.heartbeat_at = None;
+        assert!(!lock.is_stale());
+    }
+
+    #[test]
+    fn test_unreadable_lock_is_replaced() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = LockStore::new(dir.path());
+        fs::create_dir_all(&store.locks_dir).unwrap();
+        fs::write(store.lock_path("T-001"), "{\"ticket_id\": \"T-0").unwrap();
+
+        let guard = store.acquire("T-001").unwrap();
+        assert!(store.read("T-001").unwrap().unwrap().is_same_run(&guard.lock));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_racing_takeovers_of_a_stale_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = Arc::new(LockStore::new(dir.path()));
+        let mut exited = Command::new("true").spawn().unwrap();
+        exited.wait().unwrap();
+        let stale = RunLock { pid: exited.id(), ..RunLock::current("T-001") };
+        assert!(stale.is_stale());
+        fs::create_dir_all(&store.locks_dir).unwrap();
+        fs::write(store.lock_path("T-001"), serde_json::to_string_pretty(&stale).unwrap()).unwrap();
+
+        let barrier = Arc::new(std::sync::Barrier::new(2));
+        let racers: Vec<_> = (0..2)
+            .map(|_| {
+                let (store, barrier) = (store.clone(), barrier.clone());
+                thread::spawn(move || {
+                    barrier.wait();
+                    store.acquire("T-001")
+                })
+            })
+            .collect();
+        let results: Vec<_> = racers.into_iter().map(|racer| racer.join().unwrap()).collect();
+
+        let winner = results.iter().find_map(|r| r.as_ref().ok()).expect("one run takes the lock");
+        let loser = results.iter().find_map(|r| r.as_ref().err()).expect("the other is refused");
+        assert!(loser.downcast_ref::<AlreadyRunning>().unwrap().0.is_same_run(&winner.lock));
+        assert!(store.read("T-001").unwrap().unwrap().is_same_run(&winner.lock));
+    }
+}
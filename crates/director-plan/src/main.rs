@@ -1,12 +1,45 @@
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
 use director_plan::{DirectorPlan, types::{Status, TicketSummary}};
-use director_plan::context::discovery::discover_context;
-use director_plan::execution_loop::ExecutionLoop;
+use director_plan::config::PlanConfig;
+use director_plan::sla::{self, DueState};
+use director_plan::context::ast::DependencyGraph;
+use director_plan::context::cache::ContextCache;
+use director_plan::context::curate::{self, Choice};
+use director_plan::context::discovery::{discover_context, write_context};
+use director_plan::context::docs::relevant_sections;
+use director_plan::context::history::{file_history, render_history};
+use director_plan::context::images::{image_assets, render_assets, render_referenced};
+use director_plan::context::overview::workspace_overview;
+use director_plan::context::schema::relevant_slices;
+use director_plan::execution_loop::{ExecutionLoop, ExecutionResult, SessionAgent, ShellAgent};
+use director_plan::verification::{golden, policy};
+use director_plan::verification::test_report::{self, ReportFormat, TestCase};
+use director_plan::verification::batch::{self, TicketVerification};
+use director_plan::secrets::ResolvedEnv;
+use director_plan::{agent_result, blocking, budget, calibration, error, events, export, gc, impact, import, init, migrate, output, planner, report, split, ticket_format};
+use director_plan::events::PlanEvent;
+use director_plan::error::DirectorError;
+use director_plan::shutdown::{CancelToken, Interrupted, wait_for_signal};
 use director_plan::worker::Worker;
-use std::path::PathBuf;
+use director_plan::store::TicketStore;
+use director_plan::comments::{CommentStore, render_discussion};
+use director_plan::client::Client;
+use director_plan::runs::RunStore;
+use director_plan::run_diff::{self, AttemptRef};
+use director_plan::prompt::Confirm;
+use director_plan::locks::{AlreadyRunning, LockStore};
+use director_plan::leases::LeaseTable;
+use director_plan::cancel::{self, NotRunning};
+use director_plan::experiment::{self, Experiment};
+use director_plan::replay;
+use director_plan::mock_agent::{MockScript, MockTurn};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
 use anyhow::{Result, Context};
 use std::process::Command;
 use colored::*;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
 
 use director_plan::server;
 
@@ -17,10 +50,32 @@ struct Cli {
     #[arg(long, default_value = "text")]
     log_format: LogFormat,
 
+    /// Workspace root. By default the nearest directory above the current one with `plan/` or `.director-plan.toml`
+    #[arg(long, global = true)]
+    workspace: Option<PathBuf>,
+
+    /// `json` prints each command's result as JSON on stdout; progress and logs go to stderr
+    #[arg(long, value_enum, global = true, default_value_t = OutputMode::Text)]
+    output: OutputMode,
+
+    /// Answer yes to every confirmation (stash a dirty tree, replace a stale branch, accept a resized golden)
+    #[arg(long, short = 'y', global = true)]
+    yes: bool,
+
+    /// Never ask; fail where a confirmation would be needed. Implied when stdin isn't a terminal.
+    #[arg(long, global = true, conflicts_with = "yes")]
+    non_interactive: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum OutputMode {
+    Text,
+    Json,
+}
+
 #[derive(Clone, ValueEnum)]
 enum LogFormat {
     Text,
@@ -29,20 +84,101 @@ enum LogFormat {
 
 #[derive(Subcommand)]
 enum Commands {
+    /// Scaffold a director-plan workspace in the current directory
+    Init {
+        /// Also add a GitHub Actions workflow that verifies tickets in review
+        #[arg(long)]
+        github_actions: bool,
+    },
     /// List tickets
     List {
         #[arg(long, value_enum)]
         status: Option<StatusArg>,
         #[arg(long, value_enum, default_value_t = Format::Table)]
         format: Format,
+        /// Include every sub-plan from `[[workspaces]]`, with IDs prefixed by the workspace name
+        #[arg(long)]
+        all_workspaces: bool,
+    },
+    /// Report overdue and soon-due tickets
+    Overdue {
+        #[arg(long, value_enum, default_value_t = Format::Table)]
+        format: Format,
+    },
+    /// Prune old artifacts, proof output and run records per `[retention]`
+    Gc {
+        /// List what would be removed without deleting anything
+        #[arg(long)]
+        dry_run: bool,
     },
     /// Get context for a ticket
     Context {
         id: String,
+        /// Write the discovered files into the ticket's `relevant_files` for review, instead of
+        /// printing the context
+        #[arg(long)]
+        write: bool,
+        /// List the discovered files with their scores and sizes, choose which to include,
+        /// prune or exclude, and save the choices to the ticket
+        #[arg(long, conflicts_with = "write")]
+        interactive: bool,
+    },
+    /// List the files, and the open tickets, that changing a file or symbol would affect
+    Impact {
+        /// A workspace-relative file path, or the name of a function, type or constant
+        target: String,
+        /// Only follow importers this many hops out
+        #[arg(long)]
+        depth: Option<usize>,
+        /// Append the impacted files to this ticket's `relevant_files`
+        #[arg(long)]
+        add_to: Option<String>,
     },
     /// Verify a ticket
     Verify {
         id: String,
+        /// Also write the result as a test report for CI
+        #[arg(long, value_enum, requires = "report_path")]
+        report: Option<ReportArg>,
+        #[arg(long, requires = "report")]
+        report_path: Option<PathBuf>,
+    },
+    /// Have an agent turn a goal into a backlog of dependency-linked tickets
+    Plan {
+        goal: String,
+        #[arg(long)]
+        agent: String,
+    },
+    /// Create tickets from a Jira CSV, Linear JSON or Trello JSON export. Issues already
+    /// imported (by `meta.external_ref`) are skipped.
+    Import {
+        #[arg(long, value_enum)]
+        from: ImportArg,
+        file: PathBuf,
+    },
+    /// Break a ticket into child tickets. The parent is blocked until they are all done.
+    Split {
+        id: String,
+        /// Ask this agent command to do the split; without it, the description's list items
+        /// (or sections, or paragraphs) become the children
+        #[arg(long)]
+        agent: Option<String>,
+        /// How many children the agent should make
+        #[arg(long, requires = "agent")]
+        count: Option<usize>,
+    },
+    /// Verify every ticket in a status (for nightly CI). Exits non-zero if any fail.
+    VerifyAll {
+        #[arg(long, value_enum, default_value = "review")]
+        status: StatusArg,
+        /// How many verification commands to run at once
+        #[arg(long, default_value_t = 1)]
+        jobs: usize,
+        /// Also write the results as a test report, one case per ticket
+        #[arg(long, value_enum, requires = "report_path")]
+        report: Option<ReportArg>,
+        #[arg(long, requires = "report")]
+        report_path: Option<PathBuf>,
     },
     /// Update a ticket
     Update {
@@ -53,17 +189,111 @@ enum Commands {
         owner: Option<String>,
         #[arg(long)]
         comment: Option<String>,
+        /// Due date as YYYY-MM-DD
+        #[arg(long)]
+        due_date: Option<String>,
+    },
+    /// Add to a ticket's discussion, or show it when no message is given
+    Comment {
+        id: String,
+        message: Option<String>,
+        /// Answer this comment (by its number)
+        #[arg(long, requires = "message")]
+        reply_to: Option<u32>,
+        /// Defaults to git's user.name
+        #[arg(long)]
+        author: Option<String>,
+    },
+    /// Park a ticket that is waiting on something outside the plan. Workers won't pick it up.
+    Block {
+        id: String,
+        #[arg(long)]
+        reason: String,
+    },
+    /// Return a blocked ticket to the status it was blocked from
+    Unblock {
+        id: String,
+        #[arg(long)]
+        reason: Option<String>,
     },
     /// Execute a ticket using an agent
     Execute {
         id: String,
+        /// The agent command, tried before any in `execution.agents`; without it, `[[execution.routes]]` or `execution.agents` pick the agents
         #[arg(long)]
-        agent: String,
+        agent: Option<String>,
+        /// Keep one conversation with the agent across retries instead of re-prompting from scratch
+        #[arg(long)]
+        session: bool,
+        /// The agent replies with a unified diff instead of editing files; apply it for them
+        #[arg(long)]
+        patch: bool,
+        /// Write the final verification's steps as a test report for CI
+        #[arg(long, value_enum, requires = "report_path")]
+        report: Option<ReportArg>,
+        #[arg(long, requires = "report")]
+        report_path: Option<PathBuf>,
+        /// Carry on with the ticket's interrupted or crashed run instead of starting over
+        #[arg(long)]
+        resume: bool,
+        /// Run even if another `execute` or a worker is already running the ticket
+        #[arg(long)]
+        force: bool,
+        /// Stash uncommitted changes before the run and put them back after it
+        #[arg(long)]
+        autostash: bool,
+    },
+    /// Stop the `execute` or worker run of a ticket, reverting its changes
+    Cancel {
+        id: String,
+        /// Recorded in the history, and as the block reason if the ticket was in progress
+        #[arg(long)]
+        reason: Option<String>,
+        /// Cancel through this server instead, e.g. for a worker on another machine
+        #[arg(long)]
+        server: Option<String>,
     },
     /// Run the Radkit Worker
     Worker {
         #[arg(long, default_value_t = 1)]
         pool: usize,
+        /// Base URL of the director-plan server
+        #[arg(long, default_value = "http://localhost:3000")]
+        server: String,
+        /// Read and write tickets only through the server API (for workers on another machine)
+        #[arg(long)]
+        remote: bool,
+        /// Keep one conversation with the agent across retries instead of re-prompting from scratch
+        #[arg(long)]
+        session: bool,
+        /// The agent replies with a unified diff instead of editing files; apply it for them
+        #[arg(long)]
+        patch: bool,
+        /// Work only on this sub-plan from `[[workspaces]]`
+        #[arg(long, conflicts_with = "all_workspaces")]
+        sub_plan: Option<String>,
+        /// Work on the root plan and every sub-plan in turn
+        #[arg(long)]
+        all_workspaces: bool,
+    },
+    /// Show the workers registered with a server and what they are doing
+    Workers {
+        /// Base URL of the director-plan server
+        #[arg(long, default_value = "http://localhost:3000")]
+        server: String,
+    },
+    /// Stop workers from claiming tickets until `resume`. Runs already going finish.
+    Pause {
+        /// Shown by workers and in `GET /api/stats`
+        #[arg(long)]
+        reason: Option<String>,
+    },
+    /// Let workers claim tickets again after `pause`
+    Resume,
+    /// Manage golden images for visual verification
+    Golden {
+        #[command(subcommand)]
+        subcmd: GoldenCommands,
     },
     /// Search documentation
     Docs {
@@ -71,7 +301,150 @@ enum Commands {
         subcmd: DocsCommands,
     },
     /// Start the server
-    Serve,
+    Serve {
+        /// PEM certificate chain; serve HTTPS (needs --tls-key)
+        #[arg(long, requires = "tls_key")]
+        tls_cert: Option<PathBuf>,
+        /// PEM private key for --tls-cert
+        #[arg(long, requires = "tls_cert")]
+        tls_key: Option<PathBuf>,
+        /// Serve under this path, e.g. `/director` behind a reverse proxy at a subpath
+        #[arg(long, default_value = "")]
+        base_path: String,
+        /// Serve the Studio from this directory instead of the built-in one
+        #[arg(long)]
+        ui_dir: Option<PathBuf>,
+    },
+    /// Print a shell completion script, e.g. `director-plan completions zsh > ~/.zfunc/_director-plan`
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Check every ticket file parses, reporting fields the schema doesn't have (e.g. typos)
+    Validate,
+    /// Upgrade ticket files written for an older schema, backing up the originals
+    Migrate {
+        /// List what would be upgraded without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Export every ticket as CSV, JSON lines or a Markdown table, e.g. for a spreadsheet
+    Export {
+        #[arg(long, value_enum, default_value = "csv")]
+        format: ExportArg,
+        /// Add each ticket's run counts and durations
+        #[arg(long)]
+        runs: bool,
+        /// Write here instead of to stdout
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Show the event log (ticket changes, runs, verifications, PRs), oldest first
+    Audit {
+        /// Only this ticket's events
+        #[arg(long)]
+        ticket: Option<String>,
+        /// Only the most recent N events
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+    /// Compare each agent's stated confidence with how often verification actually passed,
+    /// to pick `verification.min_confidence` from data
+    Calibration {
+        /// Only this agent (the command as recorded on its runs)
+        #[arg(long)]
+        agent: Option<String>,
+        /// Number of confidence buckets between 0 and 1
+        #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u16).range(1..=100))]
+        buckets: u16,
+        /// Pass rate the suggested `min_confidence` should achieve
+        #[arg(long, default_value_t = 0.9)]
+        target: f32,
+    },
+    /// Inspect recorded runs
+    Runs {
+        #[command(subcommand)]
+        subcmd: RunsCommands,
+    },
+    /// Send a recorded prompt to another agent in a throwaway worktree and compare what it
+    /// does with the original attempt. Name the attempt as `<run-id>:<n>`, or just `<run-id>`
+    /// for its first one.
+    Replay {
+        run: String,
+        /// The agent command to send the prompt to
+        #[arg(long)]
+        agent: String,
+    },
+    /// A scripted agent for tests and demos: reads the prompt on stdin, makes the changes of
+    /// the matching turn in `--script` (or applies `--patch`) and prints the reply. Use it as
+    /// `--agent "director-plan mock-agent --script mock.toml"`.
+    MockAgent {
+        /// TOML script of `[[turn]]`s
+        #[arg(long, conflicts_with_all = ["patch", "reply"])]
+        script: Option<PathBuf>,
+        /// Unified diff to apply to every prompt
+        #[arg(long)]
+        patch: Option<PathBuf>,
+        /// Reply to print instead of the default result block
+        #[arg(long)]
+        reply: Option<String>,
+    },
+    /// Run tickets under several agents in throwaway worktrees and compare the results
+    Experiment {
+        #[command(subcommand)]
+        subcmd: ExperimentCommands,
+    },
+    /// Print the server's OpenAPI document, e.g. to generate a typed client
+    Openapi,
+    /// Write a static HTML report (board, tickets, runs, visual diffs) that needs no server
+    Report {
+        #[arg(long, default_value = "report")]
+        out: PathBuf,
+    },
+    /// Write man pages (director-plan.1 plus one per subcommand) for packaging
+    #[command(hide = true)]
+    Mangen {
+        #[arg(long, default_value = "target/man")]
+        out_dir: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum GoldenCommands {
+    /// Promote the latest screenshots in `proof/` to the ticket's golden images
+    Accept {
+        id: String,
+        /// Only accept this `[[verification.visual]]` snapshot
+        #[arg(long)]
+        snapshot: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum RunsCommands {
+    /// Show what changed between two attempts: the prompt, verification errors and files
+    /// changed. Name an attempt as `<run-id>:<n>`, or just `<run-id>` for its last one.
+    Diff {
+        a: String,
+        b: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ExperimentCommands {
+    /// Execute every ticket under every variant, each in a fresh worktree of HEAD
+    Run {
+        /// Agent commands to compare, comma-separated; each runs without fallback
+        #[arg(long, value_delimiter = ',', required = true)]
+        variants: Vec<String>,
+        /// Ticket IDs and ranges, e.g. `T-010..T-020,T-031`
+        #[arg(long)]
+        tickets: String,
+    },
+    /// Show the results of an earlier experiment
+    Show {
+        id: String,
+    },
 }
 
 #[derive(Subcommand)]
@@ -87,6 +460,7 @@ enum StatusArg {
     Todo,
     InProgress,
     Review,
+    Blocked,
     Done,
     Archived,
 }
@@ -97,12 +471,62 @@ impl From<StatusArg> for Status {
             StatusArg::Todo => Status::Todo,
             StatusArg::InProgress => Status::InProgress,
             StatusArg::Review => Status::Review,
+            StatusArg::Blocked => Status::Blocked,
             StatusArg::Done => Status::Done,
             StatusArg::Archived => Status::Archived,
         }
     }
 }
 
+#[derive(Clone, Copy, ValueEnum)]
+enum ReportArg {
+    Junit,
+    Tap,
+}
+
+impl From<ReportArg> for ReportFormat {
+    fn from(arg: ReportArg) -> Self {
+        match arg {
+            ReportArg::Junit => ReportFormat::Junit,
+            ReportArg::Tap => ReportFormat::Tap,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ImportArg {
+    Jira,
+    Linear,
+    Trello,
+}
+
+impl From<ImportArg> for import::Source {
+    fn from(arg: ImportArg) -> Self {
+        match arg {
+            ImportArg::Jira => import::Source::Jira,
+            ImportArg::Linear => import::Source::Linear,
+            ImportArg::Trello => import::Source::Trello,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportArg {
+    Csv,
+    Jsonl,
+    Md,
+}
+
+impl From<ExportArg> for export::ExportFormat {
+    fn from(arg: ExportArg) -> Self {
+        match arg {
+            ExportArg::Csv => export::ExportFormat::Csv,
+            ExportArg::Jsonl => export::ExportFormat::Jsonl,
+            ExportArg::Md => export::ExportFormat::Markdown,
+        }
+    }
+}
+
 #[derive(Clone, ValueEnum)]
 enum Format {
     Json,
@@ -112,55 +536,718 @@ enum Format {
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
+    let json = cli.output == OutputMode::Json;
+    output::set_json(json);
 
-    // Initialize tracing
+    // Initialize tracing. With JSON output, stdout is reserved for the result.
+    let writer = if json { BoxMakeWriter::new(std::io::stderr) } else { BoxMakeWriter::new(std::io::stdout) };
     let builder = tracing_subscriber::fmt()
-        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env());
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_writer(writer);
 
     match cli.log_format {
         LogFormat::Json => builder.json().init(),
         LogFormat::Text => builder.init(),
     }
 
-    let root = std::env::current_dir()?;
+    if let Err(e) = run(cli).await {
+        let (kind, code) = error::kind(&e);
+        if json {
+            output::emit(&serde_json::json!({ "ok": false, "error": format!("{:#}", e), "kind": kind, "exit_code": code }))?;
+        } else {
+            eprintln!("Error: {:?}", e);
+        }
+        std::process::exit(code);
+    }
+    Ok(())
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    let json = cli.output == OutputMode::Json;
+    let cwd = std::env::current_dir()?;
+    let root = match (&cli.workspace, &cli.command) {
+        (Some(workspace), _) => cwd.join(workspace),
+        // `init` creates the workspace where it's run rather than adopting a parent's
+        (None, Commands::Init { .. }) => cwd,
+        (None, _) => director_plan::find_workspace_root(&cwd).unwrap_or(cwd),
+    };
     let plan = DirectorPlan::new(root.clone());
 
+    let confirm = Confirm::from_flags(cli.yes, cli.non_interactive);
+
     match cli.command {
-        Commands::Serve => {
-             server::start_server(root).await?;
+        Commands::Completions { shell } => {
+            let mut cmd = Cli::command();
+            let name = cmd.get_name().to_string();
+            clap_complete::generate(shell, &mut cmd, name, &mut std::io::stdout());
+        }
+        Commands::Openapi => {
+            println!("{}", server::openapi().to_pretty_json()?);
         }
-        Commands::Worker { pool } => {
-            let worker = Worker::new(root, pool);
-            worker.run().await?;
+        Commands::Validate => {
+            let findings = ticket_format::validate_dir(&plan.get_tickets_dir())?;
+            if json {
+                output::emit(&serde_json::json!({ "ok": findings.is_empty(), "findings": findings }))?;
+            } else {
+                if findings.is_empty() {
+                    println!("{}", "All tickets are valid.".green());
+                }
+                for finding in &findings {
+                    let file = finding.file.strip_prefix(&root).unwrap_or(&finding.file);
+                    if let Some(error) = &finding.error {
+                        println!("{} {}: {}", "error".red(), file.display(), error);
+                    }
+                    for field in &finding.unknown_fields {
+                        println!("{} {}: unknown field `{}`", "error".red(), file.display(), field);
+                    }
+                }
+            }
+            if !findings.is_empty() {
+                std::process::exit(error::exit::INVALID_CONFIG);
+            }
+        }
+        Commands::Audit { ticket, limit } => {
+            let mut records = events::EventLog::new(&root).list()?;
+            if let Some(ticket) = &ticket {
+                records.retain(|r| r.event.ticket_id() == ticket);
+            }
+            if let Some(limit) = limit {
+                records.drain(..records.len().saturating_sub(limit));
+            }
+            if json {
+                return output::emit(&serde_json::json!({ "ok": true, "events": records }));
+            }
+            if records.is_empty() {
+                println!("No events recorded.");
+            }
+            for record in &records {
+                println!(
+                    "{} {} {}",
+                    record.at.format("%Y-%m-%d %H:%M:%S").to_string().dimmed(),
+                    record.event.ticket_id().bold(),
+                    record.event.summary(),
+                );
+            }
+        }
+        Commands::Calibration { agent, buckets, target } => {
+            if !(0.0..=1.0).contains(&target) {
+                anyhow::bail!("--target must be between 0 and 1, got {}", target);
+            }
+            let runs = RunStore::new(&root).list_all()?;
+            // Runs that fell back on other agents mix attempts, so it's the report that's filtered
+            let mut report = calibration::calibrate(&runs, buckets as usize, target);
+            if let Some(agent) = &agent {
+                report.retain(|a| &a.agent == agent);
+            }
+            if json {
+                return output::emit(&serde_json::json!({ "ok": true, "target": target, "agents": report }));
+            }
+            if report.is_empty() {
+                println!("No attempts with a reported confidence yet.");
+            }
+            for agent in &report {
+                println!(
+                    "{} ({} attempts, {} passed, Brier {:.3}, calibration error {:.3})",
+                    agent.agent.bold(), agent.attempts, agent.passed, agent.brier_score, agent.calibration_error,
+                );
+                for bucket in agent.buckets.iter().filter(|b| b.attempts > 0) {
+                    let (Some(said), Some(rate)) = (bucket.mean_confidence, bucket.pass_rate) else { continue };
+                    let filled = (rate * 20.0).round() as usize;
+                    let bar = format!("{}{}", "#".repeat(filled), ".".repeat(20 - filled));
+                    let bar = if rate + 0.1 < said { bar.red() } else { bar.green() };
+                    println!(
+                        "  {:.2}-{:.2} {:>5}  {} {:>3.0}% passed, said {:.0}%",
+                        bucket.low, bucket.high, bucket.attempts, bar, rate * 100.0, said * 100.0,
+                    );
+                }
+                match agent.suggested_min_confidence {
+                    Some(threshold) => println!("  suggested min_confidence for {:.0}% passes: {:.2}", target * 100.0, threshold),
+                    None => println!("  {}", format!("not enough data for a min_confidence reaching {:.0}% passes", target * 100.0).dimmed()),
+                }
+            }
+        }
+        Commands::Runs { subcmd } => {
+            match subcmd {
+                RunsCommands::Diff { a, b } => {
+                    let store = RunStore::new(&root);
+                    let (a, b) = (AttemptRef::parse(&a), AttemptRef::parse(&b));
+                    let find = |reference: &AttemptRef| -> Result<_> {
+                        store.find(&reference.run_id)?.ok_or_else(|| anyhow::anyhow!("No run {}", reference.run_id))
+                    };
+                    let diff = run_diff::diff(&find(&a)?, a.number, &find(&b)?, b.number)?;
+                    if json {
+                        return output::emit(&serde_json::json!({ "ok": true, "diff": diff }));
+                    }
+
+                    for (side, attempt) in [("a", &diff.a), ("b", &diff.b)] {
+                        let result = if attempt.passed { "passed".green() } else { "failed".red() };
+                        println!(
+                            "{} {}:{} {} (agent {} ms, verification {} ms{})",
+                            side.bold(), attempt.run_id, attempt.attempt, result, attempt.agent_ms, attempt.verification_ms,
+                            attempt.confidence.map(|c| format!(", confidence {:.2}", c)).unwrap_or_default(),
+                        );
+                        if let Some(snapshot) = &attempt.snapshot {
+                            println!("  {}", format!("kept as {}", snapshot).dimmed());
+                        }
+                    }
+                    if let (Some(a), Some(b)) = (&diff.a.snapshot, &diff.b.snapshot) {
+                        println!("{}", format!("Compare the trees with `git diff {} {}`", a, b).dimmed());
+                    }
+                    if !diff.files_known {
+                        println!("{}", "Files changed weren't recorded for one of these attempts.".dimmed());
+                    }
+                    for (label, items) in [
+                        ("Files changed only in a", &diff.files_removed),
+                        ("Files changed only in b", &diff.files_added),
+                        ("Errors only in a", &diff.errors_removed),
+                        ("Errors only in b", &diff.errors_added),
+                    ] {
+                        if !items.is_empty() {
+                            println!("\n{}:", label.bold());
+                            for item in items {
+                                println!("  {}", item.replace('\n', "\n  "));
+                            }
+                        }
+                    }
+                    println!("\n{}", "Prompt:".bold());
+                    if diff.prompt.is_empty() {
+                        println!("  {}", "unchanged".dimmed());
+                    }
+                    for line in diff.prompt.lines() {
+                        match line.chars().next() {
+                            Some('+') => println!("{}", line.green()),
+                            Some('-') => println!("{}", line.red()),
+                            Some('@') => println!("{}", line.cyan()),
+                            _ => println!("{}", line),
+                        }
+                    }
+                }
+            }
+        }
+        Commands::Replay { run, agent } => {
+            let reference = AttemptRef::parse(&run);
+            let record = RunStore::new(&root).find(&reference.run_id)?.ok_or_else(|| anyhow::anyhow!("No run {}", reference.run_id))?;
+            let cancel = CancelToken::new();
+            {
+                let cancel = cancel.clone();
+                tokio::spawn(async move {
+                    wait_for_signal().await;
+                    cancel.cancel();
+                });
+            }
+            let replay = replay::replay(&root, &record, reference.number, &agent, &cancel)?;
+            if json {
+                return output::emit(&serde_json::json!({ "ok": true, "replay": replay }));
+            }
+
+            println!(
+                "{} {}:{} sent to {} ({} ms); originally {}",
+                "Replayed".bold(), replay.run_id, replay.attempt, replay.agent, replay.agent_ms, replay.original_agent,
+            );
+            match &replay.commit_ref {
+                Some(name) => println!("  {}", format!("changes kept as {}", name).dimmed()),
+                None => println!("  {}", "the replay changed nothing".dimmed()),
+            }
+            if let (Some(original), Some(name)) = (&replay.original_commit, &replay.commit_ref) {
+                println!("{}", format!("Compare the trees with `git diff {} {}`", original, name).dimmed());
+            }
+            if !replay.files_known {
+                println!("{}", "Files changed weren't recorded for the original attempt.".dimmed());
+            }
+            for (label, items) in [("Files changed only originally", &replay.files_removed), ("Files changed only in the replay", &replay.files_added)] {
+                if !items.is_empty() {
+                    println!("\n{}:", label.bold());
+                    for item in items {
+                        println!("  {}", item);
+                    }
+                }
+            }
+            for (label, diff) in [("Reply:", Some(&replay.output)), ("Changes against the original:", replay.changes.as_ref())] {
+                let Some(diff) = diff else { continue };
+                println!("\n{}", label.bold());
+                if diff.is_empty() {
+                    println!("  {}", "the same".dimmed());
+                }
+                for line in diff.lines() {
+                    match line.chars().next() {
+                        Some('+') => println!("{}", line.green()),
+                        Some('-') => println!("{}", line.red()),
+                        Some('@') => println!("{}", line.cyan()),
+                        _ => println!("{}", line),
+                    }
+                }
+            }
+        }
+        Commands::MockAgent { script, patch, reply } => {
+            let script = match script {
+                Some(path) => MockScript::load(&path)?,
+                None => MockScript {
+                    turns: vec![MockTurn { patch_file: patch.map(|p| p.to_string_lossy().to_string()), reply, ..Default::default() }],
+                    dir: PathBuf::new(),
+                },
+            };
+            let mut prompt = String::new();
+            std::io::Read::read_to_string(&mut std::io::stdin(), &mut prompt).context("Failed to read the prompt from stdin")?;
+            // The shell adapter runs agents from the workspace they should change
+            print!("{}", script.respond(&prompt, &std::env::current_dir()?)?);
+        }
+        Commands::Experiment { subcmd } => {
+            let experiment = match subcmd {
+                ExperimentCommands::Run { variants, tickets } => {
+                    let ids: Vec<String> = plan.list_tickets(None)?.into_iter().map(|t| t.meta.id).collect();
+                    let tickets = experiment::expand_tickets(&tickets, &ids)?;
+                    // Ctrl-C reverts the current trial and keeps the ones already run
+                    let cancel = CancelToken::new();
+                    {
+                        let cancel = cancel.clone();
+                        tokio::spawn(async move {
+                            wait_for_signal().await;
+                            cancel.cancel();
+                        });
+                    }
+                    experiment::run(&root, &variants, &tickets, &cancel)?
+                }
+                ExperimentCommands::Show { id } => experiment::load(&root, &id)?,
+            };
+            if json {
+                return output::emit(&serde_json::json!({ "ok": true, "experiment": experiment, "summary": experiment.summary() }));
+            }
+            print_experiment(&experiment);
+        }
+        Commands::Impact { target, depth, add_to } => {
+            let config = PlanConfig::load(&root)?;
+            let mut graph = DependencyGraph::new(&root).with_include_paths(config.context.include_paths.clone());
+            graph.build()?;
+            let impact = impact::analyze(&root, &graph, &target, &plan.list_tickets(None)?, depth)?;
+
+            let mut added: Vec<String> = Vec::new();
+            if let Some(id) = &add_to {
+                let ticket = plan.get_ticket(id)?;
+                added = impact.files.iter()
+                    .map(|f| f.path.clone())
+                    .filter(|path| !ticket.spec.relevant_files.contains(path))
+                    .collect();
+                if !added.is_empty() {
+                    TicketStore::new(&root).update(id, |doc| {
+                        match doc["spec"]["relevant_files"].as_array_mut() {
+                            Some(files) => added.iter().for_each(|path| files.push(path.as_str())),
+                            None => doc["spec"]["relevant_files"] = toml_edit::value(added.iter().map(String::as_str).collect::<toml_edit::Array>()),
+                        }
+                        director_plan::append_history_entry(doc, &format!("Added {} relevant file(s) affected by {}", added.len(), impact.target));
+                        Ok(())
+                    })?;
+                }
+            }
+
+            if json {
+                return output::emit(&serde_json::json!({ "ok": true, "impact": impact, "added": added }));
+            }
+            println!("{} ({}): {} file(s)", impact.target.bold(), impact.kind, impact.files.len());
+            for file in &impact.files {
+                println!("  {:>2}  {}", file.depth, file.path);
+            }
+            if !impact.tickets.is_empty() {
+                println!("\nOpen tickets:");
+                for ticket in &impact.tickets {
+                    println!("  {} [{}] {} ({})", ticket.id, ticket.status.to_string(), ticket.title, ticket.files.join(", "));
+                }
+            }
+            if let Some(id) = &add_to {
+                println!("\nAdded {} file(s) to {}'s relevant_files.", added.len(), id);
+            }
+        }
+        Commands::Migrate { dry_run } => {
+            let migrated = migrate::migrate(&root, dry_run)?;
+            if json {
+                return output::emit(&serde_json::json!({ "ok": true, "dry_run": dry_run, "migrated": migrated }));
+            }
+            if migrated.is_empty() {
+                println!("All tickets are at schema version {}.", migrate::SCHEMA_VERSION);
+            }
+            for ticket in &migrated {
+                let verb = if dry_run { "would migrate" } else { "migrated" };
+                println!("{} {} (v{} -> v{})", verb.green(), ticket.id, ticket.from, ticket.to);
+                for summary in &ticket.applied {
+                    println!("  - {}", summary);
+                }
+            }
+            if let Some(backup) = migrated.first().and_then(|t| t.backup.as_ref()).and_then(|b| b.parent()) {
+                println!("Originals backed up to {}", backup.display());
+            }
+        }
+        Commands::Export { format, runs, out } => {
+            let rows = export::rows(&root, runs)?;
+            let rendered = export::render(&rows, format.into())?;
+            match out {
+                Some(out) => {
+                    std::fs::write(&out, rendered).with_context(|| format!("Failed to write {:?}", out))?;
+                    if json {
+                        return output::emit(&serde_json::json!({ "ok": true, "out": out, "tickets": rows.len() }));
+                    }
+                    println!("Exported {} ticket(s) to {}", rows.len(), out.display());
+                }
+                None => print!("{}", rendered),
+            }
+        }
+        Commands::Report { out } => {
+            let summary = report::generate(&root, &out)?;
+            if json {
+                return output::emit(&summary);
+            }
+            println!(
+                "Wrote {} ticket(s), {} run(s) and {} image(s) to {}",
+                summary.tickets, summary.runs, summary.images, out.join("index.html").display(),
+            );
+        }
+        Commands::Mangen { out_dir } => {
+            std::fs::create_dir_all(&out_dir).with_context(|| format!("Failed to create {:?}", out_dir))?;
+            clap_mangen::generate_to(Cli::command(), &out_dir).context("Failed to write man pages")?;
+            if json {
+                output::emit(&serde_json::json!({ "ok": true, "out_dir": out_dir }))?;
+            } else {
+                println!("Wrote man pages to {}", out_dir.display());
+            }
         }
-        Commands::List { status, format } => {
+        Commands::Init { github_actions } => {
+            let report = init::init(&root, github_actions)?;
+            if json {
+                return output::emit(&report);
+            }
+            for path in &report.created {
+                println!("{} {}", "created".green(), path.display());
+            }
+            for path in &report.skipped {
+                println!("{} {} (already exists)", "skipped".yellow(), path.display());
+            }
+            println!("\nNext: edit plan/tickets/T-001.toml, then run `director-plan list`.");
+        }
+        Commands::Serve { tls_cert, tls_key, base_path, ui_dir } => {
+             let options = server::ServeOptions { tls: tls_cert.zip(tls_key), base_path, ui_dir, config: None };
+             server::start_server(root, options).await?;
+        }
+        Commands::Worker { pool, server, remote, session, patch, sub_plan, all_workspaces } => {
+            let config = PlanConfig::load(&root)?;
+            let mut scopes = Vec::new();
+            match sub_plan {
+                Some(name) => scopes.push((Some(name.clone()), config.sub_plan(&root, &name)?.1)),
+                None => {
+                    scopes.push((None, root.clone()));
+                    if all_workspaces {
+                        for (name, sub_root) in config.sub_plans(&root)? {
+                            scopes.push((Some(name), sub_root));
+                        }
+                    }
+                }
+            }
+
+            let workers: Vec<Worker> = scopes.into_iter().map(|(name, scope_root)| {
+                let mut worker = Worker::new(scope_root, pool)
+                    .with_server_url(&server)
+                    .with_session(session)
+                    .with_patch_mode(patch)
+                    .with_confirm(confirm);
+                if remote {
+                    worker = worker.remote();
+                }
+                if let Some(name) = name {
+                    worker = worker.with_workspace(&name);
+                }
+                worker
+            }).collect();
+            Worker::run_all(&workers).await?;
+        }
+        Commands::List { status, format, all_workspaces } => {
+            let format = if json { Format::Json } else { format };
             let filter = status.map(Status::from);
-            let tickets = plan.list_tickets(filter)?;
+            let mut tickets = plan.list_tickets(filter.clone())?;
+            let config = PlanConfig::load(&root)?;
+            if all_workspaces {
+                for (name, sub_root) in config.sub_plans(&root)? {
+                    for mut t in DirectorPlan::new(sub_root).list_tickets(filter.clone())? {
+                        t.meta.id = format!("{}:{}", name, t.meta.id);
+                        tickets.push(t);
+                    }
+                }
+            }
+            let today = sla::today();
 
             match format {
                 Format::Json => {
-                    let summaries: Vec<TicketSummary> = tickets.into_iter().map(|t| TicketSummary {
-                        id: t.meta.id,
-                        title: t.meta.title,
-                        status: t.meta.status,
-                        priority: t.meta.priority,
+                    let summaries: Vec<TicketSummary> = tickets.into_iter().map(|t| {
+                        let due_state = sla::due_state(&t, today, config.sla.due_soon_days);
+                        TicketSummary {
+                            id: t.meta.id,
+                            title: t.meta.title,
+                            status: t.meta.status,
+                            priority: t.meta.priority,
+                            due_date: t.meta.due_date.map(|d| d.to_string()),
+                            due_state,
+                        }
                     }).collect();
-                    println!("{}", serde_json::to_string_pretty(&summaries)?);
+                    if json {
+                        output::emit(&serde_json::json!({ "ok": true, "tickets": summaries }))?;
+                    } else {
+                        println!("{}", serde_json::to_string_pretty(&summaries)?);
+                    }
                 }
                 Format::Table => {
                     for t in tickets {
-                        println!("{} [{}] {} ({:?})",
+                        let due = match sla::due_state(&t, today, config.sla.due_soon_days) {
+                            Some(DueState::Overdue) => format!(" {}", "OVERDUE".red().bold()),
+                            Some(DueState::DueSoon) => format!(" {}", "due soon".yellow()),
+                            _ => String::new(),
+                        };
+                        println!("{} [{}] {} ({:?}){}",
                             t.meta.id.bold(),
                             t.meta.status.to_string().cyan(),
                             t.meta.title,
-                            t.meta.priority
+                            t.meta.priority,
+                            due
                         );
                     }
                 }
             }
         }
-        Commands::Context { id } => {
+        Commands::Overdue { format } => {
+            let format = if json { Format::Json } else { format };
+            let config = PlanConfig::load(&root)?;
+            let today = sla::today();
+
+            let mut flagged: Vec<_> = plan.list_tickets(None)?
+                .into_iter()
+                .filter_map(|t| {
+                    let state = sla::due_state(&t, today, config.sla.due_soon_days)?;
+                    let days = sla::days_until_due(&t, today)?;
+                    match state {
+                        DueState::Overdue | DueState::DueSoon => Some((t, state, days)),
+                        DueState::OnTrack => None,
+                    }
+                })
+                .collect();
+            flagged.sort_by_key(|(_, _, days)| *days);
+
+            match format {
+                Format::Json => {
+                    let report: Vec<serde_json::Value> = flagged.iter().map(|(t, state, days)| serde_json::json!({
+                        "id": t.meta.id,
+                        "title": t.meta.title,
+                        "status": t.meta.status,
+                        "priority": t.meta.priority,
+                        "due_date": t.meta.due_date.map(|d| d.to_string()),
+                        "due_state": state,
+                        "days_until_due": days,
+                    })).collect();
+                    if json {
+                        output::emit(&serde_json::json!({ "ok": true, "tickets": report }))?;
+                    } else {
+                        println!("{}", serde_json::to_string_pretty(&report)?);
+                    }
+                }
+                Format::Table => {
+                    if flagged.is_empty() {
+                        println!("No overdue or soon-due tickets.");
+                    }
+                    for (t, _, days) in &flagged {
+                        let when = if *days < 0 {
+                            format!("{}d overdue", -days).red().bold()
+                        } else {
+                            format!("due in {}d", days).yellow()
+                        };
+                        let due_date = t.meta.due_date.map(|d| d.to_string()).unwrap_or_default();
+                        println!("{} {} ({}) [{}] {}", t.meta.id.bold(), due_date, when, t.meta.status.to_string().cyan(), t.meta.title);
+                    }
+                }
+            }
+        }
+        Commands::Workers { server } => {
+            let workers = Client::new(&server).workers().await.with_context(|| format!("Failed to list workers on {}", server))?;
+            if json {
+                return output::emit(&serde_json::json!({ "ok": true, "workers": workers }));
+            }
+            if workers.is_empty() {
+                println!("No workers have reported in the last hour.");
+            }
+            let now = chrono::Utc::now();
+            for worker in &workers {
+                let state = if worker.alive { "alive".green() } else { "gone".red() };
+                let doing = match (&worker.heartbeat.current_ticket, &worker.heartbeat.workspace) {
+                    (Some(ticket), Some(ws)) => format!("running {}/{}", ws, ticket),
+                    (Some(ticket), None) => format!("running {}", ticket),
+                    (None, _) => "idle".to_string(),
+                };
+                println!(
+                    "{} {} {} (up {}s, seen {}s ago)",
+                    worker.heartbeat.id.bold(), state, doing,
+                    worker.heartbeat.uptime_secs, (now - worker.last_seen).num_seconds(),
+                );
+                if let Some(error) = &worker.heartbeat.last_error {
+                    println!("  last error: {}", error);
+                }
+            }
+        }
+        Commands::Pause { reason } => {
+            let pause = budget::pause(&root, reason)?;
+            if json {
+                return output::emit(&serde_json::json!({ "ok": true, "paused": true, "reason": pause.reason, "paused_at": pause.paused_at }));
+            }
+            println!("{}", pause.to_string().yellow());
+        }
+        Commands::Resume => {
+            let was_paused = budget::resume(&root)?;
+            if json {
+                return output::emit(&serde_json::json!({ "ok": true, "paused": false, "was_paused": was_paused }));
+            }
+            println!("{}", if was_paused { "Executions resumed." } else { "Executions weren't paused." });
+        }
+        Commands::Gc { dry_run } => {
+            let config = PlanConfig::load(&root)?;
+            let report = gc::collect(&root, &config.retention, dry_run)?;
+            if json {
+                return output::emit(&serde_json::json!({
+                    "ok": true,
+                    "dry_run": dry_run,
+                    "report": report,
+                    "usage": gc::disk_usage(&root),
+                }));
+            }
+
+            for path in &report.removed {
+                let path = path.strip_prefix(&root).unwrap_or(path);
+                println!("{} {}", if dry_run { "would remove" } else { "removed" }, path.display());
+            }
+            for name in &report.refs {
+                println!("{} {}", if dry_run { "would delete" } else { "deleted" }, name);
+            }
+            let verb = if dry_run { "Would free" } else { "Freed" };
+            println!("{}", format!("{} {} bytes in {} files", verb, report.bytes_freed, report.removed.len()).green());
+
+            let usage = gc::disk_usage(&root);
+            println!("Artifacts: {} bytes, proof: {} bytes, runs: {} bytes", usage.artifacts_bytes, usage.proof_bytes, usage.runs_bytes);
+        }
+        Commands::Context { id, write, interactive } => {
             let ticket = plan.get_ticket(&id)?;
+            let config = PlanConfig::load(&root)?;
+            let profile = config.context.profile(&ticket)?;
+            if interactive {
+                if !std::io::stdin().is_terminal() {
+                    anyhow::bail!("--interactive needs a terminal; use --write to store the discovered files instead");
+                }
+                let context_profile = profile.map(|(_, p)| p.clone()).unwrap_or_default();
+                let mut candidates = curate::candidates(&ticket, &root, &context_profile, &config.context.include_paths);
+                if candidates.is_empty() {
+                    println!("Discovery found no files for {}.", id);
+                    return Ok(());
+                }
+                if !curate::curate(&mut candidates, std::io::stdin().lock(), std::io::stderr())? {
+                    println!("Nothing saved.");
+                    return Ok(());
+                }
+                curate::save(&ticket, &root, &candidates)?;
+                let count = |choice: Choice| candidates.iter().filter(|c| c.choice == choice).count();
+                println!(
+                    "Saved {}'s context: {} included, {} pruned, {} excluded; auto_context is now off.",
+                    id, count(Choice::Include), count(Choice::Prune), count(Choice::Exclude),
+                );
+                return Ok(());
+            }
+            if write {
+                let context_profile = profile.map(|(_, p)| p.clone()).unwrap_or_default();
+                let written = write_context(&ticket, &root, &context_profile, &config.context.include_paths)?;
+                if json {
+                    return output::emit(&serde_json::json!({ "ok": true, "id": id, "relevant_files": written }));
+                }
+                println!("Wrote {} file(s) to {}'s relevant_files; auto_context is now off:", written.len(), id);
+                for file in &written {
+                    println!("  {:>2}  {}", file.depth, file.path);
+                }
+                return Ok(());
+            }
+            let context = match profile {
+                Some((_, profile)) => config.context.with_profile(profile),
+                None => config.context.clone(),
+            };
+            let context_profile = profile.map(|(_, p)| p.clone()).unwrap_or_default();
+
+            // Auto-Context
+            let auto_context = ticket.spec.relevant_files.is_empty();
+            if auto_context {
+                // If implicit or explicit auto_context is desired.
+                // PR says: "When director-plan context <T-ID> is called, if relevant_files is empty in the TOML, the engine now dynamically populates context."
+                director_plan::progress!("\n>> Auto-Context Discovery Triggered...");
+            }
+            let mut cache = ContextCache::new(&root, None)
+                .with_profile(context_profile.clone())
+                .with_include_paths(config.context.include_paths.clone());
+            let relevant_files = cache.relevant_files(&ticket);
+            let referenced_assets = cache.referenced_assets(&ticket);
+            // What `context_exclude` kept out, worked out by asking again without it
+            let excluded: Vec<String> = if ticket.spec.context_exclude.is_empty() {
+                Vec::new()
+            } else {
+                let mut unexcluded = ticket.clone();
+                unexcluded.spec.context_exclude.clear();
+                discover_context(&unexcluded, &root, &context_profile, &config.context.include_paths)
+                    .into_iter()
+                    .filter(|f| !relevant_files.contains(f))
+                    .collect()
+            };
+
+            let files: Vec<serde_json::Value> = relevant_files.iter().map(|file_path| {
+                let p = root.join(file_path);
+                let pinned = ticket.spec.context_pin.contains(file_path);
+                if !p.exists() {
+                    return serde_json::json!({ "path": file_path, "found": false, "pinned": pinned });
+                }
+                match std::fs::read_to_string(&p) {
+                    Ok(content) => serde_json::json!({ "path": file_path, "found": true, "pinned": pinned, "content": content }),
+                    Err(e) => serde_json::json!({ "path": file_path, "found": true, "pinned": pinned, "error": e.to_string() }),
+                }
+            }).collect();
+
+            let overview = if context.overview {
+                Some(workspace_overview(&root, context.overview_depth)?)
+            } else {
+                None
+            };
+            let depth = context.git_history_depth;
+            let relevant_docs = if context.docs { relevant_sections(&ticket, &root) } else { Vec::new() };
+            let schema_slices = if context.schemas { relevant_slices(&ticket, &root) } else { Vec::new() };
+            let comments = CommentStore::new(&root).list(&ticket.meta.id)?;
+
+            if json {
+                return output::emit(&serde_json::json!({ "ok": true, "context": {
+                    "overview": overview,
+                    "id": ticket.meta.id,
+                    "title": ticket.meta.title,
+                    "description": ticket.spec.description,
+                    "constraints": ticket.spec.constraints,
+                    "allowed_paths": ticket.spec.allowed_paths,
+                    "comments": comments,
+                    "assets": ticket.spec.assets.iter()
+                        .map(|name| serde_json::json!({ "path": format!("assets/{}", name), "found": root.join("assets").join(name).is_file() }))
+                        .collect::<Vec<_>>(),
+                    "images": image_assets(&ticket, &root),
+                    "auto_context": auto_context,
+                    "context_profile": profile.map(|(name, _)| name),
+                    "files": files,
+                    "referenced_assets": referenced_assets,
+                    "excluded": excluded,
+                    "docs": relevant_docs.iter()
+                        .map(|s| serde_json::json!({ "path": s.path, "heading": s.heading, "body": s.body }))
+                        .collect::<Vec<_>>(),
+                    "schemas": schema_slices.iter()
+                        .map(|s| serde_json::json!({ "path": s.path, "language": s.language, "content": s.content }))
+                        .collect::<Vec<_>>(),
+                    "history": relevant_files.iter()
+                        .filter(|_| depth > 0)
+                        .map(|path| serde_json::json!({ "path": path, "commits": file_history(&root, path, depth).unwrap_or_default() }))
+                        .collect::<Vec<_>>(),
+                } }));
+            }
+
+            if let Some(overview) = &overview {
+                println!("{}", overview);
+            }
             println!("# TASK: {} {}", ticket.meta.id, ticket.meta.title);
             println!("## Description");
             println!("{}", ticket.spec.description);
@@ -168,75 +1255,450 @@ async fn main() -> Result<()> {
             for c in &ticket.spec.constraints {
                 println!("- {}", c);
             }
+            if !ticket.spec.allowed_paths.is_empty() {
+                println!("\n## Allowed Paths");
+                for glob in &ticket.spec.allowed_paths {
+                    println!("- {}", glob);
+                }
+            }
+            let discussion = render_discussion(&comments);
+            if !discussion.is_empty() {
+                print!("\n{}", discussion);
+            }
+            let assets = render_assets(&ticket, &root, config.context.images);
+            if !assets.is_empty() {
+                print!("\n{}", assets);
+            }
 
-            let mut relevant_files = ticket.spec.relevant_files.clone();
+            for file in &files {
+                let path = file["path"].as_str().unwrap_or_default();
+                if file["found"] != true {
+                    println!("\n## Context File: {} (NOT FOUND)", path);
+                    continue;
+                }
+                if file["pinned"] == true {
+                    println!("\n## Context File: {} (pinned)", path);
+                } else {
+                    println!("\n## Context File: {}", path);
+                }
+                match file["content"].as_str() {
+                    Some(content) => println!("```\n{}\n```", content),
+                    None => println!("Error reading file: {}", file["error"].as_str().unwrap_or_default()),
+                }
+            }
 
-            // Auto-Context
-            if relevant_files.is_empty() {
-                // If implicit or explicit auto_context is desired.
-                // PR says: "When director-plan context <T-ID> is called, if relevant_files is empty in the TOML, the engine now dynamically populates context."
-                println!("\n>> Auto-Context Discovery Triggered...");
-                relevant_files = discover_context(&ticket, &root);
+            let referenced = render_referenced(&root, &referenced_assets);
+            if !referenced.is_empty() {
+                print!("\n{}", referenced);
             }
 
-            for file_path in relevant_files {
-                let p = root.join(&file_path);
-                if p.exists() {
-                    println!("\n## Context File: {}", file_path);
-                    match std::fs::read_to_string(&p) {
-                        Ok(content) => println!("```\n{}\n```", content),
-                        Err(e) => println!("Error reading file: {}", e),
-                    }
-                } else {
-                    println!("\n## Context File: {} (NOT FOUND)", file_path);
+            if !excluded.is_empty() {
+                println!("\n## Excluded from Context");
+                for path in &excluded {
+                    println!("- {}", path);
                 }
             }
-        }
-        Commands::Verify { id } => {
-            // Git safety check
-            let git_status = Command::new("git")
-                .arg("status")
-                .arg("--porcelain")
-                .output()
-                .context("Failed to run git status")?;
 
-            if !git_status.stdout.is_empty() {
-                anyhow::bail!("Git tree is not clean. Commit or stash changes before verifying.");
+            if !relevant_docs.is_empty() {
+                println!("\n## Project Documentation");
+                for section in &relevant_docs {
+                    println!("\n### {} › {}\n{}", section.path, section.heading, section.body);
+                }
             }
 
-            let ticket = plan.get_ticket(&id)?;
-            println!("Running verification for {}: {}", id, ticket.verification.command);
+            if !schema_slices.is_empty() {
+                println!("\n## API Schema");
+                for slice in &schema_slices {
+                    println!("\n### {}\n```{}\n{}\n```", slice.path, slice.language, slice.content);
+                }
+            }
 
-            // Basic splitting by whitespace - improving this would require shell-parsing logic
-            let parts: Vec<&str> = ticket.verification.command.split_whitespace().collect();
-            if parts.is_empty() {
+            if depth > 0 {
+                println!("\n{}", render_history(&root, &relevant_files, depth));
+            }
+        }
+        Commands::Verify { id, report, report_path } => {
+            // Everything that can fail is loaded before the stash, so nothing returns early with
+            // the user's changes still stashed
+            let ticket = plan.get_ticket(&id)?;
+            let config = PlanConfig::load(&root)?;
+            if !ticket.verification.steps.is_empty() {
+                let stashed = stash_for_verify(&root, confirm, &id)?;
+                director_plan::progress!("Running {} verification steps for {}", ticket.verification.command_steps().len(), id);
+                let result = batch::verify_ticket(&root, &config, &ticket);
+                if stashed {
+                    restore_stash(&root);
+                }
+                return report_verification_steps(&result, report.zip(report_path), stashed, json);
+            }
+            if ticket.verification.command.trim().is_empty() {
                 anyhow::bail!("Verification command is empty");
             }
+            let env = ResolvedEnv::resolve(&root, &ticket.verification.env)?;
 
-            let status = Command::new(parts[0])
-                .args(&parts[1..])
-                .status()
-                .context("Failed to execute verification command")?;
+            let stashed = stash_for_verify(&root, confirm, &id)?;
+            director_plan::progress!("Running verification for {}: {}", id, ticket.verification.command);
+            let started = std::time::Instant::now();
+            let status = policy::build_command(&config.policy, &ticket.verification.command, &root)
+                .and_then(|mut cmd| cmd.envs(env.vars()).stdout(output::child_stdout()).status().context("Failed to execute verification command"));
+            let elapsed = started.elapsed();
 
-            if status.success() {
+            if stashed {
+                restore_stash(&root);
+            }
+
+            if let (Some(format), Some(path)) = (report, &report_path) {
+                let result = match &status {
+                    Ok(s) if s.success() => Ok(()),
+                    Ok(s) => Err(anyhow::anyhow!("`{}` exited with {}", ticket.verification.command, s)),
+                    Err(e) => Err(anyhow::anyhow!("{:#}", e)),
+                };
+                let case = TestCase::from_result("command", elapsed, &result);
+                test_report::write(format.into(), path, &id, &[case])?;
+            }
+
+            let status = status?;
+            events::emit(&root, PlanEvent::VerificationFinished { id: id.clone(), run_id: None, passed: status.success() });
+            if json {
+                output::emit(&serde_json::json!({
+                    "ok": status.success(),
+                    "id": id,
+                    "command": ticket.verification.command,
+                    "passed": status.success(),
+                    "exit_code": status.code(),
+                    "stashed": stashed,
+                    "report": report_path,
+                }))?;
+                if !status.success() {
+                    std::process::exit(error::exit::VERIFICATION_FAILED);
+                }
+            } else if status.success() {
                 println!("{}", "PASS".green().bold());
             } else {
                 println!("{}", "FAIL".red().bold());
-                std::process::exit(1);
+                std::process::exit(error::exit::VERIFICATION_FAILED);
+            }
+        }
+        Commands::Plan { goal, agent } => {
+            let config = PlanConfig::load(&root)?;
+            let overview = workspace_overview(&root, config.context.overview_depth)?;
+            director_plan::progress!("Asking the agent for a plan");
+            let reply = ShellAgent::new(&agent, &root).send(&planner::plan_prompt(&goal, &overview), &CancelToken::new())?;
+            let tickets = planner::order(planner::parse_json_array(&reply)?)?;
+            if tickets.is_empty() {
+                anyhow::bail!("The agent returned an empty plan");
+            }
+
+            director_plan::progress!("Plan for \"{}\" ({} tickets, in order):", goal, tickets.len());
+            for ticket in &tickets {
+                let after = if ticket.depends_on.is_empty() { String::new() } else { format!(" (after {})", ticket.depends_on.join(", ")) };
+                director_plan::progress!("  [{}] {}{}", ticket.key, ticket.title, after);
+            }
+            if !confirm.ask(&format!("Create {} tickets?", tickets.len())) {
+                anyhow::bail!("Plan discarded; pass --yes to create the tickets without asking");
+            }
+
+            let created = planner::create_tickets(&root, &goal, &tickets)?;
+            if json {
+                let tickets: Vec<serde_json::Value> = created.iter()
+                    .map(|(key, id)| serde_json::json!({ "key": key, "id": id }))
+                    .collect();
+                output::emit(&serde_json::json!({ "ok": true, "tickets": tickets }))?;
+            } else {
+                for ((_, id), ticket) in created.iter().zip(&tickets) {
+                    println!("{} {}", id.green(), ticket.title);
+                }
             }
         }
-        Commands::Update { id, status, owner, comment } => {
-             update_ticket(&plan, &id, status.map(Status::from), owner, comment)?;
+        Commands::Import { from, file } => {
+            let content = std::fs::read_to_string(&file).with_context(|| format!("Failed to read {:?}", file))?;
+            let issues = import::parse(from.into(), &content)?;
+            if issues.is_empty() {
+                anyhow::bail!("{:?} has no issues to import", file);
+            }
+            if !confirm.ask(&format!("Import {} issues from {:?}?", issues.len(), file)) {
+                anyhow::bail!("Import cancelled; pass --yes to import without asking");
+            }
+
+            let summary = import::create_tickets(&root, from.into(), &issues)?;
+            if json {
+                let created: Vec<serde_json::Value> = summary.created.iter()
+                    .map(|(external_ref, id)| serde_json::json!({ "external_ref": external_ref, "id": id }))
+                    .collect();
+                output::emit(&serde_json::json!({ "ok": true, "created": created, "skipped": summary.skipped }))?;
+            } else {
+                for (external_ref, id) in &summary.created {
+                    let title = issues.iter().find(|i| &i.external_ref == external_ref).map(|i| i.title.as_str()).unwrap_or_default();
+                    println!("{} {} ({})", id.green(), title, external_ref);
+                }
+                if !summary.skipped.is_empty() {
+                    println!("Skipped {} already imported: {}", summary.skipped.len(), summary.skipped.join(", "));
+                }
+            }
         }
-        Commands::Execute { id, agent } => {
+        Commands::Split { id, agent, count } => {
             let ticket = plan.get_ticket(&id)?;
-            let mut loop_runner = ExecutionLoop::new(&root, agent, ticket);
-            loop_runner.run()?;
+            let children = match agent {
+                Some(agent) => {
+                    director_plan::progress!("Asking the agent to split {}", id);
+                    let prompt = split::split_prompt(&ticket, count);
+                    let reply = ShellAgent::new(&agent, &root).send(&prompt, &CancelToken::new())?;
+                    split::parse_agent_reply(&reply)?
+                }
+                None => split::split_description(&ticket.spec.description),
+            };
+            if children.len() < 2 {
+                anyhow::bail!("{} doesn't break into more than one piece; pass --agent to have an agent split it", id);
+            }
+
+            director_plan::progress!("Split {} into {} tickets:", id, children.len());
+            for child in &children {
+                director_plan::progress!("  - {}", child.title);
+            }
+            if !confirm.ask(&format!("Create {} child tickets?", children.len())) {
+                anyhow::bail!("Split cancelled; pass --yes to create the tickets without asking");
+            }
+            let created = split::create_children(&root, &id, &children)?;
+            if json {
+                output::emit(&serde_json::json!({
+                    "ok": true,
+                    "id": id,
+                    "children": created,
+                }))?;
+            } else {
+                println!("{}", format!("Created {}. {} is blocked until they are done.", created.join(", "), id).green());
+            }
+        }
+        Commands::VerifyAll { status, jobs, report, report_path } => {
+            let status = Status::from(status);
+            let tickets = plan.list_tickets(Some(status.clone()))?;
+            let config = PlanConfig::load(&root)?;
+
+            let stashed = if tickets.is_empty() { false } else { stash_for_verify(&root, confirm, &format!("{:?} tickets", status))? };
+            let results = batch::verify_all(&root, &config, &tickets, jobs);
+            if stashed {
+                restore_stash(&root);
+            }
+            let results = results?;
+            let failed = results.iter().filter(|r| !r.passed).count();
+
+            if let (Some(format), Some(path)) = (report, &report_path) {
+                let cases: Vec<TestCase> = results.iter().map(|r| r.test_case()).collect();
+                test_report::write(format.into(), path, &format!("verify-all ({:?})", status), &cases)?;
+            }
+
+            if json {
+                output::emit(&serde_json::json!({
+                    "ok": failed == 0,
+                    "status": status,
+                    "total": results.len(),
+                    "passed": results.len() - failed,
+                    "failed": failed,
+                    "results": results,
+                    "report": report_path,
+                }))?;
+            } else {
+                for r in &results {
+                    let verdict = if r.passed { "PASS".green().bold() } else { "FAIL".red().bold() };
+                    println!("{} {} {} ({} ms)", verdict, r.id, r.title, r.duration_ms);
+                    if !r.passed {
+                        if let Some(error) = &r.error {
+                            println!("    {}", error);
+                        }
+                        // The end of the output is usually where the failure is
+                        let lines: Vec<&str> = r.output.lines().collect();
+                        for line in &lines[lines.len().saturating_sub(20)..] {
+                            println!("    {}", line);
+                        }
+                    }
+                }
+                println!("\n{} passed, {} failed, {} total", results.len() - failed, failed, results.len());
+            }
+            if failed > 0 {
+                std::process::exit(error::exit::VERIFICATION_FAILED);
+            }
+        }
+        Commands::Update { id, status, owner, comment, due_date } => {
+             update_ticket(&plan, &root, &id, status.map(Status::from), owner, comment, due_date)?;
+             if json {
+                 let ticket = plan.get_ticket(&id)?;
+                 output::emit(&serde_json::json!({
+                     "ok": true,
+                     "id": ticket.meta.id,
+                     "status": ticket.meta.status,
+                     "owner": ticket.meta.owner,
+                     "due_date": ticket.meta.due_date.map(|d| d.to_string()),
+                 }))?;
+             } else {
+                 println!("Ticket {} updated.", id);
+             }
+        }
+        Commands::Comment { id, message, reply_to, author } => {
+            plan.get_ticket(&id)?;
+            let store = CommentStore::new(&root);
+            if let Some(message) = message {
+                let author = author.unwrap_or_else(|| git_user_name(&root));
+                let comment = store.add(&id, &author, &message, reply_to)?;
+                if json {
+                    return output::emit(&serde_json::json!({ "ok": true, "id": id, "comment": comment }));
+                }
+                println!("Comment {} added to {}.", comment.id, id);
+            } else {
+                let comments = store.list(&id)?;
+                if json {
+                    return output::emit(&serde_json::json!({ "ok": true, "id": id, "comments": comments }));
+                }
+                if comments.is_empty() {
+                    println!("No comments on {}.", id);
+                }
+                print!("{}", render_discussion(&comments));
+            }
+        }
+        Commands::Block { id, reason } => {
+            TicketStore::new(&root).block(&id, &reason)?;
+            if json {
+                return output::emit(&serde_json::json!({ "ok": true, "id": id, "status": Status::Blocked, "blocked_reason": reason }));
+            }
+            println!("Ticket {} blocked: {}", id, reason);
+        }
+        Commands::Unblock { id, reason } => {
+            let status = TicketStore::new(&root).unblock(&id, reason.as_deref())?;
+            if json {
+                return output::emit(&serde_json::json!({ "ok": true, "id": id, "status": status }));
+            }
+            println!("Ticket {} unblocked; back to {}.", id, status.to_string());
+        }
+        Commands::Execute { id, agent, session, patch, report, report_path, resume, force, autostash } => {
+            let ticket = plan.get_ticket(&id)?;
+            // Held until the run is over, so a second `execute` or a worker can't start on it
+            let lock = LockStore::new(&root).acquire_with(&id, force).map_err(|e| match e.downcast_ref::<AlreadyRunning>() {
+                Some(running) => DirectorError::AlreadyRunning(format!("{}; pass --force to run it anyway", running)).into(),
+                None => e,
+            })?;
+            // Ctrl-C kills the agent/verification process and reverts the tree
+            // instead of leaving a half-applied change behind; `--resume` brings it back.
+            let cancel = CancelToken::new();
+            {
+                let cancel = cancel.clone();
+                tokio::spawn(async move {
+                    wait_for_signal().await;
+                    cancel.cancel();
+                });
+            }
+            // `director-plan cancel` does the same from another terminal or the API
+            lock.cancel_on_request(cancel.clone());
+            let mut loop_runner = ExecutionLoop::new(&root, agent.unwrap_or_default(), ticket)
+                .with_cancel(cancel)
+                .with_confirm(confirm);
+            if session {
+                loop_runner = loop_runner.with_session();
+            }
+            if patch {
+                loop_runner = loop_runner.with_patch_mode();
+            }
+            if resume {
+                loop_runner = loop_runner.with_resume();
+            }
+            if autostash {
+                loop_runner = loop_runner.with_autostash();
+            }
+            if let (Some(format), Some(path)) = (report, report_path) {
+                loop_runner = loop_runner.with_report(format.into(), path);
+            }
+            let result = loop_runner.run_with_handshake().map_err(|e| match LockStore::new(&root).take_cancel_request(&id) {
+                Some(request) if e.is::<Interrupted>() => e.context(match request.reason {
+                    Some(reason) => format!("Run cancelled: {}", reason),
+                    None => "Run cancelled".to_string(),
+                }),
+                _ => e,
+            })?;
+            let mut follow_ups = Vec::new();
+            if let Some(agent_result) = result.result.as_ref().filter(|_| result.success) {
+                let config = PlanConfig::load(&root)?;
+                follow_ups = agent_result::create_follow_ups(&root, &plan.get_ticket(&id)?, agent_result, &config.follow_ups)?;
+                if !follow_ups.is_empty() {
+                    director_plan::progress!(">> Created follow-up tickets: {}", follow_ups.join(", "));
+                }
+            }
+            if json {
+                output::emit(&serde_json::json!({
+                    "ok": result.success,
+                    "id": id,
+                    "success": result.success,
+                    "confidence": result.confidence,
+                    "errors": result.errors,
+                    "commit": result.commit,
+                    "run_id": result.run_id,
+                    "agent": result.agent,
+                    "result": result.result,
+                    "follow_ups": follow_ups,
+                }))?;
+                if !result.success {
+                    std::process::exit(execution_failure(&result).exit_code());
+                }
+            } else if !result.success {
+                return Err(execution_failure(&result).into());
+            }
+        }
+        Commands::Cancel { id, reason, server } => {
+            let cancellation = match server {
+                Some(server) => Client::new(&server).cancel_run(&id, reason.as_deref()).await
+                    .with_context(|| format!("Failed to cancel {} on {}", id, server))?,
+                None => cancel::cancel(&root, &id, reason.as_deref(), None).map_err(|e| {
+                    let lease = LeaseTable::load(&root).ok()
+                        .and_then(|leases| leases.get(&id).cloned())
+                        .filter(|lease| !lease.is_expired(chrono::Utc::now()));
+                    match lease {
+                        Some(lease) if e.is::<NotRunning>() => anyhow::anyhow!(
+                            "{} here; worker {} holds its lease, so cancel it through the server with --server", e, lease.worker_id,
+                        ),
+                        _ => e,
+                    }
+                })?,
+            };
+            if json {
+                return output::emit(&serde_json::json!({ "ok": true, "cancellation": cancellation }));
+            }
+            if let (Some(pid), Some(host)) = (cancellation.pid, &cancellation.host) {
+                println!("Asked the run of {} (pid {} on {}) to stop; its changes are reverted.", id, pid, host);
+            }
+            if let Some(worker) = &cancellation.worker_id {
+                println!("Revoked worker {}'s lease on {}; it stops at its next heartbeat.", worker, cancellation.ticket_id);
+            }
+            if cancellation.status == Status::Blocked {
+                println!("Ticket {} is blocked; `director-plan unblock {}` returns it to todo.", cancellation.ticket_id, cancellation.ticket_id);
+            }
+        }
+        Commands::Golden { subcmd } => {
+            match subcmd {
+                GoldenCommands::Accept { id, snapshot } => {
+                    let promotions = golden::accept(&root, &id, snapshot.as_deref())?;
+                    if json {
+                        return output::emit(&serde_json::json!({ "ok": true, "id": id, "promotions": promotions }));
+                    }
+                    for promotion in &promotions {
+                        println!("{} -> {}", promotion.actual, promotion.golden);
+                    }
+                    println!("{}", format!("Accepted {} golden image(s) for {}. Commit them with the ticket.", promotions.len(), id).green());
+                }
+            }
         }
         Commands::Docs { subcmd } => {
             match subcmd {
                 DocsCommands::Search { query } => {
-                    search_docs(&root, &query)?;
+                    let matches = search_docs(&root, &query)?;
+                    if json {
+                        return output::emit(&serde_json::json!({ "ok": true, "query": query, "matches": matches }));
+                    }
+                    if !root.join("docs").exists() {
+                        println!("No docs directory found.");
+                    }
+                    for m in &matches {
+                        println!("Found in: {:?}", m.path);
+                        for line in &m.lines {
+                            println!("  {}", line);
+                        }
+                    }
                 }
             }
         }
@@ -245,56 +1707,191 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-fn update_ticket(plan: &DirectorPlan, id: &str, status: Option<Status>, owner: Option<String>, comment: Option<String>) -> Result<()> {
-    let ticket_path = plan.get_tickets_dir().join(format!("{}.toml", id));
-    if !ticket_path.exists() {
-         anyhow::bail!("Ticket {} not found", id);
+fn update_ticket(plan: &DirectorPlan, root: &Path, id: &str, status: Option<Status>, owner: Option<String>, comment: Option<String>, due_date: Option<String>) -> Result<()> {
+    let store = TicketStore::new(root);
+    if !store.exists(id) {
+         return Err(DirectorError::TicketNotFound(id.to_string()).into());
     }
 
-    let content = std::fs::read_to_string(&ticket_path)?;
-    let mut doc = content.parse::<toml_edit::DocumentMut>()?;
+    let mut doc = store.load_document(id)?;
 
     if let Some(s) = status {
-        doc["meta"]["status"] = toml_edit::value(s.to_string());
+        blocking::check_transition(&plan.list_tickets(None)?, id, &s)?;
+        director_plan::store::set_status_in_document(&mut doc, &s);
     }
 
     if let Some(o) = owner {
         doc["meta"]["owner"] = toml_edit::value(o);
     }
 
+    if let Some(d) = due_date {
+        let date: toml_datetime::Datetime = d.parse()
+            .with_context(|| format!("Invalid due date '{}', expected YYYY-MM-DD", d))?;
+        doc["meta"]["due_date"] = toml_edit::value(date);
+    }
+
     if let Some(c) = comment {
-        let entry = format!("[{}] {}", chrono::Utc::now().to_rfc3339(), c);
+        director_plan::append_history_entry(&mut doc, &c);
+    }
 
-        // Ensure history table exists
-        if doc.get("history").is_none() {
-             doc["history"] = toml_edit::Item::Table(toml_edit::Table::new());
-        }
+    store.save_document(id, &doc)?;
 
-        let history = doc["history"].as_table_mut().unwrap();
+    Ok(())
+}
 
-        // Ensure log array exists
-        if history.get("log").is_none() {
-            history.insert("log", toml_edit::Item::Value(toml_edit::Value::Array(toml_edit::Array::new())));
-        }
+/// Who `comment` credits: git's `user.name`, else `$USER`.
+fn git_user_name(root: &Path) -> String {
+    Command::new("git")
+        .current_dir(root)
+        .args(["config", "user.name"])
+        .output()
+        .ok()
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .filter(|name| !name.is_empty())
+        .or_else(|| std::env::var("USER").ok())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Git safety check for `verify`: a dirty tree is stashed (if the user agrees) so the command
+/// sees only committed changes. Returns whether anything was stashed.
+fn stash_for_verify(root: &Path, confirm: Confirm, label: &str) -> Result<bool> {
+    let git_status = Command::new("git")
+        .current_dir(root)
+        .arg("status")
+        .arg("--porcelain")
+        .output()
+        .context("Failed to run git status")?;
+    if git_status.stdout.is_empty() {
+        return Ok(false);
+    }
+
+    if !confirm.ask("Git tree is not clean. Stash changes, verify, and restore them afterwards?") {
+        return Err(DirectorError::DirtyWorkspace("Git tree is not clean. Commit or stash changes before verifying.".to_string()).into());
+    }
+    let status = Command::new("git")
+        .current_dir(root)
+        .args(["stash", "push", "-q", "--include-untracked", "-m", &format!("director-plan: before verifying {}", label)])
+        .status()
+        .context("Failed to run git stash")?;
+    if !status.success() {
+        anyhow::bail!("git stash failed");
+    }
+    Ok(true)
+}
 
-        if let Some(log) = history.get_mut("log") {
-            if let Some(arr) = log.as_array_mut() {
-                 arr.push(entry);
+/// `verify` for a ticket with `[[verification.steps]]`: each step's result, and the output of
+/// the ones that failed.
+fn report_verification_steps(result: &TicketVerification, report: Option<(ReportArg, PathBuf)>, stashed: bool, json: bool) -> Result<()> {
+    if result.steps.is_empty() && let Some(error) = &result.error {
+        anyhow::bail!("{}", error);
+    }
+    if let Some((format, path)) = &report {
+        let cases: Vec<TestCase> = result.steps.iter().map(|step| step.test_case()).collect();
+        test_report::write((*format).into(), path, &result.id, &cases)?;
+    }
+
+    if json {
+        output::emit(&serde_json::json!({
+            "ok": result.passed,
+            "id": result.id,
+            "passed": result.passed,
+            "exit_code": result.exit_code,
+            "steps": result.steps,
+            "stashed": stashed,
+            "report": report.map(|(_, path)| path),
+        }))?;
+    } else {
+        for step in &result.steps {
+            let seconds = step.duration_ms as f64 / 1000.0;
+            match (&step.skipped, step.failure()) {
+                (Some(reason), _) => println!("{} {} ({})", "SKIP".yellow(), step.name, reason),
+                (None, Some(failure)) => {
+                    println!("{} {} ({:.1}s)", "FAIL".red(), step.name, seconds);
+                    for line in failure.lines() {
+                        println!("    {}", line);
+                    }
+                }
+                (None, None) => println!("{} {} ({:.1}s)", "PASS".green(), step.name, seconds),
             }
         }
+        println!("{}", if result.passed { "PASS".green().bold() } else { "FAIL".red().bold() });
     }
+    if !result.passed {
+        std::process::exit(error::exit::VERIFICATION_FAILED);
+    }
+    Ok(())
+}
 
-    std::fs::write(ticket_path, doc.to_string())?;
-    println!("Ticket {} updated.", id);
+/// Pops what [`stash_for_verify`] stashed. A pop that fails is reported, not returned: the
+/// verification result still stands and the changes are safe in the stash.
+fn restore_stash(root: &Path) {
+    let popped = Command::new("git").current_dir(root).args(["stash", "pop", "-q"]).status();
+    if !matches!(popped, Ok(s) if s.success()) {
+        eprintln!("{}", "Could not restore stashed changes; they are still in `git stash list`.".red());
+    }
+}
 
-    Ok(())
+/// `experiment run`/`show`: a line per variant, then how each ticket went under each.
+fn print_experiment(experiment: &Experiment) {
+    println!(
+        "{} ({} ticket(s) from {})",
+        experiment.id.bold(), experiment.tickets.len(), &experiment.base_commit[..experiment.base_commit.len().min(12)],
+    );
+    if experiment.interrupted {
+        println!("{}", "Interrupted before every trial had run.".yellow());
+    }
+    println!("\n{:<24}  {:>12}  {:>8}  {:>9}  {:>10}  {:>7}  {:>9}", "variant", "passed", "attempts", "duration", "agent time", "prompts", "chars");
+    for s in experiment.summary() {
+        println!(
+            "{:<24}  {:>12}  {:>8.1}  {:>8.1}s  {:>9.1}s  {:>7}  {:>9}",
+            s.variant, format!("{}/{} ({:.0}%)", s.succeeded, s.trials, s.success_rate * 100.0), s.mean_attempts,
+            s.mean_duration_ms as f64 / 1000.0, s.agent_ms as f64 / 1000.0, s.prompts, s.chars,
+        );
+    }
+    println!();
+    for ticket in &experiment.tickets {
+        let outcomes: Vec<String> = experiment.variants.iter()
+            .map(|variant| {
+                let outcome = match experiment.trial(ticket, variant) {
+                    Some(t) if t.success => format!("passed in {}", t.attempts).green(),
+                    Some(t) if t.error.is_some() => "errored".red(),
+                    Some(t) => format!("failed after {}", t.attempts).red(),
+                    None => "not run".dimmed(),
+                };
+                format!("{} {}", variant, outcome)
+            })
+            .collect();
+        println!("{}  {}", ticket.bold(), outcomes.join(", "));
+    }
+    for trial in &experiment.trials {
+        if let Some(error) = &trial.error {
+            println!("  {}", format!("{} under {}: {}", trial.ticket_id, trial.variant, error).dimmed());
+        }
+    }
 }
 
-fn search_docs(root: &PathBuf, query: &str) -> Result<()> {
+/// Why a run that finished without success failed, from its last attempt.
+fn execution_failure(result: &ExecutionResult) -> DirectorError {
+    let last = result.errors.last().cloned().unwrap_or_else(|| "no attempts were made".to_string());
+    if result.agent_failed {
+        DirectorError::AgentFailed(last)
+    } else {
+        DirectorError::VerificationFailed(last)
+    }
+}
+
+#[derive(serde::Serialize)]
+struct DocMatch {
+    path: PathBuf,
+    /// Matching lines, trimmed
+    lines: Vec<String>,
+}
+
+fn search_docs(root: &PathBuf, query: &str) -> Result<Vec<DocMatch>> {
     let docs_dir = root.join("docs");
+    let mut matches = Vec::new();
     if !docs_dir.exists() {
-        println!("No docs directory found.");
-        return Ok(());
+        return Ok(matches);
     }
 
     let query_lower = query.to_lowercase();
@@ -306,16 +1903,16 @@ fn search_docs(root: &PathBuf, query: &str) -> Result<()> {
         if entry.file_type().is_file() {
             if let Ok(content) = std::fs::read_to_string(entry.path()) {
                 if content.to_lowercase().contains(&query_lower) {
-                    println!("Found in: {:?}", entry.path());
-                    // print snippets?
-                    for line in content.lines() {
-                        if line.to_lowercase().contains(&query_lower) {
-                             println!("  {}", line.trim());
-                        }
-                    }
+                    matches.push(DocMatch {
+                        path: entry.path().to_path_buf(),
+                        lines: content.lines()
+                            .filter(|line| line.to_lowercase().contains(&query_lower))
+                            .map(|line| line.trim().to_string())
+                            .collect(),
+                    });
                 }
             }
         }
     }
-    Ok(())
+    Ok(matches)
 }
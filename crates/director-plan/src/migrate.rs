@@ -0,0 +1,191 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+use toml_edit::DocumentMut;
+use crate::store::TicketStore;
+use crate::ticket_format::is_ticket_file;
+
+/// The ticket schema this build writes, stored as `meta.schema_version`. Tickets without one
+/// are version 0.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// One step of the schema, upgrading a ticket from `to - 1` to `to`. A migration edits the
+/// document in place and must leave an already-upgraded ticket alone.
+struct Migration {
+    to: u32,
+    summary: &'static str,
+    apply: fn(&mut DocumentMut) -> Result<()>,
+}
+
+/// Every schema change, oldest first. A change to the ticket format adds an entry here and
+/// bumps [`SCHEMA_VERSION`].
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        to: 1,
+        summary: "Quoted dates become TOML dates, and status, priority and type are snake_case",
+        apply: normalize_values,
+    },
+];
+
+/// Sets `meta.schema_version` on a ticket being created.
+pub fn stamp(doc: &mut DocumentMut) {
+    doc["meta"]["schema_version"] = toml_edit::value(SCHEMA_VERSION as i64);
+}
+
+pub fn schema_version(doc: &DocumentMut) -> u32 {
+    doc.get("meta")
+        .and_then(|m| m.get("schema_version"))
+        .and_then(|v| v.as_integer())
+        .map_or(0, |v| v as u32)
+}
+
+/// Brings `doc` up to [`SCHEMA_VERSION`]. Returns the summaries of the migrations applied.
+pub fn migrate_document(doc: &mut DocumentMut) -> Result<Vec<&'static str>> {
+    let from = schema_version(doc);
+    if from > SCHEMA_VERSION {
+        bail!("Schema version {} is newer than this build understands ({})", from, SCHEMA_VERSION);
+    }
+    let mut applied = Vec::new();
+    for migration in MIGRATIONS.iter().filter(|m| m.to > from) {
+        (migration.apply)(doc).with_context(|| format!("Migration to schema version {} failed", migration.to))?;
+        applied.push(migration.summary);
+    }
+    if from < SCHEMA_VERSION {
+        stamp(doc);
+    }
+    Ok(applied)
+}
+
+/// A ticket `director-plan migrate` upgraded (or would upgrade, in a dry run).
+#[derive(Debug, Serialize)]
+pub struct Migrated {
+    pub id: String,
+    pub from: u32,
+    pub to: u32,
+    pub applied: Vec<String>,
+    /// Copy of the file as it was, under `.director/backups/`; none in a dry run
+    pub backup: Option<PathBuf>,
+}
+
+/// Upgrades every ticket in `plan/tickets` that is behind [`SCHEMA_VERSION`], copying each
+/// original to `.director/backups/migrate-<time>/` first. Nothing is written if any ticket
+/// can't be migrated.
+pub fn migrate(workspace_root: &Path, dry_run: bool) -> Result<Vec<Migrated>> {
+    let store = TicketStore::new(workspace_root);
+    if !store.tickets_dir().exists() {
+        return Ok(Vec::new());
+    }
+    let mut paths: Vec<PathBuf> = fs::read_dir(store.tickets_dir())?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| is_ticket_file(p))
+        .collect();
+    paths.sort();
+
+    let mut pending = Vec::new();
+    for path in paths {
+        let id = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        let mut doc = store.load_document(&id)?;
+        let from = schema_version(&doc);
+        let applied = migrate_document(&mut doc).with_context(|| format!("Can't migrate {:?}", path))?;
+        if from < SCHEMA_VERSION {
+            pending.push((path, id, from, applied, doc));
+        }
+    }
+
+    let backup_dir = crate::state_dir(workspace_root)
+        .join("backups")
+        .join(format!("migrate-{}", chrono::Utc::now().format("%Y%m%dT%H%M%S")));
+    let mut migrated = Vec::new();
+    for (path, id, from, applied, doc) in pending {
+        let mut backup = None;
+        if !dry_run {
+            fs::create_dir_all(&backup_dir)?;
+            let copy = backup_dir.join(path.file_name().unwrap_or_default());
+            fs::copy(&path, &copy).with_context(|| format!("Failed to back up {:?}", path))?;
+            store.save_document(&id, &doc)?;
+            backup = Some(copy);
+        }
+        migrated.push(Migrated {
+            id,
+            from,
+            to: SCHEMA_VERSION,
+            applied: applied.into_iter().map(String::from).collect(),
+            backup,
+        });
+    }
+    Ok(migrated)
+}
+
+/// Version 1: hand-written and early tickets spelled dates as strings (`due_date = "2025-07-01"`)
+/// and enum values as labels (`status = "In Progress"`), which no longer parse.
+fn normalize_values(doc: &mut DocumentMut) -> Result<()> {
+    let Some(meta) = doc.get_mut("meta").and_then(|m| m.as_table_like_mut()) else {
+        return Ok(());
+    };
+    for key in ["created_at", "due_date"] {
+        if let Some(item) = meta.get_mut(key)
+            && let Some(text) = item.as_str()
+        {
+            let datetime: toml_datetime::Datetime = text.trim().parse()
+                .with_context(|| format!("meta.{} = \"{}\" isn't a date", key, text))?;
+            *item = toml_edit::value(datetime);
+        }
+    }
+    for key in ["status", "priority", "type"] {
+        if let Some(item) = meta.get_mut(key)
+            && let Some(text) = item.as_str()
+        {
+            let mut value = text.trim().to_lowercase().replace([' ', '-'], "_");
+            if value == "to_do" {
+                value = "todo".to_string();
+            }
+            if value != text {
+                *item = toml_edit::value(value);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Status;
+
+    #[test]
+    fn test_migrate() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let tickets = root.join("plan/tickets");
+        fs::create_dir_all(&tickets).unwrap();
+        let legacy = "[meta]\nid = \"T-001\"\ntitle = \"Shadows\" # keep me\nstatus = \"In Progress\"\npriority = \"High\"\ndue_date = \"2025-07-01\"\n\n[spec]\ndescription = \"\"\n\n[verification]\ncommand = \"\"\n";
+        fs::write(tickets.join("T-001.toml"), legacy).unwrap();
+        let mut current = legacy.replace("In Progress", "todo").replace("High", "high").replace("\"2025-07-01\"", "2025-07-01");
+        current = current.replace("[meta]\n", "[meta]\nschema_version = 1\n");
+        fs::write(tickets.join("T-002.toml"), current.replace("T-001", "T-002")).unwrap();
+
+        let dry = migrate(root, true).unwrap();
+        assert_eq!(dry.len(), 1);
+        assert!(dry[0].backup.is_none());
+        assert_eq!(fs::read_to_string(tickets.join("T-001.toml")).unwrap(), legacy);
+
+        let migrated = migrate(root, false).unwrap();
+        assert_eq!((migrated[0].id.as_str(), migrated[0].from, migrated[0].to), ("T-001", 0, SCHEMA_VERSION));
+        assert_eq!(fs::read_to_string(migrated[0].backup.as_ref().unwrap()).unwrap(), legacy);
+
+        let written = fs::read_to_string(tickets.join("T-001.toml")).unwrap();
+        assert!(written.contains("# keep me"));
+        let ticket = crate::DirectorPlan::new(root.to_path_buf()).get_ticket("T-001").unwrap();
+        assert_eq!(ticket.meta.status, Status::InProgress);
+        assert_eq!(ticket.meta.due_date.unwrap().to_string(), "2025-07-01");
+
+        assert!(migrate(root, false).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_newer_schema_is_refused() {
+        let mut doc: DocumentMut = format!("[meta]\nschema_version = {}\n", SCHEMA_VERSION + 1).parse().unwrap();
+        assert!(migrate_document(&mut doc).is_err());
+    }
+}
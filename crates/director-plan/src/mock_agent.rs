@@ -0,0 +1,165 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use anyhow::{Context, Result, anyhow, bail};
+use serde::Deserialize;
+use crate::agent_result::{AgentResult, RESULT_FENCE, RESULT_VERSION};
+use crate::execution_loop::SessionAgent;
+use crate::shutdown::CancelToken;
+
+/// What `director-plan mock-agent` and [`MockAgent`] do instead of asking a model. Each prompt
+/// is answered by the first turn whose `when` it contains, or that has none, so the same prompt
+/// always gets the same answer:
+///
+/// ```toml
+/// [[turn]]
+/// when = "Previous Errors"        # the retry after a failed attempt
+/// patch_file = "fix.diff"         # relative to this script
+///
+/// [[turn]]
+/// reply = "Wrote a first draft"
+/// files = { "src/answer.rs" = "pub fn answer() -> u32 { 41 }\n" }
+/// ```
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct MockScript {
+    #[serde(default, rename = "turn")]
+    pub turns: Vec<MockTurn>,
+    /// Where `patch_file` paths are relative to
+    #[serde(skip)]
+    pub dir: PathBuf,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct MockTurn {
+    /// Only answers prompts containing this
+    #[serde(default)]
+    pub when: Option<String>,
+    /// A unified diff, applied with `git apply`
+    #[serde(default)]
+    pub patch: Option<String>,
+    /// A file holding a unified diff, applied with `git apply`
+    #[serde(default)]
+    pub patch_file: Option<String>,
+    /// Files written with exactly this content, relative to the workspace
+    #[serde(default)]
+    pub files: BTreeMap<String, String>,
+    /// Sent back as is. Without it the reply is a result block listing the files changed,
+    /// with confidence 1.0.
+    #[serde(default)]
+    pub reply: Option<String>,
+}
+
+impl MockScript {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).with_context(|| format!("Failed to read mock script {:?}", path))?;
+        let mut script: Self = toml_edit::de::from_str(&content).with_context(|| format!("Invalid mock script {:?}", path))?;
+        script.dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Ok(script)
+    }
+
+    /// Makes the changes of the turn that answers `prompt` in `workspace_root` and returns its reply.
+    pub fn respond(&self, prompt: &str, workspace_root: &Path) -> Result<String> {
+        let Some(turn) = self.turns.iter().find(|t| t.when.as_ref().is_none_or(|when| prompt.contains(when.as_str()))) else {
+            bail!("No turn in the mock script answers this prompt");
+        };
+
+        let mut changed: Vec<String> = Vec::new();
+        let patch = match &turn.patch_file {
+            Some(file) => {
+                let path = self.dir.join(file);
+                Some(fs::read_to_string(&path).with_context(|| format!("Failed to read mock patch {:?}", path))?)
+            }
+            None => turn.patch.clone(),
+        };
+        if let Some(patch) = patch {
+            apply(&patch, workspace_root)?;
+            changed.extend(patch.lines().filter_map(|line| line.strip_prefix("+++ b/")).map(str::to_string));
+        }
+        for (path, content) in &turn.files {
+            let target = workspace_root.join(path);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent).with_context(|| format!("Failed to create {:?}", parent))?;
+            }
+            fs::write(&target, content).with_context(|| format!("Failed to write {:?}", target))?;
+            changed.push(path.clone());
+        }
+
+        if let Some(reply) = &turn.reply {
+            return Ok(reply.clone());
+        }
+        let result = AgentResult {
+            version: RESULT_VERSION,
+            confidence: 1.0,
+            summary: "Applied the mock script".to_string(),
+            files_changed: changed,
+            follow_up_tickets: Vec::new(),
+        };
+        Ok(format!("Applied the mock script.\n\n```{}\n{}\n```\n", RESULT_FENCE, serde_json::to_string(&result)?))
+    }
+}
+
+fn apply(patch: &str, workspace_root: &Path) -> Result<()> {
+    let mut child = Command::new("git")
+        .current_dir(workspace_root)
+        .args(["apply", "--whitespace=nowarn", "-"])
+        .stdin(Stdio::piped())
+        // Under `mock-agent`, stdout is the reply
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run git apply")?;
+    child.stdin.take().context("git apply has no stdin")?.write_all(patch.as_bytes())?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(anyhow!("The mock patch didn't apply: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(())
+}
+
+/// Answers from a [`MockScript`] in-process, for tests that drive `ExecutionLoop` without a
+/// model or a shell command.
+pub struct MockAgent {
+    script: MockScript,
+    workspace_root: PathBuf,
+    /// Every prompt received, in order
+    pub prompts: Vec<String>,
+}
+
+impl MockAgent {
+    pub fn new(script: MockScript, workspace_root: &Path) -> Self {
+        Self { script, workspace_root: workspace_root.to_path_buf(), prompts: Vec::new() }
+    }
+}
+
+impl SessionAgent for MockAgent {
+    fn send(&mut self, message: &str, _cancel: &CancelToken) -> Result<String> {
+        self.prompts.push(message.to_string());
+        self.script.respond(message, &self.workspace_root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_respond() {
+        let dir = tempfile::tempdir().unwrap();
+        let script: MockScript = toml_edit::de::from_str(
+            "[[turn]]\nwhen = \"Previous Errors\"\nreply = \"fixed\"\nfiles = { \"src/a.rs\" = \"2\" }\n\n[[turn]]\nfiles = { \"src/a.rs\" = \"1\" }\n",
+        ).unwrap();
+
+        let reply = script.respond("Implement it", dir.path()).unwrap();
+        assert_eq!(fs::read_to_string(dir.path().join("src/a.rs")).unwrap(), "1");
+        assert!(reply.contains("```director-plan-result\n{\"version\":1,\"confidence\":1.0,\"summary\":\"Applied the mock script\",\"files_changed\":[\"src/a.rs\"]"));
+
+        assert_eq!(script.respond("# Previous Errors (FIX THESE)", dir.path()).unwrap(), "fixed");
+        assert_eq!(fs::read_to_string(dir.path().join("src/a.rs")).unwrap(), "2");
+
+        assert!(MockScript::default().respond("anything", dir.path()).is_err());
+    }
+}
@@ -0,0 +1,38 @@
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use serde::Serialize;
+
+/// Set by `--output json`: stdout then carries only the command's JSON result, and progress
+/// messages (and child processes' output) go to stderr.
+static JSON: AtomicBool = AtomicBool::new(false);
+
+pub fn set_json(enabled: bool) {
+    JSON.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+/// stdout for a child process whose output the user should see.
+pub fn child_stdout() -> Stdio {
+    if is_json() { Stdio::from(std::io::stderr()) } else { Stdio::inherit() }
+}
+
+/// Writes a command's result to stdout as one line of JSON.
+pub fn emit<T: Serialize>(value: &T) -> anyhow::Result<()> {
+    println!("{}", serde_json::to_string(value)?);
+    Ok(())
+}
+
+/// `println!` for progress messages; goes to stderr under `--output json`.
+#[macro_export]
+macro_rules! progress {
+    ($($arg:tt)*) => {
+        if $crate::output::is_json() {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
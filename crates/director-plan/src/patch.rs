@@ -0,0 +1,111 @@
+use std::path::Path;
+use std::process::Command;
+use anyhow::{Result, anyhow};
+use crate::process::run_cancellable;
+use crate::shutdown::CancelToken;
+
+/// Appended to the prompt for agents that answer with a patch instead of editing files.
+pub const PATCH_INSTRUCTIONS: &str = "## Output Format\n\
+You cannot edit files directly. Respond with a unified diff (as produced by `git diff`) \
+against the current working tree, inside a single ```diff fenced block. \
+Paths must be relative to the repository root.\n";
+
+/// Collects every ```diff / ```patch fenced block in the agent's output, in order.
+pub fn extract_diff(output: &str) -> Option<String> {
+    let mut diff = String::new();
+    let mut in_block = false;
+
+    for line in output.lines() {
+        let trimmed = line.trim_start();
+        if !in_block {
+            if trimmed.starts_with("```diff") || trimmed.starts_with("```patch") {
+                in_block = true;
+            }
+            continue;
+        }
+        if trimmed.starts_with("```") {
+            in_block = false;
+            continue;
+        }
+        diff.push_str(line);
+        diff.push('\n');
+    }
+
+    if diff.trim().is_empty() { None } else { Some(diff) }
+}
+
+/// Paths a diff touches, from its `---`/`+++` headers (without the `a/`/`b/` prefixes).
+pub fn touched_paths(diff: &str) -> Vec<String> {
+    let mut paths = Vec::new();
+    for line in diff.lines() {
+        let rest = match line.strip_prefix("+++ ").or_else(|| line.strip_prefix("--- ")) {
+            Some(rest) => rest,
+            None => continue,
+        };
+        // `+++ b/src/lib.rs\t2024-01-01 ...` -> `src/lib.rs`
+        let path = rest.split('\t').next().unwrap_or("").trim();
+        if path == "/dev/null" {
+            continue;
+        }
+        let path = path.strip_prefix("a/").or_else(|| path.strip_prefix("b/")).unwrap_or(path);
+        if !paths.iter().any(|p| p == path) {
+            paths.push(path.to_string());
+        }
+    }
+    paths
+}
+
+/// Rejects diffs that reach outside the repository or into director-plan's own state.
+pub fn validate_paths(diff: &str) -> Result<()> {
+    for path in touched_paths(diff) {
+        let escapes = path.starts_with('/') || path.split('/').any(|part| part == "..");
+        let protected = [".git", ".director", "plan"]
+            .iter()
+            .any(|dir| path == *dir || path.starts_with(&format!("{}/", dir)));
+        if escapes || protected {
+            return Err(anyhow!("Patch touches a disallowed path: {}", path));
+        }
+    }
+    Ok(())
+}
+
+/// Checks that `diff` applies cleanly with `git apply --check`, then applies it.
+/// Nothing is written if the check fails; the error carries git's explanation.
+pub fn apply_patch(workspace_root: &Path, diff: &str, cancel: &CancelToken) -> Result<()> {
+    validate_paths(diff)?;
+
+    // --recount: LLM-written hunks often get the line counts in `@@` headers wrong
+    for check_only in [true, false] {
+        let mut cmd = Command::new("git");
+        cmd.current_dir(workspace_root).args(["apply", "--recount"]);
+        if check_only {
+            cmd.arg("--check");
+        }
+        cmd.arg("-");
+
+        let output = run_cancellable(cmd, Some(diff), cancel)?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow!("Patch does not apply cleanly:\n{}", stderr.trim()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_and_validate_diff() {
+        let output = "Here is the fix:\n```diff\n--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1 +1 @@\n-a\n+b\n```\n{\"confidence\": 0.9}";
+        let diff = extract_diff(output).unwrap();
+        assert!(diff.starts_with("--- a/src/lib.rs"));
+        assert_eq!(touched_paths(&diff), vec!["src/lib.rs".to_string()]);
+        assert!(validate_paths(&diff).is_ok());
+
+        assert!(extract_diff("no patch here").is_none());
+        assert!(validate_paths("--- a/plan/tickets/T-001.toml\n+++ b/plan/tickets/T-001.toml\n").is_err());
+        assert!(validate_paths("--- a/../etc/passwd\n+++ b/../etc/passwd\n").is_err());
+    }
+}
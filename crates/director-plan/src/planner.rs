@@ -0,0 +1,204 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+use anyhow::{Context, Result, anyhow, bail};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use toml_edit::{Array, DocumentMut};
+use crate::store::TicketStore;
+use crate::types::{Priority, Status, TicketType};
+
+/// One ticket in the plan an agent returns for `director-plan plan`. `key` is the agent's own
+/// name for it, used by the other tickets' `depends_on` before real IDs exist.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlannedTicket {
+    pub key: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub priority: Option<Priority>,
+    #[serde(default, rename = "type")]
+    pub ticket_type: Option<TicketType>,
+    #[serde(default)]
+    pub relevant_files: Vec<String>,
+    #[serde(default)]
+    pub verification: Option<String>,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+}
+
+/// The prompt asking an agent to turn `goal` into tickets. `overview` is the workspace overview
+/// the execution prompts start with, so the plan names real files.
+pub fn plan_prompt(goal: &str, overview: &str) -> String {
+    format!(
+        "# Plan a goal\n\nBreak the goal below into tickets for this repository. Each ticket should be one change that \
+        can be done, reviewed and verified on its own.\n\n\
+        Reply with only a JSON array. Each element is an object with:\n\
+        - `key`: a short unique name for the ticket, e.g. \"schema\"\n\
+        - `title` and `description`\n\
+        - `priority`: \"low\", \"medium\", \"high\" or \"critical\"\n\
+        - `type`: \"feature\", \"bug\", \"chore\" or \"spike\"\n\
+        - `relevant_files`: paths in the repository the change touches\n\
+        - `verification`: a shell command that proves the ticket is done\n\
+        - `depends_on`: keys of the tickets that must be done first\n\n\
+        ## Goal\n\n{}\n\n{}\n",
+        goal.trim(),
+        overview.trim(),
+    )
+}
+
+/// Reads a JSON array out of an agent's reply. It may be wrapped in prose or a code fence.
+pub fn parse_json_array<T: DeserializeOwned>(reply: &str) -> Result<Vec<T>> {
+    let start = reply.find('[').ok_or_else(|| anyhow!("The agent's reply has no JSON array"))?;
+    let end = reply.rfind(']').filter(|end| *end > start).ok_or_else(|| anyhow!("The agent's reply has no JSON array"))?;
+    serde_json::from_str(&reply[start..=end]).context("The agent's reply isn't a JSON array of tickets")
+}
+
+/// Checks the plan and orders it so every ticket comes after its dependencies. Fails on
+/// duplicate or unknown keys and on cycles.
+pub fn order(plan: Vec<PlannedTicket>) -> Result<Vec<PlannedTicket>> {
+    let mut keys = BTreeSet::new();
+    for ticket in &plan {
+        if ticket.title.trim().is_empty() {
+            bail!("Planned ticket '{}' has no title", ticket.key);
+        }
+        if !keys.insert(ticket.key.as_str()) {
+            bail!("The plan uses the key '{}' twice", ticket.key);
+        }
+    }
+    for ticket in &plan {
+        if let Some(dep) = ticket.depends_on.iter().find(|d| !keys.contains(d.as_str())) {
+            bail!("'{}' depends on '{}', which isn't in the plan", ticket.key, dep);
+        }
+    }
+
+    let mut done: BTreeSet<String> = BTreeSet::new();
+    let mut remaining = plan;
+    let mut ordered = Vec::new();
+    while !remaining.is_empty() {
+        let (ready, blocked): (Vec<_>, Vec<_>) = remaining.into_iter()
+            .partition(|t| t.depends_on.iter().all(|d| done.contains(d)));
+        if ready.is_empty() {
+            let keys: Vec<&str> = blocked.iter().map(|t| t.key.as_str()).collect();
+            bail!("The plan's dependencies form a cycle among: {}", keys.join(", "));
+        }
+        done.extend(ready.iter().map(|t| t.key.clone()));
+        ordered.extend(ready);
+        remaining = blocked;
+    }
+    Ok(ordered)
+}
+
+/// The next free `T-NNN` number after the highest existing one.
+pub(crate) fn next_number(store: &TicketStore) -> Result<u32> {
+    let mut highest = 0;
+    if store.tickets_dir().exists() {
+        for entry in fs::read_dir(store.tickets_dir())? {
+            let path = entry?.path();
+            if !crate::ticket_format::is_ticket_file(&path) {
+                continue;
+            }
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+            if let Some(n) = stem.strip_prefix("T-").and_then(|n| n.parse::<u32>().ok()) {
+                highest = highest.max(n);
+            }
+        }
+    }
+    Ok(highest + 1)
+}
+
+/// Writes an ordered plan (see [`order`]) as `T-NNN` tickets, numbered on from the existing
+/// ones, with `depends_on` pointing at the new IDs. Returns each key with its ticket ID.
+pub fn create_tickets(workspace_root: &Path, goal: &str, plan: &[PlannedTicket]) -> Result<Vec<(String, String)>> {
+    let store = TicketStore::new(workspace_root);
+    fs::create_dir_all(store.tickets_dir())?;
+    let mut next = next_number(&store)?;
+    let mut ids: BTreeMap<&str, String> = BTreeMap::new();
+    let mut created = Vec::new();
+    let created_at: toml_datetime::Datetime = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string().parse()?;
+
+    for ticket in plan {
+        let id = format!("T-{:03}", next);
+        next += 1;
+
+        let mut doc = DocumentMut::new();
+        doc["meta"] = toml_edit::table();
+        doc["meta"]["id"] = toml_edit::value(id.as_str());
+        doc["meta"]["title"] = toml_edit::value(ticket.title.as_str());
+        doc["meta"]["status"] = toml_edit::value(Status::Todo.to_string());
+        let priority = ticket.priority.clone().unwrap_or(Priority::Medium);
+        doc["meta"]["priority"] = toml_edit::value(format!("{:?}", priority).to_lowercase());
+        if let Some(ticket_type) = &ticket.ticket_type {
+            doc["meta"]["type"] = toml_edit::value(format!("{:?}", ticket_type).to_lowercase());
+        }
+        doc["meta"]["created_at"] = toml_edit::value(created_at);
+        crate::migrate::stamp(&mut doc);
+        if !ticket.depends_on.is_empty() {
+            let deps: Array = ticket.depends_on.iter().map(|key| ids[key.as_str()].as_str()).collect();
+            doc["meta"]["depends_on"] = toml_edit::value(deps);
+        }
+
+        doc["spec"] = toml_edit::table();
+        doc["spec"]["description"] = toml_edit::value(ticket.description.as_str());
+        doc["spec"]["relevant_files"] = toml_edit::value(ticket.relevant_files.iter().map(String::as_str).collect::<Array>());
+
+        doc["verification"] = toml_edit::table();
+        doc["verification"]["command"] = toml_edit::value(ticket.verification.clone().unwrap_or_default());
+        crate::append_history_entry(&mut doc, &format!("Planned from goal: {}", goal.lines().next().unwrap_or_default()));
+
+        store.save_document(&id, &doc)?;
+        ids.insert(ticket.key.as_str(), id.clone());
+        created.push((ticket.key.clone(), id));
+    }
+    Ok(created)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DirectorPlan;
+
+    const REPLY: &str = r#"Plan:
+```json
+[
+  {"key": "ui", "title": "Settings page", "depends_on": ["api"], "priority": "high", "type": "feature"},
+  {"key": "schema", "title": "Settings table", "verification": "cargo test -p db"},
+  {"key": "api", "title": "Settings endpoints", "depends_on": ["schema"], "relevant_files": ["src/api.rs"]}
+]
+```"#;
+
+    #[test]
+    fn test_order() {
+        let plan = order(parse_json_array(REPLY).unwrap()).unwrap();
+        let keys: Vec<&str> = plan.iter().map(|t| t.key.as_str()).collect();
+        assert_eq!(keys, ["schema", "api", "ui"]);
+
+        let cyclic: Vec<PlannedTicket> = parse_json_array(r#"[{"key": "a", "title": "A", "depends_on": ["b"]}, {"key": "b", "title": "B", "depends_on": ["a"]}]"#).unwrap();
+        assert!(order(cyclic).unwrap_err().to_string().contains("cycle"));
+        let unknown: Vec<PlannedTicket> = parse_json_array(r#"[{"key": "a", "title": "A", "depends_on": ["z"]}]"#).unwrap();
+        assert!(order(unknown).is_err());
+    }
+
+    #[test]
+    fn test_create_tickets() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("plan/tickets")).unwrap();
+        fs::write(root.join("plan/tickets/T-007.toml"), "").unwrap();
+
+        let plan = order(parse_json_array(REPLY).unwrap()).unwrap();
+        let created = create_tickets(root, "User settings", &plan).unwrap();
+        assert_eq!(created, [
+            ("schema".to_string(), "T-008".to_string()),
+            ("api".to_string(), "T-009".to_string()),
+            ("ui".to_string(), "T-010".to_string()),
+        ]);
+
+        let plan = DirectorPlan::new(root.to_path_buf());
+        let ui = plan.get_ticket("T-010").unwrap();
+        assert_eq!(ui.meta.depends_on, ["T-009"]);
+        assert_eq!(ui.meta.priority, Priority::High);
+        assert_eq!(plan.get_ticket("T-008").unwrap().verification.command, "cargo test -p db");
+    }
+}
@@ -0,0 +1,82 @@
+use std::io::{Read, Write};
+use std::process::{Command, Output, Stdio};
+use std::thread;
+use std::time::Duration;
+use anyhow::{Context, Result};
+use crate::shutdown::{CancelToken, Interrupted};
+
+/// Runs `cmd` to completion while polling `cancel`, killing the child if it fires. On unix the
+/// child leads its own process group, and the whole group is killed, so an agent's or test
+/// runner's own subprocesses don't outlive it.
+///
+/// stdin/stdout/stderr are pumped on helper threads so large prompts or chatty agents
+/// can't deadlock on full pipe buffers.
+pub fn run_cancellable(mut cmd: Command, stdin: Option<&str>, cancel: &CancelToken) -> Result<Output> {
+    cmd.stdin(if stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut cmd, 0);
+
+    let mut child = cmd.spawn().context("Failed to spawn command")?;
+
+    let stdin_handle = match (stdin, child.stdin.take()) {
+        (Some(input), Some(mut pipe)) => {
+            let input = input.to_string();
+            Some(thread::spawn(move || {
+                // The child may exit without reading everything; that's not our error to report.
+                let _ = pipe.write_all(input.as_bytes());
+            }))
+        }
+        _ => None,
+    };
+
+    let mut stdout_pipe = child.stdout.take();
+    let stdout_handle = thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(pipe) = stdout_pipe.as_mut() {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    });
+
+    let mut stderr_pipe = child.stderr.take();
+    let stderr_handle = thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(pipe) = stderr_pipe.as_mut() {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    });
+
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if cancel.is_cancelled() {
+            kill_group(child.id());
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(anyhow::Error::new(Interrupted));
+        }
+        thread::sleep(Duration::from_millis(100));
+    };
+
+    if let Some(handle) = stdin_handle {
+        let _ = handle.join();
+    }
+
+    Ok(Output {
+        status,
+        stdout: stdout_handle.join().unwrap_or_default(),
+        stderr: stderr_handle.join().unwrap_or_default(),
+    })
+}
+
+#[cfg(unix)]
+fn kill_group(pgid: u32) {
+    let _ = Command::new("kill").args(["-KILL", "--", &format!("-{}", pgid)]).output();
+}
+
+#[cfg(not(unix))]
+fn kill_group(_pgid: u32) {}
@@ -0,0 +1,76 @@
+use std::io::{self, BufRead, IsTerminal, Write};
+
+/// How to answer yes/no questions in flows that would otherwise hard-fail
+/// (dirty tree, stale branch, golden of the wrong size).
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Confirm {
+    /// Ask on the terminal
+    Ask,
+    /// `--yes`: take the suggested way out without asking
+    AssumeYes,
+    /// `--non-interactive`, or stdin isn't a terminal: never ask, fail as before
+    #[default]
+    Never,
+}
+
+impl Confirm {
+    pub fn from_flags(yes: bool, non_interactive: bool) -> Self {
+        if yes {
+            Confirm::AssumeYes
+        } else if non_interactive || !io::stdin().is_terminal() {
+            Confirm::Never
+        } else {
+            Confirm::Ask
+        }
+    }
+
+    /// Defaults to no: an empty answer, EOF or a read error all decline.
+    pub fn ask(&self, question: &str) -> bool {
+        match self {
+            Confirm::AssumeYes => {
+                crate::progress!(">> {} yes (--yes)", question);
+                true
+            }
+            Confirm::Never => false,
+            Confirm::Ask => {
+                let stdin = io::stdin();
+                loop {
+                    eprint!(">> {} [y/N] ", question);
+                    let _ = io::stderr().flush();
+                    let mut line = String::new();
+                    match stdin.lock().read_line(&mut line) {
+                        Ok(0) | Err(_) => return false,
+                        Ok(_) => {}
+                    }
+                    match parse_answer(&line) {
+                        Some(answer) => return answer,
+                        None => crate::progress!(">> Please answer y or n."),
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn parse_answer(line: &str) -> Option<bool> {
+    match line.trim().to_ascii_lowercase().as_str() {
+        "" | "n" | "no" => Some(false),
+        "y" | "yes" => Some(true),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_answer() {
+        assert_eq!(parse_answer("Y\n"), Some(true));
+        assert_eq!(parse_answer(" yes "), Some(true));
+        assert_eq!(parse_answer("\n"), Some(false));
+        assert_eq!(parse_answer("nope"), None);
+        assert!(Confirm::AssumeYes.ask("Continue?"));
+        assert!(!Confirm::Never.ask("Continue?"));
+    }
+}
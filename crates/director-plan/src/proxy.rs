@@ -0,0 +1,69 @@
+use std::net::IpAddr;
+use anyhow::{Result, anyhow};
+
+/// The address a request came from: the peer, or for requests relayed by a trusted proxy,
+/// the client it names in `X-Forwarded-For`. Set by the server for rate limiting and logs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub IpAddr);
+
+/// Resolves the client behind `peer`. Only `X-Forwarded-For` from a `trusted` proxy is believed,
+/// and only up to the first hop that isn't trusted, since anything before that a client can forge.
+pub fn client_ip(peer: IpAddr, forwarded_for: Option<&str>, trusted: &[IpAddr]) -> IpAddr {
+    if !trusted.contains(&peer) {
+        return peer;
+    }
+    let hops: Vec<IpAddr> = forwarded_for
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|hop| hop.trim().parse().ok())
+        .collect();
+    hops.iter().rev()
+        .find(|ip| !trusted.contains(ip))
+        .or(hops.first())
+        .copied()
+        .unwrap_or(peer)
+}
+
+/// `--base-path` as the server uses it: `""` for the root, otherwise `/a/b` with no trailing slash.
+pub fn normalize_base_path(path: &str) -> Result<String> {
+    let trimmed = path.trim_matches('/');
+    if trimmed.is_empty() {
+        return Ok(String::new());
+    }
+    let valid = trimmed.split('/').all(|segment| {
+        !segment.is_empty() && segment != "." && segment != ".."
+            && segment.chars().all(|c| c.is_ascii_alphanumeric() || "-_.~".contains(c))
+    });
+    if !valid {
+        return Err(anyhow!("Invalid base path '{}'", path));
+    }
+    Ok(format!("/{}", trimmed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_ip() {
+        let proxy: IpAddr = "10.0.0.1".parse().unwrap();
+        let client: IpAddr = "203.0.113.7".parse().unwrap();
+        let trusted = [proxy];
+
+        assert_eq!(client_ip(client, Some("1.2.3.4"), &trusted), client);
+        assert_eq!(client_ip(proxy, Some("203.0.113.7"), &trusted), client);
+        // The client prepended a forged hop; the proxy appended the real one
+        assert_eq!(client_ip(proxy, Some("1.2.3.4, 203.0.113.7"), &trusted), client);
+        assert_eq!(client_ip(proxy, None, &trusted), proxy);
+    }
+
+    #[test]
+    fn test_normalize_base_path() {
+        assert_eq!(normalize_base_path("").unwrap(), "");
+        assert_eq!(normalize_base_path("/").unwrap(), "");
+        assert_eq!(normalize_base_path("director/").unwrap(), "/director");
+        assert_eq!(normalize_base_path("/tools/director").unwrap(), "/tools/director");
+        assert!(normalize_base_path("/a/../b").is_err());
+        assert!(normalize_base_path("/a b").is_err());
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use crate::config::RateLimitConfig;
+
+/// Buckets idle for this long are full again and can be forgotten.
+const IDLE: Duration = Duration::from_secs(600);
+
+/// What a request costs, so an expensive verification run can't borrow from the read budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RouteClass {
+    Read,
+    Write,
+    /// `POST /api/tickets/:id/verify`, which runs the ticket's verification command
+    Verify,
+}
+
+impl RouteClass {
+    pub fn of(method: &str, route: &str) -> Self {
+        match method {
+            "GET" | "HEAD" | "OPTIONS" => RouteClass::Read,
+            _ if route.ends_with("/verify") => RouteClass::Verify,
+            _ => RouteClass::Write,
+        }
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token buckets per client IP and [`RouteClass`]. Each bucket holds a minute's allowance and
+/// refills continuously, so short bursts are fine but sustained polling is held to the rate.
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<(IpAddr, RouteClass), Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self { config, buckets: Mutex::new(HashMap::new()) }
+    }
+
+    fn per_minute(&self, class: RouteClass) -> u32 {
+        match class {
+            RouteClass::Read => self.config.read_per_minute,
+            RouteClass::Write => self.config.write_per_minute,
+            RouteClass::Verify => self.config.verify_per_minute,
+        }
+    }
+
+    /// Takes a token for the request. `Err` holds how long until one is available.
+    pub fn check(&self, ip: IpAddr, class: RouteClass, now: Instant) -> Result<(), Duration> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+        let capacity = self.per_minute(class) as f64;
+        if capacity == 0.0 {
+            return Ok(());
+        }
+        let per_sec = capacity / 60.0;
+
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() > 10_000 {
+            buckets.retain(|_, b| now.saturating_duration_since(b.updated) < IDLE);
+        }
+        let bucket = buckets.entry((ip, class)).or_insert(Bucket { tokens: capacity, updated: now });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_sec).min(capacity);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / per_sec))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buckets_refill() {
+        let config = RateLimitConfig { enabled: true, read_per_minute: 60, write_per_minute: 60, verify_per_minute: 2 };
+        let limiter = RateLimiter::new(config);
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+        let other: IpAddr = "10.0.0.2".parse().unwrap();
+        let start = Instant::now();
+
+        assert!(limiter.check(ip, RouteClass::Verify, start).is_ok());
+        assert!(limiter.check(ip, RouteClass::Verify, start).is_ok());
+        let wait = limiter.check(ip, RouteClass::Verify, start).unwrap_err();
+        assert_eq!(wait.as_secs(), 30);
+
+        // Other classes and other clients have their own budgets
+        assert!(limiter.check(ip, RouteClass::Read, start).is_ok());
+        assert!(limiter.check(other, RouteClass::Verify, start).is_ok());
+
+        assert!(limiter.check(ip, RouteClass::Verify, start + Duration::from_secs(30)).is_ok());
+    }
+
+    #[test]
+    fn test_route_class() {
+        assert_eq!(RouteClass::of("GET", "/api/tickets/:id"), RouteClass::Read);
+        assert_eq!(RouteClass::of("POST", "/api/tickets/:id/verify"), RouteClass::Verify);
+        assert_eq!(RouteClass::of("POST", "/api/workspaces/web/tickets/:id/verify"), RouteClass::Verify);
+        assert_eq!(RouteClass::of("PATCH", "/api/tickets/:id"), RouteClass::Write);
+    }
+}
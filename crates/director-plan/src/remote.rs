@@ -0,0 +1,82 @@
+use anyhow::{Context, Result, anyhow};
+use reqwest::StatusCode;
+use toml_edit::DocumentMut;
+use crate::client::{self, Client};
+use crate::store::set_status_in_document;
+use crate::types::{Status, Ticket};
+
+/// Attempts at a read-modify-write before giving up on a ticket that keeps changing underneath us.
+const MAX_UPDATE_ATTEMPTS: usize = 3;
+
+/// Ticket access over the server's raw ticket API (`/api/tickets/:id/raw`), for workers
+/// that run against their own clone instead of the server's filesystem.
+///
+/// Mirrors [`crate::store::TicketStore`]: edits go through `toml_edit`, and writes are
+/// conditional on the ETag of the version that was read.
+pub struct RemoteTicketStore {
+    client: Client,
+}
+
+impl RemoteTicketStore {
+    /// `client` decides the plan: the root one, or a sub-plan via [`Client::for_workspace`].
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+
+    pub async fn load(&self, id: &str) -> Result<Ticket> {
+        let raw = self.client.raw_ticket(id).await?;
+        raw.format.parse_ticket(&raw.content)
+            .with_context(|| format!("Failed to parse ticket {} from server", id))
+    }
+
+    /// Every ticket on the server, optionally filtered by status.
+    pub async fn list(&self, status_filter: Option<Status>) -> Result<Vec<Ticket>> {
+        let summaries = self.client.list_tickets().await?;
+        let mut tickets = Vec::new();
+        for summary in summaries {
+            if let Some(filter) = &status_filter
+                && summary.status != filter.to_string()
+            {
+                continue;
+            }
+            tickets.push(self.load(&summary.id).await?);
+        }
+        Ok(tickets)
+    }
+
+    /// Read-modify-write of a single ticket. If someone else wrote it in between
+    /// (412 Precondition Failed), the edit is re-applied to the fresh copy.
+    pub async fn update<F>(&self, id: &str, f: F) -> Result<()>
+    where
+        F: Fn(&mut DocumentMut) -> Result<()>,
+    {
+        for _ in 0..MAX_UPDATE_ATTEMPTS {
+            let raw = self.client.raw_ticket(id).await?;
+            let mut doc = raw.format.parse_document(&raw.content)
+                .with_context(|| format!("Failed to parse ticket {} from server", id))?;
+            f(&mut doc)?;
+
+            match self.client.put_raw_ticket(id, raw.format.render(&doc)?, raw.format, Some(&raw.etag)).await {
+                Ok(_) => return Ok(()),
+                Err(e) if client::is_status(&e, StatusCode::PRECONDITION_FAILED) => continue,
+                Err(e) => return Err(e.context(format!("Failed to write ticket {}", id))),
+            }
+        }
+
+        Err(anyhow!("Ticket {} kept changing; gave up after {} attempts", id, MAX_UPDATE_ATTEMPTS))
+    }
+
+    pub async fn set_status(&self, id: &str, status: Status) -> Result<()> {
+        self.update(id, |doc| {
+            set_status_in_document(doc, &status);
+            Ok(())
+        }).await
+    }
+
+    pub async fn append_history(&self, id: &str, message: &str) -> Result<()> {
+        self.update(id, |doc| {
+            crate::append_history_entry(doc, message);
+            Ok(())
+        }).await
+    }
+}
@@ -0,0 +1,179 @@
+use std::path::Path;
+use std::time::Instant;
+use anyhow::{Result, anyhow, bail};
+use chrono::Utc;
+use serde::Serialize;
+use crate::execution_loop::{SessionAgent, ShellAgent};
+use crate::experiment::{Worktree, git_output};
+use crate::run_diff::{prompt, unified};
+use crate::runs::{Role, RunRecord};
+use crate::secrets::ResolvedEnv;
+use crate::shutdown::CancelToken;
+use crate::store::TicketStore;
+
+/// `director-plan replay`: one recorded prompt sent again to another agent, next to what the
+/// original attempt got back and changed.
+#[derive(Debug, Clone, Serialize)]
+pub struct Replay {
+    pub run_id: String,
+    pub ticket_id: String,
+    pub attempt: u32,
+    /// The agent that made the original attempt
+    pub original_agent: String,
+    pub agent: String,
+    /// The commit both the original attempt and the replay started from
+    pub base_commit: String,
+    pub agent_ms: u64,
+    pub reply: String,
+    /// The agent's reply, as unified diff hunks from the original's to the replay's. Empty when
+    /// they're the same.
+    pub output: String,
+    /// The commit holding the original attempt's changes, if it was kept
+    pub original_commit: Option<String>,
+    /// The commit holding the replay's changes, kept as `refs/director/replays/...`; `None`
+    /// when it changed nothing
+    pub commit: Option<String>,
+    /// The ref `commit` is kept under
+    pub commit_ref: Option<String>,
+    /// Files only the original attempt changed
+    pub files_removed: Vec<String>,
+    /// Files only the replay changed
+    pub files_added: Vec<String>,
+    /// `false` when the original attempt was recorded without the files it changed
+    pub files_known: bool,
+    /// `git diff` from the original attempt's tree to the replay's, when the original's is known
+    pub changes: Option<String>,
+}
+
+/// Sends the prompt attempt `number` of `run` opened with (the first attempt's if `None`) to
+/// `agent`, in a fresh worktree of the commit the run started from under `.director/replays/`.
+/// Nothing in this checkout changes: the ticket, its runs and the tree are left alone. The
+/// replay's changes are kept as a commit so they can be compared with `git diff`.
+///
+/// The agent gets the ticket's `execution.env`, if the ticket still exists, but runs on the
+/// host even with `[container]` enabled. It isn't verified.
+pub fn replay(workspace_root: &Path, run: &RunRecord, number: Option<u32>, agent: &str, cancel: &CancelToken) -> Result<Replay> {
+    let number = number.unwrap_or(1);
+    if number == 0 || number as usize > run.attempts.len() {
+        bail!("Run {} has no attempt {}; it has {}", run.run_id, number, run.attempts.len());
+    }
+    let message = prompt(run, number);
+    if message.is_empty() {
+        bail!("Run {} didn't prompt the agent on attempt {}; it was re-verified, not re-run", run.run_id, number);
+    }
+    let Some(base) = run.base_commit.clone() else {
+        bail!("Run {} was recorded without the commit it started from, so it can't be replayed", run.run_id);
+    };
+    let attempt = &run.attempts[number as usize - 1];
+    let env = match TicketStore::new(workspace_root).load(&run.ticket_id) {
+        Ok(ticket) => ResolvedEnv::resolve(workspace_root, &ticket.execution.env)?,
+        Err(_) => ResolvedEnv::default(),
+    };
+
+    let stamp = Utc::now().format("%Y%m%dT%H%M%S%3f").to_string();
+    let worktree = Worktree::add(workspace_root, &crate::state_dir(workspace_root).join("replays").join(&stamp), &base)?;
+    crate::progress!(">> Replaying {}:{} with {}", run.run_id, number, agent);
+    let started = Instant::now();
+    let reply = ShellAgent::new(agent, &worktree.path).with_env(env).send(message, cancel)?;
+    let agent_ms = started.elapsed().as_millis() as u64;
+
+    let commit = commit_changes(&worktree.path, &base, &format!("replay({}): attempt {} of run {} with {}", run.ticket_id, number, run.run_id, agent))?;
+    let commit_ref = match &commit {
+        Some(sha) => {
+            let name = format!("refs/director/replays/{}/{}-{}", run.run_id, number, stamp);
+            git_output(workspace_root, &["update-ref", &name, sha])?;
+            Some(name)
+        }
+        None => None,
+    };
+    drop(worktree);
+
+    // A passing final attempt's changes are the run's commit; a failing one's, its snapshot
+    let original_commit = attempt.snapshot.clone()
+        .or_else(|| run.commit.clone().filter(|_| attempt.passed && number as usize == run.attempts.len()));
+    let original_files = match &original_commit {
+        Some(sha) => Some(changed_files(workspace_root, &base, sha)?),
+        None => attempt.context.as_ref().map(|usage| usage.modified.iter().chain(&usage.missed).cloned().collect()),
+    };
+    let replay_files = match &commit {
+        Some(sha) => changed_files(workspace_root, &base, sha)?,
+        None => Vec::new(),
+    };
+    // Without a commit, the original attempt's tree is only known if it changed nothing
+    let original_tree = original_commit.clone()
+        .or_else(|| original_files.as_ref().filter(|files| files.is_empty()).map(|_| base.clone()));
+    let changes = match original_tree {
+        Some(tree) => Some(git_output(workspace_root, &["diff", &tree, commit.as_deref().unwrap_or(&base)])?),
+        None => None,
+    };
+    let files_known = original_files.is_some();
+    let original_files = original_files.unwrap_or_default();
+    let only = |left: &[String], right: &[String]| left.iter().filter(|x| !right.contains(x)).cloned().collect::<Vec<String>>();
+
+    Ok(Replay {
+        run_id: run.run_id.clone(),
+        ticket_id: run.ticket_id.clone(),
+        attempt: number,
+        original_agent: attempt.agent.clone().unwrap_or_else(|| run.agent.clone()),
+        agent: agent.to_string(),
+        base_commit: base,
+        agent_ms,
+        output: unified(original_reply(run, number), &reply),
+        reply,
+        original_commit,
+        commit,
+        commit_ref,
+        files_removed: only(&original_files, &replay_files),
+        files_added: only(&replay_files, &original_files),
+        files_known,
+        changes,
+    })
+}
+
+/// The agent's answer to the prompt attempt `number` opened with.
+fn original_reply(run: &RunRecord, number: u32) -> &str {
+    run.transcript.iter()
+        .scan(0, |prompts, entry| {
+            if entry.role == Role::User {
+                *prompts += 1;
+            }
+            Some((*prompts, entry))
+        })
+        .find(|(prompts, entry)| *prompts == number && entry.role == Role::Agent)
+        .map_or("", |(_, entry)| entry.content.as_str())
+}
+
+/// Commits everything the agent changed in `dir` on top of `base`, outside `plan/`. `None`
+/// when it changed nothing.
+fn commit_changes(dir: &Path, base: &str, message: &str) -> Result<Option<String>> {
+    git_output(dir, &["add", "-A", "--", ".", ":(exclude)plan"])?;
+    let tree = git_output(dir, &["write-tree"])?;
+    if tree == git_output(dir, &["rev-parse", &format!("{}^{{tree}}", base)])? {
+        return Ok(None);
+    }
+    git_output(dir, &["commit-tree", &tree, "-p", base, "-m", message]).map(Some)
+}
+
+fn changed_files(workspace_root: &Path, from: &str, to: &str) -> Result<Vec<String>> {
+    let names = git_output(workspace_root, &["diff", "--name-only", from, to])
+        .map_err(|e| anyhow!("{:#}; was {} garbage-collected?", e, to))?;
+    Ok(names.lines().map(str::to_string).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_original_reply() {
+        let mut run = RunRecord::new("T-001", "agent");
+        run.push_message(Role::User, "task");
+        run.push_message(Role::Agent, "first try");
+        run.push_message(Role::User, "task\n## Previous Errors\nlexer panics");
+        run.push_message(Role::Agent, "second try");
+        run.push_message(Role::User, "re-verify");
+        assert_eq!(original_reply(&run, 1), "first try");
+        assert_eq!(original_reply(&run, 2), "second try");
+        assert_eq!(original_reply(&run, 3), "");
+    }
+}
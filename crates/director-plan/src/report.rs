@@ -0,0 +1,283 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use walkdir::WalkDir;
+use crate::DirectorPlan;
+use crate::runs::{Role, RunRecord, RunStore, attempt_ref};
+use crate::types::{Status, Ticket};
+
+const STYLE: &str = "\
+body{font-family:system-ui,sans-serif;margin:2rem;color:#222;background:#fafafa}\
+a{color:#5a2ad6}h1,h2,h3{font-weight:600}\
+.board{display:grid;grid-template-columns:repeat(5,1fr);gap:1rem}\
+.column{background:#eee;border-radius:6px;padding:.75rem}\
+.card{display:block;background:#fff;border:1px solid #ddd;border-radius:4px;padding:.5rem;margin:.5rem 0;text-decoration:none;color:inherit}\
+.id{font-family:monospace;color:#666}.pass{color:#087a3d}.fail{color:#b3261e}\
+table{border-collapse:collapse}td,th{border:1px solid #ddd;padding:.25rem .5rem;text-align:left;vertical-align:top}\
+pre{background:#f0f0f0;padding:.75rem;overflow-x:auto;white-space:pre-wrap}\
+.images{display:flex;gap:1rem;flex-wrap:wrap}.images figure{margin:0}.images img{max-width:320px;border:1px solid #ddd}";
+
+const COLUMNS: [(Status, &str); 5] = [
+    (Status::Todo, "Todo"),
+    (Status::InProgress, "In Progress"),
+    (Status::Review, "Review"),
+    (Status::Blocked, "Blocked"),
+    (Status::Done, "Done"),
+];
+
+/// What `director-plan report` wrote.
+#[derive(Debug, Serialize)]
+pub struct ReportSummary {
+    pub out_dir: PathBuf,
+    pub tickets: usize,
+    pub runs: usize,
+    pub images: usize,
+}
+
+/// Writes a static site to `out_dir`: `index.html` with the board, and a page per ticket with
+/// its history, runs (attempts, verification errors, transcript) and visual diff images. Every
+/// file it links to is copied in, so the directory can be zipped or published as it is.
+pub fn generate(workspace_root: &Path, out_dir: &Path) -> Result<ReportSummary> {
+    let tickets = DirectorPlan::new(workspace_root.to_path_buf()).list_tickets(None)?;
+    let run_store = RunStore::new(workspace_root);
+    fs::create_dir_all(out_dir.join("tickets"))
+        .with_context(|| format!("Failed to create {:?}", out_dir))?;
+
+    let generated_at = chrono::Utc::now().format("%Y-%m-%d %H:%M UTC").to_string();
+    let mut summary = ReportSummary { out_dir: out_dir.to_path_buf(), tickets: tickets.len(), runs: 0, images: 0 };
+
+    fs::write(out_dir.join("index.html"), render_board(&tickets, &generated_at))?;
+    for ticket in &tickets {
+        let runs = run_store.list_for_ticket(&ticket.meta.id)?;
+        let images = copy_artifacts(workspace_root, out_dir, &ticket.meta.id)?;
+        let log = fs::read_to_string(workspace_root.join(format!("plan/history/{}.log", ticket.meta.id)))
+            .map(|content| content.lines().map(String::from).collect())
+            .unwrap_or_else(|_| ticket.history.log.clone());
+        let page = render_ticket(ticket, &log, &runs, &images, &generated_at);
+        fs::write(out_dir.join(format!("tickets/{}.html", ticket.meta.id)), page)?;
+        summary.runs += runs.len();
+        summary.images += images.len();
+    }
+    Ok(summary)
+}
+
+/// Copies `target/public/artifacts/<id>/**/*.png` into the report. Returns their paths
+/// relative to `out_dir/artifacts/<id>`.
+fn copy_artifacts(workspace_root: &Path, out_dir: &Path, id: &str) -> Result<Vec<String>> {
+    let source = workspace_root.join("target/public/artifacts").join(id);
+    let dest = out_dir.join("artifacts").join(id);
+    let mut images = Vec::new();
+    if !source.is_dir() {
+        return Ok(images);
+    }
+    for entry in WalkDir::new(&source).into_iter().filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if !path.is_file() || path.extension().is_none_or(|e| e != "png") {
+            continue;
+        }
+        let rel = path.strip_prefix(&source)?;
+        let target = dest.join(rel);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(path, &target).with_context(|| format!("Failed to copy {:?}", path))?;
+        images.push(rel.to_string_lossy().replace('\\', "/"));
+    }
+    images.sort();
+    Ok(images)
+}
+
+fn page(title: &str, body: &str, generated_at: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n{}\n<footer><p><small>Generated by director-plan on {}</small></p></footer>\n</body>\n</html>\n",
+        escape(title), STYLE, body, generated_at,
+    )
+}
+
+fn render_board(tickets: &[Ticket], generated_at: &str) -> String {
+    let mut body = String::from("<h1>Board</h1>\n<div class=\"board\">\n");
+    for (status, label) in COLUMNS {
+        let column: Vec<&Ticket> = tickets.iter().filter(|t| t.meta.status == status).collect();
+        let _ = writeln!(body, "<div class=\"column\"><h2>{} ({})</h2>", label, column.len());
+        for ticket in column {
+            let _ = writeln!(
+                body,
+                "<a class=\"card\" href=\"tickets/{id}.html\"><span class=\"id\">{id}</span> {title}<br><small>{priority:?}{owner}</small></a>",
+                id = escape(&ticket.meta.id),
+                title = escape(&ticket.meta.title),
+                priority = ticket.meta.priority,
+                owner = ticket.meta.owner.as_deref().map(|o| format!(" · {}", escape(o))).unwrap_or_default(),
+            );
+        }
+        body.push_str("</div>\n");
+    }
+    body.push_str("</div>\n");
+
+    let archived: Vec<&Ticket> = tickets.iter().filter(|t| t.meta.status == Status::Archived).collect();
+    if !archived.is_empty() {
+        body.push_str("<h2>Archived</h2>\n<ul>\n");
+        for ticket in archived {
+            let _ = writeln!(body, "<li><a href=\"tickets/{id}.html\">{id}</a> {}</li>", escape(&ticket.meta.title), id = escape(&ticket.meta.id));
+        }
+        body.push_str("</ul>\n");
+    }
+    page("director-plan report", &body, generated_at)
+}
+
+fn render_ticket(ticket: &Ticket, log: &[String], runs: &[RunRecord], images: &[String], generated_at: &str) -> String {
+    let id = &ticket.meta.id;
+    let mut body = String::new();
+    let _ = writeln!(body, "<p><a href=\"../index.html\">← Board</a></p>");
+    let _ = writeln!(body, "<h1><span class=\"id\">{}</span> {}</h1>", escape(id), escape(&ticket.meta.title));
+    let _ = writeln!(
+        body,
+        "<p>Status: <b>{}</b> · Priority: {:?} · Owner: {}</p>",
+        ticket.meta.status.to_string(),
+        ticket.meta.priority,
+        escape(ticket.meta.owner.as_deref().unwrap_or("none")),
+    );
+    let _ = writeln!(body, "<h2>Description</h2>\n<pre>{}</pre>", escape(&ticket.spec.description));
+    if !ticket.spec.constraints.is_empty() {
+        body.push_str("<h2>Constraints</h2>\n<ul>\n");
+        for constraint in &ticket.spec.constraints {
+            let _ = writeln!(body, "<li>{}</li>", escape(constraint));
+        }
+        body.push_str("</ul>\n");
+    }
+    let _ = writeln!(body, "<h2>Verification</h2>\n<pre>{}</pre>", escape(&ticket.verification.command));
+
+    if !images.is_empty() {
+        body.push_str("<h2>Visual Diff</h2>\n<div class=\"images\">\n");
+        for image in images {
+            let _ = writeln!(
+                body,
+                "<figure><img src=\"../artifacts/{}/{}\" alt=\"{}\"><figcaption>{}</figcaption></figure>",
+                escape(id), escape(image), escape(image), escape(image),
+            );
+        }
+        body.push_str("</div>\n");
+    }
+
+    if !ticket.timing.transitions.is_empty() || !log.is_empty() {
+        body.push_str("<h2>History</h2>\n<ul>\n");
+        for transition in &ticket.timing.transitions {
+            let from = transition.from.as_ref().map(|s| s.to_string()).unwrap_or_else(|| "new".to_string());
+            let _ = writeln!(body, "<li>{} {} → {}</li>", transition.at.format("%Y-%m-%d %H:%M"), from, transition.to.to_string());
+        }
+        for line in log {
+            let _ = writeln!(body, "<li>{}</li>", escape(line));
+        }
+        body.push_str("</ul>\n");
+    }
+
+    let _ = writeln!(body, "<h2>Runs ({})</h2>", runs.len());
+    for run in runs.iter().rev() {
+        let outcome_class = if run.outcome == crate::runs::RunOutcome::Succeeded { "pass" } else { "fail" };
+        let _ = writeln!(
+            body,
+            "<h3>{} <span class=\"{}\">{:?}</span></h3>\n<p>Agent: <code>{}</code> · Started {}{}</p>",
+            escape(&run.run_id),
+            outcome_class,
+            run.outcome,
+            escape(&run.agent),
+            run.started_at.format("%Y-%m-%d %H:%M"),
+            run.commit.as_deref().map(|c| format!(" · Commit <code>{}</code>", escape(c))).unwrap_or_default(),
+        );
+        if !run.attempts.is_empty() {
+            body.push_str("<table>\n<tr><th>#</th><th>Result</th><th>Agent</th><th>Verification</th><th>Confidence</th><th>Errors</th></tr>\n");
+            for attempt in &run.attempts {
+                let errors = attempt.errors.iter().map(|e| format!("<pre>{}</pre>", escape(e))).collect::<String>();
+                let snapshot = attempt.snapshot.as_ref()
+                    .map(|_| format!("<br><code>{}</code>", escape(&attempt_ref(&run.run_id, attempt.number))))
+                    .unwrap_or_default();
+                let _ = writeln!(
+                    body,
+                    "<tr><td>{}</td><td class=\"{}\">{}{}</td><td>{} ms</td><td>{} ms</td><td>{}</td><td>{}</td></tr>",
+                    attempt.number,
+                    if attempt.passed { "pass" } else { "fail" },
+                    if attempt.passed { "passed" } else { "failed" },
+                    snapshot,
+                    attempt.agent_ms,
+                    attempt.verification_ms,
+                    attempt.confidence.map(|c| format!("{:.2}", c)).unwrap_or_default(),
+                    errors,
+                );
+            }
+            body.push_str("</table>\n");
+        }
+        if let Some(usage) = run.attempts.iter().rev().find_map(|a| a.context.as_ref()) {
+            let missed = usage.missed.iter().map(|p| format!("<code>{}</code>", escape(p))).collect::<Vec<_>>().join(", ");
+            let _ = writeln!(
+                body,
+                "<p>Context: {} file(s) changed, {} untouched{}</p>",
+                usage.modified.len(),
+                usage.untouched.len(),
+                if missed.is_empty() { String::new() } else { format!(" · Changed outside the context: {}", missed) },
+            );
+        }
+        if !run.transcript.is_empty() {
+            body.push_str("<details><summary>Transcript</summary>\n");
+            for entry in &run.transcript {
+                let role = match entry.role {
+                    Role::User => "Director",
+                    Role::Agent => "Agent",
+                };
+                let _ = writeln!(body, "<h4>{} · {}</h4>\n<pre>{}</pre>", role, entry.at.format("%H:%M:%S"), escape(&entry.content));
+            }
+            body.push_str("</details>\n");
+        }
+    }
+
+    page(&format!("{} {}", id, ticket.meta.title), &body, generated_at)
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("plan/tickets")).unwrap();
+        fs::write(
+            root.join("plan/tickets/T-001.toml"),
+            "[meta]\nid = \"T-001\"\ntitle = \"Fix <script> tags\"\nstatus = \"review\"\npriority = \"high\"\n\n[spec]\ndescription = \"d\"\n\n[verification]\ncommand = \"cargo test\"\n",
+        ).unwrap();
+        fs::create_dir_all(root.join("target/public/artifacts/T-001/hero")).unwrap();
+        fs::write(root.join("target/public/artifacts/T-001/hero/diff.png"), "png").unwrap();
+
+        let mut run = RunRecord::new("T-001", "agent");
+        run.push_message(Role::User, "Do the thing");
+        RunStore::new(root).save(&run).unwrap();
+
+        let out = root.join("report");
+        let summary = generate(root, &out).unwrap();
+        assert_eq!((summary.tickets, summary.runs, summary.images), (1, 1, 1));
+
+        let index = fs::read_to_string(out.join("index.html")).unwrap();
+        assert!(index.contains("href=\"tickets/T-001.html\""));
+        assert!(index.contains("Fix &lt;script&gt; tags"));
+        let page = fs::read_to_string(out.join("tickets/T-001.html")).unwrap();
+        assert!(page.contains("../artifacts/T-001/hero/diff.png"));
+        assert!(page.contains("Do the thing"));
+        assert!(out.join("artifacts/T-001/hero/diff.png").is_file());
+    }
+}
@@ -0,0 +1,241 @@
+use anyhow::{Result, bail};
+use serde::Serialize;
+use crate::runs::{Role, RunRecord, attempt_ref};
+use crate::verification::structure::lcs_pairs;
+
+/// Unchanged prompt lines shown around each change.
+const CONTEXT_LINES: usize = 3;
+
+/// Past this many line pairs the changed middle of two prompts isn't aligned, just shown as
+/// removed and added; the alignment table grows with the product.
+const MAX_ALIGNED_PAIRS: usize = 4_000_000;
+
+/// One attempt, written `<run-id>` for the run's last attempt or `<run-id>:<n>` for attempt `n`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttemptRef {
+    pub run_id: String,
+    pub number: Option<u32>,
+}
+
+impl AttemptRef {
+    pub fn parse(reference: &str) -> Self {
+        if let Some((run_id, number)) = reference.rsplit_once(':')
+            && let Ok(number) = number.parse()
+        {
+            return Self { run_id: run_id.to_string(), number: Some(number) };
+        }
+        Self { run_id: reference.to_string(), number: None }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AttemptSummary {
+    pub run_id: String,
+    pub ticket_id: String,
+    pub attempt: u32,
+    pub passed: bool,
+    pub agent_ms: u64,
+    pub verification_ms: u64,
+    pub confidence: Option<f32>,
+    /// The ref a failed attempt's tree was kept under, if it changed anything
+    pub snapshot: Option<String>,
+}
+
+/// What differs between two attempts, `a` being the earlier or failing one.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RunDiff {
+    pub a: AttemptSummary,
+    pub b: AttemptSummary,
+    /// The prompt each attempt was sent, as unified diff hunks from `a` to `b`. Empty when
+    /// they're the same.
+    pub prompt: String,
+    /// Verification errors only `a` had
+    pub errors_removed: Vec<String>,
+    /// Verification errors only `b` had
+    pub errors_added: Vec<String>,
+    /// Files only `a` had changed
+    pub files_removed: Vec<String>,
+    /// Files only `b` had changed
+    pub files_added: Vec<String>,
+    /// `false` when either attempt was recorded without the files it changed
+    pub files_known: bool,
+}
+
+/// Compares attempt `a_number` of run `a` with attempt `b_number` of run `b`; `None` means
+/// the run's last attempt.
+pub fn diff(a: &RunRecord, a_number: Option<u32>, b: &RunRecord, b_number: Option<u32>) -> Result<RunDiff> {
+    let a_attempt = attempt_number(a, a_number)?;
+    let b_attempt = attempt_number(b, b_number)?;
+    let summary = |run: &RunRecord, number: u32| {
+        let attempt = &run.attempts[number as usize - 1];
+        AttemptSummary {
+            run_id: run.run_id.clone(),
+            ticket_id: run.ticket_id.clone(),
+            attempt: number,
+            passed: attempt.passed,
+            agent_ms: attempt.agent_ms,
+            verification_ms: attempt.verification_ms,
+            confidence: attempt.confidence,
+            snapshot: attempt.snapshot.as_ref().map(|_| attempt_ref(&run.run_id, number)),
+        }
+    };
+    let errors = |run: &RunRecord, number: u32| run.attempts[number as usize - 1].errors.clone();
+    let files = |run: &RunRecord, number: u32| {
+        run.attempts[number as usize - 1].context.as_ref()
+            .map(|usage| usage.modified.iter().chain(&usage.missed).cloned().collect::<Vec<String>>())
+    };
+
+    let (a_errors, b_errors) = (errors(a, a_attempt), errors(b, b_attempt));
+    let (a_files, b_files) = (files(a, a_attempt), files(b, b_attempt));
+    let files_known = a_files.is_some() && b_files.is_some();
+    let (a_files, b_files) = (a_files.unwrap_or_default(), b_files.unwrap_or_default());
+    let only = |left: &[String], right: &[String]| left.iter().filter(|x| !right.contains(x)).cloned().collect::<Vec<String>>();
+
+    Ok(RunDiff {
+        prompt: unified(prompt(a, a_attempt), prompt(b, b_attempt)),
+        errors_removed: only(&a_errors, &b_errors),
+        errors_added: only(&b_errors, &a_errors),
+        files_removed: only(&a_files, &b_files),
+        files_added: only(&b_files, &a_files),
+        files_known,
+        a: summary(a, a_attempt),
+        b: summary(b, b_attempt),
+    })
+}
+
+fn attempt_number(run: &RunRecord, number: Option<u32>) -> Result<u32> {
+    let count = run.attempts.len() as u32;
+    match number {
+        None if count == 0 => bail!("Run {} has no attempts", run.run_id),
+        None => Ok(count),
+        Some(n) if n == 0 || n > count => bail!("Run {} has no attempt {}; it has {}", run.run_id, n, count),
+        Some(n) => Ok(n),
+    }
+}
+
+/// The message attempt `number` opened with: every attempt sends the agent one, in order.
+/// Attempts added by re-verification sent nothing.
+pub(crate) fn prompt(run: &RunRecord, number: u32) -> &str {
+    run.transcript.iter()
+        .filter(|entry| entry.role == Role::User)
+        .nth(number as usize - 1)
+        .map_or("", |entry| entry.content.as_str())
+}
+
+enum Line {
+    Same(usize),
+    Removed(usize),
+    Added(usize),
+}
+
+/// Hunks turning `old` into `new`, each with a `@@ -l,n +l,n @@` header and a few unchanged
+/// lines around the change.
+pub(crate) fn unified(old: &str, new: &str) -> String {
+    let (old, new): (Vec<&str>, Vec<&str>) = (old.lines().collect(), new.lines().collect());
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+    let (old_middle, new_middle) = (&old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix]);
+    let middle = if old_middle.len() * new_middle.len() <= MAX_ALIGNED_PAIRS { lcs_pairs(old_middle, new_middle) } else { Vec::new() };
+    let pairs = (0..prefix).map(|i| (i, i))
+        .chain(middle.into_iter().map(|(i, j)| (i + prefix, j + prefix)))
+        .chain((0..suffix).map(|k| (old.len() - suffix + k, new.len() - suffix + k)));
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    for (pi, pj) in pairs.chain(std::iter::once((old.len(), new.len()))) {
+        lines.extend((i..pi).map(Line::Removed));
+        lines.extend((j..pj).map(Line::Added));
+        if pi < old.len() {
+            lines.push(Line::Same(pi));
+        }
+        i = pi + 1;
+        j = pj + 1;
+    }
+
+    let changes: Vec<usize> = lines.iter().enumerate().filter(|(_, l)| !matches!(l, Line::Same(_))).map(|(k, _)| k).collect();
+    let mut out = String::new();
+    let mut k = 0;
+    while k < changes.len() {
+        let start = changes[k].saturating_sub(CONTEXT_LINES);
+        let mut end = changes[k] + CONTEXT_LINES + 1;
+        k += 1;
+        while k < changes.len() && changes[k] < end + CONTEXT_LINES {
+            end = changes[k] + CONTEXT_LINES + 1;
+            k += 1;
+        }
+        let end = end.min(lines.len());
+
+        let in_old = |l: &Line| !matches!(l, Line::Added(_));
+        let in_new = |l: &Line| !matches!(l, Line::Removed(_));
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            lines[..start].iter().filter(|l| in_old(l)).count() + 1,
+            lines[start..end].iter().filter(|l| in_old(l)).count(),
+            lines[..start].iter().filter(|l| in_new(l)).count() + 1,
+            lines[start..end].iter().filter(|l| in_new(l)).count(),
+        ));
+        for line in &lines[start..end] {
+            match *line {
+                Line::Same(i) => out.push_str(&format!(" {}\n", old[i])),
+                Line::Removed(i) => out.push_str(&format!("-{}\n", old[i])),
+                Line::Added(j) => out.push_str(&format!("+{}\n", new[j])),
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::usage::ContextUsage;
+    use crate::runs::AttemptRecord;
+
+    fn attempt(number: u32, passed: bool, errors: &[&str], changed: &[&str]) -> AttemptRecord {
+        AttemptRecord {
+            number,
+            agent_ms: 0,
+            verification_ms: 0,
+            passed,
+            confidence: None,
+            errors: errors.iter().map(|e| e.to_string()).collect(),
+            perf: None,
+            coverage: None,
+            autofixed: Vec::new(),
+            scoped_command: None,
+            flake: None,
+            result: None,
+            context: Some(ContextUsage { modified: changed.iter().map(|p| p.to_string()).collect(), ..Default::default() }),
+            snapshot: None,
+            agent: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_ref() {
+        assert_eq!(AttemptRef::parse("T-001-20260101T120000000:2"), AttemptRef { run_id: "T-001-20260101T120000000".to_string(), number: Some(2) });
+        assert_eq!(AttemptRef::parse("T-001-20260101T120000000"), AttemptRef { run_id: "T-001-20260101T120000000".to_string(), number: None });
+    }
+
+    #[test]
+    fn test_diff_attempts() {
+        let mut run = RunRecord::new("T-001", "agent");
+        run.attempts.push(attempt(1, false, &["Verification Failed:\nlexer panics"], &["src/parse.rs"]));
+        run.attempts.push(attempt(2, true, &[], &["src/parse.rs", "src/lexer.rs"]));
+        let task: String = (1..=10).map(|i| format!("line {}\n", i)).collect();
+        run.push_message(Role::User, &task);
+        run.push_message(Role::Agent, "done");
+        run.push_message(Role::User, &format!("{}## Previous Errors\nlexer panics\n", task.replace("line 2\n", "")));
+
+        let diff = diff(&run, Some(1), &run, None).unwrap();
+        assert_eq!((diff.a.attempt, diff.b.attempt, diff.b.passed), (1, 2, true));
+        assert_eq!(diff.prompt, "@@ -1,5 +1,4 @@\n line 1\n-line 2\n line 3\n line 4\n line 5\n@@ -8,3 +7,5 @@\n line 8\n line 9\n line 10\n+## Previous Errors\n+lexer panics\n");
+        assert_eq!(diff.errors_removed, ["Verification Failed:\nlexer panics"]);
+        assert!(diff.errors_added.is_empty());
+        assert_eq!(diff.files_added, ["src/lexer.rs"]);
+        assert!(diff.files_removed.is_empty() && diff.files_known);
+
+        assert!(super::diff(&run, Some(3), &run, None).is_err());
+        assert_eq!(super::diff(&run, None, &run, None).unwrap().prompt, "");
+    }
+}
@@ -0,0 +1,277 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::agent_result::AgentResult;
+use crate::context::usage::ContextUsage;
+use crate::verification::perf::PerfSample;
+
+/// A single `ExecutionLoop` invocation for one ticket.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RunRecord {
+    pub run_id: String,
+    pub ticket_id: String,
+    /// The agent that made the last attempt: with a fallback chain (`execution.agents`), the
+    /// one that succeeded, or the last one tried
+    pub agent: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub outcome: RunOutcome,
+    #[serde(default)]
+    pub attempts: Vec<AttemptRecord>,
+    /// Messages exchanged with the agent, in order, across all attempts.
+    #[serde(default)]
+    pub transcript: Vec<TranscriptEntry>,
+    /// Commit the run started from
+    #[serde(default)]
+    pub base_commit: Option<String>,
+    /// Line coverage (%) of `base_commit`, for `verification.min_coverage_delta`
+    #[serde(default)]
+    pub base_coverage: Option<f64>,
+    /// Commit holding the agent's changes, made when the run succeeds
+    #[serde(default)]
+    pub commit: Option<String>,
+    /// The ticket's `verification.command`, for flake statistics per command
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verification_command: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RunOutcome {
+    Running,
+    Succeeded,
+    Failed,
+    Interrupted,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AttemptRecord {
+    pub number: u32,
+    pub agent_ms: u64,
+    pub verification_ms: u64,
+    pub passed: bool,
+    pub confidence: Option<f32>,
+    #[serde(default)]
+    pub errors: Vec<String>,
+    /// Measured by `verification.perf`, if the ticket has a budget
+    #[serde(default)]
+    pub perf: Option<PerfSample>,
+    /// Line coverage (%), if the ticket has `min_coverage_delta`
+    #[serde(default)]
+    pub coverage: Option<f64>,
+    /// `[[lint.steps]]` whose autofix was applied before verification
+    #[serde(default)]
+    pub autofixed: Vec<String>,
+    /// The command run in place of `verification.command` when verification was scoped to
+    /// the agent's changes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scoped_command: Option<String>,
+    /// Why verification was judged flaky rather than the agent's fault, if it was
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flake: Option<String>,
+    /// The agent's ```director-plan-result block, if it sent one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<AgentResult>,
+    /// Which context files the agent changed by the end of this attempt, and what it changed
+    /// outside them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<ContextUsage>,
+    /// For a failed attempt, the commit holding the tree as the agent left it, also kept as
+    /// [`attempt_ref`] so it survives the reset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<String>,
+    /// The agent that made the attempt; older records only have the run's `agent`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+}
+
+/// Where the snapshot of attempt `number` of run `run_id` is kept.
+pub fn attempt_ref(run_id: &str, number: u32) -> String {
+    format!("refs/director/attempts/{}/{}", run_id, number)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    User,
+    Agent,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TranscriptEntry {
+    pub role: Role,
+    pub content: String,
+    pub at: DateTime<Utc>,
+}
+
+/// Where an unfinished run got to. Written to `.director/runs/<ticket-id>.state` after each
+/// phase, so `execute --resume` can carry on after a crash or an interruption, and removed
+/// when the run ends.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Checkpoint {
+    pub run_id: String,
+    pub ticket_id: String,
+    /// Attempts finished
+    pub attempts: u32,
+    /// Errors fed back to the agent so far
+    #[serde(default)]
+    pub previous_errors: Vec<String>,
+    pub phase: Phase,
+    /// The branch the run detached HEAD from, to return to when it ends
+    #[serde(default)]
+    pub branch: Option<String>,
+    /// Hash of the context files' contents when the agent was last prompted
+    #[serde(default)]
+    pub context_hash: Option<String>,
+    /// Commit holding the agent's work, made when the run was interrupted. After a crash the
+    /// work is still in the tree instead.
+    #[serde(default)]
+    pub snapshot: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// The phase an attempt was in.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    /// Being prompted, or working
+    Agent,
+    /// The agent's changes are in; verifying them
+    Verification,
+}
+
+impl RunRecord {
+    pub fn new(ticket_id: &str, agent: &str) -> Self {
+        let started_at = Utc::now();
+        Self {
+            run_id: format!("{}-{}", ticket_id, started_at.format("%Y%m%dT%H%M%S%3f")),
+            ticket_id: ticket_id.to_string(),
+            agent: agent.to_string(),
+            started_at,
+            finished_at: None,
+            outcome: RunOutcome::Running,
+            attempts: Vec::new(),
+            transcript: Vec::new(),
+            base_commit: None,
+            base_coverage: None,
+            commit: None,
+            verification_command: None,
+        }
+    }
+
+    pub fn push_message(&mut self, role: Role, content: &str) {
+        self.transcript.push(TranscriptEntry {
+            role,
+            content: content.to_string(),
+            at: Utc::now(),
+        });
+    }
+
+    pub fn agent_ms(&self) -> u64 {
+        self.attempts.iter().map(|a| a.agent_ms).sum()
+    }
+
+    pub fn verification_ms(&self) -> u64 {
+        self.attempts.iter().map(|a| a.verification_ms).sum()
+    }
+
+    /// The last result block the agent sent during this run.
+    pub fn latest_result(&self) -> Option<&AgentResult> {
+        self.attempts.iter().rev().find_map(|a| a.result.as_ref())
+    }
+}
+
+/// Run records live under `.director/runs/<ticket-id>/<run-id>.json`.
+pub struct RunStore {
+    runs_dir: PathBuf,
+}
+
+impl RunStore {
+    pub fn new(workspace_root: &Path) -> Self {
+        Self { runs_dir: crate::state_dir(workspace_root).join("runs") }
+    }
+
+    pub fn save(&self, record: &RunRecord) -> Result<()> {
+        let dir = self.runs_dir.join(&record.ticket_id);
+        fs::create_dir_all(&dir).context("Failed to create runs directory")?;
+        let path = dir.join(format!("{}.json", record.run_id));
+        fs::write(&path, serde_json::to_string_pretty(record)?)
+            .with_context(|| format!("Failed to write run record: {:?}", path))
+    }
+
+    /// All runs for a ticket, oldest first. Unreadable records are skipped.
+    pub fn list_for_ticket(&self, ticket_id: &str) -> Result<Vec<RunRecord>> {
+        let dir = self.runs_dir.join(ticket_id);
+        let mut runs = Vec::new();
+        if !dir.exists() {
+            return Ok(runs);
+        }
+
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == "json")
+                && let Ok(content) = fs::read_to_string(&path)
+                && let Ok(record) = serde_json::from_str::<RunRecord>(&content)
+            {
+                runs.push(record);
+            }
+        }
+
+        runs.sort_by_key(|r| r.started_at);
+        Ok(runs)
+    }
+
+    fn checkpoint_path(&self, ticket_id: &str) -> PathBuf {
+        self.runs_dir.join(format!("{}.state", ticket_id))
+    }
+
+    pub fn save_checkpoint(&self, checkpoint: &Checkpoint) -> Result<()> {
+        fs::create_dir_all(&self.runs_dir).context("Failed to create runs directory")?;
+        let path = self.checkpoint_path(&checkpoint.ticket_id);
+        fs::write(&path, serde_json::to_string_pretty(checkpoint)?)
+            .with_context(|| format!("Failed to write checkpoint: {:?}", path))
+    }
+
+    /// The checkpoint of the ticket's unfinished run, if it has one.
+    pub fn load_checkpoint(&self, ticket_id: &str) -> Result<Option<Checkpoint>> {
+        let path = self.checkpoint_path(ticket_id);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path).with_context(|| format!("Failed to read checkpoint: {:?}", path))?;
+        Ok(Some(serde_json::from_str(&content).with_context(|| format!("Invalid checkpoint: {:?}", path))?))
+    }
+
+    pub fn remove_checkpoint(&self, ticket_id: &str) -> Result<()> {
+        let path = self.checkpoint_path(ticket_id);
+        if path.exists() {
+            fs::remove_file(&path).with_context(|| format!("Failed to remove checkpoint: {:?}", path))?;
+        }
+        Ok(())
+    }
+
+    /// The run with this ID, whichever ticket it belongs to.
+    pub fn find(&self, run_id: &str) -> Result<Option<RunRecord>> {
+        Ok(self.list_all()?.into_iter().find(|r| r.run_id == run_id))
+    }
+
+    pub fn list_all(&self) -> Result<Vec<RunRecord>> {
+        let mut runs = Vec::new();
+        if !self.runs_dir.exists() {
+            return Ok(runs);
+        }
+
+        for entry in fs::read_dir(&self.runs_dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                let ticket_id = entry.file_name().to_string_lossy().to_string();
+                runs.extend(self.list_for_ticket(&ticket_id)?);
+            }
+        }
+
+        runs.sort_by_key(|r| r.started_at);
+        Ok(runs)
+    }
+}
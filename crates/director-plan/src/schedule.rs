@@ -0,0 +1,353 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Timelike, Utc};
+use toml_edit::DocumentMut;
+use crate::store::TicketStore;
+use crate::events::PlanEvent;
+
+/// A five-field cron expression (`minute hour day-of-month month day-of-week`), evaluated in UTC.
+///
+/// Fields take `*`, numbers, ranges (`1-5`), lists (`1,15`), steps (`*/15`, `0-30/10`) and
+/// the names `JAN`..`DEC` and `SUN`..`SAT`. `@hourly`, `@daily`, `@weekly`, `@monthly` and
+/// `@yearly` are accepted too. As in cron, when both day fields are restricted a day matching
+/// either one counts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cron {
+    minutes: u64,
+    hours: u32,
+    days: u32,
+    months: u16,
+    weekdays: u8,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+const MONTHS: &[&str] = &["JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC"];
+const WEEKDAYS: &[&str] = &["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+impl FromStr for Cron {
+    type Err = anyhow::Error;
+
+    fn from_str(expr: &str) -> Result<Self> {
+        let expanded = match expr.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            bail!("Invalid cron expression '{}': expected 5 fields, got {}", expr, fields.len());
+        };
+        let parse = |field: &str, min: u32, max: u32, names: &[&str]| {
+            parse_field(field, min, max, names).with_context(|| format!("Invalid cron expression '{}'", expr))
+        };
+
+        // 7 is Sunday too
+        let weekdays = parse(weekday, 0, 7, WEEKDAYS)?;
+        Ok(Cron {
+            minutes: parse(minute, 0, 59, &[])?,
+            hours: parse(hour, 0, 23, &[])? as u32,
+            days: parse(day, 1, 31, &[])? as u32,
+            months: parse(month, 1, 12, MONTHS)? as u16,
+            weekdays: ((weekdays | (weekdays >> 7)) & 0x7f) as u8,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        })
+    }
+}
+
+/// Bitset of the values `field` allows. Names are numbered from `min`.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64> {
+    let value = |s: &str| -> Result<u32> {
+        if let Some(i) = names.iter().position(|n| n.eq_ignore_ascii_case(s)) {
+            return Ok(i as u32 + min);
+        }
+        let n: u32 = s.parse().map_err(|_| anyhow!("'{}' is not a number", s))?;
+        if n < min || n > max {
+            bail!("{} is outside {}-{}", n, min, max);
+        }
+        Ok(n)
+    };
+
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().ok().filter(|s| *s > 0).ok_or_else(|| anyhow!("Invalid step in '{}'", part))?),
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((a, b)) => (value(a)?, value(b)?),
+                // `5/15` means from 5 to the end
+                None if step > 1 => (value(range)?, max),
+                None => { let v = value(range)?; (v, v) }
+            },
+        };
+        if start > end {
+            bail!("Range '{}' runs backwards", range);
+        }
+        for v in (start..=end).step_by(step as usize) {
+            bits |= 1 << v;
+        }
+    }
+    Ok(bits)
+}
+
+impl Cron {
+    fn day_matches(&self, date: NaiveDate) -> bool {
+        let day = self.days & (1 << date.day()) != 0;
+        let weekday = self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+        match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (false, true) => day,
+            (true, false) => weekday,
+            (false, false) => day || weekday,
+        }
+    }
+
+    /// The first matching minute strictly after `after`. `None` if nothing matches within
+    /// five years (e.g. `0 0 30 2 *`).
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut t = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = after + Duration::days(5 * 366);
+        while t <= limit {
+            if self.months & (1 << t.month()) == 0 {
+                let (year, month) = if t.month() == 12 { (t.year() + 1, 1) } else { (t.year(), t.month() + 1) };
+                t = Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0).single()?;
+            } else if !self.day_matches(t.date_naive()) {
+                t = Utc.from_utc_datetime(&(t.date_naive() + Duration::days(1)).and_hms_opt(0, 0, 0)?);
+            } else if self.hours & (1 << t.hour()) == 0 {
+                t = t.with_minute(0)? + Duration::hours(1);
+            } else if self.minutes & (1 << t.minute()) == 0 {
+                t += Duration::minutes(1);
+            } else {
+                return Some(t);
+            }
+        }
+        None
+    }
+
+    /// The latest matching minute in `(after, now]`, if there is one. Occurrences missed while
+    /// nothing was running collapse into this one.
+    pub fn latest_between(&self, after: DateTime<Utc>, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut latest = None;
+        // Don't walk years of every-minute schedules after a long downtime
+        let mut t = after.max(now - Duration::days(366));
+        while let Some(next) = self.next_after(t).filter(|next| *next <= now) {
+            latest = Some(next);
+            t = next;
+        }
+        latest
+    }
+}
+
+/// A ticket in `plan/templates/` with a `meta.schedule`.
+#[derive(Debug, Clone)]
+pub struct ScheduledTemplate {
+    /// File stem, which keys the occurrence store
+    pub name: String,
+    pub path: PathBuf,
+    pub cron: Cron,
+    pub schedule: String,
+}
+
+pub fn templates_dir(workspace_root: &Path) -> PathBuf {
+    workspace_root.join("plan/templates")
+}
+
+/// Templates that have a schedule. Ones whose schedule doesn't parse are skipped with a warning.
+pub fn scheduled_templates(workspace_root: &Path) -> Result<Vec<ScheduledTemplate>> {
+    let dir = templates_dir(workspace_root);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut templates = Vec::new();
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("toml") {
+            continue;
+        }
+        let doc = match fs::read_to_string(&path).ok().and_then(|c| c.parse::<DocumentMut>().ok()) {
+            Some(doc) => doc,
+            None => continue,
+        };
+        let Some(schedule) = doc.get("meta").and_then(|m| m.get("schedule")).and_then(|s| s.as_str()) else {
+            continue;
+        };
+        match schedule.parse::<Cron>() {
+            Ok(cron) => templates.push(ScheduledTemplate {
+                name: path.file_stem().unwrap_or_default().to_string_lossy().to_string(),
+                path: path.clone(),
+                cron,
+                schedule: schedule.to_string(),
+            }),
+            Err(e) => crate::progress!(">> Skipping template {:?}: {:#}", path, e),
+        }
+    }
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(templates)
+}
+
+/// The last occurrence created for each template, in `.director/schedules.json`, so a restart
+/// neither repeats an occurrence nor backfills every period it was down for.
+pub struct OccurrenceStore {
+    path: PathBuf,
+}
+
+impl OccurrenceStore {
+    pub fn new(workspace_root: &Path) -> Self {
+        Self { path: crate::state_dir(workspace_root).join("schedules.json") }
+    }
+
+    pub fn load(&self) -> Result<BTreeMap<String, DateTime<Utc>>> {
+        if !self.path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        let content = fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read {:?}", self.path))?;
+        serde_json::from_str(&content).with_context(|| format!("Failed to parse {:?}", self.path))
+    }
+
+    pub fn save(&self, occurrences: &BTreeMap<String, DateTime<Utc>>) -> Result<()> {
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(occurrences)?)?;
+        fs::rename(&tmp, &self.path).with_context(|| format!("Failed to write {:?}", self.path))
+    }
+}
+
+/// Creates the ticket for each template whose schedule came due since its last occurrence.
+/// A template seen for the first time starts counting from `now`. Returns the new ticket IDs.
+pub fn run_due(workspace_root: &Path, now: DateTime<Utc>) -> Result<Vec<String>> {
+    let templates = scheduled_templates(workspace_root)?;
+    if templates.is_empty() {
+        return Ok(Vec::new());
+    }
+    let store = OccurrenceStore::new(workspace_root);
+    let mut occurrences = store.load()?;
+    let mut created = Vec::new();
+    let mut changed = false;
+
+    for template in &templates {
+        let Some(last) = occurrences.get(&template.name).copied() else {
+            occurrences.insert(template.name.clone(), now);
+            changed = true;
+            continue;
+        };
+        let Some(due) = template.cron.latest_between(last, now) else {
+            continue;
+        };
+        if let Some(id) = instantiate(workspace_root, template, due)? {
+            crate::progress!(">> Scheduled ticket {} created from {}", id, template.name);
+            created.push(id);
+        }
+        occurrences.insert(template.name.clone(), due);
+        changed = true;
+    }
+
+    if changed {
+        store.save(&occurrences)?;
+    }
+    Ok(created)
+}
+
+/// Writes the occurrence of `template` for `at` to `plan/tickets/`. The ID is derived from the
+/// occurrence time, so if it already exists (another server or worker got there first) nothing
+/// is written and `None` is returned.
+pub fn instantiate(workspace_root: &Path, template: &ScheduledTemplate, at: DateTime<Utc>) -> Result<Option<String>> {
+    let content = fs::read_to_string(&template.path)
+        .with_context(|| format!("Failed to read template {:?}", template.path))?;
+    let mut doc = content.parse::<DocumentMut>()
+        .with_context(|| format!("Failed to parse template {:?}", template.path))?;
+
+    let field = |key: &str| doc.get("meta").and_then(|m| m.get(key)).and_then(|v| v.as_str()).unwrap_or(&template.name).to_string();
+    let id = format!("{}-{}", field("id"), at.format("%Y%m%d%H%M"));
+    let title = field("title");
+
+    let meta = doc["meta"].as_table_like_mut().ok_or_else(|| anyhow!("Template {:?} has no [meta]", template.path))?;
+    meta.remove("schedule");
+    doc["meta"]["id"] = toml_edit::value(id.clone());
+    let title = format!("{} ({})", title, at.format("%Y-%m-%d"));
+    doc["meta"]["title"] = toml_edit::value(title.as_str());
+    doc["meta"]["status"] = toml_edit::value("todo");
+    doc["meta"]["created_at"] = toml_edit::value(toml_datetime_of(at)?);
+    crate::append_history_entry(&mut doc, &format!("Created from scheduled template {} ({}) for {}", template.name, template.schedule, at.to_rfc3339()));
+
+    let store = TicketStore::new(workspace_root);
+    fs::create_dir_all(store.tickets_dir())?;
+    let path = store.ticket_path(&id);
+    let mut file = match fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("Failed to create {:?}", path)),
+    };
+    file.write_all(doc.to_string().as_bytes())?;
+    crate::events::emit(workspace_root, PlanEvent::TicketCreated { id: id.clone(), title });
+    Ok(Some(id))
+}
+
+fn toml_datetime_of(at: DateTime<Utc>) -> Result<toml_datetime::Datetime> {
+    at.format("%Y-%m-%dT%H:%M:%SZ").to_string().parse().context("Failed to format created_at")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_cron_next_after() {
+        let weekly: Cron = "0 9 * * MON".parse().unwrap();
+        // 2026-10-16 is a Friday
+        assert_eq!(weekly.next_after(at("2026-10-16T12:00:00Z")), Some(at("2026-10-19T09:00:00Z")));
+        assert_eq!(weekly.next_after(at("2026-10-19T09:00:00Z")), Some(at("2026-10-26T09:00:00Z")));
+
+        let quarter: Cron = "*/15 * * * *".parse().unwrap();
+        assert_eq!(quarter.next_after(at("2026-10-16T12:07:30Z")), Some(at("2026-10-16T12:15:00Z")));
+
+        let monthly: Cron = "@monthly".parse().unwrap();
+        assert_eq!(monthly.next_after(at("2026-12-05T00:00:00Z")), Some(at("2027-01-01T00:00:00Z")));
+
+        // Either day field matches when both are set: the 1st, or any Sunday (7)
+        let either: Cron = "0 0 1 * 7".parse().unwrap();
+        assert_eq!(either.next_after(at("2026-10-16T00:00:00Z")), Some(at("2026-10-18T00:00:00Z")));
+
+        assert_eq!("0 0 30 2 *".parse::<Cron>().unwrap().next_after(at("2026-01-01T00:00:00Z")), None);
+        assert!("0 9 * *".parse::<Cron>().is_err());
+        assert!("60 * * * *".parse::<Cron>().is_err());
+        assert!("0 9 * * FUNDAY".parse::<Cron>().is_err());
+    }
+
+    #[test]
+    fn test_run_due_creates_one_occurrence() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("plan/templates")).unwrap();
+        fs::write(root.join("plan/templates/audit.toml"), "[meta]\nid = \"AUDIT\"\ntitle = \"Dependency audit\"\nstatus = \"todo\"\npriority = \"medium\"\nschedule = \"0 9 * * MON\"\n\n[spec]\ndescription = \"Run cargo audit\"\n\n[verification]\ncommand = \"cargo audit\"\n").unwrap();
+
+        // First sight only records the starting point
+        assert!(run_due(root, at("2026-10-16T12:00:00Z")).unwrap().is_empty());
+        assert!(run_due(root, at("2026-10-19T08:59:00Z")).unwrap().is_empty());
+
+        // Two missed Mondays make one ticket, for the latest
+        let created = run_due(root, at("2026-10-27T10:00:00Z")).unwrap();
+        assert_eq!(created, vec!["AUDIT-202610260900"]);
+        let ticket = TicketStore::new(root).load("AUDIT-202610260900").unwrap();
+        assert_eq!(ticket.meta.title, "Dependency audit (2026-10-26)");
+        assert_eq!(ticket.meta.schedule, None);
+
+        // Restarting doesn't repeat it
+        assert!(run_due(root, at("2026-10-27T10:05:00Z")).unwrap().is_empty());
+        assert_eq!(fs::read_dir(root.join("plan/tickets")).unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,132 @@
+use std::path::Path;
+use std::process::Command;
+use anyhow::{Context, Result, anyhow};
+use glob::{MatchOptions, Pattern};
+
+/// director-plan's own output, never counted as agent changes
+const IGNORED_PREFIXES: [&str; 2] = ["plan/", "proof/"];
+
+/// Files changed since `base_commit`: committed, staged, unstaged and untracked (but not ignored).
+pub fn changed_paths(workspace_root: &Path, base_commit: &str) -> Result<Vec<String>> {
+    let mut paths = Vec::new();
+    for args in [
+        vec!["diff", "--name-only", "--no-renames", base_commit],
+        vec!["ls-files", "--others", "--exclude-standard"],
+    ] {
+        let output = Command::new("git")
+            .current_dir(workspace_root)
+            .args(&args)
+            .output()
+            .with_context(|| format!("Failed to run git {}", args.join(" ")))?;
+        if !output.status.success() {
+            return Err(anyhow!("git {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            if !line.is_empty() && !paths.iter().any(|p| p == line) {
+                paths.push(line.to_string());
+            }
+        }
+    }
+    paths.retain(|p| !IGNORED_PREFIXES.iter().any(|prefix| p.starts_with(prefix)));
+    Ok(paths)
+}
+
+/// The changed paths no `spec.allowed_paths` glob matches. `*` stays within one directory,
+/// `**` crosses them, and a trailing `/` means everything under that directory.
+pub fn violations(allowed: &[String], changed: &[String]) -> Result<Vec<String>> {
+    let patterns = compile(allowed, "spec.allowed_paths")?;
+    Ok(changed.iter().filter(|path| !matches_any(&patterns, path)).cloned().collect())
+}
+
+/// The changed paths a `[protection] never_touch` glob matches. Same glob rules as [`violations`].
+pub fn protected(never_touch: &[String], changed: &[String]) -> Result<Vec<String>> {
+    let patterns = compile(never_touch, "[protection] never_touch")?;
+    Ok(changed.iter().filter(|path| matches_any(&patterns, path)).cloned().collect())
+}
+
+/// The paths matching one of `include` (any path, if it's empty) and none of `exclude`.
+/// Same glob rules as [`violations`].
+pub fn filter(include: &[String], exclude: &[String], paths: &[String], setting: &str) -> Result<Vec<String>> {
+    let include = compile(include, setting)?;
+    let exclude = compile(exclude, setting)?;
+    Ok(paths.iter()
+        .filter(|path| (include.is_empty() || matches_any(&include, path)) && !matches_any(&exclude, path))
+        .cloned()
+        .collect())
+}
+
+/// Fails on the first glob in `globs` that doesn't compile.
+pub fn check_globs(globs: &[String], setting: &str) -> Result<()> {
+    compile(globs, setting).map(|_| ())
+}
+
+/// Puts `paths` back the way they were at `base_commit`; files that didn't exist there are deleted.
+pub fn revert(dir: &Path, base_commit: &str, paths: &[String]) -> Result<()> {
+    for path in paths {
+        let existed = Command::new("git")
+            .current_dir(dir)
+            .args(["cat-file", "-e", &format!("{}:{}", base_commit, path)])
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false);
+        let args: Vec<&str> = if existed {
+            vec!["checkout", base_commit, "--", path]
+        } else {
+            vec!["rm", "-q", "-f", "--cached", "--ignore-unmatch", "--", path]
+        };
+        let status = Command::new("git")
+            .current_dir(dir)
+            .args(&args)
+            .status()
+            .with_context(|| format!("Failed to revert {}", path))?;
+        if !status.success() {
+            return Err(anyhow!("Failed to revert {}", path));
+        }
+        if !existed {
+            let file = dir.join(path);
+            if file.exists() {
+                std::fs::remove_file(&file).with_context(|| format!("Failed to remove {:?}", file))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn compile(globs: &[String], setting: &str) -> Result<Vec<Pattern>> {
+    globs.iter()
+        .map(|glob| {
+            let glob = if glob.ends_with('/') { format!("{}**", glob) } else { glob.clone() };
+            Pattern::new(&glob).with_context(|| format!("Invalid {} glob: {}", setting, glob))
+        })
+        .collect()
+}
+
+fn matches_any(patterns: &[Pattern], path: &str) -> bool {
+    let options = MatchOptions { require_literal_separator: true, ..MatchOptions::new() };
+    patterns.iter().any(|p| p.matches_with(path, options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_violations() {
+        let allowed = vec!["src/ui/**".to_string(), "docs/".to_string(), "Cargo.toml".to_string(), "tests/*.rs".to_string()];
+        let changed: Vec<String> = ["src/ui/button.rs", "src/ui/forms/input.rs", "docs/a/b.md", "Cargo.toml", "tests/ui.rs", "tests/deep/x.rs", "src/server.rs"]
+            .iter().map(|s| s.to_string()).collect();
+        assert_eq!(violations(&allowed, &changed).unwrap(), vec!["tests/deep/x.rs".to_string(), "src/server.rs".to_string()]);
+        assert!(violations(&["src/[".to_string()], &changed).is_err());
+
+        let never_touch = vec!["migrations/**".to_string(), ".github/**".to_string()];
+        let changed = vec!["migrations/001_init.sql".to_string(), ".github/workflows/ci.yml".to_string(), "src/db.rs".to_string()];
+        assert_eq!(protected(&never_touch, &changed).unwrap(), changed[..2].to_vec());
+
+        let include = vec!["src/components/".to_string(), "**/*.css".to_string()];
+        let exclude = vec!["**/*.stories.tsx".to_string()];
+        let paths: Vec<String> = ["src/components/button.tsx", "src/components/button.stories.tsx", "styles/app.css", "crates/api/src/lib.rs"]
+            .iter().map(|s| s.to_string()).collect();
+        assert_eq!(filter(&include, &exclude, &paths, "profile").unwrap(), ["src/components/button.tsx", "styles/app.css"]);
+        assert_eq!(filter(&[], &exclude, &paths, "profile").unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use anyhow::{Context, Result, anyhow};
+use crate::config::PlanConfig;
+
+/// Prefix marking an env value as a reference into `[secrets]`, e.g. `DATABASE_URL = "secret:TEST_DB"`.
+const SECRET_PREFIX: &str = "secret:";
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Environment variables for an agent or verification command, with `secret:` references
+/// resolved. Keeps the secret values around so they can be scrubbed from captured output
+/// before it reaches prompts, run records or API responses.
+#[derive(Debug, Clone, Default)]
+pub struct ResolvedEnv {
+    vars: Vec<(String, String)>,
+    secrets: Vec<String>,
+}
+
+impl ResolvedEnv {
+    /// Resolves a ticket env map. `secret:NAME` looks up `NAME` in the process environment first,
+    /// then in the `[secrets] file` (a flat `NAME = "value"` TOML file, `.director/secrets.toml`
+    /// by default).
+    pub fn resolve(workspace_root: &Path, env: &BTreeMap<String, String>) -> Result<Self> {
+        let mut resolved = Self::default();
+        let mut file_secrets: Option<BTreeMap<String, String>> = None;
+
+        for (key, value) in env {
+            let name = match value.strip_prefix(SECRET_PREFIX) {
+                Some(name) => name.trim(),
+                None => {
+                    resolved.vars.push((key.clone(), value.clone()));
+                    continue;
+                }
+            };
+
+            let secret = match std::env::var(name) {
+                Ok(secret) => secret,
+                Err(_) => {
+                    if file_secrets.is_none() {
+                        file_secrets = Some(load_secrets_file(workspace_root)?);
+                    }
+                    file_secrets.as_ref().and_then(|s| s.get(name)).cloned()
+                        .ok_or_else(|| anyhow!("Secret {} (for {}) is not set in the environment or the secrets file", name, key))?
+                }
+            };
+
+            if !secret.is_empty() {
+                resolved.secrets.push(secret.clone());
+            }
+            resolved.vars.push((key.clone(), secret));
+        }
+
+        Ok(resolved)
+    }
+
+    /// Both maps for one command, later entries winning.
+    pub fn merged(&self, other: &ResolvedEnv) -> ResolvedEnv {
+        let mut vars = self.vars.clone();
+        for (key, value) in &other.vars {
+            vars.retain(|(k, _)| k != key);
+            vars.push((key.clone(), value.clone()));
+        }
+        let mut secrets = self.secrets.clone();
+        secrets.extend(other.secrets.iter().cloned());
+        ResolvedEnv { vars, secrets }
+    }
+
+    pub fn vars(&self) -> Vec<(&str, &str)> {
+        self.vars.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect()
+    }
+
+    /// Replaces every secret value in `text` with `[REDACTED]`.
+    pub fn redact(&self, text: &str) -> String {
+        let mut out = text.to_string();
+        // Longest first so a secret containing another isn't partially revealed
+        let mut secrets: Vec<&String> = self.secrets.iter().collect();
+        secrets.sort_by_key(|s| std::cmp::Reverse(s.len()));
+        for secret in secrets {
+            out = out.replace(secret.as_str(), REDACTED);
+        }
+        out
+    }
+}
+
+fn load_secrets_file(workspace_root: &Path) -> Result<BTreeMap<String, String>> {
+    let config = PlanConfig::load(workspace_root)?;
+    let path = match &config.secrets.file {
+        Some(file) => workspace_root.join(file),
+        None => crate::state_dir(workspace_root).join("secrets.toml"),
+    };
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read secrets file: {:?}", path))?;
+    toml_edit::de::from_str(&content)
+        .with_context(|| format!("Failed to parse secrets file: {:?}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secrets_resolve_from_file_and_are_redacted() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(crate::state_dir(dir.path()).join("secrets.toml"), "DIRECTOR_TEST_DB = \"postgres://u:hunter2@db\"\n").unwrap();
+
+        let mut env = BTreeMap::new();
+        env.insert("DATABASE_URL".to_string(), "secret:DIRECTOR_TEST_DB".to_string());
+        env.insert("RUST_LOG".to_string(), "debug".to_string());
+
+        let resolved = ResolvedEnv::resolve(dir.path(), &env).unwrap();
+        assert!(resolved.vars().contains(&("DATABASE_URL", "postgres://u:hunter2@db")));
+        assert!(resolved.vars().contains(&("RUST_LOG", "debug")));
+        assert_eq!(resolved.redact("connect postgres://u:hunter2@db failed"), "connect [REDACTED] failed");
+
+        env.insert("MISSING".to_string(), "secret:DIRECTOR_TEST_MISSING".to_string());
+        assert!(ResolvedEnv::resolve(dir.path(), &env).is_err());
+    }
+}
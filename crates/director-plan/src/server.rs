@@ -1,30 +1,213 @@
 use axum::{
-    extract::{Path, State, Multipart, DefaultBodyLimit},
-    http::StatusCode,
+    extract::{ConnectInfo, Path, Query, State, Multipart, DefaultBodyLimit, MatchedPath, Request},
+    http::{header, HeaderName, HeaderValue, StatusCode},
+    middleware::{self, Next},
     response::{IntoResponse, Json, Response},
-    routing::{get, post},
+    response::sse::{Event, KeepAlive, Sse},
+    routing::{delete, get, post},
     Router,
 };
-use serde::Deserialize;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
-use std::net::SocketAddr;
+use std::cmp::Ordering;
+use std::convert::Infallible;
+use std::collections::BTreeMap;
+use std::net::{IpAddr, SocketAddr};
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use tokio::fs;
 use tokio::process::Command;
+use tokio::sync::broadcast;
+use tokio_stream::{Stream, StreamExt};
+use tokio_stream::wrappers::BroadcastStream;
 use tower_http::cors::CorsLayer;
 use tower_http::services::ServeDir;
-use tower_http::services::ServeFile;
-use tracing::{info, error};
-
-use crate::types::{Ticket, Status, FrontendTicket, Artifacts};
+use tracing::{info, warn, error};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use utoipa_swagger_ui::SwaggerUi;
+
+use crate::assets::{self, UploadRejection};
+use crate::config::PlanConfig;
+use crate::context::discovery::write_context;
+use crate::context::warm::WarmGraph;
+use crate::gc;
+use crate::schedule;
+use crate::blocking;
+use crate::budget;
+use crate::comments::{Comment, CommentStore};
+use crate::events::{self, EventLog, EventRecord, LoggedEvent, PlanEvent};
+use crate::webhooks;
+use crate::ticket_format::{TicketFormat, find_ticket_file};
+use crate::worker_status::{WorkerHeartbeat, WorkerRegistry, WorkerStatus};
+use crate::leases::{Claim, Lease, LeaseTable};
+use crate::proxy::{self, ClientIp};
+use crate::ratelimit::{RateLimiter, RouteClass};
+use crate::tls;
+use crate::ui::{self, UiSource};
+use crate::runs::{RunStore, RunOutcome};
+use crate::run_diff::{self, AttemptRef, RunDiff};
+use crate::cancel::{self, Cancellation, NotRunning};
+use crate::shutdown::Shutdown;
+use crate::sla::{self, DueState};
+use crate::store::{record_status_change, set_status_in_document, TicketStore};
+use crate::telemetry::{self, ServerMetrics};
+use crate::timing;
+use crate::types::{Ticket, Status, Priority, FrontendTicket, Artifacts, Metrics, SnapshotArtifacts};
+use crate::verification::{batch, flaky, golden, visual_diff};
+use crate::verification::policy::{self, PolicyViolation};
+use crate::secrets::ResolvedEnv;
 
 #[derive(Clone)]
 struct AppState {
     workspace_root: PathBuf,
+    config: PlanConfig,
+    metrics: Arc<ServerMetrics>,
+    leases: Arc<Mutex<LeaseTable>>,
+    // Serializes conditional raw-ticket writes so the ETag check and the write are atomic
+    ticket_writes: Arc<Mutex<()>>,
+    graph: WarmGraph,
+    /// `--base-path`, prefixed to the artifact and asset URLs the API returns
+    base_path: String,
+    /// Workers' latest heartbeats; only the root workspace's is used
+    workers: Arc<Mutex<WorkerRegistry>>,
+    /// Everything appended to the event log since startup, for SSE clients and webhooks
+    events: broadcast::Sender<LoggedEvent>,
+}
+
+impl AppState {
+    /// `path` (absolute, e.g. `/assets/logo.png`) as the browser must request it.
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_path, path)
+    }
 }
 
+/// How `serve` listens and where it's mounted.
+#[derive(Debug, Clone, Default)]
+pub struct ServeOptions {
+    /// PEM certificate chain and private key; serves HTTPS when set
+    pub tls: Option<(PathBuf, PathBuf)>,
+    /// Serve everything under this path, e.g. `/director` behind a reverse proxy
+    pub base_path: String,
+    /// Serve the Studio from this directory instead of the embedded or workspace build
+    pub ui_dir: Option<PathBuf>,
+    /// Use this instead of the workspace's `plan/config.toml`. Sub-plans still load their own.
+    pub config: Option<PlanConfig>,
+}
+
+/// The whole app for `workspace_root`, unbound, so it can be served on any listener or nested
+/// in another axum app.
 pub async fn create_app(workspace_root: PathBuf) -> anyhow::Result<Router> {
+    create_app_with(workspace_root, &ServeOptions::default()).await
+}
+
+/// The app with every route under `options.base_path`. TLS is up to the caller.
+pub async fn create_app_with(workspace_root: PathBuf, options: &ServeOptions) -> anyhow::Result<Router> {
+    let base_path = proxy::normalize_base_path(&options.base_path)?;
+    let state = build_state(workspace_root.clone(), base_path.clone(), options.config.clone()).await?;
+
+    let cors = CorsLayer::new()
+        .allow_origin(tower_http::cors::Any) // For dev purposes, allows frontend dev server
+        .allow_methods(tower_http::cors::Any)
+        .allow_headers(tower_http::cors::Any);
+
+    let upload_limit = state.config.assets.largest_limit() as usize;
+    let mut app = Router::new()
+        .nest("/api", ticket_api(upload_limit))
+        .route("/api/workspaces", get(list_workspaces))
+        .route("/api/workers", get(list_workers))
+        .route("/api/workers/heartbeat", post(worker_heartbeat))
+        .route("/metrics", get(prometheus_metrics))
+        .merge(swagger_ui(&base_path));
+
+    // Each sub-plan gets the same ticket API over its own plan/, leases and artifacts
+    for (name, root) in state.config.sub_plans(&workspace_root)? {
+        let sub_state = build_state(root, base_path.clone(), None).await
+            .with_context(|| format!("Failed to load workspace '{}'", name))?;
+        app = app.nest(&format!("/api/workspaces/{}", name), ticket_api(upload_limit).with_state(sub_state));
+    }
+
+    let app = app
+        .nest_service("/artifacts", ServeDir::new(workspace_root.join("target/public/artifacts")))
+        .nest_service("/assets", ServeDir::new(workspace_root.join("assets")))
+        // The Studio for everything else
+        .fallback_service(ui::service(UiSource::resolve(options.ui_dir.clone(), &workspace_root), &base_path))
+        .layer(cors)
+        .layer(DefaultBodyLimit::max(state.config.server.max_body_bytes))
+        .layer(middleware::from_fn_with_state(Arc::new(RateLimiter::new(state.config.server.rate_limit.clone())), rate_limit))
+        .layer(middleware::from_fn_with_state(Arc::new(state.config.server.trusted_proxies.clone()), resolve_client_ip))
+        .layer(middleware::from_fn_with_state(state.clone(), track_http_metrics))
+        .with_state(state);
+
+    if base_path.is_empty() {
+        Ok(app)
+    } else {
+        Ok(Router::new().nest(&base_path, app))
+    }
+}
+
+/// Swagger UI at `/api/docs`. Behind a base path the page must fetch the document from under it.
+fn swagger_ui(base_path: &str) -> SwaggerUi {
+    let ui = SwaggerUi::new("/api/docs").url("/api/openapi.json", openapi());
+    if base_path.is_empty() {
+        ui
+    } else {
+        ui.config(utoipa_swagger_ui::Config::from(format!("{}/api/openapi.json", base_path)))
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "director-plan",
+        description = "Ticket, lease, verification and asset API served by `director-plan serve`. \
+            Every `/api/tickets`, `/api/stats`, `/api/events`, `/api/context`, `/api/runs` and `/api/assets` route is also served \
+            for each sub-plan under `/api/workspaces/{ws}`.",
+    ),
+    paths(
+        list_tickets, get_stats, get_ticket, update_ticket, get_raw_ticket, put_raw_ticket,
+        claim_ticket, heartbeat_ticket, release_ticket, block_ticket, unblock_ticket,
+        list_comments, add_comment, stream_events, get_context, write_ticket_context, diff_runs, cancel_run, verify_ticket, accept_golden,
+        upload_asset, list_assets, delete_asset, rename_asset, list_workspaces, list_workers, worker_heartbeat,
+        prometheus_metrics,
+    ),
+)]
+struct ApiDoc;
+
+/// The server's OpenAPI document, also served at `/api/openapi.json`.
+pub fn openapi() -> utoipa::openapi::OpenApi {
+    ApiDoc::openapi()
+}
+
+/// Ticket routes, mounted at `/api` for this workspace and at `/api/workspaces/:ws` for each sub-plan.
+/// Uploads may be up to `upload_limit` bytes; everything else gets `[server] max_body_bytes`.
+fn ticket_api(upload_limit: usize) -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/tickets", get(list_tickets))
+        .route("/stats", get(get_stats))
+        .route("/tickets/:id", get(get_ticket).patch(update_ticket))
+        .route("/tickets/:id/verify", post(verify_ticket))
+        .route("/tickets/:id/golden/accept", post(accept_golden))
+        .route("/tickets/:id/raw", get(get_raw_ticket).put(put_raw_ticket))
+        .route("/tickets/:id/claim", post(claim_ticket))
+        .route("/tickets/:id/heartbeat", post(heartbeat_ticket))
+        .route("/tickets/:id/release", post(release_ticket))
+        .route("/tickets/:id/comments", get(list_comments).post(add_comment))
+        .route("/tickets/:id/block", post(block_ticket))
+        .route("/tickets/:id/unblock", post(unblock_ticket))
+        .route("/events", get(stream_events))
+        .route("/context/:id", get(get_context))
+        .route("/context/:id/write", post(write_ticket_context))
+        .route("/runs/diff", get(diff_runs))
+        .route("/runs/:id/cancel", post(cancel_run))
+        // Room for the multipart framing on top of the largest file `[assets]` allows
+        .route("/assets", post(upload_asset).layer(DefaultBodyLimit::max(upload_limit + 64 * 1024)).get(list_assets))
+        .route("/assets/:name", delete(delete_asset).patch(rename_asset))
+}
+
+/// State for one workspace, with its background GC and lease expiry started.
+async fn build_state(workspace_root: PathBuf, base_path: String, config: Option<PlanConfig>) -> anyhow::Result<Arc<AppState>> {
     let assets_dir = workspace_root.join("assets");
     if !assets_dir.exists() {
         fs::create_dir_all(&assets_dir).await?;
@@ -36,40 +219,129 @@ pub async fn create_app(workspace_root: PathBuf) -> anyhow::Result<Router> {
         fs::create_dir_all(&artifacts_dir).await?;
     }
 
+    let config = match config {
+        Some(config) => config,
+        None => PlanConfig::load(&workspace_root)?,
+    };
+    let graph = WarmGraph::start(workspace_root.clone(), config.context.include_paths.clone());
+
     let state = Arc::new(AppState {
         workspace_root: workspace_root.clone(),
+        config,
+        metrics: Arc::new(ServerMetrics::new()),
+        leases: Arc::new(Mutex::new(LeaseTable::load(&workspace_root)?)),
+        ticket_writes: Arc::new(Mutex::new(())),
+        graph,
+        base_path,
+        workers: Arc::new(Mutex::new(WorkerRegistry::default())),
+        events: events::follow(&workspace_root),
     });
 
-    let cors = CorsLayer::new()
-        .allow_origin(tower_http::cors::Any) // For dev purposes, allows frontend dev server
-        .allow_methods(tower_http::cors::Any)
-        .allow_headers(tower_http::cors::Any);
+    if !state.config.webhooks.is_empty() {
+        webhooks::spawn(state.config.webhooks.clone(), state.events.subscribe());
+    }
 
-    let app = Router::new()
-        .route("/api/tickets", get(list_tickets))
-        .route("/api/tickets/:id", get(get_ticket).patch(update_ticket))
-        .route("/api/tickets/:id/verify", post(verify_ticket))
-        .route("/api/assets", post(upload_asset).get(list_assets))
-        .nest_service("/artifacts", ServeDir::new(workspace_root.join("target/public/artifacts")))
-        .nest_service("/assets", ServeDir::new(workspace_root.join("assets")))
-        // SPA Fallback for everything else to dist/
-        .fallback_service(ServeDir::new(workspace_root.join("apps/director-plan/dist")).fallback(ServeFile::new(workspace_root.join("apps/director-plan/dist/index.html"))))
-        .layer(cors)
-        .layer(DefaultBodyLimit::max(10 * 1024 * 1024)) // 10MB limit for uploads
-        .with_state(state);
+    if let Some(hours) = state.config.retention.gc_interval_hours {
+        let state = state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(hours.max(1) * 60 * 60));
+            loop {
+                interval.tick().await;
+                let root = state.workspace_root.clone();
+                let retention = state.config.retention.clone();
+                match tokio::task::spawn_blocking(move || gc::collect(&root, &retention, false)).await {
+                    Ok(Ok(report)) => info!("Garbage collection removed {} files ({} bytes)", report.removed.len(), report.bytes_freed),
+                    Ok(Err(e)) => error!("Garbage collection failed: {}", e),
+                    Err(e) => error!("Garbage collection task failed: {}", e),
+                }
+            }
+        });
+    }
+
+    // Create tickets from `meta.schedule` templates as their periods come round
+    {
+        let state = state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+            loop {
+                interval.tick().await;
+                let state = state.clone();
+                let due = tokio::task::spawn_blocking(move || {
+                    let _guard = state.ticket_writes.lock().unwrap();
+                    schedule::run_due(&state.workspace_root, chrono::Utc::now())
+                });
+                match due.await {
+                    Ok(Ok(created)) if !created.is_empty() => info!("Created scheduled tickets: {}", created.join(", ")),
+                    Ok(Ok(_)) => {}
+                    Ok(Err(e)) => error!("Scheduler failed: {}", e),
+                    Err(e) => error!("Scheduler task failed: {}", e),
+                }
+            }
+        });
+    }
 
-    Ok(app)
+    // Return tickets to todo when the worker holding them stops heartbeating
+    {
+        let state = state.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(5));
+            loop {
+                interval.tick().await;
+                if let Err(e) = expire_leases(&state) {
+                    error!("Failed to expire leases: {}", e);
+                }
+            }
+        });
+    }
+
+    Ok(state)
 }
 
-pub async fn start_server(workspace_root: PathBuf) -> anyhow::Result<()> {
+pub async fn start_server(workspace_root: PathBuf, options: ServeOptions) -> anyhow::Result<()> {
     // tracing_subscriber is initialized in main now
-    let app = create_app(workspace_root).await?;
+    let grace = match &options.config {
+        Some(config) => config.server.shutdown_grace_secs,
+        None => PlanConfig::load(&workspace_root)?.server.shutdown_grace_secs,
+    };
+    // Certificate problems should stop startup, not the first handshake
+    let tls = match &options.tls {
+        Some((cert, key)) => Some(tls::load_config(cert, key)?),
+        None => None,
+    };
+    let app = create_app_with(workspace_root, &options).await?;
 
     let addr = SocketAddr::from(([0, 0, 0, 0], 3000));
-    info!("Listening on {}", addr);
+    let scheme = if tls.is_some() { "https" } else { "http" };
+    info!("Listening on {}://{}{}/", scheme, addr, proxy::normalize_base_path(&options.base_path)?);
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+
+    // Stop accepting connections on SIGINT/SIGTERM and let in-flight requests finish,
+    // but don't wait on a hung verification forever.
+    let shutdown = Shutdown::listen();
+    let server = {
+        let shutdown = shutdown.clone();
+        async move {
+            match tls {
+                Some(tls) => tls::serve(listener, app, tls, shutdown).await,
+                // Client addresses are needed for per-IP rate limiting
+                None => axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+                    .with_graceful_shutdown(async move { shutdown.wait().await })
+                    .await?,
+            }
+            anyhow::Ok(())
+        }
+    };
+    let deadline = async {
+        shutdown.wait().await;
+        info!("Shutdown requested, draining in-flight requests (up to {}s)", grace);
+        tokio::time::sleep(std::time::Duration::from_secs(grace)).await;
+    };
+
+    tokio::select! {
+        res = server => res?,
+        _ = deadline => warn!("Grace period elapsed with requests still in flight; exiting"),
+    }
 
     Ok(())
 }
@@ -83,16 +355,153 @@ async fn enrich_ticket_artifacts(ticket: &mut FrontendTicket, state: &AppState)
         let actual = artifacts_dir.join("actual.png");
         let diff = artifacts_dir.join("diff.png");
 
+        // Named snapshots live in `<name>/golden.png` + `<name>/actual.png`
+        let mut snapshots = Vec::new();
+        if let Ok(mut entries) = fs::read_dir(&artifacts_dir).await {
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let dir = entry.path();
+                if dir.join("golden.png").exists() && dir.join("actual.png").exists() {
+                    snapshots.push(SnapshotArtifacts {
+                        before_image: state.url(&format!("/artifacts/{}/{}/golden.png", ticket.id, name)),
+                        after_image: state.url(&format!("/artifacts/{}/{}/actual.png", ticket.id, name)),
+                        name,
+                    });
+                }
+            }
+        }
+        snapshots.sort_by(|a, b| a.name.cmp(&b.name));
+
         if golden.exists() && actual.exists() {
             ticket.artifacts = Some(Artifacts {
-                before_image: format!("/artifacts/{}/golden.png", ticket.id),
-                after_image: format!("/artifacts/{}/actual.png", ticket.id),
-                diff_image: if diff.exists() { Some(format!("/artifacts/{}/diff.png", ticket.id)) } else { None },
+                before_image: state.url(&format!("/artifacts/{}/golden.png", ticket.id)),
+                after_image: state.url(&format!("/artifacts/{}/actual.png", ticket.id)),
+                diff_image: if diff.exists() { Some(state.url(&format!("/artifacts/{}/diff.png", ticket.id))) } else { None },
+                snapshots,
+            });
+        } else if let Some(first) = snapshots.first() {
+            ticket.artifacts = Some(Artifacts {
+                before_image: first.before_image.clone(),
+                after_image: first.after_image.clone(),
+                diff_image: None,
+                snapshots,
             });
         }
     }
 }
 
+/// Converts a ticket into the frontend DTO, filling in server-side derived fields.
+async fn hydrate_ticket(ticket: Ticket, state: &AppState) -> FrontendTicket {
+    let id = ticket.meta.id.clone();
+    let mut ft = hydrate_ticket_with(ticket, state, Expand::ALL).await;
+    if let Ok(all) = load_all_tickets(state, false).await {
+        ft.blocked_by = blocking::waiting_on(&all, &id);
+    }
+    ft
+}
+
+/// Which of the costly parts of a [`FrontendTicket`] to fill in. Without them it's a summary.
+#[derive(Debug, Clone, Copy, Default)]
+struct Expand {
+    history: bool,
+    metrics: bool,
+    artifacts: bool,
+}
+
+impl Expand {
+    const ALL: Expand = Expand { history: true, metrics: true, artifacts: true };
+
+    /// Parses `?expand=history,metrics,artifacts`.
+    fn parse(value: Option<&str>) -> Result<Self, AppError> {
+        let mut expand = Expand::default();
+        for part in value.unwrap_or_default().split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            match part {
+                "history" => expand.history = true,
+                "metrics" => expand.metrics = true,
+                "artifacts" => expand.artifacts = true,
+                other => return Err(AppError(
+                    anyhow::anyhow!("Unknown expand '{}', expected history, metrics or artifacts", other),
+                    StatusCode::BAD_REQUEST,
+                )),
+            }
+        }
+        Ok(expand)
+    }
+}
+
+async fn hydrate_ticket_with(ticket: Ticket, state: &AppState, expand: Expand) -> FrontendTicket {
+    let due_state = sla::due_state(&ticket, sla::today(), state.config.sla.due_soon_days);
+    let metrics = expand.metrics.then(|| ticket_metrics(&ticket, state));
+    let mut ft = FrontendTicket::from(ticket);
+    ft.due_state = due_state.map(|s| s.as_str().to_string());
+    ft.metrics = metrics;
+    if !expand.history {
+        ft.logs = None;
+    }
+    if expand.artifacts {
+        enrich_ticket_artifacts(&mut ft, state).await;
+    }
+    ft
+}
+
+fn ticket_metrics(ticket: &Ticket, state: &AppState) -> Metrics {
+    let runs = RunStore::new(&state.workspace_root)
+        .list_for_ticket(&ticket.meta.id)
+        .unwrap_or_default();
+
+    // Latest two perf samples, newest first, for the render time and its trend
+    let samples: Vec<_> = runs.iter().rev()
+        .flat_map(|r| r.attempts.iter().rev())
+        .filter_map(|a| a.perf.as_ref())
+        .take(2)
+        .collect();
+    let render_time_ms = samples.first().and_then(|s| s.lcp_ms.or(s.load_ms));
+    let render_time_diff = match (render_time_ms, samples.get(1).and_then(|s| s.lcp_ms.or(s.load_ms))) {
+        (Some(latest), Some(previous)) => Some(format!("{:+.1}", latest - previous)),
+        _ => None,
+    };
+
+    Metrics {
+        render_time_ms,
+        render_time_diff,
+        bundle_kb: samples.first().and_then(|s| s.bundle_kb),
+        time_in_status_secs: timing::time_in_status(ticket, chrono::Utc::now()),
+        agent_time_ms: runs.iter().map(|r| r.agent_ms()).sum(),
+        verification_time_ms: runs.iter().map(|r| r.verification_ms()).sum(),
+        executions: runs.len(),
+        agent_result: runs.iter().rev().find_map(|r| r.latest_result()).cloned(),
+    }
+}
+
+/// Drops expired leases and hands their tickets back to `todo`.
+fn expire_leases(state: &AppState) -> anyhow::Result<()> {
+    let expired = {
+        let mut leases = state.leases.lock().unwrap();
+        let expired = leases.take_expired(chrono::Utc::now());
+        if !expired.is_empty() {
+            leases.save()?;
+        }
+        expired
+    };
+
+    let store = TicketStore::new(&state.workspace_root);
+    for lease in expired {
+        if !store.exists(&lease.ticket_id) {
+            continue;
+        }
+        if store.load(&lease.ticket_id)?.meta.status != Status::InProgress {
+            continue;
+        }
+        info!("Lease on {} held by {} expired", lease.ticket_id, lease.worker_id);
+        store.update(&lease.ticket_id, |doc| {
+            set_status_in_document(doc, &Status::Todo);
+            crate::append_history_entry(doc, &format!("Lease held by worker {} expired; returned to todo.", lease.worker_id));
+            Ok(())
+        })?;
+    }
+    Ok(())
+}
+
 fn validate_id(id: &str) -> Result<(), AppError> {
     if !id.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
         return Err(AppError(anyhow::anyhow!("Invalid ID format"), StatusCode::BAD_REQUEST));
@@ -104,16 +513,23 @@ fn validate_id(id: &str) -> Result<(), AppError> {
     Ok(())
 }
 
+/// The ticket's file in whichever format it is written, and that format.
+fn ticket_file(state: &AppState, id: &str) -> (std::path::PathBuf, TicketFormat) {
+    let path = find_ticket_file(&state.workspace_root.join("plan/tickets"), id);
+    let format = TicketFormat::from_path(&path).unwrap_or(TicketFormat::Toml);
+    (path, format)
+}
+
 async fn load_ticket_with_history(state: &AppState, id: &str) -> Result<Ticket, AppError> {
-    let ticket_path = state.workspace_root.join(format!("plan/tickets/{}.toml", id));
+    let (ticket_path, format) = ticket_file(state, id);
 
     if !ticket_path.exists() {
         return Err(AppError(anyhow::anyhow!("Ticket not found"), StatusCode::NOT_FOUND));
     }
 
     let content = fs::read_to_string(&ticket_path).await?;
-    let mut ticket: Ticket = toml_edit::de::from_str(&content)
-        .map_err(|e| anyhow::anyhow!("Failed to parse ticket: {}", e))?;
+    let mut ticket: Ticket = format.parse_ticket(&content)
+        .map_err(|e| anyhow::anyhow!("Failed to parse ticket: {:#}", e))?;
 
     // Load history
     let history_path = state.workspace_root.join(format!("plan/history/{}.log", id));
@@ -126,10 +542,15 @@ async fn load_ticket_with_history(state: &AppState, id: &str) -> Result<Ticket,
     Ok(ticket)
 }
 
-// --- Handlers ---
+/// 409 if `status` would start or close a ticket still waiting on its children or dependencies.
+async fn check_not_blocked(state: &AppState, id: &str, status: &Status) -> Result<(), AppError> {
+    let all = load_all_tickets(state, false).await?;
+    blocking::check_transition(&all, id, status).map_err(|e| AppError(e, StatusCode::CONFLICT))
+}
 
-#[tracing::instrument(skip(state))]
-async fn list_tickets(State(state): State<Arc<AppState>>) -> Result<Json<Vec<FrontendTicket>>, AppError> {
+/// Loads every parseable ticket from `plan/tickets`, sorted by ID. `with_history` also reads
+/// each ticket's `plan/history` log.
+async fn load_all_tickets(state: &AppState, with_history: bool) -> Result<Vec<Ticket>, AppError> {
     let tickets_dir = state.workspace_root.join("plan/tickets");
     let mut tickets = Vec::new();
 
@@ -137,14 +558,14 @@ async fn list_tickets(State(state): State<Arc<AppState>>) -> Result<Json<Vec<Fro
         let mut entries = fs::read_dir(tickets_dir).await?;
         while let Some(entry) = entries.next_entry().await? {
             let path = entry.path();
-            if path.extension().map_or(false, |e| e == "toml") {
+            if let Some(format) = TicketFormat::from_path(&path) {
                 let content = fs::read_to_string(&path).await?;
                 // Parse leniently or log errors
-                match toml_edit::de::from_str::<Ticket>(&content) {
+                match format.parse_ticket(&content) {
                     Ok(mut ticket) => {
                          // Load history
                          // Sanitize ticket ID from file content just in case, though file system list is safe-ish
-                        if validate_id(&ticket.meta.id).is_ok() {
+                        if with_history && validate_id(&ticket.meta.id).is_ok() {
                             let history_path = state.workspace_root.join(format!("plan/history/{}.log", ticket.meta.id));
                             if history_path.exists() {
                                 if let Ok(history_content) = fs::read_to_string(&history_path).await {
@@ -152,9 +573,7 @@ async fn list_tickets(State(state): State<Arc<AppState>>) -> Result<Json<Vec<Fro
                                 }
                             }
                         }
-                        let mut ft = FrontendTicket::from(ticket);
-                        enrich_ticket_artifacts(&mut ft, &state).await;
-                        tickets.push(ft);
+                        tickets.push(ticket);
                     },
                     Err(e) => error!("Failed to parse ticket {:?}: {}", path, e),
                 }
@@ -163,11 +582,253 @@ async fn list_tickets(State(state): State<Arc<AppState>>) -> Result<Json<Vec<Fro
     }
 
     // Sort by ID
-    tickets.sort_by(|a, b| a.id.cmp(&b.id));
+    tickets.sort_by(|a, b| a.meta.id.cmp(&b.meta.id));
+
+    Ok(tickets)
+}
+
+// --- Handlers ---
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ListTicketsQuery {
+    /// Comma-separated statuses, e.g. `todo,in_progress`
+    status: Option<String>,
+    /// Comma-separated owners; `unassigned` matches tickets without one
+    owner: Option<String>,
+    /// Comma-separated priorities, e.g. `high,critical`
+    priority: Option<String>,
+    /// `id` (the default), `title`, `status`, `priority` or `due_date` (undated first); prefix with `-` to reverse
+    sort: Option<String>,
+    /// 1-based page number. Without `page` or `per_page` every match is returned.
+    page: Option<usize>,
+    /// Page size, at most 500. Defaults to 50 when `page` is given.
+    per_page: Option<usize>,
+    /// Comma-separated extras: `history`, `metrics`, `artifacts`. Without them tickets are summaries.
+    expand: Option<String>,
+}
+
+const DEFAULT_PER_PAGE: usize = 50;
+const MAX_PER_PAGE: usize = 500;
+
+/// Splits a comma-separated filter into its values; `None` means no filter.
+fn filter_values(value: Option<&str>) -> Option<Vec<String>> {
+    let values: Vec<String> = value?.split(',').map(|v| v.trim().to_lowercase()).filter(|v| !v.is_empty()).collect();
+    (!values.is_empty()).then_some(values)
+}
 
-    Ok(Json(tickets))
+fn priority_name(priority: &Priority) -> String {
+    format!("{:?}", priority).to_lowercase()
+}
+
+/// Filters and sorts tickets per `query`. Returns the matches, before pagination.
+fn select_tickets(mut tickets: Vec<Ticket>, query: &ListTicketsQuery) -> Result<Vec<Ticket>, AppError> {
+    if let Some(statuses) = filter_values(query.status.as_deref()) {
+        tickets.retain(|t| statuses.contains(&t.meta.status.to_string()));
+    }
+    if let Some(owners) = filter_values(query.owner.as_deref()) {
+        tickets.retain(|t| {
+            let owner = t.meta.owner.as_deref().unwrap_or("unassigned").to_lowercase();
+            owners.contains(&owner)
+        });
+    }
+    if let Some(priorities) = filter_values(query.priority.as_deref()) {
+        tickets.retain(|t| priorities.contains(&priority_name(&t.meta.priority)));
+    }
+
+    let sort = query.sort.as_deref().unwrap_or("id");
+    let (field, descending) = match sort.strip_prefix('-') {
+        Some(field) => (field, true),
+        None => (sort, false),
+    };
+    let compare: fn(&Ticket, &Ticket) -> Ordering = match field {
+        "id" => |a, b| a.meta.id.cmp(&b.meta.id),
+        "title" => |a, b| a.meta.title.to_lowercase().cmp(&b.meta.title.to_lowercase()),
+        "status" => |a, b| a.meta.status.to_string().cmp(&b.meta.status.to_string()),
+        "priority" => |a, b| a.meta.priority.cmp(&b.meta.priority),
+        "due_date" => |a, b| a.meta.due_date.as_ref().map(|d| d.to_string()).cmp(&b.meta.due_date.as_ref().map(|d| d.to_string())),
+        other => return Err(AppError(
+            anyhow::anyhow!("Unknown sort '{}', expected id, title, status, priority or due_date", other),
+            StatusCode::BAD_REQUEST,
+        )),
+    };
+    // Stable on ID order, so ties stay sorted by ID whichever way the field goes
+    tickets.sort_by(|a, b| if descending { compare(b, a) } else { compare(a, b) });
+    Ok(tickets)
+}
+
+/// Tickets matching the filters, sorted and optionally paginated. The total number of matches
+/// is in `X-Total-Count`; paginated responses also carry `X-Total-Pages`.
+#[utoipa::path(
+    get, path = "/api/tickets", tag = "tickets",
+    params(ListTicketsQuery),
+    responses(
+        (status = 200, description = "The matching tickets", body = Vec<FrontendTicket>,
+            headers(
+                ("x-total-count" = usize, description = "Matches before pagination"),
+                ("x-total-pages" = usize, description = "Only when paginated"),
+            )),
+        (status = 400, description = "Unknown sort or expand value", body = ErrorBody),
+    ),
+)]
+#[tracing::instrument(skip(state, query))]
+async fn list_tickets(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ListTicketsQuery>,
+) -> Result<Response, AppError> {
+    let expand = Expand::parse(query.expand.as_deref())?;
+    let all = load_all_tickets(&state, expand.history).await?;
+    let mut blocked = blocking::blocked_map(&all);
+    let matching = select_tickets(all, &query)?;
+    let total = matching.len();
+
+    let mut headers = vec![(HeaderName::from_static("x-total-count"), total.to_string())];
+    let page: Vec<Ticket> = if query.page.is_some() || query.per_page.is_some() {
+        let per_page = query.per_page.unwrap_or(DEFAULT_PER_PAGE).clamp(1, MAX_PER_PAGE);
+        let page = query.page.unwrap_or(1).max(1);
+        headers.push((HeaderName::from_static("x-total-pages"), total.div_ceil(per_page).to_string()));
+        matching.into_iter().skip((page - 1).saturating_mul(per_page)).take(per_page).collect()
+    } else {
+        matching
+    };
+
+    let mut tickets = Vec::new();
+    for ticket in page {
+        let blocked_by = blocked.remove(&ticket.meta.id).unwrap_or_default();
+        let mut ft = hydrate_ticket_with(ticket, &state, expand).await;
+        ft.blocked_by = blocked_by;
+        tickets.push(ft);
+    }
+
+    let mut response = Json(tickets).into_response();
+    for (name, value) in headers {
+        response.headers_mut().insert(name, HeaderValue::from_str(&value)?);
+    }
+    Ok(response)
+}
+
+/// The `[[workspaces]]` registry: names and paths of the sub-plans served under `/api/workspaces/:ws`.
+#[utoipa::path(
+    get, path = "/api/workspaces", tag = "workspaces",
+    responses((status = 200, description = "`[{ name, path }]` for each `[[workspaces]]` entry", body = serde_json::Value)),
+)]
+async fn list_workspaces(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    let workspaces: Vec<serde_json::Value> = state.config.workspaces.iter()
+        .map(|w| json!({ "name": w.name, "path": w.path }))
+        .collect();
+    Json(json!(workspaces))
+}
+
+/// Workers heard from in the last hour, with what they are doing. `alive` is false once a worker
+/// has missed three heartbeats.
+#[utoipa::path(
+    get, path = "/api/workers", tag = "workers",
+    responses((status = 200, body = Vec<WorkerStatus>)),
+)]
+async fn list_workers(State(state): State<Arc<AppState>>) -> Json<Vec<WorkerStatus>> {
+    let mut workers = state.workers.lock().unwrap();
+    Json(workers.list(state.config.worker.heartbeat_secs, chrono::Utc::now()))
+}
+
+/// Registers or refreshes a worker. Unlike the lease heartbeat, this is sent whether or not
+/// the worker holds a ticket.
+#[utoipa::path(
+    post, path = "/api/workers/heartbeat", tag = "workers",
+    request_body = WorkerHeartbeat,
+    responses(
+        (status = 200, body = WorkerStatus),
+        (status = 400, description = "Missing worker ID", body = ErrorBody),
+    ),
+)]
+#[tracing::instrument(skip(state, heartbeat))]
+async fn worker_heartbeat(
+    State(state): State<Arc<AppState>>,
+    Json(heartbeat): Json<WorkerHeartbeat>,
+) -> Result<Json<WorkerStatus>, AppError> {
+    if heartbeat.id.trim().is_empty() {
+        return Err(AppError(anyhow::anyhow!("Worker ID is required"), StatusCode::BAD_REQUEST));
+    }
+    let now = chrono::Utc::now();
+    state.workers.lock().unwrap().record(heartbeat.clone(), now);
+    Ok(Json(WorkerStatus { heartbeat, last_seen: now, alive: true }))
+}
+
+#[utoipa::path(
+    get, path = "/api/stats", tag = "tickets",
+    responses((status = 200, description = "Counts by status, time tracking, executions, pause state, disk usage and SLA state", body = serde_json::Value)),
+)]
+#[tracing::instrument(skip(state))]
+async fn get_stats(State(state): State<Arc<AppState>>) -> Result<Json<serde_json::Value>, AppError> {
+    let tickets = load_all_tickets(&state, false).await?;
+    let today = sla::today();
+
+    let mut by_status: BTreeMap<String, usize> = BTreeMap::new();
+    let mut overdue = Vec::new();
+    let mut due_soon = Vec::new();
+
+    for ticket in &tickets {
+        *by_status.entry(ticket.meta.status.to_string()).or_insert(0) += 1;
+        match sla::due_state(ticket, today, state.config.sla.due_soon_days) {
+            Some(DueState::Overdue) => overdue.push(ticket.meta.id.clone()),
+            Some(DueState::DueSoon) => due_soon.push(ticket.meta.id.clone()),
+            _ => {}
+        }
+    }
+
+    // Time tracking: where tickets spend their time, and agent vs. human throughput
+    let now = chrono::Utc::now();
+    let mut status_secs: BTreeMap<String, i64> = BTreeMap::new();
+    let mut throughput: BTreeMap<String, (usize, i64)> = BTreeMap::new();
+    for ticket in &tickets {
+        for (status, secs) in timing::time_in_status(ticket, now) {
+            *status_secs.entry(status).or_insert(0) += secs;
+        }
+        if let Some(cycle) = timing::cycle_time_secs(ticket) {
+            let owner = ticket.meta.owner.clone().unwrap_or_else(|| "unassigned".to_string());
+            let entry = throughput.entry(owner).or_insert((0, 0));
+            entry.0 += 1;
+            entry.1 += cycle;
+        }
+    }
+    let throughput: BTreeMap<String, serde_json::Value> = throughput
+        .into_iter()
+        .map(|(owner, (completed, total))| (owner, json!({
+            "completed": completed,
+            "avg_cycle_secs": total / completed as i64,
+        })))
+        .collect();
+
+    let runs = RunStore::new(&state.workspace_root).list_all().unwrap_or_default();
+    let agent_time_ms: u64 = runs.iter().map(|r| r.agent_ms()).sum();
+    let verification_time_ms: u64 = runs.iter().map(|r| r.verification_ms()).sum();
+
+    Ok(Json(json!({
+        "total": tickets.len(),
+        "by_status": by_status,
+        "time_in_status_secs": status_secs,
+        "throughput_by_owner": throughput,
+        "executions": {
+            "runs": runs.len(),
+            "agent_time_ms": agent_time_ms,
+            "verification_time_ms": verification_time_ms,
+            "flakes": flaky::stats(&runs),
+        },
+        "paused": budget::paused(&state.workspace_root).map(|p| json!({ "reason": p.reason, "paused_at": p.paused_at })),
+        "disk_usage": gc::disk_usage(&state.workspace_root),
+        "sla": {
+            "overdue": overdue.len(),
+            "due_soon": due_soon.len(),
+            "overdue_ids": overdue,
+            "due_soon_ids": due_soon,
+        }
+    })))
 }
 
+#[utoipa::path(
+    get, path = "/api/tickets/{id}", tag = "tickets",
+    params(("id" = String, Path, description = "Ticket ID")),
+    responses((status = 200, body = FrontendTicket), (status = 400, description = "Invalid ID or request", body = ErrorBody), (status = 404, description = "No such ticket", body = ErrorBody)),
+)]
 #[tracing::instrument(skip(state))]
 async fn get_ticket(
     State(state): State<Arc<AppState>>,
@@ -175,17 +836,478 @@ async fn get_ticket(
 ) -> Result<Json<FrontendTicket>, AppError> {
     validate_id(&id)?;
     let ticket = load_ticket_with_history(&state, &id).await?;
-    let mut ft = FrontendTicket::from(ticket);
-    enrich_ticket_artifacts(&mut ft, &state).await;
-    Ok(Json(ft))
+    Ok(Json(hydrate_ticket(ticket, &state).await))
+}
+
+/// The ticket's context files with their contents, answered from the warm dependency graph.
+/// 503 while the graph is still being built after startup.
+#[utoipa::path(
+    get, path = "/api/context/{id}", tag = "context",
+    params(("id" = String, Path, description = "Ticket ID")),
+    responses(
+        (status = 200, description = "`{ id, title, files: [{ path, found, content }], referenced_assets, assets: [{ name, url }] }`", body = serde_json::Value),
+        (status = 400, description = "The ticket names a context profile that isn't defined", body = ErrorBody),
+        (status = 404, description = "No such ticket", body = ErrorBody),
+        (status = 503, description = "The dependency graph is still being built", body = ErrorBody),
+    ),
+)]
+#[tracing::instrument(skip(state))]
+async fn get_context(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    validate_id(&id)?;
+    let ticket = load_ticket_with_history(&state, &id).await?;
+
+    let profile = state.config.context.profile(&ticket)
+        .map_err(|e| AppError(e, StatusCode::BAD_REQUEST))?
+        .map(|(_, profile)| profile.clone())
+        .unwrap_or_default();
+    let files = {
+        let (ticket, graph, root) = (ticket.clone(), state.graph.clone(), state.workspace_root.clone());
+        tokio::task::spawn_blocking(move || graph.context_files(&ticket, &root, &profile)).await?
+    };
+    let (files, referenced) = files.ok_or_else(|| AppError(
+        anyhow::anyhow!("Dependency graph is still being built; try again shortly"),
+        StatusCode::SERVICE_UNAVAILABLE,
+    ))?;
+
+    let mut out = Vec::new();
+    for path in files {
+        match fs::read_to_string(state.workspace_root.join(&path)).await {
+            Ok(content) => out.push(json!({ "path": path, "found": true, "content": content })),
+            Err(_) => out.push(json!({ "path": path, "found": false })),
+        }
+    }
+
+    Ok(Json(json!({
+        "id": ticket.meta.id,
+        "title": ticket.meta.title,
+        "files": out,
+        "referenced_assets": referenced,
+        "assets": ticket.spec.assets.iter()
+            .map(|name| json!({ "name": name, "url": state.url(&format!("/assets/{}", name)) }))
+            .collect::<Vec<_>>(),
+    })))
+}
+
+/// Runs discovery once and writes the files into the ticket's `relevant_files`, with their
+/// depths as comments, for review before execution. Turns the ticket's `auto_context` off.
+#[utoipa::path(
+    post, path = "/api/context/{id}/write", tag = "context",
+    params(("id" = String, Path, description = "Ticket ID")),
+    responses(
+        (status = 200, description = "`{ id, relevant_files: [{ path, depth }] }`", body = serde_json::Value),
+        (status = 400, description = "The ticket names a context profile that isn't defined", body = ErrorBody),
+        (status = 404, description = "No such ticket", body = ErrorBody),
+    ),
+)]
+#[tracing::instrument(skip(state))]
+async fn write_ticket_context(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    validate_id(&id)?;
+    let ticket = load_ticket_with_history(&state, &id).await?;
+    let profile = state.config.context.profile(&ticket)
+        .map_err(|e| AppError(e, StatusCode::BAD_REQUEST))?
+        .map(|(_, profile)| profile.clone())
+        .unwrap_or_default();
+
+    let written = {
+        let state = state.clone();
+        tokio::task::spawn_blocking(move || {
+            let _guard = state.ticket_writes.lock().unwrap();
+            write_context(&ticket, &state.workspace_root, &profile, &state.config.context.include_paths)
+        }).await??
+    };
+    Ok(Json(json!({ "id": id, "relevant_files": written })))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct RunDiffQuery {
+    /// `<run-id>:<n>` for attempt `n`, or `<run-id>` for the run's last attempt
+    a: String,
+    /// The attempt to compare `a` with, named the same way
+    b: String,
+}
+
+/// What changed between two attempts: the prompt, verification errors and files changed.
+#[utoipa::path(
+    get, path = "/api/runs/diff", tag = "runs",
+    params(RunDiffQuery),
+    responses(
+        (status = 200, description = "`{ a, b, prompt, errors_removed, errors_added, files_removed, files_added, files_known }`; `prompt` is unified diff hunks", body = serde_json::Value),
+        (status = 400, description = "The run has no such attempt", body = ErrorBody),
+        (status = 404, description = "No such run", body = ErrorBody),
+    ),
+)]
+#[tracing::instrument(skip(state, query))]
+async fn diff_runs(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<RunDiffQuery>,
+) -> Result<Json<RunDiff>, AppError> {
+    let (a, b) = (AttemptRef::parse(&query.a), AttemptRef::parse(&query.b));
+    let (run_a, run_b) = {
+        let (root, a, b) = (state.workspace_root.clone(), a.run_id.clone(), b.run_id.clone());
+        tokio::task::spawn_blocking(move || -> anyhow::Result<_> {
+            let store = RunStore::new(&root);
+            Ok((store.find(&a)?, store.find(&b)?))
+        }).await??
+    };
+    let not_found = |reference: &AttemptRef| AppError(anyhow::anyhow!("No run {}", reference.run_id), StatusCode::NOT_FOUND);
+    let run_a = run_a.ok_or_else(|| not_found(&a))?;
+    let run_b = run_b.ok_or_else(|| not_found(&b))?;
+
+    run_diff::diff(&run_a, a.number, &run_b, b.number)
+        .map(Json)
+        .map_err(|e| AppError(e, StatusCode::BAD_REQUEST))
+}
+
+#[derive(Deserialize, ToSchema)]
+struct CancelPayload {
+    /// Recorded in the history, and in `meta.blocked_reason` if the ticket was in progress
+    reason: Option<String>,
+}
+
+/// Stops a running execution: the agent or verification process group is killed and its
+/// changes reverted. `id` is a run ID or the ID of the ticket being run. A worker on another
+/// machine loses its lease and abandons the run at its next heartbeat.
+#[utoipa::path(
+    post, path = "/api/runs/{id}/cancel", tag = "runs",
+    params(("id" = String, Path, description = "Run ID or ticket ID")),
+    request_body = CancelPayload,
+    responses(
+        (status = 200, body = Cancellation),
+        (status = 404, description = "No such run or ticket", body = ErrorBody),
+        (status = 409, description = "Nothing is running the ticket, or the run has finished", body = ErrorBody),
+    ),
+)]
+#[tracing::instrument(skip(state, payload))]
+async fn cancel_run(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(payload): Json<CancelPayload>,
+) -> Result<Json<Cancellation>, AppError> {
+    validate_id(&id)?;
+    expire_leases(&state)?;
+
+    let ticket_id = if TicketStore::new(&state.workspace_root).exists(&id) {
+        id
+    } else {
+        let (root, run_id) = (state.workspace_root.clone(), id.clone());
+        match tokio::task::spawn_blocking(move || RunStore::new(&root).find(&run_id)).await?? {
+            Some(run) if run.outcome == RunOutcome::Running => run.ticket_id,
+            Some(run) => return Err(AppError(anyhow::anyhow!("Run {} has already finished", run.run_id), StatusCode::CONFLICT)),
+            None => return Err(AppError(anyhow::anyhow!("No run or ticket {}", id), StatusCode::NOT_FOUND)),
+        }
+    };
+
+    let revoked = {
+        let mut leases = state.leases.lock().unwrap();
+        match leases.get(&ticket_id).map(|lease| lease.worker_id.clone()) {
+            Some(worker_id) => {
+                leases.release(&ticket_id, &worker_id);
+                leases.save()?;
+                Some(worker_id)
+            }
+            None => None,
+        }
+    };
+
+    let reason = payload.reason.filter(|r| !r.trim().is_empty());
+    let cancellation = cancel::cancel(&state.workspace_root, &ticket_id, reason.as_deref().map(str::trim), revoked)
+        .map_err(|e| {
+            let status = if e.is::<NotRunning>() { StatusCode::CONFLICT } else { StatusCode::INTERNAL_SERVER_ERROR };
+            AppError(e, status)
+        })?;
+    info!("Run on {} cancelled", ticket_id);
+    Ok(Json(cancellation))
+}
+
+/// Opaque version tag for a ticket file, used for `ETag`/`If-Match`.
+fn content_etag(content: &str) -> String {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    content.hash(&mut hasher);
+    format!("\"{:016x}\"", hasher.finish())
+}
+
+/// The ticket file exactly as stored, so remote workers can edit it with `toml_edit`
+/// and write it back without losing fields the JSON view doesn't carry. The Content-Type
+/// says whether the file is TOML, YAML or JSON.
+#[utoipa::path(
+    get, path = "/api/tickets/{id}/raw", tag = "tickets",
+    params(("id" = String, Path, description = "Ticket ID")),
+    responses(
+        (status = 200, description = "The ticket file", body = String, content_type = "application/toml",
+            headers(("etag" = String, description = "Send back as `If-Match` to make the next write conditional"))),
+        (status = 404, description = "No such ticket", body = ErrorBody),
+    ),
+)]
+#[tracing::instrument(skip(state))]
+async fn get_raw_ticket(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Response, AppError> {
+    validate_id(&id)?;
+
+    let (ticket_path, format) = ticket_file(&state, &id);
+    if !ticket_path.exists() {
+        return Err(AppError(anyhow::anyhow!("Ticket not found"), StatusCode::NOT_FOUND));
+    }
+
+    let content = fs::read_to_string(&ticket_path).await?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::ETAG, content_etag(&content)),
+        ],
+        content,
+    ).into_response())
+}
+
+/// Replaces the ticket file. The body must parse, in the file's format, as a ticket with a matching ID.
+/// When `If-Match` is sent, the write only happens if the file is unchanged, or with `*` if it
+/// exists (412 otherwise). A status change is refused with 409 if the ticket is waiting on its
+/// children or dependencies, as with `PATCH`. The body is written as sent, comments and all; a status change it doesn't record
+/// in `[[timing.transitions]]` yet is added.
+#[utoipa::path(
+    put, path = "/api/tickets/{id}/raw", tag = "tickets",
+    params(
+        ("id" = String, Path, description = "Ticket ID"),
+        ("If-Match" = Option<String>, Header, description = "ETag from the last read; the write fails with 412 if the ticket changed since"),
+    ),
+    request_body(content = String, content_type = "application/toml", description = "The complete ticket, in its file's format (TOML, YAML or JSON)"),
+    responses(
+        (status = 200, description = "`{ etag }` of the new content", body = serde_json::Value),
+        (status = 400, description = "Invalid ID or request, or unknown fields under `[server] strict_tickets`", body = ErrorBody),
+        (status = 409, description = "The new status would start or close a ticket that is still blocked", body = ErrorBody),
+        (status = 412, description = "The ticket changed since it was read", body = ErrorBody),
+    ),
+)]
+#[tracing::instrument(skip(state, headers, body))]
+async fn put_raw_ticket(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    headers: axum::http::HeaderMap,
+    body: String,
+) -> Result<Json<serde_json::Value>, AppError> {
+    validate_id(&id)?;
+
+    let (ticket_path, format) = ticket_file(&state, &id);
+    let mut doc = format.parse_document(&body)
+        .map_err(|e| AppError(anyhow::anyhow!("Invalid {:?}: {:#}", format, e), StatusCode::BAD_REQUEST))?;
+    let (ticket, unknown_fields) = format.parse_ticket_strict(&body)
+        .map_err(|e| AppError(anyhow::anyhow!("Invalid ticket: {:#}", e), StatusCode::BAD_REQUEST))?;
+    if state.config.server.strict_tickets && !unknown_fields.is_empty() {
+        return Err(AppError(
+            anyhow::anyhow!("Unknown ticket fields: {}", unknown_fields.join(", ")),
+            StatusCode::BAD_REQUEST,
+        ));
+    }
+    if ticket.meta.id != id {
+        return Err(AppError(
+            anyhow::anyhow!("Ticket ID {} does not match {}", ticket.meta.id, id),
+            StatusCode::BAD_REQUEST,
+        ));
+    }
+
+    // Checked before taking the write lock, which can't be held across an await
+    if let Ok(stored) = fs::read_to_string(&ticket_path).await
+        && let Ok(previous) = format.parse_ticket(&stored)
+        && previous.meta.status != ticket.meta.status
+    {
+        check_not_blocked(&state, &id, &ticket.meta.status).await?;
+    }
+
+    let if_match = headers.get(header::IF_MATCH).and_then(|v| v.to_str().ok());
+
+    let _guard = state.ticket_writes.lock().unwrap();
+    let current = std::fs::read_to_string(&ticket_path).unwrap_or_default();
+    let unchanged = |expected: &str| match expected {
+        "*" => ticket_path.exists(),
+        etag => content_etag(&current) == etag,
+    };
+    if let Some(expected) = if_match
+        && !unchanged(expected)
+    {
+        return Err(AppError(
+            anyhow::anyhow!("Ticket {} was modified since it was read", id),
+            StatusCode::PRECONDITION_FAILED,
+        ));
+    }
+    let mut content = body;
+    let mut change = None;
+    if let Ok(previous) = format.parse_ticket(&current)
+        && previous.meta.status != ticket.meta.status
+    {
+        record_status_change(&mut doc, &previous.meta.status, &ticket.meta.status);
+        content = format.render(&doc)?;
+        change = Some(PlanEvent::StatusChanged { id: id.clone(), from: previous.meta.status, to: ticket.meta.status.clone() });
+    }
+    std::fs::write(&ticket_path, &content)
+        .map_err(|e| anyhow::anyhow!("Failed to write ticket: {}", e))?;
+    if current.is_empty() {
+        change = Some(PlanEvent::TicketCreated { id: id.clone(), title: ticket.meta.title.clone() });
+    }
+    if let Some(event) = change {
+        events::emit(&state.workspace_root, event);
+    }
+
+    info!("Ticket {} replaced via raw API", id);
+    Ok(Json(json!({ "etag": content_etag(&content) })))
+}
+
+#[derive(Deserialize, ToSchema)]
+struct LeasePayload {
+    worker_id: String,
+    /// Lease length in seconds; defaults to 60.
+    ttl_secs: Option<i64>,
+}
+
+const DEFAULT_LEASE_TTL_SECS: i64 = 60;
+
+/// Atomically claims a `todo` ticket for a worker and moves it to `in_progress`.
+/// Answers 409 when another worker holds the lease or the ticket isn't claimable, and 503
+/// while executions are paused (`director-plan pause`).
+#[utoipa::path(
+    post, path = "/api/tickets/{id}/claim", tag = "leases",
+    params(("id" = String, Path, description = "Ticket ID")),
+    request_body = LeasePayload,
+    responses(
+        (status = 200, body = Lease),
+        (status = 404, description = "No such ticket", body = ErrorBody),
+        (status = 409, description = "Held by another worker, not todo, or blocked", body = ErrorBody),
+        (status = 503, description = "Executions are paused", body = ErrorBody),
+    ),
+)]
+#[tracing::instrument(skip(state, payload))]
+async fn claim_ticket(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(payload): Json<LeasePayload>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    validate_id(&id)?;
+    expire_leases(&state)?;
+
+    let store = TicketStore::new(&state.workspace_root);
+    if !store.exists(&id) {
+        return Err(AppError(anyhow::anyhow!("Ticket not found"), StatusCode::NOT_FOUND));
+    }
+
+    check_not_blocked(&state, &id, &Status::InProgress).await?;
+    if let Some(pause) = budget::paused(&state.workspace_root) {
+        return Err(AppError(anyhow::anyhow!("{}", pause), StatusCode::SERVICE_UNAVAILABLE));
+    }
+    let ttl = payload.ttl_secs.unwrap_or(DEFAULT_LEASE_TTL_SECS);
+    let mut leases = state.leases.lock().unwrap();
+
+    let ticket = store.load(&id)?;
+    let renewing = leases.get(&id).is_some_and(|l| l.worker_id == payload.worker_id);
+    if ticket.meta.status != Status::Todo && !renewing {
+        return Err(AppError(
+            anyhow::anyhow!("Ticket {} is {}, not todo", id, ticket.meta.status.to_string()),
+            StatusCode::CONFLICT,
+        ));
+    }
+
+    let lease = match leases.claim(&id, &payload.worker_id, ttl, chrono::Utc::now()) {
+        Claim::Granted(lease) => lease,
+        Claim::Held(holder) => {
+            return Err(AppError(
+                anyhow::anyhow!("Ticket {} is claimed by worker {}", id, holder.worker_id),
+                StatusCode::CONFLICT,
+            ));
+        }
+    };
+    leases.save()?;
+
+    if ticket.meta.status == Status::Todo {
+        store.update(&id, |doc| {
+            set_status_in_document(doc, &Status::InProgress);
+            crate::append_history_entry(doc, &format!("Claimed by worker {}.", payload.worker_id));
+            Ok(())
+        })?;
+    }
+
+    info!("Ticket {} claimed by {}", id, payload.worker_id);
+    Ok(Json(serde_json::to_value(lease)?))
 }
 
-#[derive(Deserialize)]
+/// Extends a lease. 409 means the worker has lost it and should abandon the run.
+#[utoipa::path(
+    post, path = "/api/tickets/{id}/heartbeat", tag = "leases",
+    params(("id" = String, Path, description = "Ticket ID")),
+    request_body = LeasePayload,
+    responses(
+        (status = 200, body = Lease),
+        (status = 409, description = "The worker no longer holds the lease", body = ErrorBody),
+    ),
+)]
+#[tracing::instrument(skip(state, payload))]
+async fn heartbeat_ticket(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(payload): Json<LeasePayload>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    validate_id(&id)?;
+
+    let ttl = payload.ttl_secs.unwrap_or(DEFAULT_LEASE_TTL_SECS);
+    let mut leases = state.leases.lock().unwrap();
+    match leases.renew(&id, &payload.worker_id, ttl, chrono::Utc::now()) {
+        Some(lease) => {
+            leases.save()?;
+            Ok(Json(serde_json::to_value(lease)?))
+        }
+        None => Err(AppError(
+            anyhow::anyhow!("Worker {} does not hold a lease on {}", payload.worker_id, id),
+            StatusCode::CONFLICT,
+        )),
+    }
+}
+
+#[utoipa::path(
+    post, path = "/api/tickets/{id}/release", tag = "leases",
+    params(("id" = String, Path, description = "Ticket ID")),
+    request_body = LeasePayload,
+    responses((status = 200, description = "`{ released }`: whether the worker held the lease", body = serde_json::Value)),
+)]
+#[tracing::instrument(skip(state, payload))]
+async fn release_ticket(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(payload): Json<LeasePayload>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    validate_id(&id)?;
+
+    let mut leases = state.leases.lock().unwrap();
+    let released = leases.release(&id, &payload.worker_id);
+    if released {
+        leases.save()?;
+    }
+    Ok(Json(json!({ "released": released })))
+}
+
+#[derive(Deserialize, ToSchema)]
 struct UpdateTicketPayload {
     status: Option<Status>,
     owner: Option<String>,
+    due_date: Option<String>,
 }
 
+#[utoipa::path(
+    patch, path = "/api/tickets/{id}", tag = "tickets",
+    params(("id" = String, Path, description = "Ticket ID")),
+    request_body = UpdateTicketPayload,
+    responses(
+        (status = 200, body = FrontendTicket),
+        (status = 400, description = "Invalid ID or request", body = ErrorBody),
+        (status = 404, description = "No such ticket", body = ErrorBody),
+        (status = 409, description = "The status would start or close a ticket still waiting on its children or dependencies", body = ErrorBody),
+    ),
+)]
 #[tracing::instrument(skip(state, payload))]
 async fn update_ticket(
     State(state): State<Arc<AppState>>,
@@ -194,34 +1316,260 @@ async fn update_ticket(
 ) -> Result<Json<FrontendTicket>, AppError> {
     validate_id(&id)?;
 
-    let ticket_path = state.workspace_root.join(format!("plan/tickets/{}.toml", id));
+    let (ticket_path, format) = ticket_file(&state, &id);
 
     if !ticket_path.exists() {
         return Err(AppError(anyhow::anyhow!("Ticket not found"), StatusCode::NOT_FOUND));
     }
 
     let content = fs::read_to_string(&ticket_path).await?;
-    let mut doc = content.parse::<toml_edit::DocumentMut>()
-        .map_err(|e| anyhow::anyhow!("Failed to parse TOML: {}", e))?;
+    let mut doc = format.parse_document(&content)
+        .map_err(|e| anyhow::anyhow!("Failed to parse ticket: {:#}", e))?;
 
     if let Some(status) = payload.status {
-        doc["meta"]["status"] = toml_edit::value(status.to_string());
+        check_not_blocked(&state, &id, &status).await?;
+        set_status_in_document(&mut doc, &status);
     }
 
     if let Some(owner) = payload.owner {
         doc["meta"]["owner"] = toml_edit::value(owner);
     }
 
-    fs::write(&ticket_path, doc.to_string()).await?;
+    if let Some(due_date) = payload.due_date {
+        let date: toml_datetime::Datetime = due_date.parse()
+            .map_err(|_| AppError(anyhow::anyhow!("Invalid due date '{}', expected YYYY-MM-DD", due_date), StatusCode::BAD_REQUEST))?;
+        doc["meta"]["due_date"] = toml_edit::value(date);
+    }
+
+    TicketStore::new(&state.workspace_root).save_document(&id, &doc)?;
 
     // Return the updated ticket using helper to ensure consistency
     let ticket = load_ticket_with_history(&state, &id).await?;
-    let mut ft = FrontendTicket::from(ticket);
-    enrich_ticket_artifacts(&mut ft, &state).await;
 
-    Ok(Json(ft))
+    Ok(Json(hydrate_ticket(ticket, &state).await))
 }
 
+/// The ticket's discussion, oldest first. Replies point at their parent with `reply_to`.
+#[utoipa::path(
+    get, path = "/api/tickets/{id}/comments", tag = "comments",
+    params(("id" = String, Path, description = "Ticket ID")),
+    responses(
+        (status = 200, body = Vec<Comment>),
+        (status = 404, description = "No such ticket", body = ErrorBody),
+    ),
+)]
+#[tracing::instrument(skip(state))]
+async fn list_comments(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<Comment>>, AppError> {
+    validate_id(&id)?;
+    if !TicketStore::new(&state.workspace_root).exists(&id) {
+        return Err(AppError(anyhow::anyhow!("Ticket not found"), StatusCode::NOT_FOUND));
+    }
+    Ok(Json(CommentStore::new(&state.workspace_root).list(&id)?))
+}
+
+#[derive(Deserialize, ToSchema)]
+struct CommentPayload {
+    author: String,
+    body: String,
+    /// ID of the comment this answers
+    reply_to: Option<u32>,
+}
+
+#[utoipa::path(
+    post, path = "/api/tickets/{id}/comments", tag = "comments",
+    params(("id" = String, Path, description = "Ticket ID")),
+    request_body = CommentPayload,
+    responses(
+        (status = 200, body = Comment),
+        (status = 400, description = "Empty author or body, or `reply_to` names no comment", body = ErrorBody),
+        (status = 404, description = "No such ticket", body = ErrorBody),
+    ),
+)]
+#[tracing::instrument(skip(state, payload))]
+async fn add_comment(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(payload): Json<CommentPayload>,
+) -> Result<Json<Comment>, AppError> {
+    validate_id(&id)?;
+    if !TicketStore::new(&state.workspace_root).exists(&id) {
+        return Err(AppError(anyhow::anyhow!("Ticket not found"), StatusCode::NOT_FOUND));
+    }
+    if payload.author.trim().is_empty() {
+        return Err(AppError(anyhow::anyhow!("A comment needs an author"), StatusCode::BAD_REQUEST));
+    }
+    // Comment IDs are handed out read-then-write
+    let _guard = state.ticket_writes.lock().unwrap();
+    let comment = CommentStore::new(&state.workspace_root)
+        .add(&id, &payload.author, &payload.body, payload.reply_to)
+        .map_err(|e| AppError(e, StatusCode::BAD_REQUEST))?;
+    Ok(Json(comment))
+}
+
+#[derive(Deserialize, ToSchema)]
+struct BlockPayload {
+    /// Recorded in `meta.blocked_reason` and the history when blocking; only in the history when unblocking
+    reason: Option<String>,
+}
+
+/// Plan events as Server-Sent Events, live from the event log whichever process wrote them.
+/// Each event's `event:` is its `type` and its `id:` a log offset: reconnecting with
+/// `Last-Event-ID` first replays what was missed (`0` replays the whole log).
+#[utoipa::path(
+    get, path = "/api/events", tag = "events",
+    params(("Last-Event-ID" = Option<String>, Header, description = "ID of the last event seen")),
+    responses((status = 200, content_type = "text/event-stream", description = "One `data:` line per event, holding an `EventRecord`", body = EventRecord)),
+)]
+async fn stream_events(
+    State(state): State<Arc<AppState>>,
+    headers: axum::http::HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    // Subscribe before reading the backlog so nothing falls between the two
+    let live = BroadcastStream::new(state.events.subscribe());
+    let backlog = match headers.get("last-event-id").and_then(|v| v.to_str().ok()) {
+        Some(id) => {
+            let offset: u64 = id.trim().parse()
+                .map_err(|_| AppError(anyhow::anyhow!("Invalid Last-Event-ID '{}'", id), StatusCode::BAD_REQUEST))?;
+            EventLog::new(&state.workspace_root).read_from(offset)?
+        }
+        None => Vec::new(),
+    };
+    let replayed = backlog.last().map_or(0, |e| e.offset);
+
+    let stream = tokio_stream::iter(backlog)
+        // A subscriber that falls too far behind skips ahead; the IDs show the gap
+        .chain(live.filter_map(|e| e.ok()).filter(move |e| e.offset > replayed))
+        .map(|e| Ok(Event::default()
+            .id(e.offset.to_string())
+            .event(e.record.event.kind())
+            .data(serde_json::to_string(&e.record).unwrap_or_default())));
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+/// Parks a ticket in `blocked`, out of the worker's reach, until it is unblocked.
+#[utoipa::path(
+    post, path = "/api/tickets/{id}/block", tag = "tickets",
+    params(("id" = String, Path, description = "Ticket ID")),
+    request_body = BlockPayload,
+    responses(
+        (status = 200, body = FrontendTicket),
+        (status = 400, description = "No reason given, or the ticket is closed", body = ErrorBody),
+        (status = 404, description = "No such ticket", body = ErrorBody),
+    ),
+)]
+#[tracing::instrument(skip(state, payload))]
+async fn block_ticket(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(payload): Json<BlockPayload>,
+) -> Result<Json<FrontendTicket>, AppError> {
+    validate_id(&id)?;
+    let store = TicketStore::new(&state.workspace_root);
+    if !store.exists(&id) {
+        return Err(AppError(anyhow::anyhow!("Ticket not found"), StatusCode::NOT_FOUND));
+    }
+    let reason = payload.reason.filter(|r| !r.trim().is_empty())
+        .ok_or_else(|| AppError(anyhow::anyhow!("A reason is required to block a ticket"), StatusCode::BAD_REQUEST))?;
+    store.block(&id, reason.trim()).map_err(|e| AppError(e, StatusCode::BAD_REQUEST))?;
+
+    let ticket = load_ticket_with_history(&state, &id).await?;
+    Ok(Json(hydrate_ticket(ticket, &state).await))
+}
+
+/// Returns a blocked ticket to the status it was blocked from (`todo` if it was in progress).
+#[utoipa::path(
+    post, path = "/api/tickets/{id}/unblock", tag = "tickets",
+    params(("id" = String, Path, description = "Ticket ID")),
+    request_body = BlockPayload,
+    responses(
+        (status = 200, body = FrontendTicket),
+        (status = 404, description = "No such ticket", body = ErrorBody),
+        (status = 409, description = "The ticket isn't blocked", body = ErrorBody),
+    ),
+)]
+#[tracing::instrument(skip(state, payload))]
+async fn unblock_ticket(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(payload): Json<BlockPayload>,
+) -> Result<Json<FrontendTicket>, AppError> {
+    validate_id(&id)?;
+    let store = TicketStore::new(&state.workspace_root);
+    if !store.exists(&id) {
+        return Err(AppError(anyhow::anyhow!("Ticket not found"), StatusCode::NOT_FOUND));
+    }
+    let reason = payload.reason.filter(|r| !r.trim().is_empty());
+    store.unblock(&id, reason.as_deref().map(str::trim)).map_err(|e| AppError(e, StatusCode::CONFLICT))?;
+
+    let ticket = load_ticket_with_history(&state, &id).await?;
+    Ok(Json(hydrate_ticket(ticket, &state).await))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct AcceptGoldenQuery {
+    /// Only promote this `[[verification.visual]]` entry
+    snapshot: Option<String>,
+}
+
+/// Promotes the latest screenshots under `proof/` to the ticket's golden images.
+#[utoipa::path(
+    post, path = "/api/tickets/{id}/golden/accept", tag = "verification",
+    params(("id" = String, Path, description = "Ticket ID"), AcceptGoldenQuery),
+    responses(
+        (status = 200, description = "`{ accepted }`: the goldens that were replaced", body = serde_json::Value),
+        (status = 400, description = "Invalid ID or request", body = ErrorBody),
+        (status = 404, description = "No such ticket", body = ErrorBody),
+    ),
+)]
+#[tracing::instrument(skip(state, query))]
+async fn accept_golden(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(query): Query<AcceptGoldenQuery>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    validate_id(&id)?;
+
+    if !TicketStore::new(&state.workspace_root).exists(&id) {
+        return Err(AppError(anyhow::anyhow!("Ticket not found"), StatusCode::NOT_FOUND));
+    }
+
+    let promotions = {
+        let _guard = state.ticket_writes.lock().unwrap();
+        golden::accept(&state.workspace_root, &id, query.snapshot.as_deref())
+            .map_err(|e| AppError(e, StatusCode::BAD_REQUEST))?
+    };
+
+    // Keep the served "before" images in step with the new baselines
+    let artifact_dir = state.workspace_root.join(format!("target/public/artifacts/{}", id));
+    for promotion in &promotions {
+        let dir = match &promotion.name {
+            Some(name) => artifact_dir.join(name),
+            None => artifact_dir.clone(),
+        };
+        if fs::create_dir_all(&dir).await.is_ok()
+            && let Err(e) = fs::copy(state.workspace_root.join(&promotion.golden), dir.join("golden.png")).await
+        {
+            warn!("Failed to refresh golden artifact for {}: {}", id, e);
+        }
+    }
+
+    info!("Accepted {} golden image(s) for {}", promotions.len(), id);
+    Ok(Json(json!({ "accepted": promotions })))
+}
+
+#[utoipa::path(
+    post, path = "/api/tickets/{id}/verify", tag = "verification",
+    params(("id" = String, Path, description = "Ticket ID")),
+    responses(
+        (status = 200, description = "`{ success, stdout, stderr, steps, artifacts_path, snapshots, visual_report }`. With `[[verification.steps]]`, `stdout` has every step's output and `steps` each step's result.", body = serde_json::Value),
+        (status = 404, description = "No such ticket", body = ErrorBody),
+        (status = 403, description = "The verification command is not allowed by `[policy]`", body = ErrorBody),
+    ),
+)]
 #[tracing::instrument(skip(state))]
 async fn verify_ticket(
     State(state): State<Arc<AppState>>,
@@ -234,34 +1582,51 @@ async fn verify_ticket(
     // load_ticket_with_history is fine.
     let ticket = load_ticket_with_history(&state, &id).await?;
 
-    let command_str = &ticket.verification.command;
-    let parts: Vec<&str> = command_str.split_whitespace().collect();
+    let (success, stdout, stderr, steps) = if ticket.verification.steps.is_empty() {
+        let command_str = &ticket.verification.command;
+        let parts: Vec<&str> = command_str.split_whitespace().collect();
 
-    if parts.is_empty() {
-        return Err(AppError(anyhow::anyhow!("Empty verification command"), StatusCode::BAD_REQUEST));
-    }
+        if parts.is_empty() {
+            return Err(AppError(anyhow::anyhow!("Empty verification command"), StatusCode::BAD_REQUEST));
+        }
 
-    info!("Running verification for {}: {}", id, command_str);
+        info!("Running verification for {}: {}", id, command_str);
 
-    let output = if cfg!(target_os = "windows") {
-        Command::new("powershell")
-            .args(["-Command", command_str])
-            .current_dir(&state.workspace_root)
+        let mut command = policy::build_command(&state.config.policy, command_str, &state.workspace_root)
+            .map_err(|e| {
+                let status = if e.is::<PolicyViolation>() { StatusCode::FORBIDDEN } else { StatusCode::INTERNAL_SERVER_ERROR };
+                AppError(e, status)
+            })?;
+        let env = ResolvedEnv::resolve(&state.workspace_root, &ticket.verification.env)?;
+        command.envs(env.vars());
+
+        let started = Instant::now();
+        let output = Command::from(command)
+            .kill_on_drop(true)
             .output()
             .await
-            .map_err(|e| anyhow::anyhow!("Failed to execute command: {}", e))?
+            .map_err(|e| anyhow::anyhow!("Failed to execute command: {}", e))?;
+
+        state.metrics.record_verification(output.status.success(), started.elapsed());
+        events::emit(&state.workspace_root, PlanEvent::VerificationFinished { id: id.clone(), run_id: None, passed: output.status.success() });
+
+        let stdout = env.redact(&String::from_utf8_lossy(&output.stdout));
+        let stderr = env.redact(&String::from_utf8_lossy(&output.stderr));
+
+        (output.status.success(), stdout, stderr, Vec::new())
     } else {
-        Command::new("sh")
-            .args(["-c", command_str])
-            .current_dir(&state.workspace_root)
-            .output()
-            .await
-            .map_err(|e| anyhow::anyhow!("Failed to execute command: {}", e))?
+        // Same 403 as a single command that isn't allowed, before anything runs
+        for step in ticket.verification.command_steps() {
+            policy::check(&state.config.policy, &step.command).map_err(|e| AppError(e, StatusCode::FORBIDDEN))?;
+        }
+        info!("Running {} verification steps for {}", ticket.verification.command_steps().len(), id);
+        let (root, config, verified) = (state.workspace_root.clone(), state.config.clone(), ticket.clone());
+        let result = tokio::task::spawn_blocking(move || batch::verify_ticket(&root, &config, &verified)).await?;
+        state.metrics.record_verification(result.passed, std::time::Duration::from_millis(result.duration_ms));
+        // Combined per step, in `output`; the step results carry stdout and stderr apart
+        (result.passed, result.output, String::new(), result.steps)
     };
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let stderr = String::from_utf8_lossy(&output.stderr);
-
     let target_artifact_dir = state.workspace_root.join(format!("target/public/artifacts/{}", id));
 
     // Always attempt to copy artifacts
@@ -318,20 +1683,67 @@ async fn verify_ticket(
         }
     }
 
+    // 3. Named snapshots: golden from the ticket, actual from `proof/<name>.png`
+    let mut snapshots = Vec::new();
+    for snapshot in &ticket.verification.visual {
+        if visual_diff::validate_snapshot_name(&snapshot.name).is_err()
+            || snapshot.golden.contains("..") || snapshot.golden.starts_with('/') {
+            error!("Invalid visual snapshot entry: {}", snapshot.name);
+            continue;
+        }
+        let snapshot_dir = target_artifact_dir.join(&snapshot.name);
+        fs::create_dir_all(&snapshot_dir).await?;
+
+        let copies = [
+            (state.workspace_root.join(&snapshot.golden), "golden.png"),
+            (state.workspace_root.join("proof").join(format!("{}.png", snapshot.name)), "actual.png"),
+        ];
+        for (src, file) in copies {
+            if src.exists()
+                && let Err(e) = fs::copy(&src, snapshot_dir.join(file)).await
+            {
+                error!("Failed to copy {} for snapshot {}: {}", file, snapshot.name, e);
+            }
+        }
+        snapshots.push(json!({
+            "name": snapshot.name,
+            "viewport": snapshot.viewport,
+            "artifacts_path": state.url(&format!("/artifacts/{}/{}", id, snapshot.name)),
+        }));
+    }
+
+    // Per-snapshot diff results from the last execution run, if any
+    let visual_report = fs::read_to_string(state.workspace_root.join("proof/visual-report.json")).await
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok());
+
     Ok(Json(json!({
-        "success": output.status.success(),
+        "success": success,
         "stdout": stdout,
         "stderr": stderr,
-        "artifacts_path": format!("/artifacts/{}", id)
+        "steps": steps,
+        "artifacts_path": state.url(&format!("/artifacts/{}", id)),
+        "snapshots": snapshots,
+        "visual_report": visual_report,
     })))
 }
 
+#[utoipa::path(
+    post, path = "/api/assets", tag = "assets",
+    request_body(content_type = "multipart/form-data", description = "One or more file fields"),
+    responses(
+        (status = 200, description = "`{ uploaded: [{ name, path, url }] }`. A name that's taken gets a `-1`, `-2`, ... suffix.", body = serde_json::Value),
+        (status = 400, description = "Invalid name, or the content doesn't match the extension", body = ErrorBody),
+        (status = 413, description = "Over the size limit for the file's type", body = ErrorBody),
+        (status = 415, description = "Type not in `[assets] allowed_types`", body = ErrorBody),
+    ),
+)]
 #[tracing::instrument(skip(state, multipart))]
 async fn upload_asset(
     State(state): State<Arc<AppState>>,
     mut multipart: Multipart,
 ) -> Result<Json<serde_json::Value>, AppError> {
-    let assets_dir = state.workspace_root.join("assets");
+    let assets_dir = assets::assets_dir(&state.workspace_root);
 
     let mut uploaded_files = Vec::new();
 
@@ -342,7 +1754,11 @@ async fn upload_asset(
             continue;
         };
 
-        let data = field.bytes().await.map_err(|e| anyhow::anyhow!("Read error: {}", e))?;
+        // Over the body limit shows up here, as 413
+        let data = field.bytes().await.map_err(|e| {
+            let status = e.status();
+            AppError(anyhow::anyhow!("Read error: {}", e), status)
+        })?;
 
         // Sanitize filename
         let safe_name = PathBuf::from(&file_name)
@@ -350,20 +1766,37 @@ async fn upload_asset(
             .and_then(|n| n.to_str())
             .map(|s| s.to_string())
             .unwrap_or_else(|| "unknown_file".to_string());
-
-        let dest_path = assets_dir.join(&safe_name);
-        fs::write(&dest_path, data).await?;
+        assets::check_upload(&state.config.assets, &safe_name, &data).map_err(|rejection| {
+            let status = match rejection {
+                UploadRejection::InvalidName(_) | UploadRejection::ContentMismatch { .. } => StatusCode::BAD_REQUEST,
+                UploadRejection::UnsupportedType { .. } => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                UploadRejection::TooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            };
+            AppError(rejection.into(), status)
+        })?;
+
+        // Never overwrite: an upload with a taken name gets a suffix instead
+        let safe_name = {
+            let _guard = state.ticket_writes.lock().unwrap();
+            let name = assets::unique_name(&assets_dir, &safe_name);
+            std::fs::write(assets_dir.join(&name), &data)?;
+            name
+        };
 
         uploaded_files.push(json!({
             "name": safe_name,
             "path": format!("assets/{}", safe_name),
-            "url": format!("/assets/{}", safe_name)
+            "url": state.url(&format!("/assets/{}", safe_name))
         }));
     }
 
     Ok(Json(json!({ "uploaded": uploaded_files })))
 }
 
+#[utoipa::path(
+    get, path = "/api/assets", tag = "assets",
+    responses((status = 200, description = "`[{ id, name, type, path, preview_url, rust_id }]`", body = Vec<serde_json::Value>)),
+)]
 async fn list_assets(State(state): State<Arc<AppState>>) -> Result<Json<Vec<serde_json::Value>>, AppError> {
     let assets_dir = state.workspace_root.join("assets");
     let mut assets = Vec::new();
@@ -391,7 +1824,7 @@ async fn list_assets(State(state): State<Arc<AppState>>) -> Result<Json<Vec<serd
                         "name": name,
                         "type": asset_type,
                         "path": format!("assets/{}", name),
-                        "preview_url": if asset_type == "image" { Some(format!("/assets/{}", name)) } else { None },
+                        "preview_url": if asset_type == "image" { Some(state.url(&format!("/assets/{}", name))) } else { None },
                         "rust_id": format!("ASSET_{}", name.to_uppercase().replace(|c: char| !c.is_alphanumeric(), "_"))
                     }));
                 }
@@ -402,18 +1835,189 @@ async fn list_assets(State(state): State<Arc<AppState>>) -> Result<Json<Vec<serd
     Ok(Json(assets))
 }
 
+#[derive(Deserialize, ToSchema)]
+struct RenameAssetPayload {
+    /// The new file name
+    name: String,
+}
+
+/// 400 for a bad name, 404 if the asset doesn't exist.
+fn find_asset(state: &AppState, name: &str) -> Result<(), AppError> {
+    assets::validate_name(name).map_err(|e| AppError(e, StatusCode::BAD_REQUEST))?;
+    if !assets::assets_dir(&state.workspace_root).join(name).is_file() {
+        return Err(AppError(anyhow::anyhow!("Asset not found"), StatusCode::NOT_FOUND));
+    }
+    Ok(())
+}
+
+/// Deletes an asset and removes it from every ticket's `spec.assets`.
+#[utoipa::path(
+    delete, path = "/api/assets/{name}", tag = "assets",
+    params(("name" = String, Path, description = "Asset file name")),
+    responses(
+        (status = 200, description = "`{ deleted, unlinked }`: the asset and the tickets it was removed from", body = serde_json::Value),
+        (status = 400, description = "Invalid name", body = ErrorBody),
+        (status = 404, description = "No such asset", body = ErrorBody),
+    ),
+)]
+#[tracing::instrument(skip(state))]
+async fn delete_asset(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    find_asset(&state, &name)?;
+    let unlinked = {
+        let _guard = state.ticket_writes.lock().unwrap();
+        assets::delete(&state.workspace_root, &name)?
+    };
+    Ok(Json(json!({ "deleted": name, "unlinked": unlinked })))
+}
+
+/// Renames an asset and updates every ticket that references it.
+#[utoipa::path(
+    patch, path = "/api/assets/{name}", tag = "assets",
+    params(("name" = String, Path, description = "Asset file name")),
+    request_body = RenameAssetPayload,
+    responses(
+        (status = 200, description = "`{ name, updated_tickets }`", body = serde_json::Value),
+        (status = 400, description = "Invalid name", body = ErrorBody),
+        (status = 404, description = "No such asset", body = ErrorBody),
+        (status = 409, description = "The new name is taken", body = ErrorBody),
+    ),
+)]
+#[tracing::instrument(skip(state, payload))]
+async fn rename_asset(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(payload): Json<RenameAssetPayload>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    find_asset(&state, &name)?;
+    assets::validate_name(&payload.name).map_err(|e| AppError(e, StatusCode::BAD_REQUEST))?;
+    if assets::assets_dir(&state.workspace_root).join(&payload.name).exists() {
+        return Err(AppError(anyhow::anyhow!("Asset '{}' already exists", payload.name), StatusCode::CONFLICT));
+    }
+
+    let updated = {
+        let _guard = state.ticket_writes.lock().unwrap();
+        assets::rename(&state.workspace_root, &name, &payload.name)?
+    };
+    Ok(Json(json!({ "name": payload.name, "updated_tickets": updated })))
+}
+
+// --- Metrics ---
+
+async fn track_http_metrics(State(state): State<Arc<AppState>>, req: Request, next: Next) -> Response {
+    let method = req.method().to_string();
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    let started = Instant::now();
+    let response = next.run(req).await;
+    state.metrics.record_http(&method, &route, response.status().as_u16(), started.elapsed());
+
+    response
+}
+
+/// Records the client's address as [`ClientIp`], honouring `X-Forwarded-For` from `[server] trusted_proxies`.
+async fn resolve_client_ip(State(trusted): State<Arc<Vec<IpAddr>>>, mut req: Request, next: Next) -> Response {
+    if let Some(ConnectInfo(addr)) = req.extensions().get::<ConnectInfo<SocketAddr>>().cloned() {
+        let forwarded_for = req.headers().get("x-forwarded-for").and_then(|v| v.to_str().ok());
+        let ip = proxy::client_ip(addr.ip(), forwarded_for, &trusted);
+        req.extensions_mut().insert(ClientIp(ip));
+    }
+    next.run(req).await
+}
+
+/// Per-IP limits from `[server.rate_limit]`. Requests without a peer address (in-process
+/// tests) aren't limited.
+async fn rate_limit(State(limiter): State<Arc<RateLimiter>>, req: Request, next: Next) -> Response {
+    let route = req.extensions().get::<MatchedPath>().map(|p| p.as_str()).unwrap_or_else(|| req.uri().path());
+    let class = RouteClass::of(req.method().as_str(), route);
+    if let Some(ClientIp(ip)) = req.extensions().get::<ClientIp>()
+        && let Err(wait) = limiter.check(*ip, class, Instant::now())
+    {
+        warn!("Rate limited {} ({:?} {})", ip, class, route);
+        let retry_after = wait.as_secs().max(1).to_string();
+        let mut response = AppError(anyhow::anyhow!("Too many requests; retry in {}s", retry_after), StatusCode::TOO_MANY_REQUESTS).into_response();
+        if let Ok(value) = HeaderValue::from_str(&retry_after) {
+            response.headers_mut().insert(header::RETRY_AFTER, value);
+        }
+        return response;
+    }
+    next.run(req).await
+}
+
+#[utoipa::path(
+    get, path = "/metrics", tag = "metrics",
+    responses((status = 200, description = "Prometheus text exposition format", body = String, content_type = "text/plain")),
+)]
+async fn prometheus_metrics(State(state): State<Arc<AppState>>) -> Result<Response, AppError> {
+    let mut out = String::new();
+    let tickets = load_all_tickets(&state, false).await?;
+
+    // Tickets by status (every status is emitted so dashboards don't see gaps)
+    let mut by_status: BTreeMap<String, usize> = [Status::Todo, Status::InProgress, Status::Review, Status::Blocked, Status::Done, Status::Archived]
+        .iter()
+        .map(|s| (s.to_string(), 0))
+        .collect();
+    for ticket in &tickets {
+        *by_status.entry(ticket.meta.status.to_string()).or_insert(0) += 1;
+    }
+    telemetry::write_header(&mut out, "director_plan_tickets", "gauge", "Number of tickets by status.");
+    for (status, count) in &by_status {
+        telemetry::write_sample(&mut out, "director_plan_tickets", &[("status", status.as_str())], *count as f64);
+    }
+
+    // Worker queue depth: what the worker would pick up next
+    let queue_depth = tickets
+        .iter()
+        .filter(|t| t.meta.status == Status::Todo && t.meta.owner.as_deref() == Some("radkit"))
+        .count();
+    telemetry::write_header(&mut out, "director_plan_worker_queue_depth", "gauge", "Todo tickets assigned to the worker.");
+    telemetry::write_sample(&mut out, "director_plan_worker_queue_depth", &[], queue_depth as f64);
+
+    // Executions, from persisted run records
+    let runs = RunStore::new(&state.workspace_root).list_all().unwrap_or_default();
+    let mut by_outcome: BTreeMap<&str, usize> = [("started", 0), ("succeeded", 0), ("failed", 0)].into_iter().collect();
+    for run in &runs {
+        *by_outcome.entry("started").or_insert(0) += 1;
+        match run.outcome {
+            RunOutcome::Succeeded => *by_outcome.entry("succeeded").or_insert(0) += 1,
+            RunOutcome::Failed => *by_outcome.entry("failed").or_insert(0) += 1,
+            RunOutcome::Running | RunOutcome::Interrupted => {}
+        }
+    }
+    telemetry::write_header(&mut out, "director_plan_executions_total", "counter", "Agent executions by outcome.");
+    for (outcome, count) in &by_outcome {
+        telemetry::write_sample(&mut out, "director_plan_executions_total", &[("outcome", *outcome)], *count as f64);
+    }
+
+    state.metrics.render(&mut out);
+
+    Ok(([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], out).into_response())
+}
+
 // --- Error Handling ---
 
 struct AppError(anyhow::Error, StatusCode);
 
+/// Body of every error response.
+#[derive(Serialize, ToSchema)]
+struct ErrorBody {
+    error: String,
+}
+
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         error!("API Error: {}", self.0);
         (
             self.1,
-            Json(json!({
-                "error": self.0.to_string()
-            })),
+            Json(ErrorBody {
+                error: self.0.to_string()
+            }),
         )
             .into_response()
     }
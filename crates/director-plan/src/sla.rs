@@ -0,0 +1,53 @@
+use chrono::{NaiveDate, Utc};
+use serde::Serialize;
+use crate::types::{Ticket, Status};
+
+/// Where an open ticket stands relative to its `meta.due_date`.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DueState {
+    Overdue,
+    DueSoon,
+    OnTrack,
+}
+
+impl DueState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DueState::Overdue => "overdue",
+            DueState::DueSoon => "due_soon",
+            DueState::OnTrack => "on_track",
+        }
+    }
+}
+
+pub fn today() -> NaiveDate {
+    Utc::now().date_naive()
+}
+
+/// The calendar date part of `meta.due_date`, if one is set.
+pub fn due_date(ticket: &Ticket) -> Option<NaiveDate> {
+    let date = ticket.meta.due_date.as_ref()?.date?;
+    NaiveDate::from_ymd_opt(date.year as i32, date.month as u32, date.day as u32)
+}
+
+/// Days remaining until the due date; negative once the ticket is overdue.
+pub fn days_until_due(ticket: &Ticket, today: NaiveDate) -> Option<i64> {
+    due_date(ticket).map(|due| (due - today).num_days())
+}
+
+/// Classifies an open ticket. Finished tickets and tickets without a due date have no state.
+pub fn due_state(ticket: &Ticket, today: NaiveDate, due_soon_days: i64) -> Option<DueState> {
+    if matches!(ticket.meta.status, Status::Done | Status::Archived) {
+        return None;
+    }
+
+    let days = days_until_due(ticket, today)?;
+    if days < 0 {
+        Some(DueState::Overdue)
+    } else if days <= due_soon_days {
+        Some(DueState::DueSoon)
+    } else {
+        Some(DueState::OnTrack)
+    }
+}
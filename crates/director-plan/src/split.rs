@@ -0,0 +1,217 @@
+use std::path::Path;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use toml_edit::{Array, DocumentMut};
+use crate::store::TicketStore;
+use crate::types::{Status, Ticket};
+
+/// One child ticket to create from a split.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChildDraft {
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub relevant_files: Vec<String>,
+    /// Defaults to the parent's command
+    #[serde(default)]
+    pub verification: Option<String>,
+}
+
+/// Splits a description without an agent: one child per top-level list item, or failing that
+/// per `##` section, or per paragraph. The first line of each part becomes the title.
+pub fn split_description(description: &str) -> Vec<ChildDraft> {
+    let mut items: Vec<Vec<&str>> = Vec::new();
+    for line in description.lines() {
+        if let Some(item) = list_item(line) {
+            items.push(vec![item]);
+        } else if let Some(item) = items.last_mut().filter(|_| line.starts_with(char::is_whitespace) && !line.trim().is_empty()) {
+            item.push(line.trim());
+        }
+    }
+
+    if items.len() < 2 {
+        items = description.split("\n## ")
+            .skip(usize::from(!description.starts_with("## ")))
+            .map(|section| section.trim_start_matches("## ").lines().collect())
+            .collect();
+    }
+    if items.len() < 2 {
+        items = description.split("\n\n")
+            .map(|p| p.lines().collect::<Vec<_>>())
+            .filter(|p| !p.is_empty())
+            .collect();
+    }
+
+    items.into_iter()
+        .filter_map(|lines| {
+            let (first, rest) = lines.split_first()?;
+            let title = first.trim();
+            (!title.is_empty()).then(|| ChildDraft {
+                title: title.to_string(),
+                description: rest.join("\n").trim().to_string(),
+                relevant_files: Vec::new(),
+                verification: None,
+            })
+        })
+        .collect()
+}
+
+/// The text of a `- `, `* `, `1. ` or `1) ` list item.
+fn list_item(line: &str) -> Option<&str> {
+    if let Some(text) = line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")) {
+        return Some(text);
+    }
+    let number = line.trim_start_matches(|c: char| c.is_ascii_digit());
+    if number.len() == line.len() {
+        return None;
+    }
+    number.strip_prefix(". ").or_else(|| number.strip_prefix(") "))
+}
+
+/// The prompt asking an agent to break `ticket` into children.
+pub fn split_prompt(ticket: &Ticket, count: Option<usize>) -> String {
+    let count = match count {
+        Some(n) => format!("exactly {}", n),
+        None => "between 2 and 8".to_string(),
+    };
+    format!(
+        "# Split a ticket\n\nThe ticket below is too big for one change. Break it into {} smaller tickets that can each be done, \
+        reviewed and verified on their own, in the order they should be done.\n\n\
+        Reply with only a JSON array. Each element is an object with `title`, `description`, `relevant_files` \
+        (paths, may be empty) and `verification` (a shell command that proves the piece works, or null to reuse the \
+        parent's).\n\n## {}: {}\n\n{}\n\n## Constraints\n{}\n\n## Verification\n{}\n",
+        count,
+        ticket.meta.id,
+        ticket.meta.title,
+        ticket.spec.description.trim(),
+        ticket.spec.constraints.iter().map(|c| format!("- {}", c)).collect::<Vec<_>>().join("\n"),
+        ticket.verification.command,
+    )
+}
+
+/// Reads the children out of an agent's reply. The JSON may be wrapped in prose or a code fence.
+pub fn parse_agent_reply(reply: &str) -> Result<Vec<ChildDraft>> {
+    let children: Vec<ChildDraft> = crate::planner::parse_json_array(reply)?;
+    if children.iter().any(|c| c.title.trim().is_empty()) {
+        return Err(anyhow!("The agent returned a ticket without a title"));
+    }
+    Ok(children)
+}
+
+/// Writes `children` as `<parent>-1`, `<parent>-2`, ... with `meta.parent` set, and notes the
+/// split in the parent's history. Children inherit the parent's priority, type, owner,
+/// constraints, allowed paths and (unless given their own) verification command.
+pub fn create_children(workspace_root: &Path, parent_id: &str, children: &[ChildDraft]) -> Result<Vec<String>> {
+    let store = TicketStore::new(workspace_root);
+    let parent = store.load_document(parent_id)?;
+    let mut ids = Vec::new();
+    let mut n = 1;
+
+    for child in children {
+        while store.exists(&format!("{}-{}", parent_id, n)) {
+            n += 1;
+        }
+        let id = format!("{}-{}", parent_id, n);
+
+        let mut doc = DocumentMut::new();
+        doc["meta"] = toml_edit::table();
+        doc["meta"]["id"] = toml_edit::value(id.as_str());
+        doc["meta"]["title"] = toml_edit::value(child.title.as_str());
+        doc["meta"]["status"] = toml_edit::value(Status::Todo.to_string());
+        for key in ["priority", "type", "owner"] {
+            if let Some(value) = parent.get("meta").and_then(|m| m.get(key)) {
+                doc["meta"][key] = value.clone();
+            }
+        }
+        doc["meta"]["parent"] = toml_edit::value(parent_id);
+        doc["meta"]["created_at"] = toml_edit::value(
+            chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string().parse::<toml_datetime::Datetime>()?,
+        );
+        crate::migrate::stamp(&mut doc);
+
+        doc["spec"] = toml_edit::table();
+        doc["spec"]["description"] = toml_edit::value(child.description.as_str());
+        for key in ["constraints", "allowed_paths"] {
+            if let Some(value) = parent.get("spec").and_then(|s| s.get(key)) {
+                doc["spec"][key] = value.clone();
+            }
+        }
+        doc["spec"]["relevant_files"] = toml_edit::value(child.relevant_files.iter().map(String::as_str).collect::<Array>());
+
+        doc["verification"] = toml_edit::table();
+        let command = child.verification.clone()
+            .or_else(|| parent.get("verification").and_then(|v| v.get("command")).and_then(|c| c.as_str()).map(str::to_string))
+            .unwrap_or_default();
+        doc["verification"]["command"] = toml_edit::value(command);
+        crate::append_history_entry(&mut doc, &format!("Split from {}.", parent_id));
+
+        store.save_document(&id, &doc)?;
+        ids.push(id);
+    }
+
+    store.update(parent_id, |doc| {
+        crate::append_history_entry(doc, &format!("Split into {}; blocked until they are done.", ids.join(", ")));
+        Ok(())
+    })?;
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::DirectorPlan;
+    use crate::blocking;
+
+    #[test]
+    fn test_split_description() {
+        let items = split_description("Build the editor.\n\n- Toolbar\n  with undo and redo\n- Canvas\n1. Export to PNG\n");
+        let titles: Vec<&str> = items.iter().map(|c| c.title.as_str()).collect();
+        assert_eq!(titles, ["Toolbar", "Canvas", "Export to PNG"]);
+        assert_eq!(items[0].description, "with undo and redo");
+
+        let sections = split_description("## Parser\nTokens and AST\n## Printer\nPretty output");
+        assert_eq!(sections.iter().map(|c| c.title.as_str()).collect::<Vec<_>>(), ["Parser", "Printer"]);
+        assert_eq!(sections[1].description, "Pretty output");
+    }
+
+    #[test]
+    fn test_parse_agent_reply() {
+        let reply = "Here you go:\n```json\n[{\"title\": \"A\", \"description\": \"do a\", \"relevant_files\": [\"src/a.rs\"], \"verification\": null}, {\"title\": \"B\"}]\n```";
+        let children = parse_agent_reply(reply).unwrap();
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[0].relevant_files, ["src/a.rs"]);
+        assert!(parse_agent_reply("no plan").is_err());
+    }
+
+    #[test]
+    fn test_create_children() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("plan/tickets")).unwrap();
+        fs::write(root.join("plan/tickets/T-1.toml"), "[meta]\nid = \"T-1\"\ntitle = \"Epic\"\nstatus = \"todo\"\npriority = \"high\"\nowner = \"radkit\"\n\n[spec]\ndescription = \"\"\nconstraints = [\"No unsafe\"]\n\n[verification]\ncommand = \"cargo test\"\n").unwrap();
+
+        let drafts = [
+            ChildDraft { title: "A".into(), description: String::new(), relevant_files: vec![], verification: None },
+            ChildDraft { title: "B".into(), description: String::new(), relevant_files: vec![], verification: Some("npm test".into()) },
+        ];
+        assert_eq!(create_children(root, "T-1", &drafts).unwrap(), ["T-1-1", "T-1-2"]);
+
+        let plan = DirectorPlan::new(root.to_path_buf());
+        let a = plan.get_ticket("T-1-1").unwrap();
+        assert_eq!(a.meta.parent.as_deref(), Some("T-1"));
+        assert_eq!(a.meta.owner.as_deref(), Some("radkit"));
+        assert_eq!(a.spec.constraints, ["No unsafe"]);
+        assert_eq!(a.verification.command, "cargo test");
+        assert_eq!(plan.get_ticket("T-1-2").unwrap().verification.command, "npm test");
+
+        let tickets = plan.list_tickets(None).unwrap();
+        assert_eq!(blocking::waiting_on(&tickets, "T-1"), ["T-1-1", "T-1-2"]);
+
+        TicketStore::new(root).set_status("T-1-1", Status::Done).unwrap();
+        TicketStore::new(root).set_status("T-1-2", Status::Archived).unwrap();
+        let tickets = plan.list_tickets(None).unwrap();
+        assert!(blocking::check_transition(&tickets, "T-1", &Status::Done).is_ok());
+    }
+}
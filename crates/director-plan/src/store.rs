@@ -0,0 +1,274 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde::de::IntoDeserializer;
+use serde::de::value::StrDeserializer;
+use toml_edit::DocumentMut;
+use crate::error::DirectorError;
+use crate::events::{self, PlanEvent};
+use crate::ticket_format::{TicketFormat, find_ticket_file};
+use crate::types::{Ticket, Status};
+
+/// File-backed access to `plan/tickets/`, where tickets are `.toml`, `.yaml` or `.json`
+/// files (see [`TicketFormat`]).
+///
+/// All writes go through `toml_edit` so comments and formatting survive, and every status
+/// change is funneled through [`set_status_in_document`] so transitions get timestamped.
+pub struct TicketStore {
+    workspace_root: PathBuf,
+}
+
+impl TicketStore {
+    pub fn new(workspace_root: &Path) -> Self {
+        Self { workspace_root: workspace_root.to_path_buf() }
+    }
+
+    pub fn tickets_dir(&self) -> PathBuf {
+        self.workspace_root.join("plan/tickets")
+    }
+
+    /// The ticket's file, whatever its format; `<id>.toml` for a ticket not written yet.
+    pub fn ticket_path(&self, id: &str) -> PathBuf {
+        find_ticket_file(&self.tickets_dir(), id)
+    }
+
+    fn format(path: &Path) -> TicketFormat {
+        TicketFormat::from_path(path).unwrap_or(TicketFormat::Toml)
+    }
+
+    pub fn exists(&self, id: &str) -> bool {
+        self.ticket_path(id).exists()
+    }
+
+    pub fn load(&self, id: &str) -> Result<Ticket> {
+        let path = self.ticket_path(id);
+        if !path.exists() {
+            return Err(DirectorError::TicketNotFound(id.to_string()).into());
+        }
+
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read ticket file: {:?}", path))?;
+        let ticket = Self::format(&path).parse_ticket(&content)
+            .with_context(|| format!("Failed to parse ticket file: {:?}", path))?;
+
+        Ok(ticket)
+    }
+
+    pub fn load_document(&self, id: &str) -> Result<DocumentMut> {
+        let path = self.ticket_path(id);
+        if !path.exists() {
+            return Err(DirectorError::TicketNotFound(id.to_string()).into());
+        }
+
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read ticket file: {:?}", path))?;
+        Self::format(&path).parse_document(&content)
+            .with_context(|| format!("Failed to parse ticket file: {:?}", path))
+    }
+
+    /// Writes the ticket, recording a [`PlanEvent`] when it is new or its status changed.
+    pub fn save_document(&self, id: &str, doc: &DocumentMut) -> Result<()> {
+        let path = self.ticket_path(id);
+        let format = Self::format(&path);
+        let previous = fs::read_to_string(&path).ok()
+            .map(|content| format.parse_document(&content).ok().and_then(|d| document_status(&d)));
+        fs::write(&path, format.render(doc)?)
+            .with_context(|| format!("Failed to write ticket file: {:?}", path))?;
+
+        match (previous, document_status(doc)) {
+            (None, _) => {
+                let title = doc.get("meta").and_then(|m| m.get("title")).and_then(|t| t.as_str()).unwrap_or_default();
+                events::emit(&self.workspace_root, PlanEvent::TicketCreated { id: id.to_string(), title: title.to_string() });
+            }
+            (Some(Some(from)), Some(to)) if from != to => {
+                events::emit(&self.workspace_root, PlanEvent::StatusChanged { id: id.to_string(), from, to });
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Read-modify-write of a single ticket document.
+    pub fn update<F>(&self, id: &str, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut DocumentMut) -> Result<()>,
+    {
+        let mut doc = self.load_document(id)?;
+        f(&mut doc)?;
+        self.save_document(id, &doc)
+    }
+
+    pub fn set_status(&self, id: &str, status: Status) -> Result<()> {
+        self.update(id, |doc| {
+            set_status_in_document(doc, &status);
+            Ok(())
+        })
+    }
+
+    pub fn append_history(&self, id: &str, message: &str) -> Result<()> {
+        self.update(id, |doc| {
+            crate::append_history_entry(doc, message);
+            Ok(())
+        })
+    }
+
+    /// Parks an open ticket in `blocked` with `reason`, which also goes in the history.
+    pub fn block(&self, id: &str, reason: &str) -> Result<()> {
+        let ticket = self.load(id)?;
+        if matches!(ticket.meta.status, Status::Done | Status::Archived) {
+            anyhow::bail!("Ticket {} is {}; only open tickets can be blocked", id, ticket.meta.status.to_string());
+        }
+        self.update(id, |doc| {
+            set_status_in_document(doc, &Status::Blocked);
+            doc["meta"]["blocked_reason"] = toml_edit::value(reason);
+            crate::append_history_entry(doc, &format!("Blocked: {}", reason));
+            Ok(())
+        })
+    }
+
+    /// Moves a blocked ticket back to the status it was blocked from. A ticket blocked while in
+    /// progress goes to `todo`, since nothing is running it any more. Returns the new status.
+    pub fn unblock(&self, id: &str, reason: Option<&str>) -> Result<Status> {
+        let ticket = self.load(id)?;
+        if ticket.meta.status != Status::Blocked {
+            anyhow::bail!("Ticket {} isn't blocked", id);
+        }
+        let status = match ticket.timing.transitions.iter().rev().find(|t| t.to == Status::Blocked).and_then(|t| t.from.clone()) {
+            Some(Status::InProgress | Status::Blocked) | None => Status::Todo,
+            Some(previous) => previous,
+        };
+        self.update(id, |doc| {
+            set_status_in_document(doc, &status);
+            let message = match reason {
+                Some(reason) => format!("Unblocked: {}", reason),
+                None => "Unblocked.".to_string(),
+            };
+            crate::append_history_entry(doc, &message);
+            Ok(())
+        })?;
+        Ok(status)
+    }
+}
+
+/// `meta.status`, if it names a status.
+pub fn document_status(doc: &DocumentMut) -> Option<Status> {
+    let status: StrDeserializer<serde::de::value::Error> = doc.get("meta")?.get("status")?.as_str()?.into_deserializer();
+    Status::deserialize(status).ok()
+}
+
+/// Sets `meta.status` and, if it actually changed, appends a `[[timing.transitions]]` entry.
+/// Leaving `blocked` drops `meta.blocked_reason`.
+pub fn set_status_in_document(doc: &mut DocumentMut, status: &Status) {
+    if *status != Status::Blocked
+        && let Some(meta) = doc.get_mut("meta").and_then(|m| m.as_table_like_mut())
+    {
+        meta.remove("blocked_reason");
+    }
+    let new_status = status.to_string();
+    let previous = doc
+        .get("meta")
+        .and_then(|m| m.get("status"))
+        .and_then(|s| s.as_str())
+        .map(String::from);
+
+    doc["meta"]["status"] = toml_edit::value(new_status.clone());
+
+    if previous.as_deref() == Some(new_status.as_str()) {
+        return;
+    }
+
+    if doc.get("timing").is_none() {
+        let mut timing = toml_edit::Table::new();
+        timing.set_implicit(true);
+        doc["timing"] = toml_edit::Item::Table(timing);
+    }
+
+    let timing = match doc["timing"].as_table_mut() {
+        Some(t) => t,
+        None => return,
+    };
+
+    if timing.get("transitions").is_none() {
+        timing.insert("transitions", toml_edit::Item::ArrayOfTables(toml_edit::ArrayOfTables::new()));
+    }
+
+    let mut entry = toml_edit::Table::new();
+    if let Some(from) = previous {
+        entry["from"] = toml_edit::value(from);
+    }
+    entry["to"] = toml_edit::value(new_status);
+    entry["at"] = toml_edit::value(chrono::Utc::now().to_rfc3339());
+
+    if let Some(transitions) = timing.get_mut("transitions").and_then(|t| t.as_array_of_tables_mut()) {
+        transitions.push(entry);
+    }
+}
+
+/// For a document written over a ticket whose status was `previous`: if `status` differs and
+/// the document doesn't already end with that transition, records it like
+/// [`set_status_in_document`]. Writers that edit the status through the store have recorded it
+/// already; hand-edited TOML hasn't.
+pub fn record_status_change(doc: &mut DocumentMut, previous: &Status, status: &Status) {
+    if previous == status {
+        return;
+    }
+    let recorded = doc
+        .get("timing")
+        .and_then(|t| t.get("transitions"))
+        .and_then(|t| t.as_array_of_tables())
+        .and_then(|t| t.iter().last())
+        .and_then(|last| last.get("to"))
+        .and_then(|to| to.as_str())
+        .is_some_and(|to| to == status.to_string());
+    if recorded {
+        return;
+    }
+    doc["meta"]["status"] = toml_edit::value(previous.to_string());
+    set_status_in_document(doc, status);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_status_change() {
+        let mut doc: DocumentMut = "# keep me\n[meta]\nstatus = \"review\"\n".parse().unwrap();
+        record_status_change(&mut doc, &Status::Todo, &Status::Review);
+        let text = doc.to_string();
+        assert!(text.starts_with("# keep me\n"));
+        assert_eq!(text.matches("[[timing.transitions]]").count(), 1);
+        assert!(text.contains("from = \"todo\""));
+
+        // Already recorded by the writer
+        record_status_change(&mut doc, &Status::Todo, &Status::Review);
+        assert_eq!(doc.to_string().matches("[[timing.transitions]]").count(), 1);
+    }
+
+    #[test]
+    fn test_block_and_unblock() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = TicketStore::new(dir.path());
+        fs::create_dir_all(store.tickets_dir()).unwrap();
+        fs::write(store.ticket_path("T-1"), "[meta]\nid = \"T-1\"\ntitle = \"t\"\nstatus = \"review\"\npriority = \"low\"\n\n[spec]\ndescription = \"\"\n\n[verification]\ncommand = \"true\"\n").unwrap();
+
+        store.block("T-1", "Waiting on the vendor API key").unwrap();
+        let ticket = store.load("T-1").unwrap();
+        assert_eq!(ticket.meta.status, Status::Blocked);
+        assert_eq!(ticket.meta.blocked_reason.as_deref(), Some("Waiting on the vendor API key"));
+
+        assert_eq!(store.unblock("T-1", Some("Key arrived")).unwrap(), Status::Review);
+        let ticket = store.load("T-1").unwrap();
+        assert!(ticket.meta.blocked_reason.is_none());
+        assert!(store.unblock("T-1", None).is_err());
+
+        store.set_status("T-1", Status::InProgress).unwrap();
+        store.block("T-1", "Flaky CI").unwrap();
+        assert_eq!(store.unblock("T-1", None).unwrap(), Status::Todo);
+
+        let changes = crate::events::EventLog::new(dir.path()).list().unwrap();
+        assert_eq!(changes.len(), 5);
+        assert_eq!(changes[0].event, PlanEvent::StatusChanged { id: "T-1".into(), from: Status::Review, to: Status::Blocked });
+    }
+}
@@ -0,0 +1,156 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Bucket upper bounds (seconds) for HTTP request latency.
+const HTTP_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+/// Bucket upper bounds (seconds) for verification commands, which are much slower.
+const VERIFICATION_BUCKETS: &[f64] = &[0.1, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0];
+
+/// A cumulative Prometheus histogram.
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    bounds: &'static [f64],
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Self { bounds, counts: vec![0; bounds.len()], sum: 0.0, count: 0 }
+    }
+
+    fn observe(&mut self, value: f64) {
+        for (i, bound) in self.bounds.iter().enumerate() {
+            if value <= *bound {
+                self.counts[i] += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let sep = if labels.is_empty() { "" } else { "," };
+        for (bound, count) in self.bounds.iter().zip(&self.counts) {
+            let _ = writeln!(out, "{}_bucket{{{}{}le=\"{}\"}} {}", name, labels, sep, bound, count);
+        }
+        let _ = writeln!(out, "{}_bucket{{{}{}le=\"+Inf\"}} {}", name, labels, sep, self.count);
+        let braces = if labels.is_empty() { String::new() } else { format!("{{{}}}", labels) };
+        let _ = writeln!(out, "{}_sum{} {}", name, braces, self.sum);
+        let _ = writeln!(out, "{}_count{} {}", name, braces, self.count);
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    // (method, route, status) -> count
+    http_requests: BTreeMap<(String, String, u16), u64>,
+    // route -> latency
+    http_latency: BTreeMap<String, Histogram>,
+    // outcome ("pass"/"fail") -> latency
+    verification_latency: BTreeMap<String, Histogram>,
+}
+
+/// In-process counters for `serve`, exposed at `GET /metrics`.
+/// Ticket and run gauges are computed from disk at scrape time instead of being tracked here.
+#[derive(Default)]
+pub struct ServerMetrics {
+    inner: Mutex<Inner>,
+}
+
+impl ServerMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_http(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        let mut inner = self.inner.lock().unwrap();
+        *inner.http_requests
+            .entry((method.to_string(), route.to_string(), status))
+            .or_insert(0) += 1;
+        inner.http_latency
+            .entry(route.to_string())
+            .or_insert_with(|| Histogram::new(HTTP_BUCKETS))
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn record_verification(&self, success: bool, elapsed: Duration) {
+        let outcome = if success { "pass" } else { "fail" };
+        let mut inner = self.inner.lock().unwrap();
+        inner.verification_latency
+            .entry(outcome.to_string())
+            .or_insert_with(|| Histogram::new(VERIFICATION_BUCKETS))
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Renders the in-process metrics in the Prometheus text exposition format.
+    pub fn render(&self, out: &mut String) {
+        let inner = self.inner.lock().unwrap();
+
+        write_header(out, "director_plan_http_requests_total", "counter", "HTTP requests handled by the server.");
+        for ((method, route, status), count) in &inner.http_requests {
+            let _ = writeln!(
+                out,
+                "director_plan_http_requests_total{{method=\"{}\",route=\"{}\",status=\"{}\"}} {}",
+                method, escape_label(route), status, count
+            );
+        }
+
+        write_header(out, "director_plan_http_request_duration_seconds", "histogram", "HTTP request latency.");
+        for (route, histogram) in &inner.http_latency {
+            let labels = format!("route=\"{}\"", escape_label(route));
+            histogram.render(out, "director_plan_http_request_duration_seconds", &labels);
+        }
+
+        write_header(out, "director_plan_verification_duration_seconds", "histogram", "Verification command latency triggered through the API.");
+        for (outcome, histogram) in &inner.verification_latency {
+            let labels = format!("outcome=\"{}\"", outcome);
+            histogram.render(out, "director_plan_verification_duration_seconds", &labels);
+        }
+    }
+}
+
+pub fn write_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+/// Writes a labelled sample, e.g. `name{status="todo"} 3`.
+pub fn write_sample(out: &mut String, name: &str, labels: &[(&str, &str)], value: f64) {
+    if labels.is_empty() {
+        let _ = writeln!(out, "{} {}", name, value);
+        return;
+    }
+    let labels: Vec<String> = labels
+        .iter()
+        .map(|(k, v)| format!("{}=\"{}\"", k, escape_label(v)))
+        .collect();
+    let _ = writeln!(out, "{}{{{}}} {}", name, labels.join(","), value);
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_is_cumulative() {
+        let mut h = Histogram::new(&[1.0, 5.0]);
+        h.observe(0.5);
+        h.observe(3.0);
+        h.observe(10.0);
+
+        let mut out = String::new();
+        h.render(&mut out, "x", "");
+        assert!(out.contains("x_bucket{le=\"1\"} 1"));
+        assert!(out.contains("x_bucket{le=\"5\"} 2"));
+        assert!(out.contains("x_bucket{le=\"+Inf\"} 3"));
+        assert!(out.contains("x_count 3"));
+    }
+}
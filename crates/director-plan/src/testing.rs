@@ -0,0 +1,152 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use anyhow::{Context, Result, anyhow};
+use tempfile::TempDir;
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use crate::DirectorPlan;
+use crate::client::Client;
+use crate::server::ServeOptions;
+
+/// A throwaway workspace for end-to-end tests, here and in crates embedding director-plan:
+/// `plan/tickets/` in a git repository on `main`, with a committer identity so runs can commit.
+/// Everything is deleted when it's dropped.
+///
+/// ```no_run
+/// # async fn example() -> anyhow::Result<()> {
+/// use director_plan::testing::TestWorkspace;
+///
+/// let workspace = TestWorkspace::new()?.with_remote()?;
+/// let id = workspace.ticket("Shadows", "true")?;
+/// let server = workspace.serve().await?;
+/// assert!(server.client().verify(&id).await?.success);
+/// # Ok(())
+/// # }
+/// ```
+pub struct TestWorkspace {
+    dir: TempDir,
+    remote: Option<TempDir>,
+}
+
+impl TestWorkspace {
+    pub fn new() -> Result<Self> {
+        let workspace = Self { dir: tempfile::tempdir().context("Failed to create a temp workspace")?, remote: None };
+        fs::create_dir_all(workspace.plan().get_tickets_dir())?;
+        workspace.git(&["init", "-q", "-b", "main"])?;
+        workspace.git(&["config", "user.name", "Director Test"])?;
+        workspace.git(&["config", "user.email", "test@director-plan.invalid"])?;
+        workspace.git(&["commit", "-q", "--allow-empty", "-m", "Initial commit"])?;
+        Ok(workspace)
+    }
+
+    /// Adds a bare repository as `origin`, with `main` pushed and `origin/HEAD` pointing at it,
+    /// for workers that pull and for base-branch detection.
+    pub fn with_remote(mut self) -> Result<Self> {
+        let remote = tempfile::tempdir().context("Failed to create a temp remote")?;
+        git(remote.path(), &["init", "-q", "--bare", "-b", "main"])?;
+        self.git(&["remote", "add", "origin", &remote.path().to_string_lossy()])?;
+        self.git(&["push", "-q", "-u", "origin", "main"])?;
+        self.git(&["remote", "set-head", "origin", "main"])?;
+        self.remote = Some(remote);
+        Ok(self)
+    }
+
+    pub fn root(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// The bare repository behind `origin`, after [`with_remote`](Self::with_remote).
+    pub fn remote(&self) -> Option<&Path> {
+        self.remote.as_ref().map(TempDir::path)
+    }
+
+    pub fn plan(&self) -> DirectorPlan {
+        DirectorPlan::new(self.root().to_path_buf())
+    }
+
+    /// Creates a `todo` ticket checked by `verification` and commits it. Returns its ID.
+    pub fn ticket(&self, title: &str, verification: &str) -> Result<String> {
+        let id = self.plan().create_ticket(title, "", verification)?;
+        self.commit(&format!("Add {}", id))?;
+        Ok(id)
+    }
+
+    /// One ticket whose verification passes and one whose verification fails, committed.
+    pub fn sample_tickets(&self) -> Result<(String, String)> {
+        Ok((self.ticket("Passing", "true")?, self.ticket("Failing", "false")?))
+    }
+
+    /// Writes `content` to `path` in the workspace, creating directories as needed. Nothing
+    /// is committed.
+    pub fn write(&self, path: &str, content: &str) -> Result<()> {
+        let target = self.root().join(path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&target, content).with_context(|| format!("Failed to write {:?}", target))
+    }
+
+    /// Commits everything in the tree and returns the new HEAD.
+    pub fn commit(&self, message: &str) -> Result<String> {
+        self.git(&["add", "-A"])?;
+        self.git(&["commit", "-q", "--allow-empty", "-m", message])?;
+        self.git(&["rev-parse", "HEAD"])
+    }
+
+    /// Runs git in the workspace and returns its trimmed output.
+    pub fn git(&self, args: &[&str]) -> Result<String> {
+        git(self.root(), args)
+    }
+
+    /// Serves the workspace's API on an ephemeral port until the returned server is dropped.
+    pub async fn serve(&self) -> Result<TestServer> {
+        self.serve_with(&ServeOptions::default()).await
+    }
+
+    /// Like [`serve`](Self::serve), with a base path or a config other than `plan/config.toml`.
+    pub async fn serve_with(&self, options: &ServeOptions) -> Result<TestServer> {
+        let app = crate::server::create_app_with(self.root().to_path_buf(), options).await?;
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let port = listener.local_addr()?.port();
+        let task = tokio::spawn(async move {
+            let _ = axum::serve(listener, app).await;
+        });
+        Ok(TestServer { port, task })
+    }
+}
+
+/// A server started by [`TestWorkspace::serve`]; it stops when dropped.
+pub struct TestServer {
+    pub port: u16,
+    task: JoinHandle<()>,
+}
+
+impl TestServer {
+    /// `http://127.0.0.1:<port>` followed by `path`, e.g. `/api/tickets`.
+    pub fn url(&self, path: &str) -> String {
+        format!("http://127.0.0.1:{}{}", self.port, path)
+    }
+
+    pub fn client(&self) -> Client {
+        Client::new(&self.url("/"))
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to run git {}", args.join(" ")))?;
+    if !output.status.success() {
+        return Err(anyhow!("git {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
@@ -0,0 +1,304 @@
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+use serde_json::{Map, Value as Json};
+use toml_edit::{ArrayOfTables, DocumentMut, InlineTable, Item, Table, Value};
+use crate::types::Ticket;
+
+/// Extensions a ticket file may have, in the order they are looked for.
+pub const TICKET_EXTENSIONS: [&str; 4] = ["toml", "yaml", "yml", "json"];
+
+/// The on-disk format of a ticket file, chosen by its extension. Every format has the same
+/// schema; YAML and JSON tickets are edited as a TOML document and written back in their own
+/// format, keeping key order (YAML comments are not kept).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TicketFormat {
+    Toml,
+    Yaml,
+    Json,
+}
+
+impl TicketFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "toml" => Some(Self::Toml),
+            "yaml" | "yml" => Some(Self::Yaml),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    /// MIME type for the raw ticket API.
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Toml => "application/toml",
+            Self::Yaml => "application/yaml",
+            Self::Json => "application/json",
+        }
+    }
+
+    /// The format a raw ticket API response is in; TOML unless it says otherwise.
+    pub fn from_content_type(content_type: &str) -> Self {
+        match content_type.split(';').next().unwrap_or_default().trim() {
+            "application/yaml" | "application/x-yaml" | "text/yaml" => Self::Yaml,
+            "application/json" => Self::Json,
+            _ => Self::Toml,
+        }
+    }
+
+    /// Parses a ticket file's contents into the document every edit works on.
+    pub fn parse_document(self, content: &str) -> Result<DocumentMut> {
+        let tree: Json = match self {
+            Self::Toml => return Ok(content.parse::<DocumentMut>()?),
+            Self::Yaml => serde_yaml::from_str(content)?,
+            Self::Json => serde_json::from_str(content)?,
+        };
+        let Json::Object(map) = tree else {
+            bail!("A ticket file must hold a mapping at the top level");
+        };
+        Ok(DocumentMut::from(to_table(&map)))
+    }
+
+    /// Writes `doc` back out in this format.
+    pub fn render(self, doc: &DocumentMut) -> Result<String> {
+        match self {
+            Self::Toml => Ok(doc.to_string()),
+            Self::Yaml => Ok(serde_yaml::to_string(&from_table(doc.as_table()))?),
+            Self::Json => Ok(format!("{}\n", serde_json::to_string_pretty(&from_table(doc.as_table()))?)),
+        }
+    }
+
+    pub fn parse_ticket(self, content: &str) -> Result<Ticket> {
+        Ok(toml_edit::de::from_document(self.parse_document(content)?)?)
+    }
+
+    /// Strict parsing: the ticket, plus every field the schema doesn't have (e.g. a
+    /// misspelled `meta.prioritiy`), which [`parse_ticket`](Self::parse_ticket) silently ignores.
+    /// Out-of-range verification limits are an error.
+    pub fn parse_ticket_strict(self, content: &str) -> Result<(Ticket, Vec<String>)> {
+        let deserializer = toml_edit::de::Deserializer::from(self.parse_document(content)?);
+        let mut unknown = Vec::new();
+        let ticket: Ticket = serde_ignored::deserialize(deserializer, |path| unknown.push(field_path(&path)))?;
+        ticket.verification.check()?;
+        crate::scope::check_globs(&ticket.spec.context_exclude, "spec.context_exclude")?;
+        Ok((ticket, unknown))
+    }
+}
+
+/// `meta.prioritiy`, `verification.visual[1].nmae`
+fn field_path(path: &serde_ignored::Path) -> String {
+    use serde_ignored::Path;
+    match path {
+        Path::Root => String::new(),
+        Path::Seq { parent, index } => format!("{}[{}]", field_path(parent), index),
+        Path::Map { parent, key } => match field_path(parent) {
+            parent if parent.is_empty() => key.to_string(),
+            parent => format!("{}.{}", parent, key),
+        },
+        Path::Some { parent } | Path::NewtypeStruct { parent } | Path::NewtypeVariant { parent } => field_path(parent),
+    }
+}
+
+/// A ticket file `director-plan validate` found fault with.
+#[derive(Debug, Serialize)]
+pub struct Finding {
+    pub file: PathBuf,
+    /// Why the file doesn't parse at all
+    pub error: Option<String>,
+    pub unknown_fields: Vec<String>,
+}
+
+/// Parses every ticket file in `tickets_dir` strictly. Returns the files with problems.
+pub fn validate_dir(tickets_dir: &Path) -> Result<Vec<Finding>> {
+    let mut findings = Vec::new();
+    if !tickets_dir.exists() {
+        return Ok(findings);
+    }
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(tickets_dir)?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| is_ticket_file(p))
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        let format = TicketFormat::from_path(&path).unwrap_or(TicketFormat::Toml);
+        let content = std::fs::read_to_string(&path).with_context(|| format!("Failed to read ticket file: {:?}", path))?;
+        let finding = match format.parse_ticket_strict(&content) {
+            Ok((_, unknown_fields)) if unknown_fields.is_empty() => continue,
+            Ok((_, unknown_fields)) => Finding { file: path, error: None, unknown_fields },
+            Err(e) => Finding { file: path, error: Some(format!("{:#}", e)), unknown_fields: Vec::new() },
+        };
+        findings.push(finding);
+    }
+    Ok(findings)
+}
+
+/// The file holding ticket `id` in `tickets_dir`, whichever format it is in; the `.toml`
+/// path when there is none yet.
+pub fn find_ticket_file(tickets_dir: &Path, id: &str) -> PathBuf {
+    TICKET_EXTENSIONS.iter()
+        .map(|ext| tickets_dir.join(format!("{}.{}", id, ext)))
+        .find(|path| path.exists())
+        .unwrap_or_else(|| tickets_dir.join(format!("{}.toml", id)))
+}
+
+/// Whether `path` has one of the [`TICKET_EXTENSIONS`].
+pub fn is_ticket_file(path: &Path) -> bool {
+    TicketFormat::from_path(path).is_some()
+}
+
+/// Reads and parses a ticket file of any format.
+pub fn read_ticket(path: &Path) -> Result<Ticket> {
+    let format = TicketFormat::from_path(path).unwrap_or(TicketFormat::Toml);
+    let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read ticket file: {:?}", path))?;
+    format.parse_ticket(&content).with_context(|| format!("Failed to parse ticket file: {:?}", path))
+}
+
+// Keys whose string values are TOML datetimes (`meta.created_at`, `meta.due_date`)
+const DATETIME_KEYS: [&str; 2] = ["created_at", "due_date"];
+
+fn to_table(map: &Map<String, Json>) -> Table {
+    let mut table = Table::new();
+    for (key, value) in map {
+        let item = match value {
+            Json::Null => continue,
+            Json::Object(inner) => Item::Table(to_table(inner)),
+            Json::Array(items) if !items.is_empty() && items.iter().all(Json::is_object) => {
+                Item::ArrayOfTables(items.iter().filter_map(Json::as_object).map(to_table).collect::<ArrayOfTables>())
+            }
+            _ => match to_value(key, value) {
+                Some(value) => Item::Value(value),
+                None => continue,
+            },
+        };
+        table.insert(key, item);
+    }
+    table
+}
+
+fn to_value(key: &str, value: &Json) -> Option<Value> {
+    Some(match value {
+        Json::Null => return None,
+        Json::Bool(b) => Value::from(*b),
+        Json::Number(n) => match n.as_i64() {
+            Some(i) => Value::from(i),
+            None => Value::from(n.as_f64()?),
+        },
+        Json::String(s) if DATETIME_KEYS.contains(&key) => match s.parse::<toml_datetime::Datetime>() {
+            Ok(datetime) => Value::from(datetime),
+            Err(_) => Value::from(s.as_str()),
+        },
+        Json::String(s) => Value::from(s.as_str()),
+        Json::Array(items) => Value::Array(items.iter().filter_map(|v| to_value(key, v)).collect()),
+        Json::Object(map) => Value::InlineTable(
+            map.iter().filter_map(|(k, v)| Some((k.as_str(), to_value(k, v)?))).collect::<InlineTable>(),
+        ),
+    })
+}
+
+fn from_table(table: &Table) -> Json {
+    Json::Object(table.iter().filter_map(|(key, item)| Some((key.to_string(), from_item(item)?))).collect())
+}
+
+fn from_item(item: &Item) -> Option<Json> {
+    match item {
+        Item::None => None,
+        Item::Value(value) => Some(from_value(value)),
+        Item::Table(table) => Some(from_table(table)),
+        Item::ArrayOfTables(tables) => Some(Json::Array(tables.iter().map(from_table).collect())),
+    }
+}
+
+fn from_value(value: &Value) -> Json {
+    match value {
+        Value::String(s) => Json::from(s.value().as_str()),
+        Value::Integer(i) => Json::from(*i.value()),
+        Value::Float(f) => Json::from(*f.value()),
+        Value::Boolean(b) => Json::from(*b.value()),
+        Value::Datetime(d) => Json::from(d.value().to_string()),
+        Value::Array(items) => Json::Array(items.iter().map(from_value).collect()),
+        Value::InlineTable(table) => Json::Object(table.iter().map(|(k, v)| (k.to_string(), from_value(v))).collect()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Priority, Status};
+
+    const YAML: &str = "\
+meta:
+  id: T-009
+  title: Dark mode
+  status: todo
+  priority: high
+  created_at: 2025-03-01T09:00:00Z
+  due_date: 2025-04-01
+spec:
+  description: |
+    Follow the system setting.
+  relevant_files: [src/theme.rs]
+verification:
+  command: cargo test theme
+";
+
+    #[test]
+    fn test_yaml_round_trip() {
+        let ticket = TicketFormat::Yaml.parse_ticket(YAML).unwrap();
+        assert_eq!(ticket.meta.priority, Priority::High);
+        assert_eq!(ticket.meta.due_date.unwrap().to_string(), "2025-04-01");
+        assert_eq!(ticket.spec.relevant_files, ["src/theme.rs"]);
+
+        let mut doc = TicketFormat::Yaml.parse_document(YAML).unwrap();
+        crate::store::set_status_in_document(&mut doc, &Status::InProgress);
+        let written = TicketFormat::Yaml.render(&doc).unwrap();
+        // Key order survives the trip through TOML
+        assert!(written.starts_with("meta:\n  id: T-009\n  title: Dark mode\n  status: in_progress\n"));
+        let ticket = TicketFormat::Yaml.parse_ticket(&written).unwrap();
+        assert_eq!(ticket.meta.status, Status::InProgress);
+        assert_eq!(ticket.timing.transitions.len(), 1);
+    }
+
+    #[test]
+    fn test_json_ticket() {
+        let json = r#"{"meta": {"id": "T-010", "title": "Logo", "status": "review", "priority": "low", "owner": null},
+            "spec": {"description": ""}, "verification": {"command": "npm test"}}"#;
+        let ticket = TicketFormat::Json.parse_ticket(json).unwrap();
+        assert_eq!(ticket.meta.status, Status::Review);
+        assert!(ticket.meta.owner.is_none());
+
+        let written = TicketFormat::Json.render(&TicketFormat::Json.parse_document(json).unwrap()).unwrap();
+        assert!(written.starts_with("{\n  \"meta\": {\n    \"id\": \"T-010\""));
+        assert!(TicketFormat::Json.parse_document("[1, 2]").is_err());
+    }
+
+    #[test]
+    fn test_strict_parsing() {
+        let typo = YAML.replace("  priority: high", "  priority: high\n  prioritiy: low");
+        let (ticket, unknown) = TicketFormat::Yaml.parse_ticket_strict(&typo).unwrap();
+        assert_eq!(ticket.meta.priority, Priority::High);
+        assert_eq!(unknown, ["meta.prioritiy"]);
+        assert!(TicketFormat::Yaml.parse_ticket_strict(YAML).unwrap().1.is_empty());
+        let retries = YAML.replace("cargo test theme", "cargo test theme\n  max_retries: 0");
+        let err = TicketFormat::Yaml.parse_ticket_strict(&retries).unwrap_err();
+        assert_eq!(err.to_string(), "verification.max_retries must be between 1 and 10, got 0");
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("T-009.yaml"), &typo).unwrap();
+        std::fs::write(dir.path().join("T-010.yaml"), YAML).unwrap();
+        std::fs::write(dir.path().join("T-011.toml"), "[meta]\nid = \"T-011\"\n").unwrap();
+        let findings = validate_dir(dir.path()).unwrap();
+        assert_eq!(findings.len(), 2);
+        assert_eq!(findings[0].unknown_fields, ["meta.prioritiy"]);
+        assert!(findings[1].error.is_some());
+    }
+
+    #[test]
+    fn test_find_ticket_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("T-009.yaml"), YAML).unwrap();
+        assert_eq!(find_ticket_file(dir.path(), "T-009"), dir.path().join("T-009.yaml"));
+        assert_eq!(find_ticket_file(dir.path(), "T-011"), dir.path().join("T-011.toml"));
+        assert_eq!(read_ticket(&dir.path().join("T-009.yaml")).unwrap().meta.title, "Dark mode");
+    }
+}
@@ -0,0 +1,59 @@
+use std::collections::BTreeMap;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use crate::types::{Ticket, Status};
+
+/// Converts a TOML datetime (which may lack an offset or a time) into UTC.
+/// Local datetimes are assumed to be UTC.
+pub fn to_utc(dt: &toml_datetime::Datetime) -> Option<DateTime<Utc>> {
+    let s = dt.to_string();
+    if let Ok(parsed) = DateTime::parse_from_rfc3339(&s) {
+        return Some(parsed.with_timezone(&Utc));
+    }
+    if let Ok(naive) = NaiveDateTime::parse_from_str(&s, "%Y-%m-%dT%H:%M:%S%.f") {
+        return Some(naive.and_utc());
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(&s, "%Y-%m-%d") {
+        return date.and_hms_opt(0, 0, 0).map(|n| n.and_utc());
+    }
+    None
+}
+
+/// Seconds spent in each status, derived from `created_at` and the recorded transitions.
+/// The current status accrues time up to `now`.
+pub fn time_in_status(ticket: &Ticket, now: DateTime<Utc>) -> BTreeMap<String, i64> {
+    let mut totals = BTreeMap::new();
+    let transitions = &ticket.timing.transitions;
+
+    let mut since = to_utc(&ticket.meta.created_at);
+    let mut current = transitions
+        .first()
+        .and_then(|t| t.from.clone())
+        .unwrap_or_else(|| ticket.meta.status.clone());
+
+    for transition in transitions {
+        if let Some(start) = since {
+            let secs = (transition.at - start).num_seconds().max(0);
+            *totals.entry(current.to_string()).or_insert(0) += secs;
+        }
+        since = Some(transition.at);
+        current = transition.to.clone();
+    }
+
+    if let Some(start) = since {
+        let secs = (now - start).num_seconds().max(0);
+        *totals.entry(current.to_string()).or_insert(0) += secs;
+    }
+
+    totals
+}
+
+/// Seconds from the first move into `in_progress` until the most recent move into `done`.
+pub fn cycle_time_secs(ticket: &Ticket) -> Option<i64> {
+    let transitions = &ticket.timing.transitions;
+    let started = transitions.iter().find(|t| t.to == Status::InProgress)?.at;
+    let finished = transitions.iter().rev().find(|t| t.to == Status::Done)?.at;
+    if finished < started {
+        return None;
+    }
+    Some((finished - started).num_seconds())
+}
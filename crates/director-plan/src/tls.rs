@@ -0,0 +1,79 @@
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use anyhow::{Context, Result};
+use axum::Router;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use hyper_util::server::graceful::GracefulShutdown;
+use hyper_util::service::TowerToHyperService;
+use rustls_pki_types::pem::PemObject;
+use rustls_pki_types::{CertificateDer, PrivateKeyDer};
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+use tokio_rustls::rustls::{self, ServerConfig};
+use tower::Service;
+use tracing::{debug, warn};
+use crate::shutdown::Shutdown;
+
+/// Reads a PEM certificate chain and private key for `serve --tls-cert/--tls-key`.
+pub fn load_config(cert_path: &Path, key_path: &Path) -> Result<Arc<ServerConfig>> {
+    let certs = CertificateDer::pem_file_iter(cert_path)
+        .with_context(|| format!("Failed to read certificate {:?}", cert_path))?
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("Invalid certificate {:?}", cert_path))?;
+    let key = PrivateKeyDer::from_pem_file(key_path)
+        .with_context(|| format!("Failed to read private key {:?}", key_path))?;
+
+    let mut config = ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .context("No usable TLS protocol versions")?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .context("Certificate and private key don't match")?;
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(Arc::new(config))
+}
+
+/// Serves `app` over HTTPS until `shutdown`, then waits for open connections to finish.
+/// Handlers see the client's address as `ConnectInfo<SocketAddr>`, as with plain HTTP.
+pub async fn serve(listener: TcpListener, app: Router, tls: Arc<ServerConfig>, shutdown: Shutdown) {
+    let acceptor = TlsAcceptor::from(tls);
+    let mut make_service = app.into_make_service_with_connect_info::<SocketAddr>();
+    let graceful = GracefulShutdown::new();
+
+    loop {
+        let (stream, addr) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    warn!("Failed to accept connection: {}", e);
+                    continue;
+                }
+            },
+            _ = shutdown.wait() => break,
+        };
+        let service = match make_service.call(addr).await {
+            Ok(service) => service,
+            Err(never) => match never {},
+        };
+        let acceptor = acceptor.clone();
+        let watcher = graceful.watcher();
+        tokio::spawn(async move {
+            let stream = match acceptor.accept(stream).await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    debug!("TLS handshake with {} failed: {}", addr, e);
+                    return;
+                }
+            };
+            let builder = auto::Builder::new(TokioExecutor::new());
+            let conn = builder.serve_connection_with_upgrades(TokioIo::new(stream), TowerToHyperService::new(service));
+            if let Err(e) = watcher.watch(conn.into_owned()).await {
+                debug!("Connection from {} closed with an error: {}", addr, e);
+            }
+        });
+    }
+
+    graceful.shutdown().await;
+}
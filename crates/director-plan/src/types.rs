@@ -1,4 +1,6 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use crate::config::{self, VerificationConfig};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Ticket {
@@ -7,6 +9,37 @@ pub struct Ticket {
     pub verification: Verification,
     #[serde(default)]
     pub history: History,
+    #[serde(default)]
+    pub timing: Timing,
+    #[serde(default)]
+    pub execution: Execution,
+}
+
+/// How the agent is run for this ticket.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Execution {
+    /// Extra environment for the agent. Values of the form `secret:NAME` are resolved from
+    /// `[secrets]` at run time and never written to prompts or run history.
+    #[serde(default)]
+    pub env: std::collections::BTreeMap<String, String>,
+    /// Agent commands to fall back on, in order, in place of `[execution] agents`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub agents: Vec<String>,
+}
+
+impl Execution {
+    /// The agents a run tries in turn: `first` (from `--agent` or `RADKIT_AGENT_CMD`) unless
+    /// it's empty, then the ticket's `agents`, or `defaults` if it has none.
+    pub fn agent_chain(&self, first: &str, defaults: &[String]) -> Vec<String> {
+        let chain = if self.agents.is_empty() { defaults } else { &self.agents };
+        let mut agents: Vec<String> = Some(first.to_string()).filter(|a| !a.is_empty()).into_iter().collect();
+        for agent in chain {
+            if !agents.contains(agent) {
+                agents.push(agent.clone());
+            }
+        }
+        agents
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -20,6 +53,28 @@ pub struct Meta {
     pub owner: Option<String>,
     #[serde(default = "default_created_at")]
     pub created_at: toml_datetime::Datetime,
+    pub due_date: Option<toml_datetime::Datetime>,
+    /// The ticket this one was split from; the parent stays blocked until its children are done
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    /// Tickets that must be done before this one can be started
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    /// The ticket whose run suggested this one (`follow_up_tickets` in the agent's result)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub follow_up_of: Option<String>,
+    /// Why the ticket is `blocked`; cleared when it leaves that status
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blocked_reason: Option<String>,
+    /// The issue this ticket was imported from, e.g. `jira:PROJ-12`; see [`crate::import`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_ref: Option<String>,
+    /// Ticket format version, upgraded by `director-plan migrate`; see [`crate::migrate`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_version: Option<u32>,
+    /// Cron expression on a template in `plan/templates/`; see [`crate::schedule`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<String>,
 }
 
 fn default_created_at() -> toml_datetime::Datetime {
@@ -28,12 +83,14 @@ fn default_created_at() -> toml_datetime::Datetime {
     toml_datetime::Datetime { date: Some(d), time: Some(t), offset: None }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum Status {
     Todo,
     InProgress,
     Review,
+    /// Parked while waiting on something outside the plan; see `meta.blocked_reason`
+    Blocked,
     Done,
     Archived,
 }
@@ -44,13 +101,15 @@ impl ToString for Status {
             Status::Todo => "todo".to_string(),
             Status::InProgress => "in_progress".to_string(),
             Status::Review => "review".to_string(),
+            Status::Blocked => "blocked".to_string(),
             Status::Done => "done".to_string(),
             Status::Archived => "archived".to_string(),
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+// Variant order matters: `Ord` is used to compare priorities (e.g. SLA escalation).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum Priority {
     Low,
@@ -59,7 +118,7 @@ pub enum Priority {
     Critical,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum TicketType {
     Feature,
@@ -68,6 +127,17 @@ pub enum TicketType {
     Spike,
 }
 
+impl TicketType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TicketType::Feature => "feature",
+            TicketType::Bug => "bug",
+            TicketType::Chore => "chore",
+            TicketType::Spike => "spike",
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Spec {
     pub description: String,
@@ -77,20 +147,248 @@ pub struct Spec {
     pub relevant_files: Vec<String>,
     #[serde(default)]
     pub auto_context: bool,
+    /// Globs (relative to the repo root) the agent may change. Empty means anything.
+    #[serde(default)]
+    pub allowed_paths: Vec<String>,
+    /// Files in `assets/` that belong to this ticket, e.g. design mockups. They are listed in
+    /// the agent's context and shown in the UI.
+    #[serde(default)]
+    pub assets: Vec<String>,
+    /// A `[context.profiles]` entry shaping this ticket's context. When unset, the profile
+    /// `[context.type_profiles]` gives for the ticket's type, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_profile: Option<String>,
+    /// Globs kept out of the context, whether discovery or the dependency graph found them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub context_exclude: Vec<String>,
+    /// Files always in the context, in full, whatever discovery and the profile say.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub context_pin: Vec<String>,
+    /// Context files cut down to their signatures, however close the dependency graph has them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub context_prune: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Verification {
     pub command: String,
     pub golden_image: Option<String>,
-    #[serde(default = "default_max_retries")]
-    pub max_retries: u32,
-    #[serde(default = "default_confidence_threshold")]
-    pub min_confidence: f32,
+    /// Page captured for `golden_image`, and the default for snapshots. `http://localhost:3000` when unset.
+    pub url: Option<String>,
+    /// CSS selector that must be present before the screenshot is taken.
+    pub wait_for: Option<String>,
+    /// Attempts before a run gives up, 1–10. Falls back to `[verification]` in `plan/config.toml`.
+    pub max_retries: Option<u32>,
+    /// Results the agent is less confident about (0–1) go to review instead of being merged.
+    /// Falls back to `[verification]` in `plan/config.toml`.
+    pub min_confidence: Option<f32>,
+    /// Extra environment for the verification command (same `secret:NAME` syntax as `execution.env`).
+    #[serde(default)]
+    pub env: std::collections::BTreeMap<String, String>,
+    /// Named screenshots, each compared against its own golden (`[[verification.visual]]`).
+    #[serde(default)]
+    pub visual: Vec<VisualSnapshot>,
+    /// How screenshots are compared against goldens.
+    #[serde(default)]
+    pub compare: ImageCompare,
+    /// Accessibility-tree or DOM snapshots diffed against golden JSON (`[[verification.structure]]`).
+    #[serde(default)]
+    pub structure: Vec<StructureSnapshot>,
+    pub perf: Option<PerfBudget>,
+    /// Lowest acceptable change in line coverage against the base commit, in percentage
+    /// points. `0.0` forbids any drop. Needs `[coverage]` in `plan/config.toml`.
+    pub min_coverage_delta: Option<f64>,
+    /// More commands to verify with (`[[verification.steps]]`), scheduled by their dependencies.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<VerificationStep>,
+    /// Run only the tests the agent's changes affect, on every attempt but the last.
+    pub scoped: Option<ScopedVerification>,
+}
+
+/// `[verification.scoped]`: instead of `command`, a command covering only what the changed
+/// files (and the files importing them) can break. Once it passes, `command` runs in full
+/// before the attempt counts as passed, so a scoped pass is never the final word.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScopedVerification {
+    pub runner: Option<ScopedRunner>,
+    /// Overrides `runner`. `{changed}` is replaced by the changed files, `{tests}` by the test
+    /// files they affect, `{packages}` by `-p <name>` for each Cargo package they affect.
+    pub command: Option<String>,
 }
 
-fn default_max_retries() -> u32 { 5 }
-fn default_confidence_threshold() -> f32 { 0.8 }
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScopedRunner {
+    /// `cargo test -p <package>...`
+    Cargo,
+    /// `npx vitest related --run <changed files>`
+    Vitest,
+    /// `npx jest --findRelatedTests <changed files>`
+    Jest,
+    /// `pytest <affected test files>`
+    Pytest,
+}
+
+impl ScopedRunner {
+    pub fn template(self) -> &'static str {
+        match self {
+            ScopedRunner::Cargo => "cargo test {packages}",
+            ScopedRunner::Vitest => "npx vitest related --run {changed}",
+            ScopedRunner::Jest => "npx jest --findRelatedTests {changed}",
+            ScopedRunner::Pytest => "pytest {tests}",
+        }
+    }
+}
+
+/// One verification command among several. `command` itself is the step called `command`,
+/// which runs first and alone.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VerificationStep {
+    /// Unique within the ticket; what `depends_on` refers to.
+    pub name: String,
+    pub command: String,
+    /// Steps that must pass before this one starts.
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// Safe to run at the same time as other parallel steps. Steps that aren't run alone.
+    #[serde(default)]
+    pub parallel: bool,
+}
+
+/// Limits checked after the verification command (`verification.perf`). Unset limits aren't checked.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct PerfBudget {
+    /// Page to measure. Defaults to `verification.url`.
+    pub url: Option<String>,
+    /// Largest Contentful Paint
+    pub max_lcp_ms: Option<f64>,
+    /// Time until the load event finished
+    pub max_load_ms: Option<f64>,
+    /// Total size of the files matched by `bundle`
+    pub max_bundle_kb: Option<u64>,
+    /// Glob for the build output, relative to the workspace. Defaults to `dist/**/*.js`.
+    pub bundle: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum StructureKind {
+    /// The browser's accessibility tree (roles and accessible names)
+    #[default]
+    Accessibility,
+    /// Element tags, a few identifying attributes and direct text
+    Dom,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StructureSnapshot {
+    /// Used for the output file name, `proof/<name>.json`.
+    pub name: String,
+    #[serde(default)]
+    pub kind: StructureKind,
+    /// Defaults to `verification.url`.
+    pub url: Option<String>,
+    pub viewport: Option<String>,
+    /// Defaults to `verification.wait_for`.
+    pub wait_for: Option<String>,
+    /// `dom` only: snapshot just this element instead of `body`.
+    pub selector: Option<String>,
+    /// Golden JSON, as written to `proof/<name>.json` by an accepted run.
+    pub golden: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CompareMethod {
+    /// Per-channel RGB distance
+    #[default]
+    Pixel,
+    /// Perceptual colour distance (CIE76 in Lab space)
+    DeltaE,
+    /// Structural similarity of the luma channel
+    Ssim,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ImageCompare {
+    pub method: CompareMethod,
+    /// `pixel`: channel difference (0-255) still counted as equal.
+    pub tolerance: u8,
+    /// `delta_e`: colour distance still counted as equal. About 2.3 is just noticeable.
+    pub max_delta_e: f64,
+    /// `ssim`: lowest acceptable mean similarity (1.0 is identical).
+    pub min_ssim: f64,
+    /// `pixel` / `delta_e`: share of differing pixels allowed before the check fails.
+    pub max_mismatch_percent: f64,
+}
+
+impl Default for ImageCompare {
+    fn default() -> Self {
+        Self {
+            method: CompareMethod::Pixel,
+            tolerance: 0,
+            max_delta_e: 2.3,
+            min_ssim: 0.98,
+            max_mismatch_percent: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VisualSnapshot {
+    /// Used for the output file names, e.g. `proof/<name>.png`.
+    pub name: String,
+    /// Defaults to `verification.url`.
+    pub url: Option<String>,
+    /// `WIDTHxHEIGHT`, e.g. `390x844`. The capture backend's default viewport when unset.
+    pub viewport: Option<String>,
+    /// Defaults to `verification.wait_for`.
+    pub wait_for: Option<String>,
+    pub golden: String,
+    /// Defaults to `verification.compare`.
+    pub compare: Option<ImageCompare>,
+}
+
+impl Verification {
+    pub fn max_retries(&self, defaults: &VerificationConfig) -> u32 {
+        self.max_retries.unwrap_or(defaults.max_retries)
+    }
+
+    pub fn min_confidence(&self, defaults: &VerificationConfig) -> f32 {
+        self.min_confidence.unwrap_or(defaults.min_confidence)
+    }
+
+    /// `command` (unless empty) followed by `steps`: everything [`crate::verification::steps::run`]
+    /// should run for this ticket.
+    pub fn command_steps(&self) -> Vec<VerificationStep> {
+        let command = (!self.command.trim().is_empty()).then(|| VerificationStep {
+            name: crate::verification::steps::COMMAND_STEP.to_string(),
+            command: self.command.clone(),
+            depends_on: Vec::new(),
+            parallel: false,
+        });
+        command.into_iter().chain(self.steps.iter().cloned()).collect()
+    }
+
+    /// Rejects a `max_retries` or `min_confidence` out of range, `steps` that can't be
+    /// scheduled, and a `scoped` section with nothing to run.
+    pub fn check(&self) -> anyhow::Result<()> {
+        if let Some(scoped) = &self.scoped
+            && scoped.runner.is_none()
+            && scoped.command.is_none()
+        {
+            anyhow::bail!("verification.scoped needs a runner or a command");
+        }
+        if let Some(max_retries) = self.max_retries {
+            config::check_max_retries(max_retries).map_err(|e| anyhow::anyhow!("verification.{}", e))?;
+        }
+        if let Some(min_confidence) = self.min_confidence {
+            config::check_min_confidence(min_confidence).map_err(|e| anyhow::anyhow!("verification.{}", e))?;
+        }
+        crate::verification::steps::check(&self.command_steps())
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct History {
@@ -98,8 +396,22 @@ pub struct History {
     pub log: Vec<String>,
 }
 
-// Frontend DTOs
+/// Status transitions, appended by `store::set_status_in_document`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Timing {
+    #[serde(default)]
+    pub transitions: Vec<Transition>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Transition {
+    pub from: Option<Status>,
+    pub to: Status,
+    pub at: chrono::DateTime<chrono::Utc>,
+}
+
+// Frontend DTOs
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct FrontendTicket {
     pub id: String,
     pub title: String,
@@ -112,19 +424,65 @@ pub struct FrontendTicket {
     pub artifacts: Option<Artifacts>,
     pub logs: Option<Vec<String>>,
     pub specs: Option<String>,
+    pub due_date: Option<String>,
+    pub due_state: Option<String>,
+    /// Names of the attached files in `assets/`, served under `/assets/<name>`
+    #[serde(default)]
+    pub assets: Vec<String>,
+    #[serde(default)]
+    pub parent: Option<String>,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    #[serde(default)]
+    pub follow_up_of: Option<String>,
+    #[serde(default)]
+    pub blocked_reason: Option<String>,
+    /// Open children and dependencies; until they are done this ticket can't be started or
+    /// closed. Filled in by the server.
+    #[serde(default)]
+    pub blocked_by: Vec<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, ToSchema)]
 pub struct Metrics {
-    pub render_time_ms: f64,
-    pub render_time_diff: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub render_time_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub render_time_diff: Option<String>,
+    /// Size of the build output from the latest `verification.perf` check
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bundle_kb: Option<u64>,
+    /// Seconds spent in each status so far
+    #[serde(default)]
+    pub time_in_status_secs: std::collections::BTreeMap<String, i64>,
+    /// Total agent execution time across all runs
+    #[serde(default)]
+    pub agent_time_ms: u64,
+    /// Total verification time across all runs
+    #[serde(default)]
+    pub verification_time_ms: u64,
+    #[serde(default)]
+    pub executions: usize,
+    /// What the agent reported in the latest run that sent a result block
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_result: Option<crate::agent_result::AgentResult>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct Artifacts {
     pub before_image: String,
     pub after_image: String,
     pub diff_image: Option<String>,
+    /// One entry per `[[verification.visual]]` snapshot
+    #[serde(default)]
+    pub snapshots: Vec<SnapshotArtifacts>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct SnapshotArtifacts {
+    pub name: String,
+    pub before_image: String,
+    pub after_image: String,
 }
 
 impl From<Ticket> for FrontendTicket {
@@ -141,6 +499,14 @@ impl From<Ticket> for FrontendTicket {
             artifacts: None,
             logs: if ticket.history.log.is_empty() { None } else { Some(ticket.history.log.clone()) },
             specs: Some(ticket.spec.description.clone()), // Mapping spec description to specs as well? Or raw TOML?
+            due_date: ticket.meta.due_date.map(|d| d.to_string()),
+            due_state: None, // Depends on the workspace SLA config, filled in by the server
+            assets: ticket.spec.assets.clone(),
+            parent: ticket.meta.parent.clone(),
+            depends_on: ticket.meta.depends_on.clone(),
+            follow_up_of: ticket.meta.follow_up_of.clone(),
+            blocked_reason: ticket.meta.blocked_reason.clone(),
+            blocked_by: Vec::new(),
         }
     }
 }
@@ -152,6 +518,10 @@ pub struct TicketSummary {
     pub title: String,
     pub status: Status,
     pub priority: Priority,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due_date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due_state: Option<crate::sla::DueState>,
 }
 
 #[cfg(test)]
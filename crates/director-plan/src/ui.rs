@@ -0,0 +1,115 @@
+use std::path::{Path, PathBuf};
+use axum::http::StatusCode;
+#[cfg(feature = "embed-ui")]
+use axum::http::{header, Uri};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::{any, any_service, MethodRouter};
+use tower_http::services::ServeDir;
+
+/// The Studio's built files, baked in at compile time with `--features embed-ui`.
+/// Build the frontend (`npm run build` in apps/director-plan) first.
+#[cfg(feature = "embed-ui")]
+#[derive(rust_embed::Embed)]
+#[folder = "../../apps/director-plan/dist/"]
+struct Embedded;
+
+/// Where `serve` gets the Studio from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UiSource {
+    Dir(PathBuf),
+    #[cfg(feature = "embed-ui")]
+    Embedded,
+}
+
+impl UiSource {
+    /// `--ui-dir` if given, else the embedded build if there is one, else the workspace's
+    /// `apps/director-plan/dist`.
+    pub fn resolve(ui_dir: Option<PathBuf>, workspace_root: &Path) -> Self {
+        if let Some(dir) = ui_dir {
+            return UiSource::Dir(dir);
+        }
+        #[cfg(feature = "embed-ui")]
+        if Embedded::get("index.html").is_some() {
+            return UiSource::Embedded;
+        }
+        UiSource::Dir(workspace_root.join("apps/director-plan/dist"))
+    }
+}
+
+/// Serves the Studio. Unknown paths get `index.html`, which behind a base path gains a
+/// `<base href>` so its relative asset and API URLs resolve under it.
+pub fn service(source: UiSource, base_path: &str) -> MethodRouter {
+    let base_href = format!("{}/", base_path);
+    match source {
+        UiSource::Dir(dist) => {
+            let index = dist.join("index.html");
+            let index_service = any(move || {
+                let (index, base_href) = (index.clone(), base_href.clone());
+                async move {
+                    match tokio::fs::read_to_string(&index).await {
+                        Ok(html) => Html(inject_base_href(&html, &base_href)).into_response(),
+                        Err(_) => not_built(),
+                    }
+                }
+            });
+            any_service(ServeDir::new(dist).fallback(index_service))
+        }
+        #[cfg(feature = "embed-ui")]
+        UiSource::Embedded => any(move |uri: Uri| {
+            let base_href = base_href.clone();
+            async move { embedded_file(&uri, &base_href) }
+        }),
+    }
+}
+
+#[cfg(feature = "embed-ui")]
+fn embedded_file(uri: &Uri, base_href: &str) -> Response {
+    let path = uri.path().trim_start_matches('/');
+    if !path.is_empty() && path != "index.html"
+        && let Some(file) = Embedded::get(path)
+    {
+        let mime = mime_guess::from_path(path).first_or_octet_stream();
+        return ([(header::CONTENT_TYPE, mime.as_ref().to_string())], file.data.into_owned()).into_response();
+    }
+    match Embedded::get("index.html") {
+        Some(index) => {
+            let html = String::from_utf8_lossy(&index.data);
+            ([(header::CACHE_CONTROL, "no-cache")], Html(inject_base_href(&html, base_href))).into_response()
+        }
+        None => not_built(),
+    }
+}
+
+fn not_built() -> Response {
+    (StatusCode::NOT_FOUND, "Frontend not built: run `npm run build` in apps/director-plan").into_response()
+}
+
+fn inject_base_href(html: &str, base_href: &str) -> String {
+    if base_href == "/" || html.contains("<base ") {
+        return html.to_string();
+    }
+    html.replacen("<head>", &format!("<head>\n    <base href=\"{}\" />", base_href), 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inject_base_href() {
+        let html = "<html><head><title>t</title></head></html>";
+        assert_eq!(inject_base_href(html, "/"), html);
+        assert_eq!(
+            inject_base_href(html, "/director/"),
+            "<html><head>\n    <base href=\"/director/\" /><title>t</title></head></html>",
+        );
+    }
+
+    #[test]
+    fn test_ui_dir_overrides() {
+        let root = Path::new("/repo");
+        assert_eq!(UiSource::resolve(Some(PathBuf::from("/srv/ui")), root), UiSource::Dir(PathBuf::from("/srv/ui")));
+        #[cfg(not(feature = "embed-ui"))]
+        assert_eq!(UiSource::resolve(None, root), UiSource::Dir(PathBuf::from("/repo/apps/director-plan/dist")));
+    }
+}
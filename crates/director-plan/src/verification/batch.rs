@@ -0,0 +1,165 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+use anyhow::{Context, Result, anyhow};
+use rayon::prelude::*;
+use serde::Serialize;
+use crate::config::PlanConfig;
+use crate::events::{self, PlanEvent};
+use crate::secrets::ResolvedEnv;
+use crate::types::Ticket;
+use crate::verification::{policy, steps};
+use crate::verification::steps::StepRun;
+use crate::verification::test_report::TestCase;
+
+/// The outcome of one ticket's verification command in `verify-all`.
+#[derive(Debug, Clone, Serialize)]
+pub struct TicketVerification {
+    pub id: String,
+    pub title: String,
+    pub command: String,
+    pub passed: bool,
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+    /// Combined stdout and stderr, with secrets redacted
+    pub output: String,
+    /// Why the command couldn't be run at all (empty command, policy, missing secret)
+    pub error: Option<String>,
+    /// Each step on its own, for tickets with `[[verification.steps]]`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<StepRun>,
+}
+
+impl TicketVerification {
+    pub fn test_case(&self) -> TestCase {
+        let result = if self.passed {
+            Ok(())
+        } else {
+            let reason = match (&self.error, self.exit_code) {
+                (Some(error), _) => error.clone(),
+                (None, Some(code)) => format!("`{}` exited with code {}", self.command, code),
+                (None, None) => format!("`{}` was killed by a signal", self.command),
+            };
+            Err(anyhow!("{}\n{}", reason, self.output))
+        };
+        TestCase::from_result(&self.id, Duration::from_millis(self.duration_ms), &result)
+    }
+}
+
+/// Runs one ticket's verification command and `[[verification.steps]]` with their output
+/// captured, so several tickets can run at once without interleaving.
+pub fn verify_ticket(workspace_root: &Path, config: &PlanConfig, ticket: &Ticket) -> TicketVerification {
+    let mut result = TicketVerification {
+        id: ticket.meta.id.clone(),
+        title: ticket.meta.title.clone(),
+        command: ticket.verification.command.clone(),
+        passed: false,
+        exit_code: None,
+        duration_ms: 0,
+        output: String::new(),
+        error: None,
+        steps: Vec::new(),
+    };
+    let started = Instant::now();
+    let graph = ticket.verification.command_steps();
+    let run = || -> Result<(ResolvedEnv, Vec<StepRun>)> {
+        if graph.is_empty() {
+            return Err(anyhow!("Verification command is empty"));
+        }
+        let env = ResolvedEnv::resolve(workspace_root, &ticket.verification.env)?;
+        let runs = steps::run(&graph, config.verification.step_jobs(), |step| {
+            policy::build_command(&config.policy, &step.command, workspace_root)?
+                .envs(env.vars())
+                .output()
+                .context("Failed to execute verification command")
+        })?;
+        Ok((env, runs))
+    };
+    match run() {
+        Ok((env, mut runs)) => {
+            for run in &mut runs {
+                run.stdout = env.redact(&run.stdout);
+                run.stderr = env.redact(&run.stderr);
+            }
+            result.passed = runs.iter().all(|r| r.passed);
+            let deciding = runs.iter().find(|r| !r.passed && r.skipped.is_none()).or(runs.last());
+            result.exit_code = deciding.and_then(|r| r.exit_code);
+            result.error = deciding.and_then(|r| r.error.clone());
+            result.output = match runs.as_slice() {
+                [only] => format!("{}{}", only.stdout, only.stderr),
+                _ => runs.iter()
+                    .map(|r| match &r.skipped {
+                        Some(reason) => format!("== {} (skipped: {}) ==\n", r.name, reason),
+                        None => format!("== {} ==\n{}{}", r.name, r.stdout, r.stderr),
+                    })
+                    .collect(),
+            };
+            if !ticket.verification.steps.is_empty() {
+                result.steps = runs;
+            }
+        }
+        Err(e) => result.error = Some(format!("{:#}", e)),
+    }
+    result.duration_ms = started.elapsed().as_millis() as u64;
+    events::emit(workspace_root, PlanEvent::VerificationFinished { id: result.id.clone(), run_id: None, passed: result.passed });
+    result
+}
+
+/// Verifies `tickets`, at most `jobs` at a time. Results are in the order of `tickets`.
+pub fn verify_all(workspace_root: &Path, config: &PlanConfig, tickets: &[Ticket], jobs: usize) -> Result<Vec<TicketVerification>> {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs.max(1))
+        .build()
+        .context("Failed to start verification threads")?;
+    Ok(pool.install(|| {
+        tickets.par_iter()
+            .map(|ticket| {
+                crate::progress!("Verifying {}: {}", ticket.meta.id, ticket.verification.command);
+                verify_ticket(workspace_root, config, ticket)
+            })
+            .collect()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ticket(id: &str, command: &str) -> Ticket {
+        let toml = format!(
+            "[meta]\nid = \"{}\"\ntitle = \"t\"\nstatus = \"review\"\npriority = \"low\"\n\n[spec]\ndescription = \"\"\n\n[verification]\ncommand = \"{}\"\n",
+            id, command,
+        );
+        toml_edit::de::from_str(&toml).unwrap()
+    }
+
+    #[test]
+    fn test_verify_all() {
+        let dir = tempfile::tempdir().unwrap();
+        let tickets = [ticket("T-1", "echo ok"), ticket("T-2", "exit 3"), ticket("T-3", "")];
+
+        let results = verify_all(dir.path(), &PlanConfig::default(), &tickets, 2).unwrap();
+        let ids: Vec<&str> = results.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["T-1", "T-2", "T-3"]);
+
+        assert!(results[0].passed);
+        assert_eq!(results[0].output.trim(), "ok");
+        assert!(!results[1].passed);
+        assert_eq!(results[1].exit_code, Some(3));
+        assert_eq!(results[2].error.as_deref(), Some("Verification command is empty"));
+        assert_eq!(results[1].test_case().name, "T-2");
+    }
+
+    #[test]
+    fn test_verify_steps() {
+        let dir = tempfile::tempdir().unwrap();
+        let toml = "[meta]\nid = \"T-4\"\ntitle = \"t\"\nstatus = \"review\"\npriority = \"low\"\n\n[spec]\ndescription = \"\"\n\n\
+            [verification]\ncommand = \"echo build\"\n\n\
+            [[verification.steps]]\nname = \"unit\"\ncommand = \"exit 1\"\nparallel = true\n\n\
+            [[verification.steps]]\nname = \"e2e\"\ncommand = \"echo e2e\"\ndepends_on = [\"unit\"]\n";
+        let result = verify_ticket(dir.path(), &PlanConfig::default(), &toml_edit::de::from_str(toml).unwrap());
+        assert!(!result.passed);
+        assert_eq!(result.exit_code, Some(1));
+        assert_eq!(result.steps.len(), 3);
+        assert_eq!(result.output, "== command ==\nbuild\n== unit ==\n== e2e (skipped: unit did not pass) ==\n");
+    }
+}
@@ -0,0 +1,47 @@
+use std::path::Path;
+use std::process::Command;
+use anyhow::{Context, Result, anyhow};
+use crate::config::VisualConfig;
+use super::cdp;
+
+/// Used when neither the snapshot nor the ticket names a page.
+pub const DEFAULT_URL: &str = "http://localhost:3000";
+
+/// What to screenshot.
+pub struct CaptureTarget<'a> {
+    pub url: &'a str,
+    pub viewport: Option<(u32, u32)>,
+    /// CSS selector to wait for before taking the screenshot.
+    pub wait_for: Option<&'a str>,
+}
+
+/// Writes a full-page PNG of `target` to `output` using the `[visual] backend`.
+pub fn capture(workspace_root: &Path, config: &VisualConfig, target: &CaptureTarget, output: &Path) -> Result<()> {
+    match config.backend.as_str() {
+        "cdp" => cdp::capture(config, target, output),
+        "playwright" => playwright(workspace_root, config, target, output),
+        other => Err(anyhow!("Unknown [visual] backend '{}', expected `cdp` or `playwright`", other)),
+    }
+}
+
+/// `npx playwright test scripts/snapshot.spec.ts`, run from the Playwright project so its config and deps are found.
+fn playwright(workspace_root: &Path, config: &VisualConfig, target: &CaptureTarget, output: &Path) -> Result<()> {
+    let mut cmd = Command::new("npx");
+    cmd.current_dir(workspace_root.join(&config.playwright_dir))
+        .args(["playwright", "test", "scripts/snapshot.spec.ts"])
+        .env("TARGET_URL", target.url)
+        // Playwright runs relative to its project root, so pass an absolute output path
+        .env("OUTPUT", output);
+    if let Some((width, height)) = target.viewport {
+        cmd.env("VIEWPORT", format!("{}x{}", width, height));
+    }
+    if let Some(selector) = target.wait_for {
+        cmd.env("WAIT_FOR", selector);
+    }
+
+    let status = cmd.stdout(crate::output::child_stdout()).status().context("Failed to execute playwright script")?;
+    if !status.success() {
+        return Err(anyhow!("Playwright screenshot capture failed"));
+    }
+    Ok(())
+}
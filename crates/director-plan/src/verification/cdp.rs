@@ -0,0 +1,354 @@
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use anyhow::{Context, Result, anyhow};
+use base64::Engine;
+use serde_json::{Value, json};
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+use crate::config::VisualConfig;
+use crate::types::StructureKind;
+use super::capture::CaptureTarget;
+use super::structure::{StructNode, from_ax_nodes};
+
+// Tried in order on PATH when `[visual] chrome` isn't set
+const BROWSERS: &[&str] = &[
+    "chromium",
+    "chromium-browser",
+    "google-chrome",
+    "google-chrome-stable",
+    "chrome",
+    "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
+];
+
+/// Takes a full-page PNG of `target.url` with a headless Chrome/Chromium, talking
+/// DevTools protocol over its websocket.
+pub fn capture(config: &VisualConfig, target: &CaptureTarget, output: &Path) -> Result<()> {
+    let (_browser, mut cdp, session) = open_page(config, target)?;
+    let page = Some(session.as_str());
+
+    // Full page: clip to the document size rather than the viewport
+    let metrics = cdp.call(page, "Page.getLayoutMetrics", json!({}))?;
+    let size = if metrics["cssContentSize"].is_object() { &metrics["cssContentSize"] } else { &metrics["contentSize"] };
+    let width = size["width"].as_f64().unwrap_or(0.0).ceil();
+    let height = size["height"].as_f64().unwrap_or(0.0).ceil();
+
+    let shot = cdp.call(page, "Page.captureScreenshot", json!({
+        "format": "png",
+        "captureBeyondViewport": true,
+        "clip": { "x": 0, "y": 0, "width": width, "height": height, "scale": 1 },
+    }))?;
+    let data = shot["data"].as_str()
+        .ok_or_else(|| anyhow!("Page.captureScreenshot returned no data"))?;
+    let png = base64::engine::general_purpose::STANDARD.decode(data)
+        .context("Failed to decode screenshot")?;
+    fs::write(output, png).with_context(|| format!("Failed to write screenshot to {:?}", output))?;
+
+    Ok(())
+}
+
+/// Starts a browser and loads `target` in a fresh tab: viewport applied, load event seen,
+/// `wait_for` present. Returns the DevTools session id for that tab.
+fn open_page(config: &VisualConfig, target: &CaptureTarget) -> Result<(Browser, Connection, String)> {
+    let deadline = Instant::now() + Duration::from_secs(config.timeout_secs);
+    let browser = Browser::launch(config, deadline)?;
+    let mut cdp = Connection::open(&browser.ws_url, deadline)?;
+
+    let created = cdp.call(None, "Target.createTarget", json!({ "url": "about:blank" }))?;
+    let target_id = created["targetId"].as_str()
+        .ok_or_else(|| anyhow!("Target.createTarget returned no targetId"))?;
+    let attached = cdp.call(None, "Target.attachToTarget", json!({ "targetId": target_id, "flatten": true }))?;
+    let session = attached["sessionId"].as_str()
+        .ok_or_else(|| anyhow!("Target.attachToTarget returned no sessionId"))?
+        .to_string();
+    let page = Some(session.as_str());
+
+    if let Some((width, height)) = target.viewport {
+        cdp.call(page, "Emulation.setDeviceMetricsOverride", json!({
+            "width": width,
+            "height": height,
+            "deviceScaleFactor": 1,
+            "mobile": false,
+        }))?;
+    }
+
+    cdp.call(page, "Page.enable", json!({}))?;
+    let navigated = cdp.call(page, "Page.navigate", json!({ "url": target.url }))?;
+    if let Some(error) = navigated["errorText"].as_str() {
+        return Err(anyhow!("Failed to load {}: {}", target.url, error));
+    }
+    cdp.wait_for_event(page, "Page.loadEventFired")
+        .with_context(|| format!("{} did not finish loading", target.url))?;
+
+    if let Some(selector) = target.wait_for {
+        // serde_json quoting doubles as JS string escaping
+        let expression = format!("!!document.querySelector({})", Value::from(selector));
+        loop {
+            let result = cdp.call(page, "Runtime.evaluate", json!({ "expression": expression, "returnByValue": true }))?;
+            if result["result"]["value"].as_bool() == Some(true) {
+                break;
+            }
+            if Instant::now() > deadline {
+                return Err(anyhow!("Timed out waiting for selector `{}` on {}", selector, target.url));
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+    }
+
+    Ok((browser, cdp, session))
+}
+
+/// Loads `target` and returns its accessibility tree or normalized DOM.
+pub fn capture_structure(
+    config: &VisualConfig,
+    target: &CaptureTarget,
+    kind: StructureKind,
+    selector: Option<&str>,
+) -> Result<StructNode> {
+    let (_browser, mut cdp, session) = open_page(config, target)?;
+    let page = Some(session.as_str());
+
+    match kind {
+        StructureKind::Accessibility => {
+            cdp.call(page, "Accessibility.enable", json!({}))?;
+            let tree = cdp.call(page, "Accessibility.getFullAXTree", json!({}))?;
+            let nodes = tree["nodes"].as_array().cloned().unwrap_or_default();
+            from_ax_nodes(&nodes).ok_or_else(|| anyhow!("{} has an empty accessibility tree", target.url))
+        }
+        StructureKind::Dom => {
+            let expression = DOM_SNAPSHOT_JS.replace("__SELECTOR__", &Value::from(selector.unwrap_or("body")).to_string());
+            let result = cdp.call(page, "Runtime.evaluate", json!({ "expression": expression, "returnByValue": true }))?;
+            let value = result["result"]["value"].clone();
+            if value.is_null() {
+                return Err(anyhow!("No element matches `{}` on {}", selector.unwrap_or("body"), target.url));
+            }
+            serde_json::from_value(value).context("Unexpected DOM snapshot shape")
+        }
+    }
+}
+
+/// Page timings from the browser's Performance API.
+pub struct PageTiming {
+    pub lcp_ms: Option<f64>,
+    pub load_ms: Option<f64>,
+}
+
+/// Loads `target` and reads Largest Contentful Paint and load time, both relative to navigation start.
+pub fn measure_page(config: &VisualConfig, target: &CaptureTarget) -> Result<PageTiming> {
+    let (_browser, mut cdp, session) = open_page(config, target)?;
+    let result = cdp.call(Some(session.as_str()), "Runtime.evaluate", json!({
+        "expression": PAGE_TIMING_JS,
+        "awaitPromise": true,
+        "returnByValue": true,
+    }))?;
+    let value = &result["result"]["value"];
+    Ok(PageTiming {
+        lcp_ms: value["lcp"].as_f64(),
+        load_ms: value["load"].as_f64(),
+    })
+}
+
+// LCP entries are buffered, so observing after load still sees them. Headless pages that never
+// paint anything large get no entry at all, hence the timeout.
+const PAGE_TIMING_JS: &str = r#"new Promise((resolve) => {
+  const nav = performance.getEntriesByType('navigation')[0];
+  const load = nav && nav.loadEventEnd > 0 ? nav.loadEventEnd : null;
+  let lcp = null;
+  try {
+    new PerformanceObserver((list) => {
+      const entries = list.getEntries();
+      lcp = entries[entries.length - 1].startTime;
+    }).observe({ type: 'largest-contentful-paint', buffered: true });
+  } catch (e) {}
+  setTimeout(() => resolve({ lcp, load }), 1000);
+})"#;
+
+// Tag, a few identifying attributes and the element's own text, recursively. Classes and
+// inline styles are left out: they churn without changing what the page means.
+const DOM_SNAPSHOT_JS: &str = r#"(() => {
+  const KEEP = ['id', 'role', 'aria-label', 'href', 'type', 'name', 'alt', 'placeholder'];
+  const SKIP = new Set(['SCRIPT', 'STYLE', 'NOSCRIPT', 'TEMPLATE', 'LINK', 'META']);
+  const walk = (el) => {
+    const attrs = {};
+    for (const a of KEEP) { const v = el.getAttribute(a); if (v !== null) attrs[a] = v; }
+    let text = '';
+    for (const n of el.childNodes) if (n.nodeType === Node.TEXT_NODE) text += n.textContent;
+    return {
+      role: el.tagName.toLowerCase(),
+      name: text.replace(/\s+/g, ' ').trim(),
+      attrs,
+      children: [...el.children].filter(c => !SKIP.has(c.tagName)).map(walk),
+    };
+  };
+  const root = document.querySelector(__SELECTOR__);
+  return root ? walk(root) : null;
+})()"#;
+
+/// A headless browser process with its own throwaway profile. Killed on drop.
+struct Browser {
+    child: Child,
+    profile_dir: PathBuf,
+    ws_url: String,
+}
+
+impl Browser {
+    fn launch(config: &VisualConfig, deadline: Instant) -> Result<Self> {
+        let binary = match &config.chrome {
+            Some(path) => path.clone(),
+            None => find_browser().ok_or_else(|| anyhow!(
+                "No Chrome/Chromium found on PATH; set [visual] chrome, or backend = \"playwright\""
+            ))?,
+        };
+
+        let profile_dir = std::env::temp_dir().join(format!(
+            "director-plan-chrome-{}-{}",
+            std::process::id(),
+            chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+
+        let mut child = Command::new(&binary)
+            .arg("--headless=new")
+            .arg("--remote-debugging-port=0")
+            .arg(format!("--user-data-dir={}", profile_dir.display()))
+            .args(["--no-first-run", "--no-default-browser-check", "--disable-gpu", "--hide-scrollbars", "--mute-audio"])
+            .args(&config.chrome_args)
+            .arg("about:blank")
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to start {}", binary))?;
+
+        // Chrome announces the port on stderr: "DevTools listening on ws://127.0.0.1:PORT/devtools/browser/ID".
+        // The reader thread keeps draining stderr afterwards so the pipe never fills up.
+        let stderr = child.stderr.take().expect("stderr is piped");
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stderr).lines().map_while(|l| l.ok()) {
+                if let Some(url) = line.split("DevTools listening on ").nth(1) {
+                    let _ = tx.send(url.trim().to_string());
+                }
+            }
+        });
+
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        let ws_url = match rx.recv_timeout(timeout) {
+            Ok(url) => url,
+            Err(_) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                let _ = fs::remove_dir_all(&profile_dir);
+                return Err(anyhow!("{} did not open a DevTools endpoint", binary));
+            }
+        };
+
+        Ok(Self { child, profile_dir, ws_url })
+    }
+}
+
+impl Drop for Browser {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = fs::remove_dir_all(&self.profile_dir);
+    }
+}
+
+fn find_browser() -> Option<String> {
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    for name in BROWSERS {
+        if Path::new(name).is_absolute() {
+            if Path::new(name).exists() {
+                return Some(name.to_string());
+            }
+            continue;
+        }
+        for dir in std::env::split_paths(&path) {
+            let candidate = dir.join(name);
+            if candidate.is_file() {
+                return Some(candidate.to_string_lossy().to_string());
+            }
+        }
+    }
+    None
+}
+
+/// Request/response and event plumbing over the browser websocket.
+struct Connection {
+    socket: WebSocket<MaybeTlsStream<TcpStream>>,
+    next_id: u64,
+    deadline: Instant,
+    // Events that arrived while waiting for a response
+    pending_events: Vec<Value>,
+}
+
+impl Connection {
+    fn open(ws_url: &str, deadline: Instant) -> Result<Self> {
+        let (mut socket, _) = tungstenite::connect(ws_url)
+            .with_context(|| format!("Failed to connect to DevTools at {}", ws_url))?;
+        // Short read timeout so the deadline is checked even when the page goes quiet
+        if let MaybeTlsStream::Plain(stream) = socket.get_mut() {
+            stream.set_read_timeout(Some(Duration::from_millis(250)))?;
+        }
+        Ok(Self { socket, next_id: 0, deadline, pending_events: Vec::new() })
+    }
+
+    fn call(&mut self, session: Option<&str>, method: &str, params: Value) -> Result<Value> {
+        self.next_id += 1;
+        let id = self.next_id;
+        let mut request = json!({ "id": id, "method": method, "params": params });
+        if let Some(session) = session {
+            request["sessionId"] = Value::from(session);
+        }
+        self.socket.send(Message::Text(request.to_string()))
+            .with_context(|| format!("Failed to send {}", method))?;
+
+        loop {
+            let message = self.read(method)?;
+            if message["id"].as_u64() == Some(id) {
+                if let Some(error) = message.get("error") {
+                    return Err(anyhow!("{} failed: {}", method, error["message"].as_str().unwrap_or("unknown error")));
+                }
+                return Ok(message["result"].clone());
+            }
+            if message.get("method").is_some() {
+                self.pending_events.push(message);
+            }
+        }
+    }
+
+    fn wait_for_event(&mut self, session: Option<&str>, method: &str) -> Result<Value> {
+        let is_match = |event: &Value| {
+            event["method"].as_str() == Some(method) && event["sessionId"].as_str() == session
+        };
+        if let Some(index) = self.pending_events.iter().position(&is_match) {
+            return Ok(self.pending_events.remove(index));
+        }
+        loop {
+            let message = self.read(method)?;
+            if is_match(&message) {
+                return Ok(message);
+            }
+        }
+    }
+
+    fn read(&mut self, waiting_for: &str) -> Result<Value> {
+        loop {
+            if Instant::now() > self.deadline {
+                return Err(anyhow!("Timed out waiting for {}", waiting_for));
+            }
+            match self.socket.read() {
+                Ok(Message::Text(text)) => {
+                    return serde_json::from_str(text.as_str()).context("Invalid DevTools message");
+                }
+                Ok(_) => continue,
+                Err(tungstenite::Error::Io(e))
+                    if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => continue,
+                Err(e) => return Err(anyhow!("DevTools connection failed: {}", e)),
+            }
+        }
+    }
+}
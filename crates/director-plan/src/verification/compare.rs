@@ -0,0 +1,216 @@
+use image::{DynamicImage, GenericImageView, Rgba};
+use crate::types::{CompareMethod, ImageCompare};
+use super::visual_diff::{Rect, VisualDiffReport};
+
+// SSIM is computed over non-overlapping windows of this size
+const SSIM_WINDOW: u32 = 8;
+
+/// Compares two images of the same size with the ticket's chosen method.
+pub fn compare_images(golden: &DynamicImage, actual: &DynamicImage, settings: &ImageCompare) -> VisualDiffReport {
+    match settings.method {
+        CompareMethod::Pixel => {
+            let tolerance = settings.tolerance as i16;
+            per_pixel(golden, actual, settings, "Pixel mismatch detected", |p1, p2| {
+                p1.0.iter().zip(p2.0.iter()).all(|(c1, c2)| (*c1 as i16 - *c2 as i16).abs() <= tolerance)
+            })
+        }
+        CompareMethod::DeltaE => {
+            per_pixel(golden, actual, settings, "Perceptual colour difference detected", |p1, p2| {
+                delta_e(p1, p2) <= settings.max_delta_e
+            })
+        }
+        CompareMethod::Ssim => ssim(golden, actual, settings),
+    }
+}
+
+fn per_pixel(
+    golden: &DynamicImage,
+    actual: &DynamicImage,
+    settings: &ImageCompare,
+    reason: &str,
+    matches: impl Fn(Rgba<u8>, Rgba<u8>) -> bool,
+) -> VisualDiffReport {
+    let (width, height) = golden.dimensions();
+    let mut bounds = Bounds::new(width, height);
+    let mut mismatch_count = 0u64;
+
+    for y in 0..height {
+        for x in 0..width {
+            if !matches(golden.get_pixel(x, y), actual.get_pixel(x, y)) {
+                mismatch_count += 1;
+                bounds.include(x, y, x, y);
+            }
+        }
+    }
+
+    let total_pixels = (width as u64 * height as u64).max(1) as f64;
+    let mismatch_percentage = (mismatch_count as f64 / total_pixels) * 100.0;
+    let diff_detected = mismatch_count > 0 && mismatch_percentage > settings.max_mismatch_percent;
+
+    VisualDiffReport {
+        diff_detected,
+        mismatch_percentage,
+        diff_bounds: bounds.into_rect(),
+        reason: if diff_detected { Some(reason.to_string()) } else { None },
+        ssim: None,
+    }
+}
+
+/// Mean SSIM over the luma channel. `mismatch_percentage` is the share of windows below `min_ssim`.
+fn ssim(golden: &DynamicImage, actual: &DynamicImage, settings: &ImageCompare) -> VisualDiffReport {
+    let a = golden.to_luma8();
+    let b = actual.to_luma8();
+    let (width, height) = a.dimensions();
+
+    // Standard stabilising constants for 8-bit data
+    let c1 = (0.01 * 255.0f64).powi(2);
+    let c2 = (0.03 * 255.0f64).powi(2);
+
+    let mut bounds = Bounds::new(width, height);
+    let mut total = 0.0;
+    let mut windows = 0u64;
+    let mut failing = 0u64;
+
+    for wy in (0..height).step_by(SSIM_WINDOW as usize) {
+        for wx in (0..width).step_by(SSIM_WINDOW as usize) {
+            let x_end = (wx + SSIM_WINDOW).min(width);
+            let y_end = (wy + SSIM_WINDOW).min(height);
+            let n = ((x_end - wx) * (y_end - wy)) as f64;
+
+            let (mut sum_a, mut sum_b) = (0.0, 0.0);
+            for y in wy..y_end {
+                for x in wx..x_end {
+                    sum_a += a.get_pixel(x, y).0[0] as f64;
+                    sum_b += b.get_pixel(x, y).0[0] as f64;
+                }
+            }
+            let (mean_a, mean_b) = (sum_a / n, sum_b / n);
+
+            let (mut var_a, mut var_b, mut covar) = (0.0, 0.0, 0.0);
+            for y in wy..y_end {
+                for x in wx..x_end {
+                    let da = a.get_pixel(x, y).0[0] as f64 - mean_a;
+                    let db = b.get_pixel(x, y).0[0] as f64 - mean_b;
+                    var_a += da * da;
+                    var_b += db * db;
+                    covar += da * db;
+                }
+            }
+            var_a /= n;
+            var_b /= n;
+            covar /= n;
+
+            let score = ((2.0 * mean_a * mean_b + c1) * (2.0 * covar + c2))
+                / ((mean_a * mean_a + mean_b * mean_b + c1) * (var_a + var_b + c2));
+            total += score;
+            windows += 1;
+            if score < settings.min_ssim {
+                failing += 1;
+                bounds.include(wx, wy, x_end - 1, y_end - 1);
+            }
+        }
+    }
+
+    let mean = if windows == 0 { 1.0 } else { total / windows as f64 };
+    let diff_detected = mean < settings.min_ssim;
+
+    VisualDiffReport {
+        diff_detected,
+        mismatch_percentage: if windows == 0 { 0.0 } else { failing as f64 / windows as f64 * 100.0 },
+        diff_bounds: bounds.into_rect(),
+        reason: if diff_detected {
+            Some(format!("Structural similarity {:.4} is below {}", mean, settings.min_ssim))
+        } else {
+            None
+        },
+        ssim: Some(mean),
+    }
+}
+
+/// CIE76 distance between two sRGB colours. Alpha is ignored.
+fn delta_e(p1: Rgba<u8>, p2: Rgba<u8>) -> f64 {
+    let (l1, a1, b1) = to_lab(p1);
+    let (l2, a2, b2) = to_lab(p2);
+    ((l1 - l2).powi(2) + (a1 - a2).powi(2) + (b1 - b2).powi(2)).sqrt()
+}
+
+/// sRGB -> linear RGB -> XYZ (D65) -> CIE Lab
+fn to_lab(p: Rgba<u8>) -> (f64, f64, f64) {
+    let linear = |c: u8| {
+        let c = c as f64 / 255.0;
+        if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+    };
+    let (r, g, b) = (linear(p.0[0]), linear(p.0[1]), linear(p.0[2]));
+
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+
+    let f = |t: f64| if t > 216.0 / 24389.0 { t.cbrt() } else { (24389.0 / 27.0 * t + 16.0) / 116.0 };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    (116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz))
+}
+
+/// Bounding box of everything that differed.
+struct Bounds {
+    min_x: u32,
+    min_y: u32,
+    max_x: u32,
+    max_y: u32,
+    empty: bool,
+}
+
+impl Bounds {
+    fn new(width: u32, height: u32) -> Self {
+        Self { min_x: width, min_y: height, max_x: 0, max_y: 0, empty: true }
+    }
+
+    fn include(&mut self, x0: u32, y0: u32, x1: u32, y1: u32) {
+        self.min_x = self.min_x.min(x0);
+        self.min_y = self.min_y.min(y0);
+        self.max_x = self.max_x.max(x1);
+        self.max_y = self.max_y.max(y1);
+        self.empty = false;
+    }
+
+    fn into_rect(self) -> Option<Rect> {
+        if self.empty {
+            return None;
+        }
+        Some(Rect {
+            x: self.min_x,
+            y: self.min_y,
+            width: self.max_x - self.min_x + 1,
+            height: self.max_y - self.min_y + 1,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn test_methods_tolerate_small_shifts() {
+        let golden = DynamicImage::ImageRgba8(RgbaImage::from_pixel(16, 16, Rgba([100, 100, 100, 255])));
+        let mut slightly_off = RgbaImage::from_pixel(16, 16, Rgba([100, 100, 100, 255]));
+        slightly_off.put_pixel(3, 4, Rgba([101, 100, 100, 255]));
+        let slightly_off = DynamicImage::ImageRgba8(slightly_off);
+
+        let pixel = compare_images(&golden, &slightly_off, &ImageCompare::default());
+        assert!(pixel.diff_detected);
+        assert_eq!(pixel.diff_bounds.unwrap().x, 3);
+
+        let delta_e = ImageCompare { method: CompareMethod::DeltaE, ..Default::default() };
+        assert!(!compare_images(&golden, &slightly_off, &delta_e).diff_detected);
+
+        let ssim = ImageCompare { method: CompareMethod::Ssim, ..Default::default() };
+        let report = compare_images(&golden, &slightly_off, &ssim);
+        assert!(!report.diff_detected);
+        assert!(report.ssim.unwrap() > 0.99);
+
+        let black = DynamicImage::ImageRgba8(RgbaImage::from_pixel(16, 16, Rgba([0, 0, 0, 255])));
+        assert!(compare_images(&golden, &black, &ssim).diff_detected);
+    }
+}
@@ -0,0 +1,84 @@
+use anyhow::{Context, Result, anyhow};
+use serde_json::Value;
+use crate::runs::RunRecord;
+
+/// Line coverage in percent from a report. The format is detected from the content:
+/// Cobertura XML, lcov, `cargo llvm-cov --json`, or Istanbul's `coverage-summary.json`.
+pub fn parse_report(content: &str) -> Result<f64> {
+    let trimmed = content.trim_start();
+
+    if trimmed.starts_with('<') {
+        // <coverage line-rate="0.8123" ...>
+        let rate = regex::Regex::new(r#"<coverage[^>]*\sline-rate="([0-9.]+)""#)
+            .unwrap()
+            .captures(content)
+            .and_then(|c| c[1].parse::<f64>().ok())
+            .ok_or_else(|| anyhow!("Cobertura report has no line-rate"))?;
+        return Ok(rate * 100.0);
+    }
+
+    if trimmed.starts_with('{') {
+        let json: Value = serde_json::from_str(content).context("Invalid JSON coverage report")?;
+        // llvm-cov: {"data": [{"totals": {"lines": {"percent": 81.2}}}]}
+        if let Some(percent) = json["data"][0]["totals"]["lines"]["percent"].as_f64() {
+            return Ok(percent);
+        }
+        // Istanbul json-summary: {"total": {"lines": {"pct": 81.2}}}
+        if let Some(pct) = json["total"]["lines"]["pct"].as_f64() {
+            return Ok(pct);
+        }
+        return Err(anyhow!("Unrecognised JSON coverage report"));
+    }
+
+    // lcov: sum LF (lines found) / LH (lines hit) over all files
+    let (mut found, mut hit) = (0u64, 0u64);
+    for line in content.lines() {
+        if let Some(n) = line.strip_prefix("LF:") {
+            found += n.trim().parse::<u64>().unwrap_or(0);
+        } else if let Some(n) = line.strip_prefix("LH:") {
+            hit += n.trim().parse::<u64>().unwrap_or(0);
+        }
+    }
+    if found == 0 {
+        return Err(anyhow!("Unrecognised coverage report (expected lcov, llvm-cov JSON, Istanbul summary or Cobertura)"));
+    }
+    Ok(hit as f64 / found as f64 * 100.0)
+}
+
+/// Coverage already measured for `commit` by an earlier run, so it isn't measured twice.
+pub fn baseline_from_history(runs: &[RunRecord], commit: &str) -> Option<f64> {
+    runs.iter()
+        .rev()
+        .filter(|r| r.base_commit.as_deref() == Some(commit))
+        .find_map(|r| r.base_coverage)
+}
+
+/// `Err` with both numbers when coverage fell by more than `min_delta` points.
+pub fn check_delta(base: f64, current: f64, min_delta: f64) -> Result<()> {
+    let delta = current - base;
+    if delta < min_delta {
+        return Err(anyhow!(
+            "Coverage dropped from {:.2}% to {:.2}% ({:+.2} points; the ticket allows {:+.2}). Restore or add tests.",
+            base, current, delta, min_delta
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_report_formats() {
+        let lcov = "SF:src/a.rs\nLF:10\nLH:8\nend_of_record\nSF:src/b.rs\nLF:10\nLH:2\nend_of_record\n";
+        assert_eq!(parse_report(lcov).unwrap(), 50.0);
+        assert_eq!(parse_report(r#"{"data":[{"totals":{"lines":{"percent":81.5}}}]}"#).unwrap(), 81.5);
+        assert_eq!(parse_report(r#"{"total":{"lines":{"pct":72}}}"#).unwrap(), 72.0);
+        assert_eq!(parse_report(r#"<?xml version="1.0"?><coverage line-rate="0.25" branch-rate="0">"#).unwrap(), 25.0);
+        assert!(parse_report("nonsense").is_err());
+
+        assert!(check_delta(80.0, 79.8, -0.5).is_ok());
+        assert!(check_delta(80.0, 78.0, 0.0).is_err());
+    }
+}
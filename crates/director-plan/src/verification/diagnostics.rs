@@ -0,0 +1,316 @@
+use std::collections::HashSet;
+use regex::Regex;
+
+/// More errors than this are cut off; the first ones are usually the cause of the rest.
+const MAX_DIAGNOSTICS: usize = 30;
+/// Lines kept from the end of output nothing could be extracted from.
+const MAX_RAW_LINES: usize = 80;
+
+/// One error pulled out of a tool's output.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Diagnostic {
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn new(file: Option<&str>, line: Option<&str>, message: &str) -> Self {
+        Self {
+            file: file.map(str::to_string),
+            line: line.and_then(|l| l.parse().ok()),
+            message: message.trim().to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => write!(f, "{}:{}: {}", file, line, self.message),
+            (Some(file), None) => write!(f, "{}: {}", file, self.message),
+            _ => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Errors reported in `output` by cargo/rustc (JSON or human-readable), tsc, eslint,
+/// jest/vitest and pytest, in the order they appear, without duplicates. Warnings are left out.
+pub fn extract(output: &str) -> Vec<Diagnostic> {
+    let rust_error = Regex::new(r"^error(?:\[E\d+\])?: (.+)$").unwrap();
+    let rust_location = Regex::new(r"^\s*--> (.+?):(\d+):\d+$").unwrap();
+    let rust_panic = Regex::new(r"^thread '(.+?)' panicked at (.+?):(\d+):\d+:?$").unwrap();
+    let rust_panic_old = Regex::new(r"^thread '(.+?)' panicked at '(.*)', (.+?):(\d+):\d+$").unwrap();
+    let tsc = Regex::new(r"^(.+?)(?:\((\d+),\d+\)|:(\d+):\d+ -) error (TS\d+: .+)$").unwrap();
+    let eslint_file = Regex::new(r"^(/|[A-Za-z]:\\|\./|\w).*\.\w+$").unwrap();
+    let eslint_error = Regex::new(r"^\s+(\d+):\d+\s+error\s+(.+?)(?:\s{2,}\S+)?$").unwrap();
+    let jest_test = Regex::new(r"^\s*● (.+ › .+|[^›]+)$").unwrap();
+    let vitest_test = Regex::new(r"^\s*FAIL\s+(\S+) > (.+)$").unwrap();
+    let js_location = Regex::new(r"(?:\(|❯ |at )([^\s()]+?\.[cm]?[jt]sx?):(\d+):\d+\)?\s*$").unwrap();
+    let pytest_failed = Regex::new(r"^FAILED (.+?\.py)::(\S+)(?: - (.+))?$").unwrap();
+    let pytest_location = Regex::new(r"^(.+?\.py):(\d+): (\w+)$").unwrap();
+
+    let lines: Vec<&str> = output.lines().collect();
+    let mut found = Vec::new();
+    let mut eslint_current: Option<&str> = None;
+    // pytest prints where each failure happened before the summary naming the tests
+    let mut pytest_locations: Vec<(&str, &str)> = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        if line.starts_with('{') {
+            if let Some(diagnostic) = cargo_json(line) {
+                found.push(diagnostic);
+            }
+            continue;
+        }
+        if let Some(c) = rust_error.captures(line) {
+            // Only errors pointing at code; "could not compile" and the like have no location
+            let location = lines[i + 1..].iter().take(3).find_map(|l| rust_location.captures(l));
+            if let Some(location) = location {
+                found.push(Diagnostic::new(Some(&location[1]), Some(&location[2]), &c[1]));
+            }
+            continue;
+        }
+        if let Some(c) = rust_panic_old.captures(line) {
+            found.push(Diagnostic::new(Some(&c[3]), Some(&c[4]), &format!("{} panicked: {}", &c[1], &c[2])));
+            continue;
+        }
+        if let Some(c) = rust_panic.captures(line) {
+            let message: Vec<&str> = lines[i + 1..].iter().take_while(|l| !l.trim().is_empty() && !l.starts_with("note:")).take(3).map(|l| l.trim()).collect();
+            found.push(Diagnostic::new(Some(&c[2]), Some(&c[3]), &format!("{} panicked: {}", &c[1], message.join(" "))));
+            continue;
+        }
+        if let Some(c) = tsc.captures(line) {
+            let line_number = c.get(2).or(c.get(3)).map(|m| m.as_str());
+            found.push(Diagnostic::new(Some(&c[1]), line_number, &c[4]));
+            continue;
+        }
+        if let Some(c) = eslint_error.captures(line)
+            && let Some(file) = eslint_current
+        {
+            found.push(Diagnostic::new(Some(file), Some(&c[1]), &c[2]));
+            continue;
+        }
+        if let Some(c) = vitest_test.captures(line) {
+            let (location, message) = js_failure(&lines[i + 1..], &js_location);
+            let line_number = location.filter(|(file, _)| *file == &c[1]).map(|(_, line)| line);
+            found.push(Diagnostic::new(Some(&c[1]), line_number, &format!("{}: {}", &c[2], message)));
+            continue;
+        }
+        if let Some(c) = jest_test.captures(line) {
+            let (location, message) = js_failure(&lines[i + 1..], &js_location);
+            let (file, line_number) = location.unzip();
+            found.push(Diagnostic::new(file, line_number, &format!("{}: {}", &c[1], message)));
+            continue;
+        }
+        if let Some(c) = pytest_location.captures(line) {
+            pytest_locations.push((c.get(1).unwrap().as_str(), c.get(2).unwrap().as_str()));
+            continue;
+        }
+        if let Some(c) = pytest_failed.captures(line) {
+            let file = &c[1];
+            let line_number = pytest_locations.iter().position(|(f, _)| *f == file)
+                .map(|index| pytest_locations.remove(index).1);
+            let message = match c.get(3) {
+                Some(reason) => format!("{}: {}", &c[2], reason.as_str()),
+                None => format!("{} failed", &c[2]),
+            };
+            found.push(Diagnostic::new(Some(file), line_number, &message));
+            continue;
+        }
+        if eslint_file.is_match(line) && !line.contains(' ') {
+            eslint_current = Some(line);
+        } else if line.trim().is_empty() {
+            eslint_current = None;
+        }
+    }
+
+    let mut seen = HashSet::new();
+    found.retain(|d| seen.insert(d.clone()));
+    found
+}
+
+/// A `compiler-message` line from `cargo --message-format=json`, if it's an error.
+fn cargo_json(line: &str) -> Option<Diagnostic> {
+    let value: serde_json::Value = serde_json::from_str(line).ok()?;
+    if value.get("reason")?.as_str()? != "compiler-message" {
+        return None;
+    }
+    let message = value.get("message")?;
+    if !message.get("level")?.as_str()?.starts_with("error") {
+        return None;
+    }
+    let text = message.get("message")?.as_str()?;
+    // Summary lines like "aborting due to 2 previous errors" have no spans
+    let span = message.get("spans")?.as_array()?.iter()
+        .find(|s| s.get("is_primary").and_then(|p| p.as_bool()) == Some(true))?;
+    let line = span.get("line_start").and_then(|l| l.as_u64()).map(|l| l.to_string());
+    Some(Diagnostic::new(span.get("file_name").and_then(|f| f.as_str()), line.as_deref(), text))
+}
+
+/// The first location outside `node_modules` and the first line of the error message that
+/// follow a jest/vitest failure header, up to the next one.
+fn js_failure<'a>(lines: &[&'a str], location: &Regex) -> (Option<(&'a str, &'a str)>, String) {
+    let block = lines.iter().take_while(|l| !l.trim_start().starts_with('●') && !l.trim_start().starts_with("FAIL "));
+    let mut found = None;
+    let mut message = None;
+    for line in block.take(60) {
+        if found.is_none()
+            && let Some(c) = location.captures(line)
+            && !c[1].contains("node_modules")
+        {
+            found = Some((c.get(1).unwrap().as_str(), c.get(2).unwrap().as_str()));
+        }
+        let trimmed = line.trim();
+        if message.is_none() && !trimmed.is_empty() && !location.is_match(line) && !trimmed.starts_with('⎯') {
+            message = Some(trimmed.to_string());
+        }
+    }
+    (found, message.unwrap_or_else(|| "failed".to_string()))
+}
+
+/// `error` as sent back to the agent: the extracted errors, one per line, if any could be
+/// extracted, otherwise its last lines. The full text stays in the run record.
+pub fn summarize(error: &str) -> String {
+    let diagnostics = extract(error);
+    if diagnostics.is_empty() {
+        let lines: Vec<&str> = error.lines().collect();
+        if lines.len() <= MAX_RAW_LINES {
+            return error.to_string();
+        }
+        // The first line says what failed; the end of the log says why
+        let mut summary = vec![lines[0].to_string(), format!("[... {} lines omitted ...]", lines.len() - MAX_RAW_LINES)];
+        summary.extend(lines[lines.len() - MAX_RAW_LINES + 1..].iter().map(|l| l.to_string()));
+        return summary.join("\n");
+    }
+
+    let headline = error.lines().next().unwrap_or_default();
+    let mut summary = format!("{}: {} error(s)\n", headline.trim_end_matches(':'), diagnostics.len());
+    for diagnostic in diagnostics.iter().take(MAX_DIAGNOSTICS) {
+        summary.push_str(&format!("{}\n", diagnostic));
+    }
+    if diagnostics.len() > MAX_DIAGNOSTICS {
+        summary.push_str(&format!("... and {} more\n", diagnostics.len() - MAX_DIAGNOSTICS));
+    }
+    summary
+}
+
+/// `text` cut to about `max_chars`, keeping a quarter from the start, where the command and
+/// the first error usually are, and the rest from the end, where the failure summary is.
+pub fn truncate_middle(text: &str, max_chars: usize) -> String {
+    let total = text.chars().count();
+    if max_chars == 0 || total <= max_chars {
+        return text.to_string();
+    }
+    let head = max_chars / 4;
+    let tail = max_chars - head;
+    let start: String = text.chars().take(head).collect();
+    let end: String = text.chars().skip(total - tail).collect();
+    format!("{}\n[... {} characters omitted ...]\n{}", start, total - head - tail, end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rendered(output: &str) -> Vec<String> {
+        extract(output).iter().map(|d| d.to_string()).collect()
+    }
+
+    #[test]
+    fn test_rust() {
+        let human = "\
+error[E0308]: mismatched types
+  --> src/lib.rs:12:5
+   |
+12 |     \"x\"
+   |     ^^^ expected `u32`, found `&str`
+
+warning: unused variable: `y`
+  --> src/lib.rs:3:9
+
+error: could not compile `demo` (lib) due to 1 previous error
+thread 'tests::adds' panicked at src/math.rs:40:9:
+assertion `left == right` failed
+  left: 3
+  right: 4
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+";
+        assert_eq!(rendered(human), [
+            "src/lib.rs:12: mismatched types",
+            "src/math.rs:40: tests::adds panicked: assertion `left == right` failed left: 3 right: 4",
+        ]);
+
+        let json = r#"{"reason":"compiler-message","message":{"level":"error","message":"cannot find value `z` in this scope","spans":[{"file_name":"src/main.rs","line_start":7,"is_primary":true}]}}
+{"reason":"compiler-message","message":{"level":"error","message":"aborting due to 1 previous error","spans":[]}}
+{"reason":"build-finished","success":false}"#;
+        assert_eq!(rendered(json), ["src/main.rs:7: cannot find value `z` in this scope"]);
+    }
+
+    #[test]
+    fn test_typescript_and_eslint() {
+        let output = "\
+src/app.ts(4,7): error TS2322: Type 'string' is not assignable to type 'number'.
+src/app.ts:9:1 - error TS2304: Cannot find name 'foo'.
+
+/repo/src/util.js
+   3:10  error    'x' is defined but never used  no-unused-vars
+   5:1   warning  Unexpected console statement   no-console
+
+✖ 2 problems (1 error, 1 warning)
+";
+        assert_eq!(rendered(output), [
+            "src/app.ts:4: TS2322: Type 'string' is not assignable to type 'number'.",
+            "src/app.ts:9: TS2304: Cannot find name 'foo'.",
+            "/repo/src/util.js:3: 'x' is defined but never used",
+        ]);
+    }
+
+    #[test]
+    fn test_jest_vitest_pytest() {
+        let jest = "\
+  ● math › adds numbers
+
+    expect(received).toBe(expected) // Object.is equality
+
+      at Object.<anonymous> (node_modules/expect/build/index.js:10:3)
+      at Object.<anonymous> (src/math.test.js:5:17)
+";
+        assert_eq!(rendered(jest), ["src/math.test.js:5: math › adds numbers: expect(received).toBe(expected) // Object.is equality"]);
+
+        let vitest = "\
+ FAIL  src/sum.test.ts > sum > adds
+AssertionError: expected 3 to be 4 // Object.is equality
+ ❯ src/sum.test.ts:6:17
+";
+        assert_eq!(rendered(vitest), ["src/sum.test.ts:6: sum > adds: AssertionError: expected 3 to be 4 // Object.is equality"]);
+
+        let pytest = "\
+tests/test_api.py:14: AssertionError
+tests/test_api.py:30: KeyError
+=========================== short test summary info ============================
+FAILED tests/test_api.py::test_status - assert 500 == 200
+FAILED tests/test_api.py::test_body - KeyError: 'id'
+";
+        assert_eq!(rendered(pytest), [
+            "tests/test_api.py:14: test_status: assert 500 == 200",
+            "tests/test_api.py:30: test_body: KeyError: 'id'",
+        ]);
+    }
+
+    #[test]
+    fn test_summarize() {
+        let error = "Command Failed:\nSTDOUT:\nsrc/app.ts(4,7): error TS2322: Bad type.\nSTDERR:\n";
+        assert_eq!(summarize(error), "Command Failed: 1 error(s)\nsrc/app.ts:4: TS2322: Bad type.\n");
+
+        let log: String = (0..500).map(|i| format!("line {}\n", i)).collect();
+        let summary = summarize(&format!("Command Failed:\n{}", log));
+        assert_eq!(summary.lines().count(), MAX_RAW_LINES + 1);
+        assert!(summary.starts_with("Command Failed:\n[... 421 lines omitted ...]\n"));
+        assert!(summary.ends_with("line 499"));
+
+        assert_eq!(truncate_middle("short", 0), "short");
+        let cut = truncate_middle(&"é".repeat(100), 20);
+        assert_eq!(cut, format!("{}\n[... 80 characters omitted ...]\n{}", "é".repeat(5), "é".repeat(15)));
+    }
+}
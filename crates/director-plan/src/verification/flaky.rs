@@ -0,0 +1,118 @@
+use std::collections::BTreeMap;
+use regex::Regex;
+use serde::Serialize;
+use crate::runs::RunRecord;
+
+/// The first of `[verification] flaky_patterns` found in a failure's output. Patterns that
+/// aren't valid regexes are skipped; `PlanConfig::load` has already rejected them.
+pub fn known_signature<'a>(patterns: &'a [String], output: &str) -> Option<&'a str> {
+    patterns.iter()
+        .find(|pattern| Regex::new(pattern).is_ok_and(|re| re.is_match(output)))
+        .map(String::as_str)
+}
+
+pub fn check_patterns(patterns: &[String]) -> std::result::Result<(), String> {
+    for pattern in patterns {
+        Regex::new(pattern).map_err(|e| format!("flaky_patterns: {:?} is not a valid regex: {}", pattern, e))?;
+    }
+    Ok(())
+}
+
+/// How often one verification command failed for reasons other than the agent's changes.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CommandFlakes {
+    pub command: String,
+    /// Attempts that got as far as verification
+    pub verified: usize,
+    pub failed: usize,
+    /// Attempts judged flaky: passed on a re-run, or failed with a known flaky signature
+    pub flaky: usize,
+    pub flake_rate: f64,
+}
+
+/// Flake statistics per verification command, most flaky first. Runs recorded before the
+/// command was kept on the run are left out.
+pub fn stats(runs: &[RunRecord]) -> Vec<CommandFlakes> {
+    let mut by_command: BTreeMap<&str, (usize, usize, usize)> = BTreeMap::new();
+    for run in runs {
+        let Some(command) = run.verification_command.as_deref() else { continue };
+        for attempt in &run.attempts {
+            // An attempt that failed before verification has no verification time
+            if attempt.verification_ms == 0 && !attempt.passed && attempt.flake.is_none() {
+                continue;
+            }
+            let entry = by_command.entry(command).or_default();
+            entry.0 += 1;
+            entry.1 += usize::from(!attempt.passed);
+            entry.2 += usize::from(attempt.flake.is_some());
+        }
+    }
+
+    let mut stats: Vec<CommandFlakes> = by_command.into_iter()
+        .map(|(command, (verified, failed, flaky))| CommandFlakes {
+            command: command.to_string(),
+            verified,
+            failed,
+            flaky,
+            flake_rate: flaky as f64 / verified as f64,
+        })
+        .collect();
+    stats.sort_by(|a, b| b.flaky.cmp(&a.flaky).then_with(|| a.command.cmp(&b.command)));
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runs::AttemptRecord;
+
+    fn attempt(verification_ms: u64, passed: bool, flake: Option<&str>) -> AttemptRecord {
+        AttemptRecord {
+            number: 1,
+            agent_ms: 10,
+            verification_ms,
+            passed,
+            confidence: None,
+            errors: Vec::new(),
+            perf: None,
+            coverage: None,
+            autofixed: Vec::new(),
+            scoped_command: None,
+            flake: flake.map(str::to_string),
+            result: None,
+            context: None,
+            snapshot: None,
+            agent: None,
+        }
+    }
+
+    #[test]
+    fn test_known_signature() {
+        let patterns = vec!["ECONNRESET".to_string(), r"Timeout of \d+ms exceeded".to_string()];
+        assert_eq!(known_signature(&patterns, "Error: Timeout of 2000ms exceeded."), Some(r"Timeout of \d+ms exceeded"));
+        assert_eq!(known_signature(&patterns, "assertion failed: left == right"), None);
+        assert!(check_patterns(&patterns).is_ok());
+        assert!(check_patterns(&["(unclosed".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut npm = RunRecord::new("T-001", "agent");
+        npm.verification_command = Some("npm test".to_string());
+        npm.attempts = vec![
+            attempt(0, false, None),
+            attempt(500, false, None),
+            attempt(900, true, Some("failed, then passed on re-run 1")),
+        ];
+        let mut cargo = RunRecord::new("T-002", "agent");
+        cargo.verification_command = Some("cargo test".to_string());
+        cargo.attempts = vec![attempt(300, true, None)];
+        let old = RunRecord::new("T-003", "agent");
+
+        let stats = stats(&[cargo, npm, old]);
+        assert_eq!(stats.iter().map(|s| s.command.as_str()).collect::<Vec<_>>(), ["npm test", "cargo test"]);
+        assert_eq!((stats[0].verified, stats[0].failed, stats[0].flaky), (2, 1, 1));
+        assert_eq!(stats[0].flake_rate, 0.5);
+        assert_eq!(stats[1].flaky, 0);
+    }
+}
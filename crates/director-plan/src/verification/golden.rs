@@ -0,0 +1,162 @@
+use std::fs;
+use std::path::Path;
+use anyhow::{Context, Result, anyhow};
+use serde::Serialize;
+use crate::store::TicketStore;
+use crate::types::Ticket;
+use crate::verification::visual_diff::validate_snapshot_name;
+
+/// One screenshot (or structure snapshot) promoted to a golden.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Promotion {
+    /// Snapshot name, or `None` for the single `golden_image`
+    pub name: Option<String>,
+    /// Screenshot under `proof/`, relative to the workspace root
+    pub actual: String,
+    /// Golden image path, relative to the workspace root
+    pub golden: String,
+}
+
+/// Where a ticket's golden lives if it doesn't name one: next to the tickets, so it's versioned with them.
+pub fn default_golden_path(ticket_id: &str) -> String {
+    format!("plan/goldens/{}.png", ticket_id)
+}
+
+/// Works out which screenshots would be promoted, without touching the filesystem.
+///
+/// With `snapshot` set only that `[[verification.visual]]` or `[[verification.structure]]` entry
+/// is used. Otherwise every snapshot is promoted, plus `golden_image` if the ticket has one (or
+/// has no snapshots at all).
+pub fn plan_promotions(ticket: &Ticket, snapshot: Option<&str>) -> Result<Vec<Promotion>> {
+    let visual = &ticket.verification.visual;
+    let structure = &ticket.verification.structure;
+    let mut promotions = Vec::new();
+
+    if let Some(name) = snapshot {
+        if let Some(entry) = visual.iter().find(|s| s.name == name) {
+            promotions.push(Promotion {
+                name: Some(entry.name.clone()),
+                actual: format!("proof/{}.png", entry.name),
+                golden: entry.golden.clone(),
+            });
+        } else if let Some(entry) = structure.iter().find(|s| s.name == name) {
+            promotions.push(Promotion {
+                name: Some(entry.name.clone()),
+                actual: format!("proof/{}.json", entry.name),
+                golden: entry.golden.clone(),
+            });
+        } else {
+            return Err(anyhow!("Ticket {} has no snapshot named '{}'", ticket.meta.id, name));
+        }
+    } else {
+        if ticket.verification.golden_image.is_some() || (visual.is_empty() && structure.is_empty()) {
+            promotions.push(Promotion {
+                name: None,
+                actual: "proof/actual.png".to_string(),
+                golden: ticket.verification.golden_image.clone()
+                    .unwrap_or_else(|| default_golden_path(&ticket.meta.id)),
+            });
+        }
+        for entry in visual {
+            promotions.push(Promotion {
+                name: Some(entry.name.clone()),
+                actual: format!("proof/{}.png", entry.name),
+                golden: entry.golden.clone(),
+            });
+        }
+        for entry in structure {
+            promotions.push(Promotion {
+                name: Some(entry.name.clone()),
+                actual: format!("proof/{}.json", entry.name),
+                golden: entry.golden.clone(),
+            });
+        }
+    }
+
+    for promotion in &promotions {
+        if let Some(name) = &promotion.name {
+            validate_snapshot_name(name)?;
+        }
+        let golden = &promotion.golden;
+        if golden.starts_with('/') || golden.split(['/', '\\']).any(|part| part == "..") {
+            return Err(anyhow!("Golden path must stay inside the workspace: {}", golden));
+        }
+    }
+    Ok(promotions)
+}
+
+/// Copies the latest screenshots over the ticket's golden images and records it in the history.
+///
+/// A ticket without `golden_image` gets one pointing at [`default_golden_path`]. Nothing is copied
+/// unless every screenshot exists, so a half-captured run can't leave mixed baselines.
+pub fn accept(workspace_root: &Path, ticket_id: &str, snapshot: Option<&str>) -> Result<Vec<Promotion>> {
+    let ticket = TicketStore::new(workspace_root).load(ticket_id)?;
+    let promotions = plan_promotions(&ticket, snapshot)?;
+    promote(workspace_root, &ticket, &promotions)?;
+    Ok(promotions)
+}
+
+/// Copies each promotion's screenshot over its golden and records it in the ticket's history.
+pub fn promote(workspace_root: &Path, ticket: &Ticket, promotions: &[Promotion]) -> Result<()> {
+    let ticket_id = ticket.meta.id.as_str();
+    for promotion in promotions {
+        let actual = workspace_root.join(&promotion.actual);
+        if !actual.exists() {
+            return Err(anyhow!("No screenshot at {}; run verification first", promotion.actual));
+        }
+    }
+
+    for promotion in promotions {
+        let golden = workspace_root.join(&promotion.golden);
+        if let Some(dir) = golden.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+        }
+        fs::copy(workspace_root.join(&promotion.actual), &golden)
+            .with_context(|| format!("Failed to copy {} to {}", promotion.actual, promotion.golden))?;
+    }
+
+    let assigned_default = ticket.verification.golden_image.is_none()
+        && promotions.iter().any(|p| p.name.is_none());
+    let accepted: Vec<&str> = promotions.iter().map(|p| p.golden.as_str()).collect();
+    TicketStore::new(workspace_root).update(ticket_id, |doc| {
+        if assigned_default {
+            doc["verification"]["golden_image"] = toml_edit::value(default_golden_path(ticket_id));
+        }
+        crate::append_history_entry(doc, &format!("Accepted new golden baseline: {}", accepted.join(", ")));
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ticket(extra: &str) -> Ticket {
+        let toml = format!(
+            "[meta]\nid = \"T-001\"\ntitle = \"t\"\nstatus = \"todo\"\npriority = \"low\"\ncreated_at = 2024-01-01T00:00:00Z\n\
+             [spec]\ndescription = \"d\"\n\
+             [verification]\ncommand = \"true\"\n{}",
+            extra
+        );
+        toml_edit::de::from_str(&toml).unwrap()
+    }
+
+    #[test]
+    fn test_plan_promotions() {
+        let single = plan_promotions(&ticket(""), None).unwrap();
+        assert_eq!(single.len(), 1);
+        assert_eq!(single[0].golden, "plan/goldens/T-001.png");
+
+        let snapshots = ticket(
+            "[[verification.visual]]\nname = \"mobile\"\nviewport = \"390x844\"\ngolden = \"plan/goldens/m.png\"\n\
+             [[verification.visual]]\nname = \"desktop\"\ngolden = \"plan/goldens/d.png\"\n",
+        );
+        assert_eq!(plan_promotions(&snapshots, None).unwrap().len(), 2);
+        let mobile = plan_promotions(&snapshots, Some("mobile")).unwrap();
+        assert_eq!(mobile[0].actual, "proof/mobile.png");
+        assert!(plan_promotions(&snapshots, Some("tablet")).is_err());
+
+        assert!(plan_promotions(&ticket("golden_image = \"../outside.png\"\n"), None).is_err());
+    }
+}
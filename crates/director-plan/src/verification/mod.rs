@@ -1 +1,15 @@
 pub mod visual_diff;
+pub mod policy;
+pub mod golden;
+pub mod capture;
+pub mod compare;
+pub mod structure;
+pub mod perf;
+pub mod coverage;
+pub mod cdp;
+pub mod test_report;
+pub mod batch;
+pub mod steps;
+pub mod scoped;
+pub mod flaky;
+pub mod diagnostics;
@@ -0,0 +1,101 @@
+use std::path::Path;
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+use crate::config::VisualConfig;
+use crate::types::{PerfBudget, Verification};
+use super::capture::{CaptureTarget, DEFAULT_URL};
+use super::cdp;
+
+const DEFAULT_BUNDLE_GLOB: &str = "dist/**/*.js";
+
+/// What a `verification.perf` check measured. Stored on the attempt in the run record.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct PerfSample {
+    pub lcp_ms: Option<f64>,
+    pub load_ms: Option<f64>,
+    pub bundle_kb: Option<u64>,
+}
+
+/// Measures only what the budget has limits for: page timings need a browser, bundle size doesn't.
+pub fn measure(workspace_root: &Path, verification: &Verification, budget: &PerfBudget, config: &VisualConfig) -> Result<PerfSample> {
+    let mut sample = PerfSample::default();
+
+    if budget.max_lcp_ms.is_some() || budget.max_load_ms.is_some() {
+        if config.backend != "cdp" {
+            return Err(anyhow!("Page timings need [visual] backend = \"cdp\""));
+        }
+        let target = CaptureTarget {
+            url: budget.url.as_deref().or(verification.url.as_deref()).unwrap_or(DEFAULT_URL),
+            viewport: None,
+            wait_for: verification.wait_for.as_deref(),
+        };
+        let timing = cdp::measure_page(config, &target)?;
+        sample.lcp_ms = timing.lcp_ms;
+        sample.load_ms = timing.load_ms;
+    }
+
+    if budget.max_bundle_kb.is_some() {
+        let pattern = budget.bundle.as_deref().unwrap_or(DEFAULT_BUNDLE_GLOB);
+        sample.bundle_kb = Some(bundle_kb(workspace_root, pattern)?);
+    }
+
+    Ok(sample)
+}
+
+/// One line per exceeded limit, e.g. `LCP 3120 ms exceeds the 2500 ms budget`.
+pub fn violations(budget: &PerfBudget, sample: &PerfSample) -> Vec<String> {
+    let mut violations = Vec::new();
+    match (budget.max_lcp_ms, sample.lcp_ms) {
+        (Some(max), Some(lcp)) if lcp > max => {
+            violations.push(format!("LCP {:.0} ms exceeds the {:.0} ms budget", lcp, max));
+        }
+        (Some(_), None) => violations.push("LCP could not be measured (no largest-contentful-paint entry)".to_string()),
+        _ => {}
+    }
+    match (budget.max_load_ms, sample.load_ms) {
+        (Some(max), Some(load)) if load > max => {
+            violations.push(format!("Page load {:.0} ms exceeds the {:.0} ms budget", load, max));
+        }
+        (Some(_), None) => violations.push("Page load time could not be measured".to_string()),
+        _ => {}
+    }
+    if let (Some(max), Some(size)) = (budget.max_bundle_kb, sample.bundle_kb)
+        && size > max
+    {
+        let pattern = budget.bundle.as_deref().unwrap_or(DEFAULT_BUNDLE_GLOB);
+        violations.push(format!("Bundle {} KB ({}) exceeds the {} KB budget", size, pattern, max));
+    }
+    violations
+}
+
+fn bundle_kb(workspace_root: &Path, pattern: &str) -> Result<u64> {
+    let full = workspace_root.join(pattern);
+    let mut bytes = 0;
+    let mut matched = 0;
+    for entry in glob::glob(&full.to_string_lossy()).context("Invalid bundle glob")? {
+        let path = entry?;
+        if path.is_file() {
+            bytes += path.metadata()?.len();
+            matched += 1;
+        }
+    }
+    if matched == 0 {
+        return Err(anyhow!("No build output matches {}; run the build in the verification command", pattern));
+    }
+    Ok(bytes.div_ceil(1024))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_violations_name_the_numbers() {
+        let budget = PerfBudget { max_lcp_ms: Some(2500.0), max_bundle_kb: Some(500), ..Default::default() };
+        let sample = PerfSample { lcp_ms: Some(3120.4), load_ms: None, bundle_kb: Some(420) };
+        assert_eq!(violations(&budget, &sample), vec!["LCP 3120 ms exceeds the 2500 ms budget".to_string()]);
+
+        let sample = PerfSample { lcp_ms: Some(900.0), load_ms: None, bundle_kb: Some(812) };
+        assert_eq!(violations(&budget, &sample), vec!["Bundle 812 KB (dist/**/*.js) exceeds the 500 KB budget".to_string()]);
+    }
+}
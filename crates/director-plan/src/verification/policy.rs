@@ -0,0 +1,105 @@
+use std::fmt;
+use std::path::Path;
+use std::process::Command;
+use anyhow::Result;
+use crate::config::PolicyConfig;
+
+/// Returned (wrapped in `anyhow::Error`) when a ticket's command is refused by `[policy]`.
+#[derive(Debug)]
+pub struct PolicyViolation(pub String);
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Command rejected by policy: {}", self.0)
+    }
+}
+
+impl std::error::Error for PolicyViolation {}
+
+// With an allowlist in place these would let `cargo test; curl ... | sh` through on the prefix.
+const SHELL_METACHARACTERS: &[&str] = &[";", "&", "|", "`", "$(", ">", "<", "\n"];
+
+/// Checks a ticket-supplied command against `policy.allowed_commands`.
+///
+/// Prefixes match on whole words (`cargo test` allows `cargo test -p foo`, not `cargo testx`).
+/// An empty allowlist allows everything, which is the pre-policy behaviour.
+pub fn check(policy: &PolicyConfig, command: &str) -> Result<()> {
+    if policy.allowed_commands.is_empty() {
+        return Ok(());
+    }
+
+    if let Some(meta) = SHELL_METACHARACTERS.iter().find(|m| command.contains(**m)) {
+        return Err(PolicyViolation(format!("`{}` may not contain `{}`", command, meta.escape_default())).into());
+    }
+
+    let words: Vec<&str> = command.split_whitespace().collect();
+    let allowed = policy.allowed_commands.iter().any(|prefix| {
+        let prefix: Vec<&str> = prefix.split_whitespace().collect();
+        !prefix.is_empty() && words.starts_with(&prefix)
+    });
+
+    if allowed {
+        Ok(())
+    } else {
+        Err(PolicyViolation(format!("`{}` does not match any entry in allowed_commands", command)).into())
+    }
+}
+
+/// Builds the process for a ticket-supplied command after checking it against the policy.
+///
+/// Without a sandbox the command runs through the platform shell. With `policy.sandbox` set,
+/// that argv is used instead: `{workspace}` and `{command}` are substituted, and if `{command}`
+/// doesn't appear, `sh -c <command>` is appended.
+pub fn build_command(policy: &PolicyConfig, command: &str, workspace_root: &Path) -> Result<Command> {
+    check(policy, command)?;
+
+    let mut cmd = if policy.sandbox.is_empty() {
+        shell_command(command)
+    } else {
+        let workspace = workspace_root.to_string_lossy();
+        let mut argv: Vec<String> = policy.sandbox
+            .iter()
+            .map(|arg| arg.replace("{workspace}", &workspace).replace("{command}", command))
+            .collect();
+        if !policy.sandbox.iter().any(|arg| arg.contains("{command}")) {
+            argv.extend(["sh".to_string(), "-c".to_string(), command.to_string()]);
+        }
+        let mut cmd = Command::new(&argv[0]);
+        cmd.args(&argv[1..]);
+        cmd
+    };
+
+    cmd.current_dir(workspace_root);
+    Ok(cmd)
+}
+
+fn shell_command(command: &str) -> Command {
+    if cfg!(target_os = "windows") {
+        let mut cmd = Command::new("powershell");
+        cmd.args(["-Command", command]);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", command]);
+        cmd
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allowlist_matches_whole_words() {
+        let policy = PolicyConfig {
+            allowed_commands: vec!["cargo test".to_string()],
+            ..Default::default()
+        };
+
+        assert!(check(&policy, "cargo test -p director-plan").is_ok());
+        assert!(check(&policy, "cargo testx").is_err());
+        assert!(check(&policy, "cargo build").is_err());
+        assert!(check(&policy, "cargo test; rm -rf /").is_err());
+        assert!(check(&PolicyConfig::default(), "anything at all").is_ok());
+    }
+}
@@ -0,0 +1,144 @@
+use std::fs;
+use std::path::Path;
+use crate::context::ast::DependencyGraph;
+use crate::context::discovery::pair_tests;
+use crate::types::ScopedVerification;
+
+/// What the agent's changes can reach, for filling in a `[verification.scoped]` command.
+#[derive(Debug, Default, PartialEq)]
+pub struct Affected {
+    /// The files the agent changed, deleted ones excluded
+    pub changed: Vec<String>,
+    /// Test files among the changed files, the files importing them, and their counterparts
+    pub tests: Vec<String>,
+    /// Cargo packages holding any of those files
+    pub packages: Vec<String>,
+}
+
+/// Follows `changed` through `graph` to the files importing them, then to their tests and
+/// packages. Without a graph only the changed files and their test counterparts count.
+pub fn affected(root: &Path, graph: Option<&DependencyGraph>, changed: &[String]) -> Affected {
+    let changed: Vec<String> = changed.iter().filter(|f| root.join(f).is_file()).cloned().collect();
+    let mut reached = changed.clone();
+    if let Some(graph) = graph {
+        for dependent in graph.dependents(&changed) {
+            if !reached.contains(&dependent) {
+                reached.push(dependent);
+            }
+        }
+    }
+    let reached = pair_tests(root, &reached);
+
+    let mut tests: Vec<String> = reached.iter().filter(|f| is_test_file(f)).cloned().collect();
+    tests.sort();
+    let mut packages: Vec<String> = reached.iter().filter_map(|f| cargo_package(root, f)).collect();
+    packages.sort();
+    packages.dedup();
+    Affected { changed, tests, packages }
+}
+
+/// The command to run in place of `verification.command`, or `None` if one of the placeholders
+/// it uses has nothing to fill it with; then the full command runs instead.
+pub fn render(scoped: &ScopedVerification, affected: &Affected) -> Option<String> {
+    let template = match (&scoped.command, scoped.runner) {
+        (Some(command), _) => command.as_str(),
+        (None, Some(runner)) => runner.template(),
+        (None, None) => return None,
+    };
+    let packages: Vec<String> = affected.packages.iter().map(|p| format!("-p {}", shell_word(p))).collect();
+    let mut command = template.to_string();
+    for (placeholder, values) in [
+        ("{changed}", affected.changed.iter().map(|f| shell_word(f)).collect::<Vec<_>>()),
+        ("{tests}", affected.tests.iter().map(|f| shell_word(f)).collect()),
+        ("{packages}", packages),
+    ] {
+        if command.contains(placeholder) {
+            if values.is_empty() {
+                return None;
+            }
+            command = command.replace(placeholder, &values.join(" "));
+        }
+    }
+    Some(command)
+}
+
+/// Tests by the usual conventions: `tests/` and `__tests__/` directories, `*.test.*` and
+/// `*.spec.*`, and pytest's `test_*.py` / `*_test.py`.
+fn is_test_file(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    path.starts_with("tests/")
+        || path.contains("/tests/")
+        || path.contains("__tests__/")
+        || name.contains(".test.")
+        || name.contains(".spec.")
+        || (name.ends_with(".py") && (name.starts_with("test_") || name.ends_with("_test.py")))
+}
+
+/// The `[package] name` of the closest `Cargo.toml` above `file`, within `root`.
+fn cargo_package(root: &Path, file: &str) -> Option<String> {
+    let mut dir = Path::new(file).parent();
+    while let Some(current) = dir {
+        let manifest = root.join(current).join("Cargo.toml");
+        if let Ok(content) = fs::read_to_string(&manifest) {
+            let doc: toml_edit::DocumentMut = content.parse().ok()?;
+            // A virtual workspace manifest has no package; keep looking upwards
+            if let Some(name) = doc.get("package").and_then(|p| p.get("name")).and_then(|n| n.as_str()) {
+                return Some(name.to_string());
+            }
+        }
+        dir = current.parent();
+    }
+    None
+}
+
+/// `word`, single-quoted if the shell would otherwise split or expand it.
+fn shell_word(word: &str) -> String {
+    if !word.is_empty() && word.chars().all(|c| c.is_ascii_alphanumeric() || "-_./@+=:,".contains(c)) {
+        return word.to_string();
+    }
+    format!("'{}'", word.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ScopedRunner;
+
+    #[test]
+    fn test_affected_and_render() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for (path, content) in [
+            ("Cargo.toml", "[workspace]\nmembers = [\"crates/*\"]\n"),
+            ("crates/core/Cargo.toml", "[package]\nname = \"core\"\n"),
+            ("crates/core/src/lib.rs", "mod parse;\n"),
+            ("crates/core/src/parse.rs", ""),
+            ("crates/core/tests/parse.rs", ""),
+            ("crates/cli/Cargo.toml", "[package]\nname = \"cli\"\n"),
+            ("crates/cli/src/main.rs", ""),
+            ("web/my page.ts", ""),
+        ] {
+            fs::create_dir_all(root.join(path).parent().unwrap()).unwrap();
+            fs::write(root.join(path), content).unwrap();
+        }
+        let mut graph = DependencyGraph::new(root);
+        graph.build().unwrap();
+
+        let changed = ["crates/core/src/parse.rs".to_string(), "gone.rs".to_string()];
+        let affected = affected(root, Some(&graph), &changed);
+        assert_eq!(affected.changed, ["crates/core/src/parse.rs"]);
+        assert_eq!(affected.tests, ["crates/core/tests/parse.rs"]);
+        assert_eq!(affected.packages, ["core"]);
+
+        let cargo = ScopedVerification { runner: Some(ScopedRunner::Cargo), command: None };
+        assert_eq!(render(&cargo, &affected).unwrap(), "cargo test -p core");
+        let custom = ScopedVerification { runner: None, command: Some("nextest {packages} -- {changed}".into()) };
+        assert_eq!(render(&custom, &affected).unwrap(), "nextest -p core -- crates/core/src/parse.rs");
+
+        // Nothing for a placeholder to expand to means the full command
+        let web = super::affected(root, Some(&graph), &["web/my page.ts".to_string()]);
+        assert_eq!(render(&cargo, &web), None);
+        let vitest = ScopedVerification { runner: Some(ScopedRunner::Vitest), command: None };
+        assert_eq!(render(&vitest, &web).unwrap(), "npx vitest related --run 'web/my page.ts'");
+    }
+}
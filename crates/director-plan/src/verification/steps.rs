@@ -0,0 +1,285 @@
+use std::collections::HashSet;
+use std::process::Output;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+use anyhow::{Result, anyhow, bail};
+use serde::Serialize;
+use crate::shutdown::Interrupted;
+use crate::types::VerificationStep;
+use crate::verification::test_report::TestCase;
+
+/// What the ticket's `verification.command` is called among its steps.
+pub const COMMAND_STEP: &str = "command";
+
+/// One verification step after it ran, or was skipped.
+#[derive(Debug, Clone, Serialize)]
+pub struct StepRun {
+    pub name: String,
+    pub passed: bool,
+    /// Why the step didn't run, e.g. a step it depends on failed
+    pub skipped: Option<String>,
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+    pub stdout: String,
+    pub stderr: String,
+    /// Why the command couldn't be run at all (policy, spawn failure)
+    pub error: Option<String>,
+}
+
+impl StepRun {
+    fn finished(step: &VerificationStep, duration: Duration, result: &Result<Output>) -> Self {
+        let mut run = Self {
+            name: step.name.clone(),
+            passed: false,
+            skipped: None,
+            exit_code: None,
+            duration_ms: duration.as_millis() as u64,
+            stdout: String::new(),
+            stderr: String::new(),
+            error: None,
+        };
+        match result {
+            Ok(output) => {
+                run.passed = output.status.success();
+                run.exit_code = output.status.code();
+                run.stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+                run.stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+            }
+            Err(e) => run.error = Some(format!("{:#}", e)),
+        }
+        run
+    }
+
+    fn skipped(step: &VerificationStep, reason: String) -> Self {
+        Self {
+            name: step.name.clone(),
+            passed: false,
+            skipped: Some(reason),
+            exit_code: None,
+            duration_ms: 0,
+            stdout: String::new(),
+            stderr: String::new(),
+            error: None,
+        }
+    }
+
+    /// What went wrong, as sent back to the agent. `None` if the step passed or didn't run.
+    pub fn failure(&self) -> Option<String> {
+        if self.passed || self.skipped.is_some() {
+            return None;
+        }
+        let label = if self.name == COMMAND_STEP { "Command".to_string() } else { format!("Step {}", self.name) };
+        Some(match &self.error {
+            Some(error) => format!("{} Failed: {}", label, error),
+            None => format!("{} Failed:\nSTDOUT:\n{}\nSTDERR:\n{}", label, self.stdout, self.stderr),
+        })
+    }
+
+    pub fn test_case(&self) -> TestCase {
+        if let Some(reason) = &self.skipped {
+            return TestCase::skipped(&self.name, reason);
+        }
+        let result = match self.failure() {
+            Some(failure) => Err(anyhow!(failure)),
+            None => Ok(()),
+        };
+        TestCase::from_result(&self.name, Duration::from_millis(self.duration_ms), &result)
+    }
+}
+
+/// Rejects steps that can't be scheduled: unnamed or duplicate names, dependencies on steps
+/// that don't exist, and cycles.
+pub fn check(steps: &[VerificationStep]) -> Result<()> {
+    let mut names = HashSet::new();
+    for step in steps {
+        if step.name.trim().is_empty() {
+            bail!("verification.steps: every step needs a name");
+        }
+        if !names.insert(step.name.as_str()) {
+            bail!("verification.steps: more than one step is called {}", step.name);
+        }
+        if step.command.trim().is_empty() {
+            bail!("verification.steps: step {} has no command", step.name);
+        }
+    }
+    for step in steps {
+        if let Some(missing) = step.depends_on.iter().find(|d| !names.contains(d.as_str())) {
+            bail!("verification.steps: step {} depends on unknown step {}", step.name, missing);
+        }
+    }
+
+    // Peel off steps whose dependencies are all placed; whatever is left is in a cycle
+    let mut placed: HashSet<&str> = HashSet::new();
+    while placed.len() < steps.len() {
+        let ready: Vec<&str> = steps.iter()
+            .filter(|s| !placed.contains(s.name.as_str()) && s.depends_on.iter().all(|d| placed.contains(d.as_str())))
+            .map(|s| s.name.as_str())
+            .collect();
+        if ready.is_empty() {
+            let stuck: Vec<&str> = steps.iter().map(|s| s.name.as_str()).filter(|n| !placed.contains(n)).collect();
+            bail!("verification.steps: dependency cycle between {}", stuck.join(", "));
+        }
+        placed.extend(ready);
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Pending,
+    Running,
+    Passed,
+    /// Failed or skipped; either way its dependents can't run
+    NotPassed,
+}
+
+/// Runs `steps` with `run_step`, each once everything it depends on has passed. Steps marked
+/// `parallel` run alongside each other, at most `jobs` at once; any other step waits until
+/// nothing else is running and then runs alone. A failed step doesn't stop the ones that don't
+/// depend on it, so every independent failure is reported at once. Results are in the order of
+/// `steps`.
+///
+/// Fails if the steps don't form a valid graph (see [`check`]), or with [`Interrupted`] if any
+/// step was cancelled, after the steps already running have finished.
+pub fn run<F>(steps: &[VerificationStep], jobs: usize, run_step: F) -> Result<Vec<StepRun>>
+where
+    F: Fn(&VerificationStep) -> Result<Output> + Sync,
+{
+    check(steps)?;
+    let jobs = jobs.max(1);
+    let index_of = |name: &str| steps.iter().position(|s| s.name == name);
+    let mut states = vec![State::Pending; steps.len()];
+    let mut results: Vec<Option<StepRun>> = vec![None; steps.len()];
+    let mut interrupted = false;
+
+    thread::scope(|scope| {
+        let (tx, rx) = mpsc::channel();
+        let mut running = 0;
+        let mut exclusive = false;
+        loop {
+            // Skip everything downstream of a step that didn't pass, until nothing changes
+            let mut changed = true;
+            while changed {
+                changed = false;
+                for (i, step) in steps.iter().enumerate() {
+                    if states[i] != State::Pending {
+                        continue;
+                    }
+                    let blocker = step.depends_on.iter()
+                        .find(|d| index_of(d).is_some_and(|j| states[j] == State::NotPassed));
+                    if let Some(blocker) = blocker {
+                        results[i] = Some(StepRun::skipped(step, format!("{} did not pass", blocker)));
+                        states[i] = State::NotPassed;
+                        changed = true;
+                    }
+                }
+            }
+
+            // Start what's ready, in declaration order. A serial step that's next in line
+            // holds back the steps after it rather than waiting forever.
+            for (i, step) in steps.iter().enumerate() {
+                if interrupted || exclusive || running >= jobs {
+                    break;
+                }
+                let ready = states[i] == State::Pending
+                    && step.depends_on.iter().all(|d| index_of(d).is_some_and(|j| states[j] == State::Passed));
+                if !ready {
+                    continue;
+                }
+                if !step.parallel {
+                    if running > 0 {
+                        break;
+                    }
+                    exclusive = true;
+                }
+                states[i] = State::Running;
+                running += 1;
+                let tx = tx.clone();
+                let run_step = &run_step;
+                scope.spawn(move || {
+                    let started = Instant::now();
+                    let result = run_step(step);
+                    // The receiver outlives every step
+                    let _ = tx.send((i, started.elapsed(), result));
+                });
+            }
+
+            if running == 0 {
+                break;
+            }
+            let Ok((i, duration, result)) = rx.recv() else { break };
+            running -= 1;
+            if !steps[i].parallel {
+                exclusive = false;
+            }
+            interrupted |= result.as_ref().is_err_and(|e| e.is::<Interrupted>());
+            let run = StepRun::finished(&steps[i], duration, &result);
+            states[i] = if run.passed { State::Passed } else { State::NotPassed };
+            results[i] = Some(run);
+        }
+    });
+
+    if interrupted {
+        return Err(anyhow::Error::new(Interrupted));
+    }
+    Ok(results.into_iter().map(|r| r.expect("every step finishes or is skipped")).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+    use std::sync::Mutex;
+
+    fn step(name: &str, command: &str, depends_on: &[&str], parallel: bool) -> VerificationStep {
+        VerificationStep {
+            name: name.to_string(),
+            command: command.to_string(),
+            depends_on: depends_on.iter().map(|d| d.to_string()).collect(),
+            parallel,
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        assert!(check(&[step("a", "true", &[], true), step("b", "true", &["a"], false)]).is_ok());
+        assert!(check(&[step("a", "true", &[], true), step("a", "true", &[], true)]).is_err());
+        assert!(check(&[step("a", "true", &["missing"], true)]).is_err());
+        let err = check(&[step("a", "true", &["b"], true), step("b", "true", &["a"], true), step("c", "true", &[], true)]).unwrap_err();
+        assert_eq!(err.to_string(), "verification.steps: dependency cycle between a, b");
+    }
+
+    #[test]
+    fn test_run_order_and_failures() {
+        let steps = [
+            step("lint", "sleep 0.2; echo lint", &[], true),
+            step("typecheck", "sleep 0.2; echo types; exit 2", &[], true),
+            step("unit", "sleep 0.2; echo unit", &[], true),
+            step("e2e", "echo e2e", &["typecheck", "unit"], false),
+            step("docs", "echo docs", &[], false),
+        ];
+        let log = Mutex::new(Vec::new());
+        let runs = run(&steps, 4, |step| {
+            log.lock().unwrap().push(format!("start {}", step.name));
+            let output = Command::new("sh").arg("-c").arg(&step.command).output()?;
+            log.lock().unwrap().push(format!("end {}", step.name));
+            Ok(output)
+        }).unwrap();
+
+        let names: Vec<&str> = runs.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["lint", "typecheck", "unit", "e2e", "docs"]);
+        assert!(runs[0].passed && runs[2].passed && runs[4].passed);
+        assert_eq!(runs[1].exit_code, Some(2));
+        assert_eq!(runs[1].failure().unwrap(), "Step typecheck Failed:\nSTDOUT:\ntypes\n\nSTDERR:\n");
+        assert_eq!(runs[3].skipped.as_deref(), Some("typecheck did not pass"));
+        assert_eq!(runs[2].stdout, "unit\n");
+
+        // The three parallel steps overlap; the serial one waits for all of them
+        let log = log.into_inner().unwrap();
+        let mut started: Vec<&str> = log[..3].iter().map(String::as_str).collect();
+        started.sort();
+        assert_eq!(started, ["start lint", "start typecheck", "start unit"]);
+        assert_eq!(log[log.len() - 2..], ["start docs", "end docs"]);
+    }
+}
@@ -0,0 +1,259 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::config::VisualConfig;
+use crate::types::Verification;
+use super::capture::{CaptureTarget, DEFAULT_URL};
+use super::cdp;
+use super::visual_diff::{parse_viewport, validate_snapshot_name};
+
+/// A normalized accessibility or DOM node. This is also the golden JSON format.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct StructNode {
+    /// Accessibility role, or lowercase tag name for DOM snapshots
+    pub role: String,
+    /// Accessible name, or the element's own (whitespace-collapsed) text
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub attrs: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<StructNode>,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct StructureChange {
+    pub kind: ChangeKind,
+    /// e.g. `main > list > listitem "Settings"`
+    pub path: String,
+    pub detail: Option<String>,
+}
+
+/// Result for one `[[verification.structure]]` entry.
+#[derive(Debug, Serialize)]
+pub struct StructureReport {
+    pub name: String,
+    pub actual_path: PathBuf,
+    pub changes: Vec<StructureChange>,
+}
+
+/// Captures every structure snapshot, writes it to `proof/<name>.json` and diffs it against its golden.
+pub fn verify_structures(
+    workspace_root: &Path,
+    verification: &Verification,
+    config: &VisualConfig,
+) -> Result<Vec<StructureReport>> {
+    for snapshot in &verification.structure {
+        validate_snapshot_name(&snapshot.name)?;
+        if let Some(viewport) = &snapshot.viewport {
+            parse_viewport(viewport)?;
+        }
+    }
+
+    let mut reports = Vec::new();
+    for snapshot in &verification.structure {
+        crate::progress!(">> Capturing {:?} tree '{}'", snapshot.kind, snapshot.name);
+        let target = CaptureTarget {
+            url: snapshot.url.as_deref().or(verification.url.as_deref()).unwrap_or(DEFAULT_URL),
+            viewport: snapshot.viewport.as_deref().map(parse_viewport).transpose()?,
+            wait_for: snapshot.wait_for.as_deref().or(verification.wait_for.as_deref()),
+        };
+        if config.backend != "cdp" {
+            return Err(anyhow!("Structure snapshots need [visual] backend = \"cdp\""));
+        }
+        let actual = cdp::capture_structure(config, &target, snapshot.kind, snapshot.selector.as_deref())
+            .with_context(|| format!("Structure snapshot '{}' failed", snapshot.name))?;
+
+        let actual_path = workspace_root.join("proof").join(format!("{}.json", snapshot.name));
+        if let Some(dir) = actual_path.parent() {
+            fs::create_dir_all(dir).context("Failed to create proof directory")?;
+        }
+        fs::write(&actual_path, serde_json::to_string_pretty(&actual)?)
+            .with_context(|| format!("Failed to write {:?}", actual_path))?;
+
+        let golden_path = workspace_root.join(&snapshot.golden);
+        let golden: StructNode = serde_json::from_str(
+            &fs::read_to_string(&golden_path).with_context(|| format!("Golden snapshot not found at {:?}", golden_path))?,
+        ).with_context(|| format!("Failed to parse golden snapshot {:?}", golden_path))?;
+
+        reports.push(StructureReport {
+            name: snapshot.name.clone(),
+            actual_path,
+            changes: diff(&golden, &actual),
+        });
+    }
+    Ok(reports)
+}
+
+/// Builds a tree from `Accessibility.getFullAXTree` nodes. Ignored nodes are dropped and their
+/// children moved up, and generic containers without a name are flattened the same way.
+pub fn from_ax_nodes(nodes: &[Value]) -> Option<StructNode> {
+    let by_id: HashMap<&str, &Value> = nodes.iter()
+        .filter_map(|n| Some((n["nodeId"].as_str()?, n)))
+        .collect();
+    let root = nodes.first()?;
+    let mut built = build_ax(root, &by_id);
+    if built.len() == 1 {
+        built.pop()
+    } else {
+        Some(StructNode { role: "RootWebArea".to_string(), name: String::new(), attrs: BTreeMap::new(), children: built })
+    }
+}
+
+fn build_ax(node: &Value, by_id: &HashMap<&str, &Value>) -> Vec<StructNode> {
+    let children: Vec<StructNode> = node["childIds"].as_array()
+        .map(|ids| ids.iter()
+            .filter_map(|id| by_id.get(id.as_str()?))
+            .flat_map(|child| build_ax(child, by_id))
+            .collect())
+        .unwrap_or_default();
+
+    let role = node["role"]["value"].as_str().unwrap_or("");
+    let name = node["name"]["value"].as_str().unwrap_or("").split_whitespace().collect::<Vec<_>>().join(" ");
+    let transparent = matches!(role, "" | "none" | "generic" | "InlineTextBox" | "LineBreak")
+        && name.is_empty();
+    if node["ignored"].as_bool() == Some(true) || transparent {
+        return children;
+    }
+
+    let mut attrs = BTreeMap::new();
+    if let Some(value) = node["value"]["value"].as_str() {
+        attrs.insert("value".to_string(), value.to_string());
+    }
+    vec![StructNode { role: role.to_string(), name, attrs, children }]
+}
+
+/// Added/removed/changed nodes between two trees, in document order.
+pub fn diff(golden: &StructNode, actual: &StructNode) -> Vec<StructureChange> {
+    let mut changes = Vec::new();
+    diff_node(golden, actual, &label(actual), &mut changes);
+    changes
+}
+
+fn diff_node(golden: &StructNode, actual: &StructNode, path: &str, changes: &mut Vec<StructureChange>) {
+    if golden.role != actual.role || golden.name != actual.name {
+        changes.push(StructureChange {
+            kind: ChangeKind::Changed,
+            path: path.to_string(),
+            detail: Some(format!("was {}", label(golden))),
+        });
+    }
+    if golden.attrs != actual.attrs {
+        changes.push(StructureChange {
+            kind: ChangeKind::Changed,
+            path: path.to_string(),
+            detail: Some(format!("attributes {:?} -> {:?}", golden.attrs, actual.attrs)),
+        });
+    }
+
+    // Align children on (role, name) with an LCS, then pair up leftovers of the same role as changes
+    let key = |n: &StructNode| (n.role.clone(), n.name.clone());
+    let old: Vec<_> = golden.children.iter().map(key).collect();
+    let new: Vec<_> = actual.children.iter().map(key).collect();
+    let pairs = lcs_pairs(&old, &new);
+
+    let (mut i, mut j) = (0, 0);
+    for (pi, pj) in pairs.into_iter().chain(std::iter::once((old.len(), new.len()))) {
+        let mut removed: Vec<&StructNode> = golden.children[i..pi].iter().collect();
+        let mut added: Vec<&StructNode> = actual.children[j..pj].iter().collect();
+
+        // Same role in the same gap: report as a change rather than a remove + add
+        let mut k = 0;
+        while k < removed.len() {
+            if let Some(pos) = added.iter().position(|a| a.role == removed[k].role) {
+                let (g, a) = (removed.remove(k), added.remove(pos));
+                diff_node(g, a, &format!("{} > {}", path, label(a)), changes);
+            } else {
+                k += 1;
+            }
+        }
+        for node in removed {
+            changes.push(StructureChange { kind: ChangeKind::Removed, path: format!("{} > {}", path, label(node)), detail: None });
+        }
+        for node in added {
+            changes.push(StructureChange { kind: ChangeKind::Added, path: format!("{} > {}", path, label(node)), detail: None });
+        }
+
+        if pi < old.len() {
+            let child = &actual.children[pj];
+            diff_node(&golden.children[pi], child, &format!("{} > {}", path, label(child)), changes);
+        }
+        i = pi + 1;
+        j = pj + 1;
+    }
+}
+
+/// Index pairs of a longest common subsequence, in order.
+pub(crate) fn lcs_pairs<T: PartialEq>(a: &[T], b: &[T]) -> Vec<(usize, usize)> {
+    let mut table = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            table[i][j] = if a[i] == b[j] { table[i + 1][j + 1] + 1 } else { table[i + 1][j].max(table[i][j + 1]) };
+        }
+    }
+    let (mut i, mut j, mut pairs) = (0, 0, Vec::new());
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if table[i + 1][j] >= table[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+fn label(node: &StructNode) -> String {
+    if node.name.is_empty() {
+        node.role.clone()
+    } else {
+        format!("{} {:?}", node.role, node.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(role: &str, name: &str, children: Vec<StructNode>) -> StructNode {
+        StructNode { role: role.to_string(), name: name.to_string(), attrs: BTreeMap::new(), children }
+    }
+
+    #[test]
+    fn test_diff_reports_added_removed_changed() {
+        let golden = node("main", "", vec![
+            node("heading", "Settings", vec![]),
+            node("button", "Save", vec![]),
+            node("link", "Help", vec![]),
+        ]);
+        let actual = node("main", "", vec![
+            node("heading", "Settings", vec![]),
+            node("button", "Submit", vec![]),
+            node("checkbox", "Remember me", vec![]),
+        ]);
+
+        let changes = diff(&golden, &actual);
+        assert_eq!(changes.len(), 3);
+        assert_eq!(changes[0].kind, ChangeKind::Changed);
+        assert_eq!(changes[0].path, "main > button \"Submit\"");
+        assert_eq!(changes[1].kind, ChangeKind::Removed);
+        assert_eq!(changes[1].path, "main > link \"Help\"");
+        assert_eq!(changes[2].kind, ChangeKind::Added);
+
+        assert!(diff(&golden, &golden).is_empty());
+    }
+}
@@ -0,0 +1,254 @@
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use anyhow::{Context, Result};
+
+/// Formats `verify --report` can write for CI systems to pick up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Junit,
+    Tap,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CaseOutcome {
+    Passed,
+    Failed(String),
+    /// Not run, with the reason (usually that an earlier step failed)
+    Skipped(String),
+}
+
+/// One verification step (the command, a lint step, the visual snapshots, ...).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestCase {
+    pub name: String,
+    pub duration: Duration,
+    pub outcome: CaseOutcome,
+}
+
+impl TestCase {
+    pub fn from_result<T>(name: &str, duration: Duration, result: &Result<T>) -> Self {
+        let outcome = match result {
+            Ok(_) => CaseOutcome::Passed,
+            Err(e) => CaseOutcome::Failed(format!("{:#}", e)),
+        };
+        Self { name: name.to_string(), duration, outcome }
+    }
+
+    pub fn skipped(name: &str, reason: &str) -> Self {
+        Self { name: name.to_string(), duration: Duration::ZERO, outcome: CaseOutcome::Skipped(reason.to_string()) }
+    }
+}
+
+/// Runs verification steps in order, recording each as a [`TestCase`]. After the first failure
+/// the remaining steps are recorded as skipped rather than run, and [`Steps::finish`] returns
+/// that failure.
+#[derive(Default)]
+pub struct Steps {
+    pub cases: Vec<TestCase>,
+    failed: Option<anyhow::Error>,
+}
+
+impl Steps {
+    /// Runs `step` unless an earlier one failed. Steps that don't apply to the ticket
+    /// (`enabled` false) aren't recorded at all.
+    pub fn run(&mut self, name: &str, enabled: bool, step: impl FnOnce() -> Result<()>) {
+        if !enabled {
+            return;
+        }
+        if self.failed.is_some() {
+            self.cases.push(TestCase::skipped(name, "an earlier step failed"));
+            return;
+        }
+        let started = std::time::Instant::now();
+        let result = step();
+        self.cases.push(TestCase::from_result(name, started.elapsed(), &result));
+        self.failed = result.err();
+    }
+
+    /// Like [`run`](Self::run) for steps that `group` runs together (`[[verification.steps]]`),
+    /// returning a case for each. All of their failures go into the error. If `group` fails
+    /// as a whole, e.g. when interrupted, that is recorded as one case.
+    pub fn run_all(&mut self, names: &[String], group: impl FnOnce() -> Result<Vec<TestCase>>) {
+        if names.is_empty() {
+            return;
+        }
+        if self.failed.is_some() {
+            for name in names {
+                self.cases.push(TestCase::skipped(name, "an earlier step failed"));
+            }
+            return;
+        }
+        let started = std::time::Instant::now();
+        let cases = match group() {
+            Ok(cases) => cases,
+            Err(e) => {
+                let result: Result<()> = Err(e);
+                self.cases.push(TestCase::from_result(&names.join(", "), started.elapsed(), &result));
+                self.failed = result.err();
+                return;
+            }
+        };
+        let failures: Vec<&str> = cases.iter()
+            .filter_map(|c| match &c.outcome {
+                CaseOutcome::Failed(message) => Some(message.as_str()),
+                _ => None,
+            })
+            .collect();
+        if !failures.is_empty() {
+            self.failed = Some(anyhow::anyhow!("{}", failures.join("\n\n")));
+        }
+        self.cases.extend(cases);
+    }
+
+    pub fn finish(&mut self) -> Result<()> {
+        match self.failed.take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+pub fn render(format: ReportFormat, suite: &str, cases: &[TestCase]) -> String {
+    match format {
+        ReportFormat::Junit => render_junit(suite, cases),
+        ReportFormat::Tap => render_tap(suite, cases),
+    }
+}
+
+pub fn write(format: ReportFormat, path: &Path, suite: &str, cases: &[TestCase]) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, render(format, suite, cases))
+        .with_context(|| format!("Failed to write verification report {:?}", path))
+}
+
+fn render_junit(suite: &str, cases: &[TestCase]) -> String {
+    let failures = cases.iter().filter(|c| matches!(c.outcome, CaseOutcome::Failed(_))).count();
+    let skipped = cases.iter().filter(|c| matches!(c.outcome, CaseOutcome::Skipped(_))).count();
+    let time: f64 = cases.iter().map(|c| c.duration.as_secs_f64()).sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">\n",
+        cases.len(), failures, skipped, time,
+    ));
+    xml.push_str(&format!(
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">\n",
+        escape_xml(suite), cases.len(), failures, skipped, time,
+    ));
+    for case in cases {
+        let open = format!(
+            "    <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\"",
+            escape_xml(suite), escape_xml(&case.name), case.duration.as_secs_f64(),
+        );
+        match &case.outcome {
+            CaseOutcome::Passed => xml.push_str(&format!("{} />\n", open)),
+            CaseOutcome::Failed(message) => xml.push_str(&format!(
+                "{}>\n      <failure message=\"{}\">{}</failure>\n    </testcase>\n",
+                open, escape_xml(message.lines().next().unwrap_or_default()), escape_xml(message),
+            )),
+            CaseOutcome::Skipped(reason) => xml.push_str(&format!(
+                "{}>\n      <skipped message=\"{}\" />\n    </testcase>\n",
+                open, escape_xml(reason),
+            )),
+        }
+    }
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+fn render_tap(suite: &str, cases: &[TestCase]) -> String {
+    let mut tap = format!("TAP version 13\n1..{}\n# {}\n", cases.len(), suite.replace('\n', " "));
+    for (i, case) in cases.iter().enumerate() {
+        let name = case.name.replace('#', "\\#");
+        match &case.outcome {
+            CaseOutcome::Passed => tap.push_str(&format!("ok {} - {}\n", i + 1, name)),
+            CaseOutcome::Skipped(reason) => tap.push_str(&format!("ok {} - {} # SKIP {}\n", i + 1, name, reason)),
+            CaseOutcome::Failed(message) => {
+                tap.push_str(&format!("not ok {} - {}\n  ---\n  message: |\n", i + 1, name));
+                for line in message.lines() {
+                    tap.push_str(&format!("    {}\n", line));
+                }
+                tap.push_str(&format!("  duration_ms: {}\n  ...\n", case.duration.as_millis()));
+            }
+        }
+    }
+    tap
+}
+
+fn escape_xml(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            // Control characters from command output aren't allowed in XML 1.0
+            '\t' | '\n' | '\r' => out.push(c),
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    fn sample() -> Vec<TestCase> {
+        let mut steps = Steps::default();
+        steps.run("lint: fmt", true, || Ok(()));
+        steps.run("perf", false, || Ok(()));
+        steps.run("command", true, || Err(anyhow!("Command Failed:\nexpected <1> & got \"2\"\u{1b}[0m")));
+        steps.run("visual", true, || panic!("runs after a failure"));
+        assert!(steps.finish().is_err());
+        steps.cases
+    }
+
+    #[test]
+    fn test_steps_skip_after_failure() {
+        let outcomes: Vec<_> = sample().into_iter().map(|c| (c.name, c.outcome)).collect();
+        assert_eq!(outcomes[0], ("lint: fmt".to_string(), CaseOutcome::Passed));
+        assert_eq!(outcomes[1].0, "command");
+        assert!(matches!(outcomes[1].1, CaseOutcome::Failed(_)));
+        assert_eq!(outcomes[2], ("visual".to_string(), CaseOutcome::Skipped("an earlier step failed".to_string())));
+    }
+
+    #[test]
+    fn test_steps_run_all() {
+        let mut steps = Steps::default();
+        let names = ["unit".to_string(), "e2e".to_string()];
+        steps.run_all(&names, || Ok(vec![
+            TestCase::from_result("unit", Duration::ZERO, &Err::<(), _>(anyhow!("Step unit Failed"))),
+            TestCase::from_result("e2e", Duration::ZERO, &Err::<(), _>(anyhow!("Step e2e Failed"))),
+        ]));
+        steps.run_all(&names, || panic!("runs after a failure"));
+        assert_eq!(steps.finish().unwrap_err().to_string(), "Step unit Failed\n\nStep e2e Failed");
+        assert_eq!(steps.cases.len(), 4);
+        assert_eq!(steps.cases[3].outcome, CaseOutcome::Skipped("an earlier step failed".to_string()));
+    }
+
+    #[test]
+    fn test_render_junit() {
+        let xml = render(ReportFormat::Junit, "T-1", &sample());
+        assert!(xml.contains("<testsuite name=\"T-1\" tests=\"3\" failures=\"1\" skipped=\"1\""));
+        assert!(xml.contains("<testcase classname=\"T-1\" name=\"lint: fmt\""));
+        assert!(xml.contains("<failure message=\"Command Failed:\">Command Failed:\nexpected &lt;1&gt; &amp; got &quot;2&quot;[0m</failure>"));
+        assert!(xml.contains("<skipped message=\"an earlier step failed\" />"));
+    }
+
+    #[test]
+    fn test_render_tap() {
+        let tap = render(ReportFormat::Tap, "T-1", &sample());
+        assert!(tap.starts_with("TAP version 13\n1..3\n"));
+        assert!(tap.contains("ok 1 - lint: fmt\n"));
+        assert!(tap.contains("not ok 2 - command\n  ---\n  message: |\n    Command Failed:\n"));
+        assert!(tap.contains("ok 3 - visual # SKIP an earlier step failed\n"));
+    }
+}
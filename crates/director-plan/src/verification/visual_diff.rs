@@ -1,9 +1,12 @@
-use std::path::Path;
-use std::process::Command;
+use std::path::{Path, PathBuf};
 use anyhow::{Context, Result, anyhow};
-use image::{GenericImageView, ImageReader, Pixel, Rgba};
+use image::{GenericImageView, ImageReader};
 use serde::Serialize;
 use std::fs;
+use crate::config::VisualConfig;
+use crate::types::{ImageCompare, Verification};
+use super::compare::compare_images;
+use super::capture::{CaptureTarget, DEFAULT_URL, capture};
 
 #[derive(Debug, Serialize)]
 pub struct VisualDiffReport {
@@ -11,6 +14,8 @@ pub struct VisualDiffReport {
     pub mismatch_percentage: f64,
     pub diff_bounds: Option<Rect>,
     pub reason: Option<String>,
+    /// Mean structural similarity, when compared with `method = "ssim"`
+    pub ssim: Option<f64>,
 }
 
 #[derive(Debug, Serialize)]
@@ -21,49 +26,129 @@ pub struct Rect {
     pub height: u32,
 }
 
+/// Result for one `[[verification.visual]]` entry.
+#[derive(Debug, Serialize)]
+pub struct SnapshotReport {
+    pub name: String,
+    pub viewport: Option<String>,
+    pub actual_path: PathBuf,
+    pub report: VisualDiffReport,
+}
+
+/// Captures `verification.url` and compares it against `golden_path`, writing `proof/actual.png`.
 pub fn verify_visual(
     workspace_root: &Path,
+    verification: &Verification,
     golden_path: &str,
+    config: &VisualConfig,
 ) -> Result<VisualDiffReport> {
-    let proof_dir = workspace_root.join("proof");
-    if !proof_dir.exists() {
-        fs::create_dir_all(&proof_dir).context("Failed to create proof directory")?;
+    let actual_path = workspace_root.join("proof").join("actual.png");
+    let target = CaptureTarget {
+        url: verification.url.as_deref().unwrap_or(DEFAULT_URL),
+        viewport: None,
+        wait_for: verification.wait_for.as_deref(),
+    };
+    capture_and_compare(workspace_root, config, &target, golden_path, &actual_path, &verification.compare)
+}
+
+/// Captures and compares every `verification.visual` snapshot, one capture each.
+///
+/// All entries are checked even if an earlier one differs, so the report covers every viewport.
+/// Capture failures (browser errors, missing goldens) still abort with an error.
+pub fn verify_snapshots(
+    workspace_root: &Path,
+    verification: &Verification,
+    config: &VisualConfig,
+) -> Result<Vec<SnapshotReport>> {
+    let snapshots = &verification.visual;
+    // Fail on a bad entry before spending time on screenshots
+    for snapshot in snapshots {
+        validate_snapshot_name(&snapshot.name)?;
+        if let Some(viewport) = &snapshot.viewport {
+            parse_viewport(viewport)?;
+        }
     }
 
-    let actual_path = proof_dir.join("actual.png");
-    let golden_full_path = workspace_root.join(golden_path);
+    let mut reports = Vec::new();
+    for snapshot in snapshots {
+        crate::progress!(">> Capturing snapshot '{}' ({})", snapshot.name, snapshot.viewport.as_deref().unwrap_or("default viewport"));
+        let actual_path = workspace_root.join("proof").join(format!("{}.png", snapshot.name));
+        let target = CaptureTarget {
+            url: snapshot.url.as_deref().or(verification.url.as_deref()).unwrap_or(DEFAULT_URL),
+            viewport: snapshot.viewport.as_deref().map(parse_viewport).transpose()?,
+            wait_for: snapshot.wait_for.as_deref().or(verification.wait_for.as_deref()),
+        };
+        let report = capture_and_compare(
+            workspace_root,
+            config,
+            &target,
+            &snapshot.golden,
+            &actual_path,
+            snapshot.compare.as_ref().unwrap_or(&verification.compare),
+        )
+            .with_context(|| format!("Snapshot '{}' failed", snapshot.name))?;
+        reports.push(SnapshotReport {
+            name: snapshot.name.clone(),
+            viewport: snapshot.viewport.clone(),
+            actual_path,
+            report,
+        });
+    }
+    Ok(reports)
+}
 
-    // 1. Capture Screenshot via Playwright
-    // Note: This assumes the frontend server is running on localhost:3000
-    // In a real scenario, we might need to start it or ensure it's up.
-    // For now, we rely on the environment being set up.
+impl VisualDiffReport {
+    /// The screenshot and golden differ in size, so no amount of retrying will make them match.
+    pub fn is_dimension_mismatch(&self) -> bool {
+        self.reason.as_deref().is_some_and(|r| r.starts_with(DIMENSION_MISMATCH))
+    }
+}
 
-    // We need to run this from apps/director-plan directory because that's where playwright config/deps are
-    let frontend_dir = workspace_root.join("apps/director-plan");
+const DIMENSION_MISMATCH: &str = "Dimensions mismatch";
+
+/// `"390x844"` -> `(390, 844)`
+pub fn parse_viewport(viewport: &str) -> Result<(u32, u32)> {
+    let (width, height) = viewport
+        .split_once('x')
+        .ok_or_else(|| anyhow!("Invalid viewport '{}', expected WIDTHxHEIGHT", viewport))?;
+    let parse = |v: &str| v.trim().parse::<u32>().ok().filter(|n| *n > 0);
+    match (parse(width), parse(height)) {
+        (Some(width), Some(height)) => Ok((width, height)),
+        _ => Err(anyhow!("Invalid viewport '{}', expected WIDTHxHEIGHT", viewport)),
+    }
+}
 
-    // TARGET_URL=http://localhost:3000 OUTPUT=proof/actual.png npx playwright test scripts/snapshot.spec.ts
-    // output path needs to be absolute or relative to apps/director-plan?
-    // Playwright test runs relative to the config/project root.
-    // Let's pass absolute path for output to be safe.
+/// Snapshot names become file names, so keep them to `[A-Za-z0-9_-]`.
+pub fn validate_snapshot_name(name: &str) -> Result<()> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(anyhow!("Invalid snapshot name '{}': use letters, digits, '-' and '_'", name));
+    }
+    Ok(())
+}
 
-    let output_arg = actual_path.to_string_lossy().to_string();
+fn capture_and_compare(
+    workspace_root: &Path,
+    config: &VisualConfig,
+    target: &CaptureTarget,
+    golden_path: &str,
+    actual_path: &Path,
+    settings: &ImageCompare,
+) -> Result<VisualDiffReport> {
+    if let Some(dir) = actual_path.parent()
+        && !dir.exists()
+    {
+        fs::create_dir_all(dir).context("Failed to create proof directory")?;
+    }
 
-    let status = Command::new("npx")
-        .current_dir(&frontend_dir)
-        .arg("playwright")
-        .arg("test")
-        .arg("scripts/snapshot.spec.ts")
-        .env("TARGET_URL", "http://localhost:3000") // TODO: Make configurable?
-        .env("OUTPUT", &output_arg)
-        .status()
-        .context("Failed to execute playwright script")?;
+    let golden_full_path = workspace_root.join(golden_path);
 
-    if !status.success() {
-        return Err(anyhow!("Playwright screenshot capture failed"));
-    }
+    // 1. Capture Screenshot
+    // Note: This assumes the target server is already running.
+    // For now, we rely on the environment being set up.
+    capture(workspace_root, config, target, actual_path)?;
 
     if !actual_path.exists() {
-         return Err(anyhow!("Playwright finished but actual.png was not created at {:?}", actual_path));
+         return Err(anyhow!("Capture finished but no screenshot was created at {:?}", actual_path));
     }
 
     // 2. Compare Images
@@ -78,7 +163,7 @@ pub fn verify_visual(
     }
 
     let img1 = ImageReader::open(&golden_full_path)?.decode().context("Failed to decode golden image")?;
-    let img2 = ImageReader::open(&actual_path)?.decode().context("Failed to decode actual image")?;
+    let img2 = ImageReader::open(actual_path)?.decode().context("Failed to decode actual image")?;
 
     if img1.dimensions() != img2.dimensions() {
         return Ok(VisualDiffReport {
@@ -86,68 +171,29 @@ pub fn verify_visual(
             mismatch_percentage: 100.0,
             diff_bounds: None,
             reason: Some(format!(
-                "Dimensions mismatch: Golden {:?} vs Actual {:?}",
+                "{}: Golden {:?} vs Actual {:?}",
+                DIMENSION_MISMATCH,
                 img1.dimensions(),
                 img2.dimensions()
             )),
+            ssim: None,
         });
     }
 
-    let (width, height) = img1.dimensions();
-    let mut mismatch_count = 0;
-    let mut min_x = width;
-    let mut max_x = 0;
-    let mut min_y = height;
-    let mut max_y = 0;
-
-    for y in 0..height {
-        for x in 0..width {
-            let p1 = img1.get_pixel(x, y);
-            let p2 = img2.get_pixel(x, y);
-
-            if !pixels_match(p1, p2, 0) { // Tolerance 0 for now
-                mismatch_count += 1;
-                if x < min_x { min_x = x; }
-                if x > max_x { max_x = x; }
-                if y < min_y { min_y = y; }
-                if y > max_y { max_y = y; }
-            }
-        }
-    }
-
-    let total_pixels = (width * height) as f64;
-    let mismatch_percentage = (mismatch_count as f64 / total_pixels) * 100.0;
-
-    if mismatch_count > 0 {
-        Ok(VisualDiffReport {
-            diff_detected: true,
-            mismatch_percentage,
-            diff_bounds: Some(Rect {
-                x: min_x,
-                y: min_y,
-                width: max_x - min_x + 1,
-                height: max_y - min_y + 1,
-            }),
-            reason: Some("Pixel mismatch detected".to_string()),
-        })
-    } else {
-        Ok(VisualDiffReport {
-            diff_detected: false,
-            mismatch_percentage: 0.0,
-            diff_bounds: None,
-            reason: None,
-        })
-    }
+    Ok(compare_images(&img1, &img2, settings))
 }
 
-fn pixels_match(p1: impl Pixel<Subpixel = u8>, p2: impl Pixel<Subpixel = u8>, tolerance: u8) -> bool {
-    let p1_channels = p1.channels();
-    let p2_channels = p2.channels();
-
-    for (c1, c2) in p1_channels.iter().zip(p2_channels.iter()) {
-        if (*c1 as i16 - *c2 as i16).abs() > tolerance as i16 {
-            return false;
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_viewport_and_names() {
+        assert_eq!(parse_viewport("390x844").unwrap(), (390, 844));
+        assert!(parse_viewport("390").is_err());
+        assert!(parse_viewport("0x844").is_err());
+        assert!(validate_snapshot_name("mobile_home-1").is_ok());
+        assert!(validate_snapshot_name("../golden").is_err());
+        assert!(validate_snapshot_name("").is_err());
     }
-    true
 }
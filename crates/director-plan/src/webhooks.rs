@@ -0,0 +1,42 @@
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+use crate::config::WebhookConfig;
+use crate::events::LoggedEvent;
+
+/// How long one delivery may take before it's given up on.
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// POSTs each event from `events` as JSON (an [`crate::events::EventRecord`]) to every hook
+/// that wants its type, with the type also in `X-Director-Event`. Deliveries are attempted
+/// once; a hook that is down only misses events, it doesn't hold up the others.
+pub fn spawn(hooks: Vec<WebhookConfig>, mut events: broadcast::Receiver<LoggedEvent>) -> JoinHandle<()> {
+    let client = reqwest::Client::new();
+    tokio::spawn(async move {
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(missed)) => {
+                    warn!("Webhooks fell behind; {} events were not delivered", missed);
+                    continue;
+                }
+                Err(RecvError::Closed) => return,
+            };
+            let kind = event.record.event.kind();
+            for hook in hooks.iter().filter(|h| h.wants(kind)) {
+                let sent = client.post(&hook.url)
+                    .header("X-Director-Event", kind)
+                    .timeout(DELIVERY_TIMEOUT)
+                    .json(&event.record)
+                    .send()
+                    .await;
+                match sent {
+                    Ok(resp) if resp.status().is_success() => debug!("Delivered {} to {}", kind, hook.url),
+                    Ok(resp) => warn!("Webhook {} answered {} to {}", hook.url, resp.status(), kind),
+                    Err(e) => warn!("Webhook {} failed for {}: {}", hook.url, kind, e),
+                }
+            }
+        }
+    })
+}
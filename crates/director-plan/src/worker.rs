@@ -1,92 +1,464 @@
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use std::process::Command;
 use anyhow::{Result, anyhow, Context};
 use crate::types::{Ticket, Status, Priority};
-use crate::execution_loop::ExecutionLoop;
-use reqwest::Client;
+use crate::execution_loop::{ExecutionLoop, ExecutionResult};
+use crate::config::PlanConfig;
+use crate::prompt::Confirm;
+use crate::shutdown::{CancelToken, Interrupted, Shutdown};
+use crate::locks::{self, AlreadyRunning, LockStore};
+use crate::runs::{RunOutcome, RunStore};
+use crate::sla;
+use crate::schedule;
+use crate::agent_result;
+use crate::budget;
+use crate::worker_status::WorkerHeartbeat;
+use crate::store::TicketStore;
+use crate::remote::RemoteTicketStore;
+use crate::client::{self, Client};
+use crate::events::{self, PlanEvent};
+use crate::DirectorPlan;
 use serde_json::json;
+use tokio::task::JoinHandle;
 use colored::*;
 
 pub struct Worker {
     workspace_root: PathBuf,
     pool_size: usize,
-    client: Client,
+    /// Shared by the API client and GitHub requests
+    http: reqwest::Client,
     server_url: String,
+    /// Sub-plan this worker serves (`/api/workspaces/<ws>`); `None` for the root plan.
+    workspace: Option<String>,
+    tickets: TicketAccess,
+    worker_id: String,
+    lease_ttl_secs: i64,
+    session: bool,
+    patch_mode: bool,
+    confirm: Confirm,
+    /// Why the last poll didn't claim anything (paused or over budget), so it's logged once
+    held: Mutex<Option<String>>,
+    /// Reported to the server by the status heartbeat
+    activity: Arc<Mutex<Activity>>,
+}
+
+/// What a worker is doing, for `POST /api/workers/heartbeat`.
+#[derive(Debug, Default)]
+struct Activity {
+    current_ticket: Option<String>,
+    last_error: Option<(chrono::DateTime<chrono::Utc>, String)>,
+}
+
+/// Where the worker reads and writes tickets: the server's filesystem (shared workspace)
+/// or the server's HTTP API (remote mode, the worker has its own clone).
+enum TicketAccess {
+    Local(TicketStore, PathBuf),
+    Remote(RemoteTicketStore),
+}
+
+impl TicketAccess {
+    async fn load(&self, id: &str) -> Result<Ticket> {
+        match self {
+            TicketAccess::Local(store, _) => store.load(id),
+            TicketAccess::Remote(remote) => remote.load(id).await,
+        }
+    }
+
+    async fn list(&self, status_filter: Option<Status>) -> Result<Vec<Ticket>> {
+        match self {
+            TicketAccess::Local(_, root) => DirectorPlan::new(root.clone()).list_tickets(status_filter),
+            TicketAccess::Remote(remote) => remote.list(status_filter).await,
+        }
+    }
+
+    async fn update<F>(&self, id: &str, f: F) -> Result<()>
+    where
+        F: Fn(&mut toml_edit::DocumentMut) -> Result<()>,
+    {
+        match self {
+            TicketAccess::Local(store, _) => store.update(id, f),
+            TicketAccess::Remote(remote) => remote.update(id, f).await,
+        }
+    }
+
+    async fn set_status(&self, id: &str, status: Status) -> Result<()> {
+        match self {
+            TicketAccess::Local(store, _) => store.set_status(id, status),
+            TicketAccess::Remote(remote) => remote.set_status(id, status).await,
+        }
+    }
+
+    async fn append_history(&self, id: &str, message: &str) -> Result<()> {
+        match self {
+            TicketAccess::Local(store, _) => store.append_history(id, message),
+            TicketAccess::Remote(remote) => remote.append_history(id, message).await,
+        }
+    }
 }
 
 impl Worker {
     pub fn new(workspace_root: PathBuf, pool_size: usize) -> Self {
+        let lease_ttl_secs = PlanConfig::load(&workspace_root)
+            .map(|c| c.worker.lease_ttl_secs)
+            .unwrap_or_else(|_| PlanConfig::default().worker.lease_ttl_secs);
         Self {
+            tickets: TicketAccess::Local(TicketStore::new(&workspace_root), workspace_root.clone()),
             workspace_root,
             pool_size,
-            client: Client::new(),
-            server_url: "http://localhost:3000".to_string(), // Configurable?
+            http: reqwest::Client::new(),
+            server_url: "http://localhost:3000".to_string(),
+            workspace: None,
+            worker_id: format!("{}-{}", locks::hostname(), std::process::id()),
+            lease_ttl_secs,
+            session: false,
+            patch_mode: false,
+            confirm: Confirm::Never,
+            held: Mutex::new(None),
+            activity: Arc::new(Mutex::new(Activity::default())),
+        }
+    }
+
+    /// Whether to ask before replacing a ticket branch left over from an earlier run.
+    pub fn with_confirm(mut self, confirm: Confirm) -> Self {
+        self.confirm = confirm;
+        self
+    }
+
+    /// Apply agents' unified diffs for them (see `ExecutionLoop::with_patch_mode`).
+    pub fn with_patch_mode(mut self, patch_mode: bool) -> Self {
+        self.patch_mode = patch_mode;
+        self
+    }
+
+    /// Run agents as multi-turn sessions (see `ExecutionLoop::with_session`).
+    pub fn with_session(mut self, session: bool) -> Self {
+        self.session = session;
+        self
+    }
+
+    pub fn with_server_url(mut self, url: &str) -> Self {
+        self.server_url = url.trim_end_matches('/').to_string();
+        if let TicketAccess::Remote(_) = self.tickets {
+            self.tickets = TicketAccess::Remote(RemoteTicketStore::new(self.api()));
+        }
+        self
+    }
+
+    /// Serves a sub-plan from the root config's `[[workspaces]]` through `/api/workspaces/<name>`.
+    /// `workspace_root` passed to `new` should be the sub-plan's directory.
+    pub fn with_workspace(mut self, name: &str) -> Self {
+        self.workspace = Some(name.to_string());
+        if let TicketAccess::Remote(_) = self.tickets {
+            self.tickets = TicketAccess::Remote(RemoteTicketStore::new(self.api()));
+        }
+        self
+    }
+
+    /// Reads and writes tickets only through the server API instead of `plan/` on disk,
+    /// so the worker can run on another machine with its own git clone.
+    pub fn remote(mut self) -> Self {
+        self.tickets = TicketAccess::Remote(RemoteTicketStore::new(self.api()));
+        self
+    }
+
+    /// The server's API, scoped to this worker's plan.
+    fn api(&self) -> Client {
+        let api = Client::with_http(self.http.clone(), &self.server_url);
+        match &self.workspace {
+            Some(name) => api.for_workspace(name),
+            None => api,
         }
     }
 
     pub async fn run(&self) -> Result<()> {
-        println!("{}", format!(">> Radkit Worker Started (Pool: {})", self.pool_size).green());
-        println!(">> Polling {} for tickets...", self.server_url);
-
-        loop {
-            match self.poll_ticket().await {
-                Ok(Some(ticket)) => {
-                    println!("{}", format!(">> Found Ticket: {} - {}", ticket.meta.id, ticket.meta.title).cyan());
-                    if let Err(e) = self.process_ticket(ticket).await {
-                        eprintln!("{}", format!(">> Error processing ticket: {}", e).red());
+        Self::run_all(std::slice::from_ref(self)).await
+    }
+
+    /// Polls several plans (e.g. the root and every sub-plan) in turn until shutdown.
+    /// Tickets are processed one at a time since the plans share one git checkout.
+    pub async fn run_all(workers: &[Worker]) -> Result<()> {
+        for worker in workers {
+            println!("{}", format!(">> Radkit Worker {} Started (Pool: {})", worker.worker_id, worker.pool_size).green());
+            println!(">> Polling {} for tickets...", worker.api().api_url());
+
+            if let Err(e) = worker.recover_orphans().await {
+                eprintln!("{}", format!(">> Crash recovery error: {}", e).red());
+            }
+        }
+
+        let shutdown = Shutdown::listen();
+        let status = Self::spawn_status_heartbeat(workers);
+
+        while !shutdown.is_requested() {
+            let mut busy = false;
+            let mut failed = false;
+
+            for worker in workers {
+                if shutdown.is_requested() {
+                    break;
+                }
+                match worker.tick(&shutdown).await {
+                    Ok(true) => busy = true,
+                    Ok(false) => {},
+                    Err(e) => {
+                        eprintln!("{}", format!(">> Polling error: {}", e).red());
+                        worker.note_error(&e.to_string());
+                        failed = true;
                     }
-                },
-                Ok(None) => {
-                    // No tickets, sleep
-                    tokio::time::sleep(Duration::from_secs(5)).await;
-                },
-                Err(e) => {
-                    eprintln!("{}", format!(">> Polling error: {}", e).red());
-                    tokio::time::sleep(Duration::from_secs(10)).await;
                 }
             }
+
+            // Nothing to do in any plan, sleep
+            if !busy {
+                let secs = if failed { 10 } else { 5 };
+                idle(Duration::from_secs(secs), &shutdown).await;
+            }
+        }
+
+        status.abort();
+        println!("{}", ">> Radkit Worker stopped.".green());
+        Ok(())
+    }
+
+    /// Reports the worker (one process, however many plans it polls) to
+    /// `POST /api/workers/heartbeat` every `[worker] heartbeat_secs` until aborted.
+    fn spawn_status_heartbeat(workers: &[Worker]) -> JoinHandle<()> {
+        let Some(first) = workers.first() else { return tokio::spawn(async {}) };
+        let api = first.api();
+        let id = first.worker_id.clone();
+        let every = PlanConfig::load(&first.workspace_root)
+            .map(|c| c.worker.heartbeat_secs)
+            .unwrap_or_else(|_| PlanConfig::default().worker.heartbeat_secs)
+            .max(1);
+        let activities: Vec<_> = workers.iter().map(|w| (w.workspace.clone(), w.activity.clone())).collect();
+        let started = std::time::Instant::now();
+
+        tokio::spawn(async move {
+            loop {
+                let heartbeat = status_heartbeat(&id, started.elapsed().as_secs(), &activities);
+                // Polling already reports an unreachable server
+                let _ = api.worker_heartbeat(&heartbeat).await;
+                tokio::time::sleep(Duration::from_secs(every)).await;
+            }
+        })
+    }
+
+    fn note_error(&self, error: &str) {
+        self.activity.lock().unwrap().last_error = Some((chrono::Utc::now(), error.to_string()));
+    }
+
+    /// One poll of this worker's plan. Returns whether a ticket was claimed.
+    async fn tick(&self, shutdown: &Shutdown) -> Result<bool> {
+        if let Err(e) = self.escalate_due_tickets().await {
+            eprintln!("{}", format!(">> SLA escalation error: {}", e).red());
+        }
+        // A remote worker leaves scheduling to the server, which owns the plan files
+        if let TicketAccess::Local(_, root) = &self.tickets
+            && let Err(e) = schedule::run_due(root, chrono::Utc::now())
+        {
+            eprintln!("{}", format!(">> Scheduler error: {}", e).red());
+        }
+
+        let config = PlanConfig::load(&self.workspace_root).unwrap_or_default();
+        let runs = RunStore::new(&self.workspace_root).list_all().unwrap_or_default();
+        let hold = budget::hold_reason(&self.workspace_root, &config.budget, &runs, chrono::Utc::now());
+        {
+            let mut held = self.held.lock().unwrap();
+            if *held != hold {
+                match &hold {
+                    Some(reason) => println!("{}", format!(">> Not claiming tickets: {}", reason).yellow()),
+                    None => println!(">> Claiming tickets again."),
+                }
+                *held = hold.clone();
+            }
+        }
+        if hold.is_some() {
+            return Ok(false);
+        }
+
+        let ticket = match self.poll_ticket().await? {
+            Some(ticket) => ticket,
+            None => return Ok(false),
+        };
+
+        println!("{}", format!(">> Claimed Ticket: {} - {}", ticket.meta.id, ticket.meta.title).cyan());
+        let id = ticket.meta.id.clone();
+        self.activity.lock().unwrap().current_ticket = Some(id.clone());
+        if let Err(e) = self.process_ticket(ticket, shutdown).await {
+            eprintln!("{}", format!(">> Error processing ticket: {}", e).red());
+            self.note_error(&format!("{}: {}", id, e));
+        }
+        self.activity.lock().unwrap().current_ticket = None;
+        if let Err(e) = self.api().release(&id, &self.worker_id).await {
+            eprintln!("{}", format!(">> Failed to release lease on {}: {}", id, e).red());
         }
+        Ok(true)
     }
 
-    async fn poll_ticket(&self) -> Result<Option<Ticket>> {
-        // Fetch all tickets and filter locally for now (API might not support complex filter)
-        let resp = self.client.get(format!("{}/api/tickets", self.server_url))
-            .send()
-            .await?;
+    /// Reclaims tickets stuck `in_progress` after a worker died mid-run.
+    ///
+    /// A ticket is orphaned when its run lock belongs to a process on this host that no longer
+    /// exists, or to one elsewhere that stopped renewing its heartbeat. Orphans go back to
+    /// `todo`, and their branch is discarded. Leases of runs on other machines are left to the
+    /// server, which expires them.
+    async fn recover_orphans(&self) -> Result<()> {
+        let locks = LockStore::new(&self.workspace_root);
+        let mut recovered = 0;
+
+        for lock in locks.list()? {
+            if !lock.is_stale() {
+                continue;
+            }
+            let id = &lock.ticket_id;
+            let reason = lock.stale_reason();
+
+            let branch = format!("radkit/{}", id.to_lowercase());
+            self.discard_branch(&branch)?;
+            locks.release(id)?;
+            self.finish_orphaned_runs(id)?;
+
+            // The ticket may have moved on already (e.g. its lease expired and it was re-claimed)
+            let ticket = match self.tickets.load(id).await {
+                Ok(ticket) => ticket,
+                Err(_) => continue,
+            };
+            if ticket.meta.status != Status::InProgress {
+                continue;
+            }
 
-        if !resp.status().is_success() {
-             return Err(anyhow!("Server returned {}", resp.status()));
+            println!("{}", format!(">> Recovering orphaned ticket {} ({})", id, reason).yellow());
+            self.tickets.set_status(id, Status::Todo).await?;
+            self.tickets.append_history(id, &format!("Radkit: Recovered orphaned run ({}); returned to todo.", reason)).await?;
+            recovered += 1;
+        }
+
+        // Worktrees whose directories vanished with the crashed run
+        Command::new("git").args(["worktree", "prune"]).current_dir(&self.workspace_root).output()?;
+
+        if recovered > 0 {
+            println!("{}", format!(">> Recovered {} orphaned ticket(s)", recovered).green());
+        }
+        Ok(())
+    }
+
+    /// Reverts any half-applied agent changes and deletes a leftover run branch.
+    /// plan/ is kept so ticket state survives.
+    fn discard_branch(&self, branch: &str) -> Result<()> {
+        let current = Command::new("git")
+            .args(["rev-parse", "--abbrev-ref", "HEAD"])
+            .current_dir(&self.workspace_root)
+            .output()?;
+        if String::from_utf8_lossy(&current.stdout).trim() == branch {
+            Command::new("git").args(["reset", "-q"]).current_dir(&self.workspace_root).status()?;
+            Command::new("git").args(["checkout", "HEAD", "--", ".", ":(exclude)plan"]).current_dir(&self.workspace_root).status()?;
+            Command::new("git").args(["clean", "-fdq", "--", ".", ":(exclude)plan"]).current_dir(&self.workspace_root).status()?;
+            self.reset_to_base()?;
+        }
+
+        // Fails harmlessly when the branch was never created
+        Command::new("git").args(["branch", "-D", branch]).current_dir(&self.workspace_root).output()?;
+        Ok(())
+    }
+
+    /// Marks run records the dead worker left as `running` as interrupted.
+    fn finish_orphaned_runs(&self, ticket_id: &str) -> Result<()> {
+        let runs = RunStore::new(&self.workspace_root);
+        for mut record in runs.list_for_ticket(ticket_id)? {
+            if record.outcome == RunOutcome::Running {
+                record.outcome = RunOutcome::Interrupted;
+                record.finished_at = Some(chrono::Utc::now());
+                runs.save(&record)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Raises the priority of open tickets nearing their due date, when `sla.escalate_within_days` is set.
+    /// Tickets inside the window are bumped to at least `high`, overdue ones to `critical`,
+    /// so running this on every poll is idempotent.
+    async fn escalate_due_tickets(&self) -> Result<()> {
+        let config = PlanConfig::load(&self.workspace_root)?;
+        let window = match config.sla.escalate_within_days {
+            Some(days) => days,
+            None => return Ok(()),
+        };
+
+        let today = sla::today();
+
+        for ticket in self.tickets.list(None).await? {
+            if !matches!(ticket.meta.status, Status::Todo | Status::InProgress | Status::Review) {
+                continue;
+            }
+            let days = match sla::days_until_due(&ticket, today) {
+                Some(days) if days <= window => days,
+                _ => continue,
+            };
+
+            let target = if days < 0 { Priority::Critical } else { Priority::High };
+            if ticket.meta.priority >= target {
+                continue;
+            }
+
+            let level = format!("{:?}", target).to_lowercase();
+            self.tickets.update(&ticket.meta.id, |doc| {
+                doc["meta"]["priority"] = toml_edit::value(level.clone());
+                crate::append_history_entry(doc, &format!("Radkit: Escalated priority to {} (due in {} days).", level, days));
+                Ok(())
+            }).await?;
+
+            println!("{}", format!(">> Escalated {} to {} (due in {} days)", ticket.meta.id, level, days).yellow());
         }
 
-        let tickets: Vec<crate::types::FrontendTicket> = resp.json().await?;
+        Ok(())
+    }
+
+    async fn poll_ticket(&self) -> Result<Option<Ticket>> {
+        // Fetch all tickets and filter locally for now (API might not support complex filter)
+        let api = self.api();
+        let tickets = api.list_tickets().await?;
+        let locks = LockStore::new(&self.workspace_root);
 
         // Find first TODO ticket assigned to 'radkit' (or unassigned?)
         // Prompt says: "marked status = 'todo' && assignee = 'radkit'"
         for ft in tickets {
-            if ft.status == "todo" && ft.owner == "radkit" {
-                // We need the full ticket TOML. The frontend ticket structure is flattened.
-                // We assume we can read the file from disk using the ID since we are "Native".
-                // Or we need an API to get the raw ticket.
-                // Since `director-plan` server serves from the same FS, we can read FS.
-                // ID is like "T-001". File is "plan/tickets/T-001.toml".
-
-                let path = self.workspace_root.join("plan/tickets").join(format!("{}.toml", ft.id));
-                if path.exists() {
-                     let content = std::fs::read_to_string(&path)?;
-                     let ticket: Ticket = toml_edit::de::from_str(&content)?;
-                     return Ok(Some(ticket));
+            if ft.status == "todo" && ft.owner == "radkit" && ft.blocked_by.is_empty() {
+                // Already being run here by `execute`; the lock would turn the run away anyway
+                if locks.read(&ft.id).ok().flatten().is_some_and(|lock| !lock.is_stale()) {
+                    continue;
+                }
+                // Another worker may get there first; the claim is what decides.
+                if let Err(e) = api.claim(&ft.id, &self.worker_id, self.lease_ttl_secs).await {
+                    println!(">> Could not claim {}: {}", ft.id, e);
+                    continue;
                 }
+
+                // The frontend ticket structure is flattened; fetch the full ticket
+                // (from disk, or from the raw API in remote mode).
+                return self.tickets.load(&ft.id).await.map(Some);
             }
         }
 
         Ok(None)
     }
 
-    async fn process_ticket(&self, mut ticket: Ticket) -> Result<()> {
+    async fn process_ticket(&self, mut ticket: Ticket, shutdown: &Shutdown) -> Result<()> {
         // 1. Claim Ticket (Set to InProgress)
+        // The lock is held until this function returns; if the worker dies instead,
+        // the next one finds the stale lock and reclaims the ticket.
+        // The server already moved the ticket to in_progress when granting the claim.
+        let locks = LockStore::new(&self.workspace_root);
+        let lock = match locks.acquire(&ticket.meta.id) {
+            Ok(lock) => lock,
+            Err(e) if e.is::<AlreadyRunning>() => {
+                // `execute` got to it between the poll and the claim; that run owns it now
+                self.tickets.set_status(&ticket.meta.id, Status::Todo).await?;
+                return Err(e);
+            }
+            Err(e) => return Err(e),
+        };
         ticket.meta.status = Status::InProgress;
-        self.save_ticket(&ticket)?;
 
         // 2. Create Branch
         let branch_name = format!("radkit/{}", ticket.meta.id.to_lowercase());
@@ -97,7 +469,8 @@ impl Worker {
         // What is the agent command?
         // We should probably read it from settings or config.
         // For now, let's assume a default or env var `RADKIT_AGENT_CMD`.
-        let agent_cmd = std::env::var("RADKIT_AGENT_CMD").unwrap_or_else(|_| "cursor --prompt".to_string());
+        // Without one, `execution.agents` or `[[execution.routes]]` pick the agent
+        let agent_cmd = std::env::var("RADKIT_AGENT_CMD").unwrap_or_default();
 
         // We need a way to pass the customized ExecutionLoop that captures output.
         // Since `ExecutionLoop` is in another module, we might need to modify it to return the result with confidence.
@@ -112,37 +485,133 @@ impl Worker {
         // I will update ExecutionLoop in the NEXT step.
         // So here I will write the code ASSUMING the new API exists, or I will use a placeholder.
 
-        let mut loop_runner = ExecutionLoop::new(&self.workspace_root, agent_cmd, ticket.clone());
+        // The loop blocks on agent/verification subprocesses, so it runs off the async
+        // runtime where the shutdown signal can still be observed.
+        let cancel = CancelToken::new();
+        lock.cancel_on_request(cancel.clone());
+        let handle = {
+            let root = self.workspace_root.clone();
+            let ticket = ticket.clone();
+            let cancel = cancel.clone();
+            let session = self.session;
+            let patch_mode = self.patch_mode;
+            tokio::task::spawn_blocking(move || {
+                let mut runner = ExecutionLoop::new(&root, agent_cmd, ticket)
+                    .with_cancel(cancel)
+                    .with_default_agent("cursor --prompt");
+                if session {
+                    runner = runner.with_session();
+                }
+                if patch_mode {
+                    runner = runner.with_patch_mode();
+                }
+                runner.run_with_handshake()
+            })
+        };
+
+        let heartbeat = self.spawn_heartbeat(&ticket.meta.id, cancel.clone());
+        let outcome = self.supervise(handle, &cancel, shutdown).await;
+        heartbeat.abort();
+        let lease_lost = matches!(heartbeat.await, Ok(true));
+        let cancelled = locks.take_cancel_request(&ticket.meta.id);
 
-        // Assuming run_with_handshake is the new method
-        let result = match loop_runner.run_with_handshake() {
+        let result = match outcome {
              Ok(r) => r,
+             Err(e) if e.is::<Interrupted>() && cancelled.is_some() => {
+                 // `cancel` has already blocked the ticket and noted it in the history
+                 self.reset_to_base()?;
+                 return Err(anyhow!("Run on {} was cancelled", ticket.meta.id));
+             }
+             Err(e) if e.is::<Interrupted>() && lease_lost => {
+                 // Someone else may own the ticket now, so leave its status alone.
+                 self.reset_to_base()?;
+                 return Err(anyhow!("Lease on {} was lost or revoked; run abandoned", ticket.meta.id));
+             }
+             Err(e) if e.is::<Interrupted>() => {
+                 // Killed mid-run: the loop already reverted the tree, so hand the ticket back.
+                 ticket.meta.status = Status::Todo;
+                 self.tickets.set_status(&ticket.meta.id, Status::Todo).await?;
+                 self.tickets.append_history(&ticket.meta.id, "Radkit: Execution interrupted by worker shutdown; returned to todo.").await?;
+                 self.reset_to_base()?;
+                 return Err(e);
+             }
              Err(e) => {
                  // Execution failed (crashed or max retries)
                  ticket.meta.status = Status::Review; // Review because it failed
-                 self.save_ticket(&ticket)?;
+                 self.tickets.set_status(&ticket.meta.id, Status::Review).await?;
+                 self.reset_to_base()?;
                  return Err(e);
              }
         };
 
+        // Follow-ups are written straight to plan/; the API has no way to create tickets yet
+        if let Some(agent_result) = result.result.as_ref().filter(|r| result.success && !r.follow_up_tickets.is_empty()) {
+            match &self.tickets {
+                TicketAccess::Local(_, root) => {
+                    let config = PlanConfig::load(root)?;
+                    let ids = agent_result::create_follow_ups(root, &ticket, agent_result, &config.follow_ups)?;
+                    if !ids.is_empty() {
+                        println!(">> Created follow-up tickets: {}", ids.join(", "));
+                    }
+                }
+                TicketAccess::Remote(_) => {
+                    println!(">> The agent suggested {} follow-up ticket(s); not created through the API.", agent_result.follow_up_tickets.len());
+                }
+            }
+        }
+
         // 4. Check Confidence
-        let min_confidence = ticket.verification.min_confidence;
+        let defaults = PlanConfig::load(&self.workspace_root).unwrap_or_default().verification;
+        let min_confidence = ticket.verification.min_confidence(&defaults);
         if result.confidence < min_confidence {
              println!(">> Confidence too low ({:.2} < {:.2}). Requesting feedback.", result.confidence, min_confidence);
              ticket.meta.status = Status::Review;
-             // Append to log?
-             ticket.history.log.push(format!("Radkit: Low confidence ({:.2}). Requesting human review.", result.confidence));
-             self.save_ticket(&ticket)?;
+             self.tickets.set_status(&ticket.meta.id, Status::Review).await?;
+             self.tickets.append_history(&ticket.meta.id, &format!("Radkit: Low confidence ({:.2}). Requesting human review.", result.confidence)).await?;
+             // Kept on the branch, unpushed, for the reviewer
+             if let Some(sha) = &result.commit {
+                 self.update_branch(&branch_name, sha)?;
+             }
+             self.reset_to_base()?;
              return Ok(());
         }
 
-        // 5. Submit PR
+        // 5. Point the branch at the run's commit. Nothing is pushed without one.
+        let commit = match &result.commit {
+            Some(sha) => sha.clone(),
+            None => {
+                println!(">> Run produced no commit. Nothing to push.");
+                ticket.meta.status = Status::Review;
+                self.tickets.set_status(&ticket.meta.id, Status::Review).await?;
+                self.tickets.append_history(&ticket.meta.id, "Radkit: Verification passed but the run produced no commit; nothing was pushed.").await?;
+                self.reset_to_base()?;
+                return Ok(());
+            }
+        };
+        self.update_branch(&branch_name, &commit)?;
+
+        // 6. Push (rebasing onto a moved base if needed) and open the PR
+        let pushed = match self.push_with_rebase(&branch_name, &ticket, &result.run_id).await? {
+            PushOutcome::Pushed(sha) => sha,
+            PushOutcome::NeedsReview(reason) => {
+                println!("{}", format!(">> {}. Leaving {} for review.", reason, branch_name).yellow());
+                ticket.meta.status = Status::Review;
+                self.tickets.set_status(&ticket.meta.id, Status::Review).await?;
+                self.tickets.append_history(&ticket.meta.id, &format!("Radkit: {}. Branch {} was not pushed; needs review.", reason, branch_name)).await?;
+                self.reset_to_base()?;
+                return Ok(());
+            }
+        };
+        if pushed != commit {
+            println!(">> Rebased {} to {}", commit, pushed);
+        }
         self.submit_pr(&branch_name, &ticket).await?;
+        self.tickets.append_history(&ticket.meta.id, &format!("Radkit: Pushed {} to {}.", pushed, branch_name)).await?;
 
-        // 6. Mark Done (or Review?)
+        // 7. Mark Done (or Review?)
         // Usually PR implies "Review".
         ticket.meta.status = Status::Review;
-        self.save_ticket(&ticket)?;
+        self.tickets.set_status(&ticket.meta.id, Status::Review).await?;
 
         // Checkout back to main/master?
         // Worker should reset for next ticket.
@@ -151,39 +620,194 @@ impl Worker {
         Ok(())
     }
 
-    fn save_ticket(&self, ticket: &Ticket) -> Result<()> {
-        let path = self.workspace_root.join("plan/tickets").join(format!("{}.toml", ticket.meta.id));
-        let content = toml_edit::ser::to_string_pretty(ticket)?;
-        std::fs::write(path, content)?;
-        Ok(())
+    /// Waits for an execution to finish. Once shutdown is requested the run gets
+    /// `worker.shutdown_grace_secs` to complete on its own before it is cancelled.
+    async fn supervise(
+        &self,
+        mut handle: JoinHandle<Result<ExecutionResult>>,
+        cancel: &CancelToken,
+        shutdown: &Shutdown,
+    ) -> Result<ExecutionResult> {
+        tokio::select! {
+            res = &mut handle => return res?,
+            _ = shutdown.wait() => {}
+        }
+
+        let grace = PlanConfig::load(&self.workspace_root)
+            .map(|c| c.worker.shutdown_grace_secs)
+            .unwrap_or_else(|_| PlanConfig::default().worker.shutdown_grace_secs);
+        println!("{}", format!(">> Shutdown requested. Waiting up to {}s for the current run to finish...", grace).yellow());
+
+        match tokio::time::timeout(Duration::from_secs(grace), &mut handle).await {
+            Ok(res) => res?,
+            Err(_) => {
+                println!("{}", ">> Grace period elapsed. Cancelling run.".yellow());
+                cancel.cancel();
+                handle.await?
+            }
+        }
+    }
+
+    /// Renews the lease on `id` until aborted. If the server reports the lease as lost,
+    /// the run is cancelled and the task finishes with `true`.
+    fn spawn_heartbeat(&self, id: &str, cancel: CancelToken) -> JoinHandle<bool> {
+        let api = self.api();
+        let id = id.to_string();
+        let worker_id = self.worker_id.clone();
+        let ttl_secs = self.lease_ttl_secs;
+        let every = Duration::from_secs((self.lease_ttl_secs / 3).max(1) as u64);
+
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(every).await;
+                match api.heartbeat(&id, &worker_id, ttl_secs).await {
+                    Err(e) if client::is_status(&e, reqwest::StatusCode::CONFLICT) => {
+                        eprintln!("{}", ">> Lease lost. Cancelling run.".red());
+                        cancel.cancel();
+                        return true;
+                    }
+                    Ok(()) => {}
+                    // Transient; the lease outlives a couple of missed beats
+                    Err(e) => eprintln!("{}", format!(">> Heartbeat failed: {}", e).red()),
+                }
+            }
+        })
+    }
+
+    /// Re-read per ticket so a config change doesn't need a worker restart.
+    fn base_branch(&self) -> String {
+        let config = PlanConfig::load(&self.workspace_root).unwrap_or_default();
+        crate::commit::base_branch(&self.workspace_root, &config)
     }
 
     fn create_branch(&self, branch: &str) -> Result<()> {
         // Ensure clean state
-        Command::new("git").args(&["checkout", "main"]).current_dir(&self.workspace_root).output()?;
-        Command::new("git").args(&["pull"]).current_dir(&self.workspace_root).output()?;
+        let base = self.base_branch();
+        Command::new("git").args(["checkout", &base]).current_dir(&self.workspace_root).output()?;
+        Command::new("git").args(["pull", "origin", &base]).current_dir(&self.workspace_root).output()?;
+
+        // A branch left by an earlier run would make `checkout -b` fail
+        if self.git_succeeds(&["show-ref", "--verify", "--quiet", &format!("refs/heads/{}", branch)]) {
+            if !self.confirm.ask(&format!("Branch {} already exists from an earlier run. Delete it and start fresh?", branch)) {
+                return Err(anyhow!("Branch {} already exists; delete it or rerun with --yes", branch));
+            }
+            self.git(&["branch", "-D", branch])?;
+        }
 
         // Create branch
-        Command::new("git").args(&["checkout", "-b", branch]).current_dir(&self.workspace_root).status()?;
+        let status = Command::new("git").args(&["checkout", "-b", branch]).current_dir(&self.workspace_root).status()?;
+        if !status.success() {
+            return Err(anyhow!("Failed to create branch {}", branch));
+        }
+        Ok(())
+    }
+
+    /// Moves `branch` to `commit`. The loop leaves HEAD detached, so the branch isn't checked out.
+    fn update_branch(&self, branch: &str, commit: &str) -> Result<()> {
+        let status = Command::new("git")
+            .args(["branch", "-f", branch, commit])
+            .current_dir(&self.workspace_root)
+            .status()?;
+        if !status.success() {
+            return Err(anyhow!("Failed to point {} at {}", branch, commit));
+        }
         Ok(())
     }
 
     fn reset_to_base(&self) -> Result<()> {
-        Command::new("git").args(&["checkout", "main"]).current_dir(&self.workspace_root).status()?;
+        Command::new("git").args(["checkout", &self.base_branch()]).current_dir(&self.workspace_root).status()?;
         Ok(())
     }
 
-    async fn submit_pr(&self, branch: &str, ticket: &Ticket) -> Result<()> {
-        println!(">> Pushing branch {}...", branch);
-        let status = Command::new("git")
-            .args(&["push", "-u", "origin", branch])
-            .current_dir(&self.workspace_root)
-            .status()?;
+    /// Pushes `branch`. If the base branch has moved on, the branch is rebased onto it and
+    /// re-verified first; rejected pushes are retried that way up to `[worker] push_retries` times.
+    /// Rebase conflicts and failed re-verification come back as `NeedsReview` rather than errors.
+    async fn push_with_rebase(&self, branch: &str, ticket: &Ticket, run_id: &str) -> Result<PushOutcome> {
+        let retries = PlanConfig::load(&self.workspace_root)
+            .map(|c| c.worker.push_retries)
+            .unwrap_or_else(|_| PlanConfig::default().worker.push_retries);
+        let base = self.base_branch();
+        let upstream = format!("origin/{}", base);
+
+        for attempt in 0..=retries {
+            self.git(&["fetch", "-q", "origin", &base])?;
+            if !self.git_succeeds(&["merge-base", "--is-ancestor", &upstream, branch]) {
+                println!(">> {} has moved. Rebasing {}...", upstream, branch);
+                if let Some(conflicts) = self.rebase(branch, &upstream)? {
+                    return Ok(PushOutcome::NeedsReview(format!("Rebase onto {} conflicted in {}", upstream, conflicts.join(", "))));
+                }
 
-        if !status.success() {
-             return Err(anyhow!("Failed to push branch"));
+                println!(">> Re-running verification on the rebased branch...");
+                let verified = {
+                    let root = self.workspace_root.clone();
+                    let ticket = ticket.clone();
+                    let run_id = run_id.to_string();
+                    tokio::task::spawn_blocking(move || {
+                        ExecutionLoop::new(&root, String::new(), ticket).reverify(&run_id)
+                    }).await?
+                };
+                if let Err(e) = verified {
+                    return Ok(PushOutcome::NeedsReview(format!("Verification failed after rebasing onto {}: {}", upstream, e)));
+                }
+            }
+
+            println!(">> Pushing branch {}...", branch);
+            // The branch is ours; a previous attempt may have pushed it before a rebase rewrote it
+            let output = Command::new("git")
+                .args(["push", "--force-with-lease", "-u", "origin", branch])
+                .current_dir(&self.workspace_root)
+                .output()?;
+            if output.status.success() {
+                return Ok(PushOutcome::Pushed(self.git(&["rev-parse", branch])?));
+            }
+
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if !is_push_rejection(&stderr) {
+                return Err(anyhow!("Failed to push branch: {}", stderr.trim()));
+            }
+            println!("{}", format!(">> Push rejected (attempt {}/{}).", attempt + 1, retries + 1).yellow());
+        }
+
+        Ok(PushOutcome::NeedsReview(format!("Push was still rejected after {} rebase attempts", retries)))
+    }
+
+    /// Rebases `branch` onto `upstream`. On conflict the rebase is aborted and the conflicting
+    /// files are returned.
+    fn rebase(&self, branch: &str, upstream: &str) -> Result<Option<Vec<String>>> {
+        if self.git_succeeds(&["rebase", "-q", upstream, branch]) {
+            return Ok(None);
+        }
+        let conflicts: Vec<String> = self.git(&["diff", "--name-only", "--diff-filter=U"])?
+            .lines()
+            .map(str::to_string)
+            .collect();
+        self.git(&["rebase", "--abort"])?;
+        Ok(Some(conflicts))
+    }
+
+    /// Runs git in the workspace and returns trimmed stdout.
+    fn git(&self, args: &[&str]) -> Result<String> {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(&self.workspace_root)
+            .output()
+            .with_context(|| format!("Failed to run git {}", args.join(" ")))?;
+        if !output.status.success() {
+            return Err(anyhow!("git {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim()));
         }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    fn git_succeeds(&self, args: &[&str]) -> bool {
+        Command::new("git")
+            .args(args)
+            .current_dir(&self.workspace_root)
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false)
+    }
 
+    async fn submit_pr(&self, branch: &str, ticket: &Ticket) -> Result<()> {
         // Create PR via GitHub API
         println!(">> Creating PR...");
         let token = std::env::var("GITHUB_TOKEN").context("GITHUB_TOKEN not set")?;
@@ -203,10 +827,10 @@ impl Worker {
             "title": ticket.meta.title,
             "body": format!("{}\n\nCloses {}", ticket.spec.description, ticket.meta.id),
             "head": branch,
-            "base": "main"
+            "base": self.base_branch()
         });
 
-        let resp = self.client.post(&url)
+        let resp = self.http.post(&url)
             .header("Authorization", format!("token {}", token))
             .header("User-Agent", "director-plan-radkit")
             .header("Accept", "application/vnd.github.v3+json")
@@ -218,12 +842,60 @@ impl Worker {
              let err_text = resp.text().await?;
              return Err(anyhow!("Failed to create PR: {}", err_text));
         }
+        let pr: serde_json::Value = resp.json().await.unwrap_or_default();
+        events::emit(&self.workspace_root, PlanEvent::PrOpened {
+            id: ticket.meta.id.clone(),
+            branch: branch.to_string(),
+            url: pr["html_url"].as_str().map(String::from),
+        });
 
         println!(">> PR Created Successfully!");
         Ok(())
     }
 }
 
+enum PushOutcome {
+    /// The branch is on the remote at this SHA
+    Pushed(String),
+    /// Needs a human: rebase conflicts, failed re-verification, or rejections that kept coming
+    NeedsReview(String),
+}
+
+/// Whether git refused the push because the remote has moved on (as opposed to auth/network errors).
+fn is_push_rejection(stderr: &str) -> bool {
+    stderr.contains("[rejected]") || stderr.contains("non-fast-forward") || stderr.contains("fetch first")
+        || stderr.contains("stale info")
+}
+
+/// The heartbeat for a worker process polling the plans in `activities`: the ticket any of them
+/// is running and the most recent error.
+fn status_heartbeat(id: &str, uptime_secs: u64, activities: &[(Option<String>, Arc<Mutex<Activity>>)]) -> WorkerHeartbeat {
+    let mut heartbeat = WorkerHeartbeat { id: id.to_string(), uptime_secs, ..Default::default() };
+    let mut latest_error: Option<&(chrono::DateTime<chrono::Utc>, String)> = None;
+    let activities: Vec<_> = activities.iter().map(|(workspace, a)| (workspace, a.lock().unwrap())).collect();
+    for (workspace, activity) in &activities {
+        if let Some(ticket) = &activity.current_ticket {
+            heartbeat.current_ticket = Some(ticket.clone());
+            heartbeat.workspace = (*workspace).clone();
+        }
+        if let Some(error) = &activity.last_error
+            && latest_error.is_none_or(|latest| error.0 > latest.0)
+        {
+            latest_error = Some(error);
+        }
+    }
+    heartbeat.last_error = latest_error.map(|(_, e)| e.clone());
+    heartbeat
+}
+
+/// Sleeps for `duration`, returning early if shutdown is requested.
+async fn idle(duration: Duration, shutdown: &Shutdown) {
+    tokio::select! {
+        _ = tokio::time::sleep(duration) => {},
+        _ = shutdown.wait() => {},
+    }
+}
+
 fn parse_github_url(bytes: &[u8]) -> Result<(String, String)> {
     let s = String::from_utf8_lossy(bytes).trim().to_string();
     // Handle ssh: git@github.com:owner/repo.git
@@ -0,0 +1,75 @@
+use std::collections::BTreeMap;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// What a worker reports to `POST /api/workers/heartbeat` every `[worker] heartbeat_secs`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default, ToSchema)]
+pub struct WorkerHeartbeat {
+    pub id: String,
+    /// The ticket being run, if any
+    #[serde(default)]
+    pub current_ticket: Option<String>,
+    /// Sub-plan the current ticket belongs to
+    #[serde(default)]
+    pub workspace: Option<String>,
+    #[serde(default)]
+    pub uptime_secs: u64,
+    #[serde(default)]
+    pub last_error: Option<String>,
+}
+
+/// A worker as `GET /api/workers` lists it.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub struct WorkerStatus {
+    #[serde(flatten)]
+    pub heartbeat: WorkerHeartbeat,
+    pub last_seen: DateTime<Utc>,
+    /// Heard from within the last three heartbeat intervals
+    pub alive: bool,
+}
+
+/// The server's view of its workers, kept in memory. Workers re-register with their next
+/// heartbeat after a restart.
+#[derive(Debug, Default)]
+pub struct WorkerRegistry {
+    workers: BTreeMap<String, (WorkerHeartbeat, DateTime<Utc>)>,
+}
+
+impl WorkerRegistry {
+    pub fn record(&mut self, heartbeat: WorkerHeartbeat, now: DateTime<Utc>) {
+        self.workers.insert(heartbeat.id.clone(), (heartbeat, now));
+    }
+
+    /// Every worker heard from in the last hour, by ID. Older ones are forgotten.
+    pub fn list(&mut self, heartbeat_secs: u64, now: DateTime<Utc>) -> Vec<WorkerStatus> {
+        self.workers.retain(|_, (_, seen)| now - *seen < Duration::hours(1));
+        let stale_after = Duration::seconds(heartbeat_secs as i64 * 3);
+        self.workers.values()
+            .map(|(heartbeat, seen)| WorkerStatus {
+                heartbeat: heartbeat.clone(),
+                last_seen: *seen,
+                alive: now - *seen <= stale_after,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry() {
+        let now = Utc::now();
+        let mut registry = WorkerRegistry::default();
+        registry.record(WorkerHeartbeat { id: "old".into(), ..Default::default() }, now - Duration::hours(2));
+        registry.record(WorkerHeartbeat { id: "quiet".into(), ..Default::default() }, now - Duration::seconds(60));
+        registry.record(WorkerHeartbeat { id: "busy".into(), current_ticket: Some("T-001".into()), ..Default::default() }, now);
+
+        let workers = registry.list(15, now);
+        let summary: Vec<(&str, bool)> = workers.iter().map(|w| (w.heartbeat.id.as_str(), w.alive)).collect();
+        assert_eq!(summary, [("busy", true), ("quiet", false)]);
+        assert_eq!(workers[0].heartbeat.current_ticket.as_deref(), Some("T-001"));
+    }
+}
@@ -0,0 +1,83 @@
+use director_plan::client::{self, TicketUpdate};
+use director_plan::config::PlanConfig;
+use director_plan::server::ServeOptions;
+use director_plan::testing::TestWorkspace;
+use director_plan::types::Status;
+use reqwest::StatusCode;
+
+#[tokio::test]
+async fn test_client_against_server() -> anyhow::Result<()> {
+    let workspace = TestWorkspace::new()?;
+    let plan = workspace.plan();
+    let id = plan.create_ticket("Shadows", "Soften the card shadows", "true")?;
+
+    let server = workspace.serve().await?;
+    let api = server.client();
+    assert_eq!(api.api_url(), server.url("/api"));
+
+    let tickets = api.list_tickets().await?;
+    assert_eq!(tickets.len(), 1);
+    assert_eq!(api.get_ticket(&id).await?.title, "Shadows");
+    let missing = api.get_ticket("T-404").await.unwrap_err();
+    assert!(client::is_status(&missing, StatusCode::NOT_FOUND));
+
+    // Leases: a second worker can't take a claimed ticket
+    api.claim(&id, "w1", 60).await?;
+    let taken = api.claim(&id, "w2", 60).await.unwrap_err();
+    assert!(client::is_status(&taken, StatusCode::CONFLICT));
+    api.heartbeat(&id, "w1", 60).await?;
+    assert!(api.release(&id, "w1").await?);
+
+    // Raw writes are conditional on the ETag that was read
+    let raw = api.raw_ticket(&id).await?;
+    let edited = raw.content.replace("Soften", "Deepen");
+    let etag = api.put_raw_ticket(&id, edited.clone(), raw.format, Some(&raw.etag)).await?;
+    assert_ne!(etag, raw.etag);
+    let stale = api.put_raw_ticket(&id, edited, raw.format, Some(&raw.etag)).await.unwrap_err();
+    assert!(client::is_status(&stale, StatusCode::PRECONDITION_FAILED));
+    assert_eq!(plan.get_ticket(&id)?.spec.description, "Deepen the card shadows");
+
+    // A raw write can't start a ticket that is still waiting on another, any more than PATCH can
+    let waiting = plan.create_ticket("Blur", "", "true")?;
+    let raw = api.raw_ticket(&waiting).await?;
+    let content = raw.content.replacen("[meta]\n", &format!("[meta]\ndepends_on = [\"{}\"]\n", id), 1);
+    api.put_raw_ticket(&waiting, content.clone(), raw.format, Some("*")).await?;
+    let started = api.put_raw_ticket(&waiting, content.replace("status = \"todo\"", "status = \"in_progress\""), raw.format, None).await.unwrap_err();
+    assert!(client::is_status(&started, StatusCode::CONFLICT));
+    assert_eq!(plan.get_ticket(&waiting)?.meta.status, Status::Todo);
+
+    let updated = api.update_ticket(&id, &TicketUpdate { owner: Some("dana".to_string()), ..Default::default() }).await?;
+    assert_eq!(updated.owner, "dana");
+    api.set_status(&id, Status::Review).await?;
+    assert_eq!(plan.get_ticket(&id)?.meta.status, Status::Review);
+
+    let comment = api.add_comment(&id, "dana", "Blur too?", None).await?;
+    assert_eq!(api.comments(&id).await?, vec![comment]);
+
+    assert!(api.verify(&id).await?.success);
+    assert!(api.workers().await?.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_app_with_options() -> anyhow::Result<()> {
+    let workspace = TestWorkspace::new()?;
+    let id = workspace.ticket("Shadows", "true")?;
+    // Injected rather than read from plan/config.toml
+    let mut config = PlanConfig::default();
+    config.server.max_body_bytes = 64;
+    let options = ServeOptions { base_path: "/director".to_string(), config: Some(config), ..Default::default() };
+    let server = workspace.serve_with(&options).await?;
+
+    let http = reqwest::Client::new();
+    let tickets = http.get(server.url("/director/api/tickets")).send().await?;
+    assert_eq!(tickets.status(), StatusCode::OK);
+    let oversized = http.patch(server.url(&format!("/director/api/tickets/{}", id)))
+        .json(&serde_json::json!({ "owner": "x".repeat(100) }))
+        .send()
+        .await?;
+    assert_eq!(oversized.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    Ok(())
+}
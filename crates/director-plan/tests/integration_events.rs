@@ -0,0 +1,33 @@
+use director_plan::testing::TestWorkspace;
+use director_plan::types::Status;
+
+#[tokio::test]
+async fn test_event_stream_replays_and_follows() -> anyhow::Result<()> {
+    let workspace = TestWorkspace::new()?;
+    let plan = workspace.plan();
+    let id = workspace.ticket("Shadows", "true")?;
+    let server = workspace.serve().await?;
+
+    // From the start of the log: the creation, written before the server started
+    let mut resp = reqwest::Client::new()
+        .get(server.url("/api/events"))
+        .header("Last-Event-ID", "0")
+        .send()
+        .await?;
+    assert_eq!(resp.headers()["content-type"], "text/event-stream");
+
+    let mut received = String::new();
+    let mut changed = false;
+    while !received.contains("event: status_changed") {
+        if received.contains("event: ticket_created") && !changed {
+            // Written by this process like any CLI command would; the server picks it up from the log
+            plan.set_status(&id, Status::InProgress)?;
+            changed = true;
+        }
+        let chunk = tokio::time::timeout(std::time::Duration::from_secs(5), resp.chunk()).await??;
+        received.push_str(&String::from_utf8_lossy(&chunk.expect("stream ended")));
+    }
+    assert!(received.contains("\"type\":\"status_changed\",\"id\":\"T-001\",\"from\":\"todo\",\"to\":\"in_progress\""));
+
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use director_plan::execution_loop::ExecutionLoop;
+use director_plan::mock_agent::{MockAgent, MockScript};
+use director_plan::runs::RunStore;
+use director_plan::testing::TestWorkspace;
+use std::fs;
+use std::process::Command;
+
+// Wrong on the first attempt; right once the prompt carries verification's complaint
+const SCRIPT: &str = r#"
+[[turn]]
+when = "Previous Errors"
+files = { "answer.txt" = "42\n" }
+
+[[turn]]
+patch = """
+--- a/answer.txt
++++ b/answer.txt
+@@ -1 +1 @@
+-unknown
++41
+"""
+"#;
+
+/// A committed workspace with one ticket, verified by `answer.txt` holding 42.
+fn workspace() -> anyhow::Result<(TestWorkspace, String)> {
+    let workspace = TestWorkspace::new()?;
+    workspace.write("answer.txt", "unknown\n")?;
+    let id = workspace.ticket("Answer", "grep -qx 42 answer.txt")?;
+    Ok((workspace, id))
+}
+
+#[test]
+fn test_execute_with_mock_agent() -> anyhow::Result<()> {
+    let (workspace, id) = workspace()?;
+    let root = workspace.root();
+
+    let script: MockScript = toml_edit::de::from_str(SCRIPT)?;
+    let ticket = workspace.plan().get_ticket(&id)?;
+    let result = ExecutionLoop::new(root, "mock".to_string(), ticket)
+        .with_agent(Box::new(MockAgent::new(script, root)))
+        .run_with_handshake()?;
+
+    assert!(result.success, "{:?}", result.errors);
+    let commit = result.commit.expect("the passing attempt is committed");
+    assert_eq!(workspace.git(&["show", &format!("{}:answer.txt", commit)])?, "42");
+    let run = RunStore::new(root).find(&result.run_id)?.unwrap();
+    assert_eq!(run.attempts.iter().map(|a| a.passed).collect::<Vec<_>>(), [false, true]);
+    assert_eq!(run.attempts[1].result.as_ref().unwrap().files_changed, ["answer.txt"]);
+    Ok(())
+}
+
+#[test]
+fn test_execute_cli_with_mock_agent() -> anyhow::Result<()> {
+    let (workspace, id) = workspace()?;
+    // Outside the repository, so it doesn't dirty the tree
+    let script_dir = tempfile::tempdir()?;
+    let script = script_dir.path().join("mock.toml");
+    fs::write(&script, SCRIPT)?;
+
+    let bin = env!("CARGO_BIN_EXE_director-plan");
+    let agent = format!("{} mock-agent --script {}", bin, script.display());
+    let output = Command::new(bin)
+        .current_dir(workspace.root())
+        .args(["--output", "json", "execute", &id, "--agent", &agent])
+        .output()?;
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let body: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(body["ok"], true);
+    let commit = body["commit"].as_str().expect("the passing attempt is committed");
+    assert_eq!(workspace.git(&["show", &format!("{}:answer.txt", commit)])?, "42");
+    Ok(())
+}
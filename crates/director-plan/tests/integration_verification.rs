@@ -1,21 +1,15 @@
-use director_plan::server;
+use director_plan::testing::TestWorkspace;
 use std::fs;
-use tokio::net::TcpListener;
 
 #[tokio::test]
 async fn test_verification_failure_artifacts() -> anyhow::Result<()> {
-    // 1. Setup Temp Dir
-    let temp_dir = tempfile::tempdir()?;
-    let root = temp_dir.path().to_path_buf();
-
-    // Setup directories
-    fs::create_dir_all(root.join("plan/tickets"))?;
+    // 1. Setup Temp Workspace
+    let workspace = TestWorkspace::new()?;
+    let root = workspace.root();
     fs::create_dir_all(root.join("assets"))?;
-    fs::create_dir_all(root.join("tests/snapshots"))?;
 
     // 2. Create Dummy Golden Image
-    let golden_path = root.join("tests/snapshots/test.png");
-    fs::write(&golden_path, "golden bytes")?;
+    workspace.write("tests/snapshots/test.png", "golden bytes")?;
 
     // 3. Create Ticket with failing command that generates artifacts
     // We use a simple shell command.
@@ -54,21 +48,14 @@ log = []
     // Adjust for windows if needed, but assuming unix for now or basic shell.
     // director-plan server uses "sh -c" on non-windows.
 
-    fs::write(root.join("plan/tickets/T-TEST.toml"), ticket_content)?;
+    workspace.write("plan/tickets/T-TEST.toml", &ticket_content)?;
 
     // 5. Start Server
-    let app = server::create_app(root.clone()).await?;
-    let listener = TcpListener::bind("127.0.0.1:0").await?;
-    let addr = listener.local_addr()?;
-    let port = addr.port();
-
-    tokio::spawn(async move {
-        axum::serve(listener, app).await.unwrap();
-    });
+    let server = workspace.serve().await?;
 
     // 6. Verify
     let client = reqwest::Client::new();
-    let url = format!("http://127.0.0.1:{}/api/tickets/T-TEST/verify", port);
+    let url = server.url("/api/tickets/T-TEST/verify");
 
     let res = client.post(&url).send().await?;
     let status = res.status();